@@ -0,0 +1,39 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use antsibull::lsp::LanguageServer;
+use antsibull::markup::{
+    describe_grammar, textmate_grammar, Linter, ParseOptions, StyleRules, TerminologyRule,
+};
+use std::io;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    match std::env::args().nth(1).as_deref() {
+        None => {}
+        Some("--describe-grammar") => {
+            println!("{}", describe_grammar(&ParseOptions::default()));
+            return ExitCode::SUCCESS;
+        }
+        Some("--textmate-grammar") => {
+            println!("{}", textmate_grammar(&ParseOptions::default()));
+            return ExitCode::SUCCESS;
+        }
+        Some(_) => {
+            eprintln!("Usage: antsibull-markup-lsp [--describe-grammar | --textmate-grammar]");
+            return ExitCode::FAILURE;
+        }
+    }
+    let linter = Linter::new()
+        .add_pass(TerminologyRule::inclusive())
+        .add_pass(StyleRules::all());
+    let mut server = LanguageServer::new(linter);
+    if let Err(error) = server.run(&mut io::stdin().lock(), &mut io::stdout().lock()) {
+        eprintln!("Error: {}", error);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,27 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use std::net::TcpListener;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:8080".to_string());
+    let listener = match TcpListener::bind(&address) {
+        Ok(listener) => listener,
+        Err(error) => {
+            eprintln!("Cannot listen on {}: {}", address, error);
+            return ExitCode::FAILURE;
+        }
+    };
+    eprintln!("Listening on http://{}/render", address);
+    if let Err(error) = antsibull::preview::serve(listener) {
+        eprintln!("Error: {}", error);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,343 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use antsibull::collection::{
+    collection_markup_usage, harvest_corpus, CollectionDirectory, UsageReport,
+};
+use antsibull::markup::{
+    audit_escaping, load_test_vectors, parse_paragraphs, Context, NoLinkProvider, ParseOptions,
+    PartWithSource, RenderBaseline, Renderer,
+};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts the heap memory that is currently allocated, for the `memory` command.
+struct CountingAllocator {
+    bytes: AtomicUsize,
+    blocks: AtomicUsize,
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.bytes.fetch_add(layout.size(), Ordering::Relaxed);
+        self.blocks.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.bytes.fetch_sub(layout.size(), Ordering::Relaxed);
+        self.blocks.fetch_sub(1, Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        self.bytes.fetch_add(new_size, Ordering::Relaxed);
+        self.bytes.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator {
+    bytes: AtomicUsize::new(0),
+    blocks: AtomicUsize::new(0),
+};
+
+fn allocated() -> (usize, usize) {
+    (
+        ALLOCATOR.bytes.load(Ordering::Relaxed),
+        ALLOCATOR.blocks.load(Ordering::Relaxed),
+    )
+}
+
+/// The non-empty lines of a corpus file.
+fn read_corpus(file: &str) -> Result<Vec<String>, String> {
+    match std::fs::read_to_string(file) {
+        Ok(content) => Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.to_string())
+            .collect()),
+        Err(error) => Err(format!("{}: {}", file, error)),
+    }
+}
+
+/// Render every non-empty line of the corpus files to all formats, and report output lines
+/// that look structurally invalid.
+fn audit(corpus_files: &[String]) -> ExitCode {
+    let mut inputs = Vec::new();
+    for file in corpus_files {
+        match read_corpus(file) {
+            Ok(lines) => inputs.extend(lines),
+            Err(error) => {
+                eprintln!("{}", error);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    let context = Context::new();
+    let issues = audit_escaping(
+        inputs.iter().map(|input| input.as_str()),
+        &context,
+        &ParseOptions::default(),
+    );
+    for issue in &issues {
+        println!("{}", issue);
+        println!("    input: {}", inputs[issue.input]);
+    }
+    println!(
+        "{} inputs checked, {} issues found",
+        inputs.len(),
+        issues.len()
+    );
+    if issues.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+type RendererConstructor = for<'r> fn(&'r NoLinkProvider) -> Renderer<'r>;
+
+const FORMATS: [(&str, RendererConstructor); 7] = [
+    ("antsibull-html", |lp| Renderer::antsibull_html(lp)),
+    ("plain-html", |lp| Renderer::plain_html(lp)),
+    ("md", |lp| Renderer::md(lp)),
+    ("antsibull-rst", |lp| Renderer::antsibull_rst(lp)),
+    ("plain-rst", |lp| Renderer::plain_rst(lp)),
+    ("ansible-doc-text", |lp| Renderer::ansible_doc_text(lp)),
+    ("bbcode", |lp| Renderer::bbcode(lp)),
+];
+
+/// Render every non-empty line of the corpus files as a paragraph to all formats, and compare
+/// the output to the baseline saved in the baseline directory, or save a new baseline.
+fn compare(arguments: &[String]) -> ExitCode {
+    let save = arguments
+        .first()
+        .is_some_and(|argument| argument == "--save");
+    let arguments = &arguments[usize::from(save)..];
+    let Some((baseline_directory, corpus_files)) = arguments.split_first() else {
+        eprintln!(
+            "Usage: antsibull-markup-stats compare [--save] BASELINE_DIRECTORY CORPUS_FILE..."
+        );
+        return ExitCode::FAILURE;
+    };
+    let baseline_directory = Path::new(baseline_directory);
+    let link_provider = NoLinkProvider::new();
+    let context = Context::new();
+    let opts = ParseOptions::default();
+    let mut changed = 0;
+    for file in corpus_files {
+        let lines = match read_corpus(file) {
+            Ok(lines) => lines,
+            Err(error) => {
+                eprintln!("{}", error);
+                return ExitCode::FAILURE;
+            }
+        };
+        let paragraphs = parse_paragraphs(lines.iter().map(|line| line.as_str()), &context, &opts);
+        let name = Path::new(file)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| file.clone());
+        for (format, renderer) in FORMATS {
+            let renderer = renderer(&link_provider);
+            let path = baseline_directory.join(format!("{}.{}", name, format));
+            if save {
+                let baseline = RenderBaseline::render(&renderer, &paragraphs);
+                if let Err(error) = std::fs::create_dir_all(baseline_directory)
+                    .and_then(|_| std::fs::write(&path, baseline.serialize()))
+                {
+                    eprintln!("{}: {}", path.display(), error);
+                    return ExitCode::FAILURE;
+                }
+                continue;
+            }
+            let baseline = match std::fs::read_to_string(&path) {
+                Ok(content) => RenderBaseline::parse(&content),
+                Err(error) => {
+                    eprintln!("{}: {}", path.display(), error);
+                    return ExitCode::FAILURE;
+                }
+            };
+            let differences = baseline.compare(&renderer, &paragraphs);
+            if differences.is_empty() {
+                continue;
+            }
+            changed += differences.len();
+            println!("{} ({}):", file, format);
+            for difference in differences {
+                print!("{}", difference);
+            }
+        }
+    }
+    if save {
+        println!("Saved the baseline of {} corpus files", corpus_files.len());
+        return ExitCode::SUCCESS;
+    }
+    println!("{} changed paragraphs", changed);
+    if changed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Parse every non-empty line of the corpus files as a paragraph, keep all parts in memory, and
+/// report the heap memory they use.
+fn memory(corpus_files: &[String]) -> ExitCode {
+    if corpus_files.is_empty() {
+        eprintln!("Usage: antsibull-markup-stats memory CORPUS_FILE...");
+        return ExitCode::FAILURE;
+    }
+    let mut inputs = Vec::new();
+    for file in corpus_files {
+        match read_corpus(file) {
+            Ok(lines) => inputs.extend(lines),
+            Err(error) => {
+                eprintln!("{}", error);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    let context = Context::new();
+    let opts = ParseOptions::default();
+    let (bytes_before, blocks_before) = allocated();
+    let paragraphs = parse_paragraphs(inputs.iter().map(|input| input.as_str()), &context, &opts);
+    let (bytes_after, blocks_after) = allocated();
+    let parts: usize = paragraphs.iter().map(|paragraph| paragraph.len()).sum();
+    let bytes = bytes_after.saturating_sub(bytes_before);
+    println!(
+        "{} paragraphs, {} parts of {} bytes each",
+        paragraphs.len(),
+        parts,
+        std::mem::size_of::<PartWithSource>()
+    );
+    println!(
+        "{} bytes in {} heap allocations, {:.1} bytes per part",
+        bytes,
+        blocks_after.saturating_sub(blocks_before),
+        bytes as f64 / parts.max(1) as f64
+    );
+    ExitCode::SUCCESS
+}
+
+/// Write the documentation strings of all collections in an `ansible_collections` directory
+/// as JSON Lines to stdout.
+fn harvest(arguments: &[String]) -> ExitCode {
+    let [root] = arguments else {
+        eprintln!("Usage: antsibull-markup-stats harvest ANSIBLE_COLLECTIONS_DIRECTORY");
+        return ExitCode::FAILURE;
+    };
+    let corpus = harvest_corpus(Path::new(root));
+    for error in &corpus.unreadable_files {
+        eprintln!("{}: {}", root, error);
+    }
+    print!("{}", corpus.to_jsonl());
+    ExitCode::SUCCESS
+}
+
+/// Run the test vectors of a `test-vectors.yaml` file, or only the one named with `--filter`,
+/// and report every rendering that differs from the expected output.
+fn test_vectors(arguments: &[String]) -> ExitCode {
+    let (filter, arguments) = match arguments {
+        [option, name, rest @ ..] if option == "--filter" => (Some(name.as_str()), rest),
+        _ => (None, arguments),
+    };
+    let [file] = arguments else {
+        eprintln!("Usage: antsibull-markup-stats test-vectors [--filter NAME] TEST_VECTORS_FILE");
+        return ExitCode::FAILURE;
+    };
+    let vectors = match std::fs::read_to_string(file)
+        .map_err(|error| error.to_string())
+        .and_then(|content| load_test_vectors(&content))
+    {
+        Ok(vectors) => vectors,
+        Err(error) => {
+            eprintln!("{}: {}", file, error);
+            return ExitCode::FAILURE;
+        }
+    };
+    let vectors: Vec<_> = vectors
+        .into_iter()
+        .filter(|vector| filter.is_none_or(|name| vector.name == name))
+        .collect();
+    if let (Some(name), true) = (filter, vectors.is_empty()) {
+        eprintln!("{}: no test vector named {:?}", file, name);
+        return ExitCode::FAILURE;
+    }
+    let mut failures = 0;
+    for vector in &vectors {
+        for failure in vector.run() {
+            failures += 1;
+            print!("{}", failure);
+        }
+    }
+    println!("{} test vectors run, {} failures", vectors.len(), failures);
+    if failures == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    match arguments.first().map(|argument| argument.as_str()) {
+        Some("compare") => return compare(&arguments[1..]),
+        Some("harvest") => return harvest(&arguments[1..]),
+        Some("memory") => return memory(&arguments[1..]),
+        Some("test-vectors") => return test_vectors(&arguments[1..]),
+        _ => {}
+    }
+    let mut json = false;
+    let mut audit_escaping = false;
+    let mut directories = Vec::new();
+    for argument in arguments {
+        match argument.as_str() {
+            "--json" => json = true,
+            "--audit-escaping" => audit_escaping = true,
+            _ => directories.push(argument),
+        }
+    }
+    if directories.is_empty() {
+        eprintln!("Usage: antsibull-markup-stats [--json] COLLECTION_DIRECTORY...");
+        eprintln!("       antsibull-markup-stats --audit-escaping CORPUS_FILE...");
+        eprintln!(
+            "       antsibull-markup-stats compare [--save] BASELINE_DIRECTORY CORPUS_FILE..."
+        );
+        eprintln!("       antsibull-markup-stats harvest ANSIBLE_COLLECTIONS_DIRECTORY");
+        eprintln!("       antsibull-markup-stats memory CORPUS_FILE...");
+        eprintln!("       antsibull-markup-stats test-vectors [--filter NAME] TEST_VECTORS_FILE");
+        return ExitCode::FAILURE;
+    }
+    if audit_escaping {
+        return audit(&directories);
+    }
+    let mut report = UsageReport::new();
+    for directory in &directories {
+        match collection_markup_usage(&CollectionDirectory::new(Path::new(directory))) {
+            Ok(usage) => {
+                for error in &usage.unreadable_files {
+                    eprintln!("{}: {}", directory, error);
+                }
+                report.collections.push(usage);
+            }
+            Err(error) => {
+                eprintln!("{}: {}", directory, error);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    if json {
+        println!("{}", report.to_json());
+    } else {
+        print!("{}", report.to_table());
+    }
+    ExitCode::SUCCESS
+}
@@ -7,10 +7,7 @@ SPDX-License-Identifier: GPL-3.0-or-later
 use antsibull::util::IntoString;
 
 fn main() {
-    let context = &antsibull::markup::Context {
-        current_plugin: Option::None,
-        role_entrypoint: Option::None,
-    };
+    let context = &antsibull::markup::Context::new();
     let f = antsibull::markup::parse(
         "The B(module) that I(is) C(defined) in M(ansible.builtin.debug) is L(called, https://docs.ansible.com/ansible/latest/collections/ansible/builtin/debug_module.html) U(https://docs.ansible.com/ansible/latest/), O(foo[].bar[3].baz=bam).",
         // "The B(module) that I(is) C(defined) V(fo\\o)o)",
@@ -0,0 +1,331 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::collection::is_valid_collection_name;
+use crate::util::version::{SpecifierSet, Version};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// An error in a build data file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildDataError {
+    /// The 1-based line number, or 0 if the error does not belong to a specific line.
+    pub line: usize,
+
+    /// The error message.
+    pub message: String,
+}
+
+impl fmt::Display for BuildDataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "Line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+impl std::error::Error for BuildDataError {}
+
+fn error(line: usize, message: String) -> BuildDataError {
+    BuildDataError { line, message }
+}
+
+/// Iterate over the non-empty lines that are not comments, with their 1-based line numbers.
+fn content_lines(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    content
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// Parse an `ansible.in` file, which lists the collections included in the package.
+///
+/// Empty lines and comments are ignored.
+pub fn parse_pieces_file(content: &str) -> Result<Vec<String>, BuildDataError> {
+    content_lines(content)
+        .map(|(index, line)| {
+            if is_valid_collection_name(line) {
+                Ok(line.to_string())
+            } else {
+                Err(error(
+                    index,
+                    format!("{:?} is not a valid collection name", line),
+                ))
+            }
+        })
+        .collect()
+}
+
+/// Serialize the list of collections included in the package in the `ansible.in` format.
+pub fn write_pieces_file(collections: &[String]) -> String {
+    collections
+        .iter()
+        .map(|collection| format!("{}\n", collection))
+        .collect()
+}
+
+/// The entries of a `.build` or `.deps` file, with their line numbers.
+struct Entries<'c> {
+    /// The entries starting with `_`, like `_ansible_version`.
+    header: BTreeMap<&'c str, (usize, &'c str)>,
+
+    /// The collection entries, in order.
+    collections: Vec<(usize, &'c str, &'c str)>,
+}
+
+fn parse_entries(content: &str) -> Result<Entries<'_>, BuildDataError> {
+    let mut header = BTreeMap::new();
+    let mut collections = Vec::new();
+    for (index, line) in content_lines(content) {
+        let (key, value) = line
+            .split_once(':')
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .ok_or_else(|| error(index, format!("expected \"name: value\", got {:?}", line)))?;
+        if key.starts_with('_') {
+            if !matches!(
+                key,
+                "_ansible_version" | "_ansible_core_version" | "_python"
+            ) {
+                return Err(error(index, format!("unknown entry {:?}", key)));
+            }
+            if header.insert(key, (index, value)).is_some() {
+                return Err(error(index, format!("duplicate entry {:?}", key)));
+            }
+        } else if !is_valid_collection_name(key) {
+            return Err(error(
+                index,
+                format!("{:?} is not a valid collection name", key),
+            ));
+        } else if collections.iter().any(|(_, name, _)| *name == key) {
+            return Err(error(index, format!("duplicate collection {:?}", key)));
+        } else {
+            collections.push((index, key, value));
+        }
+    }
+    Ok(Entries {
+        header,
+        collections,
+    })
+}
+
+fn required<'c>(
+    header: &BTreeMap<&str, (usize, &'c str)>,
+    key: &str,
+) -> Result<(usize, &'c str), BuildDataError> {
+    header
+        .get(key)
+        .copied()
+        .ok_or_else(|| error(0, format!("missing entry {:?}", key)))
+}
+
+fn parse_version(index: usize, value: &str) -> Result<Version, BuildDataError> {
+    Version::parse(value).map_err(|message| error(index, message))
+}
+
+fn parse_specifiers(index: usize, value: &str) -> Result<SpecifierSet, BuildDataError> {
+    SpecifierSet::parse(value).map_err(|message| error(index, message))
+}
+
+/// The contents of an `ansible-X.build` file, which lists the version ranges of the collections
+/// that can be included in a major release of the package.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildFile {
+    /// The major version of the package, like `10`.
+    pub ansible_version: String,
+
+    /// The ansible-core version range, like `>=2.17.0,<2.18.0`.
+    pub ansible_core_version: SpecifierSet,
+
+    /// The supported Python versions, if restricted.
+    pub python: Option<SpecifierSet>,
+
+    /// The version ranges of the collections, by collection name.
+    pub collections: BTreeMap<String, SpecifierSet>,
+}
+
+impl BuildFile {
+    /// Parse the contents of a `.build` file.
+    ///
+    /// Empty lines and comments are ignored.
+    pub fn parse(content: &str) -> Result<BuildFile, BuildDataError> {
+        let Entries {
+            header,
+            collections: entries,
+        } = parse_entries(content)?;
+        let (_, ansible_version) = required(&header, "_ansible_version")?;
+        let (index, ansible_core_version) = required(&header, "_ansible_core_version")?;
+        Ok(BuildFile {
+            ansible_version: ansible_version.to_string(),
+            ansible_core_version: parse_specifiers(index, ansible_core_version)?,
+            python: header
+                .get("_python")
+                .map(|(index, value)| parse_specifiers(*index, value))
+                .transpose()?,
+            collections: entries
+                .into_iter()
+                .map(|(index, name, value)| Ok((name.to_string(), parse_specifiers(index, value)?)))
+                .collect::<Result<_, BuildDataError>>()?,
+        })
+    }
+}
+
+/// Serializes in the `.build` format. Parsing the result gives the same `BuildFile` again.
+impl fmt::Display for BuildFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "_ansible_version: {}", self.ansible_version)?;
+        writeln!(f, "_ansible_core_version: {}", self.ansible_core_version)?;
+        if let Some(python) = &self.python {
+            writeln!(f, "_python: {}", python)?;
+        }
+        for (name, specifiers) in &self.collections {
+            writeln!(f, "{}: {}", name, specifiers)?;
+        }
+        Ok(())
+    }
+}
+
+/// The contents of an `ansible-X.Y.Z.deps` file, which pins the versions of the collections
+/// included in a release of the package.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepsFile {
+    /// The version of the package, like `10.1.0`.
+    pub ansible_version: Version,
+
+    /// The pinned ansible-core version.
+    pub ansible_core_version: Version,
+
+    /// The supported Python versions, if restricted.
+    pub python: Option<SpecifierSet>,
+
+    /// The pinned versions of the collections, by collection name.
+    pub collections: BTreeMap<String, Version>,
+}
+
+impl DepsFile {
+    /// Parse the contents of a `.deps` file.
+    ///
+    /// Empty lines and comments are ignored.
+    pub fn parse(content: &str) -> Result<DepsFile, BuildDataError> {
+        let Entries {
+            header,
+            collections: entries,
+        } = parse_entries(content)?;
+        let (index, ansible_version) = required(&header, "_ansible_version")?;
+        let ansible_version = parse_version(index, ansible_version)?;
+        let (index, ansible_core_version) = required(&header, "_ansible_core_version")?;
+        Ok(DepsFile {
+            ansible_version,
+            ansible_core_version: parse_version(index, ansible_core_version)?,
+            python: header
+                .get("_python")
+                .map(|(index, value)| parse_specifiers(*index, value))
+                .transpose()?,
+            collections: entries
+                .into_iter()
+                .map(|(index, name, value)| Ok((name.to_string(), parse_version(index, value)?)))
+                .collect::<Result<_, BuildDataError>>()?,
+        })
+    }
+}
+
+/// Serializes in the `.deps` format. Parsing the result gives the same `DepsFile` again.
+impl fmt::Display for DepsFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "_ansible_version: {}", self.ansible_version)?;
+        writeln!(f, "_ansible_core_version: {}", self.ansible_core_version)?;
+        if let Some(python) = &self.python {
+            writeln!(f, "_python: {}", python)?;
+        }
+        for (name, version) in &self.collections {
+            writeln!(f, "{}: {}", name, version)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pieces_file() {
+        let pieces =
+            parse_pieces_file("# Collections\n\ncommunity.general\n  ansible.netcommon\n").unwrap();
+        assert_eq!(pieces, vec!["community.general", "ansible.netcommon"]);
+        assert_eq!(
+            write_pieces_file(&pieces),
+            "community.general\nansible.netcommon\n"
+        );
+        assert_eq!(
+            parse_pieces_file("foo.bar\nfoo\n").unwrap_err().to_string(),
+            "Line 2: \"foo\" is not a valid collection name"
+        );
+    }
+
+    #[test]
+    fn test_build_file() {
+        let content = concat!(
+            "_ansible_version: 10\n",
+            "_ansible_core_version: >=2.17.0,<2.18.0\n",
+            "_python: >=3.10\n",
+            "amazon.aws: >=8.0.0,<9.0.0\n",
+            "community.general: >=9.0.0,<10.0.0\n",
+        );
+        let build = BuildFile::parse(content).unwrap();
+        assert_eq!(build.ansible_version, "10");
+        assert_eq!(build.collections.len(), 2);
+        assert!(build.collections["amazon.aws"].contains(&Version::parse("8.1.0").unwrap(), false));
+        assert_eq!(build.to_string(), content);
+
+        assert_eq!(
+            BuildFile::parse("_ansible_version: 10\n")
+                .unwrap_err()
+                .to_string(),
+            "missing entry \"_ansible_core_version\""
+        );
+        assert_eq!(
+            BuildFile::parse("_ansible_version: 10\n_foo: bar\n")
+                .unwrap_err()
+                .to_string(),
+            "Line 2: unknown entry \"_foo\""
+        );
+        assert_eq!(
+            BuildFile::parse("_ansible_version: 10\n_ansible_core_version: 2.17\n")
+                .unwrap_err()
+                .to_string(),
+            "Line 2: Invalid version specifier \"2.17\""
+        );
+    }
+
+    #[test]
+    fn test_deps_file() {
+        let content = concat!(
+            "_ansible_version: 10.1.0\n",
+            "_ansible_core_version: 2.17.1\n",
+            "amazon.aws: 8.0.1\n",
+            "community.general: 9.1.0\n",
+        );
+        let deps = DepsFile::parse(content).unwrap();
+        assert_eq!(deps.ansible_version, Version::parse("10.1.0").unwrap());
+        assert_eq!(deps.python, None);
+        assert_eq!(deps.to_string(), content);
+        assert_eq!(
+            DepsFile::parse(&format!("{}amazon.aws: 8.0.2\n", content))
+                .unwrap_err()
+                .to_string(),
+            "Line 5: duplicate collection \"amazon.aws\""
+        );
+        assert_eq!(
+            DepsFile::parse("_ansible_version: 10.1.0\n_ansible_core_version: 2.17.1\nfoo\n")
+                .unwrap_err()
+                .to_string(),
+            "Line 3: expected \"name: value\", got \"foo\""
+        );
+    }
+}
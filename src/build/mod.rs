@@ -0,0 +1,14 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Building blocks for building the Ansible community package.
+
+mod data;
+mod resolve;
+
+pub use data::{parse_pieces_file, write_pieces_file, BuildDataError, BuildFile, DepsFile};
+
+pub use resolve::{parse_cache_listing, resolve, CollectionVersion, ResolveError};
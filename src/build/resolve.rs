@@ -0,0 +1,348 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::build::data::BuildFile;
+use crate::util::version::{SpecifierSet, Version};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A version of a collection that is available for inclusion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionVersion {
+    /// The version.
+    pub version: Version,
+
+    /// The collections this version depends on, with their version ranges.
+    pub dependencies: BTreeMap<String, SpecifierSet>,
+
+    /// The ansible-core versions this version supports, if known.
+    pub requires_ansible: Option<SpecifierSet>,
+}
+
+impl CollectionVersion {
+    /// A collection version without dependencies and ansible-core requirements.
+    pub fn new(version: Version) -> CollectionVersion {
+        CollectionVersion {
+            version,
+            dependencies: BTreeMap::new(),
+            requires_ansible: None,
+        }
+    }
+}
+
+/// Extract the available collection versions from the file names of a local artifact cache,
+/// like `community-general-9.1.0.tar.gz`.
+///
+/// Other files are ignored. The versions of every collection are sorted.
+pub fn parse_cache_listing<'f, I>(file_names: I) -> BTreeMap<String, Vec<Version>>
+where
+    I: IntoIterator<Item = &'f str>,
+{
+    let mut result: BTreeMap<String, Vec<Version>> = BTreeMap::new();
+    for file_name in file_names {
+        let Some(stem) = file_name.strip_suffix(".tar.gz") else {
+            continue;
+        };
+        let mut parts = stem.splitn(3, '-');
+        if let (Some(namespace), Some(name), Some(version)) =
+            (parts.next(), parts.next(), parts.next())
+        {
+            if let Ok(version) = Version::parse(version) {
+                result
+                    .entry(format!("{}.{}", namespace, name))
+                    .or_default()
+                    .push(version);
+            }
+        }
+    }
+    for versions in result.values_mut() {
+        versions.sort();
+        versions.dedup();
+    }
+    result
+}
+
+/// Resolution failed because no version of a collection could be picked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolveError {
+    /// The collection for which no version could be picked.
+    pub collection: String,
+
+    /// Why the candidate versions were rejected.
+    pub reasons: Vec<String>,
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Cannot find a compatible version of {}", self.collection)?;
+        for reason in &self.reasons {
+            write!(f, "\n- {}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+struct Resolver<'a> {
+    ansible_core_version: &'a Version,
+    names: Vec<&'a str>,
+    candidates: Vec<Vec<&'a CollectionVersion>>,
+    pins: Vec<&'a CollectionVersion>,
+    deepest: Option<(usize, Vec<String>)>,
+}
+
+impl<'a> Resolver<'a> {
+    /// Why `candidate` of the collection at `index` cannot be added to the current pins, if it
+    /// cannot.
+    fn conflict(&self, index: usize, candidate: &CollectionVersion) -> Option<String> {
+        let name = self.names[index];
+        if let Some(requires_ansible) = &candidate.requires_ansible {
+            if !requires_ansible.contains(self.ansible_core_version, true) {
+                return Some(format!(
+                    "{} {} requires ansible-core {}",
+                    name, candidate.version, requires_ansible
+                ));
+            }
+        }
+        for dependency in candidate.dependencies.keys() {
+            if !self.names.contains(&dependency.as_str()) {
+                return Some(format!(
+                    "{} {} depends on {}, which is not included",
+                    name, candidate.version, dependency
+                ));
+            }
+        }
+        for (other, pin) in self.names.iter().zip(&self.pins) {
+            if let Some(specifiers) = candidate.dependencies.get(*other) {
+                if !specifiers.contains(&pin.version, true) {
+                    return Some(format!(
+                        "{} {} depends on {} {}, but {} {} has been picked",
+                        name, candidate.version, other, specifiers, other, pin.version
+                    ));
+                }
+            }
+            if let Some(specifiers) = pin.dependencies.get(name) {
+                if !specifiers.contains(&candidate.version, true) {
+                    return Some(format!(
+                        "{} {} has been picked, which depends on {} {}",
+                        other, pin.version, name, specifiers
+                    ));
+                }
+            }
+        }
+        None
+    }
+
+    fn search(&mut self, index: usize) -> bool {
+        if index == self.names.len() {
+            return true;
+        }
+        let mut reasons = Vec::new();
+        for candidate in self.candidates[index].clone() {
+            match self.conflict(index, candidate) {
+                Some(reason) => reasons.push(reason),
+                None => {
+                    self.pins.push(candidate);
+                    if self.search(index + 1) {
+                        return true;
+                    }
+                    self.pins.pop();
+                }
+            }
+        }
+        if self
+            .deepest
+            .as_ref()
+            .is_none_or(|(deepest, _)| index >= *deepest)
+        {
+            self.deepest = Some((index, reasons));
+        }
+        false
+    }
+}
+
+/// Compute the newest versions of the collections in `build` that are compatible with each
+/// other and with `ansible_core_version`.
+///
+/// Collections are considered in alphabetical order, and for every collection the newest
+/// version is preferred. Pre-releases are only picked if the collection's version range
+/// explicitly mentions one.
+pub fn resolve(
+    build: &BuildFile,
+    ansible_core_version: &Version,
+    available: &BTreeMap<String, Vec<CollectionVersion>>,
+) -> Result<BTreeMap<String, Version>, ResolveError> {
+    let mut names = Vec::new();
+    let mut candidates = Vec::new();
+    for (name, specifiers) in &build.collections {
+        let versions = available.get(name).map(Vec::as_slice).unwrap_or_default();
+        let mut matching: Vec<&CollectionVersion> = versions
+            .iter()
+            .filter(|candidate| specifiers.contains(&candidate.version, false))
+            .collect();
+        if matching.is_empty() {
+            let available: Vec<String> = versions.iter().map(|v| v.version.to_string()).collect();
+            return Err(ResolveError {
+                collection: name.clone(),
+                reasons: vec![if available.is_empty() {
+                    "no versions are available".to_string()
+                } else {
+                    format!(
+                        "none of the available versions ({}) matches {}",
+                        available.join(", "),
+                        specifiers
+                    )
+                }],
+            });
+        }
+        matching.sort_by(|a, b| b.version.cmp(&a.version));
+        names.push(name.as_str());
+        candidates.push(matching);
+    }
+    let mut resolver = Resolver {
+        ansible_core_version,
+        names,
+        candidates,
+        pins: Vec::new(),
+        deepest: None,
+    };
+    if resolver.search(0) {
+        return Ok(resolver
+            .names
+            .iter()
+            .zip(&resolver.pins)
+            .map(|(name, pin)| (name.to_string(), pin.version.clone()))
+            .collect());
+    }
+    let (index, reasons) = resolver.deepest.unwrap_or_default();
+    Err(ResolveError {
+        collection: resolver.names[index].to_string(),
+        reasons,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(version: &str) -> Version {
+        Version::parse(version).unwrap()
+    }
+
+    fn build(collections: &[(&str, &str)]) -> BuildFile {
+        BuildFile {
+            ansible_version: "10".to_string(),
+            ansible_core_version: SpecifierSet::parse(">=2.17.0").unwrap(),
+            python: None,
+            collections: collections
+                .iter()
+                .map(|(name, spec)| (name.to_string(), SpecifierSet::parse(spec).unwrap()))
+                .collect(),
+        }
+    }
+
+    fn version(version: &str, dependencies: &[(&str, &str)]) -> CollectionVersion {
+        CollectionVersion {
+            dependencies: dependencies
+                .iter()
+                .map(|(name, spec)| (name.to_string(), SpecifierSet::parse(spec).unwrap()))
+                .collect(),
+            ..CollectionVersion::new(v(version))
+        }
+    }
+
+    #[test]
+    fn test_parse_cache_listing() {
+        let listing = parse_cache_listing([
+            "community-general-9.1.0.tar.gz",
+            "community-general-8.6.0.tar.gz",
+            "ansible-netcommon-7.0.0-beta1.tar.gz",
+            "README.md",
+        ]);
+        assert_eq!(
+            listing,
+            BTreeMap::from([
+                ("ansible.netcommon".to_string(), vec![v("7.0.0b1")]),
+                (
+                    "community.general".to_string(),
+                    vec![v("8.6.0"), v("9.1.0")]
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_resolve() {
+        let available = BTreeMap::from([
+            (
+                "a.a".to_string(),
+                vec![
+                    version("1.0.0", &[("b.b", ">=1.0.0")]),
+                    version("2.0.0", &[("b.b", ">=2.0.0")]),
+                    version("2.1.0rc1", &[]),
+                ],
+            ),
+            (
+                "b.b".to_string(),
+                vec![
+                    version("1.5.0", &[]),
+                    version("2.0.0", &[("a.a", "<2.0.0")]),
+                ],
+            ),
+        ]);
+        let pins = resolve(
+            &build(&[("a.a", ">=1.0.0,<3.0.0"), ("b.b", ">=1.0.0")]),
+            &v("2.17.0"),
+            &available,
+        )
+        .unwrap();
+        assert_eq!(
+            pins,
+            BTreeMap::from([
+                ("a.a".to_string(), v("1.0.0")),
+                ("b.b".to_string(), v("2.0.0")),
+            ])
+        );
+
+        let error = resolve(
+            &build(&[("a.a", ">=2.0.0"), ("b.b", ">=2.0.0")]),
+            &v("2.17.0"),
+            &available,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            concat!(
+                "Cannot find a compatible version of b.b\n",
+                "- b.b 2.0.0 depends on a.a <2.0.0, but a.a 2.0.0 has been picked",
+            )
+        );
+
+        let error = resolve(&build(&[("a.a", ">=3.0.0")]), &v("2.17.0"), &available).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            concat!(
+                "Cannot find a compatible version of a.a\n",
+                "- none of the available versions (1.0.0, 2.0.0, 2.1.0rc1) matches >=3.0.0",
+            )
+        );
+
+        let available = BTreeMap::from([(
+            "a.a".to_string(),
+            vec![CollectionVersion {
+                requires_ansible: Some(SpecifierSet::parse(">=2.18.0").unwrap()),
+                ..CollectionVersion::new(v("1.0.0"))
+            }],
+        )]);
+        assert_eq!(
+            resolve(&build(&[("a.a", ">=1.0.0")]), &v("2.17.0"), &available)
+                .unwrap_err()
+                .reasons,
+            vec!["a.a 1.0.0 requires ansible-core >=2.18.0"]
+        );
+    }
+}
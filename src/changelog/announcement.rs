@@ -0,0 +1,327 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::changelog::package::PackageReleaseNotes;
+use crate::changelog::rst_inline::rst_inline_to_paragraph;
+use crate::error::Error;
+use crate::markup::{
+    append_ansible_doc_text_paragraph, append_md_paragraph, MDEscaper, NoLinkProvider,
+};
+use regex::Regex;
+use std::sync::LazyLock;
+
+static PLACEHOLDER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{|\}\}|\{([a-z_]*)\}").unwrap());
+
+/// The output format of an announcement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnouncementFormat {
+    /// Plain text, for example for mailing lists.
+    PlainText,
+
+    /// MarkDown, for example for forums or Matrix.
+    Markdown,
+}
+
+/// The channels announcements are posted to, each with its own default template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnouncementKind {
+    MailingList,
+    Forum,
+    Matrix,
+}
+
+const MAILING_LIST_TEMPLATE: &str = "Hello everyone,
+
+We're happy to announce the release of the Ansible {version} package!
+
+Ansible {version} depends on ansible-core {ansible_core_version} and includes a curated set of Ansible collections that provide a vast number of modules and plugins.
+
+Highlights:
+
+{highlights}
+
+Links:
+
+{links}
+
+On behalf of the Ansible community, thank you and happy automating!
+";
+
+const FORUM_TEMPLATE: &str = "# Ansible {version} has been released!
+
+Ansible {version} depends on ansible-core {ansible_core_version} and includes a curated set of Ansible collections that provide a vast number of modules and plugins.
+
+## Highlights
+
+{highlights}
+
+## Links
+
+{links}
+
+On behalf of the Ansible community, thank you and happy automating!
+";
+
+const MATRIX_TEMPLATE: &str =
+    "Ansible {version} has been released! It depends on ansible-core {ansible_core_version}.
+
+{links}
+";
+
+impl AnnouncementKind {
+    /// The default template for the channel.
+    pub fn template(&self) -> &'static str {
+        match self {
+            AnnouncementKind::MailingList => MAILING_LIST_TEMPLATE,
+            AnnouncementKind::Forum => FORUM_TEMPLATE,
+            AnnouncementKind::Matrix => MATRIX_TEMPLATE,
+        }
+    }
+
+    /// The output format used for the channel.
+    pub fn format(&self) -> AnnouncementFormat {
+        match self {
+            AnnouncementKind::MailingList => AnnouncementFormat::PlainText,
+            AnnouncementKind::Forum | AnnouncementKind::Matrix => AnnouncementFormat::Markdown,
+        }
+    }
+}
+
+/// A highlight of a release.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Highlight {
+    /// The collection the highlight is about, if any.
+    pub collection: Option<String>,
+
+    /// The text, using the RST inline markup of changelog entries.
+    pub text: String,
+}
+
+/// The release information shown in an announcement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Announcement {
+    /// The version of the package.
+    pub version: String,
+
+    /// The ansible-core version the package depends on.
+    pub ansible_core_version: String,
+
+    /// The release date, like `2024-06-17`.
+    pub release_date: Option<String>,
+
+    /// The highlights of the release.
+    pub highlights: Vec<Highlight>,
+
+    /// Links, each with a title and a URL.
+    pub links: Vec<(String, String)>,
+}
+
+impl Announcement {
+    /// Collect the release information from the package's release notes.
+    ///
+    /// The highlights are the added and removed collections, followed by the major and breaking
+    /// changes of the included collections.
+    pub fn from_release_notes(
+        notes: &PackageReleaseNotes,
+        release_date: Option<&str>,
+        links: Vec<(String, String)>,
+    ) -> Announcement {
+        let mut highlights = Vec::new();
+        if notes.previous_version.is_some() {
+            for change in &notes.collections {
+                let text = match (&change.previous, &change.current) {
+                    (None, Some(current)) => format!(
+                        "The ``{}`` collection {} has been added.",
+                        change.name, current
+                    ),
+                    (Some(_), None) => {
+                        format!("The ``{}`` collection has been removed.", change.name)
+                    }
+                    _ => continue,
+                };
+                highlights.push(Highlight {
+                    collection: None,
+                    text,
+                });
+            }
+        }
+        for category in &notes.categories {
+            if !matches!(
+                category.category.as_str(),
+                "major_changes" | "breaking_changes"
+            ) {
+                continue;
+            }
+            for (collection, entries) in &category.collections {
+                highlights.extend(entries.iter().map(|entry| Highlight {
+                    collection: Some(collection.clone()),
+                    text: entry.clone(),
+                }));
+            }
+        }
+        Announcement {
+            version: notes.version.clone(),
+            ansible_core_version: notes.ansible_core_version.clone(),
+            release_date: release_date.map(|date| date.to_string()),
+            highlights,
+            links,
+        }
+    }
+
+    fn escape(&self, text: &str, format: AnnouncementFormat) -> String {
+        match format {
+            AnnouncementFormat::PlainText => text.to_string(),
+            AnnouncementFormat::Markdown => MDEscaper::new().unwrap().escape(text).into_owned(),
+        }
+    }
+
+    fn format_highlights(&self, format: AnnouncementFormat) -> String {
+        let mut result = String::new();
+        for highlight in &self.highlights {
+            if !result.is_empty() {
+                result.push('\n');
+            }
+            result.push_str(match format {
+                AnnouncementFormat::PlainText => "- ",
+                AnnouncementFormat::Markdown => "* ",
+            });
+            if let Some(collection) = &highlight.collection {
+                result.push_str(&self.escape(collection, format));
+                result.push_str(": ");
+            }
+            let paragraph = rst_inline_to_paragraph(&highlight.text);
+            match format {
+                AnnouncementFormat::PlainText => append_ansible_doc_text_paragraph(
+                    &mut result,
+                    paragraph.iter(),
+                    &NoLinkProvider::new(),
+                    &None,
+                ),
+                AnnouncementFormat::Markdown => append_md_paragraph(
+                    &mut result,
+                    paragraph.iter(),
+                    &NoLinkProvider::new(),
+                    &None,
+                ),
+            }
+        }
+        result
+    }
+
+    fn format_links(&self, format: AnnouncementFormat) -> String {
+        self.links
+            .iter()
+            .map(|(title, url)| match format {
+                AnnouncementFormat::PlainText => format!("- {}: {}", title, url),
+                AnnouncementFormat::Markdown => {
+                    format!("* [{}](<{}>)", self.escape(title, format), url)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Render the announcement with a template.
+    ///
+    /// The template can use the placeholders `{version}`, `{ansible_core_version}`,
+    /// `{release_date}`, `{highlights}`, and `{links}`. Use `{{` and `}}` for literal braces.
+    pub fn render(&self, template: &str, format: AnnouncementFormat) -> Result<String, Error> {
+        let mut result = String::with_capacity(template.len());
+        let mut last = 0;
+        for captures in PLACEHOLDER_RE.captures_iter(template) {
+            let whole = captures.get(0).unwrap();
+            result.push_str(&template[last..whole.start()]);
+            last = whole.end();
+            let Some(name) = captures.get(1) else {
+                result.push_str(&whole.as_str()[..1]);
+                continue;
+            };
+            let value = match name.as_str() {
+                "version" => self.escape(&self.version, format),
+                "ansible_core_version" => self.escape(&self.ansible_core_version, format),
+                "release_date" => self.escape(self.release_date.as_deref().unwrap_or(""), format),
+                "highlights" => self.format_highlights(format),
+                "links" => self.format_links(format),
+                name => {
+                    return Err(Error::InvalidTemplate(format!(
+                        "Unknown placeholder {{{}}}",
+                        name
+                    )))
+                }
+            };
+            result.push_str(&value);
+        }
+        result.push_str(&template[last..]);
+        Ok(result)
+    }
+
+    /// Render the announcement with the default template for `kind`.
+    pub fn render_for(&self, kind: AnnouncementKind) -> String {
+        self.render(kind.template(), kind.format()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn announcement() -> Announcement {
+        Announcement {
+            version: "10.1.0".to_string(),
+            ansible_core_version: "2.17.1".to_string(),
+            release_date: Some("2024-06-18".to_string()),
+            highlights: vec![
+                Highlight {
+                    collection: None,
+                    text: "The ``foo.bar`` collection 1.0.0 has been added.".to_string(),
+                },
+                Highlight {
+                    collection: Some("community.general".to_string()),
+                    text: "foo - the ``bar`` option is now required (`#1 <https://x/1>`__)."
+                        .to_string(),
+                },
+            ],
+            links: vec![(
+                "Changelog".to_string(),
+                "https://example.com/changelog".to_string(),
+            )],
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let announcement = announcement();
+        assert_eq!(
+            announcement
+                .render(
+                    "{version} ({release_date}) {{x}}:\n{highlights}\n{links}",
+                    AnnouncementFormat::PlainText
+                )
+                .unwrap(),
+            "10.1.0 (2024-06-18) {x}:\n- The `foo.bar' collection 1.0.0 has been added.\n- community.general: foo - the `bar' option is now required (#1 <https://x/1>).\n- Changelog: https://example.com/changelog"
+        );
+        assert_eq!(
+            announcement
+                .render("{version}\n{highlights}\n{links}", AnnouncementFormat::Markdown)
+                .unwrap(),
+            "10\\.1\\.0\n* The <code>foo\\.bar</code> collection 1\\.0\\.0 has been added\\.\n* community\\.general: foo \\- the <code>bar</code> option is now required \\([\\#1](https\\://x/1)\\)\\.\n* [Changelog](<https://example.com/changelog>)"
+        );
+        assert_eq!(
+            announcement
+                .render("{foo}", AnnouncementFormat::PlainText)
+                .unwrap_err()
+                .to_string(),
+            "Unknown placeholder {foo}"
+        );
+        assert!(announcement
+            .render_for(AnnouncementKind::Forum)
+            .starts_with("# Ansible 10\\.1\\.0 has been released!\n"));
+        assert!(announcement
+            .render_for(AnnouncementKind::MailingList)
+            .contains("\nLinks:\n\n- Changelog: https://example.com/changelog\n"));
+    }
+}
@@ -0,0 +1,267 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::util::version::Version;
+use crate::util::yaml;
+use saphyr::{Hash, Yaml};
+use std::collections::BTreeMap;
+
+/// The changelog categories with their titles, in the order they are shown.
+///
+/// These are the default sections of antsibull-changelog.
+pub const CHANGELOG_CATEGORIES: &[(&str, &str)] = &[
+    ("major_changes", "Major Changes"),
+    ("minor_changes", "Minor Changes"),
+    ("breaking_changes", "Breaking Changes / Porting Guide"),
+    ("deprecated_features", "Deprecated Features"),
+    (
+        "removed_features",
+        "Removed Features (previously deprecated)",
+    ),
+    ("security_fixes", "Security Fixes"),
+    ("bugfixes", "Bugfixes"),
+    ("known_issues", "Known Issues"),
+];
+
+/// A plugin, module, or object (like a role) added in a release.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewPlugin {
+    /// The name inside the collection.
+    pub name: String,
+
+    /// The short description.
+    pub description: String,
+}
+
+/// A release of a collection, from its `changelogs/changelog.yaml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    /// The version.
+    pub version: Version,
+
+    /// The release date, like `2024-06-17`.
+    pub release_date: Option<String>,
+
+    /// The release summary.
+    pub release_summary: Option<String>,
+
+    /// The changelog entries by category, like `minor_changes`.
+    pub changes: BTreeMap<String, Vec<String>>,
+
+    /// New modules.
+    pub modules: Vec<NewPlugin>,
+
+    /// New plugins by plugin type, like `lookup`.
+    pub plugins: BTreeMap<String, Vec<NewPlugin>>,
+
+    /// New objects by object type, like `role` or `playbook`.
+    pub objects: BTreeMap<String, Vec<NewPlugin>>,
+}
+
+/// The contents of a collection's `changelogs/changelog.yaml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangelogData {
+    /// The version the changelog continues from, if it does not start with the first release.
+    pub ancestor: Option<String>,
+
+    /// The releases, sorted by version.
+    pub releases: Vec<Release>,
+}
+
+impl ChangelogData {
+    /// The releases newer than `after` (if provided), up to and including `up_to`.
+    pub fn releases_between(&self, after: Option<&Version>, up_to: &Version) -> Vec<&Release> {
+        self.releases
+            .iter()
+            .filter(|release| {
+                &release.version <= up_to && after.is_none_or(|after| &release.version > after)
+            })
+            .collect()
+    }
+}
+
+fn load_new_plugins(value: Option<&Yaml>, path: &str) -> Result<Vec<NewPlugin>, String> {
+    let entries = match value {
+        None | Some(Yaml::Null) => return Ok(Vec::new()),
+        Some(Yaml::Array(entries)) => entries,
+        Some(_) => return Err(format!("{}: expected a list", path)),
+    };
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let path = format!("{}[{}]", path, index);
+            let entry = yaml::as_hash(entry, &path)?;
+            Ok(NewPlugin {
+                name: yaml::get_string(entry, "name", &path)?
+                    .ok_or_else(|| format!("{}.name: missing", path))?,
+                description: yaml::get_string(entry, "description", &path)?.unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+fn load_plugin_map(
+    hash: &Hash,
+    key: &str,
+    path: &str,
+) -> Result<BTreeMap<String, Vec<NewPlugin>>, String> {
+    let path = format!("{}.{}", path, key);
+    match yaml::get(hash, key) {
+        None | Some(Yaml::Null) => Ok(BTreeMap::new()),
+        Some(value) => yaml::as_hash(value, &path)?
+            .iter()
+            .map(|(plugin_type, plugins)| {
+                let plugin_type = yaml::scalar_to_string(plugin_type)
+                    .ok_or_else(|| format!("{}: expected string keys", path))?;
+                let plugins =
+                    load_new_plugins(Some(plugins), &format!("{}.{}", path, plugin_type))?;
+                Ok((plugin_type, plugins))
+            })
+            .collect(),
+    }
+}
+
+fn load_release(version: &str, value: &Yaml) -> Result<Release, String> {
+    let path = format!("releases.{}", version);
+    let hash = yaml::as_hash(value, &path)?;
+    let mut release = Release {
+        version: Version::parse(version).map_err(|error| format!("{}: {}", path, error))?,
+        release_date: yaml::get_string(hash, "release_date", &path)?,
+        release_summary: None,
+        changes: BTreeMap::new(),
+        modules: load_new_plugins(yaml::get(hash, "modules"), &format!("{}.modules", path))?,
+        plugins: load_plugin_map(hash, "plugins", &path)?,
+        objects: load_plugin_map(hash, "objects", &path)?,
+    };
+    if let Some(changes) = yaml::get(hash, "changes") {
+        let changes_path = format!("{}.changes", path);
+        for (category, entries) in yaml::as_hash(changes, &changes_path)? {
+            let category = yaml::scalar_to_string(category)
+                .ok_or_else(|| format!("{}: expected string keys", changes_path))?;
+            if category == "release_summary" {
+                release.release_summary = yaml::scalar_to_string(entries);
+                continue;
+            }
+            let entries = match entries {
+                Yaml::Array(entries) => entries
+                    .iter()
+                    .map(|entry| {
+                        yaml::scalar_to_string(entry).ok_or_else(|| {
+                            format!("{}.{}: expected a list of strings", changes_path, category)
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+                Yaml::Null => Vec::new(),
+                entry => vec![yaml::scalar_to_string(entry).ok_or_else(|| {
+                    format!("{}.{}: expected a list of strings", changes_path, category)
+                })?],
+            };
+            release.changes.insert(category, entries);
+        }
+    }
+    Ok(release)
+}
+
+/// Load a `changelogs/changelog.yaml` file.
+pub fn load_changelog_yaml(content: &str) -> Result<ChangelogData, String> {
+    let mut documents = Yaml::load_from_str(content).map_err(|error| {
+        format!(
+            "Syntax error in line {}, column {}: {}",
+            error.marker().line(),
+            error.marker().col() + 1,
+            error.info()
+        )
+    })?;
+    let document = if documents.is_empty() {
+        Yaml::Null
+    } else {
+        documents.swap_remove(0)
+    };
+    let hash = yaml::as_hash(&document, "changelog")?;
+    let mut releases = match yaml::get(hash, "releases") {
+        None | Some(Yaml::Null) => Vec::new(),
+        Some(value) => yaml::as_hash(value, "releases")?
+            .iter()
+            .map(|(version, release)| {
+                let version = yaml::scalar_to_string(version)
+                    .ok_or_else(|| "releases: expected versions as keys".to_string())?;
+                load_release(&version, release)
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+    releases.sort_by(|a, b| a.version.cmp(&b.version));
+    Ok(ChangelogData {
+        ancestor: yaml::get_string(hash, "ancestor", "changelog")?,
+        releases,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANGELOG: &str = r#"
+ancestor: null
+releases:
+  1.1.0:
+    changes:
+      release_summary: Regular release.
+      minor_changes:
+        - foo - add ``bar`` option (https://github.com/foo/bar/pull/1).
+      bugfixes:
+        - foo - fix crash.
+    modules:
+      - name: baz
+        description: Do baz
+        namespace: ''
+    plugins:
+      lookup:
+        - name: bam
+          description: Look up bam
+          namespace: null
+    release_date: '2024-02-01'
+  1.0.0:
+    changes:
+      release_summary: Initial release.
+    release_date: '2024-01-01'
+"#;
+
+    #[test]
+    fn test_load_changelog_yaml() {
+        let changelog = load_changelog_yaml(CHANGELOG).unwrap();
+        assert_eq!(changelog.ancestor, None);
+        assert_eq!(
+            changelog
+                .releases
+                .iter()
+                .map(|r| r.version.to_string())
+                .collect::<Vec<_>>(),
+            vec!["1.0.0", "1.1.0"]
+        );
+        let release = &changelog.releases[1];
+        assert_eq!(
+            release.release_summary,
+            Some("Regular release.".to_string())
+        );
+        assert_eq!(release.changes["bugfixes"], vec!["foo - fix crash."]);
+        assert_eq!(release.modules[0].name, "baz");
+        assert_eq!(release.plugins["lookup"][0].description, "Look up bam");
+
+        let v = |v: &str| Version::parse(v).unwrap();
+        assert_eq!(
+            changelog
+                .releases_between(Some(&v("1.0.0")), &v("2.0.0"))
+                .len(),
+            1
+        );
+        assert_eq!(changelog.releases_between(None, &v("1.0.0")).len(), 1);
+        assert_eq!(
+            load_changelog_yaml("releases:\n  foo: {}").unwrap_err(),
+            "releases.foo: Invalid version \"foo\""
+        );
+    }
+}
@@ -0,0 +1,23 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Changelogs of collections and the Ansible package.
+
+mod announcement;
+mod data;
+mod package;
+mod porting_guide;
+mod rst_inline;
+
+pub use announcement::{Announcement, AnnouncementFormat, AnnouncementKind, Highlight};
+
+pub use data::{load_changelog_yaml, ChangelogData, NewPlugin, Release, CHANGELOG_CATEGORIES};
+
+pub use package::{CategoryEntries, CollectionChange, NewPlugins, PackageReleaseNotes};
+
+pub use porting_guide::{PortingGuide, PORTING_GUIDE_CATEGORIES};
+
+pub use rst_inline::rst_inline_to_paragraph;
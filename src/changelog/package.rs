@@ -0,0 +1,553 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::build::DepsFile;
+use crate::changelog::data::{ChangelogData, NewPlugin, CHANGELOG_CATEGORIES};
+use crate::changelog::rst_inline::rst_inline_to_paragraph;
+use crate::document::{append_md_blocks, append_plain_rst_blocks, Block, Paragraph};
+use crate::markup::{NoLinkProvider, Part};
+use std::collections::BTreeMap;
+
+/// How the version of a collection changed between two releases of the package.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionChange {
+    /// The collection name, like `community.general`.
+    pub name: String,
+
+    /// The version in the previous release, or `None` if the collection was added.
+    pub previous: Option<String>,
+
+    /// The version in this release, or `None` if the collection was removed.
+    pub current: Option<String>,
+}
+
+/// The changelog entries of one category, grouped by collection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryEntries {
+    /// The category, like `minor_changes`.
+    pub category: String,
+
+    /// The title of the category.
+    pub title: String,
+
+    /// The entries by collection name.
+    pub collections: BTreeMap<String, Vec<String>>,
+}
+
+impl CategoryEntries {
+    /// Append a section with the heading `title` at level `level` to `blocks`, with a subsection
+    /// listing the entries for every collection.
+    pub fn append_to_blocks<'a>(&'a self, blocks: &mut Vec<Block<'a>>, level: usize, title: &str) {
+        blocks.push(Block::Heading {
+            level,
+            title: title.to_string(),
+        });
+        for (collection, entries) in &self.collections {
+            blocks.push(Block::Heading {
+                level: level + 1,
+                title: collection.clone(),
+            });
+            blocks.push(Block::UnorderedList {
+                items: entries
+                    .iter()
+                    .map(|entry| vec![rst_inline_to_paragraph(entry)])
+                    .collect(),
+            });
+        }
+    }
+}
+
+/// Plugins, modules, or objects added to the package.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewPlugins {
+    /// The plugin or object type, like `module`, `lookup`, or `role`.
+    pub plugin_type: String,
+
+    /// The FQCNs with the short descriptions.
+    pub plugins: Vec<(String, String)>,
+}
+
+/// The combined release notes of a release of the Ansible package.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageReleaseNotes {
+    /// The version of the package.
+    pub version: String,
+
+    /// The version of the previous release of the package, if known.
+    pub previous_version: Option<String>,
+
+    /// The ansible-core version of this release.
+    pub ansible_core_version: String,
+
+    /// The ansible-core version of the previous release, if it is different.
+    pub previous_ansible_core_version: Option<String>,
+
+    /// All collections of both releases, sorted by name.
+    pub collections: Vec<CollectionChange>,
+
+    /// The release summaries by collection name.
+    pub release_summaries: BTreeMap<String, Vec<String>>,
+
+    /// The changelog entries, in the order of [`CHANGELOG_CATEGORIES`]. Empty categories are
+    /// omitted.
+    pub categories: Vec<CategoryEntries>,
+
+    /// The new plugins, modules, and objects, by type.
+    pub new_plugins: Vec<NewPlugins>,
+
+    /// The updated collections for which no changelog was provided.
+    pub missing_changelogs: Vec<String>,
+}
+
+fn push_new_plugins(
+    new_plugins: &mut BTreeMap<String, Vec<(String, String)>>,
+    plugin_type: &str,
+    collection: &str,
+    plugins: &[NewPlugin],
+) {
+    new_plugins
+        .entry(plugin_type.to_string())
+        .or_default()
+        .extend(plugins.iter().map(|plugin| {
+            (
+                format!("{}.{}", collection, plugin.name),
+                plugin.description.clone(),
+            )
+        }));
+}
+
+impl PackageReleaseNotes {
+    /// Combine the changelogs of the collections included in `current`.
+    ///
+    /// `previous` is the dependency file of the previous release. For collections that were
+    /// updated, the releases after the previous version up to the current version are included;
+    /// without a previous release, nothing but the list of collections is reported.
+    pub fn new(
+        current: &DepsFile,
+        previous: Option<&DepsFile>,
+        changelogs: &BTreeMap<String, ChangelogData>,
+    ) -> PackageReleaseNotes {
+        let empty = BTreeMap::new();
+        let previous_collections = previous.map_or(&empty, |previous| &previous.collections);
+        let mut names: Vec<&String> = current
+            .collections
+            .keys()
+            .chain(previous_collections.keys())
+            .collect();
+        names.sort();
+        names.dedup();
+
+        let mut collections = Vec::new();
+        let mut release_summaries = BTreeMap::new();
+        let mut entries: BTreeMap<&str, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+        let mut new_plugins = BTreeMap::new();
+        let mut missing_changelogs = Vec::new();
+        for name in names {
+            let previous_version = previous_collections.get(name);
+            let current_version = current.collections.get(name);
+            collections.push(CollectionChange {
+                name: name.clone(),
+                previous: previous_version.map(|version| version.to_string()),
+                current: current_version.map(|version| version.to_string()),
+            });
+            let (Some(previous_version), Some(current_version)) =
+                (previous_version, current_version)
+            else {
+                continue;
+            };
+            if previous_version == current_version {
+                continue;
+            }
+            let Some(changelog) = changelogs.get(name) else {
+                missing_changelogs.push(name.clone());
+                continue;
+            };
+            for release in changelog.releases_between(Some(previous_version), current_version) {
+                if let Some(summary) = &release.release_summary {
+                    release_summaries
+                        .entry(name.clone())
+                        .or_insert_with(Vec::new)
+                        .push(summary.clone());
+                }
+                for (category, changes) in &release.changes {
+                    let collection_entries = entries
+                        .entry(category.as_str())
+                        .or_default()
+                        .entry(name.clone())
+                        .or_default();
+                    for change in changes {
+                        if !collection_entries.contains(change) {
+                            collection_entries.push(change.clone());
+                        }
+                    }
+                }
+                push_new_plugins(&mut new_plugins, "module", name, &release.modules);
+                for (plugin_type, plugins) in release.plugins.iter().chain(&release.objects) {
+                    push_new_plugins(&mut new_plugins, plugin_type, name, plugins);
+                }
+            }
+        }
+
+        PackageReleaseNotes {
+            version: current.ansible_version.to_string(),
+            previous_version: previous.map(|previous| previous.ansible_version.to_string()),
+            ansible_core_version: current.ansible_core_version.to_string(),
+            previous_ansible_core_version: previous
+                .filter(|previous| previous.ansible_core_version != current.ansible_core_version)
+                .map(|previous| previous.ansible_core_version.to_string()),
+            collections,
+            release_summaries,
+            categories: CHANGELOG_CATEGORIES
+                .iter()
+                .filter_map(|(category, title)| {
+                    let collections = entries.remove(category)?;
+                    Some(CategoryEntries {
+                        category: category.to_string(),
+                        title: title.to_string(),
+                        collections,
+                    })
+                })
+                .collect(),
+            new_plugins: new_plugins
+                .into_iter()
+                .filter(|(_, plugins)| !plugins.is_empty())
+                .map(|(plugin_type, mut plugins)| {
+                    plugins.sort();
+                    NewPlugins {
+                        plugin_type,
+                        plugins,
+                    }
+                })
+                .collect(),
+            missing_changelogs,
+        }
+    }
+
+    /// Build the document of the release notes.
+    pub fn to_blocks(&self) -> Vec<Block<'_>> {
+        fn heading(level: usize, title: &str) -> Block<'static> {
+            Block::Heading {
+                level,
+                title: title.to_string(),
+            }
+        }
+        fn text(text: &'static str) -> Part<'static> {
+            Part::Text { text }
+        }
+        fn list(items: Vec<Paragraph<'_>>) -> Block<'_> {
+            Block::UnorderedList {
+                items: items.into_iter().map(|item| vec![item]).collect(),
+            }
+        }
+
+        let mut blocks = vec![heading(1, &format!("v{}", self.version))];
+
+        blocks.push(heading(2, "Ansible-core"));
+        let mut core = vec![
+            text("Ansible "),
+            Part::Text {
+                text: &self.version,
+            },
+            text(" contains ansible-core version "),
+            Part::Text {
+                text: &self.ansible_core_version,
+            },
+            text("."),
+        ];
+        if let Some(previous) = &self.previous_ansible_core_version {
+            core.push(text(" This is a newer version than version "));
+            core.push(Part::Text { text: previous });
+            core.push(text(" contained in the previous Ansible release."));
+        }
+        blocks.push(Block::Paragraph { parts: core });
+
+        let added: Vec<_> = self
+            .collections
+            .iter()
+            .filter_map(|change| match (&change.previous, &change.current) {
+                (None, Some(current)) => Some(vec![
+                    Part::Text { text: &change.name },
+                    text(" (version "),
+                    Part::Text { text: current },
+                    text(")"),
+                ]),
+                _ => None,
+            })
+            .collect();
+        if !added.is_empty() && self.previous_version.is_some() {
+            blocks.push(heading(2, "Added Collections"));
+            blocks.push(list(added));
+        }
+
+        let removed: Vec<_> = self
+            .collections
+            .iter()
+            .filter_map(|change| match (&change.previous, &change.current) {
+                (Some(previous), None) => Some(vec![
+                    Part::Text { text: &change.name },
+                    text(" (previously included version: "),
+                    Part::Text { text: previous },
+                    text(")"),
+                ]),
+                _ => None,
+            })
+            .collect();
+        if !removed.is_empty() {
+            blocks.push(heading(2, "Removed Collections"));
+            blocks.push(list(removed));
+        }
+
+        blocks.push(heading(2, "Included Collections"));
+        blocks.push(Block::Paragraph {
+            parts: vec![text(
+                "If not mentioned explicitly, the changes are reported in the combined changelog below.",
+            )],
+        });
+        blocks.push(list(
+            self.collections
+                .iter()
+                .filter_map(|change| {
+                    let current = change.current.as_ref()?;
+                    let mut item = vec![
+                        Part::Text { text: &change.name },
+                        text(": "),
+                        Part::Text { text: current },
+                    ];
+                    match &change.previous {
+                        Some(previous) if previous != current => {
+                            item.push(text(" (previously "));
+                            item.push(Part::Text { text: previous });
+                            item.push(text(")"));
+                        }
+                        _ => {}
+                    }
+                    Some(item)
+                })
+                .collect(),
+        ));
+
+        if !self.missing_changelogs.is_empty() {
+            blocks.push(heading(2, "Collections Without Changelog"));
+            blocks.push(Block::Paragraph {
+                parts: vec![text(
+                    "The changelogs of the following updated collections could not be found:",
+                )],
+            });
+            blocks.push(list(
+                self.missing_changelogs
+                    .iter()
+                    .map(|name| vec![Part::Text { text: name }])
+                    .collect(),
+            ));
+        }
+
+        if !self.release_summaries.is_empty() {
+            blocks.push(heading(2, "Release Summaries"));
+            for (collection, summaries) in &self.release_summaries {
+                blocks.push(heading(3, collection));
+                blocks.extend(summaries.iter().map(|summary| Block::Paragraph {
+                    parts: rst_inline_to_paragraph(summary),
+                }));
+            }
+        }
+
+        for category in &self.categories {
+            category.append_to_blocks(&mut blocks, 2, &category.title);
+        }
+
+        for new_plugins in &self.new_plugins {
+            blocks.push(heading(2, &new_plugin_title(&new_plugins.plugin_type)));
+            blocks.push(list(
+                new_plugins
+                    .plugins
+                    .iter()
+                    .map(|(fqcn, description)| {
+                        let mut item = vec![Part::Text { text: fqcn }];
+                        if !description.is_empty() {
+                            item.push(text(" - "));
+                            item.extend(rst_inline_to_paragraph(description));
+                        }
+                        item
+                    })
+                    .collect(),
+            ));
+        }
+
+        blocks
+    }
+
+    /// Render the release notes as RST.
+    pub fn to_rst(&self) -> String {
+        let mut result = String::new();
+        append_plain_rst_blocks(
+            &mut result,
+            &self.to_blocks(),
+            &NoLinkProvider::new(),
+            &None,
+        );
+        result.push('\n');
+        result
+    }
+
+    /// Render the release notes as MarkDown.
+    pub fn to_md(&self) -> String {
+        let mut result = String::new();
+        append_md_blocks(
+            &mut result,
+            &self.to_blocks(),
+            &NoLinkProvider::new(),
+            &None,
+        );
+        result.push('\n');
+        result
+    }
+}
+
+fn new_plugin_title(plugin_type: &str) -> String {
+    match plugin_type {
+        "module" => "New Modules".to_string(),
+        "role" => "New Roles".to_string(),
+        "playbook" => "New Playbooks".to_string(),
+        "execution_environment" => "New Execution Environments".to_string(),
+        plugin_type => {
+            let mut chars = plugin_type.chars();
+            let capitalized = match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            };
+            format!("New {} Plugins", capitalized)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::changelog::data::load_changelog_yaml;
+
+    const PREVIOUS: &str = "_ansible_version: 10.0.0
+_ansible_core_version: 2.17.0
+community.general: 9.0.0
+community.docker: 3.10.0
+foo.old: 1.0.0
+";
+
+    const CURRENT: &str = "_ansible_version: 10.1.0
+_ansible_core_version: 2.17.1
+community.general: 9.1.0
+community.docker: 3.10.0
+foo.new: 2.0.0
+";
+
+    const GENERAL: &str = r#"
+releases:
+  9.0.0:
+    changes:
+      release_summary: Major release.
+  9.1.0:
+    changes:
+      release_summary: Regular ``release``.
+      minor_changes:
+        - foo - add option (`#1 <https://x/1>`__).
+      bugfixes:
+        - bar - fix crash.
+    plugins:
+      lookup:
+        - name: bam
+          description: Look up bam
+"#;
+
+    #[test]
+    fn test_package_release_notes() {
+        let current = DepsFile::parse(CURRENT).unwrap();
+        let previous = DepsFile::parse(PREVIOUS).unwrap();
+        let changelogs = BTreeMap::from([(
+            "community.general".to_string(),
+            load_changelog_yaml(GENERAL).unwrap(),
+        )]);
+        let notes = PackageReleaseNotes::new(&current, Some(&previous), &changelogs);
+        assert_eq!(
+            notes.previous_ansible_core_version,
+            Some("2.17.0".to_string())
+        );
+        assert_eq!(notes.collections.len(), 4);
+        assert_eq!(notes.missing_changelogs, Vec::<String>::new());
+        assert_eq!(
+            notes
+                .categories
+                .iter()
+                .map(|c| c.category.as_str())
+                .collect::<Vec<_>>(),
+            vec!["minor_changes", "bugfixes"]
+        );
+        assert_eq!(
+            notes.to_rst(),
+            r#"v10.1.0
+=======
+
+Ansible-core
+------------
+
+Ansible 10.1.0 contains ansible-core version 2.17.1. This is a newer version than version 2.17.0 contained in the previous Ansible release.
+
+Added Collections
+-----------------
+
+* foo.new (version 2.0.0)
+
+Removed Collections
+-------------------
+
+* foo.old (previously included version: 1.0.0)
+
+Included Collections
+--------------------
+
+If not mentioned explicitly, the changes are reported in the combined changelog below.
+
+* community.docker: 3.10.0
+* community.general: 9.1.0 (previously 9.0.0)
+* foo.new: 2.0.0
+
+Release Summaries
+-----------------
+
+community.general
+~~~~~~~~~~~~~~~~~
+
+Regular \ :literal:`release`\ .
+
+Minor Changes
+-------------
+
+community.general
+~~~~~~~~~~~~~~~~~
+
+* foo - add option (\ `#1 <https://x/1>`__\ ).
+
+Bugfixes
+--------
+
+community.general
+~~~~~~~~~~~~~~~~~
+
+* bar - fix crash.
+
+New Lookup Plugins
+------------------
+
+* community.general.bam - Look up bam
+"#
+        );
+        assert!(notes
+            .to_md()
+            .starts_with("# v10\\.1\\.0\n\n## Ansible\\-core\n\n"));
+
+        let notes = PackageReleaseNotes::new(&current, Some(&previous), &BTreeMap::new());
+        assert_eq!(notes.missing_changelogs, vec!["community.general"]);
+        assert!(notes.categories.is_empty());
+    }
+}
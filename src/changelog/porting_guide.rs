@@ -0,0 +1,200 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::changelog::data::Release;
+use crate::changelog::package::{CategoryEntries, PackageReleaseNotes};
+use crate::document::{append_md_blocks, append_plain_rst_blocks, Block};
+use crate::markup::NoLinkProvider;
+use std::collections::BTreeMap;
+
+/// The changelog categories that are part of the porting guide, with their titles.
+pub const PORTING_GUIDE_CATEGORIES: &[(&str, &str)] = &[
+    ("breaking_changes", "Breaking Changes"),
+    ("deprecated_features", "Deprecated Features"),
+    ("removed_features", "Removed Features"),
+];
+
+/// The porting guide entries of a release, grouped by category and collection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortingGuide {
+    /// The version the porting guide is for.
+    pub version: String,
+
+    /// The entries, in the order of [`PORTING_GUIDE_CATEGORIES`]. Empty categories are omitted.
+    pub sections: Vec<CategoryEntries>,
+}
+
+impl PortingGuide {
+    /// Extract the porting guide entries from the releases of several collections.
+    ///
+    /// `releases` maps collection names to the releases that are covered.
+    pub fn extract(version: &str, releases: &BTreeMap<String, Vec<&Release>>) -> PortingGuide {
+        let sections = PORTING_GUIDE_CATEGORIES
+            .iter()
+            .filter_map(|(category, title)| {
+                let collections: BTreeMap<String, Vec<String>> = releases
+                    .iter()
+                    .filter_map(|(collection, releases)| {
+                        let mut entries: Vec<String> = Vec::new();
+                        for entry in releases
+                            .iter()
+                            .filter_map(|release| release.changes.get(*category))
+                            .flatten()
+                        {
+                            if !entries.contains(entry) {
+                                entries.push(entry.clone());
+                            }
+                        }
+                        (!entries.is_empty()).then(|| (collection.clone(), entries))
+                    })
+                    .collect();
+                (!collections.is_empty()).then(|| CategoryEntries {
+                    category: category.to_string(),
+                    title: title.to_string(),
+                    collections,
+                })
+            })
+            .collect();
+        PortingGuide {
+            version: version.to_string(),
+            sections,
+        }
+    }
+
+    /// Take the porting guide entries from the release notes of the Ansible package.
+    pub fn from_release_notes(notes: &PackageReleaseNotes) -> PortingGuide {
+        PortingGuide {
+            version: notes.version.clone(),
+            sections: PORTING_GUIDE_CATEGORIES
+                .iter()
+                .filter_map(|(category, title)| {
+                    let entries = notes.categories.iter().find(|c| c.category == *category)?;
+                    Some(CategoryEntries {
+                        title: title.to_string(),
+                        ..entries.clone()
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Whether there are no porting guide entries.
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    /// Build the document of the porting guide.
+    pub fn to_blocks(&self) -> Vec<Block<'_>> {
+        let mut blocks = vec![Block::Heading {
+            level: 1,
+            title: format!("Porting Guide for v{}", self.version),
+        }];
+        for section in &self.sections {
+            section.append_to_blocks(&mut blocks, 2, &section.title);
+        }
+        blocks
+    }
+
+    /// Render the porting guide as RST.
+    pub fn to_rst(&self) -> String {
+        let mut result = String::new();
+        append_plain_rst_blocks(
+            &mut result,
+            &self.to_blocks(),
+            &NoLinkProvider::new(),
+            &None,
+        );
+        result.push('\n');
+        result
+    }
+
+    /// Render the porting guide as MarkDown.
+    pub fn to_md(&self) -> String {
+        let mut result = String::new();
+        append_md_blocks(
+            &mut result,
+            &self.to_blocks(),
+            &NoLinkProvider::new(),
+            &None,
+        );
+        result.push('\n');
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::DepsFile;
+    use crate::changelog::data::load_changelog_yaml;
+    use crate::util::version::Version;
+
+    const CHANGELOG: &str = r#"
+releases:
+  2.0.0:
+    changes:
+      breaking_changes:
+        - foo - the ``bar`` option has been removed.
+      bugfixes:
+        - baz - fix crash.
+  2.1.0:
+    changes:
+      deprecated_features:
+        - foo - the ``baz`` option is deprecated.
+"#;
+
+    #[test]
+    fn test_porting_guide() {
+        let changelog = load_changelog_yaml(CHANGELOG).unwrap();
+        let version = Version::parse("2.1.0").unwrap();
+        let releases = BTreeMap::from([(
+            "foo.bar".to_string(),
+            changelog.releases_between(None, &version),
+        )]);
+        let guide = PortingGuide::extract("11.0.0", &releases);
+        assert_eq!(
+            guide.to_rst(),
+            r#"Porting Guide for v11.0.0
+=========================
+
+Breaking Changes
+----------------
+
+foo.bar
+~~~~~~~
+
+* foo - the \ :literal:`bar`\  option has been removed.
+
+Deprecated Features
+-------------------
+
+foo.bar
+~~~~~~~
+
+* foo - the \ :literal:`baz`\  option is deprecated.
+"#
+        );
+        assert!(guide.to_md().starts_with(
+            "# Porting Guide for v11\\.0\\.0\n\n## Breaking Changes\n\n### foo\\.bar\n\n"
+        ));
+
+        let current = DepsFile::parse(
+            "_ansible_version: 11.0.0\n_ansible_core_version: 2.18.0\nfoo.bar: 2.1.0\n",
+        )
+        .unwrap();
+        let previous = DepsFile::parse(
+            "_ansible_version: 10.0.0\n_ansible_core_version: 2.17.0\nfoo.bar: 1.0.0\n",
+        )
+        .unwrap();
+        let notes = PackageReleaseNotes::new(
+            &current,
+            Some(&previous),
+            &BTreeMap::from([("foo.bar".to_string(), changelog)]),
+        );
+        assert_eq!(PortingGuide::from_release_notes(&notes), guide);
+        assert!(PortingGuide::extract("1.0.0", &BTreeMap::new()).is_empty());
+    }
+}
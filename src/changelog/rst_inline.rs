@@ -0,0 +1,131 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::document::Paragraph;
+use crate::markup::Part;
+use regex::Regex;
+use std::sync::LazyLock;
+
+static INLINE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"``(?P<literal>.+?)``",
+        r"|:ref:`(?P<ref>[^`]+)`",
+        r"|:[a-z][a-z0-9_-]*:`(?P<role>[^`]+)`",
+        r"|`(?P<link_text>[^`<]*?)\s*<(?P<link_url>[^`>]+)>`__?",
+        r"|\*\*(?P<bold>[^*]+?)\*\*",
+        r"|\*(?P<italic>[^*\s][^*]*?)\*",
+    ))
+    .unwrap()
+});
+
+static REF_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?P<text>.*?)\s*<(?P<ref>[^<>]+)>$").unwrap());
+
+/// Convert the RST inline markup used in changelog entries to markup parts.
+///
+/// Literals, links, references, and emphasis are converted. Other roles are shown as code,
+/// and everything else is kept as text.
+pub fn rst_inline_to_paragraph(text: &str) -> Paragraph<'_> {
+    let mut result = Vec::new();
+    let mut last = 0;
+    for captures in INLINE_RE.captures_iter(text) {
+        let whole = captures.get(0).unwrap();
+        if whole.start() > last {
+            result.push(Part::Text {
+                text: &text[last..whole.start()],
+            });
+        }
+        last = whole.end();
+        let part = if let Some(literal) = captures.name("literal") {
+            Part::Code {
+                text: literal.as_str(),
+            }
+        } else if let Some(reference) = captures.name("ref") {
+            let reference = reference.as_str();
+            match REF_RE.captures(reference) {
+                Some(parts) => Part::RSTRef {
+                    text: parts.name("text").unwrap().as_str(),
+                    r#ref: parts.name("ref").unwrap().as_str(),
+                },
+                None => Part::RSTRef {
+                    text: reference,
+                    r#ref: reference,
+                },
+            }
+        } else if let Some(role) = captures.name("role") {
+            Part::Code {
+                text: role.as_str(),
+            }
+        } else if let Some(url) = captures.name("link_url") {
+            let text = captures.name("link_text").unwrap().as_str();
+            if text.is_empty() {
+                Part::URL { url: url.as_str() }
+            } else {
+                Part::Link {
+                    text,
+                    url: url.as_str(),
+                }
+            }
+        } else if let Some(bold) = captures.name("bold") {
+            Part::Bold {
+                text: bold.as_str(),
+            }
+        } else {
+            Part::Italic {
+                text: captures.name("italic").unwrap().as_str(),
+            }
+        };
+        result.push(part);
+    }
+    if last < text.len() {
+        result.push(Part::Text {
+            text: &text[last..],
+        });
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rst_inline_to_paragraph() {
+        assert_eq!(
+            rst_inline_to_paragraph(
+                "foo - add ``bar`` (`PR <https://x/1>`__, :ref:`docs <ref>`, :ansplugin:`a.b.c#module`)."
+            ),
+            vec![
+                Part::Text { text: "foo - add " },
+                Part::Code { text: "bar" },
+                Part::Text { text: " (" },
+                Part::Link {
+                    text: "PR",
+                    url: "https://x/1"
+                },
+                Part::Text { text: ", " },
+                Part::RSTRef {
+                    text: "docs",
+                    r#ref: "ref"
+                },
+                Part::Text { text: ", " },
+                Part::Code {
+                    text: "a.b.c#module"
+                },
+                Part::Text { text: ")." },
+            ]
+        );
+        assert_eq!(
+            rst_inline_to_paragraph("**a** *b* 2 * 3 * 4"),
+            vec![
+                Part::Bold { text: "a" },
+                Part::Text { text: " " },
+                Part::Italic { text: "b" },
+                Part::Text { text: " 2 * 3 * 4" },
+            ]
+        );
+    }
+}
@@ -0,0 +1,192 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::collection::files::{CollectionFiles, FilesError};
+use flate2::read::GzDecoder;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// The files of a collection artifact (`namespace-name-version.tar.gz`), read into memory.
+///
+/// Only regular files are kept; directories, links, and entries with paths leaving the artifact
+/// are skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionArtifact {
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+/// Normalize the path of an archive entry, or return `None` if it is not safe to use.
+fn normalize_entry_path(path: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            part => parts.push(part),
+        }
+    }
+    if parts.is_empty() || path.starts_with('/') {
+        None
+    } else {
+        Some(parts.join("/"))
+    }
+}
+
+impl CollectionArtifact {
+    /// Read a gzip-compressed tarball.
+    pub fn read<R: Read>(reader: R) -> Result<CollectionArtifact, FilesError> {
+        let tar_error = |error: std::io::Error| FilesError {
+            path: String::new(),
+            message: format!("invalid artifact: {}", error),
+        };
+        let mut archive = tar::Archive::new(GzDecoder::new(reader));
+        let mut files = BTreeMap::new();
+        for entry in archive.entries().map_err(tar_error)? {
+            let mut entry = entry.map_err(tar_error)?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = entry.path().map_err(tar_error)?;
+            let Some(path) = path.to_str().and_then(normalize_entry_path) else {
+                continue;
+            };
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).map_err(tar_error)?;
+            files.insert(path, content);
+        }
+        Ok(CollectionArtifact { files })
+    }
+
+    /// Read the artifact at `path`.
+    pub fn open(path: &Path) -> Result<CollectionArtifact, FilesError> {
+        let file = File::open(path).map_err(|error| FilesError {
+            path: path.display().to_string(),
+            message: error.to_string(),
+        })?;
+        CollectionArtifact::read(file).map_err(|error| FilesError {
+            path: path.display().to_string(),
+            message: error.message,
+        })
+    }
+}
+
+impl CollectionFiles for CollectionArtifact {
+    fn file_paths(&self) -> Vec<String> {
+        self.files.keys().cloned().collect()
+    }
+
+    fn read_file(&self, path: &str) -> Result<Option<Vec<u8>>, FilesError> {
+        Ok(self.files.get(path).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::verify::{sha256_hex, VerifyError};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    fn build_artifact(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (path, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, content.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn test_collection_artifact() {
+        let data = build_artifact(&[
+            (
+                "MANIFEST.json",
+                r#"{"collection_info": {"namespace": "foo", "name": "bar", "version": "1.0.0"}}"#,
+            ),
+            ("meta/runtime.yml", "requires_ansible: '>=2.15.0'\n"),
+            (
+                "./plugins/modules/baz.py",
+                "DOCUMENTATION = r'''\nmodule: baz\n'''\n",
+            ),
+            ("plugins/module_utils/x.py", ""),
+        ]);
+        let artifact = CollectionArtifact::read(data.as_slice()).unwrap();
+        assert_eq!(
+            artifact.file_paths(),
+            vec![
+                "MANIFEST.json",
+                "meta/runtime.yml",
+                "plugins/module_utils/x.py",
+                "plugins/modules/baz.py",
+            ]
+        );
+        assert_eq!(artifact.metadata().unwrap().fqcn(), "foo.bar");
+        assert_eq!(
+            artifact.runtime().unwrap().unwrap().requires_ansible,
+            Some(">=2.15.0".to_string())
+        );
+        let plugins = artifact.plugin_files();
+        assert_eq!(plugins.len(), 1);
+        assert_eq!(plugins[0].name, "baz");
+        assert!(artifact
+            .doc_blocks(&plugins[0])
+            .unwrap()
+            .documentation
+            .is_some());
+        assert_eq!(
+            artifact
+                .verify_checksums()
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>(),
+            vec!["Invalid checksum manifest: MANIFEST.json.file_manifest_file: expected a mapping"]
+        );
+        assert!(CollectionArtifact::read(&b"not a tarball"[..]).is_err());
+    }
+
+    #[test]
+    fn test_verify_checksums() {
+        let files = format!(
+            r#"{{"files": [{{"name": "README.md", "ftype": "file", "chksum_type": "sha256", "chksum_sha256": "{}"}}]}}"#,
+            sha256_hex(b"Hello")
+        );
+        let manifest = format!(
+            r#"{{"collection_info": {{}}, "file_manifest_file": {{"name": "FILES.json", "chksum_type": "sha256", "chksum_sha256": "{}"}}}}"#,
+            sha256_hex(files.as_bytes())
+        );
+        let read = |readme: &str| {
+            CollectionArtifact::read(
+                build_artifact(&[
+                    ("MANIFEST.json", &manifest),
+                    ("FILES.json", &files),
+                    ("README.md", readme),
+                ])
+                .as_slice(),
+            )
+            .unwrap()
+        };
+        assert_eq!(read("Hello").verify_checksums(), vec![]);
+        assert!(matches!(
+            &read("Hallo").verify_checksums()[..],
+            [VerifyError::ChecksumMismatch { path, .. }] if path == "README.md"
+        ));
+    }
+
+    #[test]
+    fn test_normalize_entry_path() {
+        assert_eq!(normalize_entry_path("./a//b"), Some("a/b".to_string()));
+        assert_eq!(normalize_entry_path("a/../../b"), None);
+        assert_eq!(normalize_entry_path("/etc/passwd"), None);
+        assert_eq!(normalize_entry_path("./"), None);
+    }
+}
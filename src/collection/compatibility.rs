@@ -0,0 +1,153 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::{HTMLEscaper, MDEscaper, RSTEscaper};
+use crate::util::stringbuilder::Appender;
+use crate::util::version::{Operator, Specifier, SpecifierSet, Version};
+
+/// The ansible-core versions out of `versions` that satisfy `requires_ansible`, in the given order.
+///
+/// Pre-releases are only included if `requires_ansible` explicitly mentions one.
+pub fn supported_ansible_core_versions<'v>(
+    requires_ansible: &SpecifierSet,
+    versions: &'v [Version],
+) -> Vec<&'v Version> {
+    requires_ansible.filter(versions, false)
+}
+
+/// The smallest ansible-core version out of `versions` that satisfies `requires_ansible`.
+pub fn minimum_ansible_core_version<'v>(
+    requires_ansible: &SpecifierSet,
+    versions: &'v [Version],
+) -> Option<&'v Version> {
+    supported_ansible_core_versions(requires_ansible, versions)
+        .into_iter()
+        .min()
+}
+
+fn describe_specifier(specifier: &Specifier) -> String {
+    let version = if specifier.wildcard {
+        format!("{}.x", specifier.text.trim_end_matches(".*"))
+    } else {
+        specifier.text.clone()
+    };
+    match specifier.operator {
+        Operator::GreaterEqual => format!("{} or newer", version),
+        Operator::Greater => format!("newer than {}", version),
+        Operator::LessEqual => format!("{} or older", version),
+        Operator::Less => format!("older than {}", version),
+        Operator::NotEqual => format!("not {}", version),
+        Operator::Compatible => format!("compatible with {}", version),
+        Operator::Equal | Operator::Arbitrary => version,
+    }
+}
+
+/// A human readable description of `requires_ansible`, like
+/// `ansible-core 2.14.0 or newer, older than 2.17.0`.
+pub fn describe_requires_ansible(requires_ansible: &SpecifierSet) -> String {
+    if requires_ansible.specifiers.is_empty() {
+        return "any ansible-core version".to_string();
+    }
+    let clauses: Vec<String> = requires_ansible
+        .specifiers
+        .iter()
+        .map(describe_specifier)
+        .collect();
+    format!("ansible-core {}", clauses.join(", "))
+}
+
+fn compatibility_note(requires_ansible: &SpecifierSet) -> String {
+    format!(
+        "This collection requires {}.",
+        describe_requires_ansible(requires_ansible)
+    )
+}
+
+/// Render a compatibility note for `requires_ansible` as RST.
+pub fn append_rst_compatibility_note(
+    appender: &mut dyn Appender<'_>,
+    requires_ansible: &SpecifierSet,
+) {
+    appender.push_owned_string(
+        RSTEscaper::new()
+            .escape(&compatibility_note(requires_ansible), false, false)
+            .into_owned(),
+    );
+}
+
+/// Render a compatibility note for `requires_ansible` as HTML.
+pub fn append_html_compatibility_note(
+    appender: &mut dyn Appender<'_>,
+    requires_ansible: &SpecifierSet,
+) {
+    appender.push_str("<p class=\"ansible-compatibility-note\">");
+    appender.push_owned_string(
+        HTMLEscaper::new()
+            .escape(&compatibility_note(requires_ansible))
+            .into_owned(),
+    );
+    appender.push_str("</p>");
+}
+
+/// Render a compatibility note for `requires_ansible` as MarkDown.
+pub fn append_md_compatibility_note(
+    appender: &mut dyn Appender<'_>,
+    requires_ansible: &SpecifierSet,
+) {
+    appender.push_owned_string(
+        MDEscaper::new()
+            .unwrap()
+            .escape(&compatibility_note(requires_ansible))
+            .into_owned(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::stringbuilder::{CollectorAppender, IntoString};
+
+    #[test]
+    fn test_compatibility() {
+        let versions: Vec<Version> = ["2.13.0", "2.14.0", "2.15.0rc1", "2.15.0", "2.17.0"]
+            .iter()
+            .map(|v| Version::parse(v).unwrap())
+            .collect();
+        let spec = SpecifierSet::parse(">=2.14.0,<2.17").unwrap();
+        assert_eq!(
+            supported_ansible_core_versions(&spec, &versions)
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>(),
+            vec!["2.14.0", "2.15.0"]
+        );
+        assert_eq!(
+            minimum_ansible_core_version(&spec, &versions),
+            Some(&versions[1])
+        );
+        assert_eq!(
+            describe_requires_ansible(&SpecifierSet::parse("==2.15.*,!=2.15.1").unwrap()),
+            "ansible-core 2.15.x, not 2.15.1"
+        );
+        assert_eq!(
+            describe_requires_ansible(&SpecifierSet::default()),
+            "any ansible-core version"
+        );
+
+        let mut appender = CollectorAppender::new();
+        append_html_compatibility_note(&mut appender, &spec);
+        appender.push_str("\n");
+        append_rst_compatibility_note(&mut appender, &spec);
+        assert_eq!(
+            appender.into_string(),
+            concat!(
+                "<p class=\"ansible-compatibility-note\">This collection requires ansible-core ",
+                "2.14.0 or newer, older than 2.17.</p>\n",
+                "This collection requires ansible-core 2.14.0 or newer, older than 2.17."
+            )
+        );
+    }
+}
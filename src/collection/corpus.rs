@@ -0,0 +1,232 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::collection::files::{CollectionDirectory, CollectionFiles, FilesError};
+use crate::util::json::push_json_string;
+use crate::util::yaml::collect_markup_fields;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A documentation string, with where it comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorpusEntry {
+    /// The collection's name, like `community.general`.
+    pub collection: String,
+
+    /// The collection's version, if known.
+    pub version: Option<String>,
+
+    /// The FQCN of the plugin, module, or role.
+    pub plugin: String,
+
+    /// The plugin type, like `module`, `lookup`, or `role`.
+    pub plugin_type: String,
+
+    /// The path of the file, relative to the collection's root.
+    pub file: String,
+
+    /// The YAML path of the paragraph in the documentation, like `options.name.description[0]`.
+    pub field: String,
+
+    /// The paragraph's markup, with `\r\n` line endings replaced by `\n` and surrounding
+    /// whitespace removed.
+    pub text: String,
+}
+
+impl CorpusEntry {
+    /// Render the entry as a single line JSON object.
+    pub fn to_json(&self) -> String {
+        let mut result = String::from("{\"collection\": ");
+        push_json_string(&mut result, &self.collection);
+        result.push_str(", \"version\": ");
+        match &self.version {
+            Some(version) => push_json_string(&mut result, version),
+            None => result.push_str("null"),
+        }
+        for (key, value) in [
+            ("plugin", &self.plugin),
+            ("plugin_type", &self.plugin_type),
+            ("file", &self.file),
+            ("field", &self.field),
+            ("text", &self.text),
+        ] {
+            result.push_str(", ");
+            push_json_string(&mut result, key);
+            result.push_str(": ");
+            push_json_string(&mut result, value);
+        }
+        result.push('}');
+        result
+    }
+}
+
+/// The documentation strings of installed collections.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Corpus {
+    /// The non-empty paragraphs of all markup fields, ordered by collection, plugin, and field.
+    pub entries: Vec<CorpusEntry>,
+
+    /// The collections and files whose documentation could not be read. Paths are relative to
+    /// the `ansible_collections` directory.
+    pub unreadable_files: Vec<FilesError>,
+}
+
+impl Corpus {
+    pub fn new() -> Corpus {
+        Corpus::default()
+    }
+
+    /// Add the documentation strings of a collection.
+    ///
+    /// Fails only if the collection's metadata cannot be loaded. Files whose documentation
+    /// cannot be extracted are added to [`Self::unreadable_files`] with `prefix` prepended to
+    /// their paths.
+    pub fn add_collection<F: CollectionFiles + ?Sized>(
+        &mut self,
+        files: &F,
+        prefix: &str,
+    ) -> Result<(), FilesError> {
+        let metadata = files.metadata()?;
+        let collection = format!("{}.{}", metadata.namespace, metadata.name);
+        for plugin in files.plugin_files() {
+            let blocks = match files.doc_blocks(&plugin) {
+                Ok(blocks) => blocks,
+                Err(error) => {
+                    self.unreadable_files.push(FilesError {
+                        path: format!("{}{}", prefix, error.path),
+                        message: error.message,
+                    });
+                    continue;
+                }
+            };
+            let mut fields = Vec::new();
+            for block in [&blocks.documentation, &blocks.return_values]
+                .into_iter()
+                .flatten()
+            {
+                collect_markup_fields(block, "", &mut fields);
+            }
+            for (field, _, text) in fields.iter().flatten() {
+                let text = text.replace("\r\n", "\n");
+                let text = text.trim();
+                if text.is_empty() {
+                    continue;
+                }
+                self.entries.push(CorpusEntry {
+                    collection: collection.clone(),
+                    version: metadata.version.clone(),
+                    plugin: format!("{}.{}", collection, plugin.name),
+                    plugin_type: plugin.plugin_type.clone(),
+                    file: plugin.path.clone(),
+                    field: field.clone(),
+                    text: text.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Render the corpus as JSON Lines, one [`CorpusEntry`] per line.
+    pub fn to_jsonl(&self) -> String {
+        let mut result = String::new();
+        for entry in &self.entries {
+            result.push_str(&entry.to_json());
+            result.push('\n');
+        }
+        result
+    }
+}
+
+fn sorted_directories(path: &Path) -> Vec<(String, PathBuf)> {
+    let mut result: Vec<_> = fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .map(|entry| {
+            (
+                entry.file_name().to_string_lossy().into_owned(),
+                entry.path(),
+            )
+        })
+        .collect();
+    result.sort();
+    result
+}
+
+/// Collect the documentation strings of all collections installed in an `ansible_collections`
+/// directory, which contains the collections as `namespace/name/`.
+///
+/// If `root` itself contains an `ansible_collections` directory, that one is used. Collections
+/// whose metadata cannot be loaded are listed in [`Corpus::unreadable_files`].
+pub fn harvest_corpus(root: &Path) -> Corpus {
+    let nested = root.join("ansible_collections");
+    let root = if nested.is_dir() { &nested } else { root };
+    let mut corpus = Corpus::new();
+    for (namespace, namespace_path) in sorted_directories(root) {
+        for (name, path) in sorted_directories(&namespace_path) {
+            let prefix = format!("{}/{}/", namespace, name);
+            if let Err(error) = corpus.add_collection(&CollectionDirectory::new(&path), &prefix) {
+                corpus.unreadable_files.push(FilesError {
+                    path: format!("{}{}", prefix, error.path),
+                    message: error.message,
+                });
+            }
+        }
+    }
+    corpus
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_harvest_corpus() {
+        let root = std::env::temp_dir().join(format!("antsibull-corpus-{}", std::process::id()));
+        let collection = root.join("ansible_collections/foo/bar");
+        fs::create_dir_all(collection.join("plugins/modules")).unwrap();
+        fs::create_dir_all(root.join("ansible_collections/foo/broken")).unwrap();
+        fs::write(
+            collection.join("galaxy.yml"),
+            "namespace: foo\nname: bar\nversion: 1.2.0\n",
+        )
+        .unwrap();
+        fs::write(
+            collection.join("plugins/modules/baz.py"),
+            concat!(
+                "DOCUMENTATION = r'''\n",
+                "short_description: Do \"things\"\n",
+                "description:\n",
+                "  - First.\r\n",
+                "  - ' '\n",
+                "'''\n",
+            ),
+        )
+        .unwrap();
+        let corpus = harvest_corpus(&root);
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            corpus.to_jsonl(),
+            concat!(
+                "{\"collection\": \"foo.bar\", \"version\": \"1.2.0\", \"plugin\": \"foo.bar.baz\", ",
+                "\"plugin_type\": \"module\", \"file\": \"plugins/modules/baz.py\", ",
+                "\"field\": \"short_description\", \"text\": \"Do \\\"things\\\"\"}\n",
+                "{\"collection\": \"foo.bar\", \"version\": \"1.2.0\", \"plugin\": \"foo.bar.baz\", ",
+                "\"plugin_type\": \"module\", \"file\": \"plugins/modules/baz.py\", ",
+                "\"field\": \"description[0]\", \"text\": \"First.\"}\n",
+            )
+        );
+        assert_eq!(
+            corpus
+                .unreadable_files
+                .iter()
+                .map(|error| error.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["foo/broken/."]
+        );
+    }
+}
@@ -0,0 +1,322 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::collection::metadata::{load_galaxy_yml, load_manifest_json, CollectionMetadata};
+use crate::collection::runtime::{load_runtime_yml, RuntimeMetadata};
+use crate::plugin_docs::{
+    extract_python_doc_blocks, extract_role_doc_blocks, extract_yaml_doc_blocks, DocBlocks,
+};
+use crate::util::verify::{verify_sha256, ChecksumManifest, VerifyError};
+use crate::util::yaml;
+use saphyr::Yaml;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// An error while reading a file of a collection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilesError {
+    /// The path of the file, relative to the collection's root.
+    pub path: String,
+
+    /// The error message.
+    pub message: String,
+}
+
+impl fmt::Display for FilesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for FilesError {}
+
+fn error(path: &str, message: impl ToString) -> FilesError {
+    FilesError {
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+}
+
+/// The plugin types that are stored in `plugins/<type>/`, besides `modules`.
+const PLUGIN_TYPES: &[&str] = &[
+    "become",
+    "cache",
+    "callback",
+    "cliconf",
+    "connection",
+    "filter",
+    "httpapi",
+    "inventory",
+    "lookup",
+    "netconf",
+    "shell",
+    "strategy",
+    "test",
+    "vars",
+];
+
+/// A file containing the documentation of a plugin, module, or role.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PluginFile {
+    /// The plugin type, like `module`, `lookup`, or `role`.
+    pub plugin_type: String,
+
+    /// The plugin's name inside the collection, like `foo` or `subdir.bar`.
+    pub name: String,
+
+    /// The path of the file, relative to the collection's root.
+    pub path: String,
+}
+
+impl PluginFile {
+    /// Determine the plugin a file documents, if any.
+    fn from_path(path: &str) -> Option<PluginFile> {
+        let parts: Vec<&str> = path.split('/').collect();
+        if let ["roles", role, "meta", "argument_specs.yml" | "argument_specs.yaml"] = parts[..] {
+            return Some(PluginFile {
+                plugin_type: "role".to_string(),
+                name: role.to_string(),
+                path: path.to_string(),
+            });
+        }
+        let ["plugins", directory, ref rest @ ..] = parts[..] else {
+            return None;
+        };
+        let plugin_type = match directory {
+            "modules" => "module",
+            directory if PLUGIN_TYPES.contains(&directory) => directory,
+            _ => return None,
+        };
+        let (file_name, subdirs) = rest.split_last()?;
+        // Filter and test plugins are documented in sidecar files, since one Python file can
+        // contain many of them.
+        let stem = match plugin_type {
+            "filter" | "test" => file_name
+                .strip_suffix(".yml")
+                .or_else(|| file_name.strip_suffix(".yaml"))?,
+            _ => file_name.strip_suffix(".py")?,
+        };
+        if stem.starts_with("__") {
+            return None;
+        }
+        let mut name: Vec<&str> = subdirs.to_vec();
+        name.push(stem);
+        Some(PluginFile {
+            plugin_type: plugin_type.to_string(),
+            name: name.join("."),
+            path: path.to_string(),
+        })
+    }
+}
+
+/// Read access to the files of a collection, no matter where they are stored.
+pub trait CollectionFiles {
+    /// The paths of all files, relative to the collection's root and separated by `/`.
+    fn file_paths(&self) -> Vec<String>;
+
+    /// Read a file. Returns `None` if it does not exist.
+    fn read_file(&self, path: &str) -> Result<Option<Vec<u8>>, FilesError>;
+
+    /// Read a file as UTF-8. Returns `None` if it does not exist.
+    fn read_text_file(&self, path: &str) -> Result<Option<String>, FilesError> {
+        self.read_file(path)?
+            .map(|content| String::from_utf8(content).map_err(|e| error(path, e)))
+            .transpose()
+    }
+
+    /// Load the collection's metadata. `MANIFEST.json` is preferred over `galaxy.yml`.
+    fn metadata(&self) -> Result<CollectionMetadata, FilesError> {
+        if let Some(content) = self.read_text_file("MANIFEST.json")? {
+            return load_manifest_json(&content).map_err(|e| error("MANIFEST.json", e));
+        }
+        if let Some(content) = self.read_text_file("galaxy.yml")? {
+            return load_galaxy_yml(&content).map_err(|e| error("galaxy.yml", e));
+        }
+        Err(error(".", "Found neither MANIFEST.json nor galaxy.yml"))
+    }
+
+    /// Load `meta/runtime.yml`, if present.
+    fn runtime(&self) -> Result<Option<RuntimeMetadata>, FilesError> {
+        self.read_text_file("meta/runtime.yml")?
+            .map(|content| load_runtime_yml(&content).map_err(|e| error("meta/runtime.yml", e)))
+            .transpose()
+    }
+
+    /// All files that document plugins, modules, and roles, sorted by type and name.
+    fn plugin_files(&self) -> Vec<PluginFile> {
+        let mut result: Vec<PluginFile> = self
+            .file_paths()
+            .iter()
+            .filter_map(|path| PluginFile::from_path(path))
+            .collect();
+        result.sort();
+        result
+    }
+
+    /// Extract the documentation blocks of a plugin or module.
+    ///
+    /// For roles, the argument spec is returned as `DOCUMENTATION`.
+    fn doc_blocks(&self, plugin: &PluginFile) -> Result<DocBlocks, FilesError> {
+        let content = self
+            .read_text_file(&plugin.path)?
+            .ok_or_else(|| error(&plugin.path, "file does not exist"))?;
+        let result = if plugin.path.ends_with(".py") {
+            extract_python_doc_blocks(&content)
+        } else if plugin.plugin_type == "role" {
+            extract_role_doc_blocks(&content)
+        } else {
+            extract_yaml_doc_blocks(&content)
+        };
+        result.map_err(|e| error(&plugin.path, e))
+    }
+
+    /// Verify the checksums of all files listed in `FILES.json` of a built collection.
+    ///
+    /// The checksum of `FILES.json` itself is taken from `MANIFEST.json`. An empty result means
+    /// that all files are fine.
+    fn verify_checksums(&self) -> Vec<VerifyError> {
+        let read = |path: &str| {
+            self.read_file(path).map_err(|e| VerifyError::Io {
+                path: e.path,
+                message: e.message,
+            })
+        };
+        let invalid = |message: String| vec![VerifyError::InvalidManifest { message }];
+        let manifest = match read("MANIFEST.json") {
+            Ok(Some(manifest)) => manifest,
+            Ok(None) => {
+                return vec![VerifyError::Missing {
+                    path: "MANIFEST.json".to_string(),
+                }]
+            }
+            Err(e) => return vec![e],
+        };
+        let manifest = match Yaml::load_from_str(&String::from_utf8_lossy(&manifest)) {
+            Ok(mut documents) if !documents.is_empty() => documents.swap_remove(0),
+            _ => return invalid("MANIFEST.json: cannot parse".to_string()),
+        };
+        let files_checksum = yaml::as_hash(&manifest, "MANIFEST.json")
+            .and_then(|hash| {
+                yaml::as_hash(
+                    yaml::get(hash, "file_manifest_file").unwrap_or(&Yaml::Null),
+                    "MANIFEST.json.file_manifest_file",
+                )
+            })
+            .and_then(|hash| {
+                yaml::get_string(hash, "chksum_sha256", "MANIFEST.json.file_manifest_file")
+            });
+        let files_checksum = match files_checksum {
+            Ok(Some(checksum)) => checksum,
+            Ok(None) => {
+                return invalid(
+                    "MANIFEST.json.file_manifest_file.chksum_sha256: missing".to_string(),
+                )
+            }
+            Err(message) => return invalid(message),
+        };
+        let files = match read("FILES.json") {
+            Ok(Some(files)) => files,
+            Ok(None) => {
+                return vec![VerifyError::Missing {
+                    path: "FILES.json".to_string(),
+                }]
+            }
+            Err(e) => return vec![e],
+        };
+        if let Err(e) = verify_sha256("FILES.json", &files, &files_checksum) {
+            return vec![e];
+        }
+        match ChecksumManifest::parse(&String::from_utf8_lossy(&files)) {
+            Ok(checksums) => checksums.verify(read),
+            Err(e) => vec![e],
+        }
+    }
+}
+
+/// The files of a collection checkout or installed collection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionDirectory {
+    root: PathBuf,
+}
+
+impl CollectionDirectory {
+    /// Access the collection in the directory `root`.
+    pub fn new(root: &Path) -> CollectionDirectory {
+        CollectionDirectory {
+            root: root.to_path_buf(),
+        }
+    }
+
+    fn collect(&self, directory: &Path, prefix: &str, result: &mut Vec<String>) {
+        let Ok(entries) = fs::read_dir(directory) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if prefix.is_empty() && matches!(name.as_str(), ".git" | "tests") {
+                continue;
+            }
+            let path = format!("{}{}", prefix, name);
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => {
+                    self.collect(&entry.path(), &format!("{}/", path), result)
+                }
+                Ok(file_type) if file_type.is_file() => result.push(path),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl CollectionFiles for CollectionDirectory {
+    /// The paths of all files, except those in `.git/` and `tests/`.
+    fn file_paths(&self) -> Vec<String> {
+        let mut result = Vec::new();
+        self.collect(&self.root, "", &mut result);
+        result.sort();
+        result
+    }
+
+    fn read_file(&self, path: &str) -> Result<Option<Vec<u8>>, FilesError> {
+        let full_path = self.root.join(path);
+        if !full_path.is_file() {
+            return Ok(None);
+        }
+        fs::read(full_path).map(Some).map_err(|e| error(path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_file() {
+        let plugin =
+            |path| PluginFile::from_path(path).map(|p| format!("{} {}", p.plugin_type, p.name));
+        assert_eq!(
+            plugin("plugins/modules/foo.py"),
+            Some("module foo".to_string())
+        );
+        assert_eq!(
+            plugin("plugins/lookup/sub/dir/bar.py"),
+            Some("lookup sub.dir.bar".to_string())
+        );
+        assert_eq!(
+            plugin("plugins/filter/baz.yml"),
+            Some("filter baz".to_string())
+        );
+        assert_eq!(
+            plugin("roles/r/meta/argument_specs.yml"),
+            Some("role r".to_string())
+        );
+        assert_eq!(plugin("plugins/filter/baz.py"), None);
+        assert_eq!(plugin("plugins/modules/__init__.py"), None);
+        assert_eq!(plugin("plugins/module_utils/foo.py"), None);
+        assert_eq!(plugin("plugins/modules/foo.yml"), None);
+    }
+}
@@ -0,0 +1,289 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::util::yaml;
+use saphyr::{Hash, Yaml};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// An error that occured while loading collection metadata.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataError {
+    /// Neither `MANIFEST.json` nor `galaxy.yml` exist.
+    NotFound { path: String },
+
+    /// The file could not be read.
+    Io { path: String, message: String },
+
+    /// The file is not valid YAML or JSON.
+    Syntax {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+
+    /// A required field is missing.
+    MissingField { field: String },
+
+    /// A field has the wrong type or an invalid value.
+    InvalidField { field: String, message: String },
+}
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MetadataError::NotFound { path } => {
+                write!(f, "Found neither MANIFEST.json nor galaxy.yml in {}", path)
+            }
+            MetadataError::Io { path, message } => {
+                write!(f, "Cannot read {}: {}", path, message)
+            }
+            MetadataError::Syntax {
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "Syntax error in line {}, column {}: {}",
+                line, column, message
+            ),
+            MetadataError::MissingField { field } => write!(f, "Missing field {}", field),
+            MetadataError::InvalidField { field, message } => {
+                write!(f, "Invalid field {}: {}", field, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MetadataError {}
+
+/// Metadata of a collection, from `galaxy.yml` or `MANIFEST.json`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionMetadata {
+    /// The collection's namespace.
+    pub namespace: String,
+
+    /// The collection's name.
+    pub name: String,
+
+    /// The collection's version. `galaxy.yml` of collections that are not released this way might
+    /// not contain a version.
+    pub version: Option<String>,
+
+    /// The dependencies with their version ranges, sorted by collection name.
+    pub dependencies: Vec<(String, String)>,
+
+    /// The URL of the source repository.
+    pub repository: Option<String>,
+
+    /// The URL of the documentation.
+    pub documentation: Option<String>,
+
+    /// The URL of the homepage.
+    pub homepage: Option<String>,
+
+    /// The URL of the issue tracker.
+    pub issues: Option<String>,
+}
+
+impl CollectionMetadata {
+    /// The collection's name including its namespace, like `community.general`.
+    pub fn fqcn(&self) -> String {
+        format!("{}.{}", self.namespace, self.name)
+    }
+}
+
+fn parse(content: &str) -> Result<Yaml, MetadataError> {
+    let mut documents = Yaml::load_from_str(content).map_err(|error| MetadataError::Syntax {
+        line: error.marker().line(),
+        column: error.marker().col() + 1,
+        message: error.info().to_string(),
+    })?;
+    if documents.is_empty() {
+        Ok(Yaml::Null)
+    } else {
+        Ok(documents.swap_remove(0))
+    }
+}
+
+fn as_hash<'y>(value: &'y Yaml, field: &str) -> Result<&'y Hash, MetadataError> {
+    match value {
+        Yaml::Hash(hash) => Ok(hash),
+        _ => Err(MetadataError::InvalidField {
+            field: field.to_string(),
+            message: "expected a mapping".to_string(),
+        }),
+    }
+}
+
+fn get_string(hash: &Hash, key: &str, prefix: &str) -> Result<Option<String>, MetadataError> {
+    match yaml::get(hash, key) {
+        None | Some(Yaml::Null) => Ok(None),
+        Some(value) => match yaml::scalar_to_string(value) {
+            Some(value) => Ok(Some(value)),
+            None => Err(MetadataError::InvalidField {
+                field: format!("{}{}", prefix, key),
+                message: "expected a string".to_string(),
+            }),
+        },
+    }
+}
+
+fn get_required_string(hash: &Hash, key: &str, prefix: &str) -> Result<String, MetadataError> {
+    match get_string(hash, key, prefix)? {
+        Some(value) if !value.is_empty() => Ok(value),
+        _ => Err(MetadataError::MissingField {
+            field: format!("{}{}", prefix, key),
+        }),
+    }
+}
+
+fn get_dependencies(hash: &Hash, prefix: &str) -> Result<Vec<(String, String)>, MetadataError> {
+    let field = format!("{}dependencies", prefix);
+    let dependencies = match yaml::get(hash, "dependencies") {
+        None | Some(Yaml::Null) => return Ok(Vec::new()),
+        Some(value) => as_hash(value, &field)?,
+    };
+    let mut result = dependencies
+        .iter()
+        .map(|(name, version)| {
+            match (
+                yaml::scalar_to_string(name),
+                yaml::scalar_to_string(version),
+            ) {
+                (Some(name), Some(version)) => Ok((name, version)),
+                _ => Err(MetadataError::InvalidField {
+                    field: field.clone(),
+                    message: "expected a mapping of strings to strings".to_string(),
+                }),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    result.sort();
+    Ok(result)
+}
+
+fn load_metadata(hash: &Hash, prefix: &str) -> Result<CollectionMetadata, MetadataError> {
+    Ok(CollectionMetadata {
+        namespace: get_required_string(hash, "namespace", prefix)?,
+        name: get_required_string(hash, "name", prefix)?,
+        version: get_string(hash, "version", prefix)?,
+        dependencies: get_dependencies(hash, prefix)?,
+        repository: get_string(hash, "repository", prefix)?,
+        documentation: get_string(hash, "documentation", prefix)?,
+        homepage: get_string(hash, "homepage", prefix)?,
+        issues: get_string(hash, "issues", prefix)?,
+    })
+}
+
+/// Load collection metadata from the contents of a `galaxy.yml` file.
+pub fn load_galaxy_yml(content: &str) -> Result<CollectionMetadata, MetadataError> {
+    let document = parse(content)?;
+    load_metadata(as_hash(&document, "")?, "")
+}
+
+/// Load collection metadata from the contents of a `MANIFEST.json` file.
+pub fn load_manifest_json(content: &str) -> Result<CollectionMetadata, MetadataError> {
+    let document = parse(content)?;
+    let hash = as_hash(&document, "")?;
+    match yaml::get(hash, "collection_info") {
+        None | Some(Yaml::Null) => Err(MetadataError::MissingField {
+            field: "collection_info".to_string(),
+        }),
+        Some(info) => load_metadata(as_hash(info, "collection_info")?, "collection_info."),
+    }
+}
+
+fn read(path: &Path) -> Result<String, MetadataError> {
+    fs::read_to_string(path).map_err(|error| MetadataError::Io {
+        path: path.display().to_string(),
+        message: error.to_string(),
+    })
+}
+
+/// Load the metadata of the collection in the directory `path`.
+///
+/// `MANIFEST.json` is preferred over `galaxy.yml`, since installed collections have the former,
+/// while the latter might be a template in some source checkouts.
+pub fn load_collection_metadata(path: &Path) -> Result<CollectionMetadata, MetadataError> {
+    let manifest = path.join("MANIFEST.json");
+    if manifest.is_file() {
+        return load_manifest_json(&read(&manifest)?);
+    }
+    let galaxy = path.join("galaxy.yml");
+    if galaxy.is_file() {
+        return load_galaxy_yml(&read(&galaxy)?);
+    }
+    Err(MetadataError::NotFound {
+        path: path.display().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_galaxy_yml() {
+        let metadata = load_galaxy_yml(
+            r#"
+namespace: community
+name: general
+version: 1.0.0
+dependencies:
+  foo.bar: '>=1.0.0'
+  ansible.netcommon: 2.0
+repository: https://github.com/ansible-collections/community.general
+"#,
+        )
+        .unwrap();
+        assert_eq!(metadata.fqcn(), "community.general");
+        assert_eq!(metadata.version, Some("1.0.0".to_string()));
+        assert_eq!(
+            metadata.dependencies,
+            vec![
+                ("ansible.netcommon".to_string(), "2.0".to_string()),
+                ("foo.bar".to_string(), ">=1.0.0".to_string()),
+            ]
+        );
+        assert_eq!(metadata.issues, None);
+
+        assert_eq!(
+            load_galaxy_yml("namespace: foo"),
+            Err(MetadataError::MissingField {
+                field: "name".to_string()
+            })
+        );
+        assert_eq!(
+            load_galaxy_yml("namespace: foo\nname: [bar]")
+                .unwrap_err()
+                .to_string(),
+            "Invalid field name: expected a string"
+        );
+        assert!(matches!(
+            load_galaxy_yml("namespace: foo\n  name: bar"),
+            Err(MetadataError::Syntax { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_load_manifest_json() {
+        let metadata = load_manifest_json(
+            r#"{"collection_info": {"namespace": "foo", "name": "bar", "version": "2.1.0", "dependencies": {}, "homepage": null}, "format": 1}"#,
+        )
+        .unwrap();
+        assert_eq!(metadata.fqcn(), "foo.bar");
+        assert_eq!(metadata.version, Some("2.1.0".to_string()));
+        assert_eq!(metadata.dependencies, vec![]);
+        assert_eq!(
+            load_manifest_json(r#"{"collection_info": {"namespace": "foo"}}"#)
+                .unwrap_err()
+                .to_string(),
+            "Missing field collection_info.name"
+        );
+    }
+}
@@ -0,0 +1,50 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Loading of Ansible collection metadata.
+
+mod artifact;
+mod compatibility;
+mod corpus;
+mod files;
+mod metadata;
+mod runtime;
+#[cfg(feature = "async")]
+mod snapshot;
+mod usage;
+mod validation;
+
+pub use artifact::CollectionArtifact;
+
+pub use compatibility::{
+    append_html_compatibility_note, append_md_compatibility_note, append_rst_compatibility_note,
+    describe_requires_ansible, minimum_ansible_core_version, supported_ansible_core_versions,
+};
+
+pub use corpus::{harvest_corpus, Corpus, CorpusEntry};
+
+pub use files::{CollectionDirectory, CollectionFiles, FilesError, PluginFile};
+
+pub use metadata::{
+    load_collection_metadata, load_galaxy_yml, load_manifest_json, CollectionMetadata,
+    MetadataError,
+};
+
+pub use runtime::{
+    check_fqcn, is_valid_collection_name, is_valid_fqcn, load_runtime_yml, PluginRouting,
+    RemovalInfo, RuntimeError, RuntimeMetadata,
+};
+
+#[cfg(feature = "async")]
+pub use snapshot::{read_collection_directories, CollectionSnapshot};
+
+pub use usage::{
+    collection_markup_usage, collection_markup_usage_with_progress, CollectionUsage, UsageReport,
+};
+
+pub use validation::{
+    validate_collection_markup, validate_collection_markup_with_progress, CollectionValidation,
+};
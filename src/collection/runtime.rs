@@ -0,0 +1,412 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::error::Error;
+use crate::util::version::SpecifierSet;
+use crate::util::yaml;
+use regex::Regex;
+use saphyr::{Hash, Yaml};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::LazyLock;
+
+pub use crate::util::names::is_valid_collection_name;
+
+/// An error in a `meta/runtime.yml` file, with the location of the problematic entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    /// The location of the entry, like `plugin_routing.modules.foo.redirect`.
+    pub path: String,
+
+    /// The error message.
+    pub message: String,
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+/// Information on the deprecation or removal of a plugin.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RemovalInfo {
+    /// The version in which the plugin will be or was removed.
+    pub removal_version: Option<String>,
+
+    /// The date (`YYYY-MM-DD`) on which the plugin will be or was removed.
+    pub removal_date: Option<String>,
+
+    /// Text shown to users.
+    pub warning_text: Option<String>,
+}
+
+/// The routing information for a plugin.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PluginRouting {
+    /// The FQCN of the plugin this plugin redirects to.
+    pub redirect: Option<String>,
+
+    /// Deprecation information, if the plugin is deprecated.
+    pub deprecation: Option<RemovalInfo>,
+
+    /// Removal information, if the plugin has been removed.
+    pub tombstone: Option<RemovalInfo>,
+}
+
+/// The contents of a collection's `meta/runtime.yml`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RuntimeMetadata {
+    /// The ansible-core versions supported by the collection, like `>=2.14.0`.
+    pub requires_ansible: Option<String>,
+
+    /// The routing information by plugin type (as used in `runtime.yml`, like `modules` or `lookup`)
+    /// and plugin name.
+    pub plugin_routing: BTreeMap<String, BTreeMap<String, PluginRouting>>,
+
+    /// Action groups, mapping group names to lists of actions.
+    pub action_groups: BTreeMap<String, Vec<String>>,
+}
+
+fn error(path: &str, message: &str) -> RuntimeError {
+    RuntimeError {
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+}
+
+fn as_hash<'y>(value: &'y Yaml, path: &str) -> Result<&'y Hash, RuntimeError> {
+    yaml::as_hash(value, path).map_err(|_| error(path, "expected a mapping"))
+}
+
+fn get_string(hash: &Hash, key: &str, path: &str) -> Result<Option<String>, RuntimeError> {
+    yaml::get_string(hash, key, path)
+        .map_err(|_| error(&format!("{}.{}", path, key), "expected a string"))
+}
+
+fn hash_entries<'y>(
+    hash: &'y Hash,
+    path: &str,
+) -> Result<Vec<(String, &'y Yaml, String)>, RuntimeError> {
+    hash.iter()
+        .map(|(key, value)| match yaml::scalar_to_string(key) {
+            Some(key) => {
+                let entry_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                Ok((key, value, entry_path))
+            }
+            None => Err(error(path, "keys must be strings")),
+        })
+        .collect()
+}
+
+fn load_removal_info(value: &Yaml, path: &str) -> Result<RemovalInfo, RuntimeError> {
+    let hash = as_hash(value, path)?;
+    Ok(RemovalInfo {
+        removal_version: get_string(hash, "removal_version", path)?,
+        removal_date: get_string(hash, "removal_date", path)?,
+        warning_text: get_string(hash, "warning_text", path)?,
+    })
+}
+
+fn load_optional_removal_info(
+    hash: &Hash,
+    key: &str,
+    path: &str,
+) -> Result<Option<RemovalInfo>, RuntimeError> {
+    match yaml::get(hash, key) {
+        None | Some(Yaml::Null) => Ok(None),
+        Some(value) => Ok(Some(load_removal_info(
+            value,
+            &format!("{}.{}", path, key),
+        )?)),
+    }
+}
+
+fn load_plugin_routing(value: &Yaml, path: &str) -> Result<PluginRouting, RuntimeError> {
+    let hash = as_hash(value, path)?;
+    Ok(PluginRouting {
+        redirect: get_string(hash, "redirect", path)?,
+        deprecation: load_optional_removal_info(hash, "deprecation", path)?,
+        tombstone: load_optional_removal_info(hash, "tombstone", path)?,
+    })
+}
+
+/// Load the contents of a `meta/runtime.yml` file.
+///
+/// Unknown top-level keys are ignored. Errors name the location of the invalid entry.
+pub fn load_runtime_yml(content: &str) -> Result<RuntimeMetadata, RuntimeError> {
+    let documents = Yaml::load_from_str(content).map_err(|e| error("", &e.to_string()))?;
+    let mut result = RuntimeMetadata::default();
+    let hash = match documents.first() {
+        None | Some(Yaml::Null) => return Ok(result),
+        Some(document) => as_hash(document, "")?,
+    };
+    result.requires_ansible = get_string(hash, "requires_ansible", "")
+        .map_err(|_| error("requires_ansible", "expected a string"))?;
+    if let Some(routing) = yaml::get(hash, "plugin_routing").filter(|v| !v.is_null()) {
+        for (plugin_type, plugins, type_path) in
+            hash_entries(as_hash(routing, "plugin_routing")?, "plugin_routing")?
+        {
+            let mut entries = BTreeMap::new();
+            if !plugins.is_null() {
+                for (name, value, plugin_path) in
+                    hash_entries(as_hash(plugins, &type_path)?, &type_path)?
+                {
+                    entries.insert(name, load_plugin_routing(value, &plugin_path)?);
+                }
+            }
+            result.plugin_routing.insert(plugin_type, entries);
+        }
+    }
+    if let Some(groups) = yaml::get(hash, "action_groups").filter(|v| !v.is_null()) {
+        for (name, actions, group_path) in
+            hash_entries(as_hash(groups, "action_groups")?, "action_groups")?
+        {
+            let actions = match actions {
+                Yaml::Array(actions) => actions
+                    .iter()
+                    .filter_map(|action| match action {
+                        Yaml::String(action) => Some(Ok(action.clone())),
+                        // Entries like `- metadata: {extend_group: [...]}` are not actions
+                        Yaml::Hash(_) => None,
+                        _ => Some(Err(error(&group_path, "expected a list of strings"))),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+                _ => return Err(error(&group_path, "expected a list")),
+            };
+            result.action_groups.insert(name, actions);
+        }
+    }
+    Ok(result)
+}
+
+static FQCN_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[a-z0-9][a-z0-9_]*\.[a-z0-9][a-z0-9_]*\.[A-Za-z0-9_]+(\.[A-Za-z0-9_]+)*$")
+        .unwrap()
+});
+
+/// Whether `name` is a valid FQCN, like `community.general.foo` or `ns.col.subdir.bar`.
+pub fn is_valid_fqcn(name: &str) -> bool {
+    FQCN_RE.is_match(name)
+}
+
+/// Check that `name` is a valid FQCN, like [`is_valid_fqcn`].
+pub fn check_fqcn(name: &str) -> Result<(), Error> {
+    if is_valid_fqcn(name) {
+        Ok(())
+    } else {
+        Err(Error::InvalidFqcn(name.to_string()))
+    }
+}
+
+impl RuntimeMetadata {
+    /// Parse `requires_ansible` as a specifier set, if present.
+    pub fn requires_ansible_specifiers(&self) -> Result<Option<SpecifierSet>, RuntimeError> {
+        self.requires_ansible
+            .as_deref()
+            .map(SpecifierSet::parse)
+            .transpose()
+            .map_err(|message| error("requires_ansible", &message))
+    }
+
+    /// Find cycles of redirects between plugins of the collection `collection`.
+    ///
+    /// Every cycle is returned once, as the list of FQCNs in redirect order, starting with the
+    /// smallest FQCN. Only redirects inside the same plugin type are followed.
+    pub fn find_redirect_cycles(&self, collection: &str) -> Vec<Vec<String>> {
+        let mut result = Vec::new();
+        let prefix = format!("{}.", collection);
+        for plugins in self.plugin_routing.values() {
+            let redirect_of = |fqcn: &str| -> Option<&String> {
+                fqcn.strip_prefix(&prefix)
+                    .and_then(|name| plugins.get(name))
+                    .and_then(|routing| routing.redirect.as_ref())
+            };
+            for name in plugins.keys() {
+                let start = format!("{}{}", prefix, name);
+                let mut chain = vec![start.clone()];
+                let mut current = start.clone();
+                while let Some(next) = redirect_of(&current) {
+                    if let Some(index) = chain.iter().position(|entry| entry == next) {
+                        // Only report the cycle from its smallest element to avoid duplicates
+                        let cycle = chain[index..].to_vec();
+                        if index == 0 && cycle.iter().all(|entry| *entry >= start) {
+                            result.push(cycle);
+                        }
+                        break;
+                    }
+                    chain.push(next.clone());
+                    current = next.clone();
+                }
+            }
+        }
+        result
+    }
+
+    /// Check the routing information for problems.
+    ///
+    /// This reports redirects to invalid FQCNs, redirect cycles, and deprecations and tombstones
+    /// without or with both removal version and removal date.
+    pub fn check(&self, collection: &str) -> Vec<RuntimeError> {
+        let mut result = Vec::new();
+        if let Err(error) = self.requires_ansible_specifiers() {
+            result.push(error);
+        }
+        for (plugin_type, plugins) in &self.plugin_routing {
+            for (name, routing) in plugins {
+                let path = format!("plugin_routing.{}.{}", plugin_type, name);
+                if let Some(redirect) = &routing.redirect {
+                    if let Err(message) = check_fqcn(redirect) {
+                        result.push(error(&format!("{}.redirect", path), &message.to_string()));
+                    }
+                }
+                for (key, info) in [
+                    ("deprecation", &routing.deprecation),
+                    ("tombstone", &routing.tombstone),
+                ] {
+                    if let Some(info) = info {
+                        let message = match (&info.removal_version, &info.removal_date) {
+                            (None, None) => {
+                                "either removal_version or removal_date must be provided"
+                            }
+                            (Some(_), Some(_)) => {
+                                "removal_version and removal_date must not both be provided"
+                            }
+                            _ => continue,
+                        };
+                        result.push(error(&format!("{}.{}", path, key), message));
+                    }
+                }
+            }
+        }
+        for cycle in self.find_redirect_cycles(collection) {
+            result.push(error(
+                "plugin_routing",
+                &format!("redirect cycle: {} -> {}", cycle.join(" -> "), cycle[0]),
+            ));
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUNTIME: &str = r#"
+requires_ansible: '>=2.14.0'
+plugin_routing:
+  modules:
+    old:
+      redirect: foo.bar.new
+      deprecation:
+        removal_version: 3.0.0
+        warning_text: Use foo.bar.new instead.
+    gone:
+      tombstone:
+        removal_date: '2023-01-01'
+    a:
+      redirect: foo.bar.b
+    b:
+      redirect: foo.bar.a
+    broken:
+      redirect: foo
+  lookup:
+    x:
+      deprecation:
+        warning_text: No removal info.
+action_groups:
+  group:
+    - metadata:
+        extend_group: [other.col.group]
+    - foo.bar.new
+"#;
+
+    #[test]
+    fn test_load_runtime_yml() {
+        let runtime = load_runtime_yml(RUNTIME).unwrap();
+        assert_eq!(runtime.requires_ansible, Some(">=2.14.0".to_string()));
+        let modules = &runtime.plugin_routing["modules"];
+        assert_eq!(modules["old"].redirect, Some("foo.bar.new".to_string()));
+        assert_eq!(
+            modules["old"].deprecation.as_ref().unwrap().removal_version,
+            Some("3.0.0".to_string())
+        );
+        assert_eq!(
+            modules["gone"].tombstone.as_ref().unwrap().removal_date,
+            Some("2023-01-01".to_string())
+        );
+        assert_eq!(runtime.action_groups["group"], vec!["foo.bar.new"]);
+        assert_eq!(load_runtime_yml("").unwrap(), RuntimeMetadata::default());
+        assert_eq!(
+            load_runtime_yml("plugin_routing:\n  modules:\n    foo:\n      redirect: [a]")
+                .unwrap_err()
+                .to_string(),
+            "plugin_routing.modules.foo.redirect: expected a string"
+        );
+    }
+
+    #[test]
+    fn test_check() {
+        assert!(is_valid_fqcn("foo.bar.baz"));
+        assert!(is_valid_fqcn("foo.bar.subdir.Baz"));
+        assert!(!is_valid_fqcn("foo.bar"));
+        assert!(!is_valid_fqcn("Foo.bar.baz"));
+        assert!(check_fqcn("foo.bar.baz").is_ok());
+        assert_eq!(
+            check_fqcn("foo.bar").unwrap_err().to_string(),
+            "\"foo.bar\" is not a valid FQCN"
+        );
+        assert!(is_valid_collection_name("community.general"));
+        assert!(!is_valid_collection_name("community.general.foo"));
+
+        let runtime = load_runtime_yml(RUNTIME).unwrap();
+        assert_eq!(
+            runtime.find_redirect_cycles("foo.bar"),
+            vec![vec!["foo.bar.a".to_string(), "foo.bar.b".to_string()]]
+        );
+        assert_eq!(
+            runtime
+                .check("foo.bar")
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "plugin_routing.lookup.x.deprecation: either removal_version or removal_date must be provided",
+                "plugin_routing.modules.broken.redirect: \"foo\" is not a valid FQCN",
+                "plugin_routing: redirect cycle: foo.bar.a -> foo.bar.b -> foo.bar.a",
+            ]
+        );
+
+        let runtime = load_runtime_yml("requires_ansible: '2.14'").unwrap();
+        assert_eq!(
+            runtime.check("foo.bar"),
+            vec![error(
+                "requires_ansible",
+                "Invalid version specifier \"2.14\""
+            )]
+        );
+        assert_eq!(
+            load_runtime_yml("requires_ansible: '>=2.14,<2.17'")
+                .unwrap()
+                .requires_ansible_specifiers()
+                .unwrap()
+                .unwrap()
+                .to_string(),
+            ">=2.14,<2.17"
+        );
+    }
+}
@@ -0,0 +1,134 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::collection::files::{CollectionFiles, FilesError};
+use crate::util::concurrency::map_concurrent;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The files of a collection directory, read into memory asynchronously.
+///
+/// Reading is I/O-bound and done concurrently; parsing the documentation afterwards with the
+/// [`CollectionFiles`] methods is synchronous. Requires the `async` feature.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionSnapshot {
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+fn io_error(path: &str, error: std::io::Error) -> FilesError {
+    FilesError {
+        path: path.to_string(),
+        message: error.to_string(),
+    }
+}
+
+impl CollectionSnapshot {
+    /// Read all files of the collection in `root`, reading at most `concurrency` files at the
+    /// same time.
+    ///
+    /// Like [`CollectionDirectory`](crate::collection::CollectionDirectory), files in `.git/`
+    /// and `tests/` are skipped.
+    pub async fn read_directory(
+        root: &Path,
+        concurrency: usize,
+    ) -> Result<CollectionSnapshot, FilesError> {
+        let mut paths = Vec::new();
+        let mut directories = vec![(root.to_path_buf(), String::new())];
+        while let Some((directory, prefix)) = directories.pop() {
+            let mut entries = tokio::fs::read_dir(&directory)
+                .await
+                .map_err(|e| io_error(&prefix, e))?;
+            while let Some(entry) = entries
+                .next_entry()
+                .await
+                .map_err(|e| io_error(&prefix, e))?
+            {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if prefix.is_empty() && matches!(name.as_str(), ".git" | "tests") {
+                    continue;
+                }
+                let path = format!("{}{}", prefix, name);
+                match entry.file_type().await {
+                    Ok(file_type) if file_type.is_dir() => {
+                        directories.push((entry.path(), format!("{}/", path)))
+                    }
+                    Ok(file_type) if file_type.is_file() => paths.push(path),
+                    _ => {}
+                }
+            }
+        }
+        let contents = map_concurrent(paths, concurrency, |path| {
+            let full_path = root.join(&path);
+            async move {
+                let content = tokio::fs::read(full_path).await;
+                (path, content)
+            }
+        })
+        .await;
+        let mut files = BTreeMap::new();
+        for (path, content) in contents {
+            files.insert(path.clone(), content.map_err(|e| io_error(&path, e))?);
+        }
+        Ok(CollectionSnapshot { files })
+    }
+}
+
+impl CollectionFiles for CollectionSnapshot {
+    fn file_paths(&self) -> Vec<String> {
+        self.files.keys().cloned().collect()
+    }
+
+    fn read_file(&self, path: &str) -> Result<Option<Vec<u8>>, FilesError> {
+        Ok(self.files.get(path).cloned())
+    }
+}
+
+/// Read several collection directories with [`CollectionSnapshot::read_directory`], at most
+/// `concurrency` of them at the same time. The results are in the order of `roots`.
+pub async fn read_collection_directories(
+    roots: Vec<PathBuf>,
+    concurrency: usize,
+) -> Vec<Result<CollectionSnapshot, FilesError>> {
+    map_concurrent(roots, concurrency, move |root| async move {
+        CollectionSnapshot::read_directory(&root, concurrency).await
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_read_collection_directories() {
+        let root = std::env::temp_dir().join(format!("antsibull-snapshot-{}", std::process::id()));
+        fs::create_dir_all(root.join("plugins/modules")).unwrap();
+        fs::create_dir_all(root.join("tests")).unwrap();
+        fs::write(
+            root.join("galaxy.yml"),
+            "namespace: foo\nname: bar\nversion: 1.0.0\n",
+        )
+        .unwrap();
+        fs::write(root.join("plugins/modules/baz.py"), "").unwrap();
+        fs::write(root.join("tests/x.py"), "").unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let results = runtime.block_on(read_collection_directories(
+            vec![root.clone(), root.join("missing")],
+            2,
+        ));
+        fs::remove_dir_all(&root).unwrap();
+        let snapshot = results[0].as_ref().unwrap();
+        assert_eq!(
+            snapshot.file_paths(),
+            vec!["galaxy.yml", "plugins/modules/baz.py"]
+        );
+        assert_eq!(snapshot.metadata().unwrap().name, "bar");
+        assert!(results[1].is_err());
+    }
+}
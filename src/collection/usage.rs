@@ -0,0 +1,342 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::collection::files::{CollectionFiles, FilesError};
+use crate::markup::{parse_paragraphs_without_sources, Context, MarkupUsage, ParseOptions};
+use crate::util::cancellation::{BatchStatus, CancellationToken};
+use crate::util::json::push_json_string;
+use crate::util::progress::{NoProgressReporter, ProgressReporter};
+use crate::util::yaml::collect_markup_fields;
+use std::collections::BTreeSet;
+
+/// The markup usage of one collection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionUsage {
+    /// The collection's name, like `community.general`.
+    pub name: String,
+
+    /// The statistics of all markup in the documentation of plugins, modules, and roles.
+    pub usage: MarkupUsage,
+
+    /// The files whose documentation could not be extracted.
+    pub unreadable_files: Vec<FilesError>,
+
+    /// Whether all plugin files were processed, or the operation was cancelled.
+    pub status: BatchStatus,
+}
+
+/// Collect the markup usage of a collection.
+///
+/// Fails only if the collection's metadata cannot be loaded. Files whose documentation cannot be
+/// extracted are listed in the result.
+pub fn collection_markup_usage<F: CollectionFiles + ?Sized>(
+    files: &F,
+) -> Result<CollectionUsage, FilesError> {
+    collection_markup_usage_with_progress(
+        files,
+        &NoProgressReporter::new(),
+        &CancellationToken::new(),
+    )
+}
+
+/// Collect the markup usage of a collection, like [`collection_markup_usage`], and report every
+/// plugin file to `progress`.
+///
+/// Stops before the next plugin file once `cancellation` is cancelled, and returns the usage so
+/// far with [`BatchStatus::Cancelled`].
+pub fn collection_markup_usage_with_progress<F: CollectionFiles + ?Sized>(
+    files: &F,
+    progress: &dyn ProgressReporter,
+    cancellation: &CancellationToken,
+) -> Result<CollectionUsage, FilesError> {
+    let metadata = files.metadata()?;
+    let name = format!("{}.{}", metadata.namespace, metadata.name);
+    let mut usage = MarkupUsage::new();
+    let mut unreadable_files = Vec::new();
+    let mut status = BatchStatus::Completed;
+    let opts = ParseOptions::default();
+    let plugins = files.plugin_files();
+    progress.start("Collecting markup usage", Some(plugins.len()));
+    for (done, plugin) in plugins.into_iter().enumerate() {
+        if cancellation.is_cancelled() {
+            status = BatchStatus::Cancelled;
+            break;
+        }
+        progress.progress(done, &plugin.path);
+        let blocks = match files.doc_blocks(&plugin) {
+            Ok(blocks) => blocks,
+            Err(error) => {
+                unreadable_files.push(error);
+                continue;
+            }
+        };
+        let context = Context::new()
+            .collection(&name)
+            .current_plugin(&plugin.name, &plugin.plugin_type);
+        let mut fields = Vec::new();
+        for block in [&blocks.documentation, &blocks.return_values]
+            .into_iter()
+            .flatten()
+        {
+            collect_markup_fields(block, "", &mut fields);
+        }
+        for field in &fields {
+            let paragraphs = parse_paragraphs_without_sources(
+                field.iter().map(|(_, _, text)| text.as_str()),
+                &context,
+                &opts,
+            );
+            for paragraph in &paragraphs {
+                usage.add_paragraph(paragraph);
+            }
+        }
+    }
+    progress.finish();
+    Ok(CollectionUsage {
+        name,
+        usage,
+        unreadable_files,
+        status,
+    })
+}
+
+/// The markup usage of several collections.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UsageReport {
+    pub collections: Vec<CollectionUsage>,
+}
+
+fn percentage(value: Option<f64>) -> String {
+    match value {
+        Some(value) => format!("{:.1}%", value * 100.0),
+        None => "-".to_string(),
+    }
+}
+
+fn push_table(result: &mut String, rows: &[Vec<String>]) {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    for row in rows {
+        let mut line = String::new();
+        for (column, cell) in row.iter().enumerate() {
+            if column == 0 {
+                line.push_str(&format!("{:<width$}", cell, width = widths[column]));
+            } else {
+                line.push_str(&format!("  {:>width$}", cell, width = widths[column]));
+            }
+        }
+        result.push_str(line.trim_end());
+        result.push('\n');
+    }
+}
+
+fn push_usage_json(result: &mut String, usage: &MarkupUsage) {
+    let ratio = |value: Option<f64>| match value {
+        Some(value) => format!("{}", value),
+        None => "null".to_string(),
+    };
+    result.push_str(&format!(
+        "\"paragraphs\": {}, \"paragraphs_with_errors\": {}, \"errors\": {}, \"classic\": {}, \"semantic\": {}, \"semantic_ratio\": {}, \"error_rate\": {}, \"commands\": {{",
+        usage.paragraphs,
+        usage.paragraphs_with_errors,
+        usage.errors,
+        usage.classic,
+        usage.semantic,
+        ratio(usage.semantic_ratio()),
+        ratio(usage.error_rate()),
+    ));
+    for (index, (command, count)) in usage.commands.iter().enumerate() {
+        if index > 0 {
+            result.push_str(", ");
+        }
+        push_json_string(result, command);
+        result.push_str(&format!(": {}", count));
+    }
+    result.push('}');
+}
+
+impl UsageReport {
+    pub fn new() -> UsageReport {
+        UsageReport::default()
+    }
+
+    /// The statistics of all collections together.
+    pub fn total(&self) -> MarkupUsage {
+        let mut total = MarkupUsage::new();
+        for collection in &self.collections {
+            total.merge(&collection.usage);
+        }
+        total
+    }
+
+    /// Render the report as a JSON object with the list of `collections` and the `total`.
+    pub fn to_json(&self) -> String {
+        let mut result = String::from("{\"collections\": [");
+        for (index, collection) in self.collections.iter().enumerate() {
+            if index > 0 {
+                result.push_str(", ");
+            }
+            result.push_str("{\"name\": ");
+            push_json_string(&mut result, &collection.name);
+            result.push_str(", ");
+            push_usage_json(&mut result, &collection.usage);
+            result.push_str(", \"unreadable_files\": [");
+            for (index, error) in collection.unreadable_files.iter().enumerate() {
+                if index > 0 {
+                    result.push_str(", ");
+                }
+                push_json_string(&mut result, &error.to_string());
+            }
+            result.push_str("]}");
+        }
+        result.push_str("], \"total\": {");
+        push_usage_json(&mut result, &self.total());
+        result.push_str("}}");
+        result
+    }
+
+    /// Render the report as two tables for humans: an overview per collection, and the number
+    /// of uses of every command per collection.
+    pub fn to_table(&self) -> String {
+        let total = self.total();
+        let mut rows = vec![vec![
+            "Collection".to_string(),
+            "Paragraphs".to_string(),
+            "Classic".to_string(),
+            "Semantic".to_string(),
+            "Semantic %".to_string(),
+            "Errors".to_string(),
+            "Error %".to_string(),
+        ]];
+        let usages = self
+            .collections
+            .iter()
+            .map(|collection| (collection.name.as_str(), &collection.usage))
+            .chain([("Total", &total)]);
+        for (name, usage) in usages.clone() {
+            rows.push(vec![
+                name.to_string(),
+                usage.paragraphs.to_string(),
+                usage.classic.to_string(),
+                usage.semantic.to_string(),
+                percentage(usage.semantic_ratio()),
+                usage.errors.to_string(),
+                percentage(usage.error_rate()),
+            ]);
+        }
+        let mut result = String::new();
+        push_table(&mut result, &rows);
+
+        let commands: BTreeSet<&str> = total.commands.keys().copied().collect();
+        if !commands.is_empty() {
+            let mut rows = vec![std::iter::once("Command")
+                .chain(usages.clone().map(|(name, _)| name))
+                .map(str::to_string)
+                .collect::<Vec<String>>()];
+            for command in commands {
+                rows.push(
+                    std::iter::once(command.to_string())
+                        .chain(usages.clone().map(|(_, usage)| {
+                            usage
+                                .commands
+                                .get(command)
+                                .copied()
+                                .unwrap_or(0)
+                                .to_string()
+                        }))
+                        .collect(),
+                );
+            }
+            result.push('\n');
+            push_table(&mut result, &rows);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    struct TestFiles {
+        files: BTreeMap<&'static str, &'static str>,
+    }
+
+    impl CollectionFiles for TestFiles {
+        fn file_paths(&self) -> Vec<String> {
+            self.files.keys().map(|path| path.to_string()).collect()
+        }
+
+        fn read_file(&self, path: &str) -> Result<Option<Vec<u8>>, FilesError> {
+            Ok(self
+                .files
+                .get(path)
+                .map(|content| content.as_bytes().to_vec()))
+        }
+    }
+
+    #[test]
+    fn test_collection_markup_usage() {
+        let files = TestFiles {
+            files: BTreeMap::from([
+                ("galaxy.yml", "namespace: foo\nname: bar\nversion: 1.0.0\n"),
+                (
+                    "plugins/modules/baz.py",
+                    concat!(
+                        "DOCUMENTATION = r'''\n",
+                        "short_description: Do I(things)\n",
+                        "description:\n  - Set O(a) to V(b).\n  - See M(foo.bar.bam\n",
+                        "options:\n  a:\n    description: Whether to do it.\n",
+                        "'''\n",
+                        "RETURN = r'''\n",
+                        "x:\n  description: Like O(baz#module:a).\n",
+                        "'''\n",
+                    ),
+                ),
+                ("plugins/filter/broken.yml", "DOCUMENTATION: ["),
+            ]),
+        };
+        let usage = collection_markup_usage(&files).unwrap();
+        assert_eq!(usage.name, "foo.bar");
+        assert_eq!(usage.usage.paragraphs, 5);
+        assert_eq!(usage.usage.errors, 1);
+        assert_eq!(
+            usage.usage.commands,
+            BTreeMap::from([("I", 1), ("O", 2), ("V", 1)])
+        );
+        assert_eq!(usage.unreadable_files.len(), 1);
+        assert_eq!(usage.unreadable_files[0].path, "plugins/filter/broken.yml");
+
+        let report = UsageReport {
+            collections: vec![usage],
+        };
+        assert_eq!(
+            report.to_table(),
+            concat!(
+                "Collection  Paragraphs  Classic  Semantic  Semantic %  Errors  Error %\n",
+                "foo.bar              5        1         3       75.0%       1    20.0%\n",
+                "Total                5        1         3       75.0%       1    20.0%\n",
+                "\n",
+                "Command  foo.bar  Total\n",
+                "I              1      1\n",
+                "O              2      2\n",
+                "V              1      1\n",
+            )
+        );
+        assert!(report.to_json().starts_with(
+            "{\"collections\": [{\"name\": \"foo.bar\", \"paragraphs\": 5, \"paragraphs_with_errors\": 1, \"errors\": 1, \"classic\": 1, \"semantic\": 3, \"semantic_ratio\": 0.75, \"error_rate\": 0.2, \"commands\": {\"I\": 1, \"O\": 2, \"V\": 1}, \"unreadable_files\": [\"plugins/filter/broken.yml: "
+        ));
+    }
+}
@@ -0,0 +1,169 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::collection::files::{CollectionFiles, FilesError};
+use crate::markup::{Context, LintReport, ParseOptions};
+use crate::plugin_docs::{validate_doc_blocks, FieldRules};
+use crate::util::cancellation::{BatchStatus, CancellationToken};
+use crate::util::progress::{NoProgressReporter, ProgressReporter};
+
+/// The result of checking the markup of a collection's documentation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionValidation {
+    /// The collection's name, like `community.general`.
+    pub name: String,
+
+    /// The problems found in the documentation of plugins, modules, and roles.
+    pub report: LintReport,
+
+    /// The files whose documentation could not be extracted.
+    pub unreadable_files: Vec<FilesError>,
+
+    /// Whether all plugin files were processed, or the operation was cancelled.
+    pub status: BatchStatus,
+}
+
+/// Parse and check the markup of all plugins, modules, and roles of a collection with `rules`.
+///
+/// Fails only if the collection's metadata cannot be loaded. Files whose documentation cannot be
+/// extracted are listed in the result.
+pub fn validate_collection_markup<F: CollectionFiles + ?Sized>(
+    files: &F,
+    rules: &FieldRules,
+) -> Result<CollectionValidation, FilesError> {
+    validate_collection_markup_with_progress(
+        files,
+        rules,
+        &NoProgressReporter::new(),
+        &CancellationToken::new(),
+    )
+}
+
+/// Check the markup of a collection like [`validate_collection_markup`], and report every plugin
+/// file to `progress`.
+///
+/// Stops before the next plugin file once `cancellation` is cancelled, and returns the findings
+/// so far with [`BatchStatus::Cancelled`].
+pub fn validate_collection_markup_with_progress<F: CollectionFiles + ?Sized>(
+    files: &F,
+    rules: &FieldRules,
+    progress: &dyn ProgressReporter,
+    cancellation: &CancellationToken,
+) -> Result<CollectionValidation, FilesError> {
+    let metadata = files.metadata()?;
+    let name = format!("{}.{}", metadata.namespace, metadata.name);
+    let mut report = LintReport::new();
+    let mut unreadable_files = Vec::new();
+    let mut status = BatchStatus::Completed;
+    let opts = ParseOptions::default();
+    let plugins = files.plugin_files();
+    progress.start("Validating markup", Some(plugins.len()));
+    for (done, plugin) in plugins.into_iter().enumerate() {
+        if cancellation.is_cancelled() {
+            status = BatchStatus::Cancelled;
+            break;
+        }
+        progress.progress(done, &plugin.path);
+        let blocks = match files.doc_blocks(&plugin) {
+            Ok(blocks) => blocks,
+            Err(error) => {
+                unreadable_files.push(error);
+                continue;
+            }
+        };
+        let context = Context::new()
+            .collection(&name)
+            .current_plugin(&plugin.name, &plugin.plugin_type);
+        validate_doc_blocks(&mut report, &plugin.path, &blocks, rules, &context, &opts);
+    }
+    progress.finish();
+    Ok(CollectionValidation {
+        name,
+        report,
+        unreadable_files,
+        status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    struct TestFiles {
+        files: BTreeMap<&'static str, &'static str>,
+    }
+
+    impl CollectionFiles for TestFiles {
+        fn file_paths(&self) -> Vec<String> {
+            self.files.keys().map(|path| path.to_string()).collect()
+        }
+
+        fn read_file(&self, path: &str) -> Result<Option<Vec<u8>>, FilesError> {
+            Ok(self
+                .files
+                .get(path)
+                .map(|content| content.as_bytes().to_vec()))
+        }
+    }
+
+    #[test]
+    fn test_validate_collection_markup() {
+        let files = TestFiles {
+            files: BTreeMap::from([
+                ("galaxy.yml", "namespace: foo\nname: bar\nversion: 1.0.0\n"),
+                (
+                    "plugins/modules/baz.py",
+                    concat!(
+                        "DOCUMENTATION = r'''\n",
+                        "short_description: Set O(a)\n",
+                        "author: U(https://example.com)\n",
+                        "'''\n",
+                        "RETURN = r'''\n",
+                        "x:\n  description: Like O(a\n",
+                        "'''\n",
+                    ),
+                ),
+                ("plugins/filter/broken.yml", "DOCUMENTATION: ["),
+            ]),
+        };
+        let validation = validate_collection_markup(&files, &FieldRules::new()).unwrap();
+        assert_eq!(validation.name, "foo.bar");
+        let entries: Vec<(&str, &str, &str)> = validation
+            .report
+            .entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.file.as_str(),
+                    entry.yaml_path.as_str(),
+                    entry.finding.code,
+                )
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (
+                    "plugins/modules/baz.py",
+                    "DOCUMENTATION.short_description",
+                    "semantic-markup-not-allowed"
+                ),
+                (
+                    "plugins/modules/baz.py",
+                    "DOCUMENTATION.author",
+                    "markup-not-allowed"
+                ),
+                (
+                    "plugins/modules/baz.py",
+                    "RETURN.x.description",
+                    "markup-error"
+                ),
+            ]
+        );
+        assert_eq!(validation.unreadable_files.len(), 1);
+    }
+}
@@ -0,0 +1,190 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::{
+    CompactString, DeterministicLinkProvider, LinkProvider, OptionLike, PluginIdentifier,
+};
+use crate::util::yaml;
+use saphyr::Yaml;
+use std::collections::BTreeMap;
+
+/// The URL template of the ansible-core configuration reference on docs.ansible.com.
+pub const ANSIBLE_CORE_CONFIG_URL: &str =
+    "https://docs.ansible.com/ansible/latest/reference_appendices/config.html#{setting}";
+
+/// Maps environment variables to the ansible-core configuration settings they set.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EnvVariableIndex {
+    settings: BTreeMap<String, String>,
+}
+
+impl EnvVariableIndex {
+    pub fn new() -> EnvVariableIndex {
+        EnvVariableIndex::default()
+    }
+
+    /// Record that the environment variable `name` sets the configuration setting `setting`.
+    ///
+    /// The first setting recorded for a variable wins.
+    pub fn add(&mut self, name: &str, setting: &str) {
+        self.settings
+            .entry(name.to_string())
+            .or_insert_with(|| setting.to_string());
+    }
+
+    /// Load the environment variables of ansible-core's configuration metadata, the content of
+    /// `lib/ansible/config/base.yml`.
+    pub fn load_core_config(value: &Yaml) -> Result<EnvVariableIndex, String> {
+        let mut index = EnvVariableIndex::new();
+        for (key, setting) in yaml::as_hash(value, "config")? {
+            let name = yaml::scalar_to_string(key)
+                .ok_or_else(|| "config: keys must be strings".to_string())?;
+            let path = format!("config.{}", name);
+            let Some(env) = yaml::get(yaml::as_hash(setting, &path)?, "env") else {
+                continue;
+            };
+            let path = format!("{}.env", path);
+            let Yaml::Array(entries) = env else {
+                return Err(format!("{}: expected a list", path));
+            };
+            for (i, entry) in entries.iter().enumerate() {
+                let entry_path = format!("{}[{}]", path, i + 1);
+                let entry = yaml::as_hash(entry, &entry_path)?;
+                if let Some(variable) = yaml::get_string(entry, "name", &entry_path)? {
+                    index.add(&variable, &name);
+                }
+            }
+        }
+        Ok(index)
+    }
+
+    /// The configuration setting set by the environment variable `name`.
+    pub fn setting(&self, name: &str) -> Option<&str> {
+        self.settings.get(name).map(|setting| setting.as_str())
+    }
+
+    /// The number of environment variables.
+    pub fn len(&self) -> usize {
+        self.settings.len()
+    }
+
+    /// Whether there are no environment variables.
+    pub fn is_empty(&self) -> bool {
+        self.settings.is_empty()
+    }
+}
+
+/// Links `E()` references of the environment variables in an [`EnvVariableIndex`] to the
+/// configuration reference, and asks another link provider for everything else.
+///
+/// In the URL template, `{setting}` is replaced by the anchor of the setting as created by
+/// Sphinx, like `default-remote-user`, and `{name}` by the name of the variable.
+pub struct EnvVariableLinkProvider<'l, L: ?Sized> {
+    link_provider: &'l L,
+    index: &'l EnvVariableIndex,
+    url_template: String,
+}
+
+impl<'l, L: ?Sized> EnvVariableLinkProvider<'l, L> {
+    /// Link to the configuration reference of ansible-core on docs.ansible.com.
+    pub fn new(
+        link_provider: &'l L,
+        index: &'l EnvVariableIndex,
+    ) -> EnvVariableLinkProvider<'l, L> {
+        EnvVariableLinkProvider {
+            link_provider,
+            index,
+            url_template: ANSIBLE_CORE_CONFIG_URL.to_string(),
+        }
+    }
+
+    /// Set the URL template.
+    pub fn url_template(mut self, url_template: &str) -> Self {
+        self.url_template = url_template.to_string();
+        self
+    }
+}
+
+impl<L: LinkProvider + ?Sized> LinkProvider for EnvVariableLinkProvider<'_, L> {
+    fn plugin_link(&self, plugin: &PluginIdentifier) -> Option<String> {
+        self.link_provider.plugin_link(plugin)
+    }
+
+    fn plugin_option_like_link(
+        &self,
+        plugin: &PluginIdentifier,
+        entrypoint: Option<&String>,
+        what: OptionLike,
+        name: &[CompactString],
+        current_plugin: bool,
+    ) -> Option<String> {
+        self.link_provider
+            .plugin_option_like_link(plugin, entrypoint, what, name, current_plugin)
+    }
+
+    fn env_variable_link(&self, name: &str) -> Option<String> {
+        let Some(setting) = self.index.setting(name) else {
+            return self.link_provider.env_variable_link(name);
+        };
+        let anchor = setting.to_lowercase().replace('_', "-");
+        Some(
+            self.url_template
+                .replace("{setting}", &anchor)
+                .replace("{name}", name),
+        )
+    }
+}
+
+impl<L: DeterministicLinkProvider + ?Sized> DeterministicLinkProvider
+    for EnvVariableLinkProvider<'_, L>
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::{parse_paragraphs, Context, NoLinkProvider, ParseOptions, Renderer};
+
+    #[test]
+    fn test_env_variable_link_provider() {
+        let index = EnvVariableIndex::load_core_config(
+            &Yaml::load_from_str(concat!(
+                "DEFAULT_REMOTE_USER:\n",
+                "  env: [{name: ANSIBLE_REMOTE_USER}]\n",
+                "  ini: [{key: remote_user, section: defaults}]\n",
+                "ANSIBLE_NOCOLOR:\n",
+                "  env: [{name: ANSIBLE_NOCOLOR}, {name: NO_COLOR}]\n",
+                "DEFAULT_MODULE_PATH: {}\n",
+            ))
+            .unwrap()[0],
+        )
+        .unwrap();
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.setting("NO_COLOR"), Some("ANSIBLE_NOCOLOR"));
+
+        let context = Context::new();
+        let paragraphs = parse_paragraphs(
+            ["E(ANSIBLE_REMOTE_USER) E(FOO)"].into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let no_links = NoLinkProvider::new();
+        let link_provider = EnvVariableLinkProvider::new(&no_links, &index);
+        assert_eq!(
+            Renderer::plain_html(&link_provider).render(&paragraphs),
+            concat!(
+                "<p><a href='https://docs.ansible.com/ansible/latest/reference_appendices/",
+                "config.html#default-remote-user'><code>ANSIBLE_REMOTE_USER</code></a> ",
+                "<code>FOO</code></p>",
+            )
+        );
+        let link_provider = link_provider.url_template("config.html#envvar-{name}");
+        assert_eq!(
+            Renderer::plain_rst(&link_provider).render(&paragraphs),
+            "\\ `ANSIBLE\\_REMOTE\\_USER <config.html#envvar-ANSIBLE_REMOTE_USER>`__\\  \\ :envvar:`FOO`\\ "
+        );
+    }
+}
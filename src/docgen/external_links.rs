@@ -0,0 +1,79 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::util::concurrency::map_concurrent;
+
+async fn check_external_link(client: reqwest::Client, url: &str) -> Result<(), String> {
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err("not an HTTP or HTTPS URL".to_string());
+    }
+    let mut response = client
+        .head(url)
+        .send()
+        .await
+        .map_err(|error| error.to_string())?;
+    if response.status() == reqwest::StatusCode::METHOD_NOT_ALLOWED {
+        response = client
+            .get(url)
+            .send()
+            .await
+            .map_err(|error| error.to_string())?;
+    }
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(format!("status {}", status.as_u16()))
+    }
+}
+
+/// Check that external links resolve, with at most `concurrency` requests at the same time.
+///
+/// Every URL is requested with `HEAD`, or with `GET` if the server does not allow `HEAD`.
+/// Redirects are followed as configured in `client`. Returns the URLs that failed, in the order
+/// of `urls`, with the reason. Links inside the site are checked with
+/// [`PageInventory`](crate::docgen::PageInventory) instead. Requires the `net` feature.
+pub async fn check_external_links(
+    client: &reqwest::Client,
+    urls: Vec<String>,
+    concurrency: usize,
+) -> Vec<(String, String)> {
+    map_concurrent(urls, concurrency, |url| {
+        let client = client.clone();
+        async move {
+            let result = check_external_link(client, &url).await;
+            (url, result)
+        }
+    })
+    .await
+    .into_iter()
+    .filter_map(|(url, result)| result.err().map(|message| (url, message)))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_external_links() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let failed = runtime.block_on(check_external_links(
+            &reqwest::Client::new(),
+            vec!["mailto:foo@example.com".to_string()],
+            4,
+        ));
+        assert_eq!(
+            failed,
+            vec![(
+                "mailto:foo@example.com".to_string(),
+                "not an HTTP or HTTPS URL".to_string()
+            )]
+        );
+    }
+}
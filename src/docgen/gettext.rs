@@ -0,0 +1,430 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::collection::{CollectionFiles, FilesError};
+use crate::markup::{parse_without_sources, Context, ParseOptions, Part};
+use crate::plugin_docs::DocBlocks;
+use crate::util::yaml::collect_markup_fields;
+use std::collections::BTreeMap;
+
+/// A translatable paragraph of a message catalog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageEntry {
+    /// The paragraph's markup.
+    pub msgid: String,
+
+    /// The files the paragraph appears in, written as `#:` references.
+    pub references: Vec<String>,
+
+    /// Where in the documentation the paragraph appears, like
+    /// `foo.bar.baz module: options.name.description[0]`, written as `#.` comments for
+    /// translators.
+    pub comments: Vec<String>,
+}
+
+/// The translatable paragraphs of documentation, in the order they were found.
+///
+/// Paragraphs that appear more than once are listed once, with all their references and
+/// comments.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MessageCatalog {
+    entries: Vec<MessageEntry>,
+    index: BTreeMap<String, usize>,
+}
+
+/// Whether a paragraph contains text that can be translated, and not only code, references,
+/// and values.
+fn is_translatable(paragraph: &str, context: &Context, opts: &ParseOptions) -> bool {
+    parse_without_sources(paragraph, context, opts)
+        .iter()
+        .any(|part| match part {
+            Part::Text { text }
+            | Part::Italic { text }
+            | Part::Bold { text }
+            | Part::Link { text, .. }
+            | Part::RSTRef { text, .. } => text.chars().any(char::is_alphabetic),
+            _ => false,
+        })
+}
+
+fn push_po_string(result: &mut String, keyword: &str, value: &str) {
+    let escape = |line: &str| {
+        line.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\t', "\\t")
+            .replace('\r', "\\r")
+            .replace('\n', "\\n")
+    };
+    if !value.contains('\n') {
+        result.push_str(&format!("{} \"{}\"\n", keyword, escape(value)));
+        return;
+    }
+    result.push_str(&format!("{} \"\"\n", keyword));
+    for line in value.split_inclusive('\n') {
+        result.push_str(&format!("\"{}\"\n", escape(line)));
+    }
+}
+
+impl MessageCatalog {
+    pub fn new() -> MessageCatalog {
+        MessageCatalog::default()
+    }
+
+    /// Add a paragraph, or add the reference and comment to the paragraph if it is already
+    /// present.
+    pub fn add(&mut self, msgid: &str, reference: &str, comment: &str) {
+        let index = *self.index.entry(msgid.to_string()).or_insert_with(|| {
+            self.entries.push(MessageEntry {
+                msgid: msgid.to_string(),
+                references: Vec::new(),
+                comments: Vec::new(),
+            });
+            self.entries.len() - 1
+        });
+        let entry = &mut self.entries[index];
+        if !entry.references.iter().any(|r| r == reference) {
+            entry.references.push(reference.to_string());
+        }
+        if !entry.comments.iter().any(|c| c == comment) {
+            entry.comments.push(comment.to_string());
+        }
+    }
+
+    /// Add the translatable paragraphs of the markup fields of a plugin's documentation.
+    ///
+    /// `path` is the file the documentation comes from, and `fqcn` and `plugin_type` identify
+    /// the plugin in the comments for translators. Paragraphs without text, for example
+    /// consisting only of a value or an option name, are skipped.
+    pub fn add_doc_blocks(
+        &mut self,
+        path: &str,
+        fqcn: &str,
+        plugin_type: &str,
+        blocks: &DocBlocks,
+    ) {
+        let context = Context::new().current_plugin(fqcn, plugin_type);
+        let opts = ParseOptions::default();
+        let mut fields = Vec::new();
+        for block in [&blocks.documentation, &blocks.return_values]
+            .into_iter()
+            .flatten()
+        {
+            collect_markup_fields(block, "", &mut fields);
+        }
+        for (field_path, _, text) in fields.iter().flatten() {
+            if is_translatable(text, &context, &opts) {
+                let comment = format!("{} {}: {}", fqcn, plugin_type, field_path);
+                self.add(text, path, &comment);
+            }
+        }
+    }
+
+    /// Collect the translatable paragraphs of all plugins, modules, and roles of a collection.
+    ///
+    /// Fails if the collection's metadata cannot be loaded, or if the documentation of a file
+    /// cannot be extracted.
+    pub fn from_collection<F: CollectionFiles + ?Sized>(
+        files: &F,
+    ) -> Result<MessageCatalog, FilesError> {
+        let metadata = files.metadata()?;
+        let mut catalog = MessageCatalog::new();
+        for plugin in files.plugin_files() {
+            let blocks = files.doc_blocks(&plugin)?;
+            let fqcn = format!("{}.{}.{}", metadata.namespace, metadata.name, plugin.name);
+            catalog.add_doc_blocks(&plugin.path, &fqcn, &plugin.plugin_type, &blocks);
+        }
+        Ok(catalog)
+    }
+
+    pub fn entries(&self) -> &[MessageEntry] {
+        &self.entries
+    }
+
+    /// The number of paragraphs.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no paragraphs.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Render the catalog as a gettext template (POT file).
+    pub fn to_pot(&self) -> String {
+        let mut result = String::new();
+        push_po_string(&mut result, "msgid", "");
+        push_po_string(
+            &mut result,
+            "msgstr",
+            "Content-Type: text/plain; charset=UTF-8\n",
+        );
+        for entry in &self.entries {
+            result.push('\n');
+            for comment in &entry.comments {
+                result.push_str(&format!("#. {}\n", comment));
+            }
+            for reference in &entry.references {
+                result.push_str(&format!("#: {}\n", reference));
+            }
+            push_po_string(&mut result, "msgid", &entry.msgid);
+            push_po_string(&mut result, "msgstr", "");
+        }
+        result
+    }
+}
+
+/// Translated paragraphs, loaded from a PO file.
+///
+/// Substitute the paragraphs of markup fields with [`Self::translate`] before parsing them, so
+/// that the translated markup is rendered.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Translations {
+    messages: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum PoField {
+    None,
+    Context,
+    Id,
+    Str,
+}
+
+fn parse_po_string(value: &str, line: usize) -> Result<String, String> {
+    let value = value.trim();
+    let Some(inner) = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .filter(|_| value.len() >= 2)
+    else {
+        return Err(format!("line {}: expected a quoted string", line));
+    };
+    let mut result = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some(c @ ('"' | '\\')) => result.push(c),
+            _ => return Err(format!("line {}: invalid escape sequence", line)),
+        }
+    }
+    Ok(result)
+}
+
+impl Translations {
+    pub fn new() -> Translations {
+        Translations::default()
+    }
+
+    /// Add the translation of a paragraph.
+    pub fn add(&mut self, msgid: &str, msgstr: &str) {
+        self.messages.insert(msgid.to_string(), msgstr.to_string());
+    }
+
+    /// Load the translations of a PO file.
+    ///
+    /// Entries marked as fuzzy, entries with an empty translation, and obsolete entries are
+    /// skipped. Plural forms are not supported.
+    pub fn parse_po(content: &str) -> Result<Translations, String> {
+        let mut translations = Translations::new();
+        let mut msgid = String::new();
+        let mut msgstr = String::new();
+        let mut fuzzy = false;
+        let mut field = PoField::None;
+        let mut flush = |msgid: &mut String, msgstr: &mut String, fuzzy: &mut bool| {
+            if !msgid.is_empty() && !msgstr.is_empty() && !*fuzzy {
+                translations.add(msgid, msgstr);
+            }
+            msgid.clear();
+            msgstr.clear();
+            *fuzzy = false;
+        };
+        for (index, line) in content.lines().enumerate() {
+            let number = index + 1;
+            let line = line.trim();
+            if line.is_empty() {
+                if field != PoField::None {
+                    flush(&mut msgid, &mut msgstr, &mut fuzzy);
+                    field = PoField::None;
+                }
+                continue;
+            }
+            if let Some(comment) = line.strip_prefix('#') {
+                if field == PoField::Str {
+                    flush(&mut msgid, &mut msgstr, &mut fuzzy);
+                    field = PoField::None;
+                }
+                if let Some(flags) = comment.strip_prefix(',') {
+                    fuzzy |= flags.split(',').any(|flag| flag.trim() == "fuzzy");
+                }
+                continue;
+            }
+            let (keyword, value) = match line.split_once(' ') {
+                Some((keyword, value)) if !keyword.starts_with('"') => (keyword, value),
+                _ => ("", line),
+            };
+            match keyword {
+                "" => {
+                    let value = parse_po_string(value, number)?;
+                    match field {
+                        PoField::None => {
+                            return Err(format!("line {}: string without keyword", number));
+                        }
+                        PoField::Context => {}
+                        PoField::Id => msgid.push_str(&value),
+                        PoField::Str => msgstr.push_str(&value),
+                    }
+                }
+                "msgctxt" => {
+                    if field == PoField::Str {
+                        flush(&mut msgid, &mut msgstr, &mut fuzzy);
+                    }
+                    parse_po_string(value, number)?;
+                    field = PoField::Context;
+                }
+                "msgid" => {
+                    if field == PoField::Str {
+                        flush(&mut msgid, &mut msgstr, &mut fuzzy);
+                    }
+                    msgid = parse_po_string(value, number)?;
+                    field = PoField::Id;
+                }
+                "msgstr" => {
+                    if field != PoField::Id {
+                        return Err(format!("line {}: msgstr without msgid", number));
+                    }
+                    msgstr = parse_po_string(value, number)?;
+                    field = PoField::Str;
+                }
+                keyword
+                    if keyword.starts_with("msgid_plural") || keyword.starts_with("msgstr[") =>
+                {
+                    return Err(format!("line {}: plural forms are not supported", number));
+                }
+                keyword => return Err(format!("line {}: unknown keyword {:?}", number, keyword)),
+            }
+        }
+        flush(&mut msgid, &mut msgstr, &mut fuzzy);
+        Ok(translations)
+    }
+
+    /// The translation of a paragraph, or the paragraph itself if it has not been translated.
+    pub fn translate<'a>(&'a self, paragraph: &'a str) -> &'a str {
+        self.messages
+            .get(paragraph)
+            .map(|translation| translation.as_str())
+            .unwrap_or(paragraph)
+    }
+
+    /// The number of translated paragraphs.
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Whether no paragraphs have been translated.
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::{parse_paragraphs, NoLinkProvider, Renderer};
+    use saphyr::Yaml;
+
+    #[test]
+    fn test_gettext() {
+        let blocks = DocBlocks {
+            documentation: Some(
+                Yaml::load_from_str(concat!(
+                    "short_description: Do \"things\"\n",
+                    "description:\n",
+                    "  - First line.\n",
+                    "  - V(foo)\n",
+                    "options:\n",
+                    "  name:\n",
+                    "    description: First line.\n",
+                ))
+                .unwrap()
+                .remove(0),
+            ),
+            examples: None,
+            return_values: None,
+        };
+        let mut catalog = MessageCatalog::new();
+        catalog.add_doc_blocks("plugins/modules/baz.py", "foo.bar.baz", "module", &blocks);
+        assert_eq!(catalog.len(), 2);
+        let pot = catalog.to_pot();
+        assert_eq!(
+            pot,
+            concat!(
+                "msgid \"\"\n",
+                "msgstr \"\"\n",
+                "\"Content-Type: text/plain; charset=UTF-8\\n\"\n",
+                "\n",
+                "#. foo.bar.baz module: short_description\n",
+                "#: plugins/modules/baz.py\n",
+                "msgid \"Do \\\"things\\\"\"\n",
+                "msgstr \"\"\n",
+                "\n",
+                "#. foo.bar.baz module: description[0]\n",
+                "#. foo.bar.baz module: options.name.description\n",
+                "#: plugins/modules/baz.py\n",
+                "msgid \"First line.\"\n",
+                "msgstr \"\"\n",
+            )
+        );
+
+        let translations = Translations::parse_po(concat!(
+            "msgid \"\"\n",
+            "msgstr \"Content-Type: text/plain; charset=UTF-8\\n\"\n",
+            "\n",
+            "#, fuzzy\n",
+            "msgid \"Do \\\"things\\\"\"\n",
+            "msgstr \"Tu \\\"Dinge\\\"\"\n",
+            "\n",
+            "#. foo.bar.baz module: description[0]\n",
+            "msgid \"First line.\"\n",
+            "msgstr \"Erste \"\n",
+            "\"I(Zeile).\"\n",
+            "\n",
+            "msgid \"Untranslated\"\n",
+            "msgstr \"\"\n",
+            "\n",
+            "#~ msgid \"Old\"\n",
+            "#~ msgstr \"Alt\"\n",
+        ))
+        .unwrap();
+        assert_eq!(translations.len(), 1);
+        assert_eq!(translations.translate("Do \"things\""), "Do \"things\"");
+        assert_eq!(translations.translate("First line."), "Erste I(Zeile).");
+
+        let context = Context::new();
+        let paragraphs = parse_paragraphs(
+            ["First line.", "V(foo)"]
+                .into_iter()
+                .map(|paragraph| translations.translate(paragraph)),
+            &context,
+            &ParseOptions::default(),
+        );
+        assert_eq!(
+            Renderer::plain_html(&NoLinkProvider::new()).render(&paragraphs),
+            "<p>Erste <em>Zeile</em>.</p><p><code>foo</code></p>"
+        );
+        assert_eq!(
+            Translations::parse_po("msgid \"a\"\nmsgstr[0] \"b\"\n"),
+            Err("line 2: plural forms are not supported".to_string())
+        );
+    }
+}
@@ -0,0 +1,362 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::{
+    CompactString, DeterministicLinkProvider, LinkProvider, OptionLike, PluginIdentifier,
+};
+use crate::util::cancellation::{BatchStatus, CancellationToken};
+use crate::util::progress::ProgressReporter;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// The pages of a documentation site and the anchors on them.
+///
+/// URLs are compared literally, so they must be written the same way the link provider
+/// returns them.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PageInventory {
+    pages: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl PageInventory {
+    pub fn new() -> PageInventory {
+        PageInventory::default()
+    }
+
+    /// Add a page with its anchors.
+    pub fn page<I, S>(mut self, url: &str, anchors: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.add_page(url, anchors);
+        self
+    }
+
+    /// Add a page with its anchors. Adding the same page again adds more anchors.
+    pub fn add_page<I, S>(&mut self, url: &str, anchors: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.pages
+            .entry(url.to_string())
+            .or_default()
+            .extend(anchors.into_iter().map(Into::into));
+    }
+
+    /// Whether the page `url` exists.
+    pub fn has_page(&self, url: &str) -> bool {
+        self.pages.contains_key(url)
+    }
+
+    /// Check a URL that may contain an anchor, like `page.html#anchor`.
+    ///
+    /// URLs with a scheme point outside of the site, and are not checked.
+    pub fn check(&self, url: &str) -> Result<(), MissingTarget> {
+        if url.contains("://") {
+            return Ok(());
+        }
+        let (page, anchor) = match url.split_once('#') {
+            Some((page, anchor)) => (page, Some(anchor)),
+            None => (url, None),
+        };
+        let Some(anchors) = self.pages.get(page) else {
+            return Err(MissingTarget::Page);
+        };
+        match anchor {
+            Some(anchor) if !anchors.contains(anchor) => Err(MissingTarget::Anchor),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check many URLs with [`Self::check`], reporting every URL to `progress`.
+    ///
+    /// Returns the URLs that do not resolve, in the given order, with what is missing. Stops
+    /// before the next URL once `cancellation` is cancelled.
+    pub fn check_urls<S: AsRef<str>>(
+        &self,
+        urls: &[S],
+        progress: &dyn ProgressReporter,
+        cancellation: &CancellationToken,
+    ) -> (Vec<(String, MissingTarget)>, BatchStatus) {
+        progress.start("Checking links", Some(urls.len()));
+        let mut result = Vec::new();
+        let mut status = BatchStatus::Completed;
+        for (done, url) in urls.iter().enumerate() {
+            if cancellation.is_cancelled() {
+                status = BatchStatus::Cancelled;
+                break;
+            }
+            let url = url.as_ref();
+            progress.progress(done, url);
+            if let Err(missing) = self.check(url) {
+                result.push((url.to_string(), missing));
+            }
+        }
+        progress.finish();
+        (result, status)
+    }
+}
+
+/// What is missing for a link to resolve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingTarget {
+    /// The page does not exist.
+    Page,
+
+    /// The page exists, but does not have the anchor.
+    Anchor,
+}
+
+/// A link computed during rendering that does not resolve in a [`PageInventory`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnresolvedLink {
+    /// The plugin that is referenced.
+    pub plugin: PluginIdentifier,
+
+    /// The referenced option or return value, like `option foo.bar`, or `None` for a link to the
+    /// plugin itself.
+    pub target: Option<String>,
+
+    /// The URL returned by the link provider.
+    pub url: String,
+
+    /// What is missing.
+    pub missing: MissingTarget,
+}
+
+impl fmt::Display for UnresolvedLink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.plugin.r#type, self.plugin.fqcn)?;
+        if let Some(target) = &self.target {
+            write!(f, ", {}", target)?;
+        }
+        match self.missing {
+            MissingTarget::Page => write!(f, ": page of {:?} does not exist", self.url),
+            MissingTarget::Anchor => write!(f, ": anchor of {:?} does not exist", self.url),
+        }
+    }
+}
+
+/// Checks the URLs of another link provider against a [`PageInventory`], and records the links
+/// that would dangle.
+///
+/// Dangling links are not returned by default, so the references are rendered without link.
+/// Since [`Renderer`](crate::markup::Renderer) memoizes links, every dangling link is usually
+/// recorded once per renderer.
+pub struct InventoryLinkProvider<'l, L: ?Sized> {
+    link_provider: &'l L,
+    inventory: &'l PageInventory,
+    keep_dangling: bool,
+    unresolved: RefCell<Vec<UnresolvedLink>>,
+}
+
+impl<'l, L: ?Sized> InventoryLinkProvider<'l, L> {
+    pub fn new(link_provider: &'l L, inventory: &'l PageInventory) -> InventoryLinkProvider<'l, L> {
+        InventoryLinkProvider {
+            link_provider,
+            inventory,
+            keep_dangling: false,
+            unresolved: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Set whether to return dangling links anyway, and only record them.
+    pub fn keep_dangling(mut self, keep_dangling: bool) -> Self {
+        self.keep_dangling = keep_dangling;
+        self
+    }
+
+    /// The links that did not resolve so far, in the order they were first seen.
+    pub fn unresolved(&self) -> Vec<UnresolvedLink> {
+        self.unresolved.borrow().clone()
+    }
+
+    /// Remove and return the links that did not resolve so far.
+    pub fn take_unresolved(&self) -> Vec<UnresolvedLink> {
+        self.unresolved.take()
+    }
+
+    /// A report of the links that did not resolve so far, one per line.
+    pub fn report(&self) -> String {
+        self.unresolved
+            .borrow()
+            .iter()
+            .map(|link| format!("{}\n", link))
+            .collect()
+    }
+
+    fn check(
+        &self,
+        plugin: &PluginIdentifier,
+        target: Option<String>,
+        url: Option<String>,
+    ) -> Option<String> {
+        let url = url?;
+        let Err(missing) = self.inventory.check(&url) else {
+            return Some(url);
+        };
+        let link = UnresolvedLink {
+            plugin: plugin.clone(),
+            target,
+            url,
+            missing,
+        };
+        let mut unresolved = self.unresolved.borrow_mut();
+        if !unresolved.contains(&link) {
+            unresolved.push(link.clone());
+        }
+        if self.keep_dangling {
+            Some(link.url)
+        } else {
+            None
+        }
+    }
+}
+
+impl<L: LinkProvider + ?Sized> LinkProvider for InventoryLinkProvider<'_, L> {
+    fn plugin_link(&self, plugin: &PluginIdentifier) -> Option<String> {
+        self.check(plugin, None, self.link_provider.plugin_link(plugin))
+    }
+
+    fn plugin_option_like_link(
+        &self,
+        plugin: &PluginIdentifier,
+        entrypoint: Option<&String>,
+        what: OptionLike,
+        name: &[CompactString],
+        current_plugin: bool,
+    ) -> Option<String> {
+        let target = format!(
+            "{} {}{}",
+            match what {
+                OptionLike::Option => "option",
+                OptionLike::RetVal => "return value",
+            },
+            entrypoint.map(|ep| format!("{}:", ep)).unwrap_or_default(),
+            name.join("."),
+        );
+        let url = self.link_provider.plugin_option_like_link(
+            plugin,
+            entrypoint,
+            what,
+            name,
+            current_plugin,
+        );
+        self.check(plugin, Some(target), url)
+    }
+
+    fn env_variable_link(&self, name: &str) -> Option<String> {
+        self.link_provider.env_variable_link(name)
+    }
+}
+
+impl<L: DeterministicLinkProvider + ?Sized> DeterministicLinkProvider
+    for InventoryLinkProvider<'_, L>
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::{parse_paragraphs, Context, ParseOptions, Renderer};
+    use crate::util::progress::tests::RecordingProgressReporter;
+
+    struct TestLinkProvider {}
+
+    impl LinkProvider for TestLinkProvider {
+        fn plugin_link(&self, plugin: &PluginIdentifier) -> Option<String> {
+            Some(format!("{}_{}.html", plugin.fqcn, plugin.r#type))
+        }
+
+        fn plugin_option_like_link(
+            &self,
+            plugin: &PluginIdentifier,
+            _entrypoint: Option<&String>,
+            _what: OptionLike,
+            name: &[CompactString],
+            _current_plugin: bool,
+        ) -> Option<String> {
+            Some(format!(
+                "{}_{}.html#parameter-{}",
+                plugin.fqcn,
+                plugin.r#type,
+                name.join("/")
+            ))
+        }
+    }
+
+    #[test]
+    fn test_inventory_link_provider() {
+        let inventory = PageInventory::new()
+            .page("foo.bar.baz_module.html", ["parameter-a"])
+            .page("foo.bar.bam_module.html", Vec::<String>::new());
+        assert!(inventory.has_page("foo.bar.bam_module.html"));
+        assert_eq!(inventory.check("https://example.com/x.html"), Ok(()));
+        let progress = RecordingProgressReporter::default();
+        assert_eq!(
+            inventory.check_urls(
+                &[
+                    "foo.bar.baz_module.html#parameter-a",
+                    "foo.bar.nope_module.html"
+                ],
+                &progress,
+                &CancellationToken::new(),
+            ),
+            (
+                vec![("foo.bar.nope_module.html".to_string(), MissingTarget::Page)],
+                BatchStatus::Completed
+            )
+        );
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        assert_eq!(
+            inventory.check_urls(&["x.html"], &progress, &cancellation),
+            (Vec::new(), BatchStatus::Cancelled)
+        );
+        assert_eq!(progress.calls.borrow().len(), 6);
+
+        let context = Context::new();
+        let paragraphs = parse_paragraphs(
+            [
+                "M(foo.bar.baz) M(foo.bar.nope) O(foo.bar.baz#module:a) O(foo.bar.bam#module:b)",
+                "M(foo.bar.nope)",
+            ]
+            .into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let link_provider = InventoryLinkProvider::new(&TestLinkProvider {}, &inventory);
+        assert_eq!(
+            Renderer::md(&link_provider).render(&paragraphs),
+            concat!(
+                "[foo\\.bar\\.baz](foo\\.bar\\.baz\\_module\\.html) foo\\.bar\\.nope ",
+                "<code><strong><a href=\"foo.bar.baz_module.html#parameter-a\">a</a></strong></code> ",
+                "<code><strong>b</strong></code>",
+                "\n\nfoo\\.bar\\.nope",
+            )
+        );
+        assert_eq!(
+            link_provider.report(),
+            concat!(
+                "module foo.bar.nope: page of \"foo.bar.nope_module.html\" does not exist\n",
+                "module foo.bar.bam, option b: ",
+                "anchor of \"foo.bar.bam_module.html#parameter-b\" does not exist\n",
+            )
+        );
+        assert_eq!(link_provider.take_unresolved().len(), 2);
+        assert!(link_provider.unresolved().is_empty());
+
+        let link_provider =
+            InventoryLinkProvider::new(&TestLinkProvider {}, &inventory).keep_dangling(true);
+        assert!(Renderer::md(&link_provider)
+            .render(&paragraphs)
+            .contains("[foo\\.bar\\.nope](foo\\.bar\\.nope\\_module\\.html)"));
+        assert_eq!(link_provider.unresolved()[0].missing, MissingTarget::Page);
+    }
+}
@@ -0,0 +1,280 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::document::{Block, DefinitionListItem};
+use crate::markup::{parse_without_sources, Context, ParseOptions, Part};
+use crate::util::yaml;
+use saphyr::Yaml;
+
+/// The playbook objects that have keywords, in the order of the reference page.
+pub const KEYWORD_OBJECTS: [&str; 4] = ["Play", "Role", "Block", "Task"];
+
+/// A playbook keyword of ansible-core.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaybookKeyword {
+    /// The name of the keyword, like `become_user`.
+    pub name: String,
+
+    /// The description, in Ansible markup.
+    pub description: String,
+
+    /// The playbook objects the keyword can be used on, in the order of [`KEYWORD_OBJECTS`].
+    pub applies_to: Vec<String>,
+}
+
+fn parse_document(source: &str, name: &str) -> Result<Yaml, String> {
+    let mut documents = Yaml::load_from_str(source).map_err(|e| format!("{}: {}", name, e))?;
+    Ok(if documents.is_empty() {
+        Yaml::Null
+    } else {
+        documents.swap_remove(0)
+    })
+}
+
+/// Load the playbook keywords from ansible-core's keyword metadata.
+///
+/// `descriptions` is the content of `keyword_desc.yml`, a mapping of keywords to their
+/// descriptions. `objects` maps the names from [`KEYWORD_OBJECTS`] to the lists of keywords
+/// their classes accept, like `{Play: [hosts, name], Task: [name]}`; other objects are ignored.
+/// Keywords are sorted by name, and keywords without description are left out.
+pub fn load_playbook_keywords(
+    descriptions: &str,
+    objects: &str,
+) -> Result<Vec<PlaybookKeyword>, String> {
+    let descriptions = parse_document(descriptions, "keyword_desc")?;
+    let objects = parse_document(objects, "objects")?;
+    let objects = yaml::as_hash(&objects, "objects")?;
+    let mut keywords = yaml::as_hash(&descriptions, "keyword_desc")?
+        .iter()
+        .map(|(key, description)| {
+            let name = yaml::scalar_to_string(key)
+                .ok_or_else(|| "keyword_desc: keys must be strings".to_string())?;
+            let description = yaml::scalar_to_string(description)
+                .ok_or_else(|| format!("keyword_desc.{}: expected a string", name))?;
+            let mut applies_to = Vec::new();
+            for object in KEYWORD_OBJECTS {
+                if yaml::get_string_list(objects, object, "objects")?.contains(&name) {
+                    applies_to.push(object.to_string());
+                }
+            }
+            Ok(PlaybookKeyword {
+                name,
+                description,
+                applies_to,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    keywords.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(keywords)
+}
+
+/// Create the reference page of the playbook keywords.
+///
+/// Every object of [`KEYWORD_OBJECTS`] gets a section with a definition list of its keywords.
+/// Descriptions are parsed as Ansible markup with `context` and `opts`.
+pub fn playbook_keywords_page<'a>(
+    keywords: &'a [PlaybookKeyword],
+    context: &'a Context,
+    opts: &ParseOptions,
+) -> Vec<Block<'a>> {
+    let mut blocks = vec![
+        Block::Heading {
+            level: 1,
+            title: "Playbook Keywords".to_string(),
+        },
+        Block::Paragraph {
+            parts: vec![Part::Text {
+                text: concat!(
+                    "These are the keywords available on common playbook objects. ",
+                    "Keywords are one of several sources for configuring Ansible behavior.",
+                ),
+            }],
+        },
+    ];
+    for object in KEYWORD_OBJECTS {
+        let items: Vec<DefinitionListItem> = keywords
+            .iter()
+            .filter(|keyword| keyword.applies_to.iter().any(|o| o == object))
+            .map(|keyword| DefinitionListItem {
+                term: vec![Part::Text {
+                    text: &keyword.name,
+                }],
+                definition: vec![parse_without_sources(&keyword.description, context, opts)],
+            })
+            .collect();
+        if items.is_empty() {
+            continue;
+        }
+        blocks.push(Block::Heading {
+            level: 2,
+            title: object.to_string(),
+        });
+        blocks.push(Block::DefinitionList { items });
+    }
+    blocks
+}
+
+/// The definition of an attribute that modules and plugins can declare in `attributes`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeDefinition {
+    /// The name of the attribute, like `check_mode`.
+    pub name: String,
+
+    /// The description paragraphs, in Ansible markup.
+    pub description: Vec<String>,
+
+    /// Further details paragraphs, in Ansible markup.
+    pub details: Vec<String>,
+
+    /// The version of ansible-core that added the attribute.
+    pub version_added: Option<String>,
+}
+
+/// Load attribute definitions from a mapping of attribute names to their documentation, like the
+/// `attributes` of ansible-core's `action_common_attributes` documentation fragment.
+///
+/// The attributes are sorted by name.
+pub fn load_attribute_definitions(value: &Yaml) -> Result<Vec<AttributeDefinition>, String> {
+    let mut attributes = yaml::as_hash(value, "attributes")?
+        .iter()
+        .map(|(key, value)| {
+            let name = yaml::scalar_to_string(key)
+                .ok_or_else(|| "attributes: keys must be strings".to_string())?;
+            let path = format!("attributes.{}", name);
+            let hash = yaml::as_hash(value, &path)?;
+            Ok(AttributeDefinition {
+                description: yaml::get_string_list(hash, "description", &path)?,
+                details: yaml::get_string_list(hash, "details", &path)?,
+                version_added: yaml::get_string(hash, "version_added", &path)?,
+                name,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    attributes.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(attributes)
+}
+
+/// Create the reference page of the attributes of modules and plugins.
+///
+/// Every attribute gets a section with its description and details. Descriptions are parsed as
+/// Ansible markup with `context` and `opts`.
+pub fn attributes_page<'a>(
+    attributes: &'a [AttributeDefinition],
+    context: &'a Context,
+    opts: &ParseOptions,
+) -> Vec<Block<'a>> {
+    let mut blocks = vec![
+        Block::Heading {
+            level: 1,
+            title: "Module and Plugin Attributes".to_string(),
+        },
+        Block::Paragraph {
+            parts: vec![Part::Text {
+                text: concat!(
+                    "Attributes describe how modules and plugins behave, ",
+                    "and how much they support features like check mode.",
+                ),
+            }],
+        },
+    ];
+    for attribute in attributes {
+        blocks.push(Block::Heading {
+            level: 2,
+            title: attribute.name.clone(),
+        });
+        for paragraph in attribute.description.iter().chain(&attribute.details) {
+            blocks.push(Block::Paragraph {
+                parts: parse_without_sources(paragraph, context, opts),
+            });
+        }
+        if let Some(version_added) = &attribute.version_added {
+            blocks.push(Block::Paragraph {
+                parts: vec![
+                    Part::Text {
+                        text: "Added in ansible-core ",
+                    },
+                    Part::Text {
+                        text: version_added,
+                    },
+                    Part::Text { text: "." },
+                ],
+            });
+        }
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::append_md_blocks;
+    use crate::markup::NoLinkProvider;
+    use crate::util::{CollectorAppender, IntoString};
+
+    #[test]
+    fn test_playbook_keywords_page() {
+        let keywords = load_playbook_keywords(
+            concat!(
+                "name: Identifier. Can be used for documentation, or in tasks/handlers.\n",
+                "hosts: A list of groups, hosts or host pattern.\n",
+                "become: Boolean that controls if privilege escalation is used or not on I(Task) execution.\n",
+            ),
+            "Play: [hosts, name, become]\nTask: [name, become]\nBlock: [become]\n",
+        )
+        .unwrap();
+        assert_eq!(keywords[0].name, "become");
+        assert_eq!(keywords[0].applies_to, vec!["Play", "Block", "Task"]);
+        let context = Context::new();
+        let blocks = playbook_keywords_page(&keywords, &context, &ParseOptions::default());
+        let mut appender = CollectorAppender::new();
+        append_md_blocks(&mut appender, blocks.iter(), &NoLinkProvider::new(), &None);
+        let page = appender.into_string();
+        assert!(page.starts_with("# Playbook Keywords\n\n"));
+        assert!(page.contains(concat!(
+            "## Block\n\n",
+            "* <b>become</b>\n\n",
+            "  Boolean that controls if privilege escalation is used or not on <em>Task</em> execution\\.\n\n",
+            "## Task\n\n",
+        )));
+        assert!(!page.contains("## Role"));
+        assert_eq!(
+            load_playbook_keywords("name: [a]", "{}").unwrap_err(),
+            "keyword_desc.name: expected a string"
+        );
+
+        let attributes = load_attribute_definitions(
+            &Yaml::load_from_str(concat!(
+                "diff_mode:\n",
+                "  description: Will return details on what has changed.\n",
+                "check_mode:\n",
+                "  description: Can run in C(check_mode).\n",
+                "  details: Returns a predicted result.\n",
+                "  version_added: '2.14'\n",
+            ))
+            .unwrap()[0],
+        )
+        .unwrap();
+        let blocks = attributes_page(&attributes, &context, &ParseOptions::default());
+        let mut appender = CollectorAppender::new();
+        append_md_blocks(
+            &mut appender,
+            blocks[2..].iter(),
+            &NoLinkProvider::new(),
+            &None,
+        );
+        assert_eq!(
+            appender.into_string(),
+            concat!(
+                "## check\\_mode\n\n",
+                "Can run in <code>check\\_mode</code>\\.\n\n",
+                "Returns a predicted result\\.\n\n",
+                "Added in ansible\\-core 2\\.14\\.\n\n",
+                "## diff\\_mode\n\n",
+                "Will return details on what has changed\\.",
+            )
+        );
+    }
+}
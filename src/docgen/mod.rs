@@ -0,0 +1,53 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Helpers for generating documentation sites.
+
+mod env_variables;
+#[cfg(feature = "net")]
+mod external_links;
+mod gettext;
+mod inventory;
+mod keywords;
+mod navigation;
+mod ordering;
+mod output;
+mod redirects;
+mod sitemap;
+mod versions;
+
+pub use env_variables::{EnvVariableIndex, EnvVariableLinkProvider, ANSIBLE_CORE_CONFIG_URL};
+
+#[cfg(feature = "net")]
+pub use external_links::check_external_links;
+
+pub use gettext::{MessageCatalog, MessageEntry, Translations};
+
+pub use inventory::{InventoryLinkProvider, MissingTarget, PageInventory, UnresolvedLink};
+
+pub use keywords::{
+    attributes_page, load_attribute_definitions, load_playbook_keywords, playbook_keywords_page,
+    AttributeDefinition, PlaybookKeyword, KEYWORD_OBJECTS,
+};
+
+pub use navigation::{
+    append_html_navigation_footer, append_html_navigation_header, append_md_navigation_footer,
+    append_md_navigation_header, plugin_navigation, plugin_type_title, NavigationLinkProvider,
+    PluginNavigation,
+};
+
+pub use ordering::{
+    collection_of, compare_fqcns, compare_names, compare_plugin_types, compare_plugins,
+    plugin_type_rank, sort_plugins,
+};
+
+pub use output::{FileSystemSink, MemorySink, OutputSink, WriteOutcome};
+
+pub use redirects::{redirect_page, PluginRedirect, RedirectMap};
+
+pub use sitemap::{Sitemap, SitemapEntry};
+
+pub use versions::{DocsiteVersion, MultiVersionDocsite, VersionLink, VersionedLinkProvider};
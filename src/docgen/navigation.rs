@@ -0,0 +1,333 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::docgen::ordering::{collection_of, sort_plugins};
+use crate::markup::{HTMLEscaper, LinkProvider, MDEscaper, PluginIdentifier, URLEscaper};
+use crate::util::stringbuilder::Appender;
+use std::rc::Rc;
+
+/// Provides URLs for the pages plugin pages link to besides other plugins.
+pub trait NavigationLinkProvider {
+    /// The URL of a collection's index page.
+    fn collection_link(&self, collection: &str) -> Option<String>;
+
+    /// The URL of the list of plugins of a type in a collection.
+    fn plugin_type_link(&self, _collection: &str, _plugin_type: &str) -> Option<String> {
+        None
+    }
+}
+
+/// Navigation metadata of a plugin page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginNavigation {
+    /// The plugin the page belongs to.
+    pub plugin: Rc<PluginIdentifier>,
+
+    /// The collection the plugin belongs to, like `community.general`.
+    pub collection: String,
+
+    /// The section of the collection's index the plugin is listed in, like `Modules`.
+    pub section: String,
+
+    /// The previous plugin of the collection, if there is one.
+    pub previous: Option<Rc<PluginIdentifier>>,
+
+    /// The next plugin of the collection, if there is one.
+    pub next: Option<Rc<PluginIdentifier>>,
+}
+
+/// The title of the section listing plugins of a type, like `Modules` or `Lookup Plugins`.
+pub fn plugin_type_title(plugin_type: &str) -> String {
+    match plugin_type {
+        "module" => "Modules".to_string(),
+        "role" => "Roles".to_string(),
+        "cliconf" => "Cliconf Plugins".to_string(),
+        "httpapi" => "Httpapi Plugins".to_string(),
+        "netconf" => "Netconf Plugins".to_string(),
+        plugin_type => {
+            let mut chars = plugin_type.chars();
+            match chars.next() {
+                Some(first) => format!("{}{} Plugins", first.to_uppercase(), chars.as_str()),
+                None => "Plugins".to_string(),
+            }
+        }
+    }
+}
+
+/// Compute the navigation metadata for all given plugins.
+///
+/// Plugins are ordered with `sort_plugins()`. The previous and next plugins never cross collection
+/// boundaries. The result is in that order.
+pub fn plugin_navigation(plugins: &[Rc<PluginIdentifier>]) -> Vec<PluginNavigation> {
+    let mut plugins = plugins.to_vec();
+    sort_plugins(&mut plugins);
+    let same_collection = |a: &PluginIdentifier, b: &PluginIdentifier| {
+        collection_of(&a.fqcn) == collection_of(&b.fqcn)
+    };
+    plugins
+        .iter()
+        .enumerate()
+        .map(|(index, plugin)| PluginNavigation {
+            plugin: plugin.clone(),
+            collection: collection_of(&plugin.fqcn).to_string(),
+            section: plugin_type_title(&plugin.r#type),
+            previous: index
+                .checked_sub(1)
+                .map(|previous| &plugins[previous])
+                .filter(|previous| same_collection(previous, plugin))
+                .cloned(),
+            next: plugins
+                .get(index + 1)
+                .filter(|next| same_collection(next, plugin))
+                .cloned(),
+        })
+        .collect()
+}
+
+fn append_html_link(
+    appender: &mut dyn Appender<'_>,
+    text: &str,
+    url: Option<String>,
+    class: &'static str,
+) {
+    let escaper = HTMLEscaper::new();
+    match url {
+        Some(url) => {
+            appender.push_str("<a class=\"");
+            appender.push_str(class);
+            appender.push_str("\" href=\"");
+            appender
+                .push_owned_string(URLEscaper::new().escape_with_html_escape(&url).into_owned());
+            appender.push_str("\">");
+            appender.push_owned_string(escaper.escape(text).into_owned());
+            appender.push_str("</a>");
+        }
+        None => {
+            appender.push_str("<span class=\"");
+            appender.push_str(class);
+            appender.push_str("\">");
+            appender.push_owned_string(escaper.escape(text).into_owned());
+            appender.push_str("</span>");
+        }
+    }
+}
+
+fn append_md_link(appender: &mut dyn Appender<'_>, text: &str, url: Option<String>) {
+    let escaper = MDEscaper::new().unwrap();
+    match url {
+        Some(url) => {
+            appender.push_str("[");
+            appender.push_owned_string(escaper.escape(text).into_owned());
+            appender.push_str("](");
+            appender
+                .push_owned_string(escaper.escape(&URLEscaper::new().escape(&url)).into_owned());
+            appender.push_str(")");
+        }
+        None => appender.push_owned_string(escaper.escape(text).into_owned()),
+    }
+}
+
+/// Render breadcrumbs (collection, section, plugin) as HTML.
+pub fn append_html_navigation_header(
+    appender: &mut dyn Appender<'_>,
+    navigation: &PluginNavigation,
+    navigation_link_provider: &dyn NavigationLinkProvider,
+) {
+    appender.push_str("<nav class=\"ansible-breadcrumbs\">");
+    append_html_link(
+        appender,
+        &navigation.collection,
+        navigation_link_provider.collection_link(&navigation.collection),
+        "ansible-breadcrumb",
+    );
+    appender.push_str(" » ");
+    append_html_link(
+        appender,
+        &navigation.section,
+        navigation_link_provider
+            .plugin_type_link(&navigation.collection, &navigation.plugin.r#type),
+        "ansible-breadcrumb",
+    );
+    appender.push_str(" » ");
+    append_html_link(
+        appender,
+        &navigation.plugin.fqcn,
+        None,
+        "ansible-breadcrumb-current",
+    );
+    appender.push_str("</nav>");
+}
+
+/// Render links to the previous and next plugins as HTML.
+///
+/// `link_provider` is used to compute the URLs of the plugins.
+pub fn append_html_navigation_footer(
+    appender: &mut dyn Appender<'_>,
+    navigation: &PluginNavigation,
+    link_provider: &dyn LinkProvider,
+) {
+    appender.push_str("<nav class=\"ansible-prev-next\">");
+    if let Some(previous) = &navigation.previous {
+        append_html_link(
+            appender,
+            &format!("« {}", previous.fqcn),
+            link_provider.plugin_link(previous),
+            "ansible-prev",
+        );
+    }
+    if let Some(next) = &navigation.next {
+        append_html_link(
+            appender,
+            &format!("{} »", next.fqcn),
+            link_provider.plugin_link(next),
+            "ansible-next",
+        );
+    }
+    appender.push_str("</nav>");
+}
+
+/// Render breadcrumbs (collection, section, plugin) as MarkDown.
+pub fn append_md_navigation_header(
+    appender: &mut dyn Appender<'_>,
+    navigation: &PluginNavigation,
+    navigation_link_provider: &dyn NavigationLinkProvider,
+) {
+    append_md_link(
+        appender,
+        &navigation.collection,
+        navigation_link_provider.collection_link(&navigation.collection),
+    );
+    appender.push_str(" » ");
+    append_md_link(
+        appender,
+        &navigation.section,
+        navigation_link_provider
+            .plugin_type_link(&navigation.collection, &navigation.plugin.r#type),
+    );
+    appender.push_str(" » ");
+    append_md_link(appender, &navigation.plugin.fqcn, None);
+}
+
+/// Render links to the previous and next plugins as MarkDown.
+///
+/// `link_provider` is used to compute the URLs of the plugins. Nothing is rendered if there is
+/// neither a previous nor a next plugin.
+pub fn append_md_navigation_footer(
+    appender: &mut dyn Appender<'_>,
+    navigation: &PluginNavigation,
+    link_provider: &dyn LinkProvider,
+) {
+    if let Some(previous) = &navigation.previous {
+        append_md_link(
+            appender,
+            &format!("« {}", previous.fqcn),
+            link_provider.plugin_link(previous),
+        );
+    }
+    if let Some(next) = &navigation.next {
+        if navigation.previous.is_some() {
+            appender.push_str(" | ");
+        }
+        append_md_link(
+            appender,
+            &format!("{} »", next.fqcn),
+            link_provider.plugin_link(next),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::{CompactString, NoLinkProvider, OptionLike};
+    use crate::util::stringbuilder::{CollectorAppender, IntoString};
+
+    struct TestLinkProvider {}
+
+    impl LinkProvider for TestLinkProvider {
+        fn plugin_link(&self, plugin: &PluginIdentifier) -> Option<String> {
+            Some(format!("/{}_{}.html", plugin.fqcn, plugin.r#type))
+        }
+
+        fn plugin_option_like_link(
+            &self,
+            _plugin: &PluginIdentifier,
+            _entrypoint: Option<&String>,
+            _what: OptionLike,
+            _name: &[CompactString],
+            _current_plugin: bool,
+        ) -> Option<String> {
+            None
+        }
+    }
+
+    impl NavigationLinkProvider for TestLinkProvider {
+        fn collection_link(&self, collection: &str) -> Option<String> {
+            Some(format!("/{}/index.html", collection))
+        }
+    }
+
+    fn plugin(fqcn: &str, r#type: &str) -> Rc<PluginIdentifier> {
+        Rc::new(PluginIdentifier {
+            fqcn: fqcn.to_string(),
+            r#type: r#type.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_plugin_navigation() {
+        assert_eq!(plugin_type_title("module"), "Modules");
+        assert_eq!(plugin_type_title("lookup"), "Lookup Plugins");
+
+        let navigation = plugin_navigation(&[
+            plugin("foo.bar.b", "module"),
+            plugin("foo.bar.c", "lookup"),
+            plugin("foo.baz.a", "module"),
+            plugin("foo.bar.a", "module"),
+        ]);
+        assert_eq!(
+            navigation
+                .iter()
+                .map(|n| (
+                    n.plugin.fqcn.as_str(),
+                    n.previous.as_ref().map(|p| p.fqcn.as_str()),
+                    n.next.as_ref().map(|p| p.fqcn.as_str()),
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("foo.bar.a", None, Some("foo.bar.b")),
+                ("foo.bar.b", Some("foo.bar.a"), Some("foo.bar.c")),
+                ("foo.bar.c", Some("foo.bar.b"), None),
+                ("foo.baz.a", None, None),
+            ]
+        );
+
+        let mut appender = CollectorAppender::new();
+        append_html_navigation_header(&mut appender, &navigation[1], &TestLinkProvider {});
+        append_html_navigation_footer(&mut appender, &navigation[1], &TestLinkProvider {});
+        assert_eq!(
+            appender.into_string(),
+            concat!(
+                "<nav class=\"ansible-breadcrumbs\">",
+                "<a class=\"ansible-breadcrumb\" href=\"/foo.bar/index.html\">foo.bar</a> » ",
+                "<span class=\"ansible-breadcrumb\">Modules</span> » ",
+                "<span class=\"ansible-breadcrumb-current\">foo.bar.b</span></nav>",
+                "<nav class=\"ansible-prev-next\">",
+                "<a class=\"ansible-prev\" href=\"/foo.bar.a_module.html\">« foo.bar.a</a>",
+                "<a class=\"ansible-next\" href=\"/foo.bar.c_lookup.html\">foo.bar.c »</a></nav>",
+            )
+        );
+
+        let mut appender = CollectorAppender::new();
+        append_md_navigation_header(&mut appender, &navigation[0], &TestLinkProvider {});
+        appender.push_str("\n");
+        append_md_navigation_footer(&mut appender, &navigation[0], &NoLinkProvider::new());
+        assert_eq!(
+            appender.into_string(),
+            "[foo\\.bar](/foo\\.bar/index\\.html) » Modules » foo\\.bar\\.a\nfoo\\.bar\\.b »"
+        );
+    }
+}
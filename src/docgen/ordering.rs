@@ -0,0 +1,129 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::PluginIdentifier;
+use std::cmp::Ordering;
+
+/// Compare two names case-insensitively.
+///
+/// Names that only differ in case are ordered case-sensitively, so that the order is total.
+pub fn compare_names(a: &str, b: &str) -> Ordering {
+    a.chars()
+        .flat_map(char::to_lowercase)
+        .cmp(b.chars().flat_map(char::to_lowercase))
+        .then_with(|| a.cmp(b))
+}
+
+/// Compare two FQCNs (or collection names) component by component.
+///
+/// Every component is compared with `compare_names()`, so `a.b.c` comes before `a.b_c.a`
+/// and `A.b.c` comes before `a.c.a`.
+pub fn compare_fqcns(a: &str, b: &str) -> Ordering {
+    let mut a_parts = a.split('.');
+    let mut b_parts = b.split('.');
+    loop {
+        match (a_parts.next(), b_parts.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a_part), Some(b_part)) => match compare_names(a_part, b_part) {
+                Ordering::Equal => continue,
+                ordering => return ordering,
+            },
+        }
+    }
+}
+
+/// The rank of a plugin type in listings: modules come first, roles last, and all other
+/// plugin types are in between.
+pub fn plugin_type_rank(plugin_type: &str) -> u8 {
+    match plugin_type {
+        "module" => 0,
+        "role" => 2,
+        _ => 1,
+    }
+}
+
+/// Compare two plugin types. Plugin types of the same rank are ordered by name.
+pub fn compare_plugin_types(a: &str, b: &str) -> Ordering {
+    plugin_type_rank(a)
+        .cmp(&plugin_type_rank(b))
+        .then_with(|| compare_names(a, b))
+}
+
+/// The collection part of a FQCN.
+pub fn collection_of(fqcn: &str) -> &str {
+    match fqcn.match_indices('.').nth(1) {
+        Some((index, _)) => &fqcn[..index],
+        None => fqcn,
+    }
+}
+
+/// Compare two plugins for listings.
+///
+/// Plugins are ordered by collection, then grouped by plugin type, and then ordered by name.
+pub fn compare_plugins(a: &PluginIdentifier, b: &PluginIdentifier) -> Ordering {
+    compare_fqcns(collection_of(&a.fqcn), collection_of(&b.fqcn))
+        .then_with(|| compare_plugin_types(&a.r#type, &b.r#type))
+        .then_with(|| compare_fqcns(&a.fqcn, &b.fqcn))
+}
+
+/// Sort plugins in the order used for listings.
+pub fn sort_plugins<P: AsRef<PluginIdentifier>>(plugins: &mut [P]) {
+    plugins.sort_by(|a, b| compare_plugins(a.as_ref(), b.as_ref()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(compare_names("abc", "ABD"), Ordering::Less);
+        assert_eq!(compare_names("ABC", "abc"), Ordering::Less);
+        assert_eq!(compare_names("b", "B"), Ordering::Greater);
+        assert_eq!(compare_fqcns("a.b.c", "a.b_c.a"), Ordering::Less);
+        assert_eq!(compare_fqcns("a.b", "a.b.c"), Ordering::Less);
+        assert_eq!(compare_fqcns("A.b.c", "a.c.a"), Ordering::Less);
+        assert_eq!(compare_plugin_types("module", "become"), Ordering::Less);
+        assert_eq!(compare_plugin_types("vars", "role"), Ordering::Less);
+        assert_eq!(compare_plugin_types("lookup", "filter"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_sort_plugins() {
+        let mut plugins: Vec<Rc<PluginIdentifier>> = [
+            ("foo.bar.Zeta", "module"),
+            ("foo.bar.alpha", "role"),
+            ("foo.bar.beta", "lookup"),
+            ("foo.bar.alpha", "module"),
+            ("abc.def.x", "filter"),
+        ]
+        .into_iter()
+        .map(|(fqcn, r#type)| {
+            Rc::new(PluginIdentifier {
+                fqcn: fqcn.to_string(),
+                r#type: r#type.to_string(),
+            })
+        })
+        .collect();
+        sort_plugins(&mut plugins);
+        assert_eq!(
+            plugins
+                .iter()
+                .map(|p| format!("{} {}", p.fqcn, p.r#type))
+                .collect::<Vec<_>>(),
+            vec![
+                "abc.def.x filter",
+                "foo.bar.alpha module",
+                "foo.bar.Zeta module",
+                "foo.bar.beta lookup",
+                "foo.bar.alpha role",
+            ]
+        );
+    }
+}
@@ -0,0 +1,198 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// What writing a file did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteOutcome {
+    /// The file did not exist before.
+    Created,
+
+    /// The file existed and was replaced.
+    Updated,
+
+    /// The file already had the content and was not touched.
+    Unchanged,
+}
+
+/// A destination for generated files.
+///
+/// Paths are relative to the root of the output, like `collections/foo/bar/baz_module.html`.
+/// Absolute paths and paths containing `..` are rejected.
+pub trait OutputSink {
+    /// Write `content` to the file at `path`, creating parent directories as needed.
+    fn write(&mut self, path: &Path, content: &[u8]) -> io::Result<WriteOutcome>;
+}
+
+fn check_path(path: &Path) -> io::Result<()> {
+    let valid = path.components().next().is_some()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if valid {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{:?} is not a relative path inside the output", path),
+        ))
+    }
+}
+
+/// Writes files below a directory.
+///
+/// Every file is written to a temporary file next to it first, which then replaces the file, so
+/// readers never see partially written files. With [`FileSystemSink::only_if_changed`], files
+/// that already have the content are not written at all, which keeps their modification times
+/// for incremental builds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSystemSink {
+    root: PathBuf,
+    only_if_changed: bool,
+}
+
+impl FileSystemSink {
+    pub fn new(root: &Path) -> FileSystemSink {
+        FileSystemSink {
+            root: root.to_path_buf(),
+            only_if_changed: false,
+        }
+    }
+
+    /// Set whether to skip writing files whose content did not change.
+    pub fn only_if_changed(mut self, only_if_changed: bool) -> Self {
+        self.only_if_changed = only_if_changed;
+        self
+    }
+}
+
+/// Makes the names of temporary files unique within the process, so that sinks writing the same
+/// file at the same time do not use the same temporary file.
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+impl OutputSink for FileSystemSink {
+    fn write(&mut self, path: &Path, content: &[u8]) -> io::Result<WriteOutcome> {
+        check_path(path)?;
+        let path = self.root.join(path);
+        // Only read the existing file if its content matters.
+        let existing = if self.only_if_changed {
+            fs::read(&path).map(|existing| existing == content)
+        } else {
+            fs::metadata(&path).map(|_| false)
+        };
+        let outcome = match existing {
+            Ok(true) => return Ok(WriteOutcome::Unchanged),
+            Ok(false) => WriteOutcome::Updated,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => WriteOutcome::Created,
+            Err(error) => return Err(error),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut temp_name = std::ffi::OsString::from(".");
+        temp_name.push(path.file_name().unwrap_or_default());
+        temp_name.push(format!(
+            ".antsibull-tmp-{}-{}",
+            std::process::id(),
+            TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let temp_path = path.with_file_name(temp_name);
+        fs::write(&temp_path, content)?;
+        if let Err(error) = fs::rename(&temp_path, &path) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(error);
+        }
+        Ok(outcome)
+    }
+}
+
+/// Keeps files in memory, for tests and for post-processing the output.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MemorySink {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl MemorySink {
+    pub fn new() -> MemorySink {
+        MemorySink::default()
+    }
+
+    /// The content of the file at `path`.
+    pub fn get(&self, path: &Path) -> Option<&[u8]> {
+        self.files.get(path).map(|content| content.as_slice())
+    }
+
+    /// The content of the file at `path`, if it is valid UTF-8.
+    pub fn get_str(&self, path: &Path) -> Option<&str> {
+        self.get(path)
+            .and_then(|content| std::str::from_utf8(content).ok())
+    }
+
+    /// All files, sorted by path.
+    pub fn files(&self) -> &BTreeMap<PathBuf, Vec<u8>> {
+        &self.files
+    }
+}
+
+impl OutputSink for MemorySink {
+    fn write(&mut self, path: &Path, content: &[u8]) -> io::Result<WriteOutcome> {
+        check_path(path)?;
+        let outcome = match self.files.get(path) {
+            Some(existing) if existing == content => return Ok(WriteOutcome::Unchanged),
+            Some(_) => WriteOutcome::Updated,
+            None => WriteOutcome::Created,
+        };
+        self.files.insert(path.to_path_buf(), content.to_vec());
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_sinks() {
+        let mut memory = MemorySink::new();
+        let path = Path::new("foo/bar.html");
+        assert_eq!(memory.write(path, b"a").unwrap(), WriteOutcome::Created);
+        assert_eq!(memory.write(path, b"a").unwrap(), WriteOutcome::Unchanged);
+        assert_eq!(memory.write(path, b"b").unwrap(), WriteOutcome::Updated);
+        assert_eq!(memory.get_str(path), Some("b"));
+        assert!(memory.write(Path::new("../x"), b"").is_err());
+        assert!(memory.write(Path::new("/x"), b"").is_err());
+
+        let root = std::env::temp_dir().join(format!("antsibull-output-{}", std::process::id()));
+        let mut sink = FileSystemSink::new(&root).only_if_changed(true);
+        assert_eq!(sink.write(path, b"a").unwrap(), WriteOutcome::Created);
+        assert_eq!(sink.write(path, b"a").unwrap(), WriteOutcome::Unchanged);
+        assert_eq!(sink.write(path, b"b").unwrap(), WriteOutcome::Updated);
+        assert_eq!(fs::read_to_string(root.join(path)).unwrap(), "b");
+        assert_eq!(fs::read_dir(root.join("foo")).unwrap().count(), 1);
+        let mut sink = FileSystemSink::new(&root);
+        assert_eq!(sink.write(path, b"b").unwrap(), WriteOutcome::Updated);
+
+        // Sinks writing the same file at the same time do not share temporary files.
+        let writers: Vec<_> = (0..8)
+            .map(|_| {
+                let root = root.clone();
+                std::thread::spawn(move || {
+                    let mut sink = FileSystemSink::new(&root);
+                    (0..20).all(|_| sink.write(path, b"c").is_ok())
+                })
+            })
+            .collect();
+        assert!(writers.into_iter().all(|writer| writer.join().unwrap()));
+        assert_eq!(fs::read_to_string(root.join(path)).unwrap(), "c");
+        assert_eq!(fs::read_dir(root.join("foo")).unwrap().count(), 1);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
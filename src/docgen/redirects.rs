@@ -0,0 +1,256 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::collection::RuntimeMetadata;
+use crate::docgen::output::{FileSystemSink, OutputSink};
+use crate::markup::{HTMLEscaper, LinkProvider, PluginIdentifier};
+use crate::util::cancellation::{BatchStatus, CancellationToken};
+use crate::util::progress::{NoProgressReporter, ProgressReporter};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A redirect from the page of a plugin that has been renamed or moved to the page of the
+/// plugin it redirects to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginRedirect {
+    /// The old plugin.
+    pub from: PluginIdentifier,
+
+    /// The plugin the old plugin redirects to, after following all redirects.
+    pub to: PluginIdentifier,
+
+    /// The URL of the old plugin's page.
+    pub from_url: String,
+
+    /// The URL of the new plugin's page.
+    pub to_url: String,
+}
+
+/// The redirects of a documentation site.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RedirectMap {
+    pub redirects: Vec<PluginRedirect>,
+}
+
+/// The plugin type used in plugin identifiers for a plugin type used in `meta/runtime.yml`.
+fn plugin_type_of_routing(plugin_type: &str) -> &str {
+    match plugin_type {
+        "modules" => "module",
+        plugin_type => plugin_type,
+    }
+}
+
+impl RedirectMap {
+    /// Compute the redirects of the collection `collection` from its runtime metadata.
+    ///
+    /// Chains of redirects inside the collection are followed, so every redirect points to the
+    /// final plugin. Redirects that end in a cycle, and redirects where one of the pages has no
+    /// URL or both pages have the same URL, are skipped. Redirects are ordered by plugin type
+    /// and old name.
+    pub fn from_runtime(
+        collection: &str,
+        runtime: &RuntimeMetadata,
+        link_provider: &dyn LinkProvider,
+    ) -> RedirectMap {
+        let prefix = format!("{}.", collection);
+        let mut redirects = Vec::new();
+        for (routing_type, plugins) in &runtime.plugin_routing {
+            let plugin_type = plugin_type_of_routing(routing_type);
+            let redirect_of = |fqcn: &str| -> Option<&String> {
+                fqcn.strip_prefix(&prefix)
+                    .and_then(|name| plugins.get(name))
+                    .and_then(|routing| routing.redirect.as_ref())
+            };
+            for name in plugins.keys() {
+                let start = format!("{}{}", prefix, name);
+                let mut chain = vec![start];
+                while let Some(next) = redirect_of(chain.last().unwrap()) {
+                    if chain.contains(next) {
+                        chain.clear();
+                        break;
+                    }
+                    chain.push(next.clone());
+                }
+                if chain.len() < 2 {
+                    continue;
+                }
+                let from = PluginIdentifier {
+                    fqcn: chain[0].clone(),
+                    r#type: plugin_type.to_string(),
+                };
+                let to = PluginIdentifier {
+                    fqcn: chain.pop().unwrap(),
+                    r#type: plugin_type.to_string(),
+                };
+                if let (Some(from_url), Some(to_url)) = (
+                    link_provider.plugin_link(&from),
+                    link_provider.plugin_link(&to),
+                ) {
+                    if from_url != to_url {
+                        redirects.push(PluginRedirect {
+                            from,
+                            to,
+                            from_url,
+                            to_url,
+                        });
+                    }
+                }
+            }
+        }
+        RedirectMap { redirects }
+    }
+
+    /// Render the redirects in the `_redirects` format understood by Netlify and Cloudflare
+    /// Pages, one permanent redirect per line.
+    pub fn to_redirects_file(&self) -> String {
+        let mut result = String::new();
+        for redirect in &self.redirects {
+            result.push_str(&format!("{} {} 301\n", redirect.from_url, redirect.to_url));
+        }
+        result
+    }
+
+    /// Write the redirects in the `_redirects` format to `path`.
+    pub fn write_redirects_file(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_redirects_file())
+    }
+
+    /// Write an HTML page for every redirect that forwards to the new page.
+    ///
+    /// This is for static hosting without support for redirects. The pages are written below
+    /// `root`, at the path of the old URL relative to the root of the site. Redirects whose old
+    /// URL has a scheme are skipped.
+    pub fn write_redirect_pages(&self, root: &Path) -> io::Result<()> {
+        self.write_redirect_pages_to(
+            &mut FileSystemSink::new(root),
+            &NoProgressReporter::new(),
+            &CancellationToken::new(),
+        )
+        .map(|_| ())
+    }
+
+    /// Write an HTML page for every redirect to `sink`, like [`Self::write_redirect_pages`], and
+    /// report every page to `progress`.
+    ///
+    /// Stops before the next page once `cancellation` is cancelled.
+    pub fn write_redirect_pages_to(
+        &self,
+        sink: &mut dyn OutputSink,
+        progress: &dyn ProgressReporter,
+        cancellation: &CancellationToken,
+    ) -> io::Result<BatchStatus> {
+        progress.start("Writing redirect pages", Some(self.redirects.len()));
+        let mut status = BatchStatus::Completed;
+        for (done, redirect) in self.redirects.iter().enumerate() {
+            if cancellation.is_cancelled() {
+                status = BatchStatus::Cancelled;
+                break;
+            }
+            progress.progress(done, &redirect.from_url);
+            if redirect.from_url.contains("://") {
+                continue;
+            }
+            sink.write(
+                Path::new(redirect.from_url.trim_start_matches('/')),
+                redirect_page(&redirect.to_url).as_bytes(),
+            )?;
+        }
+        progress.finish();
+        Ok(status)
+    }
+}
+
+/// An HTML page that forwards to `url`.
+pub fn redirect_page(url: &str) -> String {
+    let escaper = HTMLEscaper::new();
+    let url_attr = escaper.escape_attr_double(url);
+    format!(
+        concat!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n",
+            "<meta http-equiv=\"refresh\" content=\"0; url={0}\">\n",
+            "<link rel=\"canonical\" href=\"{0}\">\n</head>\n",
+            "<body><p>This page has moved to <a href=\"{0}\">{1}</a>.</p></body>\n</html>\n",
+        ),
+        url_attr,
+        escaper.escape_text(url),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::load_runtime_yml;
+    use crate::markup::{CompactString, OptionLike};
+
+    struct TestLinkProvider {}
+
+    impl LinkProvider for TestLinkProvider {
+        fn plugin_link(&self, plugin: &PluginIdentifier) -> Option<String> {
+            if plugin.fqcn.starts_with("other.") {
+                return None;
+            }
+            Some(format!("/{}_{}.html", plugin.fqcn, plugin.r#type))
+        }
+
+        fn plugin_option_like_link(
+            &self,
+            _plugin: &PluginIdentifier,
+            _entrypoint: Option<&String>,
+            _what: OptionLike,
+            _name: &[CompactString],
+            _current_plugin: bool,
+        ) -> Option<String> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_redirect_map() {
+        let runtime = load_runtime_yml(
+            r#"
+plugin_routing:
+  modules:
+    old:
+      redirect: foo.bar.older
+    older:
+      redirect: foo.bar.new
+    external:
+      redirect: other.col.new
+    a:
+      redirect: foo.bar.b
+    b:
+      redirect: foo.bar.a
+    gone:
+      tombstone:
+        removal_version: 2.0.0
+  lookup:
+    x:
+      redirect: foo.bar.y
+"#,
+        )
+        .unwrap();
+        let redirects = RedirectMap::from_runtime("foo.bar", &runtime, &TestLinkProvider {});
+        assert_eq!(
+            redirects.to_redirects_file(),
+            concat!(
+                "/foo.bar.x_lookup.html /foo.bar.y_lookup.html 301\n",
+                "/foo.bar.old_module.html /foo.bar.new_module.html 301\n",
+                "/foo.bar.older_module.html /foo.bar.new_module.html 301\n",
+            )
+        );
+        assert_eq!(redirects.redirects[1].to.fqcn, "foo.bar.new");
+        assert_eq!(redirects.redirects[1].to.r#type, "module");
+
+        let root = std::env::temp_dir().join(format!("antsibull-redirects-{}", std::process::id()));
+        redirects.write_redirect_pages(&root).unwrap();
+        let page = fs::read_to_string(root.join("foo.bar.old_module.html")).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(page, redirect_page("/foo.bar.new_module.html"));
+        assert!(redirect_page("/a?b=1&c=\"2\"")
+            .contains("content=\"0; url=/a?b=1&amp;c=&quot;2&quot;\""));
+    }
+}
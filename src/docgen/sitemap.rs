@@ -0,0 +1,195 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::docgen::navigation::{plugin_navigation, NavigationLinkProvider};
+use crate::markup::{HTMLEscaper, LinkProvider, PluginIdentifier};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Join a URL returned by a link provider with the base URL of the site.
+///
+/// URLs with a scheme are returned unchanged. All other URLs are considered relative to the
+/// root of the site.
+pub(crate) fn absolute_url(base_url: &str, url: &str) -> String {
+    if url.contains("://") {
+        return url.to_string();
+    }
+    format!(
+        "{}/{}",
+        base_url.trim_end_matches('/'),
+        url.trim_start_matches('/')
+    )
+}
+
+/// A page listed in a sitemap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitemapEntry {
+    /// The absolute URL of the page.
+    pub location: String,
+
+    /// The date of the last modification of the page, like `2024-01-31`.
+    pub last_modified: Option<String>,
+}
+
+/// The pages of a documentation site, in the order they are listed in `sitemap.xml`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Sitemap {
+    pub entries: Vec<SitemapEntry>,
+}
+
+impl Sitemap {
+    pub fn new() -> Sitemap {
+        Sitemap::default()
+    }
+
+    /// Compute the sitemap of a documentation site with pages for the given plugins.
+    ///
+    /// The sitemap contains the index page of every collection, the lists of plugins of every
+    /// type, and the plugin pages, as far as the link providers provide URLs for them. Pages
+    /// are listed in the order of `plugin_navigation()`, and every URL is listed once.
+    pub fn for_plugins(
+        base_url: &str,
+        plugins: &[Rc<PluginIdentifier>],
+        link_provider: &dyn LinkProvider,
+        navigation_link_provider: &dyn NavigationLinkProvider,
+    ) -> Sitemap {
+        let mut sitemap = Sitemap::new();
+        let mut seen = BTreeSet::new();
+        let mut add = |url: Option<String>| {
+            if let Some(url) = url {
+                let location = absolute_url(base_url, &url);
+                if seen.insert(location.clone()) {
+                    sitemap.entries.push(SitemapEntry {
+                        location,
+                        last_modified: None,
+                    });
+                }
+            }
+        };
+        for navigation in plugin_navigation(plugins) {
+            add(navigation_link_provider.collection_link(&navigation.collection));
+            add(navigation_link_provider
+                .plugin_type_link(&navigation.collection, &navigation.plugin.r#type));
+            add(link_provider.plugin_link(&navigation.plugin));
+        }
+        sitemap
+    }
+
+    /// Add a page to the sitemap.
+    pub fn add(&mut self, location: String, last_modified: Option<String>) {
+        self.entries.push(SitemapEntry {
+            location,
+            last_modified,
+        });
+    }
+
+    /// Render the sitemap in the sitemaps.org XML format.
+    pub fn to_xml(&self) -> String {
+        let escaper = HTMLEscaper::new();
+        let mut result = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+        ));
+        for entry in &self.entries {
+            result.push_str("  <url>\n    <loc>");
+            result.push_str(&escaper.escape_text(&entry.location));
+            result.push_str("</loc>\n");
+            if let Some(last_modified) = &entry.last_modified {
+                result.push_str("    <lastmod>");
+                result.push_str(&escaper.escape_text(last_modified));
+                result.push_str("</lastmod>\n");
+            }
+            result.push_str("  </url>\n");
+        }
+        result.push_str("</urlset>\n");
+        result
+    }
+
+    /// Write the sitemap to `path`, usually `sitemap.xml` in the root of the site.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_xml())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::{CompactString, OptionLike};
+
+    struct TestLinkProvider {}
+
+    impl LinkProvider for TestLinkProvider {
+        fn plugin_link(&self, plugin: &PluginIdentifier) -> Option<String> {
+            Some(format!("/{}_{}.html", plugin.fqcn, plugin.r#type))
+        }
+
+        fn plugin_option_like_link(
+            &self,
+            _plugin: &PluginIdentifier,
+            _entrypoint: Option<&String>,
+            _what: OptionLike,
+            _name: &[CompactString],
+            _current_plugin: bool,
+        ) -> Option<String> {
+            None
+        }
+    }
+
+    impl NavigationLinkProvider for TestLinkProvider {
+        fn collection_link(&self, collection: &str) -> Option<String> {
+            Some(format!("{}/index.html", collection))
+        }
+    }
+
+    fn plugin(fqcn: &str, r#type: &str) -> Rc<PluginIdentifier> {
+        Rc::new(PluginIdentifier {
+            fqcn: fqcn.to_string(),
+            r#type: r#type.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_sitemap() {
+        assert_eq!(
+            absolute_url("https://example.com/docs/", "/a.html"),
+            "https://example.com/docs/a.html"
+        );
+        assert_eq!(
+            absolute_url("https://example.com/docs", "https://example.org/"),
+            "https://example.org/"
+        );
+
+        let mut sitemap = Sitemap::for_plugins(
+            "https://example.com/",
+            &[
+                plugin("foo.bar.b", "lookup"),
+                plugin("foo.bar.a&b", "module"),
+            ],
+            &TestLinkProvider {},
+            &TestLinkProvider {},
+        );
+        sitemap.add(
+            "https://example.com/changelog.html".to_string(),
+            Some("2024-01-31".to_string()),
+        );
+        assert_eq!(
+            sitemap.to_xml(),
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+                "  <url>\n    <loc>https://example.com/foo.bar/index.html</loc>\n  </url>\n",
+                "  <url>\n    <loc>https://example.com/foo.bar.a&amp;b_module.html</loc>\n  </url>\n",
+                "  <url>\n    <loc>https://example.com/foo.bar.b_lookup.html</loc>\n  </url>\n",
+                "  <url>\n    <loc>https://example.com/changelog.html</loc>\n",
+                "    <lastmod>2024-01-31</lastmod>\n  </url>\n",
+                "</urlset>\n",
+            )
+        );
+    }
+}
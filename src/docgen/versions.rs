@@ -0,0 +1,271 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::docgen::navigation::NavigationLinkProvider;
+use crate::docgen::sitemap::absolute_url;
+use crate::markup::{
+    CompactString, DeterministicLinkProvider, LinkProvider, OptionLike, PluginIdentifier,
+};
+
+/// A published version of a documentation site, like `latest`, `9`, or `devel`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocsiteVersion {
+    /// The name of the version, which is used in URLs.
+    pub name: String,
+
+    /// The title of the version shown to readers, like `Ansible 9`.
+    pub title: String,
+
+    /// The prefix of all URLs of this version, like `/9/`.
+    pub url_prefix: String,
+}
+
+impl DocsiteVersion {
+    /// Create a version whose URLs are prefixed with `/<name>/`.
+    pub fn new(name: &str, title: &str) -> DocsiteVersion {
+        DocsiteVersion {
+            name: name.to_string(),
+            title: title.to_string(),
+            url_prefix: format!("/{}/", name),
+        }
+    }
+
+    /// Set the prefix of all URLs of this version.
+    pub fn url_prefix(mut self, url_prefix: &str) -> Self {
+        self.url_prefix = url_prefix.to_string();
+        self
+    }
+
+    /// The URL of a page of this version.
+    ///
+    /// `url` is relative to the root of a version. URLs with a scheme are returned unchanged.
+    pub fn url(&self, url: &str) -> String {
+        absolute_url(&self.url_prefix, url)
+    }
+}
+
+/// A link to a page in another version of the documentation site.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionLink {
+    /// The name of the version.
+    pub name: String,
+
+    /// The title of the version.
+    pub title: String,
+
+    /// The URL of the page in that version.
+    pub url: String,
+
+    /// Whether this is the version the page belongs to.
+    pub current: bool,
+}
+
+/// The versions of a documentation site that are generated from the same sources.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MultiVersionDocsite {
+    /// The versions, in the order they are presented to readers.
+    pub versions: Vec<DocsiteVersion>,
+}
+
+impl MultiVersionDocsite {
+    pub fn new() -> MultiVersionDocsite {
+        MultiVersionDocsite::default()
+    }
+
+    /// Add a version.
+    pub fn version(mut self, version: DocsiteVersion) -> Self {
+        self.versions.push(version);
+        self
+    }
+
+    /// Look up a version by name.
+    pub fn get(&self, name: &str) -> Option<&DocsiteVersion> {
+        self.versions.iter().find(|version| version.name == name)
+    }
+
+    /// Create a link provider for the version `name` that prefixes the URLs of `link_provider`.
+    ///
+    /// Returns `None` if there is no such version.
+    pub fn link_provider<'l, L: ?Sized>(
+        &self,
+        name: &str,
+        link_provider: &'l L,
+    ) -> Option<VersionedLinkProvider<'l, L>> {
+        self.get(name).map(|version| VersionedLinkProvider {
+            link_provider,
+            version: version.clone(),
+        })
+    }
+
+    /// Links to a page in all versions, for example for a version switcher.
+    ///
+    /// `url` is the URL of the page relative to the root of a version, and `has_page` decides
+    /// whether a version contains the page. The version `current` is always included.
+    pub fn page_versions(
+        &self,
+        current: &str,
+        url: &str,
+        has_page: impl Fn(&DocsiteVersion) -> bool,
+    ) -> Vec<VersionLink> {
+        self.versions
+            .iter()
+            .filter(|version| version.name == current || has_page(version))
+            .map(|version| VersionLink {
+                name: version.name.clone(),
+                title: version.title.clone(),
+                url: version.url(url),
+                current: version.name == current,
+            })
+            .collect()
+    }
+}
+
+/// Prefixes the URLs of another link provider with the URL prefix of a version.
+///
+/// This implements [`NavigationLinkProvider`] and [`DeterministicLinkProvider`] if the wrapped
+/// link provider does.
+pub struct VersionedLinkProvider<'l, L: ?Sized> {
+    link_provider: &'l L,
+    version: DocsiteVersion,
+}
+
+impl<L: ?Sized> VersionedLinkProvider<'_, L> {
+    /// The version the URLs belong to.
+    pub fn version(&self) -> &DocsiteVersion {
+        &self.version
+    }
+}
+
+impl<L: LinkProvider + ?Sized> LinkProvider for VersionedLinkProvider<'_, L> {
+    fn plugin_link(&self, plugin: &PluginIdentifier) -> Option<String> {
+        self.link_provider
+            .plugin_link(plugin)
+            .map(|url| self.version.url(&url))
+    }
+
+    fn plugin_option_like_link(
+        &self,
+        plugin: &PluginIdentifier,
+        entrypoint: Option<&String>,
+        what: OptionLike,
+        name: &[CompactString],
+        current_plugin: bool,
+    ) -> Option<String> {
+        self.link_provider
+            .plugin_option_like_link(plugin, entrypoint, what, name, current_plugin)
+            .map(|url| self.version.url(&url))
+    }
+
+    fn env_variable_link(&self, name: &str) -> Option<String> {
+        self.link_provider
+            .env_variable_link(name)
+            .map(|url| self.version.url(&url))
+    }
+}
+
+impl<L: DeterministicLinkProvider + ?Sized> DeterministicLinkProvider
+    for VersionedLinkProvider<'_, L>
+{
+}
+
+impl<L: NavigationLinkProvider + ?Sized> NavigationLinkProvider for VersionedLinkProvider<'_, L> {
+    fn collection_link(&self, collection: &str) -> Option<String> {
+        self.link_provider
+            .collection_link(collection)
+            .map(|url| self.version.url(&url))
+    }
+
+    fn plugin_type_link(&self, collection: &str, plugin_type: &str) -> Option<String> {
+        self.link_provider
+            .plugin_type_link(collection, plugin_type)
+            .map(|url| self.version.url(&url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestLinkProvider {}
+
+    impl LinkProvider for TestLinkProvider {
+        fn plugin_link(&self, plugin: &PluginIdentifier) -> Option<String> {
+            Some(format!(
+                "collections/{}_{}.html",
+                plugin.fqcn, plugin.r#type
+            ))
+        }
+
+        fn plugin_option_like_link(
+            &self,
+            plugin: &PluginIdentifier,
+            _entrypoint: Option<&String>,
+            _what: OptionLike,
+            name: &[CompactString],
+            _current_plugin: bool,
+        ) -> Option<String> {
+            Some(format!(
+                "https://example.org/{}#{}",
+                plugin.fqcn,
+                name.join("/")
+            ))
+        }
+    }
+
+    impl NavigationLinkProvider for TestLinkProvider {
+        fn collection_link(&self, collection: &str) -> Option<String> {
+            Some(format!("/collections/{}/index.html", collection))
+        }
+    }
+
+    #[test]
+    fn test_multi_version_docsite() {
+        let docsite = MultiVersionDocsite::new()
+            .version(DocsiteVersion::new("latest", "Latest"))
+            .version(DocsiteVersion::new("9", "Ansible 9"))
+            .version(DocsiteVersion::new("devel", "Development").url_prefix("/devel/en/"));
+        assert!(docsite.link_provider("8", &TestLinkProvider {}).is_none());
+
+        let plugin = PluginIdentifier {
+            fqcn: "foo.bar.baz".to_string(),
+            r#type: "module".to_string(),
+        };
+        let link_provider = docsite.link_provider("9", &TestLinkProvider {}).unwrap();
+        assert_eq!(link_provider.version().title, "Ansible 9");
+        assert_eq!(
+            link_provider.plugin_link(&plugin).unwrap(),
+            "/9/collections/foo.bar.baz_module.html"
+        );
+        assert_eq!(
+            link_provider
+                .plugin_option_like_link(&plugin, None, OptionLike::Option, &["a".into()], false)
+                .unwrap(),
+            "https://example.org/foo.bar.baz#a"
+        );
+        assert_eq!(
+            link_provider.collection_link("foo.bar").unwrap(),
+            "/9/collections/foo.bar/index.html"
+        );
+        assert_eq!(link_provider.plugin_type_link("foo.bar", "module"), None);
+
+        let page = TestLinkProvider {}.plugin_link(&plugin).unwrap();
+        assert_eq!(
+            docsite
+                .page_versions("9", &page, |version| version.name != "latest")
+                .iter()
+                .map(|link| (link.name.as_str(), link.url.as_str(), link.current))
+                .collect::<Vec<_>>(),
+            vec![
+                ("9", "/9/collections/foo.bar.baz_module.html", true),
+                (
+                    "devel",
+                    "/devel/en/collections/foo.bar.baz_module.html",
+                    false
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,210 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::document::dom;
+use crate::document::format;
+use crate::markup;
+use crate::markup::{
+    AnsibleDocTextFormatter, AnsibleDocTextOptions, LinkProvider, PluginIdentifier,
+};
+use crate::util::stringbuilder::{Appender, CollectorAppender, IntoString};
+use std::rc::Rc;
+use std::sync::LazyLock;
+
+pub struct AnsibleDocTextBlockFormatter {
+    formatter: AnsibleDocTextFormatter,
+}
+
+impl AnsibleDocTextBlockFormatter {
+    fn new() -> AnsibleDocTextBlockFormatter {
+        AnsibleDocTextBlockFormatter::with_options(AnsibleDocTextOptions::new())
+    }
+
+    /// Create a block formatter with custom conventions.
+    ///
+    /// Paragraphs are wrapped before they are indented, so the width applies to the content of
+    /// nested blocks.
+    pub fn with_options(options: AnsibleDocTextOptions) -> AnsibleDocTextBlockFormatter {
+        AnsibleDocTextBlockFormatter {
+            formatter: AnsibleDocTextFormatter::with_options(options),
+        }
+    }
+
+    fn options(&self) -> &AnsibleDocTextOptions {
+        self.formatter.options()
+    }
+
+    fn render_paragraph<'a>(
+        &self,
+        parts: &'a dom::Paragraph<'a>,
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) -> String {
+        let mut content = CollectorAppender::new();
+        markup::append_paragraph(
+            &mut content,
+            parts.iter(),
+            &self.formatter,
+            link_provider,
+            "",
+            "",
+            "",
+            current_plugin,
+        );
+        self.options().wrap(&content.into_string())
+    }
+
+    fn render_paragraphs<'a>(
+        &self,
+        paragraphs: &'a [dom::Paragraph<'a>],
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) -> String {
+        paragraphs
+            .iter()
+            .map(|paragraph| self.render_paragraph(paragraph, link_provider, current_plugin))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    fn append_list<'a, F>(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        items: &'a [Vec<dom::Paragraph<'a>>],
+        marker: F,
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) where
+        F: Fn(usize) -> String,
+    {
+        let item_sep = if format::is_compact_list(items) {
+            "\n"
+        } else {
+            "\n\n"
+        };
+        format::append_rendered_list(
+            appender,
+            items
+                .iter()
+                .map(|item| self.render_paragraphs(item, link_provider, current_plugin)),
+            item_sep,
+            marker,
+        );
+    }
+
+    /// Render paragraphs and append them indented.
+    fn append_indented_paragraphs<'a>(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        paragraphs: &'a [dom::Paragraph<'a>],
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) {
+        if paragraphs.is_empty() {
+            return;
+        }
+        let content = self.render_paragraphs(paragraphs, link_provider, current_plugin);
+        appender.push_str("\n");
+        appender.push_owned_string(format::indent(&content, "  ", ""));
+    }
+}
+
+impl<'a> format::BlockFormatter<'a> for AnsibleDocTextBlockFormatter {
+    fn append_block(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        block: &'a dom::Block<'a>,
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) {
+        match block {
+            dom::Block::Paragraph { parts } => appender.push_owned_string(self.render_paragraph(
+                parts,
+                link_provider,
+                current_plugin,
+            )),
+            dom::Block::Admonition {
+                kind,
+                title,
+                paragraphs,
+            } => {
+                appender.push_str(title.unwrap_or(kind.title()));
+                appender.push_str(":");
+                self.append_indented_paragraphs(
+                    appender,
+                    paragraphs,
+                    link_provider,
+                    current_plugin,
+                );
+            }
+            dom::Block::UnorderedList { items } => self.append_list(
+                appender,
+                items,
+                |_| self.options().bullet.clone(),
+                link_provider,
+                current_plugin,
+            ),
+            dom::Block::OrderedList { start, items } => self.append_list(
+                appender,
+                items,
+                |index| format::ordered_list_marker(*start, index),
+                link_provider,
+                current_plugin,
+            ),
+            dom::Block::DefinitionList { items } => {
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        appender.push_str("\n");
+                    }
+                    appender.push_owned_string(self.render_paragraph(
+                        &item.term,
+                        link_provider,
+                        current_plugin,
+                    ));
+                    self.append_indented_paragraphs(
+                        appender,
+                        &item.definition,
+                        link_provider,
+                        current_plugin,
+                    );
+                }
+            }
+            dom::Block::Heading { level, title } => {
+                let underline = if *level <= 1 { "=" } else { "-" };
+                appender.push_owned_string(title.clone());
+                appender.push_str("\n");
+                appender.push_owned_string(underline.repeat(title.chars().count()));
+            }
+            dom::Block::CodeBlock { language: _, text } => {
+                appender.push_owned_string(format::indent(format::trim_code(text), "    ", ""));
+            }
+        }
+    }
+}
+
+pub static ANSIBLE_DOC_TEXT_BLOCK_FORMATTER: LazyLock<AnsibleDocTextBlockFormatter> =
+    LazyLock::new(AnsibleDocTextBlockFormatter::new);
+
+/// Apply the ansible-doc text block formatter to all given blocks, and concatenate the results.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup inside the blocks.
+pub fn append_ansible_doc_text_blocks<'a, I>(
+    appender: &mut dyn Appender<'a>,
+    blocks: I,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) where
+    I: IntoIterator<Item = &'a dom::Block<'a>>,
+{
+    format::append_blocks(
+        appender,
+        blocks,
+        &*ANSIBLE_DOC_TEXT_BLOCK_FORMATTER,
+        link_provider,
+        "\n\n",
+        current_plugin,
+    );
+}
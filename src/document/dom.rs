@@ -0,0 +1,130 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::Part;
+use std::fmt;
+
+/// A paragraph of markup parts.
+pub type Paragraph<'a> = Vec<Part<'a>>;
+
+/// The kind of an admonition.
+///
+/// These correspond to the specific admonitions supported by docutils and Sphinx.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdmonitionKind {
+    Attention,
+    Caution,
+    Danger,
+    Error,
+    Hint,
+    Important,
+    Note,
+    Tip,
+    Warning,
+}
+
+impl AdmonitionKind {
+    /// The name of the admonition, as used for RST directives and HTML classes.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AdmonitionKind::Attention => "attention",
+            AdmonitionKind::Caution => "caution",
+            AdmonitionKind::Danger => "danger",
+            AdmonitionKind::Error => "error",
+            AdmonitionKind::Hint => "hint",
+            AdmonitionKind::Important => "important",
+            AdmonitionKind::Note => "note",
+            AdmonitionKind::Tip => "tip",
+            AdmonitionKind::Warning => "warning",
+        }
+    }
+
+    /// The default title of the admonition.
+    pub fn title(&self) -> &'static str {
+        match self {
+            AdmonitionKind::Attention => "Attention",
+            AdmonitionKind::Caution => "Caution",
+            AdmonitionKind::Danger => "Danger",
+            AdmonitionKind::Error => "Error",
+            AdmonitionKind::Hint => "Hint",
+            AdmonitionKind::Important => "Important",
+            AdmonitionKind::Note => "Note",
+            AdmonitionKind::Tip => "Tip",
+            AdmonitionKind::Warning => "Warning",
+        }
+    }
+}
+
+impl fmt::Display for AdmonitionKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A block of a document.
+///
+/// A document is a sequence of blocks. Every block is rendered on its own,
+/// and blocks are separated by format-specific separators.
+#[derive(Debug, PartialEq)]
+pub enum Block<'a> {
+    /// A paragraph of markup.
+    Paragraph { parts: Paragraph<'a> },
+
+    /// An admonition (note, warning, ...) containing markup paragraphs.
+    Admonition {
+        /// The kind of admonition.
+        kind: AdmonitionKind,
+
+        /// An optional custom title. If not provided, the default title of the kind is used.
+        title: Option<&'a str>,
+
+        /// The paragraphs inside the admonition.
+        paragraphs: Vec<Paragraph<'a>>,
+    },
+
+    /// An unordered (bullet) list. Every item consists of markup paragraphs.
+    UnorderedList { items: Vec<Vec<Paragraph<'a>>> },
+
+    /// An ordered (numbered) list. Every item consists of markup paragraphs.
+    OrderedList {
+        /// The number of the first item.
+        start: usize,
+
+        /// The items of the list.
+        items: Vec<Vec<Paragraph<'a>>>,
+    },
+
+    /// A definition list.
+    DefinitionList { items: Vec<DefinitionListItem<'a>> },
+
+    /// A section heading.
+    Heading {
+        /// The level of the heading, starting with 1 for the document title.
+        level: usize,
+
+        /// The plain text of the heading.
+        title: String,
+    },
+
+    /// A block of code.
+    CodeBlock {
+        /// The language of the code, used for syntax highlighting.
+        language: Option<&'a str>,
+
+        /// The code itself. Trailing newlines are ignored.
+        text: &'a str,
+    },
+}
+
+/// An item of a definition list.
+#[derive(Debug, PartialEq)]
+pub struct DefinitionListItem<'a> {
+    /// The term that is defined.
+    pub term: Paragraph<'a>,
+
+    /// The definition, consisting of markup paragraphs.
+    pub definition: Vec<Paragraph<'a>>,
+}
@@ -0,0 +1,171 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::document::dom;
+use saphyr::Yaml;
+use std::fmt;
+
+/// The language used for the code blocks of examples.
+pub const EXAMPLES_LANGUAGE: &str = "yaml+jinja";
+
+/// A YAML document from an EXAMPLES block.
+#[derive(Debug, PartialEq)]
+pub struct ExampleDocument<'a> {
+    /// The document's text, without document separators.
+    pub text: &'a str,
+
+    /// The line (1-based) of the EXAMPLES block on which the document starts.
+    pub line: usize,
+}
+
+/// A YAML syntax error found in an EXAMPLES block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExamplesError {
+    /// The line (1-based) of the EXAMPLES block on which the error occured.
+    pub line: usize,
+
+    /// The column (1-based) on which the error occured.
+    pub column: usize,
+
+    /// The error message.
+    pub message: String,
+}
+
+impl fmt::Display for ExamplesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "YAML syntax error in line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+fn is_document_separator(line: &str) -> bool {
+    let line = line.trim_end_matches(['\n', '\r']);
+    if line == "..." {
+        return true;
+    }
+    match line.strip_prefix("---") {
+        Some(rest) => rest.is_empty() || rest.starts_with([' ', '\t']),
+        None => false,
+    }
+}
+
+/// Split an EXAMPLES block into its YAML documents.
+///
+/// Document separators (`---`) and document end markers (`...`) are removed. Documents
+/// that only consist of whitespace are skipped.
+pub fn split_examples(examples: &str) -> Vec<ExampleDocument<'_>> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    let mut start_line = 1;
+    let mut offset = 0;
+    let mut push = |start: usize, end: usize, line: usize| {
+        let text = &examples[start..end];
+        if !text.trim().is_empty() {
+            result.push(ExampleDocument { text, line });
+        }
+    };
+    for (index, line) in examples.split_inclusive('\n').enumerate() {
+        if is_document_separator(line) {
+            push(start, offset, start_line);
+            start = offset + line.len();
+            start_line = index + 2;
+        }
+        offset += line.len();
+    }
+    push(start, examples.len(), start_line);
+    result
+}
+
+/// Validate all YAML documents of an EXAMPLES block.
+///
+/// Returns the syntax errors found. Every document is validated on its own, so errors in
+/// one document do not hide errors in later documents.
+pub fn validate_examples(examples: &str) -> Vec<ExamplesError> {
+    split_examples(examples)
+        .iter()
+        .filter_map(|document| match Yaml::load_from_str(document.text) {
+            Ok(_) => None,
+            Err(error) => Some(ExamplesError {
+                line: document.line + error.marker().line() - 1,
+                column: error.marker().col() + 1,
+                message: error.info().to_string(),
+            }),
+        })
+        .collect()
+}
+
+/// Convert an EXAMPLES block to code blocks, one per YAML document.
+///
+/// If `validate` is `true`, the YAML documents are validated first, and all syntax errors are
+/// returned if there are any.
+pub fn examples_to_blocks(
+    examples: &str,
+    validate: bool,
+) -> Result<Vec<dom::Block<'_>>, Vec<ExamplesError>> {
+    if validate {
+        let errors = validate_examples(examples);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+    }
+    Ok(split_examples(examples)
+        .into_iter()
+        .map(|document| dom::Block::CodeBlock {
+            language: Some(EXAMPLES_LANGUAGE),
+            text: document.text,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_examples() {
+        assert_eq!(split_examples(""), vec![]);
+        assert_eq!(
+            split_examples("- name: Foo\n  debug:\n"),
+            vec![ExampleDocument {
+                text: "- name: Foo\n  debug:\n",
+                line: 1
+            }]
+        );
+        assert_eq!(
+            split_examples("\n---\n- a: b\n...\n--- # foo\n\n- c\n---"),
+            vec![
+                ExampleDocument {
+                    text: "- a: b\n",
+                    line: 3
+                },
+                ExampleDocument {
+                    text: "\n- c\n",
+                    line: 6
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_examples() {
+        assert_eq!(validate_examples("- a: b\n---\n- c: d\n"), vec![]);
+        let errors = validate_examples("- a: b\n---\n- c: d\n  e: [\n---\nfoo: bar: baz\n");
+        assert_eq!(errors.len(), 2);
+        assert_eq!((errors[0].line, errors[0].column), (5, 1));
+        assert_eq!((errors[1].line, errors[1].column), (6, 9));
+        assert!(examples_to_blocks("foo: bar: baz", true).is_err());
+        assert_eq!(
+            examples_to_blocks("foo: bar: baz", false),
+            Ok(vec![dom::Block::CodeBlock {
+                language: Some(EXAMPLES_LANGUAGE),
+                text: "foo: bar: baz"
+            }])
+        );
+    }
+}
@@ -0,0 +1,137 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::document::dom;
+use crate::markup::{LinkProvider, PluginIdentifier};
+use crate::util::stringbuilder::Appender;
+use std::rc::Rc;
+
+pub trait BlockFormatter<'a> {
+    fn append_block(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        block: &'a dom::Block<'a>,
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    );
+}
+
+/// Apply the block formatter to all given blocks, and insert separators between blocks.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup inside the blocks.
+pub fn append_blocks<'a, I>(
+    appender: &mut dyn Appender<'a>,
+    blocks: I,
+    formatter: &dyn BlockFormatter<'a>,
+    link_provider: &dyn LinkProvider,
+    block_sep: &'a str,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) where
+    I: IntoIterator<Item = &'a dom::Block<'a>>,
+{
+    let mut first = true;
+    for block in blocks {
+        if first {
+            first = false;
+        } else {
+            appender.push_str(block_sep);
+        }
+        formatter.append_block(appender, block, link_provider, current_plugin);
+    }
+}
+
+/// Prefix every line of `text` with `prefix`.
+///
+/// Empty lines are prefixed with `empty_prefix` instead, which allows to avoid trailing whitespace.
+pub(crate) fn indent(text: &str, prefix: &str, empty_prefix: &str) -> String {
+    indent_first(text, prefix, prefix, empty_prefix)
+}
+
+/// Prefix the first line of `text` with `first_prefix`, and every other line with `prefix`.
+///
+/// Empty lines after the first one are prefixed with `empty_prefix` instead. If `text` is empty,
+/// `first_prefix` without trailing whitespace is returned.
+pub(crate) fn indent_first(
+    text: &str,
+    first_prefix: &str,
+    prefix: &str,
+    empty_prefix: &str,
+) -> String {
+    if text.is_empty() {
+        return first_prefix.trim_end().to_string();
+    }
+    let mut result = String::with_capacity(text.len() + prefix.len() * 8);
+    for (index, line) in text.split('\n').enumerate() {
+        if index == 0 {
+            if line.is_empty() {
+                result.push_str(first_prefix.trim_end());
+            } else {
+                result.push_str(first_prefix);
+                result.push_str(line);
+            }
+            continue;
+        }
+        result.push('\n');
+        if line.is_empty() {
+            result.push_str(empty_prefix);
+        } else {
+            result.push_str(prefix);
+            result.push_str(line);
+        }
+    }
+    result
+}
+
+/// Whether the list items can be rendered as a compact list, without empty lines between items.
+pub(crate) fn is_compact_list(items: &[Vec<dom::Paragraph>]) -> bool {
+    items.iter().all(|item| item.len() <= 1)
+}
+
+/// Append already rendered list items.
+///
+/// The first line of every item is prefixed with the item's marker, and all other lines are
+/// indented by the marker's width.
+pub(crate) fn append_rendered_list<'a, I, F>(
+    appender: &mut dyn Appender<'a>,
+    items: I,
+    item_sep: &'a str,
+    marker: F,
+) where
+    I: Iterator<Item = String>,
+    F: Fn(usize) -> String,
+{
+    for (index, item) in items.enumerate() {
+        if index > 0 {
+            appender.push_str(item_sep);
+        }
+        let first_prefix = marker(index);
+        let prefix = " ".repeat(first_prefix.chars().count());
+        appender.push_owned_string(indent_first(&item, &first_prefix, &prefix, ""));
+    }
+}
+
+/// The character used to underline RST section headings of the given level.
+///
+/// This follows the convention of the Ansible documentation.
+pub(crate) fn rst_heading_char(level: usize) -> char {
+    match level {
+        0 | 1 => '=',
+        2 => '-',
+        3 => '~',
+        4 => '^',
+        _ => '.',
+    }
+}
+
+/// Remove trailing newlines from code.
+pub(crate) fn trim_code(text: &str) -> &str {
+    text.trim_end_matches(['\n', '\r'])
+}
+
+/// The marker for an item of an ordered list.
+pub(crate) fn ordered_list_marker(start: usize, index: usize) -> String {
+    format!("{}. ", start + index)
+}
@@ -0,0 +1,204 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::document::dom;
+use crate::document::format;
+use crate::markup;
+use crate::markup::{HTMLEscaper, LinkProvider, PluginIdentifier};
+use crate::util::stringbuilder::Appender;
+use std::rc::Rc;
+use std::sync::LazyLock;
+
+pub struct HTMLBlockFormatter {
+    formatter: &'static (dyn for<'x> markup::Formatter<'x> + Sync),
+    html_escaper: HTMLEscaper,
+}
+
+impl HTMLBlockFormatter {
+    fn new(formatter: &'static (dyn for<'x> markup::Formatter<'x> + Sync)) -> HTMLBlockFormatter {
+        HTMLBlockFormatter {
+            formatter,
+            html_escaper: HTMLEscaper::new(),
+        }
+    }
+
+    fn append_paragraphs<'a>(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        paragraphs: &'a [dom::Paragraph<'a>],
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) {
+        markup::append_paragraphs(
+            appender,
+            paragraphs.iter().map(|paragraph| paragraph.iter()),
+            self.formatter,
+            link_provider,
+            "<p>",
+            "</p>",
+            "",
+            "",
+            current_plugin,
+        );
+    }
+
+    fn append_list_items<'a>(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        items: &'a [Vec<dom::Paragraph<'a>>],
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) {
+        for item in items {
+            appender.push_str("<li>");
+            self.append_paragraphs(appender, item, link_provider, current_plugin);
+            appender.push_str("</li>");
+        }
+    }
+}
+
+impl<'a> format::BlockFormatter<'a> for HTMLBlockFormatter {
+    fn append_block(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        block: &'a dom::Block<'a>,
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) {
+        match block {
+            dom::Block::Paragraph { parts } => markup::append_paragraph(
+                appender,
+                parts.iter(),
+                self.formatter,
+                link_provider,
+                "<p>",
+                "</p>",
+                "",
+                current_plugin,
+            ),
+            dom::Block::Admonition {
+                kind,
+                title,
+                paragraphs,
+            } => {
+                appender.push_str("<div class=\"admonition ");
+                appender.push_str(kind.name());
+                appender.push_str("\"><p class=\"admonition-title\">");
+                match title {
+                    Some(t) => appender.push_cow_str(self.html_escaper.escape_text(t)),
+                    None => appender.push_str(kind.title()),
+                }
+                appender.push_str("</p>");
+                self.append_paragraphs(appender, paragraphs, link_provider, current_plugin);
+                appender.push_str("</div>");
+            }
+            dom::Block::UnorderedList { items } => {
+                appender.push_str("<ul>");
+                self.append_list_items(appender, items, link_provider, current_plugin);
+                appender.push_str("</ul>");
+            }
+            dom::Block::OrderedList { start, items } => {
+                if *start == 1 {
+                    appender.push_str("<ol>");
+                } else {
+                    appender.push_owned_string(format!("<ol start=\"{}\">", start));
+                }
+                self.append_list_items(appender, items, link_provider, current_plugin);
+                appender.push_str("</ol>");
+            }
+            dom::Block::DefinitionList { items } => {
+                appender.push_str("<dl>");
+                for item in items {
+                    markup::append_paragraph(
+                        appender,
+                        item.term.iter(),
+                        self.formatter,
+                        link_provider,
+                        "<dt>",
+                        "</dt>",
+                        "",
+                        current_plugin,
+                    );
+                    appender.push_str("<dd>");
+                    self.append_paragraphs(
+                        appender,
+                        &item.definition,
+                        link_provider,
+                        current_plugin,
+                    );
+                    appender.push_str("</dd>");
+                }
+                appender.push_str("</dl>");
+            }
+            dom::Block::Heading { level, title } => {
+                let tag = format!("h{}", (*level).clamp(1, 6));
+                appender.push_owned_string(format!("<{}>", tag));
+                appender.push_cow_str(self.html_escaper.escape_text(title));
+                appender.push_owned_string(format!("</{}>", tag));
+            }
+            dom::Block::CodeBlock { language, text } => {
+                match language {
+                    Some(l) => {
+                        appender.push_str("<pre><code class=\"language-");
+                        appender.push_cow_str(self.html_escaper.escape_attr_double(l));
+                        appender.push_str("\">");
+                    }
+                    None => appender.push_str("<pre><code>"),
+                }
+                appender.push_cow_str(self.html_escaper.escape_text(format::trim_code(text)));
+                appender.push_str("</code></pre>");
+            }
+        }
+    }
+}
+
+pub static ANTSIBULL_HTML_BLOCK_FORMATTER: LazyLock<HTMLBlockFormatter> =
+    LazyLock::new(|| HTMLBlockFormatter::new(&*markup::ANTSIBULL_HTML_FORMATTER));
+
+pub static PLAIN_HTML_BLOCK_FORMATTER: LazyLock<HTMLBlockFormatter> =
+    LazyLock::new(|| HTMLBlockFormatter::new(&*markup::PLAIN_HTML_FORMATTER));
+
+/// Apply the Antsibull HTML block formatter to all given blocks, and concatenate the results.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup inside the blocks.
+pub fn append_antsibull_html_blocks<'a, I>(
+    appender: &mut dyn Appender<'a>,
+    blocks: I,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) where
+    I: IntoIterator<Item = &'a dom::Block<'a>>,
+{
+    format::append_blocks(
+        appender,
+        blocks,
+        &*ANTSIBULL_HTML_BLOCK_FORMATTER,
+        link_provider,
+        "",
+        current_plugin,
+    );
+}
+
+/// Apply the plain HTML block formatter to all given blocks, and concatenate the results.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup inside the blocks.
+pub fn append_plain_html_blocks<'a, I>(
+    appender: &mut dyn Appender<'a>,
+    blocks: I,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) where
+    I: IntoIterator<Item = &'a dom::Block<'a>>,
+{
+    format::append_blocks(
+        appender,
+        blocks,
+        &*PLAIN_HTML_BLOCK_FORMATTER,
+        link_provider,
+        "",
+        current_plugin,
+    );
+}
@@ -0,0 +1,230 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::document::dom;
+use crate::document::format;
+use crate::error::Error;
+use crate::markup;
+use crate::markup::{LinkProvider, MDEscaper, PluginIdentifier};
+use crate::util::stringbuilder::{Appender, CollectorAppender, IntoString};
+use std::rc::Rc;
+use std::sync::LazyLock;
+
+pub struct MDBlockFormatter {
+    md_escaper: MDEscaper,
+}
+
+impl MDBlockFormatter {
+    fn new() -> Result<MDBlockFormatter, Error> {
+        Ok(MDBlockFormatter {
+            md_escaper: MDEscaper::new()?,
+        })
+    }
+
+    fn append_paragraphs<'a>(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        paragraphs: &'a [dom::Paragraph<'a>],
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) {
+        markup::append_md_paragraphs(
+            appender,
+            paragraphs.iter().map(|paragraph| paragraph.iter()),
+            link_provider,
+            current_plugin,
+        );
+    }
+
+    fn render_paragraphs<'a>(
+        &self,
+        paragraphs: &'a [dom::Paragraph<'a>],
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) -> String {
+        let mut content = CollectorAppender::new();
+        self.append_paragraphs(&mut content, paragraphs, link_provider, current_plugin);
+        content.into_string()
+    }
+
+    fn append_list<'a, F>(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        items: &'a [Vec<dom::Paragraph<'a>>],
+        marker: F,
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) where
+        F: Fn(usize) -> String,
+    {
+        let item_sep = if format::is_compact_list(items) {
+            "\n"
+        } else {
+            "\n\n"
+        };
+        format::append_rendered_list(
+            appender,
+            items
+                .iter()
+                .map(|item| self.render_paragraphs(item, link_provider, current_plugin)),
+            item_sep,
+            marker,
+        );
+    }
+
+    /// A code fence that is longer than every sequence of backticks in the code.
+    fn code_fence(code: &str) -> String {
+        let mut longest = 0;
+        let mut current = 0;
+        for c in code.chars() {
+            if c == '`' {
+                current += 1;
+                longest = longest.max(current);
+            } else {
+                current = 0;
+            }
+        }
+        "`".repeat((longest + 1).max(3))
+    }
+
+    /// The GitHub callout type used for an admonition kind.
+    fn callout_type(kind: dom::AdmonitionKind) -> &'static str {
+        match kind {
+            dom::AdmonitionKind::Note => "NOTE",
+            dom::AdmonitionKind::Hint | dom::AdmonitionKind::Tip => "TIP",
+            dom::AdmonitionKind::Attention | dom::AdmonitionKind::Important => "IMPORTANT",
+            dom::AdmonitionKind::Warning => "WARNING",
+            dom::AdmonitionKind::Caution
+            | dom::AdmonitionKind::Danger
+            | dom::AdmonitionKind::Error => "CAUTION",
+        }
+    }
+}
+
+impl<'a> format::BlockFormatter<'a> for MDBlockFormatter {
+    fn append_block(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        block: &'a dom::Block<'a>,
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) {
+        match block {
+            dom::Block::Paragraph { parts } => {
+                markup::append_md_paragraph(appender, parts.iter(), link_provider, current_plugin)
+            }
+            dom::Block::Admonition {
+                kind,
+                title,
+                paragraphs,
+            } => {
+                appender.push_str("> [!");
+                appender.push_str(MDBlockFormatter::callout_type(*kind));
+                appender.push_str("]");
+                if let Some(t) = title {
+                    appender.push_str("\n> <b>");
+                    appender.push_cow_str(self.md_escaper.escape(t));
+                    appender.push_str("</b>");
+                    if !paragraphs.is_empty() {
+                        appender.push_str("\n>");
+                    }
+                }
+                if !paragraphs.is_empty() {
+                    let content = self.render_paragraphs(paragraphs, link_provider, current_plugin);
+                    appender.push_str("\n");
+                    appender.push_owned_string(format::indent(&content, "> ", ">"));
+                }
+            }
+            dom::Block::UnorderedList { items } => self.append_list(
+                appender,
+                items,
+                |_| "* ".to_string(),
+                link_provider,
+                current_plugin,
+            ),
+            dom::Block::OrderedList { start, items } => self.append_list(
+                appender,
+                items,
+                |index| format::ordered_list_marker(*start, index),
+                link_provider,
+                current_plugin,
+            ),
+            dom::Block::DefinitionList { items } => {
+                // MarkDown has no definition lists; use a bullet list with emphasized terms
+                let item_sep = if items.iter().all(|item| item.definition.is_empty()) {
+                    "\n"
+                } else {
+                    "\n\n"
+                };
+                format::append_rendered_list(
+                    appender,
+                    items.iter().map(|item| {
+                        let mut content = CollectorAppender::new();
+                        markup::append_md_paragraph(
+                            &mut content,
+                            item.term.iter(),
+                            link_provider,
+                            current_plugin,
+                        );
+                        let term = content.into_string();
+                        let definition =
+                            self.render_paragraphs(&item.definition, link_provider, current_plugin);
+                        if definition.is_empty() {
+                            format!("<b>{}</b>", term)
+                        } else {
+                            format!("<b>{}</b>\n\n{}", term, definition)
+                        }
+                    }),
+                    item_sep,
+                    |_| "* ".to_string(),
+                );
+            }
+            dom::Block::Heading { level, title } => {
+                appender.push_owned_string("#".repeat((*level).clamp(1, 6)));
+                appender.push_str(" ");
+                appender.push_owned_string(self.md_escaper.escape(title).into_owned());
+            }
+            dom::Block::CodeBlock { language, text } => {
+                let code = format::trim_code(text);
+                let fence = MDBlockFormatter::code_fence(code);
+                appender.push_borrowed_string(&fence);
+                if let Some(l) = language {
+                    appender.push_str(l);
+                }
+                appender.push_str("\n");
+                if !code.is_empty() {
+                    appender.push_str(code);
+                    appender.push_str("\n");
+                }
+                appender.push_owned_string(fence);
+            }
+        }
+    }
+}
+
+pub static MD_BLOCK_FORMATTER: LazyLock<MDBlockFormatter> =
+    LazyLock::new(|| MDBlockFormatter::new().unwrap());
+
+/// Apply the MarkDown block formatter to all given blocks, and concatenate the results.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup inside the blocks.
+pub fn append_md_blocks<'a, I>(
+    appender: &mut dyn Appender<'a>,
+    blocks: I,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) where
+    I: IntoIterator<Item = &'a dom::Block<'a>>,
+{
+    format::append_blocks(
+        appender,
+        blocks,
+        &*MD_BLOCK_FORMATTER,
+        link_provider,
+        "\n\n",
+        current_plugin,
+    );
+}
@@ -0,0 +1,197 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Documentation blocks built from Ansible markup, and their rendering.
+
+mod ansible_doc_text;
+mod dom;
+mod examples;
+mod format;
+mod html;
+mod md;
+mod rst;
+
+pub use ansible_doc_text::{append_ansible_doc_text_blocks, AnsibleDocTextBlockFormatter};
+
+pub use dom::{AdmonitionKind, Block, DefinitionListItem, Paragraph};
+
+pub use examples::{
+    examples_to_blocks, split_examples, validate_examples, ExampleDocument, ExamplesError,
+    EXAMPLES_LANGUAGE,
+};
+
+pub use format::{append_blocks, BlockFormatter};
+
+pub(crate) use format::{indent, indent_first};
+
+pub use html::{append_antsibull_html_blocks, append_plain_html_blocks, HTMLBlockFormatter};
+
+pub use md::{append_md_blocks, MDBlockFormatter};
+
+pub use rst::{append_antsibull_rst_blocks, append_plain_rst_blocks, RSTBlockFormatter};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::{
+        parse_without_sources, AnsibleDocTextOptions, Context, LinkProvider, NoLinkProvider,
+        ParseOptions, PluginIdentifier,
+    };
+    use crate::util::{Appender, CollectorAppender, IntoString};
+    use std::rc::Rc;
+
+    type AppendBlocks<'a> = fn(
+        &mut dyn Appender<'a>,
+        std::slice::Iter<'a, Block<'a>>,
+        &dyn LinkProvider,
+        &Option<Rc<PluginIdentifier>>,
+    );
+
+    fn render<'a>(blocks: &'a [Block<'a>], append: AppendBlocks<'a>) -> String {
+        let mut appender = CollectorAppender::new();
+        append(&mut appender, blocks.iter(), &NoLinkProvider::new(), &None);
+        appender.into_string()
+    }
+
+    #[test]
+    fn test_admonitions() {
+        let context = Context::new();
+        let options = ParseOptions::default();
+        let blocks = vec![
+            Block::Admonition {
+                kind: AdmonitionKind::Note,
+                title: None,
+                paragraphs: vec![
+                    parse_without_sources("Foo B(bar).", &context, &options),
+                    parse_without_sources("Baz.", &context, &options),
+                ],
+            },
+            Block::Admonition {
+                kind: AdmonitionKind::Danger,
+                title: Some("Be <careful>"),
+                paragraphs: vec![parse_without_sources("C(rm -rf)", &context, &options)],
+            },
+        ];
+        assert_eq!(
+            render(&blocks, append_antsibull_rst_blocks),
+            ".. note::\n\n  Foo \\ :strong:`bar`\\ .\n\n  Baz.\n\n.. admonition:: Be \\<careful\\>\n  :class: danger\n\n  \\ :literal:`rm -rf`\\ "
+        );
+        assert_eq!(
+            render(&blocks, append_antsibull_html_blocks),
+            "<div class=\"admonition note\"><p class=\"admonition-title\">Note</p><p>Foo <b>bar</b>.</p><p>Baz.</p></div><div class=\"admonition danger\"><p class=\"admonition-title\">Be &lt;careful&gt;</p><p><code class='docutils literal notranslate'>rm -rf</code></p></div>"
+        );
+        assert_eq!(
+            render(&blocks, append_md_blocks),
+            "> [!NOTE]\n> Foo <b>bar</b>\\.\n>\n> Baz\\.\n\n> [!CAUTION]\n> <b>Be \\<careful\\></b>\n>\n> <code>rm \\-rf</code>"
+        );
+        assert_eq!(
+            render(&blocks, append_ansible_doc_text_blocks),
+            "Note:\n  Foo *bar*.\n\n  Baz.\n\nBe <careful>:\n  `rm -rf'"
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        let context = Context::new();
+        let options = ParseOptions::default();
+        let blocks = vec![
+            Block::UnorderedList {
+                items: vec![
+                    vec![parse_without_sources("I(foo)", &context, &options)],
+                    vec![parse_without_sources("bar", &context, &options)],
+                ],
+            },
+            Block::OrderedList {
+                start: 9,
+                items: vec![
+                    vec![
+                        parse_without_sources("first", &context, &options),
+                        parse_without_sources("second", &context, &options),
+                    ],
+                    vec![parse_without_sources("third", &context, &options)],
+                ],
+            },
+            Block::DefinitionList {
+                items: vec![DefinitionListItem {
+                    term: parse_without_sources("O(foo)", &context, &options),
+                    definition: vec![parse_without_sources("Foo.", &context, &options)],
+                }],
+            },
+        ];
+        assert_eq!(render(&blocks, append_antsibull_rst_blocks), "* \\ :emphasis:`foo`\\ \n* bar\n\n9. first\n\n   second\n\n10. third\n\n\\ :ansopt:`foo`\\ \n  Foo.");
+        assert_eq!(render(&blocks, append_plain_html_blocks), "<ul><li><p><em>foo</em></p></li><li><p>bar</p></li></ul><ol start=\"9\"><li><p>first</p><p>second</p></li><li><p>third</p></li></ol><dl><dt><code><strong>foo</strong></code></dt><dd><p>Foo.</p></dd></dl>");
+        assert_eq!(render(&blocks, append_md_blocks), "* <em>foo</em>\n* bar\n\n9. first\n\n   second\n\n10. third\n\n* <b><code><strong>foo</strong></code></b>\n\n  Foo\\.");
+        assert_eq!(
+            render(&blocks, append_ansible_doc_text_blocks),
+            "* `foo'\n* bar\n\n9. first\n\n   second\n\n10. third\n\n`foo'\n  Foo."
+        );
+
+        let mut appender = CollectorAppender::new();
+        append_blocks(
+            &mut appender,
+            blocks[..1].iter(),
+            &AnsibleDocTextBlockFormatter::with_options(AnsibleDocTextOptions::new().bullet("• ")),
+            &NoLinkProvider::new(),
+            "\n\n",
+            &None,
+        );
+        assert_eq!(appender.into_string(), "• `foo'\n• bar");
+    }
+
+    #[test]
+    fn test_headings() {
+        let blocks = vec![
+            Block::Heading {
+                level: 1,
+                title: "Foo <bar>".to_string(),
+            },
+            Block::Heading {
+                level: 3,
+                title: "*baz*".to_string(),
+            },
+        ];
+        assert_eq!(
+            render(&blocks, append_antsibull_rst_blocks),
+            "Foo \\<bar\\>\n===========\n\n\\*baz\\*\n~~~~~~~"
+        );
+        assert_eq!(
+            render(&blocks, append_plain_html_blocks),
+            "<h1>Foo &lt;bar&gt;</h1><h3>*baz*</h3>"
+        );
+        assert_eq!(
+            render(&blocks, append_md_blocks),
+            "# Foo \\<bar\\>\n\n### \\*baz\\*"
+        );
+        assert_eq!(
+            render(&blocks, append_ansible_doc_text_blocks),
+            "Foo <bar>\n=========\n\n*baz*\n-----"
+        );
+    }
+
+    #[test]
+    fn test_code_blocks() {
+        let blocks = vec![
+            Block::CodeBlock {
+                language: Some("yaml"),
+                text: "- name: Foo\n  debug:\n\n    msg: \"<```>\"\n",
+            },
+            Block::CodeBlock {
+                language: None,
+                text: "",
+            },
+        ];
+        assert_eq!(render(&blocks, append_antsibull_rst_blocks), ".. code-block:: yaml\n\n  - name: Foo\n    debug:\n\n      msg: \"<```>\"\n\n.. code-block:: text");
+        assert_eq!(render(&blocks, append_antsibull_html_blocks), "<pre><code class=\"language-yaml\">- name: Foo\n  debug:\n\n    msg: \"&lt;```&gt;\"</code></pre><pre><code></code></pre>");
+        assert_eq!(
+            render(&blocks, append_md_blocks),
+            "````yaml\n- name: Foo\n  debug:\n\n    msg: \"<```>\"\n````\n\n```\n```"
+        );
+        assert_eq!(
+            render(&blocks, append_ansible_doc_text_blocks),
+            "    - name: Foo\n      debug:\n\n        msg: \"<```>\"\n\n"
+        );
+    }
+}
@@ -0,0 +1,250 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::document::dom;
+use crate::document::format;
+use crate::markup;
+use crate::markup::{LinkProvider, PluginIdentifier, RSTEscaper};
+use crate::util::stringbuilder::{Appender, CollectorAppender, IntoString};
+use std::rc::Rc;
+use std::sync::LazyLock;
+
+pub struct RSTBlockFormatter {
+    formatter: &'static (dyn for<'x> markup::Formatter<'x> + Sync),
+    rst_escaper: RSTEscaper,
+}
+
+impl RSTBlockFormatter {
+    fn new(formatter: &'static (dyn for<'x> markup::Formatter<'x> + Sync)) -> RSTBlockFormatter {
+        RSTBlockFormatter {
+            formatter,
+            rst_escaper: RSTEscaper::new(),
+        }
+    }
+
+    fn append_paragraphs<'a>(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        paragraphs: &'a [dom::Paragraph<'a>],
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) {
+        markup::append_paragraphs(
+            appender,
+            paragraphs.iter().map(|paragraph| paragraph.iter()),
+            self.formatter,
+            link_provider,
+            "",
+            "",
+            "\n\n",
+            "\\ ",
+            current_plugin,
+        );
+    }
+
+    fn render_paragraphs<'a>(
+        &self,
+        paragraphs: &'a [dom::Paragraph<'a>],
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) -> String {
+        let mut content = CollectorAppender::new();
+        self.append_paragraphs(&mut content, paragraphs, link_provider, current_plugin);
+        content.into_string()
+    }
+
+    /// Render paragraphs and append them indented as the content of a directive.
+    fn append_directive_content<'a>(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        paragraphs: &'a [dom::Paragraph<'a>],
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) {
+        if paragraphs.is_empty() {
+            return;
+        }
+        let content = self.render_paragraphs(paragraphs, link_provider, current_plugin);
+        appender.push_str("\n\n");
+        appender.push_owned_string(format::indent(&content, "  ", ""));
+    }
+
+    fn append_list<'a, F>(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        items: &'a [Vec<dom::Paragraph<'a>>],
+        marker: F,
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) where
+        F: Fn(usize) -> String,
+    {
+        let item_sep = if format::is_compact_list(items) {
+            "\n"
+        } else {
+            "\n\n"
+        };
+        format::append_rendered_list(
+            appender,
+            items
+                .iter()
+                .map(|item| self.render_paragraphs(item, link_provider, current_plugin)),
+            item_sep,
+            marker,
+        );
+    }
+}
+
+impl<'a> format::BlockFormatter<'a> for RSTBlockFormatter {
+    fn append_block(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        block: &'a dom::Block<'a>,
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) {
+        match block {
+            dom::Block::Paragraph { parts } => markup::append_paragraph(
+                appender,
+                parts.iter(),
+                self.formatter,
+                link_provider,
+                "",
+                "",
+                "\\ ",
+                current_plugin,
+            ),
+            dom::Block::Admonition {
+                kind,
+                title,
+                paragraphs,
+            } => {
+                match title {
+                    Some(t) => {
+                        appender.push_str(".. admonition:: ");
+                        appender.push_cow_str(self.rst_escaper.escape(t, false, true));
+                        appender.push_str("\n  :class: ");
+                        appender.push_str(kind.name());
+                    }
+                    None => {
+                        appender.push_str(".. ");
+                        appender.push_str(kind.name());
+                        appender.push_str("::");
+                    }
+                }
+                self.append_directive_content(appender, paragraphs, link_provider, current_plugin);
+            }
+            dom::Block::UnorderedList { items } => self.append_list(
+                appender,
+                items,
+                |_| "* ".to_string(),
+                link_provider,
+                current_plugin,
+            ),
+            dom::Block::OrderedList { start, items } => self.append_list(
+                appender,
+                items,
+                |index| format::ordered_list_marker(*start, index),
+                link_provider,
+                current_plugin,
+            ),
+            dom::Block::DefinitionList { items } => {
+                let item_sep = if items.iter().all(|item| item.definition.len() <= 1) {
+                    "\n"
+                } else {
+                    "\n\n"
+                };
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        appender.push_str(item_sep);
+                    }
+                    markup::append_paragraph(
+                        appender,
+                        item.term.iter(),
+                        self.formatter,
+                        link_provider,
+                        "",
+                        "",
+                        "\\ ",
+                        current_plugin,
+                    );
+                    if !item.definition.is_empty() {
+                        let definition =
+                            self.render_paragraphs(&item.definition, link_provider, current_plugin);
+                        appender.push_str("\n");
+                        appender.push_owned_string(format::indent(&definition, "  ", ""));
+                    }
+                }
+            }
+            dom::Block::Heading { level, title } => {
+                let title = self.rst_escaper.escape(title, false, false).into_owned();
+                let underline = format::rst_heading_char(*level)
+                    .to_string()
+                    .repeat(title.chars().count());
+                appender.push_owned_string(title);
+                appender.push_str("\n");
+                appender.push_owned_string(underline);
+            }
+            dom::Block::CodeBlock { language, text } => {
+                appender.push_str(".. code-block:: ");
+                appender.push_str(language.unwrap_or("text"));
+                let code = format::trim_code(text);
+                if !code.is_empty() {
+                    appender.push_str("\n\n");
+                    appender.push_owned_string(format::indent(code, "  ", ""));
+                }
+            }
+        }
+    }
+}
+
+pub static ANTSIBULL_RST_BLOCK_FORMATTER: LazyLock<RSTBlockFormatter> =
+    LazyLock::new(|| RSTBlockFormatter::new(&*markup::ANTSIBULL_RST_FORMATTER));
+
+pub static PLAIN_RST_BLOCK_FORMATTER: LazyLock<RSTBlockFormatter> =
+    LazyLock::new(|| RSTBlockFormatter::new(&*markup::PLAIN_RST_FORMATTER));
+
+/// Apply the Antsibull RST block formatter to all given blocks, and concatenate the results.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup inside the blocks.
+pub fn append_antsibull_rst_blocks<'a, I>(
+    appender: &mut dyn Appender<'a>,
+    blocks: I,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) where
+    I: IntoIterator<Item = &'a dom::Block<'a>>,
+{
+    format::append_blocks(
+        appender,
+        blocks,
+        &*ANTSIBULL_RST_BLOCK_FORMATTER,
+        link_provider,
+        "\n\n",
+        current_plugin,
+    );
+}
+
+/// Apply the plain RST block formatter to all given blocks, and concatenate the results.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup inside the blocks.
+pub fn append_plain_rst_blocks<'a, I>(
+    appender: &mut dyn Appender<'a>,
+    blocks: I,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) where
+    I: IntoIterator<Item = &'a dom::Block<'a>>,
+{
+    format::append_blocks(
+        appender,
+        blocks,
+        &*PLAIN_RST_BLOCK_FORMATTER,
+        link_provider,
+        "\n\n",
+        current_plugin,
+    );
+}
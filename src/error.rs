@@ -0,0 +1,118 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! The error type shared by the constructors and entry points of the crate.
+
+use alloc::string::{String, ToString};
+use core::fmt;
+
+/// An error returned by the crate.
+///
+/// Errors convert into `String` with their message, for code written against the older APIs
+/// that returned `Result<_, String>`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A regular expression could not be compiled.
+    Regex(regex::Error),
+
+    /// A set of markup commands that cannot be turned into a parser, for example because it
+    /// contains unknown commands.
+    Parser(String),
+
+    /// A template with an unknown placeholder or invalid syntax.
+    InvalidTemplate(String),
+
+    /// A name that is not a valid FQCN.
+    InvalidFqcn(String),
+
+    /// A parsing context that is inconsistent, for example because the current plugin does not
+    /// belong to the current collection.
+    InvalidContext(String),
+
+    /// Reading or writing a file failed.
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+
+    /// A YAML document could not be parsed.
+    #[cfg(feature = "yaml")]
+    Yaml(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Regex(error) => write!(f, "Compiling regular expression: {}", error),
+            Error::Parser(message)
+            | Error::InvalidTemplate(message)
+            | Error::InvalidContext(message) => f.write_str(message),
+            Error::InvalidFqcn(name) => write!(f, "{:?} is not a valid FQCN", name),
+            #[cfg(feature = "std")]
+            Error::Io(error) => error.fmt(f),
+            #[cfg(feature = "yaml")]
+            Error::Yaml(message) => f.write_str(message),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Regex(error) => Some(error),
+            Error::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<regex::Error> for Error {
+    fn from(error: regex::Error) -> Self {
+        Error::Regex(error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+#[cfg(feature = "yaml")]
+impl From<saphyr::ScanError> for Error {
+    fn from(error: saphyr::ScanError) -> Self {
+        Error::Yaml(error.to_string())
+    }
+}
+
+impl From<Error> for String {
+    fn from(error: Error) -> Self {
+        error.to_string()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn test_error() {
+        let error = Error::InvalidFqcn("foo".to_string());
+        assert_eq!(String::from(error), "\"foo\" is not a valid FQCN");
+
+        let pattern = "(".to_string();
+        let error = Error::from(regex::Regex::new(&pattern).unwrap_err());
+        assert!(error
+            .to_string()
+            .starts_with("Compiling regular expression: "));
+        assert!(error.source().is_some());
+
+        let error = Error::from(std::io::Error::new(std::io::ErrorKind::NotFound, "gone"));
+        assert_eq!(error.to_string(), "gone");
+    }
+}
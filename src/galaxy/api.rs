@@ -0,0 +1,278 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::build::CollectionVersion;
+use crate::util::version::{SpecifierSet, Version};
+use crate::util::yaml;
+use saphyr::Yaml;
+use std::collections::BTreeMap;
+
+/// Information on a collection version published on Galaxy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionInfo {
+    /// The collection's name, like `community.general`.
+    pub collection: String,
+
+    /// The version.
+    pub version: Version,
+
+    /// The URL of the artifact.
+    pub download_url: String,
+
+    /// The artifact's file name, like `community-general-9.1.0.tar.gz`.
+    pub filename: String,
+
+    /// The SHA-256 checksum of the artifact, as lowercase hex digits.
+    pub sha256: String,
+
+    /// The collections this version depends on, with their version ranges.
+    pub dependencies: BTreeMap<String, SpecifierSet>,
+
+    /// The ansible-core versions this version supports, if known.
+    pub requires_ansible: Option<SpecifierSet>,
+}
+
+impl VersionInfo {
+    /// The information needed by the dependency resolver.
+    pub fn to_collection_version(&self) -> CollectionVersion {
+        CollectionVersion {
+            version: self.version.clone(),
+            dependencies: self.dependencies.clone(),
+            requires_ansible: self.requires_ansible.clone(),
+        }
+    }
+}
+
+/// Split a collection name into namespace and name.
+pub(crate) fn split_collection(collection: &str) -> Result<(&str, &str), String> {
+    collection
+        .split_once('.')
+        .filter(|(namespace, name)| !namespace.is_empty() && !name.contains('.'))
+        .ok_or_else(|| format!("{:?} is not a valid collection name", collection))
+}
+
+/// The URL of the first page of the version list of a collection.
+pub(crate) fn versions_url(server: &str, collection: &str) -> Result<String, String> {
+    let (namespace, name) = split_collection(collection)?;
+    Ok(format!(
+        "{}/api/v3/plugin/ansible/content/published/collections/index/{}/{}/versions/?limit=100",
+        server.trim_end_matches('/'),
+        namespace,
+        name
+    ))
+}
+
+/// The URL of the information on a collection version.
+pub(crate) fn version_url(server: &str, collection: &str, version: &str) -> Result<String, String> {
+    let (namespace, name) = split_collection(collection)?;
+    Ok(format!(
+        "{}/api/v3/plugin/ansible/content/published/collections/index/{}/{}/versions/{}/",
+        server.trim_end_matches('/'),
+        namespace,
+        name,
+        version
+    ))
+}
+
+fn parse_json(content: &str) -> Result<Yaml, String> {
+    let mut documents =
+        Yaml::load_from_str(content).map_err(|error| format!("invalid JSON: {}", error))?;
+    if documents.is_empty() {
+        return Err("empty response".to_string());
+    }
+    Ok(documents.swap_remove(0))
+}
+
+/// Resolve a link from a response, which might be relative to the server.
+fn resolve_link(server: &str, link: &str) -> String {
+    if link.starts_with('/') {
+        format!("{}{}", server.trim_end_matches('/'), link)
+    } else {
+        link.to_string()
+    }
+}
+
+/// Parse a page of a version list. Returns the versions and the URL of the next page.
+pub(crate) fn parse_versions_page(
+    server: &str,
+    content: &str,
+) -> Result<(Vec<Version>, Option<String>), String> {
+    let document = parse_json(content)?;
+    let hash = yaml::as_hash(&document, "response")?;
+    let data = match yaml::get(hash, "data") {
+        Some(Yaml::Array(data)) => data,
+        _ => return Err("response.data: expected a list".to_string()),
+    };
+    let versions = data
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let path = format!("response.data[{}]", index);
+            let entry = yaml::as_hash(entry, &path)?;
+            let version = yaml::get_string(entry, "version", &path)?
+                .ok_or_else(|| format!("{}.version: missing", path))?;
+            Version::parse(&version).map_err(|error| format!("{}.version: {}", path, error))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let next = match yaml::get(hash, "links") {
+        Some(links) => yaml::get_string(
+            yaml::as_hash(links, "response.links")?,
+            "next",
+            "response.links",
+        )?,
+        None => None,
+    };
+    Ok((versions, next.map(|next| resolve_link(server, &next))))
+}
+
+/// Parse the information on a collection version.
+pub(crate) fn parse_version_info(
+    server: &str,
+    collection: &str,
+    content: &str,
+) -> Result<VersionInfo, String> {
+    let document = parse_json(content)?;
+    let hash = yaml::as_hash(&document, "response")?;
+    let required = |hash, key: &str, path: &str| -> Result<String, String> {
+        yaml::get_string(hash, key, path)?.ok_or_else(|| format!("{}.{}: missing", path, key))
+    };
+    let version = required(hash, "version", "response")?;
+    let version =
+        Version::parse(&version).map_err(|error| format!("response.version: {}", error))?;
+    let artifact = yaml::as_hash(
+        yaml::get(hash, "artifact").unwrap_or(&Yaml::Null),
+        "response.artifact",
+    )?;
+    let mut dependencies = BTreeMap::new();
+    if let Some(metadata) = yaml::get(hash, "metadata") {
+        let metadata = yaml::as_hash(metadata, "response.metadata")?;
+        if let Some(Yaml::Hash(deps)) = yaml::get(metadata, "dependencies") {
+            for (name, range) in deps {
+                let (Some(name), Some(range)) =
+                    (yaml::scalar_to_string(name), yaml::scalar_to_string(range))
+                else {
+                    return Err("response.metadata.dependencies: expected strings".to_string());
+                };
+                let specifiers = parse_galaxy_range(&range).map_err(|error| {
+                    format!("response.metadata.dependencies.{}: {}", name, error)
+                })?;
+                dependencies.insert(name, specifiers);
+            }
+        }
+    }
+    let requires_ansible = yaml::get_string(hash, "requires_ansible", "response")?
+        .map(|spec| SpecifierSet::parse(&spec))
+        .transpose()
+        .map_err(|error| format!("response.requires_ansible: {}", error))?;
+    Ok(VersionInfo {
+        collection: collection.to_string(),
+        version,
+        download_url: resolve_link(server, &required(hash, "download_url", "response")?),
+        filename: required(artifact, "filename", "response.artifact")?,
+        sha256: required(artifact, "sha256", "response.artifact")?.to_lowercase(),
+        dependencies,
+        requires_ansible,
+    })
+}
+
+/// Parse a version range as used in collection dependencies.
+///
+/// Galaxy's ranges are like PEP 440 specifier sets, but additionally allow `*` for any version
+/// and a plain version for an exact match.
+pub(crate) fn parse_galaxy_range(range: &str) -> Result<SpecifierSet, String> {
+    let range = range.trim();
+    if range == "*" {
+        return Ok(SpecifierSet::default());
+    }
+    if range.starts_with(|c: char| c.is_ascii_digit()) {
+        return SpecifierSet::parse(&format!("=={}", range));
+    }
+    SpecifierSet::parse(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVER: &str = "https://galaxy.ansible.com/";
+
+    #[test]
+    fn test_urls() {
+        assert_eq!(
+            versions_url(SERVER, "community.general").unwrap(),
+            "https://galaxy.ansible.com/api/v3/plugin/ansible/content/published/collections/index/community/general/versions/?limit=100"
+        );
+        assert_eq!(
+            version_url(SERVER, "foo.bar", "1.0.0").unwrap(),
+            "https://galaxy.ansible.com/api/v3/plugin/ansible/content/published/collections/index/foo/bar/versions/1.0.0/"
+        );
+        assert!(versions_url(SERVER, "foo").is_err());
+        assert!(versions_url(SERVER, "foo.bar.baz").is_err());
+    }
+
+    #[test]
+    fn test_parse_versions_page() {
+        let (versions, next) = parse_versions_page(
+            SERVER,
+            r#"{"meta": {"count": 3}, "links": {"next": "/api/v3/foo/?offset=2"}, "data": [{"version": "1.0.0"}, {"version": "2.0.0-beta1"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            versions,
+            vec![
+                Version::parse("1.0.0").unwrap(),
+                Version::parse("2.0.0b1").unwrap()
+            ]
+        );
+        assert_eq!(
+            next,
+            Some("https://galaxy.ansible.com/api/v3/foo/?offset=2".to_string())
+        );
+        let (_, next) =
+            parse_versions_page(SERVER, r#"{"links": {"next": null}, "data": []}"#).unwrap();
+        assert_eq!(next, None);
+        assert_eq!(
+            parse_versions_page(SERVER, r#"{"data": [{"version": "foo"}]}"#).unwrap_err(),
+            "response.data[0].version: Invalid version \"foo\""
+        );
+    }
+
+    #[test]
+    fn test_parse_version_info() {
+        let info = parse_version_info(
+            SERVER,
+            "foo.bar",
+            r#"{
+                "version": "1.2.0",
+                "download_url": "https://example.com/foo-bar-1.2.0.tar.gz",
+                "requires_ansible": ">=2.15.0",
+                "artifact": {"filename": "foo-bar-1.2.0.tar.gz", "sha256": "ABCDEF", "size": 123},
+                "metadata": {"dependencies": {"foo.baz": ">=1.0.0", "foo.bam": "*", "foo.bat": "2.0.0"}}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(info.filename, "foo-bar-1.2.0.tar.gz");
+        assert_eq!(info.sha256, "abcdef");
+        assert_eq!(
+            info.dependencies
+                .iter()
+                .map(|(name, spec)| format!("{} {}", name, spec))
+                .collect::<Vec<_>>(),
+            vec!["foo.bam ", "foo.bat ==2.0.0", "foo.baz >=1.0.0"]
+        );
+        let version = info.to_collection_version();
+        assert_eq!(version.version, Version::parse("1.2").unwrap());
+        assert_eq!(
+            version.requires_ansible.map(|spec| spec.to_string()),
+            Some(">=2.15.0".to_string())
+        );
+        assert_eq!(
+            parse_version_info(SERVER, "foo.bar", r#"{"version": "1.0.0", "artifact": {}}"#)
+                .unwrap_err(),
+            "response.download_url: missing"
+        );
+    }
+}
@@ -0,0 +1,313 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::galaxy::api::{
+    parse_version_info, parse_versions_page, version_url, versions_url, VersionInfo,
+};
+use crate::util::concurrency::map_concurrent;
+use crate::util::verify::sha256_hex;
+use crate::util::version::Version;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// The default Galaxy server.
+pub const DEFAULT_GALAXY_SERVER: &str = "https://galaxy.ansible.com";
+
+/// An error that occured while talking to Galaxy.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GalaxyError {
+    /// The request could not be sent, or the response could not be received.
+    Network { url: String, message: String },
+
+    /// The server responded with an error status.
+    Status { url: String, status: u16 },
+
+    /// The response could not be interpreted.
+    InvalidResponse { url: String, message: String },
+
+    /// A downloaded artifact does not have the expected checksum.
+    ChecksumMismatch {
+        url: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// The downloaded artifact could not be written.
+    Io { path: String, message: String },
+
+    /// The request was invalid, for example because of an invalid collection name.
+    InvalidRequest { message: String },
+}
+
+impl fmt::Display for GalaxyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GalaxyError::Network { url, message } => {
+                write!(f, "Error while requesting {}: {}", url, message)
+            }
+            GalaxyError::Status { url, status } => {
+                write!(f, "Request to {} failed with status {}", url, status)
+            }
+            GalaxyError::InvalidResponse { url, message } => {
+                write!(f, "Invalid response from {}: {}", url, message)
+            }
+            GalaxyError::ChecksumMismatch {
+                url,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Checksum mismatch for {}: expected SHA-256 {}, got {}",
+                url, expected, actual
+            ),
+            GalaxyError::Io { path, message } => write!(f, "Cannot write {}: {}", path, message),
+            GalaxyError::InvalidRequest { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for GalaxyError {}
+
+impl GalaxyError {
+    /// Whether retrying the request might help.
+    fn is_transient(&self) -> bool {
+        match self {
+            GalaxyError::Network { .. } => true,
+            GalaxyError::Status { status, .. } => *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
+}
+
+/// How often and how fast failed requests are retried.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// The maximal number of attempts, including the first one.
+    pub max_attempts: u32,
+
+    /// The delay before the first retry. The delay doubles with every retry.
+    pub initial_delay: Duration,
+
+    /// The maximal delay between two attempts.
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// The delay before the given retry, where `1` is the first retry.
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.initial_delay
+            .saturating_mul(1 << retry.saturating_sub(1).min(16))
+            .min(self.max_delay)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 4,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+        }
+    }
+}
+
+/// An async client for the Galaxy v3 API.
+///
+/// The client can be cloned cheaply; clones share the limit on concurrent requests.
+#[derive(Debug, Clone)]
+pub struct GalaxyClient {
+    client: reqwest::Client,
+    server: String,
+    retry_policy: RetryPolicy,
+    semaphore: Arc<Semaphore>,
+}
+
+impl GalaxyClient {
+    /// Create a client for the Galaxy server `server`, like `DEFAULT_GALAXY_SERVER`.
+    ///
+    /// At most four requests are made at the same time.
+    pub fn new(server: &str) -> GalaxyClient {
+        GalaxyClient {
+            client: reqwest::Client::new(),
+            server: server.trim_end_matches('/').to_string(),
+            retry_policy: RetryPolicy::default(),
+            semaphore: Arc::new(Semaphore::new(4)),
+        }
+    }
+
+    /// Set the maximal number of concurrent requests.
+    pub fn with_concurrency(mut self, concurrency: usize) -> GalaxyClient {
+        self.semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+        self
+    }
+
+    /// Set the retry policy.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> GalaxyClient {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    async fn get_once(&self, url: &str) -> Result<Vec<u8>, GalaxyError> {
+        let network_error = |error: reqwest::Error| GalaxyError::Network {
+            url: url.to_string(),
+            message: error.to_string(),
+        };
+        let _permit = self
+            .semaphore
+            .acquire()
+            .await
+            .map_err(|error| GalaxyError::Network {
+                url: url.to_string(),
+                message: error.to_string(),
+            })?;
+        let response = self.client.get(url).send().await.map_err(network_error)?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(GalaxyError::Status {
+                url: url.to_string(),
+                status: status.as_u16(),
+            });
+        }
+        Ok(response.bytes().await.map_err(network_error)?.to_vec())
+    }
+
+    /// Fetch `url`, retrying on network errors, rate limiting, and server errors.
+    async fn get(&self, url: &str) -> Result<Vec<u8>, GalaxyError> {
+        let mut attempt = 1;
+        loop {
+            match self.get_once(url).await {
+                Err(error) if error.is_transient() && attempt < self.retry_policy.max_attempts => {
+                    tokio::time::sleep(self.retry_policy.delay(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn get_text(&self, url: &str) -> Result<String, GalaxyError> {
+        String::from_utf8(self.get(url).await?).map_err(|error| GalaxyError::InvalidResponse {
+            url: url.to_string(),
+            message: error.to_string(),
+        })
+    }
+
+    /// List all versions of the collection `collection`, like `community.general`.
+    ///
+    /// The versions are sorted.
+    pub async fn versions(&self, collection: &str) -> Result<Vec<Version>, GalaxyError> {
+        let mut url = Some(
+            versions_url(&self.server, collection)
+                .map_err(|message| GalaxyError::InvalidRequest { message })?,
+        );
+        let mut result = Vec::new();
+        while let Some(current) = url {
+            let content = self.get_text(&current).await?;
+            let (versions, next) =
+                parse_versions_page(&self.server, &content).map_err(|message| {
+                    GalaxyError::InvalidResponse {
+                        url: current.clone(),
+                        message,
+                    }
+                })?;
+            result.extend(versions);
+            url = next;
+        }
+        result.sort();
+        Ok(result)
+    }
+
+    /// Retrieve information on a version of the collection `collection`.
+    pub async fn version_info(
+        &self,
+        collection: &str,
+        version: &Version,
+    ) -> Result<VersionInfo, GalaxyError> {
+        let url = version_url(&self.server, collection, &version.to_string())
+            .map_err(|message| GalaxyError::InvalidRequest { message })?;
+        let content = self.get_text(&url).await?;
+        parse_version_info(&self.server, collection, &content)
+            .map_err(|message| GalaxyError::InvalidResponse { url, message })
+    }
+
+    /// Download the artifact of a collection version into the directory `destination`.
+    ///
+    /// The artifact's checksum is verified before it is written. Returns the path of the artifact.
+    pub async fn download(
+        &self,
+        info: &VersionInfo,
+        destination: &Path,
+    ) -> Result<PathBuf, GalaxyError> {
+        if info.filename.contains(['/', '\\']) || info.filename.starts_with('.') {
+            return Err(GalaxyError::InvalidResponse {
+                url: info.download_url.clone(),
+                message: format!("invalid artifact file name {:?}", info.filename),
+            });
+        }
+        let data = self.get(&info.download_url).await?;
+        let actual = sha256_hex(&data);
+        if actual != info.sha256 {
+            return Err(GalaxyError::ChecksumMismatch {
+                url: info.download_url.clone(),
+                expected: info.sha256.clone(),
+                actual,
+            });
+        }
+        let path = destination.join(&info.filename);
+        tokio::fs::write(&path, data)
+            .await
+            .map_err(|error| GalaxyError::Io {
+                path: path.display().to_string(),
+                message: error.to_string(),
+            })?;
+        Ok(path)
+    }
+
+    /// Download the artifacts of several collection versions into the directory `destination`,
+    /// like [`Self::download`].
+    ///
+    /// The downloads run concurrently, limited by the client's concurrency. The results are in
+    /// the order of `infos`.
+    pub async fn download_all(
+        &self,
+        infos: Vec<VersionInfo>,
+        destination: &Path,
+    ) -> Vec<Result<PathBuf, GalaxyError>> {
+        let count = infos.len();
+        map_concurrent(infos, count, |info| {
+            let client = self.clone();
+            let destination = destination.to_path_buf();
+            async move { client.download(&info, &destination).await }
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_policy() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay(1), Duration::from_millis(500));
+        assert_eq!(policy.delay(3), Duration::from_secs(2));
+        assert_eq!(policy.delay(10), Duration::from_secs(10));
+        assert!(GalaxyError::Status {
+            url: String::new(),
+            status: 503
+        }
+        .is_transient());
+        assert!(!GalaxyError::Status {
+            url: String::new(),
+            status: 404
+        }
+        .is_transient());
+    }
+}
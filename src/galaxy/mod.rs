@@ -0,0 +1,14 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Access to the Galaxy API. Requires the `net` feature.
+
+mod api;
+mod client;
+
+pub use api::VersionInfo;
+
+pub use client::{GalaxyClient, GalaxyError, RetryPolicy, DEFAULT_GALAXY_SERVER};
@@ -5,6 +5,39 @@ SPDX-License-Identifier: GPL-3.0-or-later
 */
 
 //! Antsibull Rust library and executables.
+//!
+//! The `full` feature, which is enabled by default, includes all formatters and the modules
+//! that process documentation and collections. To embed only markup parsing and one output
+//! format, disable the default features and enable one of `ansible-doc`, `bbcode`, `html`,
+//! `md`, or `rst`.
+//!
+//! Without the `std` feature, the crate is `no_std` and only provides markup parsing, the DOM,
+//! and the escapers. They only need `core` and `alloc`.
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "full")]
+pub mod build;
+#[cfg(feature = "full")]
+pub mod changelog;
+#[cfg(feature = "full")]
+pub mod collection;
+#[cfg(feature = "full")]
+pub mod docgen;
+#[cfg(feature = "full")]
+pub mod document;
+pub mod error;
+#[cfg(feature = "net")]
+pub mod galaxy;
+#[cfg(feature = "lsp")]
+pub mod lsp;
 pub mod markup;
+#[cfg(feature = "full")]
+pub mod plugin_docs;
+#[cfg(feature = "server")]
+pub mod preview;
 pub mod util;
+
+pub use error::Error;
@@ -0,0 +1,300 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::{
+    locate_edit, parse_paragraphs, parse_paragraphs_with_metadata, Context, Linter, NoLinkProvider,
+    ParagraphMetadata, ParseOptions, Part, Renderer, TextEdit,
+};
+use crate::plugin_docs::find_string_assignment;
+use crate::util::yaml::collect_markup_fields;
+use crate::util::yamledit::{find_scalar, parse_path};
+use saphyr::Yaml;
+
+/// The documentation blocks of Python files that contain markup.
+const PYTHON_BLOCKS: [&str; 2] = ["DOCUMENTATION", "RETURN"];
+
+/// A paragraph of markup in a documentation file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkupParagraph {
+    /// The YAML path of the paragraph, like `DOCUMENTATION.options.state.description[0]`.
+    pub path: String,
+
+    /// The key of the field the paragraph belongs to, like `description`.
+    pub field: String,
+
+    /// The markup.
+    pub text: String,
+
+    /// The byte offset of the start of the YAML scalar in the file.
+    pub start: usize,
+
+    /// The byte offset after the end of the YAML scalar in the file.
+    pub end: usize,
+}
+
+impl MarkupParagraph {
+    /// Render the paragraph as MarkDown.
+    pub fn to_markdown(&self) -> String {
+        let context = Context::new();
+        let paragraphs = parse_paragraphs(
+            [self.text.as_str()].into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        Renderer::md(&NoLinkProvider::new()).render(&paragraphs)
+    }
+}
+
+/// The severity of a [`Diagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Invalid YAML or markup.
+    Error,
+
+    /// A lint finding.
+    Warning,
+}
+
+/// A problem in a documentation file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The byte offset of the start of the problem.
+    pub start: usize,
+
+    /// The byte offset after the end of the problem.
+    pub end: usize,
+
+    /// The severity.
+    pub severity: Severity,
+
+    /// The code of the lint rule, if the problem is a lint finding.
+    pub code: Option<&'static str>,
+
+    /// A human readable description of the problem.
+    pub message: String,
+
+    /// A replacement for the text between `start` and `end` that fixes the problem.
+    pub fix: Option<String>,
+}
+
+/// The markup of a documentation file, and the problems found in it.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DocumentAnalysis {
+    /// The markup fields of the file. Every field is a list of paragraphs.
+    pub fields: Vec<Vec<MarkupParagraph>>,
+
+    /// The problems found in the file, in the order of the fields.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl DocumentAnalysis {
+    /// Analyze the file `name` with content `source`.
+    ///
+    /// For Python files, the `DOCUMENTATION` and `RETURN` blocks are analyzed. Other files are
+    /// treated as YAML, like sidecar documentation and role argument specs. Every string or list
+    /// of strings stored under a markup key like `description` is parsed and linted with
+    /// `linter`.
+    pub fn new(name: &str, source: &str, linter: &Linter) -> DocumentAnalysis {
+        let mut result = DocumentAnalysis::default();
+        let mut blocks = Vec::new();
+        if name.ends_with(".py") {
+            for block in PYTHON_BLOCKS {
+                match find_string_assignment(source, block) {
+                    Ok(Some(literal)) => blocks.push((
+                        Some(block),
+                        literal.start,
+                        &source[literal.start..literal.end],
+                        literal.value(source),
+                    )),
+                    Ok(None) => {}
+                    Err(message) => result.error(0, 0, message),
+                }
+            }
+        } else {
+            blocks.push((None, 0, source, source.to_string()));
+        }
+
+        for (block, offset, content, value) in blocks {
+            let document = match Yaml::load_from_str(&value) {
+                Ok(mut documents) if !documents.is_empty() => documents.swap_remove(0),
+                Ok(_) => continue,
+                Err(error) => {
+                    let message = format!(
+                        "{}syntax error in line {}, column {}: {}",
+                        block
+                            .map(|block| format!("{}: ", block))
+                            .unwrap_or_default(),
+                        error.marker().line(),
+                        error.marker().col() + 1,
+                        error.info()
+                    );
+                    result.error(offset, offset, message);
+                    continue;
+                }
+            };
+            let mut fields = Vec::new();
+            collect_markup_fields(&document, "", &mut fields);
+            for field in fields {
+                let paragraphs: Vec<MarkupParagraph> = field
+                    .into_iter()
+                    .map(|(path, field, text)| {
+                        let (start, end) = find_scalar(content, &parse_path(&path))
+                            .map(|location| (location.start, location.end))
+                            .unwrap_or_default();
+                        MarkupParagraph {
+                            path: match block {
+                                Some(block) => format!("{}.{}", block, path),
+                                None => path,
+                            },
+                            field,
+                            text,
+                            start: offset + start,
+                            end: offset + end,
+                        }
+                    })
+                    .collect();
+                result.check_field(source, &paragraphs, linter);
+                result.fields.push(paragraphs);
+            }
+        }
+        result
+    }
+
+    fn error(&mut self, start: usize, end: usize, message: String) {
+        self.diagnostics.push(Diagnostic {
+            start,
+            end,
+            severity: Severity::Error,
+            code: None,
+            message,
+            fix: None,
+        });
+    }
+
+    fn check_field(&mut self, source: &str, field: &[MarkupParagraph], linter: &Linter) {
+        let context = Context::new();
+        let paragraphs = parse_paragraphs_with_metadata(
+            field.iter().map(|paragraph| {
+                (
+                    paragraph.text.as_str(),
+                    ParagraphMetadata::new()
+                        .path(&paragraph.path)
+                        .field(&paragraph.field),
+                )
+            }),
+            &context,
+            &ParseOptions::default(),
+        );
+        for (paragraph, annotated) in field.iter().zip(&paragraphs) {
+            let scalar = &source[paragraph.start..paragraph.end];
+            for part in &annotated.parts {
+                let Part::Error { message } = &part.part else {
+                    continue;
+                };
+                // Point at the erroneous markup if it can be found in the scalar unambiguously.
+                let mut found = scalar.match_indices(part.source);
+                let (start, end) = match (found.next(), found.next()) {
+                    (Some((index, _)), None) if !part.source.is_empty() => (
+                        paragraph.start + index,
+                        paragraph.start + index + part.source.len(),
+                    ),
+                    _ => (paragraph.start, paragraph.end),
+                };
+                self.error(start, end, message.to_string());
+            }
+        }
+
+        let parts: Vec<Vec<Part<'_>>> = paragraphs
+            .iter()
+            .map(|annotated| annotated.parts.iter().map(|ps| ps.part.clone()).collect())
+            .collect();
+        for finding in linter.lint(&parts) {
+            let paragraph = &field[finding.paragraph];
+            let located = TextEdit::from_finding(&finding, &parts).and_then(|edit| {
+                locate_edit(&source[paragraph.start..paragraph.end], &edit)
+                    .ok()
+                    .map(|(start, end)| (paragraph.start + start, paragraph.start + end, edit))
+            });
+            let (start, end, fix) = match located {
+                Some((start, end, edit)) => (start, end, Some(edit.new)),
+                None => (paragraph.start, paragraph.end, None),
+            };
+            self.diagnostics.push(Diagnostic {
+                start,
+                end,
+                severity: Severity::Warning,
+                code: Some(finding.code),
+                message: finding.message,
+                fix,
+            });
+        }
+    }
+
+    /// Return the paragraph at the byte offset `offset`.
+    pub fn paragraph_at(&self, offset: usize) -> Option<&MarkupParagraph> {
+        self.fields
+            .iter()
+            .flatten()
+            .find(|paragraph| paragraph.start <= offset && offset <= paragraph.end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::TerminologyRule;
+
+    #[test]
+    fn test_document_analysis() {
+        let source = concat!(
+            "#!/usr/bin/python\n",
+            "DOCUMENTATION = r'''\n",
+            "module: foo\n",
+            "description:\n",
+            "  - Add hosts to the whitelist.\n",
+            "  - Broken C(foo.\n",
+            "options:\n",
+            "  name:\n",
+            "    description: The I(name).\n",
+            "'''\n",
+        );
+        let linter = Linter::new().add_pass(TerminologyRule::inclusive());
+        let analysis = DocumentAnalysis::new("plugins/modules/foo.py", source, &linter);
+        assert_eq!(
+            analysis
+                .fields
+                .iter()
+                .map(|field| field.iter().map(|p| p.path.as_str()).collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            vec![
+                vec![
+                    "DOCUMENTATION.description[0]",
+                    "DOCUMENTATION.description[1]"
+                ],
+                vec!["DOCUMENTATION.options.name.description"],
+            ]
+        );
+        let paragraph = analysis
+            .paragraph_at(source.find("I(name)").unwrap())
+            .unwrap();
+        assert_eq!(&source[paragraph.start..paragraph.end], "The I(name).");
+        assert_eq!(paragraph.to_markdown(), "The <em>name</em>\\.");
+
+        assert_eq!(analysis.diagnostics.len(), 2);
+        let error = &analysis.diagnostics[0];
+        assert_eq!(error.severity, Severity::Error);
+        assert_eq!(&source[error.start..error.end], "C(foo.");
+        assert!(error.message.contains("DOCUMENTATION.description[1]"));
+        let warning = &analysis.diagnostics[1];
+        assert_eq!(warning.code, Some("preferred-term"));
+        assert_eq!(&source[warning.start..warning.end], "whitelist");
+        assert_eq!(warning.fix.as_deref(), Some("allowlist"));
+
+        let analysis = DocumentAnalysis::new("meta/argument_specs.yml", "a: [\n", &linter);
+        assert_eq!(analysis.diagnostics.len(), 1);
+        assert_eq!(analysis.diagnostics[0].severity, Severity::Error);
+    }
+}
@@ -0,0 +1,17 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! A language server for markup in plugin and role documentation, for editor integration.
+
+mod analysis;
+mod position;
+mod server;
+
+pub use analysis::{Diagnostic, DocumentAnalysis, MarkupParagraph, Severity};
+
+pub use position::{LineIndex, Position};
+
+pub use server::LanguageServer;
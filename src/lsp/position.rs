@@ -0,0 +1,77 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+/// A position in a text document, as used by the Language Server Protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    /// The line, starting with 0.
+    pub line: usize,
+
+    /// The offset in the line in UTF-16 code units, starting with 0.
+    pub character: usize,
+}
+
+/// Converts between byte offsets and [`Position`]s in a text.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> LineIndex<'a> {
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(index, _)| index + 1));
+        LineIndex { text, line_starts }
+    }
+
+    /// Return the position of the byte offset `offset`.
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.text.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line];
+        Position {
+            line,
+            character: self.text[start..offset].chars().map(char::len_utf16).sum(),
+        }
+    }
+
+    /// Return the byte offset of `position`.
+    ///
+    /// Positions after the end of a line are moved to the end of the line.
+    pub fn offset(&self, position: Position) -> usize {
+        let Some(&start) = self.line_starts.get(position.line) else {
+            return self.text.len();
+        };
+        let line = self.text[start..].split('\n').next().unwrap_or_default();
+        let mut character = 0;
+        for (index, c) in line.char_indices() {
+            if character >= position.character {
+                return start + index;
+            }
+            character += c.len_utf16();
+        }
+        start + line.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_index() {
+        let index = LineIndex::new("ab\nc\u{1f600}d\n");
+        let position = |line, character| Position { line, character };
+        assert_eq!(index.position(0), position(0, 0));
+        assert_eq!(index.position(3), position(1, 0));
+        assert_eq!(index.position(8), position(1, 3));
+        assert_eq!(index.position(10), position(2, 0));
+        assert_eq!(index.offset(position(1, 3)), 8);
+        assert_eq!(index.offset(position(0, 10)), 2);
+        assert_eq!(index.offset(position(5, 0)), 10);
+    }
+}
@@ -0,0 +1,421 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::lsp::analysis::{DocumentAnalysis, Severity};
+use crate::lsp::position::{LineIndex, Position};
+use crate::markup::Linter;
+use crate::util::json::push_json_string;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_PARAMS: i64 = -32602;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Look up a value by a path of keys in nested mappings.
+fn lookup<'v>(value: &'v Value, keys: &[&str]) -> Option<&'v Value> {
+    keys.iter()
+        .try_fold(value, |value, key| value.as_object()?.get(*key))
+}
+
+fn lookup_str<'v>(value: &'v Value, keys: &[&str]) -> Option<&'v str> {
+    lookup(value, keys).and_then(Value::as_str)
+}
+
+fn lookup_position(value: &Value, keys: &[&str]) -> Option<Position> {
+    let position = lookup(value, keys)?;
+    let number = |key| {
+        lookup(position, &[key])
+            .and_then(Value::as_u64)
+            .and_then(|n| usize::try_from(n).ok())
+    };
+    Some(Position {
+        line: number("line")?,
+        character: number("character")?,
+    })
+}
+
+/// Serialize a request ID, which is a number or a string.
+fn id_to_json(id: &Value) -> String {
+    match id {
+        Value::Number(id) => id.to_string(),
+        Value::String(id) => {
+            let mut result = String::new();
+            push_json_string(&mut result, id);
+            result
+        }
+        _ => "null".to_string(),
+    }
+}
+
+fn response(id: &str, result: &str) -> String {
+    format!(
+        "{{\"jsonrpc\": \"2.0\", \"id\": {}, \"result\": {}}}",
+        id, result
+    )
+}
+
+fn error_response(id: &str, code: i64, message: &str) -> String {
+    let mut result = format!(
+        "{{\"jsonrpc\": \"2.0\", \"id\": {}, \"error\": {{\"code\": {}, \"message\": ",
+        id, code
+    );
+    push_json_string(&mut result, message);
+    result.push_str("}}");
+    result
+}
+
+fn push_position(result: &mut String, position: Position) {
+    result.push_str(&format!(
+        "{{\"line\": {}, \"character\": {}}}",
+        position.line, position.character
+    ));
+}
+
+fn push_range(result: &mut String, index: &LineIndex<'_>, start: usize, end: usize) {
+    result.push_str("{\"start\": ");
+    push_position(result, index.position(start));
+    result.push_str(", \"end\": ");
+    push_position(result, index.position(end));
+    result.push('}');
+}
+
+struct OpenDocument {
+    text: String,
+    analysis: DocumentAnalysis,
+}
+
+/// A language server for markup in documentation files.
+///
+/// It publishes diagnostics for invalid markup and lint findings, shows the paragraph under
+/// the cursor rendered as MarkDown on hover, and offers quick fixes for lint findings that have
+/// a suggested replacement. Documents are synchronized in full.
+pub struct LanguageServer {
+    linter: Linter,
+    documents: HashMap<String, OpenDocument>,
+    exited: bool,
+}
+
+impl LanguageServer {
+    /// Create a server that lints with `linter`.
+    pub fn new(linter: Linter) -> LanguageServer {
+        LanguageServer {
+            linter,
+            documents: HashMap::new(),
+            exited: false,
+        }
+    }
+
+    /// Whether the client asked the server to exit.
+    pub fn exited(&self) -> bool {
+        self.exited
+    }
+
+    /// Handle one JSON-RPC message, and return the messages to send to the client.
+    pub fn handle_message(&mut self, message: &str) -> Vec<String> {
+        let message: Value = match serde_json::from_str(message) {
+            Ok(message) => message,
+            Err(error) => return vec![error_response("null", PARSE_ERROR, &error.to_string())],
+        };
+        let Some(object) = message.as_object() else {
+            return vec![error_response("null", PARSE_ERROR, "Expected an object")];
+        };
+        let id = object.get("id").map(id_to_json);
+        let method = lookup_str(&message, &["method"]).unwrap_or_default();
+        let params = lookup(&message, &["params"]).unwrap_or(&Value::Null);
+        let uri = lookup_str(params, &["textDocument", "uri"]);
+        match (method, id) {
+            ("initialize", Some(id)) => vec![response(
+                &id,
+                &format!(
+                    concat!(
+                        "{{\"capabilities\": {{\"textDocumentSync\": 1, \"hoverProvider\": true, ",
+                        "\"codeActionProvider\": {{\"codeActionKinds\": [\"quickfix\"]}}}}, ",
+                        "\"serverInfo\": {{\"name\": \"antsibull-markup-lsp\", \"version\": \"{}\"}}}}"
+                    ),
+                    env!("CARGO_PKG_VERSION")
+                ),
+            )],
+            ("shutdown", Some(id)) => vec![response(&id, "null")],
+            ("exit", None) => {
+                self.exited = true;
+                Vec::new()
+            }
+            ("textDocument/didOpen", None) => {
+                let text = lookup_str(params, &["textDocument", "text"]);
+                match (uri, text) {
+                    (Some(uri), Some(text)) => vec![self.update(uri, text.to_string())],
+                    _ => Vec::new(),
+                }
+            }
+            ("textDocument/didChange", None) => {
+                let text = match lookup(params, &["contentChanges"]) {
+                    Some(Value::Array(changes)) => changes
+                        .last()
+                        .and_then(|change| lookup_str(change, &["text"])),
+                    _ => None,
+                };
+                match (uri, text) {
+                    (Some(uri), Some(text)) => vec![self.update(uri, text.to_string())],
+                    _ => Vec::new(),
+                }
+            }
+            ("textDocument/didClose", None) => match uri {
+                Some(uri) => {
+                    self.documents.remove(uri);
+                    vec![publish_diagnostics(uri, "[]")]
+                }
+                None => Vec::new(),
+            },
+            ("textDocument/hover", Some(id)) => {
+                let position = lookup_position(params, &["position"]);
+                match (uri, position) {
+                    (Some(uri), Some(position)) => vec![response(&id, &self.hover(uri, position))],
+                    _ => vec![error_response(&id, INVALID_PARAMS, "Missing position")],
+                }
+            }
+            ("textDocument/codeAction", Some(id)) => {
+                let start = lookup_position(params, &["range", "start"]);
+                let end = lookup_position(params, &["range", "end"]);
+                match (uri, start, end) {
+                    (Some(uri), Some(start), Some(end)) => {
+                        vec![response(&id, &self.code_actions(uri, start, end))]
+                    }
+                    _ => vec![error_response(&id, INVALID_PARAMS, "Missing range")],
+                }
+            }
+            (method, Some(id)) => vec![error_response(
+                &id,
+                METHOD_NOT_FOUND,
+                &format!("Unknown method {:?}", method),
+            )],
+            (_, None) => Vec::new(),
+        }
+    }
+
+    fn update(&mut self, uri: &str, text: String) -> String {
+        let analysis = DocumentAnalysis::new(uri, &text, &self.linter);
+        let index = LineIndex::new(&text);
+        let mut diagnostics = String::from("[");
+        for (number, diagnostic) in analysis.diagnostics.iter().enumerate() {
+            if number > 0 {
+                diagnostics.push_str(", ");
+            }
+            diagnostics.push_str("{\"range\": ");
+            push_range(&mut diagnostics, &index, diagnostic.start, diagnostic.end);
+            diagnostics.push_str(match diagnostic.severity {
+                Severity::Error => ", \"severity\": 1",
+                Severity::Warning => ", \"severity\": 2",
+            });
+            if let Some(code) = diagnostic.code {
+                diagnostics.push_str(", \"code\": ");
+                push_json_string(&mut diagnostics, code);
+            }
+            diagnostics.push_str(", \"source\": \"antsibull\", \"message\": ");
+            push_json_string(&mut diagnostics, &diagnostic.message);
+            diagnostics.push('}');
+        }
+        diagnostics.push(']');
+        self.documents
+            .insert(uri.to_string(), OpenDocument { text, analysis });
+        publish_diagnostics(uri, &diagnostics)
+    }
+
+    fn hover(&self, uri: &str, position: Position) -> String {
+        let Some(document) = self.documents.get(uri) else {
+            return "null".to_string();
+        };
+        let index = LineIndex::new(&document.text);
+        let Some(paragraph) = document.analysis.paragraph_at(index.offset(position)) else {
+            return "null".to_string();
+        };
+        let mut result = String::from("{\"contents\": {\"kind\": \"markdown\", \"value\": ");
+        push_json_string(&mut result, &paragraph.to_markdown());
+        result.push_str("}, \"range\": ");
+        push_range(&mut result, &index, paragraph.start, paragraph.end);
+        result.push('}');
+        result
+    }
+
+    fn code_actions(&self, uri: &str, start: Position, end: Position) -> String {
+        let Some(document) = self.documents.get(uri) else {
+            return "[]".to_string();
+        };
+        let index = LineIndex::new(&document.text);
+        let (start, end) = (index.offset(start), index.offset(end));
+        let mut result = String::from("[");
+        for diagnostic in &document.analysis.diagnostics {
+            let Some(fix) = &diagnostic.fix else {
+                continue;
+            };
+            if diagnostic.end < start || end < diagnostic.start {
+                continue;
+            }
+            if result.len() > 1 {
+                result.push_str(", ");
+            }
+            result.push_str("{\"title\": ");
+            push_json_string(
+                &mut result,
+                &format!(
+                    "Replace {:?} with {:?}",
+                    &document.text[diagnostic.start..diagnostic.end],
+                    fix
+                ),
+            );
+            result.push_str(", \"kind\": \"quickfix\", \"edit\": {\"changes\": {");
+            push_json_string(&mut result, uri);
+            result.push_str(": [{\"range\": ");
+            push_range(&mut result, &index, diagnostic.start, diagnostic.end);
+            result.push_str(", \"newText\": ");
+            push_json_string(&mut result, fix);
+            result.push_str("}]}}}");
+        }
+        result.push(']');
+        result
+    }
+
+    /// Serve the client on `reader` and `writer`, usually stdin and stdout, until it asks the
+    /// server to exit or closes `reader`.
+    pub fn run<R: BufRead, W: Write>(&mut self, reader: &mut R, writer: &mut W) -> io::Result<()> {
+        while let Some(message) = read_message(reader)? {
+            for reply in self.handle_message(&message) {
+                write!(writer, "Content-Length: {}\r\n\r\n{}", reply.len(), reply)?;
+            }
+            writer.flush()?;
+            if self.exited {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: &str) -> String {
+    let mut result = String::from(
+        "{\"jsonrpc\": \"2.0\", \"method\": \"textDocument/publishDiagnostics\", \"params\": {\"uri\": ",
+    );
+    push_json_string(&mut result, uri);
+    result.push_str(", \"diagnostics\": ");
+    result.push_str(diagnostics);
+    result.push_str("}}");
+    result
+}
+
+/// Read a message with a `Content-Length` header. Returns `None` at the end of the input.
+fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<String>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().ok();
+            }
+        }
+    }
+    let Some(content_length) = content_length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "missing Content-Length header",
+        ));
+    };
+    let mut content = vec![0; content_length];
+    reader.read_exact(&mut content)?;
+    String::from_utf8(content)
+        .map(Some)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::TerminologyRule;
+
+    #[test]
+    fn test_language_server() {
+        let mut server = LanguageServer::new(Linter::new().add_pass(TerminologyRule::inclusive()));
+        let replies = server
+            .handle_message(r#"{"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}"#);
+        assert!(replies[0].starts_with(
+            r#"{"jsonrpc": "2.0", "id": 1, "result": {"capabilities": {"textDocumentSync": 1, "hoverProvider": true, "#
+        ));
+
+        let replies = server.handle_message(concat!(
+            r#"{"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {"textDocument": {"#,
+            r#""uri": "file:///c/meta/argument_specs.yml", "languageId": "yaml", "version": 1, "#,
+            r#""text": "argument_specs:\n  main:\n    short_description: Use the whitelist.\n    description: I(x) C(y\n"}}}"#,
+        ));
+        assert_eq!(
+            replies,
+            vec![concat!(
+                r#"{"jsonrpc": "2.0", "method": "textDocument/publishDiagnostics", "params": {"uri": "file:///c/meta/argument_specs.yml", "diagnostics": ["#,
+                r#"{"range": {"start": {"line": 2, "character": 31}, "end": {"line": 2, "character": 40}}, "severity": 2, "code": "preferred-term", "source": "antsibull", "message": "Use \"allowlist\" instead of \"whitelist\""}, "#,
+                r#"{"range": {"start": {"line": 3, "character": 22}, "end": {"line": 3, "character": 25}}, "severity": 1, "source": "antsibull", "message": "While parsing \"C(y\" at index 6 of argument_specs.main.description: Cannot find closing \")\" after last parameter"}]}}"#,
+            )]
+        );
+
+        let replies = server.handle_message(concat!(
+            r#"{"jsonrpc": "2.0", "id": "h", "method": "textDocument/hover", "params": {"#,
+            r#""textDocument": {"uri": "file:///c/meta/argument_specs.yml"}, "position": {"line": 3, "character": 18}}}"#,
+        ));
+        assert_eq!(
+            replies,
+            vec![concat!(
+                r#"{"jsonrpc": "2.0", "id": "h", "result": {"contents": {"kind": "markdown", "value": "#,
+                r#""<em>x</em> <b>ERROR while parsing</b>: While parsing \\\"C\\(y\\\" at index 6 of paragraph 1\\: Cannot find closing \\\"\\)\\\" after last parameter"}, "#,
+                r#""range": {"start": {"line": 3, "character": 17}, "end": {"line": 3, "character": 25}}}}"#,
+            )]
+        );
+
+        let replies = server.handle_message(concat!(
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "textDocument/codeAction", "params": {"#,
+            r#""textDocument": {"uri": "file:///c/meta/argument_specs.yml"}, "#,
+            r#""range": {"start": {"line": 2, "character": 35}, "end": {"line": 2, "character": 35}}, "context": {"diagnostics": []}}}"#,
+        ));
+        assert_eq!(
+            replies,
+            vec![concat!(
+                r#"{"jsonrpc": "2.0", "id": 3, "result": [{"title": "Replace \"whitelist\" with \"allowlist\"", "kind": "quickfix", "#,
+                r#""edit": {"changes": {"file:///c/meta/argument_specs.yml": [{"range": {"start": {"line": 2, "character": 31}, "end": {"line": 2, "character": 40}}, "newText": "allowlist"}]}}}]}"#,
+            )]
+        );
+
+        assert_eq!(
+            server.handle_message("[1, 2"),
+            vec![
+                r#"{"jsonrpc": "2.0", "id": null, "error": {"code": -32700, "message": "EOF while parsing a list at line 1 column 5"}}"#
+            ]
+        );
+
+        let replies =
+            server.handle_message(r#"{"jsonrpc": "2.0", "id": 4, "method": "workspace/symbol"}"#);
+        assert_eq!(
+            replies,
+            vec![
+                r#"{"jsonrpc": "2.0", "id": 4, "error": {"code": -32601, "message": "Unknown method \"workspace/symbol\""}}"#
+            ]
+        );
+
+        let frame = |message: &str| format!("Content-Length: {}\r\n\r\n{}", message.len(), message);
+        let input = frame(r#"{"jsonrpc": "2.0", "id": 5, "method": "shutdown"}"#)
+            + &frame(r#"{"jsonrpc": "2.0", "method": "exit"}"#);
+        let mut output = Vec::new();
+        server.run(&mut input.as_bytes(), &mut output).unwrap();
+        assert!(server.exited());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            frame(r#"{"jsonrpc": "2.0", "id": 5, "result": null}"#)
+        );
+    }
+}
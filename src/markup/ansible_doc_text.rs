@@ -6,15 +6,133 @@ SPDX-License-Identifier: GPL-3.0-or-later
 
 use crate::markup::dom;
 use crate::markup::format;
-use crate::util::stringbuilder::Appender;
+use crate::util::stringbuilder::{Appender, CollectorAppender, IntoString};
+use std::borrow::Cow;
 use std::rc::Rc;
 use std::sync::LazyLock;
 
-pub struct AnsibleDocTextFormatter {}
+/// Conventions of the ansible-doc text output.
+///
+/// The defaults reproduce the output of ansible-core's `ansible-doc`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnsibleDocTextOptions {
+    /// Wrap lines that are longer than this many characters. `None` disables wrapping.
+    pub width: Option<usize>,
+
+    /// The indentation of continuation lines of wrapped lines.
+    pub continuation_indent: String,
+
+    /// The marker of items of unordered lists.
+    pub bullet: String,
+
+    /// Whether to qualify options and return values of other plugins, like `(of module foo.bar.baz)`.
+    pub plugin_qualifiers: bool,
+}
+
+impl AnsibleDocTextOptions {
+    /// Create the options used by ansible-core.
+    pub fn new() -> AnsibleDocTextOptions {
+        AnsibleDocTextOptions {
+            width: None,
+            continuation_indent: "".to_string(),
+            bullet: "* ".to_string(),
+            plugin_qualifiers: true,
+        }
+    }
+
+    /// Wrap lines that are longer than `width` characters.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Set the indentation of continuation lines of wrapped lines.
+    pub fn continuation_indent(mut self, continuation_indent: &str) -> Self {
+        self.continuation_indent = continuation_indent.to_string();
+        self
+    }
+
+    /// Set the marker of items of unordered lists.
+    pub fn bullet(mut self, bullet: &str) -> Self {
+        self.bullet = bullet.to_string();
+        self
+    }
+
+    /// Set whether to qualify options and return values of other plugins.
+    pub fn plugin_qualifiers(mut self, plugin_qualifiers: bool) -> Self {
+        self.plugin_qualifiers = plugin_qualifiers;
+        self
+    }
+
+    /// Wrap all lines of `text` according to `width` and `continuation_indent`.
+    ///
+    /// Lines are only broken at spaces, and the whitespace between words of broken lines is
+    /// collapsed. Continuation lines keep the indentation of the line they belong to. Words that
+    /// are longer than the width are not broken.
+    pub fn wrap(&self, text: &str) -> String {
+        let Some(width) = self.width else {
+            return text.to_string();
+        };
+        let mut result = String::with_capacity(text.len());
+        for (index, line) in text.split('\n').enumerate() {
+            if index > 0 {
+                result.push('\n');
+            }
+            if line.chars().count() <= width {
+                result.push_str(line);
+                continue;
+            }
+            let content = line.trim_start_matches(' ');
+            let indent = &line[..line.len() - content.len()];
+            let mut current = indent.to_string();
+            let mut current_length = indent.chars().count();
+            let mut current_empty = true;
+            for word in content.split(' ').filter(|word| !word.is_empty()) {
+                let word_length = word.chars().count();
+                if !current_empty && current_length + 1 + word_length > width {
+                    result.push_str(&current);
+                    result.push('\n');
+                    current = format!("{}{}", indent, self.continuation_indent);
+                    current_length = current.chars().count();
+                    current_empty = true;
+                }
+                if !current_empty {
+                    current.push(' ');
+                    current_length += 1;
+                }
+                current.push_str(word);
+                current_length += word_length;
+                current_empty = false;
+            }
+            result.push_str(&current);
+        }
+        result
+    }
+}
+
+impl Default for AnsibleDocTextOptions {
+    fn default() -> Self {
+        AnsibleDocTextOptions::new()
+    }
+}
+
+pub struct AnsibleDocTextFormatter {
+    options: AnsibleDocTextOptions,
+}
 
 impl AnsibleDocTextFormatter {
     fn new() -> AnsibleDocTextFormatter {
-        AnsibleDocTextFormatter {}
+        AnsibleDocTextFormatter::with_options(AnsibleDocTextOptions::new())
+    }
+
+    /// Create a formatter with custom conventions.
+    pub fn with_options(options: AnsibleDocTextOptions) -> AnsibleDocTextFormatter {
+        AnsibleDocTextFormatter { options }
+    }
+
+    /// The conventions of the formatter.
+    pub fn options(&self) -> &AnsibleDocTextOptions {
+        &self.options
     }
 
     #[inline]
@@ -41,19 +159,19 @@ impl AnsibleDocTextFormatter {
     fn append_option_like<'a>(
         &self,
         appender: &mut dyn Appender<'a>,
-        name: &'a String,
-        value: &'a Option<String>,
+        name: &'a str,
+        value: &'a Option<Cow<'a, str>>,
         plugin: &Option<Rc<dom::PluginIdentifier>>,
         entrypoint: &Option<Rc<String>>,
     ) {
         appender.push_str("`");
-        appender.push_string(name);
+        appender.push_str(name);
         if let Some(v) = value {
             appender.push_str("=");
-            appender.push_string(v);
+            appender.push_str(v);
         }
         appender.push_str("'");
-        if let Some(p) = plugin {
+        if let Some(p) = plugin.as_ref().filter(|_| self.options.plugin_qualifiers) {
             appender.push_str(" (of ");
             appender.push_borrowed_string(&p.r#type);
             if !matches!(p.r#type.as_str(), "role" | "module" | "playbook") {
@@ -171,3 +289,97 @@ pub fn append_ansible_doc_text_paragraphs<'a, I, II>(
         current_plugin,
     );
 }
+
+/// Apply an ansible-doc text formatter with custom conventions to all parts of the given
+/// paragraphs, wrap the results, and concatenate them.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs that will be passed to the ansible-doc text formatter.
+pub fn append_ansible_doc_text_paragraphs_with_options<'a, I, II>(
+    appender: &mut dyn Appender<'a>,
+    paragraphs: I,
+    link_provider: &dyn format::LinkProvider,
+    current_plugin: &Option<Rc<dom::PluginIdentifier>>,
+    options: &AnsibleDocTextOptions,
+) where
+    I: IntoIterator<Item = II>,
+    II: Iterator<Item = &'a dom::Part<'a>>,
+{
+    let formatter = AnsibleDocTextFormatter::with_options(options.clone());
+    for (index, paragraph) in paragraphs.into_iter().enumerate() {
+        if index > 0 {
+            appender.push_str("\n\n");
+        }
+        let mut content = CollectorAppender::new();
+        format::append_paragraph(
+            &mut content,
+            paragraph,
+            &formatter,
+            link_provider,
+            "",
+            "",
+            "",
+            current_plugin,
+        );
+        appender.push_owned_string(options.wrap(&content.into_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::{parse_paragraphs_without_sources, Context, NoLinkProvider, ParseOptions};
+
+    #[test]
+    fn test_ansible_doc_text_options() {
+        let options = AnsibleDocTextOptions::new()
+            .width(20)
+            .continuation_indent("  ");
+        assert_eq!(options.wrap("short line"), "short line");
+        assert_eq!(
+            options.wrap("  a line that is  too long for the width\nnext"),
+            "  a line that is too\n    long for the\n    width\nnext"
+        );
+        assert_eq!(
+            options.wrap("averyveryverylongword and more"),
+            "averyveryverylongword\n  and more"
+        );
+        assert_eq!(
+            AnsibleDocTextOptions::new().wrap("  a line that is  too long"),
+            "  a line that is  too long"
+        );
+
+        let context = Context::new();
+        let paragraphs = parse_paragraphs_without_sources(
+            [
+                "Set O(foo.bar.baz#module:name=value) and C(a).",
+                "See M(foo.bar.baz).",
+            ]
+            .into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let render = |options: &AnsibleDocTextOptions| {
+            let mut appender = CollectorAppender::new();
+            append_ansible_doc_text_paragraphs_with_options(
+                &mut appender,
+                paragraphs.iter().map(|paragraph| paragraph.iter()),
+                &NoLinkProvider::new(),
+                &None,
+                options,
+            );
+            appender.into_string()
+        };
+        assert_eq!(
+            render(&AnsibleDocTextOptions::new()),
+            "Set `name=value' (of module foo.bar.baz) and `a'.\n\nSee [foo.bar.baz]."
+        );
+        assert_eq!(
+            render(
+                &AnsibleDocTextOptions::new()
+                    .plugin_qualifiers(false)
+                    .width(15)
+            ),
+            "Set\n`name=value'\nand `a'.\n\nSee\n[foo.bar.baz]."
+        );
+    }
+}
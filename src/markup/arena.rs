@@ -0,0 +1,326 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::{Part, PluginIdentifier};
+use crate::markup::parse::{parse_without_sources, Context, ParseOptions};
+use bumpalo::Bump;
+use compact_str::CompactString;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Identifies a plugin by FQCN and plugin type, with the strings stored in an arena.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArenaPluginIdentifier<'b> {
+    /// The FQCN of the plugin.
+    pub fqcn: &'b str,
+
+    /// The plugin type of the plugin.
+    pub r#type: &'b str,
+}
+
+/// A markup element (part) whose data lives in an arena.
+///
+/// This mirrors [`Part`], but all strings and lists are borrowed from the input or the arena,
+/// so the parts own no heap allocations and are freed all at once together with the arena.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArenaPart<'b> {
+    Text {
+        text: &'b str,
+    },
+    Italic {
+        text: &'b str,
+    },
+    Bold {
+        text: &'b str,
+    },
+    Code {
+        text: &'b str,
+    },
+    Module {
+        fqcn: &'b str,
+    },
+    Plugin {
+        plugin: &'b ArenaPluginIdentifier<'b>,
+    },
+    URL {
+        url: &'b str,
+    },
+    Link {
+        text: &'b str,
+        url: &'b str,
+    },
+    RSTRef {
+        text: &'b str,
+        r#ref: &'b str,
+    },
+    OptionName {
+        plugin: Option<&'b ArenaPluginIdentifier<'b>>,
+        entrypoint: Option<&'b str>,
+        link: &'b [&'b str],
+        name: &'b str,
+        value: Option<&'b str>,
+    },
+    OptionValue {
+        value: &'b str,
+    },
+    EnvVariable {
+        name: &'b str,
+    },
+    ReturnValue {
+        plugin: Option<&'b ArenaPluginIdentifier<'b>>,
+        entrypoint: Option<&'b str>,
+        link: &'b [&'b str],
+        name: &'b str,
+        value: Option<&'b str>,
+    },
+    HorizontalLine,
+    Error {
+        message: &'b str,
+    },
+}
+
+impl<'b> ArenaPart<'b> {
+    /// Convert to a regular [`Part`], for example to render it with a formatter.
+    ///
+    /// This allocates the plugin identifiers and option links that [`Part`] owns.
+    pub fn to_part(&self) -> Part<'b> {
+        let plugin = |plugin: &ArenaPluginIdentifier| PluginIdentifier {
+            fqcn: plugin.fqcn.to_string(),
+            r#type: plugin.r#type.to_string(),
+        };
+        let link = |link: &[&str]| link.iter().map(|s| CompactString::from(*s)).collect();
+        match *self {
+            ArenaPart::Text { text } => Part::Text { text },
+            ArenaPart::Italic { text } => Part::Italic { text },
+            ArenaPart::Bold { text } => Part::Bold { text },
+            ArenaPart::Code { text } => Part::Code { text },
+            ArenaPart::Module { fqcn } => Part::Module { fqcn },
+            ArenaPart::Plugin { plugin: p } => Part::Plugin { plugin: plugin(p) },
+            ArenaPart::URL { url } => Part::URL { url },
+            ArenaPart::Link { text, url } => Part::Link { text, url },
+            ArenaPart::RSTRef { text, r#ref } => Part::RSTRef { text, r#ref },
+            ArenaPart::OptionName {
+                plugin: p,
+                entrypoint,
+                link: l,
+                name,
+                value,
+            } => Part::OptionName {
+                plugin: p.map(|p| Rc::new(plugin(p))),
+                entrypoint: entrypoint.map(|ep| Rc::new(ep.to_string())),
+                link: link(l),
+                name: Cow::Borrowed(name),
+                value: value.map(Cow::Borrowed),
+            },
+            ArenaPart::OptionValue { value } => Part::OptionValue {
+                value: Cow::Borrowed(value),
+            },
+            ArenaPart::EnvVariable { name } => Part::EnvVariable {
+                name: Cow::Borrowed(name),
+            },
+            ArenaPart::ReturnValue {
+                plugin: p,
+                entrypoint,
+                link: l,
+                name,
+                value,
+            } => Part::ReturnValue {
+                plugin: p.map(|p| Rc::new(plugin(p))),
+                entrypoint: entrypoint.map(|ep| Rc::new(ep.to_string())),
+                link: link(l),
+                name: Cow::Borrowed(name),
+                value: value.map(Cow::Borrowed),
+            },
+            ArenaPart::HorizontalLine => Part::HorizontalLine,
+            ArenaPart::Error { message } => Part::Error {
+                message: message.to_string(),
+            },
+        }
+    }
+}
+
+struct ArenaBuilder<'b> {
+    bump: &'b Bump,
+    // The plugin identifiers are kept alive so that their addresses are not reused.
+    plugins:
+        HashMap<*const PluginIdentifier, (Rc<PluginIdentifier>, &'b ArenaPluginIdentifier<'b>)>,
+    entrypoints: HashMap<*const String, (Rc<String>, &'b str)>,
+}
+
+impl<'b> ArenaBuilder<'b> {
+    /// Borrow the string from the input if the parser did, and copy it into the arena otherwise.
+    fn str(&self, s: &Cow<'b, str>) -> &'b str {
+        match s {
+            Cow::Borrowed(s) => s,
+            Cow::Owned(s) => self.bump.alloc_str(s),
+        }
+    }
+
+    fn entrypoint(&mut self, entrypoint: &Rc<String>) -> &'b str {
+        let bump = self.bump;
+        self.entrypoints
+            .entry(Rc::as_ptr(entrypoint))
+            .or_insert_with(|| (entrypoint.clone(), bump.alloc_str(entrypoint)))
+            .1
+    }
+
+    fn plugin(&mut self, plugin: &Rc<PluginIdentifier>) -> &'b ArenaPluginIdentifier<'b> {
+        let bump = self.bump;
+        self.plugins
+            .entry(Rc::as_ptr(plugin))
+            .or_insert_with(|| {
+                (
+                    plugin.clone(),
+                    bump.alloc(ArenaPluginIdentifier {
+                        fqcn: bump.alloc_str(&plugin.fqcn),
+                        r#type: bump.alloc_str(&plugin.r#type),
+                    }),
+                )
+            })
+            .1
+    }
+
+    fn link(&self, link: &[CompactString]) -> &'b [&'b str] {
+        let bump = self.bump;
+        bump.alloc_slice_fill_iter(link.iter().map(|s| &*bump.alloc_str(s)))
+    }
+
+    fn convert(&mut self, part: &Part<'b>) -> ArenaPart<'b> {
+        let bump = self.bump;
+        match part {
+            Part::Text { text } => ArenaPart::Text { text },
+            Part::Italic { text } => ArenaPart::Italic { text },
+            Part::Bold { text } => ArenaPart::Bold { text },
+            Part::Code { text } => ArenaPart::Code { text },
+            Part::Module { fqcn } => ArenaPart::Module { fqcn },
+            Part::Plugin { plugin } => ArenaPart::Plugin {
+                plugin: bump.alloc(ArenaPluginIdentifier {
+                    fqcn: bump.alloc_str(&plugin.fqcn),
+                    r#type: bump.alloc_str(&plugin.r#type),
+                }),
+            },
+            Part::URL { url } => ArenaPart::URL { url },
+            Part::Link { text, url } => ArenaPart::Link { text, url },
+            Part::RSTRef { text, r#ref } => ArenaPart::RSTRef { text, r#ref },
+            Part::OptionName {
+                plugin,
+                entrypoint,
+                link,
+                name,
+                value,
+            } => ArenaPart::OptionName {
+                plugin: plugin.as_ref().map(|p| self.plugin(p)),
+                entrypoint: entrypoint.as_ref().map(|ep| self.entrypoint(ep)),
+                link: self.link(link),
+                name: self.str(name),
+                value: value.as_ref().map(|v| self.str(v)),
+            },
+            Part::OptionValue { value } => ArenaPart::OptionValue {
+                value: self.str(value),
+            },
+            Part::EnvVariable { name } => ArenaPart::EnvVariable {
+                name: self.str(name),
+            },
+            Part::ReturnValue {
+                plugin,
+                entrypoint,
+                link,
+                name,
+                value,
+            } => ArenaPart::ReturnValue {
+                plugin: plugin.as_ref().map(|p| self.plugin(p)),
+                entrypoint: entrypoint.as_ref().map(|ep| self.entrypoint(ep)),
+                link: self.link(link),
+                name: self.str(name),
+                value: value.as_ref().map(|v| self.str(v)),
+            },
+            Part::HorizontalLine => ArenaPart::HorizontalLine,
+            Part::Error { message } => ArenaPart::Error {
+                message: bump.alloc_str(message),
+            },
+        }
+    }
+}
+
+/// Parse paragraphs and store the results in the arena `bump`.
+///
+/// The strings of the parts are borrowed from the input unless the parser had to unescape them,
+/// in which case they are copied into the arena. Plugin identifiers and entrypoints of the same
+/// context are copied only once and shared. Intermediate parse results are freed
+/// before returning, so the only long-lived memory is the arena itself.
+pub fn parse_paragraphs_in_arena<'b, I>(
+    bump: &'b Bump,
+    input: I,
+    context: &'b Context,
+    opts: &ParseOptions,
+) -> &'b [&'b [ArenaPart<'b>]]
+where
+    I: Iterator<Item = &'b str>,
+{
+    let mut builder = ArenaBuilder {
+        bump,
+        plugins: HashMap::new(),
+        entrypoints: HashMap::new(),
+    };
+    let paragraphs: Vec<&'b [ArenaPart<'b>]> = input
+        .enumerate()
+        .map(|(index, p)| {
+            let parts = parse_without_sources(p, context, &opts.add_paragraph_to_where(index + 1));
+            &*bump.alloc_slice_fill_iter(parts.iter().map(|part| builder.convert(part)))
+        })
+        .collect();
+    bump.alloc_slice_copy(&paragraphs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_in_arena() {
+        let bump = Bump::new();
+        let context = Context::new().current_plugin("foo.bar.baz", "module");
+        let input = ["a O(b.c[1]=d) RV(e)", "V(x\\)y) P(a.b.c#lookup)"];
+        let paragraphs =
+            parse_paragraphs_in_arena(&bump, input.into_iter(), &context, &ParseOptions::default());
+        assert_eq!(paragraphs.len(), 2);
+        let ArenaPart::OptionName {
+            plugin: Some(option_plugin),
+            link,
+            name,
+            value,
+            ..
+        } = paragraphs[0][1]
+        else {
+            panic!("unexpected part {:?}", paragraphs[0][1]);
+        };
+        assert_eq!(link, ["b", "c"]);
+        assert_eq!(name, "b.c[1]");
+        assert_eq!(value, Some("d"));
+        // Names without escapes are not copied.
+        assert!(input[0].as_bytes().as_ptr_range().contains(&name.as_ptr()));
+        let ArenaPart::ReturnValue {
+            plugin: Some(return_value_plugin),
+            ..
+        } = paragraphs[0][3]
+        else {
+            panic!("unexpected part {:?}", paragraphs[0][3]);
+        };
+        assert!(std::ptr::eq(option_plugin, return_value_plugin));
+        assert_eq!(paragraphs[1][0], ArenaPart::OptionValue { value: "x)y" });
+
+        let expected = crate::markup::parse_paragraphs_without_sources(
+            ["a O(b.c[1]=d) RV(e)", "V(x\\)y) P(a.b.c#lookup)"].into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        for (paragraph, expected) in paragraphs.iter().zip(expected) {
+            let parts: Vec<Part> = paragraph.iter().map(|part| part.to_part()).collect();
+            assert_eq!(parts, expected);
+        }
+    }
+}
@@ -0,0 +1,193 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::PartWithSource;
+use crate::markup::renderer::Renderer;
+use std::fmt;
+
+/// The rendered paragraphs of a document, saved to compare the rendering of later versions of
+/// the formatters against.
+///
+/// The serialized form has one line per paragraph, with newlines written as `\n` and backslashes
+/// as `\\`, so that baselines can be reviewed and diffed with line-based tools.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RenderBaseline {
+    pub paragraphs: Vec<String>,
+}
+
+/// A paragraph whose rendering differs from the baseline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaselineDifference {
+    /// The index of the paragraph, starting with 0.
+    pub paragraph: usize,
+
+    /// The rendering in the baseline, if the baseline has this paragraph.
+    pub baseline: Option<String>,
+
+    /// The current rendering, if the document still has this paragraph.
+    pub current: Option<String>,
+
+    /// The parts of the current paragraph whose rendering does not appear in the baseline,
+    /// written like `code="foo"`.
+    pub parts: Vec<String>,
+}
+
+impl fmt::Display for BaselineDifference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "paragraph {}:", self.paragraph + 1)?;
+        if let Some(baseline) = &self.baseline {
+            writeln!(f, "  - {}", baseline)?;
+        }
+        if let Some(current) = &self.current {
+            writeln!(f, "  + {}", current)?;
+        }
+        if !self.parts.is_empty() {
+            writeln!(f, "  changed parts: {}", self.parts.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+fn escape_line(paragraph: &str) -> String {
+    paragraph.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape_line(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some(c) => result.push(c),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+impl RenderBaseline {
+    /// Render every paragraph on its own with `renderer`.
+    pub fn render(renderer: &Renderer, paragraphs: &[Vec<PartWithSource<'_>>]) -> RenderBaseline {
+        RenderBaseline {
+            paragraphs: paragraphs
+                .iter()
+                .map(|paragraph| {
+                    let mut result = String::new();
+                    renderer.render_paragraph(&mut result, paragraph.iter().map(|ps| &ps.part));
+                    result
+                })
+                .collect(),
+        }
+    }
+
+    /// Load a baseline serialized with [`Self::serialize`].
+    pub fn parse(content: &str) -> RenderBaseline {
+        RenderBaseline {
+            paragraphs: content.lines().map(unescape_line).collect(),
+        }
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut result = String::new();
+        for paragraph in &self.paragraphs {
+            result.push_str(&escape_line(paragraph));
+            result.push('\n');
+        }
+        result
+    }
+
+    /// Render `paragraphs` with `renderer`, and compare them paragraph by paragraph with this
+    /// baseline.
+    ///
+    /// Every changed paragraph is attributed to the parts whose rendering on their own cannot be
+    /// found in the baseline's paragraph. If the rendering of all parts is found, the change is
+    /// caused by how the parts are combined, and no parts are listed.
+    pub fn compare(
+        &self,
+        renderer: &Renderer,
+        paragraphs: &[Vec<PartWithSource<'_>>],
+    ) -> Vec<BaselineDifference> {
+        let current = RenderBaseline::render(renderer, paragraphs);
+        let count = self.paragraphs.len().max(current.paragraphs.len());
+        let mut result = Vec::new();
+        for index in 0..count {
+            let baseline = self.paragraphs.get(index);
+            let rendered = current.paragraphs.get(index);
+            if baseline == rendered {
+                continue;
+            }
+            let mut parts = Vec::new();
+            if let (Some(baseline), Some(paragraph)) = (baseline, paragraphs.get(index)) {
+                for ps in paragraph {
+                    let mut part = String::new();
+                    renderer.render_part(&mut part, &ps.part);
+                    if !baseline.contains(&part) {
+                        parts.push(ps.part.to_string());
+                    }
+                }
+            }
+            result.push(BaselineDifference {
+                paragraph: index,
+                baseline: baseline.cloned(),
+                current: rendered.cloned(),
+                parts,
+            });
+        }
+        result
+    }
+}
+
+#[cfg(all(test, feature = "md"))]
+mod tests {
+    use super::*;
+    use crate::markup::{parse_paragraphs, Context, NoLinkProvider, ParseOptions};
+
+    #[test]
+    fn test_render_baseline() {
+        let context = Context::new();
+        let paragraphs = parse_paragraphs(
+            ["Foo C(bar) I(baz).", "Second.", "Third."].into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let link_provider = NoLinkProvider::new();
+        let renderer = Renderer::md(&link_provider);
+        let baseline = RenderBaseline::render(&renderer, &paragraphs);
+        assert!(baseline.compare(&renderer, &paragraphs).is_empty());
+
+        let multiline = RenderBaseline {
+            paragraphs: vec!["a\\b\nc".to_string()],
+        };
+        assert_eq!(multiline.serialize(), "a\\\\b\\nc\n");
+        assert_eq!(RenderBaseline::parse(&multiline.serialize()), multiline);
+
+        let old = RenderBaseline {
+            paragraphs: vec![
+                "Foo <code>bar</code> <i>baz</i>\\.".to_string(),
+                baseline.paragraphs[1].clone(),
+            ],
+        };
+        let differences = old.compare(&renderer, &paragraphs);
+        assert_eq!(
+            differences
+                .iter()
+                .map(|difference| difference.to_string())
+                .collect::<String>(),
+            concat!(
+                "paragraph 1:\n",
+                "  - Foo <code>bar</code> <i>baz</i>\\.\n",
+                "  + Foo <code>bar</code> <em>baz</em>\\.\n",
+                "  changed parts: italic=\"baz\"\n",
+                "paragraph 3:\n",
+                "  + Third\\.\n",
+            )
+        );
+    }
+}
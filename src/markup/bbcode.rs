@@ -0,0 +1,204 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::bbcode_helper;
+use crate::markup::dom;
+use crate::markup::format;
+use crate::markup::html_helper;
+use crate::util::stringbuilder::Appender;
+use std::borrow::Cow;
+use std::rc::Rc;
+use std::sync::LazyLock;
+
+pub struct BBCodeFormatter {
+    bbcode_escaper: bbcode_helper::BBCodeEscaper,
+    url_escaper: html_helper::URLEscaper,
+}
+
+impl BBCodeFormatter {
+    fn new() -> BBCodeFormatter {
+        BBCodeFormatter {
+            bbcode_escaper: bbcode_helper::BBCodeEscaper::new(),
+            url_escaper: html_helper::URLEscaper::new(),
+        }
+    }
+
+    #[inline]
+    fn append_tag<'a>(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        start: &'a str,
+        text: &'a str,
+        end: &'a str,
+    ) {
+        appender.push_str(start);
+        appender.push_cow_str(self.bbcode_escaper.escape(text));
+        appender.push_str(end);
+    }
+
+    #[inline]
+    fn append_url_start<'a>(&self, appender: &mut dyn Appender<'a>, url: &str) {
+        appender.push_str("[url=");
+        appender.push_owned_string(
+            self.bbcode_escaper
+                .escape_url(&self.url_escaper.escape(url))
+                .into_owned(),
+        );
+        appender.push_str("]");
+    }
+
+    #[inline]
+    fn append_link<'a>(&self, appender: &mut dyn Appender<'a>, text: &'a str, url: &'a str) {
+        self.append_url_start(appender, url);
+        appender.push_cow_str(self.bbcode_escaper.escape(text));
+        appender.push_str("[/url]");
+    }
+
+    #[inline]
+    fn append_fqcn<'a>(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        fqcn: &'a str,
+        url: &Option<String>,
+    ) {
+        match url {
+            Some(u) => {
+                self.append_url_start(appender, u);
+                appender.push_cow_str(self.bbcode_escaper.escape(fqcn));
+                appender.push_str("[/url]");
+            }
+            None => appender.push_cow_str(self.bbcode_escaper.escape(fqcn)),
+        }
+    }
+
+    #[inline]
+    fn append_option_like<'a>(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        name: &'a str,
+        value: &'a Option<Cow<'a, str>>,
+        what: format::OptionLike,
+        url: &Option<String>,
+    ) {
+        // Most forums do not interpret tags inside [code], so links and bold go outside
+        let strong = matches!(what, format::OptionLike::Option) && value.is_none();
+        if let Some(u) = url {
+            self.append_url_start(appender, u);
+        }
+        if strong {
+            appender.push_str("[b]");
+        }
+        appender.push_str("[code]");
+        appender.push_cow_str(self.bbcode_escaper.escape(name));
+        if let Some(v) = value {
+            appender.push_str("=");
+            appender.push_cow_str(self.bbcode_escaper.escape(v));
+        }
+        appender.push_str("[/code]");
+        if strong {
+            appender.push_str("[/b]");
+        }
+        if url.is_some() {
+            appender.push_str("[/url]");
+        }
+    }
+}
+
+impl<'a> format::Formatter<'a> for BBCodeFormatter {
+    fn append(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        part: &'a dom::Part<'a>,
+        url: Option<String>,
+    ) {
+        match part {
+            dom::Part::Text { text } => appender.push_cow_str(self.bbcode_escaper.escape(text)),
+            dom::Part::Bold { text } => self.append_tag(appender, "[b]", text, "[/b]"),
+            dom::Part::Italic { text } => self.append_tag(appender, "[i]", text, "[/i]"),
+            dom::Part::Code { text } => self.append_tag(appender, "[code]", text, "[/code]"),
+            dom::Part::HorizontalLine => appender.push_str("[hr]"),
+            dom::Part::OptionValue { value } => {
+                self.append_tag(appender, "[code]", value, "[/code]")
+            }
+            dom::Part::EnvVariable { name } => self.append_tag(appender, "[code]", name, "[/code]"),
+            dom::Part::Error { message } => {
+                appender.push_str("[b]ERROR while parsing[/b]: ");
+                appender.push_cow_str(self.bbcode_escaper.escape(message));
+            }
+            dom::Part::RSTRef { text, r#ref: _ } => {
+                appender.push_cow_str(self.bbcode_escaper.escape(text))
+            }
+            dom::Part::Link { text, url } => self.append_link(appender, text, url),
+            dom::Part::URL { url } => self.append_link(appender, url, url),
+            dom::Part::Module { fqcn } => self.append_fqcn(appender, fqcn, &url),
+            dom::Part::Plugin { plugin } => self.append_fqcn(appender, &plugin.fqcn, &url),
+            dom::Part::OptionName {
+                plugin: _,
+                entrypoint: _,
+                link: _,
+                name,
+                value,
+            } => self.append_option_like(appender, name, value, format::OptionLike::Option, &url),
+            dom::Part::ReturnValue {
+                plugin: _,
+                entrypoint: _,
+                link: _,
+                name,
+                value,
+            } => self.append_option_like(appender, name, value, format::OptionLike::RetVal, &url),
+        };
+    }
+}
+
+pub static BBCODE_FORMATTER: LazyLock<BBCodeFormatter> = LazyLock::new(BBCodeFormatter::new);
+
+/// Apply the BBCode formatter to all parts of the given paragraph, and concatenate the results.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs that will be passed to the BBCode formatter.
+pub fn append_bbcode_paragraph<'a, I>(
+    appender: &mut dyn Appender<'a>,
+    paragraph: I,
+    link_provider: &dyn format::LinkProvider,
+    current_plugin: &Option<Rc<dom::PluginIdentifier>>,
+) where
+    I: Iterator<Item = &'a dom::Part<'a>>,
+{
+    format::append_paragraph(
+        appender,
+        paragraph,
+        &*BBCODE_FORMATTER,
+        link_provider,
+        "",
+        "",
+        "",
+        current_plugin,
+    );
+}
+
+/// Apply the BBCode formatter to all parts of the given paragraphs, and concatenate the results.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs that will be passed to the BBCode formatter.
+pub fn append_bbcode_paragraphs<'a, I, II>(
+    appender: &mut dyn Appender<'a>,
+    paragraphs: I,
+    link_provider: &dyn format::LinkProvider,
+    current_plugin: &Option<Rc<dom::PluginIdentifier>>,
+) where
+    I: IntoIterator<Item = II>,
+    II: Iterator<Item = &'a dom::Part<'a>>,
+{
+    format::append_paragraphs(
+        appender,
+        paragraphs,
+        &*BBCODE_FORMATTER,
+        link_provider,
+        "",
+        "",
+        "\n\n",
+        "",
+        current_plugin,
+    );
+}
@@ -0,0 +1,46 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+
+#[derive(Default)]
+pub struct BBCodeEscaper {}
+
+impl BBCodeEscaper {
+    pub fn new() -> BBCodeEscaper {
+        BBCodeEscaper {}
+    }
+
+    /// Escape text so that it is not interpreted as BBCode tags.
+    ///
+    /// BBCode has no escape mechanism of its own, so brackets are replaced by HTML character
+    /// references, which forums accepting BBCode render as-is. `&` is escaped as well so that
+    /// existing character references in the text are preserved.
+    pub fn escape<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if !text.contains(['[', ']', '&']) {
+            return Cow::Borrowed(text);
+        }
+        let mut result = String::with_capacity(text.len() + 8);
+        for c in text.chars() {
+            match c {
+                '[' => result.push_str("&#91;"),
+                ']' => result.push_str("&#93;"),
+                '&' => result.push_str("&amp;"),
+                c => result.push(c),
+            }
+        }
+        Cow::Owned(result)
+    }
+
+    /// Escape an URL (that has already been percent-encoded) for use in `[url=...]`.
+    pub fn escape_url<'a>(&self, url: &'a str) -> Cow<'a, str> {
+        if !url.contains(['[', ']']) {
+            return Cow::Borrowed(url);
+        }
+        Cow::Owned(url.replace('[', "%5B").replace(']', "%5D"))
+    }
+}
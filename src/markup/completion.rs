@@ -0,0 +1,434 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::PluginIdentifier;
+use crate::markup::parse::{describe_commands, CommandDescription};
+use crate::markup::plugin_types::PLUGIN_TYPES;
+use crate::markup::{Context, ParseOptions};
+use std::collections::{BTreeMap, BTreeSet};
+
+type PluginKey = (PluginIdentifier, Option<String>);
+
+/// The plugins, options, and return values that can be completed.
+///
+/// Options and return values are stored per plugin and role entrypoint, as dotted paths like
+/// `config.key`. Every parent of a path must be added as well, so that `config` is completed
+/// before `key`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CompletionIndex {
+    plugins: BTreeSet<PluginIdentifier>,
+    options: BTreeMap<PluginKey, BTreeSet<String>>,
+    return_values: BTreeMap<PluginKey, BTreeSet<String>>,
+}
+
+impl CompletionIndex {
+    pub fn new() -> CompletionIndex {
+        CompletionIndex::default()
+    }
+
+    /// Add a plugin.
+    pub fn plugin(mut self, plugin: &PluginIdentifier) -> Self {
+        self.add_plugin(plugin);
+        self
+    }
+
+    /// Add a plugin.
+    pub fn add_plugin(&mut self, plugin: &PluginIdentifier) {
+        self.plugins.insert(plugin.clone());
+    }
+
+    /// Add the option `path` of a plugin. The plugin is added as well.
+    pub fn option(
+        mut self,
+        plugin: &PluginIdentifier,
+        entrypoint: Option<&str>,
+        path: &str,
+    ) -> Self {
+        self.add_option(plugin, entrypoint, path);
+        self
+    }
+
+    /// Add the option `path` of a plugin. The plugin is added as well.
+    pub fn add_option(&mut self, plugin: &PluginIdentifier, entrypoint: Option<&str>, path: &str) {
+        self.add_plugin(plugin);
+        self.options
+            .entry((plugin.clone(), entrypoint.map(ToString::to_string)))
+            .or_default()
+            .insert(path.to_string());
+    }
+
+    /// Add the return value `path` of a plugin. The plugin is added as well.
+    pub fn return_value(
+        mut self,
+        plugin: &PluginIdentifier,
+        entrypoint: Option<&str>,
+        path: &str,
+    ) -> Self {
+        self.add_return_value(plugin, entrypoint, path);
+        self
+    }
+
+    /// Add the return value `path` of a plugin. The plugin is added as well.
+    pub fn add_return_value(
+        &mut self,
+        plugin: &PluginIdentifier,
+        entrypoint: Option<&str>,
+        path: &str,
+    ) {
+        self.add_plugin(plugin);
+        self.return_values
+            .entry((plugin.clone(), entrypoint.map(ToString::to_string)))
+            .or_default()
+            .insert(path.to_string());
+    }
+}
+
+/// What a [`Completion`] inserts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompletionKind {
+    /// The start of a command, like `O(` or `HORIZONTALLINE`.
+    Command,
+
+    /// The FQCN of a plugin, possibly followed by its type, like `foo.bar.baz#module:`.
+    Plugin,
+
+    /// A plugin type, like `module`.
+    PluginType,
+
+    /// The entrypoint of a role, like `main:`.
+    Entrypoint,
+
+    /// The name of an option.
+    Option,
+
+    /// The name of a return value.
+    ReturnValue,
+}
+
+/// A completion candidate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    /// The text that replaces the text between `start` and the cursor.
+    pub label: String,
+
+    /// What the text is.
+    pub kind: CompletionKind,
+
+    /// The byte offset where the completed text starts.
+    pub start: usize,
+}
+
+struct Completer<'a> {
+    index: &'a CompletionIndex,
+    offset: usize,
+    result: Vec<Completion>,
+}
+
+impl Completer<'_> {
+    fn push(&mut self, label: String, kind: CompletionKind, typed: &str) {
+        self.result.push(Completion {
+            label,
+            kind,
+            start: self.offset - typed.len(),
+        });
+    }
+
+    fn plugins(&mut self, typed: &str, module_only: bool, suffix: Option<&str>) {
+        let index = self.index;
+        for plugin in &index.plugins {
+            if (module_only && plugin.r#type != "module") || !plugin.fqcn.starts_with(typed) {
+                continue;
+            }
+            let label = match suffix {
+                Some(suffix) => format!("{}#{}{}", plugin.fqcn, plugin.r#type, suffix),
+                None => plugin.fqcn.clone(),
+            };
+            self.push(label, CompletionKind::Plugin, typed);
+        }
+    }
+
+    fn plugin_types(&mut self, fqcn: &str, typed: &str, suffix: &str) {
+        let mut types: BTreeSet<&str> = self
+            .index
+            .plugins
+            .iter()
+            .filter(|plugin| plugin.fqcn == fqcn)
+            .map(|plugin| plugin.r#type.as_str())
+            .collect();
+        if types.is_empty() {
+            types.extend(PLUGIN_TYPES);
+        }
+        for plugin_type in types {
+            if plugin_type.starts_with(typed) {
+                self.push(
+                    format!("{}{}", plugin_type, suffix),
+                    CompletionKind::PluginType,
+                    typed,
+                );
+            }
+        }
+    }
+
+    fn entrypoints(&mut self, plugin: &PluginIdentifier, typed: &str) {
+        let index = self.index;
+        let entrypoints: BTreeSet<&str> = index
+            .options
+            .keys()
+            .chain(index.return_values.keys())
+            .filter(|(other, _)| other == plugin)
+            .filter_map(|(_, entrypoint)| entrypoint.as_deref())
+            .collect();
+        for entrypoint in entrypoints {
+            if entrypoint.starts_with(typed) {
+                self.push(
+                    format!("{}:", entrypoint),
+                    CompletionKind::Entrypoint,
+                    typed,
+                );
+            }
+        }
+    }
+
+    fn paths(
+        &mut self,
+        plugin: &PluginIdentifier,
+        entrypoint: Option<&str>,
+        typed: &str,
+        kind: CompletionKind,
+    ) {
+        let index = self.index;
+        let paths = match kind {
+            CompletionKind::ReturnValue => &index.return_values,
+            _ => &index.options,
+        };
+        let Some(paths) = paths.get(&(plugin.clone(), entrypoint.map(ToString::to_string))) else {
+            return;
+        };
+        let (parent, name) = typed.rsplit_once('.').unwrap_or(("", typed));
+        let parent = strip_array_stubs(parent);
+        for path in paths {
+            let (path_parent, path_name) = path.rsplit_once('.').unwrap_or(("", path));
+            if path_parent == parent && path_name.starts_with(name) {
+                self.push(path_name.to_string(), kind, name);
+            }
+        }
+    }
+
+    fn option_like(&mut self, typed: &str, context: &Context, kind: CompletionKind) {
+        if typed.contains('=') {
+            return;
+        }
+        let Some((fqcn, rest)) = typed.split_once('#') else {
+            if let Some(plugin) = &context.current_plugin {
+                let entrypoint = context.role_entrypoint.as_deref().map(String::as_str);
+                self.paths(plugin, entrypoint, typed, kind);
+            }
+            if !typed.contains('[') {
+                self.plugins(typed, false, Some(":"));
+            }
+            return;
+        };
+        let Some((plugin_type, rest)) = rest.split_once(':') else {
+            self.plugin_types(fqcn, rest, ":");
+            return;
+        };
+        let plugin = PluginIdentifier {
+            fqcn: fqcn.to_string(),
+            r#type: plugin_type.to_string(),
+        };
+        if plugin_type != "role" {
+            self.paths(&plugin, None, rest, kind);
+        } else if let Some((entrypoint, rest)) = rest.split_once(':') {
+            self.paths(&plugin, Some(entrypoint), rest, kind);
+        } else {
+            self.entrypoints(&plugin, rest);
+        }
+    }
+
+    fn commands(&mut self, prefix: &str, commands: &[CommandDescription]) {
+        let typed = &prefix[prefix
+            .trim_end_matches(|c: char| c.is_ascii_uppercase())
+            .len()..];
+        let before = &prefix[..prefix.len() - typed.len()];
+        if before.ends_with(|c: char| c.is_alphanumeric() || c == '_') {
+            return;
+        }
+        for command in commands {
+            if command.name.starts_with(typed) {
+                self.push(
+                    command.command_match.to_string(),
+                    CompletionKind::Command,
+                    typed,
+                );
+            }
+        }
+    }
+}
+
+fn strip_array_stubs(path: &str) -> String {
+    let mut result = String::with_capacity(path.len());
+    let mut depth = 0;
+    for c in path.chars() {
+        match c {
+            '[' => depth += 1,
+            ']' if depth > 0 => depth -= 1,
+            _ if depth == 0 => result.push(c),
+            _ => {}
+        }
+    }
+    result
+}
+
+/// Whether `argument` is terminated, that is whether it contains an unescaped `)`.
+fn is_terminated(argument: &str, escaped: bool) -> bool {
+    let mut chars = argument.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if escaped => {
+                chars.next();
+            }
+            ')' => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Find the last command in `prefix` whose parameters are not terminated yet, and return it
+/// together with the text of its parameters.
+fn open_command<'a, 'b>(
+    prefix: &'a str,
+    commands: &'b [CommandDescription],
+) -> Option<(&'b CommandDescription, &'a str)> {
+    let mut result: Option<(usize, &CommandDescription)> = None;
+    for command in commands.iter().filter(|command| command.parameters > 0) {
+        for (start, _) in prefix.match_indices(command.command_match) {
+            if prefix[..start].ends_with(|c: char| c.is_alphanumeric() || c == '_') {
+                continue;
+            }
+            let argument = &prefix[start + command.command_match.len()..];
+            if is_terminated(argument, command.escaped_arguments) {
+                continue;
+            }
+            if result.is_none_or(|(other, _)| start > other) {
+                result = Some((start, command));
+            }
+        }
+    }
+    result.map(|(start, command)| (command, &prefix[start + command.command_match.len()..]))
+}
+
+/// Return the completion candidates at the byte offset `offset` of `text`.
+///
+/// Outside of commands, the names of the commands known to the parser selected by `opts` are
+/// completed. In the parameter of `M()`, FQCNs of modules from `index` are completed, and in `P()`
+/// FQCNs with plugin types. In `O()` and `RV()`, options and return values of the current plugin
+/// of `context` are completed, as well as plugins, role entrypoints, and the options and return
+/// values of other plugins. After `#`, plugin types are completed: the ones of the plugins in
+/// `index` with that FQCN, or all plugin types if there are none.
+///
+/// Returns no candidates if `offset` is not a character boundary of `text`.
+pub fn complete(
+    text: &str,
+    offset: usize,
+    context: &Context,
+    opts: &ParseOptions,
+    index: &CompletionIndex,
+) -> Vec<Completion> {
+    if !text.is_char_boundary(offset) {
+        return Vec::new();
+    }
+    let prefix = &text[..offset];
+    let commands = describe_commands(opts);
+    let mut completer = Completer {
+        index,
+        offset,
+        result: Vec::new(),
+    };
+    match open_command(prefix, &commands) {
+        Some((command, argument)) => match command.name {
+            "M" => completer.plugins(argument, true, None),
+            "P" => match argument.split_once('#') {
+                Some((fqcn, typed)) => completer.plugin_types(fqcn, typed, ""),
+                None => completer.plugins(argument, false, Some("")),
+            },
+            "O" => completer.option_like(argument, context, CompletionKind::Option),
+            "RV" => completer.option_like(argument, context, CompletionKind::ReturnValue),
+            _ => {}
+        },
+        None => completer.commands(prefix, &commands),
+    }
+    completer.result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(completions: &[Completion]) -> Vec<(&str, usize)> {
+        completions
+            .iter()
+            .map(|completion| (completion.label.as_str(), completion.start))
+            .collect()
+    }
+
+    #[test]
+    fn test_complete() {
+        let module = PluginIdentifier {
+            fqcn: "foo.bar.baz".to_string(),
+            r#type: "module".to_string(),
+        };
+        let role = PluginIdentifier {
+            fqcn: "foo.bar.role".to_string(),
+            r#type: "role".to_string(),
+        };
+        let index = CompletionIndex::new()
+            .option(&module, None, "config")
+            .option(&module, None, "config.key")
+            .option(&module, None, "state")
+            .return_value(&module, None, "result")
+            .option(&role, Some("main"), "name")
+            .plugin(&PluginIdentifier {
+                fqcn: "foo.bar.baz".to_string(),
+                r#type: "lookup".to_string(),
+            });
+        let context = Context::new().current_plugin("foo.bar.baz", "module");
+        let opts = ParseOptions::default();
+        let complete = |text: &str| complete(text, text.len(), &context, &opts, &index);
+
+        assert_eq!(labels(&complete("See R")), vec![("R(", 4), ("RV(", 4)]);
+        assert_eq!(labels(&complete("FOR")), vec![]);
+        assert_eq!(labels(&complete("I(x) O(s")), vec![("state", 7)]);
+        assert_eq!(labels(&complete("O(config[0].")), vec![("key", 12)]);
+        assert_eq!(labels(&complete("O(x=")), vec![]);
+        assert_eq!(
+            labels(&complete("O(foo.bar.r")),
+            vec![("foo.bar.role#role:", 2)]
+        );
+        assert_eq!(
+            labels(&complete("O(foo.bar.role#role:")),
+            vec![("main:", 20)]
+        );
+        assert_eq!(
+            labels(&complete("O(foo.bar.role#role:main:n")),
+            vec![("name", 25)]
+        );
+        assert_eq!(
+            labels(&complete("RV(foo.bar.baz#module:r")),
+            vec![("result", 22)]
+        );
+        assert_eq!(
+            labels(&complete("P(foo.bar.baz#")),
+            vec![("lookup", 14), ("module", 14)]
+        );
+        assert_eq!(labels(&complete("P(x.y.z#mod")), vec![("module", 8)]);
+        assert_eq!(labels(&complete("M(foo.")), vec![("foo.bar.baz", 2)]);
+        assert_eq!(labels(&complete("M(foo.bar.baz) O(")).len(), 5);
+        assert_eq!(labels(&complete("C(foo")), vec![]);
+        assert_eq!(complete("O(ä").len(), 0);
+        assert_eq!(super::complete("Ä", 1, &context, &opts, &index), Vec::new());
+    }
+}
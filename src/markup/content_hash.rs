@@ -0,0 +1,215 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::{Part, PartWithSource, PluginIdentifier};
+use crate::markup::renderer::Renderer;
+use sha2::{Digest, Sha256};
+
+/// Feeds a canonical encoding of parts into a SHA-256 hasher.
+///
+/// Every string is prefixed with its length, and every part with its kind, so that different
+/// documents cannot produce the same sequence of bytes. The encoding does not depend on the
+/// platform, so hashes can be compared between machines and runs.
+struct PartHasher {
+    hasher: Sha256,
+}
+
+impl PartHasher {
+    fn str(&mut self, value: &str) {
+        self.hasher.update((value.len() as u64).to_le_bytes());
+        self.hasher.update(value.as_bytes());
+    }
+
+    fn optional_str(&mut self, value: Option<&str>) {
+        match value {
+            Some(value) => {
+                self.hasher.update([1]);
+                self.str(value);
+            }
+            None => self.hasher.update([0]),
+        }
+    }
+
+    fn plugin(&mut self, plugin: Option<&PluginIdentifier>) {
+        self.optional_str(plugin.map(|plugin| plugin.fqcn.as_str()));
+        self.optional_str(plugin.map(|plugin| plugin.r#type.as_str()));
+    }
+
+    fn part(&mut self, part: &Part<'_>) {
+        match part {
+            Part::Text { text } => self.tagged("text", &[text]),
+            Part::Italic { text } => self.tagged("italic", &[text]),
+            Part::Bold { text } => self.tagged("bold", &[text]),
+            Part::Code { text } => self.tagged("code", &[text]),
+            Part::Module { fqcn } => self.tagged("module", &[fqcn]),
+            Part::Plugin { plugin } => self.tagged("plugin", &[&plugin.fqcn, &plugin.r#type]),
+            Part::URL { url } => self.tagged("url", &[url]),
+            Part::Link { text, url } => self.tagged("link", &[text, url]),
+            Part::RSTRef { text, r#ref } => self.tagged("rst-ref", &[text, r#ref]),
+            Part::OptionName {
+                plugin,
+                entrypoint,
+                link,
+                name,
+                value,
+            }
+            | Part::ReturnValue {
+                plugin,
+                entrypoint,
+                link,
+                name,
+                value,
+            } => {
+                let kind = if matches!(part, Part::OptionName { .. }) {
+                    "option-name"
+                } else {
+                    "return-value"
+                };
+                self.tagged(kind, &[name]);
+                self.plugin(plugin.as_deref());
+                self.optional_str(entrypoint.as_deref().map(String::as_str));
+                self.hasher.update((link.len() as u64).to_le_bytes());
+                for entry in link.iter() {
+                    self.str(entry);
+                }
+                self.optional_str(value.as_deref());
+            }
+            Part::OptionValue { value } => self.tagged("option-value", &[value]),
+            Part::EnvVariable { name } => self.tagged("env-variable", &[name]),
+            Part::HorizontalLine => self.tagged("horizontal-line", &[]),
+            Part::Error { message } => self.tagged("error", &[message]),
+        }
+    }
+
+    fn tagged(&mut self, kind: &str, values: &[&str]) {
+        self.str(kind);
+        for value in values {
+            self.str(value);
+        }
+    }
+
+    fn finish(self) -> String {
+        self.hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+}
+
+/// Compute a stable hash of paragraphs of parts, as lowercase hex digits.
+///
+/// The hash only depends on the content of the parts, so documents that are parsed from
+/// different sources, but result in the same parts, have the same hash.
+pub fn hash_paragraphs<'p, 'a: 'p, I, II>(paragraphs: I) -> String
+where
+    I: IntoIterator<Item = II>,
+    II: IntoIterator<Item = &'p Part<'a>>,
+{
+    let mut hasher = PartHasher {
+        hasher: Sha256::new(),
+    };
+    for paragraph in paragraphs {
+        hasher.hasher.update(b"paragraph");
+        for part in paragraph {
+            hasher.part(part);
+        }
+    }
+    hasher.finish()
+}
+
+/// Compute a stable hash of parsed paragraphs, ignoring the source of the parts.
+pub fn hash_document(paragraphs: &[Vec<PartWithSource<'_>>]) -> String {
+    hash_paragraphs(
+        paragraphs
+            .iter()
+            .map(|paragraph| paragraph.iter().map(|ps| &ps.part)),
+    )
+}
+
+/// Compute the hash of rendered output, as lowercase hex digits.
+pub fn hash_output(output: &str) -> String {
+    let mut hasher = PartHasher {
+        hasher: Sha256::new(),
+    };
+    hasher.hasher.update(output.as_bytes());
+    hasher.finish()
+}
+
+/// Rendered output together with the hashes of the document and of the output.
+///
+/// Incremental builds can compare `output_hash` with the hash of the last build to skip
+/// writing unchanged pages, or use it as an ETag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashedRendering {
+    /// The rendered output.
+    pub output: String,
+
+    /// The hash of the parsed paragraphs, see [`hash_document`].
+    pub document_hash: String,
+
+    /// The hash of the output, see [`hash_output`].
+    pub output_hash: String,
+}
+
+impl Renderer<'_> {
+    /// Render parsed paragraphs to a string, and compute the hashes of the paragraphs and the
+    /// output.
+    pub fn render_hashed(&self, paragraphs: &[Vec<PartWithSource<'_>>]) -> HashedRendering {
+        let output = self.render(paragraphs);
+        HashedRendering {
+            document_hash: hash_document(paragraphs),
+            output_hash: hash_output(&output),
+            output,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "md"))]
+mod tests {
+    use super::*;
+    use crate::markup::{parse_paragraphs, Context, NoLinkProvider, ParseOptions};
+
+    #[test]
+    fn test_content_hash() {
+        let context = Context::new();
+        let parse = |paragraphs: &'static [&'static str]| {
+            parse_paragraphs(
+                paragraphs.iter().copied(),
+                &context,
+                &ParseOptions::default(),
+            )
+        };
+        let document = parse(&["Use O(foo=bar) and I(this)."]);
+        let hash = hash_document(&document);
+        assert_eq!(hash.len(), 64);
+        assert_eq!(
+            hash,
+            hash_document(&parse(&["Use O(foo=b\\ar) and I(this)."]))
+        );
+        assert_ne!(
+            hash,
+            hash_document(&parse(&["Use O(foo=bar) and B(this)."]))
+        );
+        assert_ne!(
+            hash,
+            hash_document(&parse(&["Use O(foo=bar)", " and I(this)."]))
+        );
+        assert_ne!(
+            hash,
+            hash_document(&parse(&["Use O(foo=bar) and I(this). "]))
+        );
+        assert_eq!(
+            hash_output(""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+
+        let link_provider = NoLinkProvider::new();
+        let rendered = Renderer::md(&link_provider).render_hashed(&document);
+        assert_eq!(rendered.document_hash, hash);
+        assert_eq!(rendered.output_hash, hash_output(&rendered.output));
+    }
+}
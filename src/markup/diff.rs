@@ -0,0 +1,309 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::Part;
+use crate::markup::renderer::Renderer;
+use std::mem;
+
+/// A change of a single part inside a paragraph.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PartChange<'a> {
+    /// A part that only exists in the new paragraph.
+    Added { new: &'a Part<'a> },
+
+    /// A part that only exists in the old paragraph.
+    Removed { old: &'a Part<'a> },
+
+    /// A part that was replaced by a part of the same kind, for example a text whose content
+    /// changed, or an option reference that now points to another option.
+    Changed {
+        old: &'a Part<'a>,
+        new: &'a Part<'a>,
+    },
+}
+
+/// A change of a paragraph. Indices are zero-based.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParagraphChange<'a> {
+    /// A paragraph that only exists in the new document.
+    Added {
+        new_index: usize,
+        paragraph: &'a [Part<'a>],
+    },
+
+    /// A paragraph that only exists in the old document.
+    Removed {
+        old_index: usize,
+        paragraph: &'a [Part<'a>],
+    },
+
+    /// A paragraph that exists in both documents, but with different parts.
+    Changed {
+        old_index: usize,
+        new_index: usize,
+        changes: Vec<PartChange<'a>>,
+    },
+}
+
+/// The differences between two versions of a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentDiff<'a> {
+    /// The changed paragraphs, in document order.
+    pub changes: Vec<ParagraphChange<'a>>,
+}
+
+pub(crate) enum Edit {
+    Keep,
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Compute a shortest edit script from `old` to `new` using their longest common subsequence.
+pub(crate) fn edit_script<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Edit> {
+    let columns = new.len() + 1;
+    let mut lengths = vec![0usize; (old.len() + 1) * columns];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * columns + j] = if old[i] == new[j] {
+                lengths[(i + 1) * columns + j + 1] + 1
+            } else {
+                lengths[(i + 1) * columns + j].max(lengths[i * columns + j + 1])
+            };
+        }
+    }
+    let mut result = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            result.push(Edit::Keep);
+            i += 1;
+            j += 1;
+        } else if j == new.len()
+            || (i < old.len() && lengths[(i + 1) * columns + j] >= lengths[i * columns + j + 1])
+        {
+            result.push(Edit::Delete(i));
+            i += 1;
+        } else {
+            result.push(Edit::Insert(j));
+            j += 1;
+        }
+    }
+    result
+}
+
+/// Walk through the edit script of `old` and `new`, and call `pair` for every run of deleted
+/// and inserted elements.
+fn for_each_run<T, F>(old: &[T], new: &[T], mut pair: F)
+where
+    T: PartialEq,
+    F: FnMut(Vec<usize>, Vec<usize>),
+{
+    let mut deleted = Vec::new();
+    let mut inserted = Vec::new();
+    for edit in edit_script(old, new) {
+        match edit {
+            Edit::Keep => {
+                if !deleted.is_empty() || !inserted.is_empty() {
+                    pair(mem::take(&mut deleted), mem::take(&mut inserted));
+                }
+            }
+            Edit::Delete(index) => deleted.push(index),
+            Edit::Insert(index) => inserted.push(index),
+        }
+    }
+    if !deleted.is_empty() || !inserted.is_empty() {
+        pair(deleted, inserted);
+    }
+}
+
+fn diff_parts<'a>(old: &'a [Part<'a>], new: &'a [Part<'a>]) -> Vec<PartChange<'a>> {
+    let mut changes = Vec::new();
+    for_each_run(old, new, |deleted, inserted| {
+        let paired = deleted.len().min(inserted.len());
+        for (&o, &n) in deleted.iter().zip(inserted.iter()) {
+            if mem::discriminant(&old[o]) == mem::discriminant(&new[n]) {
+                changes.push(PartChange::Changed {
+                    old: &old[o],
+                    new: &new[n],
+                });
+            } else {
+                changes.push(PartChange::Removed { old: &old[o] });
+                changes.push(PartChange::Added { new: &new[n] });
+            }
+        }
+        changes.extend(
+            deleted[paired..]
+                .iter()
+                .map(|&o| PartChange::Removed { old: &old[o] }),
+        );
+        changes.extend(
+            inserted[paired..]
+                .iter()
+                .map(|&n| PartChange::Added { new: &new[n] }),
+        );
+    });
+    changes
+}
+
+/// Compare two versions of a parsed document, for example the descriptions of a plugin in two
+/// releases, and return the changes at paragraph and part granularity.
+///
+/// Paragraphs that were replaced one by one are reported as changed paragraphs with their
+/// part changes; all other differences are reported as added or removed paragraphs.
+pub fn diff<'a>(old: &'a [Vec<Part<'a>>], new: &'a [Vec<Part<'a>>]) -> DocumentDiff<'a> {
+    let mut changes = Vec::new();
+    for_each_run(old, new, |deleted, inserted| {
+        let paired = deleted.len().min(inserted.len());
+        for (&o, &n) in deleted.iter().zip(inserted.iter()) {
+            changes.push(ParagraphChange::Changed {
+                old_index: o,
+                new_index: n,
+                changes: diff_parts(&old[o], &new[n]),
+            });
+        }
+        changes.extend(deleted[paired..].iter().map(|&o| ParagraphChange::Removed {
+            old_index: o,
+            paragraph: &old[o],
+        }));
+        changes.extend(inserted[paired..].iter().map(|&n| ParagraphChange::Added {
+            new_index: n,
+            paragraph: &new[n],
+        }));
+    });
+    DocumentDiff { changes }
+}
+
+impl<'a> DocumentDiff<'a> {
+    /// Whether both documents are identical.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Render a human readable change report. Parts are rendered with `renderer`, and every
+    /// change is put on its own line. Paragraph numbers in the report start with 1.
+    pub fn render(&self, renderer: &Renderer) -> String {
+        let mut result = String::new();
+        let render = |result: &mut String, parts: &[&'a Part<'a>]| {
+            let mut rendered = String::new();
+            renderer.render_paragraph(&mut rendered, parts.iter().copied());
+            result.push_str(&rendered);
+        };
+        for change in &self.changes {
+            match change {
+                ParagraphChange::Added {
+                    new_index,
+                    paragraph,
+                } => {
+                    result.push_str(&format!("Added paragraph {}: ", new_index + 1));
+                    render(&mut result, &paragraph.iter().collect::<Vec<_>>());
+                    result.push('\n');
+                }
+                ParagraphChange::Removed {
+                    old_index,
+                    paragraph,
+                } => {
+                    result.push_str(&format!("Removed paragraph {}: ", old_index + 1));
+                    render(&mut result, &paragraph.iter().collect::<Vec<_>>());
+                    result.push('\n');
+                }
+                ParagraphChange::Changed {
+                    old_index,
+                    new_index,
+                    changes,
+                } => {
+                    if old_index == new_index {
+                        result.push_str(&format!("Changed paragraph {}:\n", new_index + 1));
+                    } else {
+                        result.push_str(&format!(
+                            "Changed paragraph {} (previously {}):\n",
+                            new_index + 1,
+                            old_index + 1
+                        ));
+                    }
+                    for change in changes {
+                        match change {
+                            PartChange::Added { new } => {
+                                result.push_str("  added: ");
+                                render(&mut result, &[new]);
+                            }
+                            PartChange::Removed { old } => {
+                                result.push_str("  removed: ");
+                                render(&mut result, &[old]);
+                            }
+                            PartChange::Changed { old, new } => {
+                                result.push_str("  changed: ");
+                                render(&mut result, &[old]);
+                                result.push_str(" -> ");
+                                render(&mut result, &[new]);
+                            }
+                        }
+                        result.push('\n');
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+#[cfg(all(test, feature = "ansible-doc"))]
+mod tests {
+    use super::*;
+    use crate::markup::{parse_paragraphs_without_sources, Context, NoLinkProvider, ParseOptions};
+
+    #[test]
+    fn test_diff() {
+        let context = Context::new();
+        let old = parse_paragraphs_without_sources(
+            [
+                "Set O(foo) to V(bar).",
+                "This paragraph is removed.",
+                "Unchanged.",
+            ]
+            .into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let new = parse_paragraphs_without_sources(
+            ["Set O(baz) to V(bar) C(now).", "Unchanged.", "New."].into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let changes = diff(&old, &new);
+        assert_eq!(
+            changes.changes,
+            vec![
+                ParagraphChange::Changed {
+                    old_index: 0,
+                    new_index: 0,
+                    changes: vec![
+                        PartChange::Changed {
+                            old: &old[0][1],
+                            new: &new[0][1]
+                        },
+                        PartChange::Added { new: &new[0][4] },
+                        PartChange::Added { new: &new[0][5] },
+                    ],
+                },
+                ParagraphChange::Removed {
+                    old_index: 1,
+                    paragraph: &old[1]
+                },
+                ParagraphChange::Added {
+                    new_index: 2,
+                    paragraph: &new[2]
+                },
+            ]
+        );
+
+        let link_provider = NoLinkProvider::new();
+        assert_eq!(
+            changes.render(&Renderer::ansible_doc_text(&link_provider)),
+            "Changed paragraph 1:\n  changed: `foo' -> `baz'\n  added:  \n  added: `now'\nRemoved paragraph 2: This paragraph is removed.\nAdded paragraph 3: New.\n"
+        );
+        assert!(diff(&old, &old).is_empty());
+    }
+}
@@ -4,11 +4,17 @@ SPDX-FileCopyrightText: 2024, Felix Fontein
 SPDX-License-Identifier: GPL-3.0-or-later
 */
 
-use std::fmt;
-use std::rc::Rc;
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use compact_str::CompactString;
+use core::fmt;
+use core::ops::Range;
 
 /// Identifies a plugin by FQCN and plugin type.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct PluginIdentifier {
     /// The FQCN of the plugin.
     pub fqcn: String,
@@ -33,7 +39,7 @@ impl fmt::Display for PluginIdentifier {
 ///
 /// Describes a part of a paragraph. These parts are concatenated without separators
 /// to form the paragraph.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Part<'a> {
     /// Some plain text.
     Text { text: &'a str },
@@ -77,22 +83,22 @@ pub enum Part<'a> {
         /// suboption `baz` of `bar`, which in turn is a suboption of
         /// the top-level option `foo`, this is the list `["foo", "bar", "baz"]`.
         /// This list does not contain array stubs.
-        link: Box<[String]>,
+        link: Box<[CompactString]>,
 
         /// The option name, including array stubs.
         ///
         /// For example `foo[1].bar[].baz`.
-        name: String,
+        name: Cow<'a, str>,
 
         /// The option's value, if present.
-        value: Option<String>,
+        value: Option<Cow<'a, str>>,
     },
 
     /// Option value.
-    OptionValue { value: String },
+    OptionValue { value: Cow<'a, str> },
 
     /// Environment variable.
-    EnvVariable { name: String },
+    EnvVariable { name: Cow<'a, str> },
 
     /// Reference to a return value, with optional value.
     ReturnValue {
@@ -108,15 +114,15 @@ pub enum Part<'a> {
         /// sub-return value `baz` of `bar`, which in turn is a sub-return value
         /// of the top-level return value `foo`, this is the list
         /// `["foo", "bar", "baz"]`. This list does not contain array stubs.
-        link: Box<[String]>,
+        link: Box<[CompactString]>,
 
         /// The return value name, including array stubs.
         ///
         /// For example `foo[1].bar[].baz`.
-        name: String,
+        name: Cow<'a, str>,
 
         /// The return value's value, if present.
-        value: Option<String>,
+        value: Option<Cow<'a, str>>,
     },
 
     /// A horizontal line as a separator.
@@ -128,6 +134,29 @@ pub enum Part<'a> {
     Error { message: String },
 }
 
+impl Part<'_> {
+    /// The kind of the part in snake case, like `text`, `option_name`, or `horizontal_line`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Part::Text { .. } => "text",
+            Part::Italic { .. } => "italic",
+            Part::Bold { .. } => "bold",
+            Part::Code { .. } => "code",
+            Part::Module { .. } => "module",
+            Part::Plugin { .. } => "plugin",
+            Part::URL { .. } => "url",
+            Part::Link { .. } => "link",
+            Part::RSTRef { .. } => "rst_ref",
+            Part::OptionName { .. } => "option_name",
+            Part::OptionValue { .. } => "option_value",
+            Part::EnvVariable { .. } => "env_variable",
+            Part::ReturnValue { .. } => "return_value",
+            Part::HorizontalLine => "horizontal_line",
+            Part::Error { .. } => "error",
+        }
+    }
+}
+
 impl<'a> fmt::Display for Part<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -196,14 +225,34 @@ impl<'a> fmt::Display for Part<'a> {
     }
 }
 
+/// A backslash escape in a parameter of a semantic markup command, like `\)` in `V(a\)b)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EscapePosition {
+    /// The index of the command's parameter that contains the escape.
+    pub parameter: usize,
+
+    /// The byte offset of the escaped character in the unescaped parameter.
+    pub offset: usize,
+
+    /// The byte offset of the backslash in the source of the part.
+    pub source_offset: usize,
+}
+
 /// A markup element (part) together with its source string.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PartWithSource<'a> {
     /// The DOM part.
     pub part: Part<'a>,
 
     /// The source string that resulted in the DOM part.
     pub source: &'a str,
+
+    /// The escapes in the parameters of the command, in source order.
+    ///
+    /// Only recorded if [`ParseOptions::preserve_escapes`](crate::markup::ParseOptions::preserve_escapes)
+    /// is used, and only for semantic markup commands that were parsed without error. Empty
+    /// otherwise.
+    pub escapes: Vec<EscapePosition>,
 }
 
 impl<'a> fmt::Display for PartWithSource<'a> {
@@ -211,3 +260,65 @@ impl<'a> fmt::Display for PartWithSource<'a> {
         write!(f, "({}; source={:?})", self.part, self.source)
     }
 }
+
+/// A part of a paragraph found by [`part_at_offset`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartAtOffset<'p, 'a> {
+    /// The index of the part in the paragraph.
+    pub index: usize,
+
+    /// The part.
+    pub part: &'p PartWithSource<'a>,
+
+    /// The byte offsets of the part's source in the paragraph.
+    pub span: Range<usize>,
+
+    /// The kind of the part, see [`Part::kind`].
+    pub kind: &'static str,
+}
+
+/// Return the part of a paragraph whose source contains the byte offset `offset` of the
+/// paragraph's source.
+///
+/// The offset right after the end of the paragraph belongs to the last part, so that a cursor
+/// at the end of the text still finds it. Returns `None` for larger offsets and empty paragraphs.
+pub fn part_at_offset<'p, 'a>(
+    paragraph: &'p [PartWithSource<'a>],
+    offset: usize,
+) -> Option<PartAtOffset<'p, 'a>> {
+    let mut start = 0;
+    for (index, part) in paragraph.iter().enumerate() {
+        let end = start + part.source.len();
+        if offset < end || (offset == end && index + 1 == paragraph.len()) {
+            return Some(PartAtOffset {
+                index,
+                part,
+                span: start..end,
+                kind: part.part.kind(),
+            });
+        }
+        start = end;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::{parse, Context, ParseOptions};
+
+    #[test]
+    fn test_part_at_offset() {
+        let context = Context::new();
+        let paragraph = parse("See O(foo) here", &context, &ParseOptions::default());
+        let found = part_at_offset(&paragraph, 6).unwrap();
+        assert_eq!(found.index, 1);
+        assert_eq!(found.span, 4..10);
+        assert_eq!(found.kind, "option_name");
+        assert_eq!(found.part.source, "O(foo)");
+        assert_eq!(part_at_offset(&paragraph, 10).unwrap().kind, "text");
+        assert_eq!(part_at_offset(&paragraph, 15).unwrap().span, 10..15);
+        assert_eq!(part_at_offset(&paragraph, 16), None);
+        assert_eq!(part_at_offset(&[], 0), None);
+    }
+}
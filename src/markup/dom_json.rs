@@ -0,0 +1,167 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::{Part, PartWithSource, PluginIdentifier};
+use crate::util::json::push_json_string;
+use compact_str::CompactString;
+use std::rc::Rc;
+
+fn push_field(result: &mut String, name: &str, value: &str) {
+    result.push_str(", ");
+    push_json_string(result, name);
+    result.push_str(": ");
+    push_json_string(result, value);
+}
+
+fn push_optional_field(result: &mut String, name: &str, value: Option<&str>) {
+    match value {
+        Some(value) => push_field(result, name, value),
+        None => {
+            result.push_str(", ");
+            push_json_string(result, name);
+            result.push_str(": null");
+        }
+    }
+}
+
+fn push_plugin(result: &mut String, plugin: &PluginIdentifier) {
+    result.push_str("{\"fqcn\": ");
+    push_json_string(result, &plugin.fqcn);
+    result.push_str(", \"type\": ");
+    push_json_string(result, &plugin.r#type);
+    result.push('}');
+}
+
+fn push_option_like(
+    result: &mut String,
+    plugin: &Option<Rc<PluginIdentifier>>,
+    entrypoint: &Option<Rc<String>>,
+    link: &[CompactString],
+    name: &str,
+    value: Option<&str>,
+) {
+    result.push_str(", \"plugin\": ");
+    match plugin {
+        Some(plugin) => push_plugin(result, plugin),
+        None => result.push_str("null"),
+    }
+    push_optional_field(
+        result,
+        "entrypoint",
+        entrypoint.as_deref().map(String::as_str),
+    );
+    result.push_str(", \"link\": [");
+    for (index, entry) in link.iter().enumerate() {
+        if index > 0 {
+            result.push_str(", ");
+        }
+        push_json_string(result, entry);
+    }
+    result.push(']');
+    push_field(result, "name", name);
+    push_optional_field(result, "value", value);
+}
+
+/// Append the `type` field and the fields of a part, without the braces of the object.
+fn push_part_fields(result: &mut String, part: &Part<'_>) {
+    result.push_str("\"type\": ");
+    push_json_string(result, part.kind());
+    match part {
+        Part::Text { text } | Part::Italic { text } | Part::Bold { text } | Part::Code { text } => {
+            push_field(result, "text", text)
+        }
+        Part::Module { fqcn } => push_field(result, "fqcn", fqcn),
+        Part::Plugin { plugin } => {
+            result.push_str(", \"plugin\": ");
+            push_plugin(result, plugin);
+        }
+        Part::URL { url } => push_field(result, "url", url),
+        Part::Link { text, url } => {
+            push_field(result, "text", text);
+            push_field(result, "url", url);
+        }
+        Part::RSTRef { text, r#ref } => {
+            push_field(result, "text", text);
+            push_field(result, "ref", r#ref);
+        }
+        Part::OptionName {
+            plugin,
+            entrypoint,
+            link,
+            name,
+            value,
+        }
+        | Part::ReturnValue {
+            plugin,
+            entrypoint,
+            link,
+            name,
+            value,
+        } => push_option_like(result, plugin, entrypoint, link, name, value.as_deref()),
+        Part::OptionValue { value } => push_field(result, "value", value),
+        Part::EnvVariable { name } => push_field(result, "name", name),
+        Part::HorizontalLine => {}
+        Part::Error { message } => push_field(result, "message", message),
+    }
+}
+
+/// Serialize parsed paragraphs as a JSON list of paragraphs, each a list of parts.
+///
+/// Every part is an object with a `type` field like `option_name`, the fields of the part, and
+/// a `source` field with the markup the part was parsed from.
+pub fn paragraphs_to_json(paragraphs: &[Vec<PartWithSource<'_>>]) -> String {
+    let mut result = String::from("[");
+    for (index, paragraph) in paragraphs.iter().enumerate() {
+        if index > 0 {
+            result.push_str(", ");
+        }
+        result.push('[');
+        for (index, part) in paragraph.iter().enumerate() {
+            if index > 0 {
+                result.push_str(", ");
+            }
+            result.push('{');
+            push_part_fields(&mut result, &part.part);
+            push_field(&mut result, "source", part.source);
+            result.push('}');
+        }
+        result.push(']');
+    }
+    result.push(']');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::{parse_paragraphs, Context, ParseOptions};
+
+    #[test]
+    fn test_paragraphs_to_json() {
+        let context = Context::new();
+        let paragraphs = parse_paragraphs(
+            [
+                "Use O(foo.bar.baz#module:a.b=\"c\") and M(foo.bar.baz).",
+                "",
+                "B(x",
+            ]
+            .into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        assert_eq!(
+            paragraphs_to_json(&paragraphs),
+            concat!(
+                r#"[[{"type": "text", "text": "Use ", "source": "Use "}, "#,
+                r#"{"type": "option_name", "plugin": {"fqcn": "foo.bar.baz", "type": "module"}, "entrypoint": null, "link": ["a", "b"], "name": "a.b", "value": "\"c\"", "source": "O(foo.bar.baz#module:a.b=\"c\")"}, "#,
+                r#"{"type": "text", "text": " and ", "source": " and "}, "#,
+                r#"{"type": "module", "fqcn": "foo.bar.baz", "source": "M(foo.bar.baz)"}, "#,
+                r#"{"type": "text", "text": ".", "source": "."}], [], "#,
+                r#"[{"type": "error", "message": "While parsing \"B(x\" at index 1 of paragraph 3: Cannot find closing \")\" after last parameter", "source": "B(x"}]]"#,
+            )
+        );
+    }
+}
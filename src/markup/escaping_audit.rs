@@ -0,0 +1,284 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::PluginIdentifier;
+use crate::markup::format::{LinkProvider, OptionLike};
+use crate::markup::parse::{parse_paragraphs, Context, ParseOptions};
+use crate::markup::renderer::Renderer;
+use compact_str::CompactString;
+use std::fmt;
+
+/// The output formats checked by [`audit_escaping`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AuditedFormat {
+    AntsibullHTML,
+    PlainHTML,
+    MD,
+    AntsibullRST,
+    PlainRST,
+}
+
+impl AuditedFormat {
+    /// All audited formats, in the order they are checked.
+    pub const ALL: [AuditedFormat; 5] = [
+        AuditedFormat::AntsibullHTML,
+        AuditedFormat::PlainHTML,
+        AuditedFormat::MD,
+        AuditedFormat::AntsibullRST,
+        AuditedFormat::PlainRST,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AuditedFormat::AntsibullHTML => "antsibull-html",
+            AuditedFormat::PlainHTML => "plain-html",
+            AuditedFormat::MD => "md",
+            AuditedFormat::AntsibullRST => "antsibull-rst",
+            AuditedFormat::PlainRST => "plain-rst",
+        }
+    }
+
+    fn renderer<'r>(&self, link_provider: &'r dyn LinkProvider) -> Renderer<'r> {
+        match self {
+            AuditedFormat::AntsibullHTML => Renderer::antsibull_html(link_provider),
+            AuditedFormat::PlainHTML => Renderer::plain_html(link_provider),
+            AuditedFormat::MD => Renderer::md(link_provider),
+            AuditedFormat::AntsibullRST => Renderer::antsibull_rst(link_provider),
+            AuditedFormat::PlainRST => Renderer::plain_rst(link_provider),
+        }
+    }
+}
+
+/// An output line that looks structurally invalid for its format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EscapingIssue {
+    /// The index of the input in the corpus.
+    pub input: usize,
+
+    pub format: AuditedFormat,
+
+    /// The line number in the rendered output, starting with 1.
+    pub line: usize,
+
+    /// The content of the output line.
+    pub output: String,
+
+    pub message: &'static str,
+}
+
+impl fmt::Display for EscapingIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "input {}, {} line {}: {}: {}",
+            self.input + 1,
+            self.format.name(),
+            self.line,
+            self.message,
+            self.output
+        )
+    }
+}
+
+/// Links every plugin and option, so that URLs and link texts show up in the audited output.
+struct AuditLinkProvider;
+
+impl LinkProvider for AuditLinkProvider {
+    fn plugin_link(&self, plugin: &PluginIdentifier) -> Option<String> {
+        Some(format!(
+            "https://example.com/{}_{}.html",
+            plugin.fqcn, plugin.r#type
+        ))
+    }
+
+    fn plugin_option_like_link(
+        &self,
+        plugin: &PluginIdentifier,
+        _entrypoint: Option<&String>,
+        what: OptionLike,
+        name: &[CompactString],
+        _current_plugin: bool,
+    ) -> Option<String> {
+        let what = match what {
+            OptionLike::Option => "parameter",
+            OptionLike::RetVal => "return",
+        };
+        Some(format!(
+            "https://example.com/{}_{}.html#{}-{}",
+            plugin.fqcn,
+            plugin.r#type,
+            what,
+            name.join("/")
+        ))
+    }
+
+    fn env_variable_link(&self, name: &str) -> Option<String> {
+        Some(format!("https://example.com/config.html#{}", name))
+    }
+}
+
+/// Whether a line of RST has an odd number of backticks that are not escaped with a backslash.
+fn has_unbalanced_backticks(line: &str) -> bool {
+    let mut count = 0;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '`' => count += 1,
+            _ => {}
+        }
+    }
+    count % 2 != 0
+}
+
+const MD_TAGS: [&str; 8] = ["a", "b", "br", "code", "em", "hr", "span", "strong"];
+
+/// Whether a line of MarkDown has a `<` that is neither escaped nor starts a known tag.
+fn has_raw_angle_bracket(line: &str) -> bool {
+    let mut rest = line;
+    while let Some(index) = rest.find(['\\', '<']) {
+        if rest[index..].starts_with('\\') {
+            let mut tail = rest[index + 1..].chars();
+            tail.next();
+            rest = tail.as_str();
+            continue;
+        }
+        let tag = &rest[index + 1..];
+        let Some(end) = tag.find('>') else {
+            return true;
+        };
+        let name = tag[..end]
+            .strip_prefix('/')
+            .unwrap_or(&tag[..end])
+            .split([' ', '/'])
+            .next()
+            .unwrap_or("");
+        if !MD_TAGS.contains(&name) || tag[..end].contains('<') {
+            return true;
+        }
+        rest = &tag[end + 1..];
+    }
+    false
+}
+
+/// The byte offsets of `<p>` tags that open while another paragraph is still open.
+fn nested_paragraphs(html: &str) -> Vec<usize> {
+    let mut result = Vec::new();
+    let mut open = 0usize;
+    let mut offset = 0;
+    while let Some(index) = html[offset..].find('<') {
+        let position = offset + index;
+        let tag = &html[position..];
+        if tag.starts_with("<p>") || tag.starts_with("<p ") {
+            if open > 0 {
+                result.push(position);
+            }
+            open += 1;
+        } else if tag.starts_with("</p>") {
+            open = open.saturating_sub(1);
+        }
+        offset = position + 1;
+    }
+    result
+}
+
+fn audit_output(input: usize, format: AuditedFormat, output: &str) -> Vec<EscapingIssue> {
+    let issue = |line: usize, message| EscapingIssue {
+        input,
+        format,
+        line: line + 1,
+        output: output.lines().nth(line).unwrap_or("").to_string(),
+        message,
+    };
+    let mut result = Vec::new();
+    match format {
+        AuditedFormat::AntsibullHTML | AuditedFormat::PlainHTML => {
+            for position in nested_paragraphs(output) {
+                let line = output[..position].matches('\n').count();
+                result.push(issue(line, "nested <p>"));
+            }
+        }
+        AuditedFormat::MD => {
+            for (line, content) in output.lines().enumerate() {
+                if has_raw_angle_bracket(content) {
+                    result.push(issue(line, "raw < outside of a tag"));
+                }
+            }
+        }
+        AuditedFormat::AntsibullRST | AuditedFormat::PlainRST => {
+            for (line, content) in output.lines().enumerate() {
+                if has_unbalanced_backticks(content) {
+                    result.push(issue(line, "unbalanced backticks"));
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Render every input of a corpus, one paragraph per input, to all [`AuditedFormat`]s, and
+/// report output lines that look structurally invalid for their format.
+///
+/// The checks are heuristics meant to catch escaping bugs of the formatters in bulk: unbalanced
+/// backticks in RST, raw `<` outside of tags in MarkDown, and nested `<p>` in HTML. All plugin,
+/// option, and environment variable references are linked, so that URLs are checked as well.
+pub fn audit_escaping<'a, I>(
+    inputs: I,
+    context: &Context,
+    opts: &ParseOptions,
+) -> Vec<EscapingIssue>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let link_provider = AuditLinkProvider;
+    let mut result = Vec::new();
+    for (index, input) in inputs.into_iter().enumerate() {
+        let paragraphs = parse_paragraphs(std::iter::once(input), context, opts);
+        for format in AuditedFormat::ALL {
+            let output = format
+                .renderer(&link_provider)
+                .with_current_plugin(context.current_plugin.clone())
+                .render(&paragraphs);
+            result.extend(audit_output(index, format, &output));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_escaping() {
+        let context = Context::new().current_plugin("foo.bar.baz", "module");
+        let issues = audit_escaping(
+            [
+                "a < b and `x` and C(a`b) and V(<p>)",
+                "L(x<y, https://example.com/?a<b) O(foo.bar.baz#module:a<b=c`d) E(A_<B)",
+                "P(foo.bar.baz#module) RV(x[0]) U(https://example.com/`x`)",
+            ],
+            &context,
+            &ParseOptions::default(),
+        );
+        assert_eq!(issues, vec![]);
+
+        assert!(has_unbalanced_backticks(":literal:`a` `b"));
+        assert!(!has_unbalanced_backticks(":literal:`a\\`` b\\\\`c`"));
+        assert!(has_raw_angle_bracket("a <b c"));
+        assert!(has_raw_angle_bracket("<code>a</code> <p>"));
+        assert!(!has_raw_angle_bracket("<a href=\"x\">\\<y</a>"));
+        assert_eq!(nested_paragraphs("<p>a</p>\n<p>b<p>c</p></p>"), vec![13]);
+
+        let issues = audit_output(2, AuditedFormat::PlainHTML, "<p>a</p>\n<p>b<p>c</p></p>");
+        assert_eq!(
+            issues.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
+            vec!["input 3, plain-html line 2: nested <p>: <p>b<p>c</p></p>"]
+        );
+    }
+}
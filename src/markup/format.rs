@@ -6,6 +6,7 @@ SPDX-License-Identifier: GPL-3.0-or-later
 
 use crate::markup::dom;
 use crate::util::stringbuilder::Appender;
+use compact_str::CompactString;
 use std::rc::Rc;
 
 pub trait Formatter<'a> {
@@ -19,16 +20,36 @@ pub enum OptionLike {
 
 pub trait LinkProvider {
     fn plugin_link(&self, plugin: &dom::PluginIdentifier) -> Option<String>;
+
+    /// The URL of the option or return value `name` of `plugin`, referenced with `O()` or
+    /// `RV()`. `name` lists the components of the name, without array indices.
+    ///
+    /// Breaking change: the components used to be passed as `&[String]`. They are
+    /// [`CompactString`]s now, which dereference to `str`, so implementations need to change
+    /// the type of `name` in their signature.
     fn plugin_option_like_link(
         &self,
         plugin: &dom::PluginIdentifier,
         entrypoint: Option<&String>,
         what: OptionLike,
-        name: &[String],
+        name: &[CompactString],
         current_plugin: bool,
     ) -> Option<String>;
+
+    /// The URL of the environment variable `name` referenced with `E()`, like its entry in the
+    /// ansible-core configuration reference. Environment variables are not linked by default.
+    fn env_variable_link(&self, _name: &str) -> Option<String> {
+        None
+    }
 }
 
+/// Marks link providers whose links only depend on their arguments.
+///
+/// Implement this only if repeated calls with the same arguments return the same links, and
+/// the links do not depend on the order of calls, the time, or other global state. This is
+/// required by [`crate::markup::render_deterministic`].
+pub trait DeterministicLinkProvider: LinkProvider {}
+
 pub struct NoLinkProvider {}
 
 impl NoLinkProvider {
@@ -47,13 +68,15 @@ impl LinkProvider for NoLinkProvider {
         _plugin: &dom::PluginIdentifier,
         _entrypoint: Option<&String>,
         _what: OptionLike,
-        _name: &[String],
+        _name: &[CompactString],
         _current_plugin: bool,
     ) -> Option<String> {
         None
     }
 }
 
+impl DeterministicLinkProvider for NoLinkProvider {}
+
 /// Apply the formatter to all parts of the given paragraph, concatenate the results, and insert start and end sequences for the paragraph.
 ///
 /// `link_provider` and `current_plugin` will be used to compute optional URLs that will be passed to the formatter.
@@ -117,6 +140,7 @@ pub fn append_paragraph<'a, I>(
                 ),
                 None => None,
             },
+            dom::Part::EnvVariable { name } => link_provider.env_variable_link(name),
             _ => None,
         };
         formatter.append(appender, part, url);
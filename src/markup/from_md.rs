@@ -0,0 +1,304 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::Part;
+use bumpalo::Bump;
+use pulldown_cmark::{CowStr, Event, LinkType, Options, Parser, Tag, TagEnd};
+use std::fmt;
+use std::ops::Range;
+
+/// A MarkDown construct that cannot be represented as Ansible markup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedMarkdown {
+    /// A short description of the construct, like `heading`.
+    pub construct: String,
+
+    /// The byte range of the construct in the input.
+    pub range: Range<usize>,
+
+    /// The line of the start of the construct, starting with 1.
+    pub line: usize,
+
+    /// The column of the start of the construct in bytes, starting with 1.
+    pub column: usize,
+}
+
+impl fmt::Display for UnsupportedMarkdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: unsupported {}",
+            self.line, self.column, self.construct
+        )
+    }
+}
+
+/// The result of converting MarkDown to Ansible markup parts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkdownDocument<'a> {
+    /// The converted paragraphs.
+    pub paragraphs: Vec<Vec<Part<'a>>>,
+
+    /// The constructs that could not be converted. Their text content is kept as plain text.
+    pub unsupported: Vec<UnsupportedMarkdown>,
+}
+
+enum InlineKind<'a> {
+    Italic,
+    Bold,
+    Link { url: &'a str, autolink: bool },
+    Plain,
+}
+
+struct Inline<'a> {
+    kind: InlineKind<'a>,
+    text: String,
+}
+
+struct Converter<'a> {
+    input: &'a str,
+    bump: &'a Bump,
+    paragraphs: Vec<Vec<Part<'a>>>,
+    current: Vec<Part<'a>>,
+    inlines: Vec<Inline<'a>>,
+    unsupported: Vec<UnsupportedMarkdown>,
+}
+
+impl<'a> Converter<'a> {
+    fn alloc(&self, value: CowStr<'a>) -> &'a str {
+        match value {
+            CowStr::Borrowed(value) => value,
+            value => self.bump.alloc_str(&value),
+        }
+    }
+
+    fn report(&mut self, construct: &str, range: Range<usize>) {
+        let before = &self.input[..range.start];
+        let line_start = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+        self.unsupported.push(UnsupportedMarkdown {
+            construct: construct.to_string(),
+            line: before.matches('\n').count() + 1,
+            column: range.start - line_start + 1,
+            range,
+        });
+    }
+
+    fn flush(&mut self) {
+        if !self.current.is_empty() {
+            self.paragraphs.push(std::mem::take(&mut self.current));
+        }
+    }
+
+    fn push_inline(&mut self, kind: InlineKind<'a>, construct: &str, range: Range<usize>) {
+        if !self.inlines.is_empty() && !matches!(kind, InlineKind::Plain) {
+            self.report(&format!("{} inside formatting or link", construct), range);
+        }
+        self.inlines.push(Inline {
+            kind,
+            text: String::new(),
+        });
+    }
+
+    fn pop_inline(&mut self) {
+        let Some(inline) = self.inlines.pop() else {
+            return;
+        };
+        if let Some(parent) = self.inlines.last_mut() {
+            parent.text.push_str(&inline.text);
+            return;
+        }
+        let text = &*self.bump.alloc_str(&inline.text);
+        self.current.push(match inline.kind {
+            InlineKind::Italic => Part::Italic { text },
+            InlineKind::Bold => Part::Bold { text },
+            InlineKind::Link { url, autolink } if autolink && text == url => Part::URL { url },
+            InlineKind::Link { url, .. } => Part::Link { text, url },
+            InlineKind::Plain => Part::Text { text },
+        });
+    }
+
+    fn text(&mut self, text: CowStr<'a>, code: bool, range: Range<usize>) {
+        if let Some(inline) = self.inlines.last_mut() {
+            inline.text.push_str(&text);
+            if code {
+                self.report("code inside formatting or link", range);
+            }
+            return;
+        }
+        let text = self.alloc(text);
+        self.current.push(if code {
+            Part::Code { text }
+        } else {
+            Part::Text { text }
+        });
+    }
+
+    fn start(&mut self, tag: Tag<'a>, range: Range<usize>) {
+        match tag {
+            Tag::Paragraph => {}
+            Tag::Emphasis => self.push_inline(InlineKind::Italic, "emphasis", range),
+            Tag::Strong => self.push_inline(InlineKind::Bold, "strong emphasis", range),
+            Tag::Link {
+                link_type,
+                dest_url,
+                ..
+            } => {
+                let url = match link_type {
+                    LinkType::Email => &*self.bump.alloc_str(&format!("mailto:{}", dest_url)),
+                    _ => self.alloc(dest_url),
+                };
+                let autolink = matches!(link_type, LinkType::Autolink);
+                self.push_inline(InlineKind::Link { url, autolink }, "link", range);
+            }
+            Tag::Image { .. } => {
+                self.report("image", range.clone());
+                self.push_inline(InlineKind::Plain, "image", range);
+            }
+            Tag::Heading { .. } => {
+                self.report("heading", range);
+                self.flush();
+            }
+            Tag::BlockQuote(_) => {
+                self.report("block quote", range);
+                self.flush();
+            }
+            Tag::CodeBlock(_) => {
+                self.report("code block", range);
+                self.flush();
+            }
+            Tag::List(_) => {
+                self.report("list", range);
+                self.flush();
+            }
+            Tag::Item => self.flush(),
+            _ => {
+                self.report("block", range);
+                self.flush();
+            }
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Link | TagEnd::Image => self.pop_inline(),
+            _ => self.flush(),
+        }
+    }
+
+    fn convert(mut self) -> MarkdownDocument<'a> {
+        for (event, range) in Parser::new_ext(self.input, Options::empty()).into_offset_iter() {
+            match event {
+                Event::Start(tag) => self.start(tag, range),
+                Event::End(tag) => self.end(tag),
+                Event::Text(text) => self.text(text, false, range),
+                Event::Code(text) => self.text(text, true, range),
+                Event::SoftBreak | Event::HardBreak => {
+                    self.text(CowStr::Borrowed(" "), false, range)
+                }
+                Event::Rule => {
+                    self.flush();
+                    self.paragraphs.push(vec![Part::HorizontalLine]);
+                }
+                Event::Html(_) | Event::InlineHtml(_) => self.report("HTML", range),
+                _ => self.report("construct", range),
+            }
+        }
+        self.flush();
+        MarkdownDocument {
+            paragraphs: self.paragraphs,
+            unsupported: self.unsupported,
+        }
+    }
+}
+
+/// Convert a safe subset of MarkDown to Ansible markup parts.
+///
+/// Paragraphs, emphasis, strong emphasis, inline code, links, and horizontal rules are
+/// supported. Text that is not borrowed from `input`, like decoded entities or the content of
+/// formatting, is stored in `bump`. All other constructs are reported in
+/// [`MarkdownDocument::unsupported`].
+pub fn from_md<'a>(input: &'a str, bump: &'a Bump) -> MarkdownDocument<'a> {
+    Converter {
+        input,
+        bump,
+        paragraphs: Vec::new(),
+        current: Vec::new(),
+        inlines: Vec::new(),
+        unsupported: Vec::new(),
+    }
+    .convert()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_md() {
+        let bump = Bump::new();
+        let document = from_md(
+            "Some *italic*, **bold &amp; strong**, and `code`.\nSee [the docs](https://docs.ansible.com/) or <https://x.org/>.\n\n---\n\n# Title\n\n- item\n",
+            &bump,
+        );
+        assert_eq!(
+            document.paragraphs,
+            vec![
+                vec![
+                    Part::Text { text: "Some " },
+                    Part::Italic { text: "italic" },
+                    Part::Text { text: ", " },
+                    Part::Bold {
+                        text: "bold & strong"
+                    },
+                    Part::Text { text: ", and " },
+                    Part::Code { text: "code" },
+                    Part::Text { text: "." },
+                    Part::Text { text: " " },
+                    Part::Text { text: "See " },
+                    Part::Link {
+                        text: "the docs",
+                        url: "https://docs.ansible.com/"
+                    },
+                    Part::Text { text: " or " },
+                    Part::URL {
+                        url: "https://x.org/"
+                    },
+                    Part::Text { text: "." },
+                ],
+                vec![Part::HorizontalLine],
+                vec![Part::Text { text: "Title" }],
+                vec![Part::Text { text: "item" }],
+            ]
+        );
+        assert_eq!(
+            document
+                .unsupported
+                .iter()
+                .map(|unsupported| unsupported.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "line 6, column 1: unsupported heading",
+                "line 8, column 1: unsupported list",
+            ]
+        );
+
+        let document = from_md("*a `b`* ![x](y.png)", &bump);
+        assert_eq!(
+            document.paragraphs,
+            vec![vec![
+                Part::Italic { text: "a b" },
+                Part::Text { text: " " },
+                Part::Text { text: "x" },
+            ]]
+        );
+        assert_eq!(document.unsupported.len(), 2);
+        assert_eq!(
+            document.unsupported[0].construct,
+            "code inside formatting or link"
+        );
+        assert_eq!(document.unsupported[1].range, 8..19);
+    }
+}
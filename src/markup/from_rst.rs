@@ -0,0 +1,334 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::{Part, PluginIdentifier};
+use crate::markup::format::OptionLike;
+use crate::markup::parse::{parse_option_like_part, Context};
+use regex::Regex;
+use std::borrow::Cow;
+use std::sync::LazyLock;
+
+static ROLE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new("^:([a-z]+):`").unwrap());
+
+static BLANK_LINE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new("\n[ \t]*\n").unwrap());
+
+/// Return the index of the next backtick in `text` at or after `from` that is not escaped.
+fn find_backtick(text: &str, from: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut index = from;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\\' => index += 2,
+            b'`' => return Some(index),
+            _ => index += 1,
+        }
+    }
+    None
+}
+
+/// Split `text <target>` into its text and target.
+fn split_target(content: &str) -> Option<(&str, &str)> {
+    let content = content.strip_suffix('>')?;
+    let index = content.rfind(" <")?;
+    Some((&content[..index], &content[index + 2..]))
+}
+
+/// Remove backslash escapes. Escaped whitespace vanishes, all other escaped characters are kept.
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some(' ' | '\n') => {}
+            Some(next) => result.push(next),
+            None => result.push(c),
+        }
+    }
+    result
+}
+
+/// An inline construct of RST markup, with backslash escapes removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Inline {
+    Text(String),
+    Role {
+        role: String,
+        content: String,
+    },
+    Reference {
+        text: String,
+        target: Option<String>,
+    },
+    Link {
+        text: String,
+        url: Option<String>,
+    },
+    HorizontalLine,
+}
+
+fn push_text(inlines: &mut Vec<Inline>, raw: &str) {
+    let text = unescape(raw);
+    if !text.is_empty() {
+        inlines.push(Inline::Text(text));
+    }
+}
+
+fn role(role: &str, content: &str) -> Inline {
+    if role != "ref" {
+        return Inline::Role {
+            role: role.to_string(),
+            content: unescape(content),
+        };
+    }
+    match split_target(content) {
+        Some((text, target)) => Inline::Reference {
+            text: unescape(text),
+            target: Some(unescape(target)),
+        },
+        None => Inline::Reference {
+            text: unescape(content),
+            target: None,
+        },
+    }
+}
+
+fn link(content: &str) -> Inline {
+    match split_target(content) {
+        Some((text, url)) => Inline::Link {
+            text: unescape(text),
+            url: Some(unescape(url)),
+        },
+        None => Inline::Link {
+            text: unescape(content),
+            url: None,
+        },
+    }
+}
+
+fn read_block(inlines: &mut Vec<Inline>, block: &str) {
+    let bytes = block.as_bytes();
+    let mut text_start = 0;
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\\' => {
+                index += 2;
+                continue;
+            }
+            b':' => {
+                if let Some(capture) = ROLE_RE.captures(&block[index..]) {
+                    let content_start = index + capture[0].len();
+                    if let Some(end) = find_backtick(block, content_start) {
+                        push_text(inlines, &block[text_start..index]);
+                        inlines.push(role(&capture[1], &block[content_start..end]));
+                        index = end + 1;
+                        text_start = index;
+                        continue;
+                    }
+                }
+            }
+            b'`' => {
+                if let Some(end) = find_backtick(block, index + 1) {
+                    let suffix = &block[end + 1..];
+                    let suffix_len = if suffix.starts_with("__") {
+                        2
+                    } else if suffix.starts_with('_') {
+                        1
+                    } else {
+                        0
+                    };
+                    if suffix_len > 0 {
+                        push_text(inlines, &block[text_start..index]);
+                        inlines.push(link(&block[index + 1..end]));
+                        index = end + 1 + suffix_len;
+                        text_start = index;
+                        continue;
+                    }
+                }
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    push_text(inlines, &block[text_start..]);
+}
+
+/// RST inline markup read with [`from_rst`].
+///
+/// The document owns the unescaped text; the parts returned by [`Self::parts`] borrow from it.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RSTDocument {
+    paragraphs: Vec<Vec<Inline>>,
+}
+
+fn reference<'a>(text: &'a str, target: Option<&'a str>) -> Part<'a> {
+    let Some(target) = target else {
+        return Part::RSTRef { text, r#ref: text };
+    };
+    let plugin_type = target
+        .strip_prefix("ansible_collections.")
+        .and_then(|rest| rest.strip_prefix(text))
+        .and_then(|rest| rest.strip_prefix('_'))
+        .filter(|plugin_type| !plugin_type.is_empty() && text.split('.').count() == 3);
+    match plugin_type {
+        Some("module") => Part::Module { fqcn: text },
+        Some(plugin_type) => Part::Plugin {
+            plugin: PluginIdentifier {
+                fqcn: text.to_string(),
+                r#type: plugin_type.to_string(),
+            },
+        },
+        None => Part::RSTRef {
+            text,
+            r#ref: target,
+        },
+    }
+}
+
+fn option_like<'a>(content: &'a str, context: &'a Context, what: OptionLike) -> Part<'a> {
+    parse_option_like_part(Cow::Borrowed(content), context, what)
+        .unwrap_or_else(|message| Part::Error { message })
+}
+
+impl RSTDocument {
+    fn part<'a>(inline: &'a Inline, context: &'a Context) -> Part<'a> {
+        match inline {
+            Inline::Text(text) => Part::Text { text },
+            Inline::HorizontalLine => Part::HorizontalLine,
+            Inline::Reference { text, target } => reference(text, target.as_deref()),
+            Inline::Link { text, url: None } => Part::Text { text },
+            Inline::Link {
+                text,
+                url: Some(url),
+            } if text == url => Part::URL { url },
+            Inline::Link {
+                text,
+                url: Some(url),
+            } => Part::Link { text, url },
+            Inline::Role { role, content } => match role.as_str() {
+                "literal" | "code" => Part::Code { text: content },
+                "emphasis" => Part::Italic { text: content },
+                "strong" => Part::Bold { text: content },
+                "ansval" => Part::OptionValue {
+                    value: Cow::Borrowed(content),
+                },
+                "envvar" => Part::EnvVariable {
+                    name: Cow::Borrowed(content),
+                },
+                "ansopt" => option_like(content, context, OptionLike::Option),
+                "ansretval" => option_like(content, context, OptionLike::RetVal),
+                _ => Part::Error {
+                    message: format!("Unsupported RST role :{}:", role),
+                },
+            },
+        }
+    }
+
+    /// Convert the document to parts.
+    ///
+    /// Option names and return values without a plugin are resolved relative to `context`, so
+    /// pass an empty context to keep them unqualified.
+    pub fn parts<'a>(&'a self, context: &'a Context) -> Vec<Vec<Part<'a>>> {
+        self.paragraphs
+            .iter()
+            .map(|paragraph| {
+                paragraph
+                    .iter()
+                    .map(|inline| RSTDocument::part(inline, context))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Read RST inline markup, as generated by the Antsibull RST formatter, to convert it back to
+/// parts with [`RSTDocument::parts`].
+///
+/// Paragraphs are separated by blank lines. The `raw` HTML block emitted for horizontal lines
+/// becomes a [`Part::HorizontalLine`] inside the surrounding paragraph. The roles `literal`,
+/// `emphasis`, `strong`, `ref`, `ansval`, `ansopt`, `ansretval`, and `envvar` are converted to
+/// their parts, and hyperlink references are converted to links and URLs. Other roles result in
+/// [`Part::Error`]; everything else is kept as text.
+pub fn from_rst(input: &str) -> RSTDocument {
+    let mut paragraphs = Vec::new();
+    let mut current: Option<Vec<Inline>> = None;
+    let mut continues = false;
+    let mut blocks = BLANK_LINE_RE.split(input).peekable();
+    while let Some(block) = blocks.next() {
+        if block.trim().is_empty() {
+            continue;
+        }
+        if block.trim() == ".. raw:: html" && blocks.peek().map(|b| b.trim()) == Some("<hr>") {
+            blocks.next();
+            current
+                .get_or_insert_with(Vec::new)
+                .push(Inline::HorizontalLine);
+            continues = true;
+            continue;
+        }
+        if !continues {
+            paragraphs.extend(current.take());
+        }
+        continues = false;
+        read_block(current.get_or_insert_with(Vec::new), block);
+    }
+    paragraphs.extend(current);
+    RSTDocument { paragraphs }
+}
+
+#[cfg(all(test, feature = "rst"))]
+mod tests {
+    use super::*;
+    use crate::markup::{
+        append_antsibull_rst_paragraphs, parse_paragraphs_without_sources, NoLinkProvider,
+        ParseOptions,
+    };
+
+    #[test]
+    fn test_from_rst_round_trip() {
+        let context = Context::new();
+        let sources = [
+            "This is a C(test) I(module) B(markup).",
+            "The M(a.b.c) module and the P(a.b.c#lookup) lookup.",
+            "foo E(FOOBAR) bar V( foo\\),bar\\\\bam ) C(a_b*c`d) I().",
+            "An U(https://example.com) and L(a link,https://example.org).",
+            "A R(RST reference,ansible_collections.community.general.ufw_module).",
+            "O(foo.bar.baz#role:main:foo=bar) RV(baz[1].bam) foo HORIZONTALLINE bar",
+            "",
+        ];
+        let expected = parse_paragraphs_without_sources(
+            sources.into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let mut rst = String::new();
+        append_antsibull_rst_paragraphs(
+            &mut rst,
+            expected.iter().map(|paragraph| paragraph.iter()),
+            &NoLinkProvider::new(),
+            &None,
+        );
+        assert_eq!(from_rst(&rst).parts(&context), expected);
+    }
+
+    #[test]
+    fn test_from_rst_unsupported() {
+        let context = Context::new();
+        assert_eq!(
+            from_rst(":foo:`bar` `baz` \\*").parts(&context),
+            vec![vec![
+                Part::Error {
+                    message: "Unsupported RST role :foo:".to_string()
+                },
+                Part::Text { text: " `baz` *" },
+            ]]
+        );
+    }
+}
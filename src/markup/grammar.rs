@@ -0,0 +1,201 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::parse::{describe_commands, CommandDescription};
+use crate::markup::ParseOptions;
+use crate::util::json::push_json_string;
+
+fn push_command(result: &mut String, command: &CommandDescription) {
+    result.push_str("{\"name\": ");
+    push_json_string(result, command.name);
+    result.push_str(", \"start\": ");
+    push_json_string(result, command.command_match);
+    result.push_str(", \"pattern\": ");
+    push_json_string(result, &command.pattern());
+    result.push_str(&format!(
+        ", \"parameters\": {}, \"escaped_arguments\": {}, \"semantic\": {}}}",
+        command.parameters, command.escaped_arguments, command.semantic
+    ));
+}
+
+/// Describe the grammar accepted by the parser selected by `opts` as a JSON object.
+///
+/// `commands` lists every command with its `name`, the `start` text, the regular expression
+/// `pattern` that matches the start, the number of `parameters`, whether the parameters use
+/// backslash escapes (`escaped_arguments`), and whether it is `semantic` markup.
+///
+/// `escaping` describes the parameters of commands with escapes: the escape `character`, the
+/// characters that can be `escapable` (`null` for all characters), and the `separator` and
+/// `terminator` of parameters, which can be surrounded by spaces. Parameters of commands without
+/// escapes end at the first `separator` or `terminator`.
+pub fn describe_grammar(opts: &ParseOptions) -> String {
+    let mut result = String::from("{\"commands\": [");
+    for (index, command) in describe_commands(opts).iter().enumerate() {
+        if index > 0 {
+            result.push_str(", ");
+        }
+        push_command(&mut result, command);
+    }
+    result.push_str("], \"escaping\": {\"character\": \"\\\\\", \"escapable\": ");
+    if opts.is_strict() {
+        result.push_str("[\"\\\\\", \")\"]");
+    } else {
+        result.push_str("null");
+    }
+    result.push_str(", \"separator\": \",\", \"terminator\": \")\"}}");
+    result
+}
+
+/// The scope name of the TextMate grammar, and the suffix of all its scopes.
+pub const TEXTMATE_SCOPE: &str = "ansible-doc-markup";
+
+/// The scope of the parameters of a command.
+fn parameter_scope(name: &str) -> &'static str {
+    match name {
+        "B" => "markup.bold",
+        "I" => "markup.italic",
+        "C" => "markup.inline.raw",
+        "M" | "P" => "entity.name.type.plugin",
+        "U" | "L" | "R" => "markup.underline.link",
+        "O" => "variable.parameter.option",
+        "V" => "constant.other.value",
+        "E" => "variable.other.environment",
+        "RV" => "variable.other.return-value",
+        _ => "string.unquoted.parameter",
+    }
+}
+
+fn push_scope(result: &mut String, scope: &str) {
+    push_json_string(result, &format!("{}.{}", scope, TEXTMATE_SCOPE));
+}
+
+fn push_textmate_rule(result: &mut String, command: &CommandDescription) {
+    let kind = if command.semantic {
+        "keyword.other.command.semantic"
+    } else {
+        "keyword.other.command.classic"
+    };
+    if command.parameters == 0 {
+        result.push_str("{\"match\": ");
+        push_json_string(result, &command.pattern());
+        result.push_str(", \"name\": ");
+        push_scope(result, kind);
+        result.push('}');
+        return;
+    }
+    let name = command
+        .command_match
+        .strip_suffix('(')
+        .unwrap_or(command.command_match);
+    result.push_str("{\"begin\": ");
+    push_json_string(result, &format!("\\b({})(\\()", regex::escape(name)));
+    result.push_str(", \"beginCaptures\": {\"1\": {\"name\": ");
+    push_scope(result, kind);
+    result.push_str("}, \"2\": {\"name\": ");
+    push_scope(result, "punctuation.definition.arguments.begin");
+    result.push_str("}}, \"end\": \"\\\\)\", \"endCaptures\": {\"0\": {\"name\": ");
+    push_scope(result, "punctuation.definition.arguments.end");
+    result.push_str("}}, \"contentName\": ");
+    push_scope(result, parameter_scope(command.name));
+    result.push_str(", \"patterns\": [");
+    let mut first = true;
+    if command.escaped_arguments {
+        result.push_str("{\"match\": \"\\\\\\\\.\", \"name\": ");
+        push_scope(result, "constant.character.escape");
+        result.push('}');
+        first = false;
+    }
+    if command.parameters > 1 {
+        if !first {
+            result.push_str(", ");
+        }
+        result.push_str("{\"match\": \",\", \"name\": ");
+        push_scope(result, "punctuation.separator.arguments");
+        result.push('}');
+    }
+    result.push_str("]}");
+}
+
+/// Generate a TextMate grammar (in the JSON format of `.tmLanguage.json` files) that
+/// highlights the commands known to the parser selected by `opts`.
+///
+/// Every command has a rule `command-<name>` in the repository. The command names, parameters,
+/// escapes, and parameter separators get their own scopes, all ending in [`TEXTMATE_SCOPE`].
+pub fn textmate_grammar(opts: &ParseOptions) -> String {
+    let commands = describe_commands(opts);
+    let mut result = String::from("{\"name\": \"Ansible documentation markup\", \"scopeName\": ");
+    push_scope(&mut result, "text");
+    result.push_str(", \"patterns\": [");
+    for (index, command) in commands.iter().enumerate() {
+        if index > 0 {
+            result.push_str(", ");
+        }
+        result.push_str("{\"include\": ");
+        push_json_string(&mut result, &format!("#command-{}", command.name));
+        result.push('}');
+    }
+    result.push_str("], \"repository\": {");
+    for (index, command) in commands.iter().enumerate() {
+        if index > 0 {
+            result.push_str(", ");
+        }
+        push_json_string(&mut result, &format!("command-{}", command.name));
+        result.push_str(": ");
+        push_textmate_rule(&mut result, command);
+    }
+    result.push_str("}}");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::Parser;
+
+    #[test]
+    fn test_describe_grammar() {
+        let opts = ParseOptions::default()
+            .strict()
+            .parser(Parser::new(&["L", "O", "HORIZONTALLINE"]).unwrap());
+        assert_eq!(
+            describe_grammar(&opts),
+            concat!(
+                r#"{"commands": ["#,
+                r#"{"name": "HORIZONTALLINE", "start": "HORIZONTALLINE", "pattern": "\\bHORIZONTALLINE\\b", "parameters": 0, "escaped_arguments": false, "semantic": false}, "#,
+                r#"{"name": "L", "start": "L(", "pattern": "\\bL\\(", "parameters": 2, "escaped_arguments": false, "semantic": false}, "#,
+                r#"{"name": "O", "start": "O(", "pattern": "\\bO\\(", "parameters": 1, "escaped_arguments": true, "semantic": true}], "#,
+                r#""escaping": {"character": "\\", "escapable": ["\\", ")"], "separator": ",", "terminator": ")"}}"#,
+            )
+        );
+        assert_eq!(
+            describe_commands(&ParseOptions::default().only_classic_markup()).len(),
+            8
+        );
+        assert!(describe_grammar(&ParseOptions::default()).contains("\"escapable\": null"));
+    }
+
+    #[test]
+    fn test_textmate_grammar() {
+        let opts = ParseOptions::default().parser(Parser::new(&["HORIZONTALLINE", "V"]).unwrap());
+        assert_eq!(
+            textmate_grammar(&opts),
+            concat!(
+                r#"{"name": "Ansible documentation markup", "scopeName": "text.ansible-doc-markup", "#,
+                r##""patterns": [{"include": "#command-HORIZONTALLINE"}, {"include": "#command-V"}], "##,
+                r#""repository": {"command-HORIZONTALLINE": {"match": "\\bHORIZONTALLINE\\b", "name": "keyword.other.command.classic.ansible-doc-markup"}, "#,
+                r#""command-V": {"begin": "\\b(V)(\\()", "#,
+                r#""beginCaptures": {"1": {"name": "keyword.other.command.semantic.ansible-doc-markup"}, "2": {"name": "punctuation.definition.arguments.begin.ansible-doc-markup"}}, "#,
+                r#""end": "\\)", "endCaptures": {"0": {"name": "punctuation.definition.arguments.end.ansible-doc-markup"}}, "#,
+                r#""contentName": "constant.other.value.ansible-doc-markup", "#,
+                r#""patterns": [{"match": "\\\\.", "name": "constant.character.escape.ansible-doc-markup"}]}}}"#,
+            )
+        );
+        let grammar = textmate_grammar(&ParseOptions::default());
+        assert!(grammar.contains(
+            r#""patterns": [{"match": ",", "name": "punctuation.separator.arguments.ansible-doc-markup"}]"#
+        ));
+    }
+}
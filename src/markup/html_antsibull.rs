@@ -7,20 +7,82 @@ SPDX-License-Identifier: GPL-3.0-or-later
 use crate::markup::dom;
 use crate::markup::format;
 use crate::markup::html_helper;
+use crate::markup::value_type::OptionValueType;
 use crate::util::stringbuilder::Appender;
+use std::borrow::Cow;
 use std::rc::Rc;
 use std::sync::LazyLock;
 
+/// Options of the antsibull-docs HTML formatter.
+///
+/// The defaults reproduce the output of antsibull-docs.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AntsibullHTMLOptions {
+    /// Whether to add a class like `ansible-value-boolean` to values of `V()`, depending on the
+    /// kind of value guessed by [`OptionValueType::classify`]. No class is added for
+    /// [`OptionValueType::Other`].
+    pub value_type_classes: bool,
+}
+
+impl AntsibullHTMLOptions {
+    pub fn new() -> AntsibullHTMLOptions {
+        AntsibullHTMLOptions::default()
+    }
+
+    /// Set whether to add classes for the kinds of values.
+    pub fn value_type_classes(mut self, value_type_classes: bool) -> Self {
+        self.value_type_classes = value_type_classes;
+        self
+    }
+}
+
 pub struct AntsibullHTMLFormatter {
     html_escaper: html_helper::HTMLEscaper,
     url_escaper: html_helper::URLEscaper,
+    options: AntsibullHTMLOptions,
 }
 
 impl AntsibullHTMLFormatter {
     fn new() -> AntsibullHTMLFormatter {
+        AntsibullHTMLFormatter::with_options(AntsibullHTMLOptions::new())
+    }
+
+    /// Create a formatter with custom options.
+    pub fn with_options(options: AntsibullHTMLOptions) -> AntsibullHTMLFormatter {
         AntsibullHTMLFormatter {
             html_escaper: html_helper::HTMLEscaper::new(),
             url_escaper: html_helper::URLEscaper::new(),
+            options,
+        }
+    }
+
+    /// The options of the formatter.
+    pub fn options(&self) -> &AntsibullHTMLOptions {
+        &self.options
+    }
+
+    /// The start tag of the value of `V()`.
+    fn option_value_start(&self, value: &str) -> &'static str {
+        if !self.options.value_type_classes {
+            return "<code class=\"ansible-value literal notranslate\">";
+        }
+        match OptionValueType::classify(value) {
+            OptionValueType::Boolean => {
+                "<code class=\"ansible-value ansible-value-boolean literal notranslate\">"
+            }
+            OptionValueType::Integer => {
+                "<code class=\"ansible-value ansible-value-integer literal notranslate\">"
+            }
+            OptionValueType::Float => {
+                "<code class=\"ansible-value ansible-value-float literal notranslate\">"
+            }
+            OptionValueType::QuotedString => {
+                "<code class=\"ansible-value ansible-value-quoted-string literal notranslate\">"
+            }
+            OptionValueType::List => {
+                "<code class=\"ansible-value ansible-value-list literal notranslate\">"
+            }
+            OptionValueType::Other => "<code class=\"ansible-value literal notranslate\">",
         }
     }
 
@@ -33,7 +95,7 @@ impl AntsibullHTMLFormatter {
         end: &'a str,
     ) {
         appender.push_str(start);
-        appender.push_cow_str(self.html_escaper.escape(text));
+        appender.push_cow_str(self.html_escaper.escape_text(text));
         appender.push_str(end);
     }
 
@@ -42,7 +104,7 @@ impl AntsibullHTMLFormatter {
         appender.push_str("<a href='");
         appender.push_cow_str(self.url_escaper.escape_with_html_escape(url));
         appender.push_str("'>");
-        appender.push_cow_str(self.html_escaper.escape(text));
+        appender.push_cow_str(self.html_escaper.escape_text(text));
         appender.push_str("</a>");
     }
 
@@ -59,12 +121,12 @@ impl AntsibullHTMLFormatter {
                 appender
                     .push_owned_string(self.url_escaper.escape_with_html_escape(u).into_owned());
                 appender.push_str("' class='module'>");
-                appender.push_cow_str(self.html_escaper.escape(fqcn));
+                appender.push_cow_str(self.html_escaper.escape_text(fqcn));
                 appender.push_str("</a>");
             }
             None => {
                 appender.push_str("<span class='module'>");
-                appender.push_cow_str(self.html_escaper.escape(fqcn));
+                appender.push_cow_str(self.html_escaper.escape_text(fqcn));
                 appender.push_str("</span>");
             }
         }
@@ -74,8 +136,8 @@ impl AntsibullHTMLFormatter {
     fn append_option_like<'a>(
         &self,
         appender: &mut dyn Appender<'a>,
-        name: &'a String,
-        value: &'a Option<String>,
+        name: &'a str,
+        value: &'a Option<Cow<'a, str>>,
         what: format::OptionLike,
         url: &Option<String>,
     ) {
@@ -98,10 +160,10 @@ impl AntsibullHTMLFormatter {
             appender.push_owned_string(self.url_escaper.escape_with_html_escape(u).into_owned());
             appender.push_str("\"><span class=\"std std-ref\"><span class=\"pre\">");
         }
-        appender.push_cow_str(self.html_escaper.escape(name));
+        appender.push_cow_str(self.html_escaper.escape_text(name));
         if let Some(v) = value {
             appender.push_str("=");
-            appender.push_cow_str(self.html_escaper.escape(v));
+            appender.push_cow_str(self.html_escaper.escape_text(v));
         }
         if let Some(_) = url {
             appender.push_str("</span></span></a>");
@@ -121,7 +183,7 @@ impl<'a> format::Formatter<'a> for AntsibullHTMLFormatter {
         url: Option<String>,
     ) {
         match part {
-            dom::Part::Text { text } => appender.push_cow_str(self.html_escaper.escape(text)),
+            dom::Part::Text { text } => appender.push_cow_str(self.html_escaper.escape_text(text)),
             dom::Part::Bold { text } => self.append_tag(appender, "<b>", text, "</b>"),
             dom::Part::Italic { text } => self.append_tag(appender, "<em>", text, "</em>"),
             dom::Part::Code { text } => self.append_tag(
@@ -131,21 +193,33 @@ impl<'a> format::Formatter<'a> for AntsibullHTMLFormatter {
                 "</code>",
             ),
             dom::Part::HorizontalLine => appender.push_str("<hr/>"),
-            dom::Part::OptionValue { value } => self.append_tag(
-                appender,
-                "<code class=\"ansible-value literal notranslate\">",
-                value,
-                "</code>",
-            ),
-            dom::Part::EnvVariable { name } => self.append_tag(
-                appender,
-                "<code class=\"xref std std-envvar literal notranslate\">",
-                name,
-                "</code>",
-            ),
+            dom::Part::OptionValue { value } => {
+                self.append_tag(appender, self.option_value_start(value), value, "</code>")
+            }
+            dom::Part::EnvVariable { name } => match url {
+                Some(u) => {
+                    appender.push_str("<a class=\"reference external\" href=\"");
+                    appender.push_owned_string(
+                        self.url_escaper.escape_with_html_escape(&u).into_owned(),
+                    );
+                    appender.push_str("\">");
+                    self.append_tag(
+                        appender,
+                        "<code class=\"xref std std-envvar literal notranslate\">",
+                        name,
+                        "</code></a>",
+                    );
+                }
+                None => self.append_tag(
+                    appender,
+                    "<code class=\"xref std std-envvar literal notranslate\">",
+                    name,
+                    "</code>",
+                ),
+            },
             dom::Part::Error { message } => {
                 appender.push_str("<span class=\"error\">ERROR while parsing: ");
-                appender.push_cow_str(self.html_escaper.escape(message));
+                appender.push_cow_str(self.html_escaper.escape_text(message));
                 appender.push_str("</span>");
             }
             dom::Part::RSTRef { text, r#ref: _ } => {
@@ -223,3 +297,40 @@ pub fn append_antsibull_html_paragraphs<'a, I, II>(
         current_plugin,
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::format::Formatter;
+
+    #[test]
+    fn test_value_type_classes() {
+        let render = |formatter: &AntsibullHTMLFormatter, value: &'static str| {
+            let part: &'static dom::Part<'static> = Box::leak(Box::new(dom::Part::OptionValue {
+                value: Cow::Borrowed(value),
+            }));
+            let mut result = String::new();
+            formatter.append(&mut result, part, None);
+            result
+        };
+        let formatter = AntsibullHTMLFormatter::with_options(
+            AntsibullHTMLOptions::new().value_type_classes(true),
+        );
+        assert_eq!(
+            render(&formatter, "true"),
+            "<code class=\"ansible-value ansible-value-boolean literal notranslate\">true</code>"
+        );
+        assert_eq!(
+            render(&formatter, "42"),
+            "<code class=\"ansible-value ansible-value-integer literal notranslate\">42</code>"
+        );
+        assert_eq!(
+            render(&formatter, "present"),
+            "<code class=\"ansible-value literal notranslate\">present</code>"
+        );
+        assert_eq!(
+            render(&ANTSIBULL_HTML_FORMATTER, "true"),
+            "<code class=\"ansible-value literal notranslate\">true</code>"
+        );
+    }
+}
@@ -0,0 +1,184 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::PluginIdentifier;
+use crate::markup::format::NoLinkProvider;
+use crate::markup::html_helper::HTMLEscaper;
+use crate::markup::parse::{parse_paragraphs, Context, ParseOptions};
+use crate::markup::renderer::Renderer;
+use crate::markup::untrusted::UntrustedProfile;
+use crate::util::html_audit::{audit_html, HtmlAllowList};
+use std::rc::Rc;
+
+/// The rules of the stylesheet included in fragments. `{}` is replaced by the wrapper's class.
+const STYLE_RULES: &[&str] = &[
+    ".{} code { font-family: monospace; background: #f5f5f5; padding: 0 0.2em; border-radius: 3px; }",
+    ".{} .ansible-option strong { font-weight: bold; }",
+    ".{} .ansible-value, .{} .ansible-return-value { color: #1a6b1a; }",
+    ".{} .xref { color: #6b1a6b; }",
+    ".{} .module { font-style: italic; }",
+    ".{} .error { color: #c00; font-weight: bold; }",
+];
+
+/// Options for [`render_html_fragment`] and [`render_sanitized_html_fragment`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtmlFragmentOptions {
+    /// The plugin the markup belongs to. Options and return values without plugin refer to it.
+    pub current_plugin: Option<PluginIdentifier>,
+
+    /// The role entrypoint the markup belongs to.
+    pub role_entrypoint: Option<String>,
+
+    /// Whether to use the plain HTML formatter instead of the one with antsibull-docs' classes.
+    pub plain: bool,
+
+    /// The class of the `<div>` that wraps the fragment.
+    pub class: String,
+
+    /// Whether to include a `<style>` element that styles the classes of the fragment.
+    pub style: bool,
+}
+
+impl HtmlFragmentOptions {
+    /// Antsibull HTML wrapped in `<div class="ansible-markup">`, with a stylesheet.
+    pub fn new() -> HtmlFragmentOptions {
+        HtmlFragmentOptions {
+            current_plugin: None,
+            role_entrypoint: None,
+            plain: false,
+            class: "ansible-markup".to_string(),
+            style: true,
+        }
+    }
+
+    /// Set the plugin the markup belongs to.
+    pub fn current_plugin(mut self, fqcn: &str, r#type: &str) -> Self {
+        self.current_plugin = Some(PluginIdentifier {
+            fqcn: fqcn.to_string(),
+            r#type: r#type.to_string(),
+        });
+        self
+    }
+
+    /// Set the role entrypoint the markup belongs to.
+    pub fn role_entrypoint(mut self, role_entrypoint: &str) -> Self {
+        self.role_entrypoint = Some(role_entrypoint.to_string());
+        self
+    }
+
+    /// Use the plain HTML formatter.
+    pub fn plain(mut self, plain: bool) -> Self {
+        self.plain = plain;
+        self
+    }
+
+    /// Set the class of the wrapping `<div>`. It must be a valid CSS class name.
+    pub fn class(mut self, class: &str) -> Self {
+        self.class = class.to_string();
+        self
+    }
+
+    /// Set whether to include a stylesheet.
+    pub fn style(mut self, style: bool) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+impl Default for HtmlFragmentOptions {
+    fn default() -> Self {
+        HtmlFragmentOptions::new()
+    }
+}
+
+fn render_fragment(markup: &str, options: &HtmlFragmentOptions, sanitize: bool) -> String {
+    let current_plugin = options.current_plugin.clone().map(Rc::new);
+    let context = Context {
+        current_plugin: current_plugin.clone(),
+        role_entrypoint: options.role_entrypoint.clone().map(Rc::new),
+        ..Context::new()
+    };
+    let paragraphs = parse_paragraphs([markup].into_iter(), &context, &ParseOptions::default());
+    let link_provider = NoLinkProvider::new();
+    let renderer = if options.plain {
+        Renderer::plain_html(&link_provider)
+    } else {
+        Renderer::antsibull_html(&link_provider)
+    }
+    .with_current_plugin(current_plugin);
+    let escaper = HTMLEscaper::new();
+    let mut body = if sanitize {
+        let profile = UntrustedProfile::new().max_output_length(None);
+        renderer.render_untrusted(&profile, &paragraphs).output
+    } else {
+        renderer.render(&paragraphs)
+    };
+    if sanitize && !audit_html(&body, &HtmlAllowList::antsibull()).is_empty() {
+        body = format!("<p>{}</p>", escaper.escape_text(markup));
+    }
+
+    let class = escaper.escape_attr_double(&options.class);
+    let mut result = String::new();
+    if options.style {
+        result.push_str("<style>");
+        for rule in STYLE_RULES {
+            result.push_str(&rule.replace("{}", &class));
+        }
+        result.push_str("</style>");
+    }
+    result.push_str("<div class=\"");
+    result.push_str(&class);
+    result.push_str("\">");
+    result.push_str(&body);
+    result.push_str("</div>");
+    result
+}
+
+/// Render a paragraph of markup as a self-contained HTML fragment, for example for a notebook
+/// cell or a tooltip.
+///
+/// The output is wrapped in a `<div>` with the class configured in `options`, and is preceded by
+/// a stylesheet for the classes used by the formatter unless disabled. Links to plugins and
+/// options are not created.
+pub fn render_html_fragment(markup: &str, options: &HtmlFragmentOptions) -> String {
+    render_fragment(markup, options, false)
+}
+
+/// Render a paragraph of markup from an untrusted source as a self-contained HTML fragment.
+///
+/// Like [`render_html_fragment`], except that the markup is sanitized with
+/// [`sanitize_untrusted`](crate::markup::sanitize_untrusted), and that the rendered HTML is
+/// checked against [`HtmlAllowList::antsibull`]. If the check fails, the markup is shown as
+/// escaped text instead.
+pub fn render_sanitized_html_fragment(markup: &str, options: &HtmlFragmentOptions) -> String {
+    render_fragment(markup, options, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_html_fragment() {
+        let options = HtmlFragmentOptions::new().style(false);
+        assert_eq!(
+            render_html_fragment("Set O(state=present) and see U(https://example.com).", &options),
+            "<div class=\"ansible-markup\"><p>Set <code class=\"ansible-option-value literal notranslate\">state=present</code> and see <a href='https://example.com'>https://example.com</a>.</p></div>"
+        );
+        assert_eq!(
+            render_sanitized_html_fragment(
+                "See L(here,https://example.com) and I(x\u{202e}y).",
+                &options.clone().plain(true).class("x\"y")
+            ),
+            "<div class=\"x&quot;y\"><p>See here and <em>xy</em>.</p></div>"
+        );
+        let html = render_html_fragment("C(x)", &HtmlFragmentOptions::new().class("doc"));
+        assert!(html.starts_with("<style>.doc code { font-family: monospace;"));
+        assert!(html.ends_with(
+            "</style><div class=\"doc\"><p><code class='docutils literal notranslate'>x</code></p></div>"
+        ));
+    }
+}
@@ -4,7 +4,9 @@ SPDX-FileCopyrightText: 2024, Felix Fontein
 SPDX-License-Identifier: GPL-3.0-or-later
 */
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::{String, ToString};
 
 #[inline(always)]
 fn is_url_safe(c: u8) -> bool {
@@ -15,11 +17,29 @@ fn is_url_safe(c: u8) -> bool {
     )
 }
 
+#[inline(always)]
+fn is_url_component_safe(c: u8) -> bool {
+    matches!(
+        c,
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'!' | b'~' | b'*' | b'\'' | b'(' | b')'
+    )
+}
+
 #[inline(always)]
 fn is_html_safe(c: u8) -> bool {
     !matches!(c, b'<' | b'>' | b'&')
 }
 
+#[inline(always)]
+fn is_html_attr_double_safe(c: u8) -> bool {
+    !matches!(c, b'<' | b'>' | b'&' | b'"')
+}
+
+#[inline(always)]
+fn is_html_attr_single_safe(c: u8) -> bool {
+    !matches!(c, b'<' | b'>' | b'&' | b'\'')
+}
+
 #[inline(always)]
 fn hex_digit(value: u8) -> u8 {
     // `encodeURI()` uses upper-case hex digits
@@ -35,46 +55,137 @@ fn alloc_string(length: usize) -> String {
     String::with_capacity(length | 15)
 }
 
-pub struct URLEscaper {}
+fn percent_encode(url: &str, is_safe: fn(u8) -> bool) -> Cow<'_, str> {
+    let length = url.len();
+    let mut index = 0;
+    let mut result = alloc_string(length);
+    loop {
+        let mut next_index = index;
+        while next_index < length && is_safe(url.as_bytes()[next_index]) {
+            next_index += 1;
+        }
+        if index == 0 && next_index == length {
+            return Cow::Borrowed(url);
+        }
+        if index < next_index {
+            result.push_str(&url[index..next_index]);
+        }
+        if next_index == length {
+            result.shrink_to_fit();
+            return Cow::Owned(result);
+        }
+        let c = url.as_bytes()[next_index];
+        let enc = &[b'%', hex_digit(c >> 4), hex_digit(c & 15)];
+        result.push_str(unsafe { core::str::from_utf8_unchecked(enc) });
+        index = next_index + 1;
+    }
+}
+
+/// Convert the host name of an URL to its ASCII form if it contains non-ASCII characters.
+///
+/// The host is processed according to UTS #46, which normalizes it to NFC and maps it to lower
+/// case before encoding it with Punycode. Only the host is changed; the rest of the URL is
+/// percent-encoded as usual afterwards. Hosts that are not valid domain names are kept.
+#[cfg(feature = "idn")]
+fn encode_idn_host(url: &str) -> Cow<'_, str> {
+    let Some(scheme_end) = url.find("://") else {
+        return Cow::Borrowed(url);
+    };
+    let authority_start = scheme_end + 3;
+    let authority_end = url[authority_start..]
+        .find(['/', '?', '#'])
+        .map_or(url.len(), |index| authority_start + index);
+    let authority = &url[authority_start..authority_end];
+    let host_start = authority.rfind('@').map_or(0, |index| index + 1);
+    let host_end = match authority[host_start..].rfind(':') {
+        Some(index)
+            if authority[host_start + index + 1..]
+                .bytes()
+                .all(|c| c.is_ascii_digit()) =>
+        {
+            host_start + index
+        }
+        _ => authority.len(),
+    };
+    let host = &authority[host_start..host_end];
+    if host.is_ascii() {
+        return Cow::Borrowed(url);
+    }
+    let Ok(ascii_host) = idna::domain_to_ascii(host) else {
+        return Cow::Borrowed(url);
+    };
+    let offset = authority_start + host_start;
+    Cow::Owned(format!(
+        "{}{}{}",
+        &url[..offset],
+        ascii_host,
+        &url[authority_start + host_end..]
+    ))
+}
+
+pub struct URLEscaper {
+    #[cfg(feature = "idn")]
+    idn_hosts: bool,
+}
 
 impl URLEscaper {
+    /// Create an escaper. Internationalized host names are converted to Punycode if the `idn`
+    /// feature is enabled.
     pub fn new() -> URLEscaper {
-        URLEscaper {}
+        URLEscaper {
+            #[cfg(feature = "idn")]
+            idn_hosts: true,
+        }
+    }
+
+    /// Create an escaper that converts internationalized host names to Punycode if `idn_hosts`
+    /// is `true`, instead of percent-encoding them like the rest of the URL.
+    #[cfg(feature = "idn")]
+    pub fn with_idn_hosts(idn_hosts: bool) -> URLEscaper {
+        URLEscaper { idn_hosts }
+    }
+
+    fn prepare<'a>(&self, url: &'a str) -> Cow<'a, str> {
+        #[cfg(feature = "idn")]
+        if self.idn_hosts {
+            return encode_idn_host(url);
+        }
+        Cow::Borrowed(url)
     }
 
     /// Percent encode an URL similar to JavaScript's `encodeURI()` method.
     ///
     /// See [the MDN page for `encodeURI()`](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/encodeURI).
     pub fn escape<'a>(&self, url: &'a str) -> Cow<'a, str> {
-        let length = url.len();
-        let mut index = 0;
-        let mut result = alloc_string(length);
-        loop {
-            let mut next_index = index;
-            while next_index < length && is_url_safe(url.as_bytes()[next_index]) {
-                next_index += 1;
-            }
-            if index == 0 && next_index == length {
-                return Cow::Borrowed(url);
-            }
-            if index < next_index {
-                result.push_str(&url[index..next_index]);
-            }
-            if next_index == length {
-                result.shrink_to_fit();
-                return Cow::Owned(result);
-            }
-            let c = url.as_bytes()[next_index];
-            let enc = &[b'%', hex_digit(c >> 4), hex_digit(c & 15)];
-            result.push_str(unsafe { std::str::from_utf8_unchecked(enc) });
-            index = next_index + 1;
+        match self.prepare(url) {
+            Cow::Borrowed(url) => percent_encode(url, is_url_safe),
+            Cow::Owned(url) => Cow::Owned(percent_encode(&url, is_url_safe).into_owned()),
         }
     }
 
+    /// Percent encode an URL component similar to JavaScript's `encodeURIComponent()` method.
+    ///
+    /// In contrast to escape(), this also encodes the characters with a special meaning in URLs,
+    /// like `/`, `?`, `#`, `&`, and `=`, so that the result can be used as a path segment, query
+    /// parameter name or value, or fragment.
+    ///
+    /// See [the MDN page for `encodeURIComponent()`](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/encodeURIComponent).
+    pub fn escape_component<'a>(&self, component: &'a str) -> Cow<'a, str> {
+        percent_encode(component, is_url_component_safe)
+    }
+
     /// Percent encode an URL similar to JavaScript's `encodeURI()` method, and then HTML escape the result.
     ///
-    /// The only difference to escape() is that '&' is escaped to '&amp;'.
+    /// The only differences to escape() are that '&' is escaped to '&amp;' and '\'' is escaped
+    /// to '&#x27;', so that the result can be used in both single- and double-quoted attributes.
     pub fn escape_with_html_escape<'a>(&self, url: &'a str) -> Cow<'a, str> {
+        match self.prepare(url) {
+            Cow::Borrowed(url) => self.percent_encode_with_html_escape(url),
+            Cow::Owned(url) => Cow::Owned(self.percent_encode_with_html_escape(&url).into_owned()),
+        }
+    }
+
+    fn percent_encode_with_html_escape<'a>(&self, url: &'a str) -> Cow<'a, str> {
         let length = url.len();
         let mut index = 0;
         let mut result = alloc_string(length);
@@ -82,7 +193,7 @@ impl URLEscaper {
             let mut next_index = index;
             while next_index < length
                 && is_url_safe(url.as_bytes()[next_index])
-                && is_html_safe(url.as_bytes()[next_index])
+                && is_html_attr_single_safe(url.as_bytes()[next_index])
             {
                 next_index += 1;
             }
@@ -99,15 +210,72 @@ impl URLEscaper {
             let c = url.as_bytes()[next_index];
             if c == b'&' {
                 result.push_str("&amp;");
+            } else if c == b'\'' {
+                result.push_str("&#x27;");
             } else {
                 let enc = &[b'%', hex_digit(c >> 4), hex_digit(c & 15)];
-                result.push_str(unsafe { std::str::from_utf8_unchecked(enc) });
+                result.push_str(unsafe { core::str::from_utf8_unchecked(enc) });
             }
             index = next_index + 1;
         }
     }
 }
 
+/// Builds an URL with query parameters and a fragment.
+///
+/// Parameter names, values, and the fragment are encoded with `encodeURIComponent()` semantics,
+/// so they can contain characters like spaces, `#`, `&`, or `[]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct URLQueryBuilder {
+    url: String,
+    has_query: bool,
+    fragment: Option<String>,
+}
+
+impl URLQueryBuilder {
+    /// Start with `base`, which is used as-is. It can already contain a query and a fragment.
+    pub fn new(base: &str) -> URLQueryBuilder {
+        let (url, fragment) = match base.split_once('#') {
+            Some((url, fragment)) => (url, Some(fragment.to_string())),
+            None => (base, None),
+        };
+        URLQueryBuilder {
+            url: url.to_string(),
+            has_query: url.contains('?'),
+            fragment,
+        }
+    }
+
+    /// Append the query parameter `name` with the value `value`.
+    pub fn param(mut self, name: &str, value: &str) -> URLQueryBuilder {
+        let escaper = URLEscaper::new();
+        if !self.has_query {
+            self.url.push('?');
+            self.has_query = true;
+        } else if !self.url.ends_with(['?', '&']) {
+            self.url.push('&');
+        }
+        self.url.push_str(&escaper.escape_component(name));
+        self.url.push('=');
+        self.url.push_str(&escaper.escape_component(value));
+        self
+    }
+
+    /// Set the fragment, replacing a fragment of the base URL.
+    pub fn fragment(mut self, fragment: &str) -> URLQueryBuilder {
+        self.fragment = Some(URLEscaper::new().escape_component(fragment).into_owned());
+        self
+    }
+
+    /// The resulting URL.
+    pub fn build(self) -> String {
+        match self.fragment {
+            Some(fragment) => format!("{}#{}", self.url, fragment),
+            None => self.url,
+        }
+    }
+}
+
 pub struct HTMLEscaper {}
 
 impl HTMLEscaper {
@@ -115,35 +283,58 @@ impl HTMLEscaper {
         HTMLEscaper {}
     }
 
-    /// Escape HTML.
-    pub fn escape<'a>(&self, url: &'a str) -> Cow<'a, str> {
-        let length = url.len();
-        let mut index = 0;
-        let mut result = alloc_string(length);
-        loop {
-            let mut next_index = index;
-            while next_index < length && is_html_safe(url.as_bytes()[next_index]) {
-                next_index += 1;
-            }
-            if index == 0 && next_index == length {
-                return Cow::Borrowed(url);
-            }
-            if index < next_index {
-                result.push_str(&url[index..next_index]);
-            }
-            if next_index == length {
-                result.shrink_to_fit();
-                return Cow::Owned(result);
-            }
-            let c = url.as_bytes()[next_index];
-            result.push_str(match c {
-                b'<' => "&lt;",
-                b'>' => "&gt;",
-                b'&' => "&amp;",
-                _ => "",
-            });
-            index = next_index + 1;
+    /// Escape HTML. This is the same as [`HTMLEscaper::escape_text`].
+    pub fn escape<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        html_escape(text, is_html_safe)
+    }
+
+    /// Escape text content of an element.
+    ///
+    /// Quotes are not escaped, so the result must not be used inside attribute values.
+    pub fn escape_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        html_escape(text, is_html_safe)
+    }
+
+    /// Escape text for use in an attribute value delimited by double quotes.
+    pub fn escape_attr_double<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        html_escape(text, is_html_attr_double_safe)
+    }
+
+    /// Escape text for use in an attribute value delimited by single quotes.
+    pub fn escape_attr_single<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        html_escape(text, is_html_attr_single_safe)
+    }
+}
+
+fn html_escape(text: &str, is_safe: fn(u8) -> bool) -> Cow<'_, str> {
+    let length = text.len();
+    let mut index = 0;
+    let mut result = alloc_string(length);
+    loop {
+        let mut next_index = index;
+        while next_index < length && is_safe(text.as_bytes()[next_index]) {
+            next_index += 1;
+        }
+        if index == 0 && next_index == length {
+            return Cow::Borrowed(text);
+        }
+        if index < next_index {
+            result.push_str(&text[index..next_index]);
+        }
+        if next_index == length {
+            result.shrink_to_fit();
+            return Cow::Owned(result);
         }
+        let c = text.as_bytes()[next_index];
+        result.push_str(match c {
+            b'<' => "&lt;",
+            b'>' => "&gt;",
+            b'&' => "&amp;",
+            b'"' => "&quot;",
+            b'\'' => "&#x27;",
+            _ => "",
+        });
+        index = next_index + 1;
     }
 }
 
@@ -185,6 +376,55 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg(feature = "idn")]
+    fn test_idn_hosts() {
+        let e = URLEscaper::with_idn_hosts(true);
+        assert_eq!(
+            e.escape("https://user@Bücher.example:8080/straße?q=ä#x"),
+            "https://user@xn--bcher-kva.example:8080/stra%C3%9Fe?q=%C3%A4#x"
+        );
+        assert_eq!(
+            e.escape_with_html_escape("http://例え.テスト/?a=1&b=2"),
+            "http://xn--r8jz45g.xn--zckzah/?a=1&amp;b=2"
+        );
+        assert_eq!(e.escape("/relative/ä"), "/relative/%C3%A4");
+        // The decomposed form of "bücher" is normalized before encoding.
+        assert_eq!(
+            e.escape("https://bu\u{308}cher.example/"),
+            "https://xn--bcher-kva.example/"
+        );
+        assert_eq!(
+            URLEscaper::with_idn_hosts(false).escape("https://bücher.example/"),
+            "https://b%C3%BCcher.example/"
+        );
+    }
+
+    #[test]
+    fn test_url_escape_component() {
+        let e = URLEscaper::new();
+        assert_eq!(e.escape_component("foo_bar-1.2"), "foo_bar-1.2");
+        assert_eq!(
+            e.escape_component("a b/c?d#e&f=g[h]"),
+            "a%20b%2Fc%3Fd%23e%26f%3Dg%5Bh%5D"
+        );
+        assert_eq!(e.escape_component("ä"), "%C3%A4");
+
+        assert_eq!(
+            URLQueryBuilder::new("https://example.com/search.html")
+                .param("q", "foo bar[0]")
+                .param("type", "option#1")
+                .fragment("parameter-foo/bar baz")
+                .build(),
+            "https://example.com/search.html?q=foo%20bar%5B0%5D&type=option%231#parameter-foo%2Fbar%20baz"
+        );
+        assert_eq!(
+            URLQueryBuilder::new("/s?x=1#top").param("y", "&").build(),
+            "/s?x=1&y=%26#top"
+        );
+        assert_eq!(URLQueryBuilder::new("/s?").param("y", "").build(), "/s?y=");
+    }
+
     #[test]
     fn test_html_escape() {
         let e = HTMLEscaper::new();
@@ -192,5 +432,18 @@ mod tests {
         assert_eq!(e.escape("test"), "test");
         assert_eq!(e.escape("<foo>"), "&lt;foo&gt;");
         assert_eq!(e.escape("<f&o>"), "&lt;f&amp;o&gt;");
+        assert_eq!(e.escape_text("a \"b\" 'c'"), "a \"b\" 'c'");
+        assert_eq!(
+            e.escape_attr_double("a \"b\" 'c' <&>"),
+            "a &quot;b&quot; 'c' &lt;&amp;&gt;"
+        );
+        assert_eq!(
+            e.escape_attr_single("a \"b\" 'c' <&>"),
+            "a \"b\" &#x27;c&#x27; &lt;&amp;&gt;"
+        );
+        assert_eq!(
+            URLEscaper::new().escape_with_html_escape("https://x/it's?a=\"b\""),
+            "https://x/it&#x27;s?a=%22b%22"
+        );
     }
 }
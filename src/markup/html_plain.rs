@@ -8,6 +8,7 @@ use crate::markup::dom;
 use crate::markup::format;
 use crate::markup::html_helper;
 use crate::util::stringbuilder::Appender;
+use std::borrow::Cow;
 use std::rc::Rc;
 use std::sync::LazyLock;
 
@@ -33,7 +34,7 @@ impl PlainHTMLFormatter {
         end: &'a str,
     ) {
         appender.push_str(start);
-        appender.push_cow_str(self.html_escaper.escape(text));
+        appender.push_cow_str(self.html_escaper.escape_text(text));
         appender.push_str(end);
     }
 
@@ -42,7 +43,7 @@ impl PlainHTMLFormatter {
         appender.push_str("<a href='");
         appender.push_cow_str(self.url_escaper.escape_with_html_escape(url));
         appender.push_str("'>");
-        appender.push_cow_str(self.html_escaper.escape(text));
+        appender.push_cow_str(self.html_escaper.escape_text(text));
         appender.push_str("</a>");
     }
 
@@ -59,12 +60,12 @@ impl PlainHTMLFormatter {
                 appender
                     .push_owned_string(self.url_escaper.escape_with_html_escape(u).into_owned());
                 appender.push_str("'>");
-                appender.push_cow_str(self.html_escaper.escape(fqcn));
+                appender.push_cow_str(self.html_escaper.escape_text(fqcn));
                 appender.push_str("</a>");
             }
             None => {
                 appender.push_str("<span>");
-                appender.push_cow_str(self.html_escaper.escape(fqcn));
+                appender.push_cow_str(self.html_escaper.escape_text(fqcn));
                 appender.push_str("</span>");
             }
         }
@@ -74,8 +75,8 @@ impl PlainHTMLFormatter {
     fn append_option_like<'a>(
         &self,
         appender: &mut dyn Appender<'a>,
-        name: &'a String,
-        value: &'a Option<String>,
+        name: &'a str,
+        value: &'a Option<Cow<'a, str>>,
         what: format::OptionLike,
         url: &Option<String>,
     ) {
@@ -89,10 +90,10 @@ impl PlainHTMLFormatter {
             appender.push_owned_string(self.url_escaper.escape_with_html_escape(u).into_owned());
             appender.push_str("\">");
         }
-        appender.push_cow_str(self.html_escaper.escape(name));
+        appender.push_cow_str(self.html_escaper.escape_text(name));
         if let Some(v) = value {
             appender.push_str("=");
-            appender.push_cow_str(self.html_escaper.escape(v));
+            appender.push_cow_str(self.html_escaper.escape_text(v));
         }
         if let Some(_) = url {
             appender.push_str("</a>");
@@ -112,7 +113,7 @@ impl<'a> format::Formatter<'a> for PlainHTMLFormatter {
         url: Option<String>,
     ) {
         match part {
-            dom::Part::Text { text } => appender.push_cow_str(self.html_escaper.escape(text)),
+            dom::Part::Text { text } => appender.push_cow_str(self.html_escaper.escape_text(text)),
             dom::Part::Bold { text } => self.append_tag(appender, "<b>", text, "</b>"),
             dom::Part::Italic { text } => self.append_tag(appender, "<em>", text, "</em>"),
             dom::Part::Code { text } => self.append_tag(appender, "<code>", text, "</code>"),
@@ -120,10 +121,20 @@ impl<'a> format::Formatter<'a> for PlainHTMLFormatter {
             dom::Part::OptionValue { value } => {
                 self.append_tag(appender, "<code>", value, "</code>")
             }
-            dom::Part::EnvVariable { name } => self.append_tag(appender, "<code>", name, "</code>"),
+            dom::Part::EnvVariable { name } => match url {
+                Some(u) => {
+                    appender.push_str("<a href='");
+                    appender.push_owned_string(
+                        self.url_escaper.escape_with_html_escape(&u).into_owned(),
+                    );
+                    appender.push_str("'>");
+                    self.append_tag(appender, "<code>", name, "</code></a>");
+                }
+                None => self.append_tag(appender, "<code>", name, "</code>"),
+            },
             dom::Part::Error { message } => {
                 appender.push_str("<span class=\"error\">ERROR while parsing: ");
-                appender.push_cow_str(self.html_escaper.escape(message));
+                appender.push_cow_str(self.html_escaper.escape_text(message));
                 appender.push_str("</span>");
             }
             dom::Part::RSTRef { text, r#ref: _ } => {
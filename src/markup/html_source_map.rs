@@ -0,0 +1,144 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::PartWithSource;
+use crate::markup::renderer::Renderer;
+
+/// Which source information [`Renderer::render_html_with_source_map`] adds to the HTML output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HtmlSourceMap {
+    /// Whether to wrap every part in a `<span>` with `data-source-start` and `data-source-end`
+    /// attributes, the byte offsets of the part's source in its paragraph.
+    pub part_offsets: bool,
+
+    /// Whether to add a `data-paragraph` attribute with the index of the paragraph, starting
+    /// with 0, to the start tag of every paragraph.
+    pub paragraph_index: bool,
+}
+
+impl HtmlSourceMap {
+    /// Add both offsets of parts and indices of paragraphs.
+    pub fn new() -> HtmlSourceMap {
+        HtmlSourceMap {
+            part_offsets: true,
+            paragraph_index: true,
+        }
+    }
+
+    /// Set whether to add the offsets of parts.
+    pub fn part_offsets(mut self, part_offsets: bool) -> Self {
+        self.part_offsets = part_offsets;
+        self
+    }
+
+    /// Set whether to add the indices of paragraphs.
+    pub fn paragraph_index(mut self, paragraph_index: bool) -> Self {
+        self.paragraph_index = paragraph_index;
+        self
+    }
+}
+
+impl Default for HtmlSourceMap {
+    fn default() -> Self {
+        HtmlSourceMap::new()
+    }
+}
+
+impl Renderer<'_> {
+    /// Render parsed paragraphs to HTML, and annotate the output with the location of the
+    /// source of paragraphs and parts, for example to jump from the output to the markup in a
+    /// preview tool.
+    ///
+    /// The paragraph index is added to the start sequence of the paragraphs if it is an HTML
+    /// start tag, like the `<p>` of the HTML renderers.
+    pub fn render_html_with_source_map(
+        &self,
+        paragraphs: &[Vec<PartWithSource<'_>>],
+        source_map: &HtmlSourceMap,
+    ) -> String {
+        let options = &self.options;
+        let par_start = options.par_start.strip_suffix('>').filter(|start| {
+            source_map.paragraph_index && start.starts_with('<') && !start.contains('>')
+        });
+        let mut result = String::new();
+        for (index, paragraph) in paragraphs.iter().enumerate() {
+            if index > 0 {
+                result.push_str(options.par_sep);
+            }
+            match par_start {
+                Some(start) => {
+                    result.push_str(start);
+                    result.push_str(&format!(" data-paragraph=\"{}\">", index));
+                }
+                None => result.push_str(options.par_start),
+            }
+            if paragraph.is_empty() {
+                result.push_str(options.par_empty);
+            }
+            let mut offset = 0;
+            for part in paragraph {
+                let end = offset + part.source.len();
+                if source_map.part_offsets {
+                    result.push_str(&format!(
+                        "<span data-source-start=\"{}\" data-source-end=\"{}\">",
+                        offset, end
+                    ));
+                }
+                self.render_part(&mut result, &part.part);
+                if source_map.part_offsets {
+                    result.push_str("</span>");
+                }
+                offset = end;
+            }
+            result.push_str(options.par_end);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::{parse_paragraphs, Context, NoLinkProvider, ParseOptions};
+
+    #[test]
+    fn test_render_html_with_source_map() {
+        let context = Context::new();
+        let paragraphs = parse_paragraphs(
+            ["See B(x) here.", ""].into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let link_provider = NoLinkProvider::new();
+        let renderer = Renderer::plain_html(&link_provider);
+        assert_eq!(
+            renderer.render_html_with_source_map(&paragraphs, &HtmlSourceMap::new()),
+            concat!(
+                "<p data-paragraph=\"0\">",
+                "<span data-source-start=\"0\" data-source-end=\"4\">See </span>",
+                "<span data-source-start=\"4\" data-source-end=\"8\"><b>x</b></span>",
+                "<span data-source-start=\"8\" data-source-end=\"14\"> here.</span>",
+                "</p><p data-paragraph=\"1\"></p>",
+            )
+        );
+        assert_eq!(
+            renderer.render_html_with_source_map(
+                &paragraphs,
+                &HtmlSourceMap::new().part_offsets(false)
+            ),
+            "<p data-paragraph=\"0\">See <b>x</b> here.</p><p data-paragraph=\"1\"></p>"
+        );
+        assert_eq!(
+            renderer.render_html_with_source_map(
+                &paragraphs,
+                &HtmlSourceMap::new()
+                    .paragraph_index(false)
+                    .part_offsets(false)
+            ),
+            renderer.render(&paragraphs)
+        );
+    }
+}
@@ -0,0 +1,281 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::Part;
+use crate::markup::metadata::{AnnotatedParagraph, ParagraphMetadata};
+use std::fmt;
+
+/// A problem found by a lint pass.
+///
+/// `part` is the index of the part in the paragraph, and `start` and `end` are byte offsets
+/// into the text of that part.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFinding {
+    /// The code of the rule that produced the finding, like `preferred-term`.
+    pub code: &'static str,
+
+    /// A human readable description of the problem.
+    pub message: String,
+
+    /// The index of the paragraph, starting with 0.
+    pub paragraph: usize,
+
+    /// The index of the part in the paragraph, starting with 0.
+    pub part: usize,
+
+    /// The start of the problem in the part's text.
+    pub start: usize,
+
+    /// The end of the problem in the part's text.
+    pub end: usize,
+
+    /// A suggested replacement for the text between `start` and `end`.
+    pub fix: Option<String>,
+}
+
+impl LintFinding {
+    /// Attach a suggested replacement for the text between `start` and `end`.
+    pub fn with_fix<S: Into<String>>(mut self, fix: S) -> LintFinding {
+        self.fix = Some(fix.into());
+        self
+    }
+}
+
+impl fmt::Display for LintFinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Paragraph {}, part {}, {}..{}: {} [{}]",
+            self.paragraph + 1,
+            self.part + 1,
+            self.start,
+            self.end,
+            self.message,
+            self.code
+        )
+    }
+}
+
+/// A word of prose, with its location in the paragraph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Word<'a> {
+    /// The word itself.
+    pub text: &'a str,
+
+    /// The index of the part in the paragraph.
+    pub part: usize,
+
+    /// The start of the word in the part's text.
+    pub start: usize,
+
+    /// The end of the word in the part's text.
+    pub end: usize,
+}
+
+/// Return the prose text of a part. Code, option names, values, and references are not prose.
+pub(crate) fn prose<'p>(part: &'p Part<'_>) -> Option<&'p str> {
+    match part {
+        Part::Text { text } | Part::Italic { text } | Part::Bold { text } => Some(text),
+        _ => None,
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '\'' || c == '-'
+}
+
+/// A paragraph that is checked by the lint passes.
+#[derive(Debug, Clone, Copy)]
+pub struct LintParagraph<'p, 'a> {
+    /// The index of the paragraph, starting with 0.
+    pub index: usize,
+
+    /// The number of paragraphs in the document.
+    pub count: usize,
+
+    /// The parts of the paragraph.
+    pub parts: &'p [Part<'a>],
+
+    /// The metadata of the paragraph, if it was parsed with metadata.
+    pub metadata: Option<&'p ParagraphMetadata>,
+}
+
+impl<'p, 'a> LintParagraph<'p, 'a> {
+    /// Iterate over the words of the prose parts of the paragraph.
+    ///
+    /// Words consist of alphanumeric characters, apostrophes, and hyphens, and never start or
+    /// end with an apostrophe or hyphen.
+    pub fn words(&self) -> impl Iterator<Item = Word<'p>> + 'p {
+        self.parts
+            .iter()
+            .enumerate()
+            .filter_map(|(index, part)| prose(part).map(|text| (index, text)))
+            .flat_map(|(index, text)| {
+                text.split(|c| !is_word_char(c))
+                    .map(move |word| {
+                        let word = word.trim_matches(|c| c == '\'' || c == '-');
+                        let start = word.as_ptr() as usize - text.as_ptr() as usize;
+                        Word {
+                            text: word,
+                            part: index,
+                            start,
+                            end: start + word.len(),
+                        }
+                    })
+                    .filter(|word| !word.text.is_empty())
+            })
+    }
+
+    /// Create a finding for this paragraph.
+    pub fn finding(
+        &self,
+        code: &'static str,
+        message: String,
+        part: usize,
+        start: usize,
+        end: usize,
+    ) -> LintFinding {
+        LintFinding {
+            code,
+            message,
+            paragraph: self.index,
+            part,
+            start,
+            end,
+            fix: None,
+        }
+    }
+}
+
+/// A lint finding together with the metadata of the paragraph it was found in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnotatedLintFinding {
+    /// The metadata of the paragraph.
+    pub metadata: ParagraphMetadata,
+
+    /// The finding.
+    pub finding: LintFinding,
+}
+
+impl fmt::Display for AnnotatedLintFinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.metadata.path {
+            Some(path) => write!(f, "{}: {}", path, self.finding),
+            None => self.finding.fmt(f),
+        }
+    }
+}
+
+/// A check that is run on every paragraph of a document.
+pub trait LintPass {
+    /// Check `paragraph` and append problems to `findings`.
+    fn check_paragraph(&self, paragraph: &LintParagraph<'_, '_>, findings: &mut Vec<LintFinding>);
+}
+
+/// Runs a set of lint passes over documents.
+pub struct Linter {
+    passes: Vec<Box<dyn LintPass>>,
+}
+
+impl Linter {
+    /// A linter without passes.
+    pub fn new() -> Linter {
+        Linter { passes: Vec::new() }
+    }
+
+    /// Add a lint pass.
+    pub fn add_pass<P: LintPass + 'static>(mut self, pass: P) -> Linter {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    fn check(&self, paragraph: &LintParagraph<'_, '_>, findings: &mut Vec<LintFinding>) {
+        for pass in &self.passes {
+            pass.check_paragraph(paragraph, findings);
+        }
+    }
+
+    /// Run all passes over the paragraphs of a document, one paragraph at a time.
+    pub fn lint(&self, paragraphs: &[Vec<Part<'_>>]) -> Vec<LintFinding> {
+        let mut findings = Vec::new();
+        for (index, parts) in paragraphs.iter().enumerate() {
+            let paragraph = LintParagraph {
+                index,
+                count: paragraphs.len(),
+                parts,
+                metadata: None,
+            };
+            self.check(&paragraph, &mut findings);
+        }
+        findings
+    }
+
+    /// Run all passes over paragraphs parsed with metadata, and attach the metadata of the
+    /// paragraph to every finding.
+    pub fn lint_annotated(
+        &self,
+        paragraphs: &[AnnotatedParagraph<'_>],
+    ) -> Vec<AnnotatedLintFinding> {
+        let mut result = Vec::new();
+        for (index, annotated) in paragraphs.iter().enumerate() {
+            let parts: Vec<Part<'_>> = annotated.parts.iter().map(|ps| ps.part.clone()).collect();
+            let paragraph = LintParagraph {
+                index,
+                count: paragraphs.len(),
+                parts: &parts,
+                metadata: Some(&annotated.metadata),
+            };
+            let mut findings = Vec::new();
+            self.check(&paragraph, &mut findings);
+            result.extend(findings.into_iter().map(|finding| AnnotatedLintFinding {
+                metadata: annotated.metadata.clone(),
+                finding,
+            }));
+        }
+        result
+    }
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Linter::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_words() {
+        let parts = [
+            Part::Text {
+                text: "Don't use  -the- ",
+            },
+            Part::Code { text: "whitelist" },
+            Part::Bold { text: "well-known" },
+        ];
+        let paragraph = LintParagraph {
+            index: 0,
+            count: 1,
+            parts: &parts,
+            metadata: None,
+        };
+        let words: Vec<_> = paragraph
+            .words()
+            .map(|word| (word.text, word.part, word.start, word.end))
+            .collect();
+        assert_eq!(
+            words,
+            vec![
+                ("Don't", 0, 0, 5),
+                ("use", 0, 6, 9),
+                ("the", 0, 12, 15),
+                ("well-known", 2, 0, 10),
+            ]
+        );
+    }
+}
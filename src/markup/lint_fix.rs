@@ -0,0 +1,297 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::diff::{edit_script, Edit};
+use crate::markup::dom::Part;
+use crate::markup::lint::{prose, LintFinding};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The maximal number of bytes of context before and after the replaced text.
+const CONTEXT: usize = 16;
+
+/// The number of unchanged lines shown around changes in diffs.
+const DIFF_CONTEXT: usize = 3;
+
+/// A replacement of text in the documentation.
+///
+/// `before` and `after` are the text around `old` on the same line. They are used to find the
+/// text in the source file, which contains the documentation in its YAML or Python form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// The text before the replaced text.
+    pub before: String,
+
+    /// The replaced text.
+    pub old: String,
+
+    /// The text after the replaced text.
+    pub after: String,
+
+    /// The replacement.
+    pub new: String,
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn ceil_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+impl TextEdit {
+    /// Create an edit from the suggested fix of `finding`.
+    ///
+    /// `paragraphs` must be the paragraphs that were linted. Returns `None` if the finding
+    /// has no fix.
+    pub fn from_finding(finding: &LintFinding, paragraphs: &[Vec<Part<'_>>]) -> Option<TextEdit> {
+        let fix = finding.fix.as_ref()?;
+        let text = prose(paragraphs.get(finding.paragraph)?.get(finding.part)?)?;
+        let old = text.get(finding.start..finding.end)?;
+        let before =
+            &text[floor_char_boundary(text, finding.start.saturating_sub(CONTEXT))..finding.start];
+        let after =
+            &text[finding.end..ceil_char_boundary(text, (finding.end + CONTEXT).min(text.len()))];
+        Some(TextEdit {
+            before: before.rsplit('\n').next().unwrap_or_default().to_string(),
+            old: old.to_string(),
+            after: after.split('\n').next().unwrap_or_default().to_string(),
+            new: fix.clone(),
+        })
+    }
+
+    /// Whether the replacement can be put into any YAML scalar or Python string without
+    /// changing its quoting.
+    fn is_safe(&self) -> bool {
+        !self.new.contains(['\n', '\r', '\\', '"', '\''])
+            && !self.new.contains(": ")
+            && !self.new.contains(" #")
+            && !self.old.contains('\n')
+    }
+}
+
+/// The result of applying edits to a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixResult {
+    /// The fixed source.
+    pub source: String,
+
+    /// The edits that were applied.
+    pub applied: Vec<TextEdit>,
+
+    /// The edits that were not applied, with the reason.
+    pub skipped: Vec<(TextEdit, String)>,
+}
+
+/// Find the text replaced by `edit` in `source`, the content of a YAML or Python file, and
+/// return its start and end.
+///
+/// The text and its context must occur exactly once in `source`, and the replacement must need
+/// no quoting or escaping.
+pub(crate) fn locate_edit(source: &str, edit: &TextEdit) -> Result<(usize, usize), String> {
+    if !edit.is_safe() {
+        return Err("replacement needs quoting".to_string());
+    }
+    let needle = format!("{}{}{}", edit.before, edit.old, edit.after);
+    if needle.is_empty() {
+        return Err("no text to locate".to_string());
+    }
+    let mut found = source.match_indices(&needle);
+    let (Some((position, _)), None) = (found.next(), found.next()) else {
+        let count = source.matches(&needle).count();
+        return Err(if count == 0 {
+            "text not found".to_string()
+        } else {
+            format!("text found {} times", count)
+        });
+    };
+    let start = position + edit.before.len();
+    Ok((start, start + edit.old.len()))
+}
+
+/// Apply `edits` to `source`, the content of a YAML or Python file.
+///
+/// An edit is only applied if its text and context occur exactly once in `source`, if it does
+/// not overlap with another edit, and if the replacement needs no quoting or escaping. This
+/// leaves quoting and indentation of the file untouched.
+pub fn apply_edits(source: &str, edits: &[TextEdit]) -> FixResult {
+    let mut replacements: Vec<(usize, usize, &TextEdit)> = Vec::new();
+    let mut skipped = Vec::new();
+    for edit in edits {
+        let (start, end) = match locate_edit(source, edit) {
+            Ok(range) => range,
+            Err(reason) => {
+                skipped.push((edit.clone(), reason));
+                continue;
+            }
+        };
+        if replacements
+            .iter()
+            .any(|&(s, e, _)| (start < e && s < end) || (start == end && start == s && s == e))
+        {
+            skipped.push((edit.clone(), "overlaps with another edit".to_string()));
+            continue;
+        }
+        replacements.push((start, end, edit));
+    }
+    replacements.sort_by_key(|&(start, end, _)| (start, end));
+    let mut result = String::with_capacity(source.len());
+    let mut position = 0;
+    for &(start, end, edit) in &replacements {
+        result.push_str(&source[position..start]);
+        result.push_str(&edit.new);
+        position = end;
+    }
+    result.push_str(&source[position..]);
+    FixResult {
+        source: result,
+        applied: replacements
+            .into_iter()
+            .map(|(_, _, edit)| edit.clone())
+            .collect(),
+        skipped,
+    }
+}
+
+/// Create a unified diff between `old` and `new`, the old and new content of `path`.
+///
+/// Returns an empty string if both are identical.
+pub fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for edit in edit_script(&old_lines, &new_lines) {
+        match edit {
+            Edit::Keep => {
+                lines.push((' ', i, j, old_lines[i]));
+                i += 1;
+                j += 1;
+            }
+            Edit::Delete(index) => {
+                lines.push(('-', i, j, old_lines[index]));
+                i += 1;
+            }
+            Edit::Insert(index) => {
+                lines.push(('+', i, j, new_lines[index]));
+                j += 1;
+            }
+        }
+    }
+    let changes: Vec<usize> = (0..lines.len()).filter(|&k| lines[k].0 != ' ').collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+    let mut result = format!("--- a/{}\n+++ b/{}\n", path, path);
+    let mut index = 0;
+    while index < changes.len() {
+        let start = changes[index].saturating_sub(DIFF_CONTEXT);
+        let mut end = changes[index] + 1;
+        while index < changes.len() && changes[index] <= end + 2 * DIFF_CONTEXT {
+            end = changes[index] + 1;
+            index += 1;
+        }
+        let end = (end + DIFF_CONTEXT).min(lines.len());
+        let hunk = &lines[start..end];
+        let old_count = hunk.iter().filter(|line| line.0 != '+').count();
+        let new_count = hunk.iter().filter(|line| line.0 != '-').count();
+        let (_, old_start, new_start, _) = hunk[0];
+        result.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start + usize::from(old_count > 0),
+            old_count,
+            new_start + usize::from(new_count > 0),
+            new_count
+        ));
+        for (kind, _, _, line) in hunk {
+            result.push(*kind);
+            result.push_str(line);
+            result.push('\n');
+        }
+    }
+    result
+}
+
+/// Apply `edits` to the file at `path`, and return the result and a unified diff.
+///
+/// With `dry_run`, the file is not modified. Otherwise the fixed content is written to a
+/// temporary file next to it, which then replaces the file.
+pub fn fix_file(path: &Path, edits: &[TextEdit], dry_run: bool) -> io::Result<(FixResult, String)> {
+    let source = fs::read_to_string(path)?;
+    let result = apply_edits(&source, edits);
+    let diff = unified_diff(&path.to_string_lossy(), &source, &result.source);
+    if !dry_run && !result.applied.is_empty() {
+        let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+        temp_name.push(".antsibull-fix");
+        let temp_path = path.with_file_name(temp_name);
+        fs::write(&temp_path, &result.source)?;
+        if let Err(error) = fs::rename(&temp_path, path) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(error);
+        }
+    }
+    Ok((result, diff))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::{
+        parse_paragraphs_without_sources, Context, Linter, ParseOptions, StyleRules,
+        TerminologyRule,
+    };
+
+    #[test]
+    fn test_fix_file() {
+        let source = "DOCUMENTATION = r'''\nmodule: foo\ndescription:\n  - Add hosts to the whitelist.\n  - 'it has  two spaces'\noptions: {}\n'''\n";
+        let path = std::env::temp_dir().join(format!("antsibull-fix-{}.py", std::process::id()));
+        fs::write(&path, source).unwrap();
+
+        let context = Context::new();
+        let paragraphs = parse_paragraphs_without_sources(
+            ["Add hosts to the whitelist.", "it has  two spaces"].into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let findings = Linter::new()
+            .add_pass(TerminologyRule::inclusive())
+            .add_pass(StyleRules::all())
+            .lint(&paragraphs);
+        let edits: Vec<_> = findings
+            .iter()
+            .filter_map(|finding| TextEdit::from_finding(finding, &paragraphs))
+            .collect();
+        assert_eq!(edits.len(), 3);
+
+        let (result, diff) = fix_file(&path, &edits, true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), source);
+        assert_eq!(result.applied.len(), 3);
+        assert!(result.skipped.is_empty());
+        assert_eq!(
+            diff,
+            format!(
+                "--- a/{0}\n+++ b/{0}\n@@ -1,7 +1,7 @@\n DOCUMENTATION = r'''\n module: foo\n description:\n-  - Add hosts to the whitelist.\n-  - 'it has  two spaces'\n+  - Add hosts to the allowlist.\n+  - 'it has two spaces.'\n options: {{}}\n '''\n",
+                path.to_string_lossy()
+            )
+        );
+
+        let (result, _) = fix_file(&path, &edits, false).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), result.source);
+        let (result, diff) = fix_file(&path, &edits, false).unwrap();
+        assert!(result.applied.is_empty());
+        assert_eq!(result.skipped.len(), 3);
+        assert_eq!(diff, "");
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,213 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::lint::{AnnotatedLintFinding, LintFinding};
+use crate::util::json::push_json_string;
+use std::collections::BTreeSet;
+use std::fmt;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// A lint finding together with the place of the linted text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintReportEntry {
+    /// The file that contains the linted text.
+    pub file: String,
+
+    /// The path of the linted text inside the file's documentation, like
+    /// `DOCUMENTATION.options.state.description`.
+    pub yaml_path: String,
+
+    /// The finding.
+    pub finding: LintFinding,
+}
+
+/// Collects lint findings of several files and writes them as text, JSON, or SARIF.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintReport {
+    /// The findings in the order they were added.
+    pub entries: Vec<LintReportEntry>,
+}
+
+fn push_json_field(result: &mut String, name: &str, value: &str) {
+    push_json_string(result, name);
+    result.push_str(": ");
+    push_json_string(result, value);
+}
+
+fn push_json_optional(result: &mut String, value: &Option<String>) {
+    match value {
+        Some(value) => push_json_string(result, value),
+        None => result.push_str("null"),
+    }
+}
+
+impl LintReport {
+    pub fn new() -> LintReport {
+        LintReport::default()
+    }
+
+    /// Add the findings for the text at `yaml_path` in `file`.
+    pub fn add<I>(&mut self, file: &str, yaml_path: &str, findings: I)
+    where
+        I: IntoIterator<Item = LintFinding>,
+    {
+        self.entries
+            .extend(findings.into_iter().map(|finding| LintReportEntry {
+                file: file.to_string(),
+                yaml_path: yaml_path.to_string(),
+                finding,
+            }));
+    }
+
+    /// Add findings of paragraphs parsed with metadata for `file`.
+    ///
+    /// The YAML path of every finding is taken from the metadata of its paragraph.
+    pub fn add_annotated<I>(&mut self, file: &str, findings: I)
+    where
+        I: IntoIterator<Item = AnnotatedLintFinding>,
+    {
+        self.entries
+            .extend(findings.into_iter().map(|annotated| LintReportEntry {
+                file: file.to_string(),
+                yaml_path: annotated.metadata.path.unwrap_or_default(),
+                finding: annotated.finding,
+            }));
+    }
+
+    /// Whether the report has no findings.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Write the findings as a JSON object with a `findings` list.
+    ///
+    /// `start` and `end` are byte offsets into the text of the part with index `part` of the
+    /// paragraph with index `paragraph`; all indices start with 0.
+    pub fn to_json(&self) -> String {
+        let mut result = String::from("{\"findings\": [");
+        for (index, entry) in self.entries.iter().enumerate() {
+            if index > 0 {
+                result.push_str(", ");
+            }
+            let finding = &entry.finding;
+            result.push('{');
+            push_json_field(&mut result, "file", &entry.file);
+            result.push_str(", ");
+            push_json_field(&mut result, "yaml_path", &entry.yaml_path);
+            result.push_str(", ");
+            push_json_field(&mut result, "code", finding.code);
+            result.push_str(", ");
+            push_json_field(&mut result, "message", &finding.message);
+            result.push_str(&format!(
+                ", \"paragraph\": {}, \"part\": {}, \"start\": {}, \"end\": {}, \"fix\": ",
+                finding.paragraph, finding.part, finding.start, finding.end
+            ));
+            push_json_optional(&mut result, &finding.fix);
+            result.push('}');
+        }
+        result.push_str("]}");
+        result
+    }
+
+    /// Write the findings as a [SARIF](https://sarifweb.azurewebsites.net/) 2.1.0 log, for
+    /// example for GitHub code scanning.
+    ///
+    /// The YAML path is stored as logical location. The span and the suggested fix are stored
+    /// in the result's property bag, since the byte offsets are relative to the parsed text and
+    /// not to the file.
+    pub fn to_sarif(&self, tool_name: &str, tool_version: &str) -> String {
+        let mut result = String::from("{");
+        push_json_field(&mut result, "$schema", SARIF_SCHEMA);
+        result.push_str(", \"version\": \"2.1.0\", \"runs\": [{\"tool\": {\"driver\": {");
+        push_json_field(&mut result, "name", tool_name);
+        result.push_str(", ");
+        push_json_field(&mut result, "version", tool_version);
+        result.push_str(", \"rules\": [");
+        let codes: BTreeSet<&str> = self.entries.iter().map(|e| e.finding.code).collect();
+        for (index, code) in codes.iter().enumerate() {
+            if index > 0 {
+                result.push_str(", ");
+            }
+            result.push('{');
+            push_json_field(&mut result, "id", code);
+            result.push('}');
+        }
+        result.push_str("]}}, \"results\": [");
+        for (index, entry) in self.entries.iter().enumerate() {
+            if index > 0 {
+                result.push_str(", ");
+            }
+            let finding = &entry.finding;
+            result.push('{');
+            push_json_field(&mut result, "ruleId", finding.code);
+            result.push_str(", \"level\": \"warning\", \"message\": {");
+            push_json_field(&mut result, "text", &finding.message);
+            result.push_str("}, \"locations\": [{\"physicalLocation\": {\"artifactLocation\": {");
+            push_json_field(&mut result, "uri", &entry.file);
+            result.push_str("}}, \"logicalLocations\": [{");
+            push_json_field(&mut result, "fullyQualifiedName", &entry.yaml_path);
+            result.push_str(", \"kind\": \"member\"}]}], \"properties\": {");
+            result.push_str(&format!(
+                "\"paragraph\": {}, \"part\": {}, \"start\": {}, \"end\": {}, \"fix\": ",
+                finding.paragraph, finding.part, finding.start, finding.end
+            ));
+            push_json_optional(&mut result, &finding.fix);
+            result.push_str("}}");
+        }
+        result.push_str("]}]}");
+        result
+    }
+}
+
+impl fmt::Display for LintReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "{}: {}: {}", entry.file, entry.yaml_path, entry.finding)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint_report() {
+        let mut report = LintReport::new();
+        report.add(
+            "plugins/modules/foo.py",
+            "DOCUMENTATION.description",
+            [LintFinding {
+                code: "preferred-term",
+                message: "Use \"allowlist\" instead of \"whitelist\"".to_string(),
+                paragraph: 0,
+                part: 1,
+                start: 4,
+                end: 13,
+                fix: Some("allowlist".to_string()),
+            }],
+        );
+        assert_eq!(
+            report.to_string(),
+            "plugins/modules/foo.py: DOCUMENTATION.description: Paragraph 1, part 2, 4..13: Use \"allowlist\" instead of \"whitelist\" [preferred-term]\n"
+        );
+        assert_eq!(
+            report.to_json(),
+            r#"{"findings": [{"file": "plugins/modules/foo.py", "yaml_path": "DOCUMENTATION.description", "code": "preferred-term", "message": "Use \"allowlist\" instead of \"whitelist\"", "paragraph": 0, "part": 1, "start": 4, "end": 13, "fix": "allowlist"}]}"#
+        );
+        assert_eq!(
+            report.to_sarif("antsibull-lint", "1.0.0"),
+            concat!(
+                r#"{"$schema": "https://json.schemastore.org/sarif-2.1.0.json", "version": "2.1.0", "runs": [{"tool": {"driver": {"name": "antsibull-lint", "version": "1.0.0", "rules": [{"id": "preferred-term"}]}}, "#,
+                r#""results": [{"ruleId": "preferred-term", "level": "warning", "message": {"text": "Use \"allowlist\" instead of \"whitelist\""}, "#,
+                r#""locations": [{"physicalLocation": {"artifactLocation": {"uri": "plugins/modules/foo.py"}}, "logicalLocations": [{"fullyQualifiedName": "DOCUMENTATION.description", "kind": "member"}]}], "#,
+                r#""properties": {"paragraph": 0, "part": 1, "start": 4, "end": 13, "fix": "allowlist"}}]}]}"#,
+            )
+        );
+    }
+}
@@ -0,0 +1,268 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::lint::{prose, LintFinding, LintParagraph, LintPass};
+use std::collections::BTreeSet;
+
+/// A style check for descriptions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StyleRule {
+    /// The first paragraph starts with a capital letter.
+    Capitalization,
+
+    /// The last paragraph ends with a period.
+    FinalPeriod,
+
+    /// Prose contains no double spaces.
+    DoubleSpace,
+
+    /// No line ends with whitespace.
+    TrailingWhitespace,
+}
+
+impl StyleRule {
+    /// All style rules.
+    pub const ALL: [StyleRule; 4] = [
+        StyleRule::Capitalization,
+        StyleRule::FinalPeriod,
+        StyleRule::DoubleSpace,
+        StyleRule::TrailingWhitespace,
+    ];
+
+    /// The code used for findings of this rule.
+    pub fn code(&self) -> &'static str {
+        match self {
+            StyleRule::Capitalization => "capitalization",
+            StyleRule::FinalPeriod => "final-period",
+            StyleRule::DoubleSpace => "double-space",
+            StyleRule::TrailingWhitespace => "trailing-whitespace",
+        }
+    }
+
+    /// Find the rule with the given code.
+    pub fn from_code(code: &str) -> Option<StyleRule> {
+        StyleRule::ALL.into_iter().find(|rule| rule.code() == code)
+    }
+}
+
+/// Checks the style of descriptions, like ansible-core's `validate-modules` does.
+///
+/// Every rule can be enabled and disabled individually.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyleRules {
+    rules: BTreeSet<StyleRule>,
+}
+
+impl StyleRules {
+    /// No enabled rules.
+    pub fn new() -> StyleRules {
+        StyleRules {
+            rules: BTreeSet::new(),
+        }
+    }
+
+    /// All rules enabled.
+    pub fn all() -> StyleRules {
+        StyleRules {
+            rules: StyleRule::ALL.into_iter().collect(),
+        }
+    }
+
+    /// Enable `rule`.
+    pub fn enable(mut self, rule: StyleRule) -> StyleRules {
+        self.rules.insert(rule);
+        self
+    }
+
+    /// Disable `rule`.
+    pub fn disable(mut self, rule: StyleRule) -> StyleRules {
+        self.rules.remove(&rule);
+        self
+    }
+
+    /// Whether `rule` is enabled.
+    pub fn is_enabled(&self, rule: StyleRule) -> bool {
+        self.rules.contains(&rule)
+    }
+
+    fn check_capitalization(
+        &self,
+        paragraph: &LintParagraph<'_, '_>,
+        findings: &mut Vec<LintFinding>,
+    ) {
+        let Some(text) = paragraph.parts.first().and_then(prose) else {
+            return;
+        };
+        let trimmed = text.trim_start();
+        if let Some(c) = trimmed.chars().next() {
+            if c.is_lowercase() {
+                let start = text.len() - trimmed.len();
+                findings.push(
+                    paragraph
+                        .finding(
+                            StyleRule::Capitalization.code(),
+                            "Description should start with a capital letter".to_string(),
+                            0,
+                            start,
+                            start + c.len_utf8(),
+                        )
+                        .with_fix(c.to_uppercase().to_string()),
+                );
+            }
+        }
+    }
+
+    fn check_final_period(
+        &self,
+        paragraph: &LintParagraph<'_, '_>,
+        findings: &mut Vec<LintFinding>,
+    ) {
+        let Some(part) = paragraph.parts.len().checked_sub(1) else {
+            return;
+        };
+        let end = prose(&paragraph.parts[part]).map(|text| text.trim_end());
+        if end.is_some_and(|text| text.ends_with('.')) {
+            return;
+        }
+        let end = end.map(str::len).unwrap_or(0);
+        findings.push(
+            paragraph
+                .finding(
+                    StyleRule::FinalPeriod.code(),
+                    "Description should end with a period".to_string(),
+                    part,
+                    end,
+                    end,
+                )
+                .with_fix("."),
+        );
+    }
+
+    fn check_whitespace(&self, paragraph: &LintParagraph<'_, '_>, findings: &mut Vec<LintFinding>) {
+        let last = paragraph.parts.len().saturating_sub(1);
+        for (part, text) in paragraph
+            .parts
+            .iter()
+            .enumerate()
+            .filter_map(|(index, part)| prose(part).map(|text| (index, text)))
+        {
+            let mut offset = 0;
+            for line in text.split_inclusive('\n') {
+                let content = line.strip_suffix('\n').unwrap_or(line);
+                let is_line_end = content.len() < line.len() || part == last;
+                let trimmed = content.trim_end_matches([' ', '\t']);
+                if self.is_enabled(StyleRule::TrailingWhitespace)
+                    && is_line_end
+                    && trimmed.len() < content.len()
+                {
+                    findings.push(
+                        paragraph
+                            .finding(
+                                StyleRule::TrailingWhitespace.code(),
+                                "Line ends with whitespace".to_string(),
+                                part,
+                                offset + trimmed.len(),
+                                offset + content.len(),
+                            )
+                            .with_fix(""),
+                    );
+                }
+                if self.is_enabled(StyleRule::DoubleSpace) {
+                    let mut index = 0;
+                    while let Some(found) = trimmed[index..].find("  ") {
+                        let start = index + found;
+                        let end = start + trimmed[start..].len()
+                            - trimmed[start..].trim_start_matches(' ').len();
+                        if start > 0 {
+                            findings.push(
+                                paragraph
+                                    .finding(
+                                        StyleRule::DoubleSpace.code(),
+                                        "Double space".to_string(),
+                                        part,
+                                        offset + start,
+                                        offset + end,
+                                    )
+                                    .with_fix(" "),
+                            );
+                        }
+                        index = end;
+                    }
+                }
+                offset += line.len();
+            }
+        }
+    }
+}
+
+impl Default for StyleRules {
+    fn default() -> Self {
+        StyleRules::all()
+    }
+}
+
+impl LintPass for StyleRules {
+    fn check_paragraph(&self, paragraph: &LintParagraph<'_, '_>, findings: &mut Vec<LintFinding>) {
+        if paragraph.index == 0 && self.is_enabled(StyleRule::Capitalization) {
+            self.check_capitalization(paragraph, findings);
+        }
+        if paragraph.index + 1 == paragraph.count && self.is_enabled(StyleRule::FinalPeriod) {
+            self.check_final_period(paragraph, findings);
+        }
+        self.check_whitespace(paragraph, findings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::lint::Linter;
+    use crate::markup::{parse_paragraphs_without_sources, Context, ParseOptions};
+
+    #[test]
+    fn test_style_rules() {
+        let context = Context::new();
+        let paragraphs = parse_paragraphs_without_sources(
+            [
+                "the first  paragraph. \nIt has C(code).",
+                "The last paragraph ends with C(code)",
+            ]
+            .into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let lint = |rules: StyleRules| -> Vec<String> {
+            Linter::new()
+                .add_pass(rules)
+                .lint(&paragraphs)
+                .iter()
+                .map(|finding| finding.to_string())
+                .collect()
+        };
+        assert_eq!(
+            lint(StyleRules::all()),
+            vec![
+                "Paragraph 1, part 1, 0..1: Description should start with a capital letter [capitalization]",
+                "Paragraph 1, part 1, 21..22: Line ends with whitespace [trailing-whitespace]",
+                "Paragraph 1, part 1, 9..11: Double space [double-space]",
+                "Paragraph 2, part 2, 0..0: Description should end with a period [final-period]",
+            ]
+        );
+        assert_eq!(
+            lint(
+                StyleRules::new()
+                    .enable(StyleRule::DoubleSpace)
+                    .enable(StyleRule::FinalPeriod)
+                    .disable(StyleRule::FinalPeriod)
+            ),
+            vec!["Paragraph 1, part 1, 9..11: Double space [double-space]"]
+        );
+        assert_eq!(
+            StyleRule::from_code("final-period"),
+            Some(StyleRule::FinalPeriod)
+        );
+    }
+}
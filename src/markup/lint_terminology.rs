@@ -0,0 +1,139 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::lint::{LintFinding, LintParagraph, LintPass, Word};
+
+struct Term {
+    words: Vec<String>,
+    replacement: Option<String>,
+}
+
+/// Reports banned terms, and terms for which a preferred alternative exists.
+///
+/// Terms can consist of multiple words, and are matched case-insensitively against whole words
+/// of the same part.
+pub struct TerminologyRule {
+    terms: Vec<Term>,
+}
+
+impl TerminologyRule {
+    /// A rule without terms.
+    pub fn new() -> TerminologyRule {
+        TerminologyRule { terms: Vec::new() }
+    }
+
+    /// A rule with the terms the Ansible documentation replaced with inclusive language.
+    pub fn inclusive() -> TerminologyRule {
+        TerminologyRule::new()
+            .prefer("whitelist", "allowlist")
+            .prefer("whitelisted", "allowlisted")
+            .prefer("blacklist", "denylist")
+            .prefer("blacklisted", "denylisted")
+            .prefer("slave", "secondary")
+    }
+
+    fn add(mut self, term: &str, replacement: Option<&str>) -> TerminologyRule {
+        self.terms.push(Term {
+            words: term.split_whitespace().map(|w| w.to_lowercase()).collect(),
+            replacement: replacement.map(|r| r.to_string()),
+        });
+        self
+    }
+
+    /// Report every use of `term`.
+    pub fn ban(self, term: &str) -> TerminologyRule {
+        self.add(term, None)
+    }
+
+    /// Report every use of `term`, and suggest `replacement` instead.
+    pub fn prefer(self, term: &str, replacement: &str) -> TerminologyRule {
+        self.add(term, Some(replacement))
+    }
+}
+
+impl Default for TerminologyRule {
+    fn default() -> Self {
+        TerminologyRule::new()
+    }
+}
+
+fn matches(term: &Term, words: &[Word<'_>]) -> bool {
+    words.len() >= term.words.len()
+        && words[0].part == words[term.words.len() - 1].part
+        && term
+            .words
+            .iter()
+            .zip(words)
+            .all(|(term, word)| word.text.to_lowercase() == *term)
+}
+
+impl LintPass for TerminologyRule {
+    fn check_paragraph(&self, paragraph: &LintParagraph<'_, '_>, findings: &mut Vec<LintFinding>) {
+        let words: Vec<_> = paragraph.words().collect();
+        for index in 0..words.len() {
+            for term in &self.terms {
+                if term.words.is_empty() || !matches(term, &words[index..]) {
+                    continue;
+                }
+                let first = &words[index];
+                let last = &words[index + term.words.len() - 1];
+                let found = term.words.join(" ");
+                findings.push(match &term.replacement {
+                    Some(replacement) => paragraph
+                        .finding(
+                            "preferred-term",
+                            format!("Use {:?} instead of {:?}", replacement, found),
+                            first.part,
+                            first.start,
+                            last.end,
+                        )
+                        .with_fix(replacement.as_str()),
+                    None => paragraph.finding(
+                        "banned-term",
+                        format!("Avoid {:?}", found),
+                        first.part,
+                        first.start,
+                        last.end,
+                    ),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::lint::Linter;
+    use crate::markup::{parse_paragraphs_without_sources, Context, ParseOptions};
+
+    #[test]
+    fn test_terminology() {
+        let context = Context::new();
+        let paragraphs = parse_paragraphs_without_sources(
+            [
+                "Add hosts to the Whitelist, see O(whitelist).",
+                "This is a sanity  check. C(sanity check) is I(sanity) check.",
+            ]
+            .into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let linter = Linter::new().add_pass(TerminologyRule::inclusive().ban("sanity check"));
+        let findings: Vec<_> = linter
+            .lint(&paragraphs)
+            .iter()
+            .map(|finding| finding.to_string())
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                "Paragraph 1, part 1, 17..26: Use \"allowlist\" instead of \"whitelist\" [preferred-term]",
+                "Paragraph 2, part 1, 10..23: Avoid \"sanity check\" [banned-term]",
+            ]
+        );
+    }
+}
@@ -4,12 +4,13 @@ SPDX-FileCopyrightText: 2024, Felix Fontein
 SPDX-License-Identifier: GPL-3.0-or-later
 */
 
+use crate::error::Error;
 use crate::markup::dom;
 use crate::markup::format;
 use crate::markup::html_helper;
 use crate::markup::md_helper;
 use crate::util::stringbuilder::Appender;
-use regex;
+use std::borrow::Cow;
 use std::rc::Rc;
 use std::sync::LazyLock;
 
@@ -19,7 +20,7 @@ pub struct MDFormatter {
 }
 
 impl MDFormatter {
-    fn new() -> Result<MDFormatter, regex::Error> {
+    fn new() -> Result<MDFormatter, Error> {
         Ok(MDFormatter {
             md_escaper: md_helper::MDEscaper::new()?,
             url_escaper: html_helper::URLEscaper::new(),
@@ -79,8 +80,8 @@ impl MDFormatter {
     fn append_option_like<'a>(
         &self,
         appender: &mut dyn Appender<'a>,
-        name: &'a String,
-        value: &'a Option<String>,
+        name: &'a str,
+        value: &'a Option<Cow<'a, str>>,
         what: format::OptionLike,
         url: &Option<String>,
     ) {
@@ -125,7 +126,18 @@ impl<'a> format::Formatter<'a> for MDFormatter {
             dom::Part::OptionValue { value } => {
                 self.append_tag(appender, "<code>", value, "</code>")
             }
-            dom::Part::EnvVariable { name } => self.append_tag(appender, "<code>", name, "</code>"),
+            dom::Part::EnvVariable { name } => match url {
+                Some(u) => {
+                    appender.push_str("<code><a href=\"");
+                    appender.push_owned_string(
+                        self.url_escaper.escape_with_html_escape(&u).into_owned(),
+                    );
+                    appender.push_str("\">");
+                    appender.push_cow_str(self.md_escaper.escape(name));
+                    appender.push_str("</a></code>");
+                }
+                None => self.append_tag(appender, "<code>", name, "</code>"),
+            },
             dom::Part::Error { message } => {
                 appender.push_str("<b>ERROR while parsing</b>: ");
                 appender.push_cow_str(self.md_escaper.escape(message));
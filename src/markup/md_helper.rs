@@ -4,15 +4,16 @@ SPDX-FileCopyrightText: 2024, Felix Fontein
 SPDX-License-Identifier: GPL-3.0-or-later
 */
 
+use crate::error::Error;
+use alloc::borrow::Cow;
 use regex;
-use std::borrow::Cow;
 
 pub struct MDEscaper {
     md_escape_re: regex::Regex,
 }
 
 impl MDEscaper {
-    pub fn new() -> Result<MDEscaper, regex::Error> {
+    pub fn new() -> Result<MDEscaper, Error> {
         Ok(MDEscaper {
             md_escape_re: regex::Regex::new("([!\"#$%&'()*+,:;<=>?@\\[\\\\\\]^_`{|}~.-])")?,
         })
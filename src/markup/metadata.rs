@@ -0,0 +1,251 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::{Part, PartWithSource};
+use crate::markup::parse::{parse, Context, ParseOptions};
+use crate::markup::renderer::Renderer;
+use std::collections::BTreeMap;
+
+/// How much the content of a paragraph is trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum TrustLevel {
+    /// Content written by the maintainers of the documentation.
+    #[default]
+    Trusted,
+
+    /// Content from untrusted sources, like snippets submitted by users. Links and URLs in
+    /// untrusted paragraphs are rendered as text by [`Renderer::render_annotated`].
+    Untrusted,
+}
+
+/// Information about the origin of a paragraph.
+///
+/// The metadata is passed through parsing, linting, and rendering, so that results can be
+/// correlated with the fields they come from.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ParagraphMetadata {
+    /// The YAML path of the paragraph, like `DOCUMENTATION.options.state.description[1]`.
+    pub path: Option<String>,
+
+    /// The name of the field the paragraph belongs to, like `description`.
+    pub field: Option<String>,
+
+    /// How much the content of the paragraph is trusted.
+    pub trust: TrustLevel,
+
+    /// Arbitrary further metadata.
+    pub extra: BTreeMap<String, String>,
+}
+
+impl ParagraphMetadata {
+    pub fn new() -> ParagraphMetadata {
+        ParagraphMetadata::default()
+    }
+
+    /// Set the YAML path of the paragraph.
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    /// Set the name of the field the paragraph belongs to.
+    pub fn field(mut self, field: &str) -> Self {
+        self.field = Some(field.to_string());
+        self
+    }
+
+    /// Set how much the content of the paragraph is trusted.
+    pub fn trust(mut self, trust: TrustLevel) -> Self {
+        self.trust = trust;
+        self
+    }
+
+    /// Add further metadata.
+    pub fn extra(mut self, key: &str, value: &str) -> Self {
+        self.extra.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+/// A parsed paragraph together with its metadata.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotatedParagraph<'a> {
+    /// The metadata passed to [`parse_paragraphs_with_metadata`].
+    pub metadata: ParagraphMetadata,
+
+    /// The parts of the paragraph.
+    pub parts: Vec<PartWithSource<'a>>,
+}
+
+/// Parse paragraphs that come with metadata.
+///
+/// Error messages mention the YAML path of the paragraph if the metadata has one, and the
+/// number of the paragraph otherwise.
+pub fn parse_paragraphs_with_metadata<'a, I>(
+    input: I,
+    context: &'a Context,
+    opts: &'_ ParseOptions,
+) -> Vec<AnnotatedParagraph<'a>>
+where
+    I: Iterator<Item = (&'a str, ParagraphMetadata)>,
+{
+    input
+        .enumerate()
+        .map(|(index, (paragraph, metadata))| {
+            let opts = match &metadata.path {
+                Some(path) => opts.add_path_to_where(path),
+                None => opts.add_paragraph_to_where(index + 1),
+            };
+            AnnotatedParagraph {
+                parts: parse(paragraph, context, &opts),
+                metadata,
+            }
+        })
+        .collect()
+}
+
+/// A rendered paragraph together with its metadata.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedParagraph<'m> {
+    /// The metadata of the paragraph.
+    pub metadata: &'m ParagraphMetadata,
+
+    /// The rendered paragraph.
+    pub output: String,
+
+    /// Problems found while rendering: the messages of errors in the paragraph, and links of
+    /// untrusted paragraphs that were rendered as text.
+    pub warnings: Vec<String>,
+}
+
+impl Renderer<'_> {
+    /// Render every paragraph on its own, keeping the metadata with the output.
+    ///
+    /// Links and URLs of [untrusted](TrustLevel::Untrusted) paragraphs are rendered as text, so
+    /// that untrusted content cannot link anywhere. Warnings mention the YAML path of the
+    /// paragraph if the metadata has one, and the number of the paragraph otherwise.
+    pub fn render_annotated<'m>(
+        &self,
+        paragraphs: &'m [AnnotatedParagraph<'_>],
+    ) -> Vec<RenderedParagraph<'m>> {
+        paragraphs
+            .iter()
+            .enumerate()
+            .map(|(index, paragraph)| {
+                let untrusted = paragraph.metadata.trust == TrustLevel::Untrusted;
+                let location = match &paragraph.metadata.path {
+                    Some(path) => path.clone(),
+                    None => format!("paragraph {}", index + 1),
+                };
+                let mut warnings = Vec::new();
+                let parts: Vec<Part<'_>> = paragraph
+                    .parts
+                    .iter()
+                    .map(|ps| match &ps.part {
+                        Part::Error { message } => {
+                            warnings.push(message.clone());
+                            ps.part.clone()
+                        }
+                        Part::URL { url } if untrusted => {
+                            warnings
+                                .push(format!("{}: Not linking untrusted URL {}", location, url));
+                            Part::Text { text: url }
+                        }
+                        Part::Link { text, url } if untrusted => {
+                            warnings
+                                .push(format!("{}: Not linking untrusted URL {}", location, url));
+                            Part::Text { text }
+                        }
+                        part => part.clone(),
+                    })
+                    .collect();
+                let mut output = String::new();
+                self.render_paragraph(&mut output, parts.iter());
+                RenderedParagraph {
+                    metadata: &paragraph.metadata,
+                    output,
+                    warnings,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, feature = "md"))]
+mod tests {
+    use super::*;
+    use crate::markup::{LintReport, Linter, NoLinkProvider, TerminologyRule};
+
+    #[test]
+    fn test_paragraph_metadata() {
+        let context = Context::new();
+        let paragraphs = parse_paragraphs_with_metadata(
+            [
+                (
+                    "Add I(foo) to the whitelist.",
+                    ParagraphMetadata::new()
+                        .path("DOCUMENTATION.description[0]")
+                        .field("description"),
+                ),
+                (
+                    "See L(here, https://example.com). Broken C(.",
+                    ParagraphMetadata::new()
+                        .path("DOCUMENTATION.description[1]")
+                        .trust(TrustLevel::Untrusted)
+                        .extra("source", "preview"),
+                ),
+                ("Also broken C(.", ParagraphMetadata::new()),
+            ]
+            .into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        assert_eq!(paragraphs[1].metadata.trust, TrustLevel::Untrusted);
+        assert_eq!(paragraphs[1].metadata.extra["source"], "preview");
+        let error = |paragraph: &AnnotatedParagraph<'_>| {
+            paragraph
+                .parts
+                .iter()
+                .find_map(|ps| match &ps.part {
+                    Part::Error { message } => Some(message.to_string()),
+                    _ => None,
+                })
+                .unwrap()
+        };
+        assert_eq!(
+            error(&paragraphs[1]),
+            "While parsing \"C(.\" at index 42 of DOCUMENTATION.description[1]: Cannot find closing \")\" after last parameter"
+        );
+        assert!(error(&paragraphs[2]).contains(" at index 13 of paragraph 3: "));
+
+        let link_provider = NoLinkProvider::new();
+        let rendered = Renderer::md(&link_provider).render_annotated(&paragraphs);
+        assert_eq!(rendered.len(), 3);
+        assert_eq!(rendered[0].metadata.field.as_deref(), Some("description"));
+        assert_eq!(rendered[0].output, "Add <em>foo</em> to the whitelist\\.");
+        assert_eq!(rendered[0].warnings, Vec::<String>::new());
+        assert!(rendered[1].output.starts_with("See here\\. Broken "));
+        assert_eq!(
+            rendered[1].warnings[0],
+            "DOCUMENTATION.description[1]: Not linking untrusted URL https://example.com"
+        );
+        assert_eq!(rendered[2].warnings.len(), 1);
+
+        let linter = Linter::new().add_pass(TerminologyRule::inclusive());
+        let findings = linter.lint_annotated(&paragraphs);
+        assert_eq!(
+            findings
+                .iter()
+                .map(|finding| finding.to_string())
+                .collect::<Vec<_>>(),
+            vec!["DOCUMENTATION.description[0]: Paragraph 1, part 3, 8..17: Use \"allowlist\" instead of \"whitelist\" [preferred-term]"]
+        );
+
+        let mut report = LintReport::new();
+        report.add_annotated("plugins/modules/foo.py", findings);
+        assert_eq!(report.entries[0].yaml_path, "DOCUMENTATION.description[0]");
+    }
+}
@@ -0,0 +1,215 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::util::stringbuilder::Appender;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Metrics of rendering with one output format.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderMetrics {
+    /// The number of times content was rendered.
+    pub calls: usize,
+
+    /// The number of bytes produced.
+    pub bytes_out: usize,
+
+    /// The total time spent rendering.
+    pub duration: Duration,
+}
+
+/// A snapshot of the values recorded by [`Metrics`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetricsReport {
+    /// The number of bytes of markup that were parsed.
+    pub bytes_in: usize,
+
+    /// The number of paragraphs that were parsed.
+    pub paragraphs: usize,
+
+    /// The number of parts produced by the parser.
+    pub parts: usize,
+
+    /// The total time spent parsing.
+    pub parse_duration: Duration,
+
+    /// The render metrics, indexed by output format name.
+    pub render: BTreeMap<String, RenderMetrics>,
+
+    /// The number of escaped strings that could be borrowed from the input.
+    pub borrowed_strings: usize,
+
+    /// The number of escaped strings that needed an allocation.
+    pub allocated_strings: usize,
+}
+
+impl MetricsReport {
+    /// The number of bytes produced by all formats.
+    pub fn bytes_out(&self) -> usize {
+        self.render.values().map(|render| render.bytes_out).sum()
+    }
+
+    /// The fraction of escaped strings that could be borrowed, or `None` if nothing was escaped.
+    pub fn borrowed_ratio(&self) -> Option<f64> {
+        let total = self.borrowed_strings + self.allocated_strings;
+        (total > 0).then(|| self.borrowed_strings as f64 / total as f64)
+    }
+}
+
+/// Collects metrics of parsing and rendering.
+///
+/// Pass it to [`crate::markup::parse_paragraphs_with_metrics`] and [`render_with_metrics`], and
+/// retrieve the collected values with [`Metrics::report`].
+#[derive(Debug, Default)]
+pub struct Metrics {
+    report: RefCell<MetricsReport>,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics::default()
+    }
+
+    /// Return a snapshot of the values recorded so far.
+    pub fn report(&self) -> MetricsReport {
+        self.report.borrow().clone()
+    }
+
+    /// Clear all recorded values.
+    pub fn reset(&self) {
+        *self.report.borrow_mut() = MetricsReport::default();
+    }
+
+    pub(crate) fn record_parse(&self, bytes_in: usize, parts: usize, duration: Duration) {
+        let mut report = self.report.borrow_mut();
+        report.bytes_in += bytes_in;
+        report.paragraphs += 1;
+        report.parts += parts;
+        report.parse_duration += duration;
+    }
+}
+
+struct MetricsAppender<'m, 'a> {
+    inner: &'m mut dyn Appender<'a>,
+    bytes_out: usize,
+    borrowed_strings: usize,
+    allocated_strings: usize,
+}
+
+impl<'a> Appender<'a> for MetricsAppender<'_, 'a> {
+    fn push_str(&mut self, value: &'a str) {
+        self.bytes_out += value.len();
+        self.inner.push_str(value);
+    }
+
+    fn push_string(&mut self, value: &'a String) {
+        self.bytes_out += value.len();
+        self.inner.push_string(value);
+    }
+
+    fn push_borrowed_string(&mut self, value: &String) {
+        self.bytes_out += value.len();
+        self.allocated_strings += 1;
+        self.inner.push_borrowed_string(value);
+    }
+
+    fn push_owned_string(&mut self, value: String) {
+        self.bytes_out += value.len();
+        self.allocated_strings += 1;
+        self.inner.push_owned_string(value);
+    }
+
+    fn push_cow_str(&mut self, value: Cow<'a, str>) {
+        self.bytes_out += value.len();
+        match value {
+            Cow::Borrowed(_) => self.borrowed_strings += 1,
+            Cow::Owned(_) => self.allocated_strings += 1,
+        }
+        self.inner.push_cow_str(value);
+    }
+}
+
+/// Run `render` and record its output and duration under the output format name `format`.
+///
+/// Escaped strings that the formatters pass on as owned strings are counted as allocations.
+/// If `metrics` is `None`, `render` is called directly with `appender`.
+pub fn render_with_metrics<'a, F>(
+    appender: &mut dyn Appender<'a>,
+    format: &str,
+    metrics: Option<&Metrics>,
+    render: F,
+) where
+    F: FnOnce(&mut dyn Appender<'a>),
+{
+    let Some(metrics) = metrics else {
+        render(appender);
+        return;
+    };
+    let mut metrics_appender = MetricsAppender {
+        inner: appender,
+        bytes_out: 0,
+        borrowed_strings: 0,
+        allocated_strings: 0,
+    };
+    let start = Instant::now();
+    render(&mut metrics_appender);
+    let duration = start.elapsed();
+    let mut report = metrics.report.borrow_mut();
+    report.borrowed_strings += metrics_appender.borrowed_strings;
+    report.allocated_strings += metrics_appender.allocated_strings;
+    let render_metrics = report.render.entry(format.to_string()).or_default();
+    render_metrics.calls += 1;
+    render_metrics.bytes_out += metrics_appender.bytes_out;
+    render_metrics.duration += duration;
+}
+
+#[cfg(all(test, feature = "html"))]
+mod tests {
+    use super::*;
+    use crate::markup::{
+        append_plain_html_paragraphs, parse_paragraphs_with_metrics, Context, NoLinkProvider,
+        ParseOptions,
+    };
+
+    #[test]
+    fn test_metrics() {
+        let metrics = Metrics::new();
+        let context = Context::new();
+        let paragraphs = parse_paragraphs_with_metrics(
+            ["foo C(bar)", "<baz>"].into_iter(),
+            &context,
+            &ParseOptions::default(),
+            Some(&metrics),
+        );
+        let mut result = String::new();
+        render_with_metrics(&mut result, "html", Some(&metrics), |appender| {
+            append_plain_html_paragraphs(
+                appender,
+                paragraphs
+                    .iter()
+                    .map(|paragraph| paragraph.iter().map(|ps| &ps.part)),
+                &NoLinkProvider::new(),
+                &None,
+            )
+        });
+        assert_eq!(result, "<p>foo <code>bar</code></p><p>&lt;baz&gt;</p>");
+
+        let report = metrics.report();
+        assert_eq!(report.bytes_in, 15);
+        assert_eq!(report.paragraphs, 2);
+        assert_eq!(report.parts, 3);
+        assert_eq!(report.bytes_out(), result.len());
+        assert_eq!(report.render["html"].calls, 1);
+        assert_eq!(report.borrowed_strings, 2);
+        assert_eq!(report.allocated_strings, 1);
+        assert_eq!(report.borrowed_ratio(), Some(2.0 / 3.0));
+
+        metrics.reset();
+        assert_eq!(metrics.report(), MetricsReport::default());
+    }
+}
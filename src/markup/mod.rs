@@ -6,334 +6,387 @@ SPDX-License-Identifier: GPL-3.0-or-later
 
 //! Ansible markup parsing and rendering functionality.
 
+#[cfg(feature = "ansible-doc")]
 mod ansible_doc_text;
+#[cfg(feature = "arena")]
+mod arena;
+#[cfg(feature = "std")]
+mod baseline;
+#[cfg(feature = "bbcode")]
+mod bbcode;
+mod bbcode_helper;
+#[cfg(feature = "std")]
+mod completion;
+#[cfg(feature = "hash")]
+mod content_hash;
+#[cfg(feature = "std")]
+mod diff;
 mod dom;
+#[cfg(feature = "std")]
+mod dom_json;
+#[cfg(all(feature = "html", feature = "md", feature = "rst"))]
+mod escaping_audit;
+#[cfg(feature = "std")]
 mod format;
+#[cfg(feature = "markdown")]
+mod from_md;
+#[cfg(feature = "std")]
+mod from_rst;
+#[cfg(feature = "std")]
+mod grammar;
+#[cfg(feature = "html")]
 mod html_antsibull;
+#[cfg(feature = "html")]
+mod html_fragment;
 mod html_helper;
+#[cfg(feature = "html")]
 mod html_plain;
+#[cfg(feature = "html")]
+mod html_source_map;
+#[cfg(feature = "std")]
+mod lint;
+#[cfg(feature = "std")]
+mod lint_fix;
+#[cfg(feature = "std")]
+mod lint_report;
+#[cfg(feature = "std")]
+mod lint_style;
+#[cfg(feature = "std")]
+mod lint_terminology;
+#[cfg(feature = "md")]
 mod md;
 mod md_helper;
+#[cfg(feature = "std")]
+mod metadata;
+#[cfg(feature = "std")]
+mod metrics;
+mod option_aliases;
 mod parse;
+mod plugin_types;
+#[cfg(feature = "std")]
+mod pseudo_locale;
+#[cfg(feature = "std")]
+mod reference_graph;
+#[cfg(feature = "std")]
+mod renderer;
+#[cfg(feature = "rst")]
 mod rst_antsibull;
 mod rst_helper;
+#[cfg(feature = "rst")]
 mod rst_plain;
+#[cfg(feature = "std")]
+mod short_description;
+#[cfg(any(
+    feature = "ansible-doc",
+    feature = "html",
+    feature = "md",
+    feature = "rst"
+))]
+mod shortcuts;
+#[cfg(feature = "std")]
+mod summary;
+#[cfg(all(
+    feature = "ansible-doc",
+    feature = "html",
+    feature = "md",
+    feature = "rst",
+    feature = "yaml"
+))]
+mod test_vectors;
+#[cfg(feature = "std")]
+mod untrusted;
+#[cfg(feature = "std")]
+mod usage;
+mod value_type;
 
+#[cfg(feature = "ansible-doc")]
 pub use ansible_doc_text::{
-    append_ansible_doc_text_paragraph, append_ansible_doc_text_paragraphs, AnsibleDocTextFormatter,
+    append_ansible_doc_text_paragraph, append_ansible_doc_text_paragraphs,
+    append_ansible_doc_text_paragraphs_with_options, AnsibleDocTextFormatter,
+    AnsibleDocTextOptions,
 };
 
-pub use dom::{Part, PartWithSource, PluginIdentifier};
+#[cfg(feature = "arena")]
+pub use arena::{parse_paragraphs_in_arena, ArenaPart, ArenaPluginIdentifier};
+
+#[cfg(feature = "std")]
+pub use baseline::{BaselineDifference, RenderBaseline};
+
+#[cfg(feature = "bbcode")]
+pub use bbcode::{append_bbcode_paragraph, append_bbcode_paragraphs, BBCodeFormatter};
+
+pub use bbcode_helper::BBCodeEscaper;
+
+#[cfg(feature = "std")]
+pub use completion::{complete, Completion, CompletionIndex, CompletionKind};
+
+pub use compact_str::CompactString;
+
+#[cfg(feature = "hash")]
+pub use content_hash::{hash_document, hash_output, hash_paragraphs, HashedRendering};
+
+#[cfg(feature = "std")]
+pub use diff::{diff, DocumentDiff, ParagraphChange, PartChange};
+
+pub use dom::{
+    part_at_offset, EscapePosition, Part, PartAtOffset, PartWithSource, PluginIdentifier,
+};
+
+#[cfg(feature = "std")]
+pub use dom_json::paragraphs_to_json;
+
+#[cfg(feature = "std")]
+pub use lint::{AnnotatedLintFinding, LintFinding, LintParagraph, LintPass, Linter, Word};
+
+#[cfg(feature = "std")]
+pub use lint_fix::{apply_edits, fix_file, unified_diff, FixResult, TextEdit};
+
+#[cfg(feature = "std")]
+pub use lint_report::{LintReport, LintReportEntry};
+
+#[cfg(feature = "std")]
+pub use lint_style::{StyleRule, StyleRules};
+
+#[cfg(feature = "std")]
+pub use lint_terminology::TerminologyRule;
+
+pub use option_aliases::OptionAliases;
 
 pub use parse::{
-    parse, parse_paragraphs, parse_paragraphs_without_sources, parse_without_sources, Context,
-    ParseOptions,
+    describe_commands, escape_parameter, join_split_commands, parse, parse_paragraphs,
+    parse_paragraphs_with_warnings, parse_paragraphs_without_sources, parse_with_warnings,
+    parse_without_sources, CommandDescription, Context, JoinedParagraphs, MissingEntrypoint,
+    ParseConfig, ParseOptions, Parser, DEFAULT_ROLE_ENTRYPOINT,
 };
 
+pub use plugin_types::PluginTypeSynonyms;
+
+#[cfg(feature = "std")]
+pub use pseudo_locale::{pseudo_localize, PseudoLocalizingFormatter};
+
+#[cfg(feature = "std")]
+pub use parse::parse_paragraphs_with_metrics;
+
+#[cfg(feature = "markdown")]
+pub use from_md::{from_md, MarkdownDocument, UnsupportedMarkdown};
+#[cfg(feature = "std")]
+pub use from_rst::{from_rst, RSTDocument};
+
+#[cfg(all(feature = "html", feature = "md", feature = "rst"))]
+pub use escaping_audit::{audit_escaping, AuditedFormat, EscapingIssue};
+
+#[cfg(feature = "std")]
 pub use format::{
-    append_paragraph, append_paragraphs, Formatter, LinkProvider, NoLinkProvider, OptionLike,
+    append_paragraph, append_paragraphs, DeterministicLinkProvider, Formatter, LinkProvider,
+    NoLinkProvider, OptionLike,
 };
 
-pub use html_helper::{HTMLEscaper, URLEscaper};
+#[cfg(feature = "std")]
+pub use grammar::{describe_grammar, textmate_grammar, TEXTMATE_SCOPE};
 
+pub use html_helper::{HTMLEscaper, URLEscaper, URLQueryBuilder};
+
+#[cfg(feature = "html")]
 pub use html_antsibull::{
     append_antsibull_html_paragraph, append_antsibull_html_paragraphs, AntsibullHTMLFormatter,
+    AntsibullHTMLOptions,
 };
 
+#[cfg(feature = "html")]
+pub use html_fragment::{
+    render_html_fragment, render_sanitized_html_fragment, HtmlFragmentOptions,
+};
+
+#[cfg(feature = "html")]
 pub use html_plain::{
     append_plain_html_paragraph, append_plain_html_paragraphs, PlainHTMLFormatter,
 };
 
+#[cfg(feature = "html")]
+pub use html_source_map::HtmlSourceMap;
+
+#[cfg(feature = "md")]
 pub use md::{append_md_paragraph, append_md_paragraphs, MDFormatter};
 
 pub use md_helper::MDEscaper;
 
+#[cfg(feature = "std")]
+pub use metadata::{
+    parse_paragraphs_with_metadata, AnnotatedParagraph, ParagraphMetadata, RenderedParagraph,
+    TrustLevel,
+};
+
+#[cfg(feature = "std")]
+pub use metrics::{render_with_metrics, Metrics, MetricsReport, RenderMetrics};
+
+#[cfg(feature = "std")]
+pub use reference_graph::{ReferenceGraph, ReferenceTarget};
+
+#[cfg(feature = "std")]
+pub use renderer::{
+    render_deterministic, DedupRenderer, NondeterministicRendering, ParagraphOptions, Renderer,
+};
+
+#[cfg(feature = "rst")]
 pub use rst_antsibull::{
     append_antsibull_rst_paragraph, append_antsibull_rst_paragraphs, AntsibullRSTFormatter,
+    AntsibullRSTOptions,
 };
 
 pub use rst_helper::RSTEscaper;
 
+#[cfg(feature = "rst")]
 pub use rst_plain::{append_plain_rst_paragraph, append_plain_rst_paragraphs, PlainRSTFormatter};
 
-#[cfg(test)]
-mod tests {
-    use crate::markup::{
-        append_ansible_doc_text_paragraphs, append_antsibull_html_paragraphs,
-        append_antsibull_rst_paragraphs, append_md_paragraphs, append_plain_html_paragraphs,
-        append_plain_rst_paragraphs, dom, parse, parse_paragraphs, LinkProvider, NoLinkProvider,
-        OptionLike, ParseOptions, PluginIdentifier,
-    };
-    use crate::util::{CollectorAppender, IntoString};
-    use saphyr::{Hash, Yaml};
-    use std::fs::File;
-    use std::io::Read;
-    use std::rc::Rc;
-
-    struct TemplatedLinkProvider {
-        plugin_link: Option<String>,
-        plugin_option_like_link: Option<String>,
-    }
+#[cfg(any(
+    feature = "ansible-doc",
+    feature = "html",
+    feature = "md",
+    feature = "rst"
+))]
+pub use shortcuts::ParagraphSource;
+#[cfg(feature = "ansible-doc")]
+pub use shortcuts::{to_ansible_doc_text, to_ansible_doc_text_paragraphs};
+#[cfg(feature = "html")]
+pub use shortcuts::{to_html, to_html_paragraphs};
+#[cfg(feature = "md")]
+pub use shortcuts::{to_md, to_md_paragraphs};
+#[cfg(feature = "rst")]
+pub use shortcuts::{to_rst, to_rst_paragraphs};
 
-    impl TemplatedLinkProvider {
-        pub fn new(
-            plugin_link: &Option<String>,
-            plugin_option_like_link: &Option<String>,
-        ) -> Result<TemplatedLinkProvider, String> {
-            // TODO do some basic checking
-            Ok(TemplatedLinkProvider {
-                plugin_link: plugin_link.clone(),
-                plugin_option_like_link: plugin_option_like_link.clone(),
-            })
-        }
+#[cfg(feature = "std")]
+pub use short_description::{ShortDescriptionRule, TrailingPeriod, SHORT_DESCRIPTION_MAX_LENGTH};
 
-        pub fn parse(opts: &Hash) -> Result<TemplatedLinkProvider, String> {
-            TemplatedLinkProvider::new(
-                &opts
-                    .get(&Yaml::from_str("pluginLinkTemplate"))
-                    .map(|v| v.as_str().unwrap().to_string()),
-                &opts
-                    .get(&Yaml::from_str("pluginOptionLikeLinkTemplate"))
-                    .map(|v| v.as_str().unwrap().to_string()),
-            )
-        }
-    }
+#[cfg(feature = "std")]
+pub use summary::Summarizer;
 
-    impl LinkProvider for TemplatedLinkProvider {
-        fn plugin_link(&self, plugin: &dom::PluginIdentifier) -> Option<String> {
-            match &self.plugin_link {
-                Some(template) => Some(
-                    template
-                        .replace("{plugin_fqcn}", &plugin.fqcn)
-                        .replace("{plugin_fqcn_slashes}", &plugin.fqcn.replace(".", "/"))
-                        .replace("{plugin_type}", &plugin.r#type),
-                ),
-                None => None,
-            }
-        }
+#[cfg(all(
+    feature = "ansible-doc",
+    feature = "html",
+    feature = "md",
+    feature = "rst",
+    feature = "yaml"
+))]
+pub use test_vectors::{
+    load_test_vectors, ExpectedOutput, TemplatedLinkProvider, TestVector, TestVectorFailure,
+    TestVectorFormat,
+};
 
-        fn plugin_option_like_link(
-            &self,
-            plugin: &dom::PluginIdentifier,
-            entrypoint: Option<&String>,
-            what: OptionLike,
-            name: &[String],
-            _current_plugin: bool,
-        ) -> Option<String> {
-            match &self.plugin_option_like_link {
-                Some(template) => Some(
-                    template
-                        .replace("{plugin_fqcn}", &plugin.fqcn)
-                        .replace("{plugin_fqcn_slashes}", &plugin.fqcn.replace(".", "/"))
-                        .replace("{plugin_type}", &plugin.r#type)
-                        .replace(
-                            "{what}",
-                            match what {
-                                OptionLike::Option => "option",
-                                OptionLike::RetVal => "retval",
-                            },
-                        )
-                        .replace(
-                            "{entrypoint}",
-                            &entrypoint.map(|v| v.as_str()).unwrap_or(""),
-                        )
-                        .replace(
-                            "{entrypoint_with_leading_dash}",
-                            &entrypoint
-                                .map(|ep| format!("-{}", ep))
-                                .unwrap_or_else(|| "".to_string()),
-                        )
-                        .replace("{name_dots}", &name.join("."))
-                        .replace("{name_slashes}", &name.join("/")),
-                ),
-                None => None,
-            }
-        }
-    }
+#[cfg(feature = "std")]
+pub use untrusted::{sanitize_untrusted, UntrustedProfile, UntrustedRendering};
 
-    fn parse_current_plugin(opts: &Hash) -> Result<Option<Rc<dom::PluginIdentifier>>, String> {
-        match opts.get(&Yaml::from_str("currentPlugin")) {
-            Some(cp) => {
-                let current_plugin = cp.as_hash().unwrap();
-                Ok(Some(Rc::new(dom::PluginIdentifier {
-                    fqcn: current_plugin
-                        .get(&Yaml::from_str("fqcn"))
-                        .unwrap()
-                        .as_str()
-                        .unwrap()
-                        .to_string(),
-                    r#type: current_plugin
-                        .get(&Yaml::from_str("type"))
-                        .unwrap()
-                        .as_str()
-                        .unwrap()
-                        .to_string(),
-                })))
-            }
-            None => Ok(None),
-        }
-    }
+#[cfg(feature = "std")]
+pub use usage::MarkupUsage;
 
-    fn get_render_options(
-        params: &Hash,
-        name: &str,
-    ) -> (Option<Rc<dom::PluginIdentifier>>, Box<dyn LinkProvider>) {
-        let mut current_plugin: Option<Rc<dom::PluginIdentifier>> = None;
-        let mut link_provider: Box<dyn LinkProvider> = Box::new(NoLinkProvider::new());
-        if let Some(o) = &params.get(&Yaml::from_str(name)) {
-            let opts = o.as_hash().unwrap();
-            link_provider = Box::new(TemplatedLinkProvider::parse(opts).unwrap());
-            current_plugin = parse_current_plugin(opts).unwrap();
-        }
-        (current_plugin, link_provider)
+pub use value_type::OptionValueType;
+
+#[cfg(feature = "ansible-doc")]
+pub(crate) use ansible_doc_text::ANSIBLE_DOC_TEXT_FORMATTER;
+#[cfg(feature = "bbcode")]
+pub(crate) use bbcode::BBCODE_FORMATTER;
+#[cfg(feature = "html")]
+pub(crate) use html_antsibull::ANTSIBULL_HTML_FORMATTER;
+#[cfg(feature = "html")]
+pub(crate) use html_plain::PLAIN_HTML_FORMATTER;
+#[cfg(feature = "lsp")]
+pub(crate) use lint_fix::locate_edit;
+#[cfg(feature = "md")]
+pub(crate) use md::MARKDOWN_FORMATTER;
+#[cfg(feature = "rst")]
+pub(crate) use rst_antsibull::ANTSIBULL_RST_FORMATTER;
+#[cfg(feature = "rst")]
+pub(crate) use rst_plain::PLAIN_RST_FORMATTER;
+
+#[cfg(all(test, feature = "full"))]
+mod tests {
+    use crate::markup::{
+        append_antsibull_rst_paragraphs, append_bbcode_paragraphs, append_plain_rst_paragraphs,
+        dom, load_test_vectors, parse, parse_paragraphs, NoLinkProvider, ParseOptions,
+        TemplatedLinkProvider,
+    };
+    use crate::util::{CollectorAppender, IntoString};
+
+    #[test]
+    fn test_vectors() {
+        let contents = std::fs::read_to_string("test-vectors.yaml").unwrap();
+        let vectors = load_test_vectors(&contents).unwrap_or_else(|error| panic!("{}", error));
+        let failures: Vec<String> = vectors
+            .iter()
+            .flat_map(|vector| vector.run())
+            .map(|failure| failure.to_string())
+            .collect();
+        assert!(failures.is_empty(), "\n{}", failures.join("\n"));
     }
 
-    fn get_context_options(params: &Hash) -> (parse::Context, ParseOptions) {
-        let mut context = parse::Context {
-            current_plugin: None,
-            role_entrypoint: None,
-        };
-        let mut options = ParseOptions::default();
-        if let Some(parse_opts_t) = &params.get(&Yaml::from_str("parse_opts")) {
-            let parse_opts = parse_opts_t.as_hash().unwrap();
-            if let Some(f) = &parse_opts.get(&Yaml::from_str("currentPlugin")) {
-                let current_plugin = &f.as_hash().unwrap();
-                context.current_plugin = Some(Rc::new(PluginIdentifier {
-                    fqcn: current_plugin[&Yaml::from_str("fqcn")]
-                        .as_str()
-                        .unwrap()
-                        .to_string(),
-                    r#type: current_plugin[&Yaml::from_str("type")]
-                        .as_str()
-                        .unwrap()
-                        .to_string(),
-                }));
-            }
-            if let Some(f) = &parse_opts.get(&Yaml::from_str("roleEntrypoint")) {
-                context.role_entrypoint = Some(Rc::new(f.as_str().unwrap().to_string()));
-            }
-            if let Some(f) = &parse_opts.get(&Yaml::from_str("onlyClassicMarkup")) {
-                if f.as_bool().unwrap() {
-                    options = options.only_classic_markup();
-                }
-            }
-            if let Some(f) = &parse_opts.get(&Yaml::from_str("helpfulErrors")) {
-                if !f.as_bool().unwrap() {
-                    options = options.unhelpful_errors();
-                }
-            }
-        };
-        (context, options)
+    #[test]
+    fn test_bbcode() {
+        let context = parse::Context::new();
+        let paragraphs = parse_paragraphs(
+            [
+                "B(bold [x]) I(it & co) C(code) L(text,https://example.com/a b) U(https://x/[1])",
+                "M(foo.bar.baz) O(foo.bar.baz#module:opt) O(foo.bar.baz#module:opt=val) RV(foo.bar.baz#module:ret)",
+            ]
+            .into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let link_provider = TemplatedLinkProvider::new(
+            Some("https://docs/{plugin_fqcn_slashes}".to_string()),
+            Some("https://docs/{plugin_fqcn_slashes}#{what}-{name_dots}".to_string()),
+        );
+        let mut appender = CollectorAppender::new();
+        append_bbcode_paragraphs(
+            &mut appender,
+            paragraphs
+                .iter()
+                .map(|paragraph| paragraph.iter().map(|ps| &ps.part)),
+            &link_provider,
+            &None,
+        );
+        assert_eq!(
+            appender.into_string(),
+            concat!(
+                "[b]bold &#91;x&#93;[/b] [i]it &amp; co[/i] [code]code[/code] ",
+                "[url=https://example.com/a%20b]text[/url] [url=https://x/%5B1%5D]https://x/&#91;1&#93;[/url]",
+                "\n\n",
+                "[url=https://docs/foo/bar/baz]foo.bar.baz[/url] ",
+                "[url=https://docs/foo/bar/baz#option-opt][b][code]opt[/code][/b][/url] ",
+                "[url=https://docs/foo/bar/baz#option-opt][code]opt=val[/code][/url] ",
+                "[url=https://docs/foo/bar/baz#retval-ret][code]ret[/code][/url]",
+            )
+        );
     }
 
     #[test]
-    fn test_vectors() {
-        let mut contents = String::new();
-        File::open("test-vectors.yaml")
-            .unwrap()
-            .read_to_string(&mut contents)
-            .unwrap();
-        let yaml_data = Yaml::load_from_str(&contents).unwrap();
-        let data = yaml_data[0].as_hash().unwrap()[&Yaml::from_str("test_vectors")]
-            .as_hash()
-            .unwrap();
-        for (k, v) in data {
-            let name = k.as_str().unwrap();
-            println!("Processing test {:?}...", name);
-            let params = v.as_hash().unwrap();
-            let (context, options) = get_context_options(params);
-            let parsed_source = match &params[&Yaml::from_str("source")] {
-                Yaml::String(s) => vec![parse(&s, &context, &options)],
-                Yaml::Array(a) => {
-                    parse_paragraphs(a.iter().map(|s| s.as_str().unwrap()), &context, &options)
-                }
-                v => panic!("Unknown type: {:?}", v),
-            };
-            if let Some(f) = &params.get(&Yaml::from_str("html")) {
-                let expected = f.as_str().unwrap();
-                let (current_plugin, link_provider) = get_render_options(params, "html_opts");
-                let mut appender = CollectorAppender::new();
-                append_antsibull_html_paragraphs(
-                    &mut appender,
-                    parsed_source
-                        .iter()
-                        .map(|paragraph| paragraph.iter().map(|ps| &ps.part)),
-                    &*link_provider,
-                    &current_plugin,
-                );
-                assert_eq!(appender.into_string(), expected);
-            }
-            if let Some(f) = &params.get(&Yaml::from_str("html_plain")) {
-                let expected = f.as_str().unwrap();
-                let (current_plugin, link_provider) = get_render_options(params, "html_opts");
-                let mut appender = CollectorAppender::new();
-                append_plain_html_paragraphs(
-                    &mut appender,
-                    parsed_source
-                        .iter()
-                        .map(|paragraph| paragraph.iter().map(|ps| &ps.part)),
-                    &*link_provider,
-                    &current_plugin,
-                );
-                assert_eq!(appender.into_string(), expected);
-            }
-            if let Some(f) = &params.get(&Yaml::from_str("md")) {
-                let expected = f.as_str().unwrap();
-                let (current_plugin, link_provider) = get_render_options(params, "md_opts");
-                let mut appender = CollectorAppender::new();
-                append_md_paragraphs(
-                    &mut appender,
-                    parsed_source
-                        .iter()
-                        .map(|paragraph| paragraph.iter().map(|ps| &ps.part)),
-                    &*link_provider,
-                    &current_plugin,
-                );
-                assert_eq!(appender.into_string(), expected);
-            }
-            if let Some(f) = &params.get(&Yaml::from_str("rst")) {
-                let expected = f.as_str().unwrap();
-                let (current_plugin, link_provider) = get_render_options(params, "rst_opts");
-                let mut appender = CollectorAppender::new();
-                append_antsibull_rst_paragraphs(
-                    &mut appender,
-                    parsed_source
-                        .iter()
-                        .map(|paragraph| paragraph.iter().map(|ps| &ps.part)),
-                    &*link_provider,
-                    &current_plugin,
-                );
-                assert_eq!(appender.into_string(), expected);
-            }
-            if let Some(f) = &params.get(&Yaml::from_str("rst_plain")) {
-                let expected = f.as_str().unwrap();
-                let (current_plugin, link_provider) = get_render_options(params, "rst_opts");
-                let mut appender = CollectorAppender::new();
-                append_plain_rst_paragraphs(
-                    &mut appender,
-                    parsed_source
-                        .iter()
-                        .map(|paragraph| paragraph.iter().map(|ps| &ps.part)),
-                    &*link_provider,
-                    &current_plugin,
-                );
-                assert_eq!(appender.into_string(), expected);
-            }
-            if let Some(f) = &params.get(&Yaml::from_str("ansible_doc_text")) {
-                let expected = f.as_str().unwrap();
-                let (current_plugin, link_provider) =
-                    get_render_options(params, "ansible_doc_text_opts");
-                let mut appender = CollectorAppender::new();
-                append_ansible_doc_text_paragraphs(
-                    &mut appender,
-                    parsed_source
-                        .iter()
-                        .map(|paragraph| paragraph.iter().map(|ps| &ps.part)),
-                    &*link_provider,
-                    &current_plugin,
-                );
-                assert_eq!(appender.into_string(), expected);
-            }
+    fn test_rst_escape_modes() {
+        let paragraph = [
+            dom::Part::Text { text: "a\\" },
+            dom::Part::Code { text: " `b` " },
+            dom::Part::RSTRef {
+                text: "c <d>",
+                r#ref: "e>\\",
+            },
+            dom::Part::Link {
+                text: "f <g>",
+                url: "https://x/`h`",
+            },
+        ];
+        for append in [append_plain_rst_paragraphs, append_antsibull_rst_paragraphs] {
+            let mut appender = CollectorAppender::new();
+            append(
+                &mut appender,
+                [paragraph.iter()],
+                &NoLinkProvider::new(),
+                &None,
+            );
+            assert_eq!(
+                appender.into_string(),
+                concat!(
+                    "a\\\\\\ :literal:`\\  \\`b\\` \\ `\\ ",
+                    "\\ :ref:`c \\<d\\> <e\\>\\\\>`\\ ",
+                    "\\ `f \\<g\\> <https://x/%60h%60>`__\\ ",
+                )
+            );
         }
     }
 }
@@ -0,0 +1,140 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::PluginIdentifier;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use compact_str::CompactString;
+
+type AliasKey = (Vec<CompactString>, CompactString);
+
+/// Aliases of options of plugins, used to canonicalize the link of `O()` references.
+///
+/// Aliases are stored per plugin and role entrypoint, and per level of suboptions: the alias `k`
+/// of the suboption `key` of `config` is only known below `config`. Aliases of parents are
+/// resolved as well, so with the alias `cfg` of `config`, `O(cfg.k)` links to `config.key`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OptionAliases {
+    aliases: BTreeMap<(PluginIdentifier, Option<String>), BTreeMap<AliasKey, CompactString>>,
+}
+
+impl OptionAliases {
+    pub fn new() -> OptionAliases {
+        OptionAliases::default()
+    }
+
+    /// Add an alias.
+    ///
+    /// `path` consists of the canonical names of the parent options, followed by the alias.
+    pub fn alias(
+        mut self,
+        plugin: &PluginIdentifier,
+        entrypoint: Option<&str>,
+        path: &[&str],
+        canonical: &str,
+    ) -> Self {
+        self.add_alias(plugin, entrypoint, path, canonical);
+        self
+    }
+
+    /// Add an alias.
+    ///
+    /// `path` consists of the canonical names of the parent options, followed by the alias.
+    /// An empty path is ignored.
+    pub fn add_alias(
+        &mut self,
+        plugin: &PluginIdentifier,
+        entrypoint: Option<&str>,
+        path: &[&str],
+        canonical: &str,
+    ) {
+        let Some((alias, parents)) = path.split_last() else {
+            return;
+        };
+        self.aliases
+            .entry((plugin.clone(), entrypoint.map(ToString::to_string)))
+            .or_default()
+            .insert(
+                (
+                    parents
+                        .iter()
+                        .map(|&parent| CompactString::from(parent))
+                        .collect(),
+                    CompactString::from(*alias),
+                ),
+                CompactString::from(canonical),
+            );
+    }
+
+    /// Whether no aliases are known.
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+
+    /// Replace all aliases in `link` by the canonical option names.
+    ///
+    /// Returns `None` if `link` does not contain aliases.
+    pub fn canonicalize(
+        &self,
+        plugin: &PluginIdentifier,
+        entrypoint: Option<&str>,
+        link: &[CompactString],
+    ) -> Option<Box<[CompactString]>> {
+        let aliases = self
+            .aliases
+            .get(&(plugin.clone(), entrypoint.map(ToString::to_string)))?;
+        let mut result: Vec<CompactString> = Vec::with_capacity(link.len());
+        let mut changed = false;
+        for segment in link {
+            match aliases.get(&(result.clone(), segment.clone())) {
+                Some(canonical) => {
+                    result.push(canonical.clone());
+                    changed = true;
+                }
+                None => result.push(segment.clone()),
+            }
+        }
+        changed.then(|| result.into_boxed_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonicalize() {
+        let plugin = PluginIdentifier {
+            fqcn: "foo.bar.baz".to_string(),
+            r#type: "module".to_string(),
+        };
+        let aliases = OptionAliases::new()
+            .alias(&plugin, None, &["cfg"], "config")
+            .alias(&plugin, None, &["config", "k"], "key");
+        let link = |path: &[&str]| -> Vec<CompactString> {
+            path.iter()
+                .map(|&segment| CompactString::from(segment))
+                .collect()
+        };
+        assert_eq!(
+            aliases
+                .canonicalize(&plugin, None, &link(&["cfg", "k"]))
+                .as_deref(),
+            Some(link(&["config", "key"]).as_slice())
+        );
+        assert_eq!(
+            aliases.canonicalize(&plugin, None, &link(&["config", "key"])),
+            None
+        );
+        assert_eq!(aliases.canonicalize(&plugin, None, &link(&["k"])), None);
+        assert_eq!(
+            aliases.canonicalize(&plugin, Some("main"), &link(&["cfg"])),
+            None
+        );
+    }
+}
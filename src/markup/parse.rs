@@ -4,18 +4,82 @@ SPDX-FileCopyrightText: 2024, Felix Fontein
 SPDX-License-Identifier: GPL-3.0-or-later
 */
 
+use crate::error::Error;
 use crate::markup::dom;
+#[cfg(feature = "std")]
+use crate::markup::format::OptionLike;
+#[cfg(feature = "std")]
+use crate::markup::metrics::Metrics;
+use crate::markup::option_aliases::OptionAliases;
+use crate::markup::plugin_types::PluginTypeSynonyms;
+use crate::util::names::is_valid_collection_name;
 use crate::util::stringbuilder;
 use crate::util::stringbuilder::{Appender, IntoString};
 
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use compact_str::CompactString;
+use core::fmt;
+use core::ops::Range;
+use once_cell::race::OnceBox;
 use regex;
-use std::collections::HashMap;
-use std::collections::VecDeque;
-use std::rc::Rc;
-use std::sync::LazyLock;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+#[cfg(feature = "std")]
+use std::time::Instant;
 
 const IGNORE_MARKER: &'static str = "ignore:";
 
+/// The entrypoint assumed for role references without entrypoint.
+pub const DEFAULT_ROLE_ENTRYPOINT: &str = "main";
+
+/// How to handle references to options and return values of roles without entrypoint, like
+/// `O(foo.bar.baz#role:opt)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingEntrypoint {
+    /// Produce an error.
+    #[default]
+    Error,
+
+    /// Assume the entrypoint [`DEFAULT_ROLE_ENTRYPOINT`].
+    AssumeMain,
+
+    /// Assume the entrypoint [`DEFAULT_ROLE_ENTRYPOINT`], and emit a warning.
+    WarnAndAssumeMain,
+}
+
+/// The parsing options that affect the conversion of commands to parts.
+#[derive(Debug, Clone, Copy, Default)]
+struct PartSettings<'b> {
+    missing_entrypoint: MissingEntrypoint,
+    plugin_type_synonyms: Option<&'b PluginTypeSynonyms>,
+}
+
+impl PartSettings<'_> {
+    /// Return the plugin type, with synonyms replaced by the plugin type and a warning.
+    fn plugin_type(&self, plugin_type: &str, warnings: &mut Vec<String>) -> String {
+        match self
+            .plugin_type_synonyms
+            .and_then(|synonyms| synonyms.normalize(plugin_type))
+        {
+            Some(normalized) => {
+                warnings.push(format!(
+                    "Plugin type {:?} is not valid, assuming {:?}",
+                    plugin_type, normalized
+                ));
+                normalized.to_string()
+            }
+            None => plugin_type.to_string(),
+        }
+    }
+}
+
 struct Command<'a> {
     command: &'a str,
     command_match: &'a str,
@@ -77,8 +141,8 @@ const ALL_COMMANDS: [Command<'static>; 13] = [
     RETURN_VALUE,
 ];
 
-struct Parser<'a> {
-    command_map: HashMap<&'a str, &'a Command<'a>>,
+struct ParserState<'a> {
+    command_map: BTreeMap<&'a str, &'a Command<'a>>,
     regex: regex::Regex,
     escape_or_comma: regex::Regex,
     escape_or_closing: regex::Regex,
@@ -86,16 +150,13 @@ struct Parser<'a> {
     plugin_type_re: regex::Regex,
     array_stub_re: regex::Regex,
     fqcn_type_prefix_re: regex::Regex,
+    short_name_type_prefix_re: regex::Regex,
 }
 
-fn _map_re_error<T>(result: Result<T, regex::Error>) -> Result<T, String> {
-    result.map_err(|error| format!("Compiling regular expression: {}", error))
-}
-
-impl<'a> Parser<'a> {
-    fn new<'b>(commands: &'b [&'a Command<'a>]) -> Result<Parser<'a>, String> {
+impl<'a> ParserState<'a> {
+    fn new<'b>(commands: &'b [&'a Command<'a>]) -> Result<ParserState<'a>, Error> {
         let mut regex_buf = String::new();
-        let mut command_map: HashMap<&'a str, &'a Command<'a>> = HashMap::new();
+        let mut command_map: BTreeMap<&'a str, &'a Command<'a>> = BTreeMap::new();
         if commands.len() == 0 {
             regex_buf.push_str("x^"); // does not match anything
         } else {
@@ -104,10 +165,10 @@ impl<'a> Parser<'a> {
                 match command_map.insert(command.command_match, command) {
                     None => {}
                     Some(previous) => {
-                        return Err(format!(
+                        return Err(Error::Parser(format!(
                             "Duplicate command {0:?} (with {1} and {2} arguments, resp.)",
                             command.command_match, previous.parameters, command.parameters,
-                        ));
+                        )));
                     }
                 }
                 if index > 0 {
@@ -121,20 +182,16 @@ impl<'a> Parser<'a> {
             }
             regex_buf.push_str(")");
         }
-        Ok(Parser {
+        Ok(ParserState {
             command_map: command_map,
-            regex: regex::Regex::new(&regex_buf)
-                .map_err(|error| format!("Compiling regular expression: {}", error))?,
-            escape_or_comma: _map_re_error(regex::Regex::new("\\\\.| *, *"))?,
-            escape_or_closing: _map_re_error(regex::Regex::new("\\\\.|\\)"))?,
-            fqcn_re: _map_re_error(regex::Regex::new(
-                "^[a-z0-9_]+\\.[a-z0-9_]+(?:\\.[a-z0-9_]+)+$",
-            ))?,
-            plugin_type_re: _map_re_error(regex::Regex::new("^[a-z_]+$"))?,
-            array_stub_re: _map_re_error(regex::Regex::new("\\[([^\\]]*)\\]"))?,
-            fqcn_type_prefix_re: _map_re_error(regex::Regex::new(
-                "^([^.]+\\.[^.]+\\.[^#]+)#([^:]+):(.*)$",
-            ))?,
+            regex: regex::Regex::new(&regex_buf)?,
+            escape_or_comma: regex::Regex::new("\\\\.| *, *")?,
+            escape_or_closing: regex::Regex::new("\\\\.|\\)")?,
+            fqcn_re: regex::Regex::new("^[a-z0-9_]+\\.[a-z0-9_]+(?:\\.[a-z0-9_]+)+$")?,
+            plugin_type_re: regex::Regex::new("^[a-z_]+$")?,
+            array_stub_re: regex::Regex::new("\\[([^\\]]*)\\]")?,
+            fqcn_type_prefix_re: regex::Regex::new("^([^.]+\\.[^.]+\\.[^#]+)#([^:]+):(.*)$")?,
+            short_name_type_prefix_re: regex::Regex::new("^([^.#:]+)#([^:]+):(.*)$")?,
         })
     }
 
@@ -147,14 +204,173 @@ impl<'a> Parser<'a> {
     }
 }
 
-static CLASSIC_MARKUP_PARSER: LazyLock<Parser<'static>> = LazyLock::new(|| {
-    let commands: Vec<&Command<'static>> = ALL_COMMANDS.iter().filter(|c| c.old_markup).collect();
-    Parser::new(commands.as_slice()).unwrap()
-});
-static FULL_PARSER: LazyLock<Parser<'static>> = LazyLock::new(|| {
-    let commands: Vec<&Command<'static>> = ALL_COMMANDS.iter().collect();
-    Parser::new(commands.as_slice()).unwrap()
-});
+static CLASSIC_MARKUP_PARSER: OnceBox<ParserState<'static>> = OnceBox::new();
+static FULL_PARSER: OnceBox<ParserState<'static>> = OnceBox::new();
+
+fn classic_markup_parser() -> &'static ParserState<'static> {
+    CLASSIC_MARKUP_PARSER.get_or_init(|| {
+        let commands: Vec<&Command<'static>> =
+            ALL_COMMANDS.iter().filter(|c| c.old_markup).collect();
+        Box::new(ParserState::new(commands.as_slice()).unwrap())
+    })
+}
+
+fn full_parser() -> &'static ParserState<'static> {
+    FULL_PARSER.get_or_init(|| {
+        let commands: Vec<&Command<'static>> = ALL_COMMANDS.iter().collect();
+        Box::new(ParserState::new(commands.as_slice()).unwrap())
+    })
+}
+
+/// Compiled parsers for custom command sets, keyed by the sorted command names.
+#[cfg(feature = "std")]
+static PARSER_CACHE: Mutex<BTreeMap<Vec<&'static str>, Parser>> = Mutex::new(BTreeMap::new());
+
+/// A compiled parser for a custom set of markup commands.
+///
+/// Cloning is cheap, since the compiled regular expressions are shared.
+#[derive(Clone)]
+pub struct Parser {
+    state: Arc<ParserState<'static>>,
+}
+
+impl Parser {
+    fn commands_for(names: &[&str]) -> Result<Vec<&'static Command<'static>>, Error> {
+        let all: &'static [Command<'static>] = &ALL_COMMANDS;
+        let mut commands: Vec<&'static Command<'static>> = Vec::new();
+        for name in names {
+            let command = all
+                .iter()
+                .find(|command| command.command == *name)
+                .ok_or_else(|| Error::Parser(format!("Unknown command {:?}", name)))?;
+            if !commands.iter().any(|c| c.command == command.command) {
+                commands.push(command);
+            }
+        }
+        commands.sort_by_key(|command| command.command);
+        Ok(commands)
+    }
+
+    /// Return a parser for the commands with the given names, like `["I", "B", "O"]`.
+    ///
+    /// Parsers for the same set of commands are compiled once and shared, independent of the
+    /// order of the names. Without the `std` feature, this is the same as [`Parser::build`].
+    #[cfg(feature = "std")]
+    pub fn new(names: &[&str]) -> Result<Parser, Error> {
+        let commands = Parser::commands_for(names)?;
+        let key: Vec<&'static str> = commands.iter().map(|command| command.command).collect();
+        let mut cache = PARSER_CACHE
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        if let Some(parser) = cache.get(&key) {
+            return Ok(parser.clone());
+        }
+        let parser = Parser {
+            state: Arc::new(ParserState::new(&commands)?),
+        };
+        cache.insert(key, parser.clone());
+        Ok(parser)
+    }
+
+    /// Return a parser for the commands with the given names, like `["I", "B", "O"]`.
+    #[cfg(not(feature = "std"))]
+    pub fn new(names: &[&str]) -> Result<Parser, Error> {
+        Parser::build(names)
+    }
+
+    /// Compile a parser for the commands with the given names without using the cache.
+    pub fn build(names: &[&str]) -> Result<Parser, Error> {
+        Ok(Parser {
+            state: Arc::new(ParserState::new(&Parser::commands_for(names)?)?),
+        })
+    }
+
+    /// The names of the commands known to the parser, in alphabetical order.
+    pub fn commands(&self) -> Vec<&'static str> {
+        let mut commands: Vec<&'static str> = self
+            .state
+            .command_map
+            .values()
+            .map(|command| command.command)
+            .collect();
+        commands.sort();
+        commands
+    }
+
+    /// Whether both parsers share the same compiled state.
+    pub fn ptr_eq(&self, other: &Parser) -> bool {
+        Arc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+/// The description of a markup command known to a parser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandDescription {
+    /// The name of the command, like `O` or `HORIZONTALLINE`.
+    pub name: &'static str,
+
+    /// The text that starts the command, like `O(`.
+    pub command_match: &'static str,
+
+    /// The number of parameters.
+    pub parameters: u32,
+
+    /// Whether backslash escapes are allowed in the parameters.
+    ///
+    /// Parameters of commands without escapes end at the first comma or closing parenthesis.
+    pub escaped_arguments: bool,
+
+    /// Whether this is a semantic markup command, and not classic markup.
+    pub semantic: bool,
+}
+
+impl CommandDescription {
+    fn new(command: &Command<'static>) -> CommandDescription {
+        CommandDescription {
+            name: command.command,
+            command_match: command.command_match,
+            parameters: command.parameters,
+            escaped_arguments: command.escaped_arguments,
+            semantic: !command.old_markup,
+        }
+    }
+
+    /// The regular expression that matches the start of the command.
+    pub fn pattern(&self) -> String {
+        let mut result = format!("\\b{}", regex::escape(self.command_match));
+        if self.parameters == 0 {
+            result.push_str("\\b");
+        }
+        result
+    }
+}
+
+impl ParserState<'static> {
+    fn describe(&self) -> Vec<CommandDescription> {
+        let mut result: Vec<CommandDescription> = self
+            .command_map
+            .values()
+            .map(|command| CommandDescription::new(command))
+            .collect();
+        result.sort_by_key(|command| command.name);
+        result
+    }
+}
+
+impl Parser {
+    /// Describe the commands known to the parser, in alphabetical order.
+    pub fn describe(&self) -> Vec<CommandDescription> {
+        self.state.describe()
+    }
+}
+
+impl fmt::Debug for Parser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Parser")
+            .field("commands", &self.commands())
+            .finish()
+    }
+}
 
 enum Token<'a> {
     End,
@@ -171,7 +387,8 @@ enum Token<'a> {
     },
     EscapedCommand {
         command: &'a Command<'a>,
-        parameters: Vec<String>,
+        parameters: Vec<Cow<'a, str>>,
+        escapes: Vec<dom::EscapePosition>,
         start: usize,
         end: usize,
     },
@@ -193,6 +410,7 @@ fn get_source<'a>(input: &'a str, token: &'_ Token<'a>) -> Option<&'a str> {
         Token::EscapedCommand {
             command: _,
             parameters: _,
+            escapes: _,
             start,
             end,
         } => Option::Some(&input[*start..*end]),
@@ -215,10 +433,15 @@ struct StringParser<'a, 'b> {
     length: usize,
     position: usize,
     tokens: VecDeque<Token<'a>>,
-    parser: &'a Parser<'a>,
+    parser: &'b ParserState<'static>,
     strict: bool,
     helpful_errors: bool,
     r#where: &'b Option<String>,
+    preserve_escapes: bool,
+    escapes: Vec<dom::EscapePosition>,
+    unterminated: bool,
+    settings: PartSettings<'b>,
+    warnings: Vec<String>,
 }
 
 // This should really be str::find_at...
@@ -229,10 +452,12 @@ fn find_at<'a>(slice: &'a str, pat: &'a str, at: usize) -> Option<usize> {
 impl<'a, 'b> StringParser<'a, 'b> {
     fn new(
         input: &'a str,
-        parser: &'a Parser<'a>,
+        parser: &'b ParserState<'static>,
         strict: bool,
         helpful_errors: bool,
         r#where: &'b Option<String>,
+        preserve_escapes: bool,
+        settings: PartSettings<'b>,
     ) -> StringParser<'a, 'b> {
         StringParser {
             input: input,
@@ -243,6 +468,11 @@ impl<'a, 'b> StringParser<'a, 'b> {
             strict: strict,
             helpful_errors: helpful_errors,
             r#where: r#where,
+            preserve_escapes,
+            escapes: Vec::new(),
+            unterminated: false,
+            settings,
+            warnings: Vec::new(),
         }
     }
 
@@ -277,6 +507,7 @@ impl<'a, 'b> StringParser<'a, 'b> {
         &mut self,
         m: regex::Match,
         argument: &mut stringbuilder::CollectorAppender<'a>,
+        has_escapes: &mut bool,
     ) -> Result<bool, String> {
         if m.start() > self.position {
             argument.push_str(&self.input[self.position..m.start()]);
@@ -285,6 +516,7 @@ impl<'a, 'b> StringParser<'a, 'b> {
         if self.input.as_bytes()[m.start()] != b'\\' {
             return Ok(true);
         }
+        *has_escapes = true;
         let escaped = &self.input[m.start() + 1..self.position];
         if self.strict && escaped != ")" && escaped != "\\" {
             self.position = m.end();
@@ -294,54 +526,81 @@ impl<'a, 'b> StringParser<'a, 'b> {
         return Ok(false);
     }
 
-    fn parse_escaped_call(&mut self, count: u32) -> Result<Vec<String>, String> {
-        let mut parameters = Vec::new();
-        if count == 0 {
-            return Ok(parameters);
-        }
-        let mut commas_left = count - 1;
-        while commas_left > 0 {
-            let mut argument = stringbuilder::CollectorAppender::new();
-            loop {
-                let m = match self
-                    .parser
-                    .escape_or_comma
-                    .find_at(self.input, self.position)
-                {
-                    Some(m) => m,
-                    None => {
-                        self.position = self.length;
-                        return Err(format!(
-                            "Cannot find comma separating parameter {} from the next one",
-                            count - commas_left
-                        ));
-                    }
-                };
-                if self._process_match(m, &mut argument)? {
-                    break;
-                }
-            }
-            parameters.push(argument.into_string());
-            commas_left -= 1;
-        }
+    /// Parse one escaped argument up to the next match of `terminator`.
+    ///
+    /// If the argument contains no escapes, it is borrowed from the input. If escapes are
+    /// preserved, their positions are recorded relative to `command_start`.
+    fn parse_escaped_argument(
+        &mut self,
+        terminator: &regex::Regex,
+        parameter: usize,
+        command_start: usize,
+        missing: impl Fn() -> String,
+    ) -> Result<Cow<'a, str>, String> {
+        let start = self.position;
         let mut argument = stringbuilder::CollectorAppender::new();
+        let mut has_escapes = false;
+        let mut escape_count = 0;
         loop {
-            let m = match self
-                .parser
-                .escape_or_closing
-                .find_at(self.input, self.position)
-            {
+            let m = match terminator.find_at(self.input, self.position) {
                 Some(m) => m,
                 None => {
                     self.position = self.length;
-                    return Err("Cannot find closing \")\" after last parameter".to_string());
+                    self.unterminated = true;
+                    return Err(missing());
                 }
             };
-            if self._process_match(m, &mut argument)? {
-                break;
+            if self._process_match(m, &mut argument, &mut has_escapes)? {
+                return Ok(if has_escapes {
+                    Cow::Owned(argument.into_string())
+                } else {
+                    Cow::Borrowed(&self.input[start..m.start()])
+                });
             }
+            if self.preserve_escapes {
+                self.escapes.push(dom::EscapePosition {
+                    parameter,
+                    offset: m.start() - start - escape_count,
+                    source_offset: m.start() - command_start,
+                });
+            }
+            escape_count += 1;
         }
-        parameters.push(argument.into_string());
+    }
+
+    fn parse_escaped_call(
+        &mut self,
+        count: u32,
+        command_start: usize,
+    ) -> Result<Vec<Cow<'a, str>>, String> {
+        let mut parameters = Vec::new();
+        if count == 0 {
+            return Ok(parameters);
+        }
+        let parser = self.parser;
+        let mut commas_left = count - 1;
+        while commas_left > 0 {
+            let parameter = parameters.len();
+            parameters.push(self.parse_escaped_argument(
+                &parser.escape_or_comma,
+                parameter,
+                command_start,
+                || {
+                    format!(
+                        "Cannot find comma separating parameter {} from the next one",
+                        count - commas_left
+                    )
+                },
+            )?);
+            commas_left -= 1;
+        }
+        let parameter = parameters.len();
+        parameters.push(self.parse_escaped_argument(
+            &parser.escape_or_closing,
+            parameter,
+            command_start,
+            || "Cannot find closing \")\" after last parameter".to_string(),
+        )?);
         Ok(parameters)
     }
 
@@ -357,6 +616,7 @@ impl<'a, 'b> StringParser<'a, 'b> {
                 Some(index) => index,
                 None => {
                     self.position = self.length;
+                    self.unterminated = true;
                     return Err(format!(
                         "Cannot find comma separating parameter {} from the next one",
                         count - commas_left
@@ -373,6 +633,7 @@ impl<'a, 'b> StringParser<'a, 'b> {
             Some(index) => index,
             None => {
                 self.position = self.length;
+                self.unterminated = true;
                 return Err("Cannot find closing \")\" after last parameter".to_string());
             }
         };
@@ -439,16 +700,18 @@ impl<'a, 'b> StringParser<'a, 'b> {
         };
         self.position = m.end();
         if command.escaped_arguments {
-            match self.parse_escaped_call(command.parameters) {
+            match self.parse_escaped_call(command.parameters, m.start()) {
                 Ok(parameters) => {
                     self.tokens.push_back(Token::EscapedCommand {
                         command: command,
                         parameters: parameters,
+                        escapes: core::mem::take(&mut self.escapes),
                         start: m.start(),
                         end: self.position,
                     });
                 }
                 Err(error) => {
+                    self.escapes.clear();
                     self.tokens.push_back(Token::Error {
                         message: self._compose_parsing_error(
                             command,
@@ -502,6 +765,11 @@ impl<'a, 'b> StringParser<'a, 'b> {
 }
 
 /// The parsing context.
+///
+/// Create it with [`Context::new()`] and the builder methods, since fields can be added in the
+/// future.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct Context {
     /// The current plugin for which this documentation is parsed.
     pub current_plugin: Option<Rc<dom::PluginIdentifier>>,
@@ -509,28 +777,141 @@ pub struct Context {
     /// The current role entrypoint (if applicable) for which this
     /// documentation is parsed.
     pub role_entrypoint: Option<Rc<String>>,
+
+    /// The collection (`namespace.name`) for which this documentation is parsed.
+    ///
+    /// If set, plugin references in `O()` and `RV()` can use the short name of a plugin of this
+    /// collection, like `O(foo#module:bar)`.
+    pub current_collection: Option<Rc<String>>,
+
+    /// Aliases of options, used to canonicalize the link of `O()` references.
+    ///
+    /// The name of the reference is kept as written.
+    pub option_aliases: Option<Rc<OptionAliases>>,
+}
+
+impl Context {
+    /// Create an empty context.
+    pub fn new() -> Context {
+        Context {
+            current_plugin: None,
+            role_entrypoint: None,
+            current_collection: None,
+            option_aliases: None,
+        }
+    }
+
+    /// Set the collection for which this documentation is parsed.
+    ///
+    /// A context with only the collection set can be cloned for every plugin of the collection.
+    pub fn collection(mut self, collection: &str) -> Self {
+        self.current_collection = Some(Rc::new(collection.to_string()));
+        self
+    }
+
+    /// Set the plugin for which this documentation is parsed.
+    ///
+    /// If the collection is set, `fqcn` can also be the short name of a plugin of the collection.
+    pub fn current_plugin(mut self, fqcn: &str, r#type: &str) -> Self {
+        self.current_plugin = Some(Rc::new(dom::PluginIdentifier {
+            fqcn: self
+                .resolve_short_name(fqcn)
+                .unwrap_or_else(|| fqcn.to_string()),
+            r#type: r#type.to_string(),
+        }));
+        self
+    }
+
+    /// Set the role entrypoint for which this documentation is parsed.
+    pub fn role_entrypoint(mut self, role_entrypoint: &str) -> Self {
+        self.role_entrypoint = Some(Rc::new(role_entrypoint.to_string()));
+        self
+    }
+
+    /// Set the aliases of options used to canonicalize the link of `O()` references.
+    pub fn option_aliases(mut self, option_aliases: Rc<OptionAliases>) -> Self {
+        self.option_aliases = Some(option_aliases);
+        self
+    }
+
+    /// Check that the collection is a valid collection name, and that the current plugin belongs
+    /// to it.
+    pub fn validate(&self) -> Result<(), Error> {
+        let Some(collection) = self.current_collection.as_deref() else {
+            return Ok(());
+        };
+        if !is_valid_collection_name(collection) {
+            return Err(Error::InvalidContext(format!(
+                "{:?} is not a valid collection name",
+                collection
+            )));
+        }
+        if let Some(plugin) = self.current_plugin.as_deref() {
+            let in_collection = plugin
+                .fqcn
+                .strip_prefix(collection.as_str())
+                .and_then(|rest| rest.strip_prefix('.'))
+                .is_some_and(|name| !name.is_empty());
+            if !in_collection {
+                return Err(Error::InvalidContext(format!(
+                    "Current plugin {:?} does not belong to collection {:?}",
+                    plugin.fqcn, collection
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the FQCN of the plugin `name` of the current collection, if `name` is a short name
+    /// and the collection is set.
+    fn resolve_short_name(&self, name: &str) -> Option<String> {
+        let collection = self.current_collection.as_deref()?;
+        if name.is_empty() || name.contains('.') {
+            return None;
+        }
+        Some(format!("{}.{}", collection, name))
+    }
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Context::new()
+    }
+}
+
+/// Return `part`, which must be a substring of `input`, borrowing from the input if possible.
+fn sub_cow<'a>(input: &Cow<'a, str>, part: &str) -> Cow<'a, str> {
+    match input {
+        Cow::Borrowed(input) => {
+            let start = part.as_ptr() as usize - input.as_ptr() as usize;
+            Cow::Borrowed(&input[start..start + part.len()])
+        }
+        Cow::Owned(_) => Cow::Owned(part.to_string()),
+    }
 }
 
 fn _parse_option_like<'a>(
-    input: String,
+    input: Cow<'a, str>,
     context: &'a Context,
-    parser: &'a Parser<'a>,
+    parser: &ParserState<'_>,
+    settings: &PartSettings<'_>,
+    warnings: &mut Vec<String>,
 ) -> Result<
     (
         Option<Rc<dom::PluginIdentifier>>,
         Option<Rc<String>>,
-        Box<[String]>,
-        String,
-        Option<String>,
+        Box<[CompactString]>,
+        Cow<'a, str>,
+        Option<Cow<'a, str>>,
     ),
     String,
 > {
-    let mut text = input.as_str();
-    let mut value: Option<String> = Option::None;
+    let mut text = input.as_ref();
+    let mut value: Option<Cow<'a, str>> = Option::None;
     match text.split_once("=") {
         Some((r, ov)) => {
             text = r;
-            value = Option::Some(ov.to_string());
+            value = Option::Some(sub_cow(&input, ov));
         }
         None => {}
     }
@@ -549,12 +930,36 @@ fn _parse_option_like<'a>(
             text = &text[capture.get(3).unwrap().start()..];
             plugin = Some(Rc::new(dom::PluginIdentifier {
                 fqcn: fqcn.to_string(),
-                r#type: plugin_type.to_string(),
+                r#type: settings.plugin_type(plugin_type, warnings),
             }))
         }
         None => {
             if text.starts_with(IGNORE_MARKER) {
                 text = &text[IGNORE_MARKER.len()..]
+            } else if let Some((name, plugin_type, rest)) = context
+                .current_collection
+                .as_ref()
+                .and_then(|_| parser.short_name_type_prefix_re.captures(text))
+                .map(|capture| {
+                    (
+                        capture.get(1).unwrap().as_str(),
+                        capture.get(2).unwrap().as_str(),
+                        capture.get(3).unwrap().start(),
+                    )
+                })
+            {
+                if !parser.is_plugin_type(plugin_type) {
+                    return Err(format!("Plugin type {:?} is not valid", plugin_type));
+                }
+                let fqcn = context.resolve_short_name(name).unwrap();
+                if !parser.is_fqcn(&fqcn) {
+                    return Err(format!("Plugin name {:?} is not a FQCN", fqcn));
+                }
+                plugin = Some(Rc::new(dom::PluginIdentifier {
+                    fqcn,
+                    r#type: settings.plugin_type(plugin_type, warnings),
+                }));
+                text = &text[rest..];
             } else {
                 plugin = context.current_plugin.clone();
                 entrypoint = context.role_entrypoint.clone();
@@ -571,29 +976,86 @@ fn _parse_option_like<'a>(
                 None => {}
             }
             if entrypoint == Option::None {
-                return Err("Role reference is missing entrypoint".to_string());
+                match settings.missing_entrypoint {
+                    MissingEntrypoint::Error => {
+                        return Err("Role reference is missing entrypoint".to_string());
+                    }
+                    MissingEntrypoint::AssumeMain => {}
+                    MissingEntrypoint::WarnAndAssumeMain => warnings.push(format!(
+                        "Role reference is missing entrypoint, assuming {:?}",
+                        DEFAULT_ROLE_ENTRYPOINT
+                    )),
+                }
+                entrypoint = Some(Rc::new(DEFAULT_ROLE_ENTRYPOINT.to_string()));
             }
         }
     }
     if text.contains(":") || text.contains("#") {
         return Err(format!("Invalid option/return value name {:?}", text));
     }
-    let link: Vec<String> = parser
+    let link: Vec<CompactString> = parser
         .array_stub_re
         .replace_all(text, "")
-        .to_string()
         .split(".")
-        .map(|s| s.to_string())
+        .map(CompactString::from)
         .collect();
     Ok((
         plugin,
         entrypoint,
         link.into_boxed_slice(),
-        text.to_string(),
+        sub_cow(&input, text),
         value,
     ))
 }
 
+/// Replace aliases in the link of an option by the canonical option names.
+fn canonicalize_option_link(
+    context: &Context,
+    plugin: &Option<Rc<dom::PluginIdentifier>>,
+    entrypoint: &Option<Rc<String>>,
+    link: Box<[CompactString]>,
+) -> Box<[CompactString]> {
+    let (Some(aliases), Some(plugin)) = (context.option_aliases.as_deref(), plugin.as_deref())
+    else {
+        return link;
+    };
+    aliases
+        .canonicalize(plugin, entrypoint.as_deref().map(String::as_str), &link)
+        .unwrap_or(link)
+}
+
+/// Parse the parameter of `O(...)` or `RV(...)` into an option name or return value part.
+#[cfg(feature = "std")]
+pub(crate) fn parse_option_like_part<'a>(
+    input: Cow<'a, str>,
+    context: &'a Context,
+    what: OptionLike,
+) -> Result<dom::Part<'a>, String> {
+    let (plugin, entrypoint, link, name, value) = _parse_option_like(
+        input,
+        context,
+        full_parser(),
+        &PartSettings::default(),
+        &mut Vec::new(),
+    )?;
+    Ok(match what {
+        OptionLike::Option => dom::Part::OptionName {
+            link: canonicalize_option_link(context, &plugin, &entrypoint, link),
+            plugin,
+            entrypoint,
+            name,
+            value,
+        },
+        OptionLike::RetVal => dom::Part::ReturnValue {
+            plugin,
+            entrypoint,
+            link,
+            name,
+            value,
+        },
+    })
+}
+
 struct ToPartError<'a> {
     command: &'a Command<'a>,
     start: usize,
@@ -617,7 +1079,9 @@ impl<'a> ToPartError<'a> {
 fn to_part<'a>(
     token: Token<'a>,
     context: &'a Context,
-    parser: &'a Parser<'a>,
+    parser: &ParserState<'_>,
+    settings: &PartSettings<'_>,
+    warnings: &mut Vec<String>,
 ) -> Result<Option<dom::Part<'a>>, ToPartError<'a>> {
     match token {
         Token::End => panic!("Cannot get part from end token"),
@@ -676,6 +1140,7 @@ fn to_part<'a>(
         Token::EscapedCommand {
             command,
             mut parameters,
+            escapes: _,
             start,
             end,
         } => match match command.command {
@@ -691,7 +1156,7 @@ fn to_part<'a>(
                             Ok(dom::Part::Plugin {
                                 plugin: dom::PluginIdentifier {
                                     fqcn: fqcn.to_string(),
-                                    r#type: ptype.to_string(),
+                                    r#type: settings.plugin_type(ptype, warnings),
                                 },
                             })
                         }
@@ -708,16 +1173,30 @@ fn to_part<'a>(
             "V" => Ok(dom::Part::OptionValue {
                 value: parameters.pop().unwrap(),
             }),
-            "O" => _parse_option_like(parameters.pop().unwrap(), context, parser).map(
+            "O" => _parse_option_like(
+                parameters.pop().unwrap(),
+                context,
+                parser,
+                settings,
+                warnings,
+            )
+            .map(
                 |(plugin, entrypoint, link, name, value)| dom::Part::OptionName {
+                    link: canonicalize_option_link(context, &plugin, &entrypoint, link),
                     plugin: plugin,
                     entrypoint: entrypoint,
-                    link: link,
                     name: name,
                     value: value,
                 },
             ),
-            "RV" => _parse_option_like(parameters.pop().unwrap(), context, parser).map(
+            "RV" => _parse_option_like(
+                parameters.pop().unwrap(),
+                context,
+                parser,
+                settings,
+                warnings,
+            )
+            .map(
                 |(plugin, entrypoint, link, name, value)| dom::Part::ReturnValue {
                     plugin: plugin,
                     entrypoint: entrypoint,
@@ -747,23 +1226,63 @@ fn to_part<'a>(
     }
 }
 
+/// Convert a token to a part, and record the warnings of the conversion in `parser`.
+fn token_to_part<'a, 'b>(
+    parser: &mut StringParser<'a, 'b>,
+    token: Token<'a>,
+    context: &'a Context,
+) -> Option<dom::Part<'a>> {
+    let origin = match &token {
+        Token::EscapedCommand {
+            command,
+            start,
+            end,
+            ..
+        } => Some((*command, *start, *end)),
+        _ => None,
+    };
+    let mut warnings = Vec::new();
+    let part = to_part(
+        token,
+        context,
+        parser.parser,
+        &parser.settings,
+        &mut warnings,
+    )
+    .unwrap_or_else(|err| err.to_part(parser));
+    if let Some((command, start, end)) = origin {
+        for warning in warnings {
+            let warning = parser._compose_parsing_error(command, start, end, warning);
+            parser.warnings.push(warning);
+        }
+    }
+    part
+}
+
 fn do_parse_with_source<'a, 'b>(
     parser: &mut StringParser<'a, 'b>,
     context: &'a Context,
 ) -> Vec<dom::PartWithSource<'a>> {
     let mut result = Vec::new();
     loop {
-        let token = parser.next();
+        let mut token = parser.next();
         if matches!(token, Token::End) {
             break;
         }
         let source = get_source(parser.input, &token);
-        match to_part(token, context, parser.parser).unwrap_or_else(|err| err.to_part(parser)) {
-            Some(part) => result.push(dom::PartWithSource {
-                part: part,
+        let escapes = match &mut token {
+            Token::EscapedCommand { escapes, .. } => core::mem::take(escapes),
+            _ => Vec::new(),
+        };
+        if let Some(part) = token_to_part(parser, token, context) {
+            result.push(dom::PartWithSource {
+                escapes: match part {
+                    dom::Part::Error { .. } => Vec::new(),
+                    _ => escapes,
+                },
+                part,
                 source: source.unwrap(),
-            }),
-            None => {}
+            });
         }
     }
     result
@@ -779,15 +1298,18 @@ fn do_parse_without_source<'a, 'b>(
         if matches!(token, Token::End) {
             break;
         }
-        match to_part(token, context, parser.parser).unwrap_or_else(|err| err.to_part(parser)) {
-            Some(part) => result.push(part),
-            None => {}
+        if let Some(part) = token_to_part(parser, token, context) {
+            result.push(part);
         }
     }
     result
 }
 
 /// Parsing options.
+///
+/// Options can be modified with the consuming builder methods like [`ParseOptions::strict`], or
+/// in place with the setters like [`ParseOptions::set_strict`].
+#[derive(Debug, Clone)]
 pub struct ParseOptions {
     /// Whether to allow all markup, or only classic markup (before introduction of semantic markup).
     only_classic_markup: bool,
@@ -802,6 +1324,18 @@ pub struct ParseOptions {
 
     /// More location information to include in error messages.
     r#where: Option<String>,
+
+    /// A parser for a custom set of commands. Takes precedence over `only_classic_markup`.
+    parser: Option<Parser>,
+
+    /// Whether to record the positions of escapes in [`dom::PartWithSource::escapes`].
+    preserve_escapes: bool,
+
+    /// How to handle role references without entrypoint.
+    missing_entrypoint: MissingEntrypoint,
+
+    /// Synonyms of plugin types that are replaced by the plugin types, with a warning.
+    plugin_type_synonyms: Option<PluginTypeSynonyms>,
 }
 
 impl ParseOptions {
@@ -812,78 +1346,288 @@ impl ParseOptions {
             strict: false,
             helpful_errors: true,
             r#where: Option::None,
+            parser: Option::None,
+            preserve_escapes: false,
+            missing_entrypoint: MissingEntrypoint::Error,
+            plugin_type_synonyms: Option::None,
         }
     }
 
+    /// Parsing options for generating a docsite: all markup, lenient parsing, and helpful errors.
+    ///
+    /// This is the same as [`ParseOptions::default`].
+    pub fn docsite() -> ParseOptions {
+        ParseOptions::default()
+    }
+
+    /// Parsing options for linting: all markup, strict parsing, and helpful errors.
+    pub fn lint_strict() -> ParseOptions {
+        ParseOptions::default().strict()
+    }
+
+    /// Parsing options that behave like ansible-doc: all markup, lenient parsing, and short
+    /// error messages.
+    pub fn ansible_doc() -> ParseOptions {
+        ParseOptions::default().unhelpful_errors()
+    }
+
     /// Modify parsing information to restrict to classic markup.
-    pub fn only_classic_markup(self) -> ParseOptions {
-        ParseOptions {
-            only_classic_markup: true,
-            strict: self.strict,
-            helpful_errors: self.helpful_errors,
-            r#where: self.r#where,
-        }
+    pub fn only_classic_markup(mut self) -> ParseOptions {
+        self.only_classic_markup = true;
+        self
     }
 
     /// Modify parsing information to enable strict parsing.
-    pub fn strict(self) -> ParseOptions {
-        ParseOptions {
-            only_classic_markup: self.only_classic_markup,
-            strict: true,
-            helpful_errors: self.helpful_errors,
-            r#where: self.r#where,
-        }
+    pub fn strict(mut self) -> ParseOptions {
+        self.strict = true;
+        self
     }
 
     /// Modify parsing information to disable helpful error messages.
-    pub fn unhelpful_errors(self) -> ParseOptions {
-        ParseOptions {
-            only_classic_markup: self.only_classic_markup,
-            strict: self.strict,
-            helpful_errors: false,
-            r#where: self.r#where,
-        }
+    pub fn unhelpful_errors(mut self) -> ParseOptions {
+        self.helpful_errors = false;
+        self
     }
 
     /// Modify parsing information to add location information to error messages.
-    pub fn r#where(self, r#where: String) -> ParseOptions {
-        ParseOptions {
-            only_classic_markup: self.only_classic_markup,
-            strict: self.strict,
-            helpful_errors: self.helpful_errors,
-            r#where: Option::Some(r#where),
-        }
+    pub fn r#where(mut self, r#where: String) -> ParseOptions {
+        self.r#where = Some(r#where);
+        self
+    }
+
+    /// Modify parsing information to use a parser for a custom set of commands.
+    pub fn parser(mut self, parser: Parser) -> ParseOptions {
+        self.parser = Some(parser);
+        self
+    }
+
+    /// Modify parsing information to record the positions of escapes in the parameters of
+    /// semantic markup commands.
+    ///
+    /// This allows to re-create the source of unmodified parameters with [`escape_parameter`].
+    pub fn preserve_escapes(mut self) -> ParseOptions {
+        self.preserve_escapes = true;
+        self
+    }
+
+    /// Modify parsing information to handle role references without entrypoint as given.
+    pub fn missing_entrypoint(mut self, missing_entrypoint: MissingEntrypoint) -> ParseOptions {
+        self.missing_entrypoint = missing_entrypoint;
+        self
+    }
+
+    /// Modify parsing information to replace synonyms of plugin types, like `modules`, by the
+    /// plugin types, with a warning.
+    pub fn plugin_type_synonyms(mut self, synonyms: PluginTypeSynonyms) -> ParseOptions {
+        self.plugin_type_synonyms = Some(synonyms);
+        self
+    }
+
+    /// Set whether to restrict to classic markup.
+    pub fn set_only_classic_markup(&mut self, only_classic_markup: bool) -> &mut Self {
+        self.only_classic_markup = only_classic_markup;
+        self
+    }
+
+    /// Set whether to do strict parsing.
+    pub fn set_strict(&mut self, strict: bool) -> &mut Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Set whether to include more information in error messages.
+    pub fn set_helpful_errors(&mut self, helpful_errors: bool) -> &mut Self {
+        self.helpful_errors = helpful_errors;
+        self
+    }
+
+    /// Set or remove the location information added to error messages.
+    pub fn set_where(&mut self, r#where: Option<String>) -> &mut Self {
+        self.r#where = r#where;
+        self
+    }
+
+    /// Set or remove the parser for a custom set of commands.
+    pub fn set_parser(&mut self, parser: Option<Parser>) -> &mut Self {
+        self.parser = parser;
+        self
+    }
+
+    /// Set whether to record the positions of escapes.
+    pub fn set_preserve_escapes(&mut self, preserve_escapes: bool) -> &mut Self {
+        self.preserve_escapes = preserve_escapes;
+        self
+    }
+
+    /// Set how to handle role references without entrypoint.
+    pub fn set_missing_entrypoint(&mut self, missing_entrypoint: MissingEntrypoint) -> &mut Self {
+        self.missing_entrypoint = missing_entrypoint;
+        self
+    }
+
+    /// Set or remove the synonyms of plugin types.
+    pub fn set_plugin_type_synonyms(&mut self, synonyms: Option<PluginTypeSynonyms>) -> &mut Self {
+        self.plugin_type_synonyms = synonyms;
+        self
+    }
+
+    /// Whether only classic markup is allowed.
+    pub fn is_only_classic_markup(&self) -> bool {
+        self.only_classic_markup
+    }
+
+    /// Whether parsing is strict.
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Whether error messages include more information.
+    pub fn has_helpful_errors(&self) -> bool {
+        self.helpful_errors
+    }
+
+    /// The location information added to error messages.
+    pub fn where_info(&self) -> Option<&str> {
+        self.r#where.as_deref()
+    }
+
+    /// The parser for a custom set of commands, if any.
+    pub fn custom_parser(&self) -> Option<&Parser> {
+        self.parser.as_ref()
+    }
+
+    /// Whether the positions of escapes are recorded.
+    pub fn preserves_escapes(&self) -> bool {
+        self.preserve_escapes
+    }
+
+    /// How role references without entrypoint are handled.
+    pub fn missing_entrypoint_handling(&self) -> MissingEntrypoint {
+        self.missing_entrypoint
+    }
+
+    /// The synonyms of plugin types that are replaced, if any.
+    pub fn plugin_type_synonym_map(&self) -> Option<&PluginTypeSynonyms> {
+        self.plugin_type_synonyms.as_ref()
     }
 
     /// Modify parsing information to add paragraph index to error messages.
-    fn add_paragraph_to_where(&self, index: usize) -> ParseOptions {
-        let prefix = format!(" of paragraph {}", index);
-        ParseOptions {
-            only_classic_markup: self.only_classic_markup,
-            strict: self.strict,
-            helpful_errors: self.helpful_errors,
-            r#where: match self.r#where.as_ref() {
-                Some(w) => Some(prefix + &w),
-                None => Some(prefix),
-            },
-        }
+    pub(crate) fn add_paragraph_to_where(&self, index: usize) -> ParseOptions {
+        self.add_prefix_to_where(format!(" of paragraph {}", index))
+    }
+
+    /// Add the YAML path of a paragraph to error messages.
+    #[cfg(feature = "std")]
+    pub(crate) fn add_path_to_where(&self, path: &str) -> ParseOptions {
+        self.add_prefix_to_where(format!(" of {}", path))
+    }
+
+    fn add_prefix_to_where(&self, prefix: String) -> ParseOptions {
+        let mut result = self.clone();
+        result.r#where = match self.r#where.as_ref() {
+            Some(w) => Some(prefix + w),
+            None => Some(prefix),
+        };
+        result
+    }
+}
+
+/// A parsing context together with parsing options.
+///
+/// This allows to pass both through a generation pipeline as one value.
+#[derive(Debug, Clone)]
+pub struct ParseConfig {
+    /// The parsing context.
+    pub context: Context,
+
+    /// The parsing options.
+    pub options: ParseOptions,
+}
+
+impl ParseConfig {
+    /// Combine a parsing context with parsing options.
+    pub fn new(context: Context, options: ParseOptions) -> ParseConfig {
+        ParseConfig { context, options }
+    }
+
+    /// Parse a paragraph with [`parse`].
+    pub fn parse<'a>(&'a self, input: &'a str) -> Vec<dom::PartWithSource<'a>> {
+        parse(input, &self.context, &self.options)
+    }
+
+    /// Parse a paragraph with [`parse_without_sources`].
+    pub fn parse_without_sources<'a>(&'a self, input: &'a str) -> Vec<dom::Part<'a>> {
+        parse_without_sources(input, &self.context, &self.options)
+    }
+
+    /// Parse paragraphs with [`parse_paragraphs`].
+    pub fn parse_paragraphs<'a, I>(&'a self, input: I) -> Vec<Vec<dom::PartWithSource<'a>>>
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        parse_paragraphs(input, &self.context, &self.options)
+    }
+
+    /// Parse paragraphs with [`parse_paragraphs_without_sources`].
+    pub fn parse_paragraphs_without_sources<'a, I>(&'a self, input: I) -> Vec<Vec<dom::Part<'a>>>
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        parse_paragraphs_without_sources(input, &self.context, &self.options)
     }
 }
 
 fn create_parser<'a, 'b>(input: &'a str, opts: &'b ParseOptions) -> StringParser<'a, 'b> {
     StringParser::new(
         input,
-        if opts.only_classic_markup {
-            &*CLASSIC_MARKUP_PARSER
-        } else {
-            &*FULL_PARSER
+        match &opts.parser {
+            Some(parser) => &parser.state,
+            None if opts.only_classic_markup => classic_markup_parser(),
+            None => full_parser(),
         },
         opts.strict,
         opts.helpful_errors,
         &opts.r#where,
+        opts.preserve_escapes,
+        PartSettings {
+            missing_entrypoint: opts.missing_entrypoint,
+            plugin_type_synonyms: opts.plugin_type_synonyms.as_ref(),
+        },
     )
 }
 
+/// Describe the commands known to the parser selected by `opts`, in alphabetical order.
+pub fn describe_commands(opts: &ParseOptions) -> Vec<CommandDescription> {
+    match &opts.parser {
+        Some(parser) => parser.describe(),
+        None if opts.only_classic_markup => classic_markup_parser().describe(),
+        None => full_parser().describe(),
+    }
+}
+
+/// Escape the unescaped parameter `parameter` of a semantic markup command at the positions
+/// recorded in `escapes`, which can contain escapes of other parameters as well.
+///
+/// For an unmodified parameter, this results in exactly the parameter's source. No other
+/// characters are escaped.
+pub fn escape_parameter(text: &str, parameter: usize, escapes: &[dom::EscapePosition]) -> String {
+    let mut result = String::with_capacity(text.len() + escapes.len());
+    let mut position = 0;
+    for escape in escapes
+        .iter()
+        .filter(|escape| escape.parameter == parameter)
+    {
+        if escape.offset < position || !text.is_char_boundary(escape.offset) {
+            continue;
+        }
+        result.push_str(&text[position..escape.offset]);
+        result.push('\\');
+        position = escape.offset;
+    }
+    result.push_str(&text[position..]);
+    result
+}
+
 /// Parse a paragraph and emit a list of parts.
 pub fn parse<'a>(
     input: &'a str,
@@ -894,6 +1638,18 @@ pub fn parse<'a>(
     do_parse_with_source(&mut string_parser, context)
 }
 
+/// Parse a paragraph like [`parse`], and also return the warnings for markup that was accepted
+/// with a guess, like a role reference without entrypoint.
+pub fn parse_with_warnings<'a>(
+    input: &'a str,
+    context: &'a Context,
+    opts: &'_ ParseOptions,
+) -> (Vec<dom::PartWithSource<'a>>, Vec<String>) {
+    let mut string_parser = create_parser(input, opts);
+    let result = do_parse_with_source(&mut string_parser, context);
+    (result, string_parser.warnings)
+}
+
 /// Parse a paragraph and emit a list of parts with source information.
 pub fn parse_without_sources<'a>(
     input: &'a str,
@@ -919,6 +1675,162 @@ where
         .collect()
 }
 
+/// Return the start of the command at the end of `input` that is missing its closing
+/// parenthesis or a comma, if there is one.
+fn unterminated_command(input: &str, opts: &ParseOptions) -> Option<usize> {
+    let mut string_parser = create_parser(input, opts);
+    let mut last_error = None;
+    loop {
+        match string_parser.next() {
+            Token::End => break,
+            Token::Error { start, .. } => last_error = Some(start),
+            _ => {}
+        }
+    }
+    if string_parser.unterminated {
+        last_error
+    } else {
+        None
+    }
+}
+
+/// Paragraphs where commands that are split over several paragraphs have been joined.
+///
+/// Created by [`join_split_commands`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JoinedParagraphs<'a> {
+    /// The paragraphs. Paragraphs that have not been joined are borrowed from the input.
+    pub paragraphs: Vec<Cow<'a, str>>,
+
+    /// For every paragraph, the indices of the input paragraphs it has been created from.
+    pub origins: Vec<Range<usize>>,
+
+    /// A warning for every join.
+    pub warnings: Vec<String>,
+}
+
+impl<'a> JoinedParagraphs<'a> {
+    /// Parse the paragraphs with [`parse_paragraphs`].
+    ///
+    /// Paragraph indices in error messages refer to the joined paragraphs.
+    pub fn parse(
+        &'a self,
+        context: &'a Context,
+        opts: &'_ ParseOptions,
+    ) -> Vec<Vec<dom::PartWithSource<'a>>> {
+        parse_paragraphs(
+            self.paragraphs.iter().map(|paragraph| paragraph.as_ref()),
+            context,
+            opts,
+        )
+    }
+
+    /// Parse the paragraphs with [`parse_paragraphs_without_sources`].
+    pub fn parse_without_sources(
+        &'a self,
+        context: &'a Context,
+        opts: &'_ ParseOptions,
+    ) -> Vec<Vec<dom::Part<'a>>> {
+        parse_paragraphs_without_sources(
+            self.paragraphs.iter().map(|paragraph| paragraph.as_ref()),
+            context,
+            opts,
+        )
+    }
+}
+
+/// Join paragraphs that end with a command missing its closing parenthesis with the following
+/// paragraph, separated by a space, if that closes the command.
+///
+/// This happens when long commands like `L()` are wrapped over two list items in YAML. A
+/// command that is not closed by the following paragraph is left alone, and results in an error
+/// when parsing. This is opt-in: to use it, parse the result with
+/// [`JoinedParagraphs::parse`] instead of parsing the input with [`parse_paragraphs`].
+pub fn join_split_commands<'a, I>(input: I, opts: &'_ ParseOptions) -> JoinedParagraphs<'a>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let input: Vec<&'a str> = input.collect();
+    let mut result = JoinedParagraphs {
+        paragraphs: Vec::new(),
+        origins: Vec::new(),
+        warnings: Vec::new(),
+    };
+    let mut index = 0;
+    while index < input.len() {
+        let mut paragraph = Cow::Borrowed(input[index]);
+        let mut end = index + 1;
+        let start = unterminated_command(input[index], opts);
+        if let (Some(start), Some(next)) = (start, input.get(index + 1)) {
+            let joined = format!("{} {}", input[index], next);
+            if unterminated_command(&joined, opts) != Some(start) {
+                result.warnings.push(format!(
+                    "Joined paragraphs {} to {} to close the command at index {} of paragraph {}{}",
+                    index + 1,
+                    index + 2,
+                    start + 1,
+                    index + 1,
+                    opts.r#where.as_deref().unwrap_or(""),
+                ));
+                paragraph = Cow::Owned(joined);
+                end = index + 2;
+            }
+        }
+        result.paragraphs.push(paragraph);
+        result.origins.push(index..end);
+        index = end;
+    }
+    result
+}
+
+/// Parse paragraphs like [`parse_paragraphs`], and also return the warnings of all paragraphs.
+pub fn parse_paragraphs_with_warnings<'a, I>(
+    input: I,
+    context: &'a Context,
+    opts: &'_ ParseOptions,
+) -> (Vec<Vec<dom::PartWithSource<'a>>>, Vec<String>)
+where
+    I: Iterator<Item = &'a str>,
+{
+    let mut warnings = Vec::new();
+    let paragraphs = input
+        .enumerate()
+        .map(|(index, p)| {
+            let (parts, paragraph_warnings) =
+                parse_with_warnings(p, context, &opts.add_paragraph_to_where(index + 1));
+            warnings.extend(paragraph_warnings);
+            parts
+        })
+        .collect();
+    (paragraphs, warnings)
+}
+
+/// Parse paragraphs like [`parse_paragraphs`], and record their sizes, part counts, and the
+/// time spent parsing in `metrics`.
+#[cfg(feature = "std")]
+pub fn parse_paragraphs_with_metrics<'a, I>(
+    input: I,
+    context: &'a Context,
+    opts: &'_ ParseOptions,
+    metrics: Option<&Metrics>,
+) -> Vec<Vec<dom::PartWithSource<'a>>>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let Some(metrics) = metrics else {
+        return parse_paragraphs(input, context, opts);
+    };
+    input
+        .enumerate()
+        .map(|(index, p)| {
+            let start = Instant::now();
+            let result = parse(p, context, &opts.add_paragraph_to_where(index + 1));
+            metrics.record_parse(p.len(), result.len(), start.elapsed());
+            result
+        })
+        .collect()
+}
+
 /// Parse a paragraph and emit a list of parts with source information.
 pub fn parse_paragraphs_without_sources<'a, I>(
     input: I,
@@ -943,17 +1855,331 @@ mod tests {
 
     #[test]
     fn parse_simple() {
-        let context = Context {
-            current_plugin: None,
-            role_entrypoint: None,
-        };
+        let context = Context::new();
         assert_eq!(parse("", &context, &ParseOptions::default()), vec!());
         assert_eq!(
             parse("Foo", &context, &ParseOptions::default()),
             vec!(dom::PartWithSource {
                 part: dom::Part::Text { text: "Foo" },
-                source: "Foo"
+                source: "Foo",
+                escapes: vec!(),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_borrowed_parameters() {
+        let context = Context::new();
+        let parts = parse_without_sources(
+            "O(foo.bar[1]=baz) E(HOME) V(a\\)b) RV(x.y=a\\\\b)",
+            &context,
+            &ParseOptions::default(),
+        );
+        let dom::Part::OptionName {
+            link, name, value, ..
+        } = &parts[0]
+        else {
+            panic!("unexpected part {:?}", parts[0]);
+        };
+        assert_eq!(link.as_ref(), ["foo", "bar"]);
+        assert!(link.iter().all(|s| !s.is_heap_allocated()));
+        assert!(matches!(name, Cow::Borrowed("foo.bar[1]")));
+        assert!(matches!(value, Some(Cow::Borrowed("baz"))));
+        assert!(matches!(
+            &parts[2],
+            dom::Part::EnvVariable {
+                name: Cow::Borrowed("HOME")
+            }
+        ));
+        let dom::Part::OptionValue { value } = &parts[4] else {
+            panic!("unexpected part {:?}", parts[4]);
+        };
+        assert!(matches!(value, Cow::Owned(v) if v == "a)b"));
+        let dom::Part::ReturnValue { name, value, .. } = &parts[6] else {
+            panic!("unexpected part {:?}", parts[6]);
+        };
+        assert!(matches!(name, Cow::Owned(n) if n == "x.y"));
+        assert!(matches!(value, Some(Cow::Owned(v)) if v == "a\\b"));
+    }
+
+    #[test]
+    fn parse_with_custom_parser() {
+        let context = Context::new();
+        let parser = Parser::new(&["I", "B", "I"]).unwrap();
+        assert_eq!(parser.commands(), vec!["B", "I"]);
+        #[cfg(feature = "std")]
+        assert!(parser.ptr_eq(&Parser::new(&["B", "I"]).unwrap()));
+        assert!(!parser.ptr_eq(&Parser::build(&["B", "I"]).unwrap()));
+        assert_eq!(
+            Parser::new(&["I", "X"]).err().map(String::from),
+            Some("Unknown command \"X\"".to_string())
+        );
+        assert_eq!(
+            parse_without_sources(
+                "I(a) C(b)",
+                &context,
+                &ParseOptions::default().parser(parser)
+            ),
+            vec![
+                dom::Part::Italic { text: "a" },
+                dom::Part::Text { text: " C(b)" },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_options_builder() {
+        let opts = ParseOptions::lint_strict();
+        assert!(opts.is_strict());
+        assert!(opts.has_helpful_errors());
+        assert!(!ParseOptions::docsite().is_strict());
+        assert!(!ParseOptions::ansible_doc().has_helpful_errors());
+
+        let mut opts = ParseOptions::docsite();
+        opts.set_strict(true)
+            .set_only_classic_markup(true)
+            .set_where(Some(" of foo".to_string()));
+        assert!(opts.is_strict());
+        assert!(opts.is_only_classic_markup());
+        assert_eq!(opts.where_info(), Some(" of foo"));
+        assert_eq!(
+            opts.add_paragraph_to_where(2).where_info(),
+            Some(" of paragraph 2 of foo")
+        );
+        opts.set_parser(Some(Parser::build(&["B"]).unwrap()));
+        assert!(format!("{:?}", opts).contains("commands: [\"B\"]"));
+        assert_eq!(opts.clone().custom_parser().unwrap().commands(), vec!["B"]);
+
+        let config = ParseConfig::new(Context::new(), opts);
+        assert_eq!(
+            config.parse_paragraphs_without_sources(["B(a) I(b)"].into_iter()),
+            vec![vec![
+                dom::Part::Bold { text: "a" },
+                dom::Part::Text { text: " I(b)" },
+            ]]
+        );
+    }
+
+    #[test]
+    fn parse_with_collection() {
+        let context = Context::new()
+            .collection("foo.bar")
+            .current_plugin("baz", "module");
+        assert!(context.validate().is_ok());
+        assert_eq!(context.current_plugin.as_ref().unwrap().fqcn, "foo.bar.baz");
+        assert_eq!(
+            Context::new()
+                .collection("foo.bar")
+                .current_plugin("foo.other.baz", "module")
+                .validate()
+                .err()
+                .map(String::from),
+            Some(
+                "Current plugin \"foo.other.baz\" does not belong to collection \"foo.bar\""
+                    .to_string()
+            )
+        );
+        assert!(Context::new().collection("foo").validate().is_err());
+
+        let parts = parse_without_sources(
+            "O(bam#lookup:a=b) RV(foo.bar.bam#filter:c) O(baz)",
+            &context,
+            &ParseOptions::default(),
+        );
+        let plugin = |fqcn: &str, r#type: &str| {
+            Some(Rc::new(dom::PluginIdentifier {
+                fqcn: fqcn.to_string(),
+                r#type: r#type.to_string(),
+            }))
+        };
+        assert!(
+            matches!(&parts[0], dom::Part::OptionName { plugin: p, name, value, .. }
+            if *p == plugin("foo.bar.bam", "lookup") && name == "a" && *value == Some("b".into()))
+        );
+        assert!(
+            matches!(&parts[2], dom::Part::ReturnValue { plugin: p, name, .. }
+            if *p == plugin("foo.bar.bam", "filter") && name == "c")
+        );
+        assert!(matches!(&parts[4], dom::Part::OptionName { plugin: p, .. }
+            if *p == plugin("foo.bar.baz", "module")));
+
+        let context = Context::new();
+        let parts = parse_without_sources("O(bam#lookup:d)", &context, &ParseOptions::default());
+        assert!(matches!(&parts[0], dom::Part::Error { .. }));
+    }
+
+    #[test]
+    fn parse_preserve_escapes() {
+        let context = Context::new();
+        let input = "V(a\\)b\\c) and O(x=\\\\y) E(FOO)";
+        let parts = parse(input, &context, &ParseOptions::default().preserve_escapes());
+        assert_eq!(
+            parts[0].escapes,
+            vec!(
+                dom::EscapePosition {
+                    parameter: 0,
+                    offset: 1,
+                    source_offset: 3,
+                },
+                dom::EscapePosition {
+                    parameter: 0,
+                    offset: 3,
+                    source_offset: 6,
+                },
+            )
+        );
+        let dom::Part::OptionValue { value } = &parts[0].part else {
+            panic!("unexpected part {:?}", parts[0]);
+        };
+        assert_eq!(value, "a)bc");
+        assert_eq!(
+            format!("V({})", escape_parameter(value, 0, &parts[0].escapes)),
+            parts[0].source
+        );
+        assert_eq!(parts[2].escapes.len(), 1);
+        assert_eq!(parts[2].escapes[0].source_offset, 4);
+        assert!(parts[4].escapes.is_empty());
+
+        let parts = parse(input, &context, &ParseOptions::default());
+        assert!(parts.iter().all(|part| part.escapes.is_empty()));
+    }
+
+    #[test]
+    fn join_split_commands_test() {
+        let context = Context::new();
+        let opts = ParseOptions::default();
+        let joined = join_split_commands(
+            [
+                "See L(the docs,",
+                "https://example.com/docs) and",
+                "C(foo)",
+                "Broken C(bar",
+            ]
+            .into_iter(),
+            &opts,
+        );
+        assert_eq!(
+            joined.paragraphs,
+            vec!(
+                "See L(the docs, https://example.com/docs) and",
+                "C(foo)",
+                "Broken C(bar"
+            )
+        );
+        assert!(matches!(joined.paragraphs[1], Cow::Borrowed(_)));
+        assert_eq!(joined.origins, vec!(0..2, 2..3, 3..4));
+        assert_eq!(
+            joined.warnings,
+            vec!("Joined paragraphs 1 to 2 to close the command at index 5 of paragraph 1")
+        );
+        let paragraphs = joined.parse_without_sources(&context, &opts);
+        assert_eq!(
+            paragraphs[0][1],
+            dom::Part::Link {
+                text: "the docs",
+                url: "https://example.com/docs"
+            }
+        );
+        assert!(matches!(paragraphs[2][1], dom::Part::Error { .. }));
+
+        // A command that the following paragraph does not close is never joined.
+        let joined = join_split_commands(["C(foo", "bar", "baz)"].into_iter(), &opts);
+        assert_eq!(joined.paragraphs, vec!("C(foo", "bar", "baz)"));
+        assert_eq!(joined.origins, vec!(0..1, 1..2, 2..3));
+        assert!(joined.warnings.is_empty());
+    }
+
+    #[test]
+    fn parse_option_aliases() {
+        let plugin = dom::PluginIdentifier {
+            fqcn: "foo.bar.baz".to_string(),
+            r#type: "module".to_string(),
+        };
+        let aliases = OptionAliases::new().alias(&plugin, None, &["n"], "name");
+        let context = Context::new()
+            .current_plugin("foo.bar.baz", "module")
+            .option_aliases(Rc::new(aliases));
+        let parts = parse_without_sources(
+            "O(n[1]=x) O(foo.bar.baz#module:n) O(foo.bar.bam#module:n) RV(n)",
+            &context,
+            &ParseOptions::default(),
+        );
+        let links: Vec<(&str, &[CompactString])> = parts
+            .iter()
+            .filter_map(|part| match part {
+                dom::Part::OptionName { name, link, .. }
+                | dom::Part::ReturnValue { name, link, .. } => Some((name.as_ref(), &**link)),
+                _ => None,
             })
+            .collect();
+        assert_eq!(
+            links,
+            vec!(
+                ("n[1]", &["name".into()] as &[CompactString]),
+                ("n", &["name".into()]),
+                ("n", &["n".into()]),
+                ("n", &["n".into()]),
+            )
+        );
+    }
+
+    #[test]
+    fn parse_missing_entrypoint() {
+        let context = Context::new();
+        let input = "O(foo.bar.baz#role:opt)";
+        let entrypoint = |parts: &[dom::PartWithSource]| match &parts[0].part {
+            dom::Part::OptionName { entrypoint, .. } => entrypoint.as_deref().cloned(),
+            part => panic!("unexpected part {:?}", part),
+        };
+        let (parts, warnings) = parse_with_warnings(input, &context, &ParseOptions::default());
+        assert!(matches!(parts[0].part, dom::Part::Error { .. }));
+        assert!(warnings.is_empty());
+
+        let opts = ParseOptions::default().missing_entrypoint(MissingEntrypoint::AssumeMain);
+        let (parts, warnings) = parse_with_warnings(input, &context, &opts);
+        assert_eq!(entrypoint(&parts), Some("main".to_string()));
+        assert!(warnings.is_empty());
+
+        let opts = ParseOptions::default().missing_entrypoint(MissingEntrypoint::WarnAndAssumeMain);
+        let (parts, warnings) =
+            parse_paragraphs_with_warnings(["", input].into_iter(), &context, &opts);
+        assert_eq!(entrypoint(&parts[1]), Some("main".to_string()));
+        assert_eq!(
+            warnings,
+            vec!("While parsing \"O(foo.bar.baz#role:opt)\" at index 1 of paragraph 2: Role reference is missing entrypoint, assuming \"main\"")
+        );
+    }
+
+    #[test]
+    fn parse_plugin_type_synonyms() {
+        let context = Context::new();
+        let input = "P(foo.bar.baz#modules) O(foo.bar.baz#filter_plugin:a)";
+        let plugin_types = |parts: &[dom::PartWithSource]| -> Vec<String> {
+            parts
+                .iter()
+                .filter_map(|part| match &part.part {
+                    dom::Part::Plugin { plugin } => Some(plugin.r#type.clone()),
+                    dom::Part::OptionName {
+                        plugin: Some(plugin),
+                        ..
+                    } => Some(plugin.r#type.clone()),
+                    _ => None,
+                })
+                .collect()
+        };
+        let (parts, warnings) = parse_with_warnings(input, &context, &ParseOptions::default());
+        assert_eq!(plugin_types(&parts), vec!("modules", "filter_plugin"));
+        assert!(warnings.is_empty());
+
+        let opts = ParseOptions::default().plugin_type_synonyms(PluginTypeSynonyms::new());
+        let (parts, warnings) = parse_with_warnings(input, &context, &opts);
+        assert_eq!(plugin_types(&parts), vec!("module", "filter"));
+        assert_eq!(
+            warnings,
+            vec!(
+                "While parsing \"P(foo.bar.baz#modules)\" at index 1: Plugin type \"modules\" is not valid, assuming \"module\"",
+                "While parsing \"O(foo.bar.baz#filter_plugin:a)\" at index 24: Plugin type \"filter_plugin\" is not valid, assuming \"filter\"",
+            )
         );
     }
 }
@@ -0,0 +1,108 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+
+/// The plugin types, which all have common synonyms like `modules` or `filter_plugins`.
+pub(crate) const PLUGIN_TYPES: [&str; 16] = [
+    "become",
+    "cache",
+    "callback",
+    "cliconf",
+    "connection",
+    "filter",
+    "httpapi",
+    "inventory",
+    "lookup",
+    "module",
+    "netconf",
+    "role",
+    "shell",
+    "strategy",
+    "test",
+    "vars",
+];
+
+/// Maps misspelled plugin types, like `modules` or `filter_plugin`, to the correct plugin types.
+///
+/// Used by the parser if set with [`ParseOptions::plugin_type_synonyms`](crate::markup::ParseOptions::plugin_type_synonyms).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginTypeSynonyms {
+    synonyms: BTreeMap<String, String>,
+}
+
+impl PluginTypeSynonyms {
+    /// Create a map with the common synonyms: plurals (`modules`, `inventories`), the names of
+    /// plugin directories (`filter_plugins`, `library`), and their singulars (`filter_plugin`).
+    pub fn new() -> PluginTypeSynonyms {
+        let mut result = PluginTypeSynonyms::empty();
+        for plugin_type in PLUGIN_TYPES {
+            let plural = match plugin_type {
+                "inventory" => "inventories".to_string(),
+                "vars" => "vars".to_string(),
+                _ => format!("{}s", plugin_type),
+            };
+            result.add_synonym(&plural, plugin_type);
+            result.add_synonym(&format!("{}_plugin", plugin_type), plugin_type);
+            result.add_synonym(&format!("{}_plugins", plugin_type), plugin_type);
+        }
+        result.add_synonym("library", "module");
+        result
+    }
+
+    /// Create a map without synonyms.
+    pub fn empty() -> PluginTypeSynonyms {
+        PluginTypeSynonyms {
+            synonyms: BTreeMap::new(),
+        }
+    }
+
+    /// Add a synonym.
+    pub fn synonym(mut self, synonym: &str, plugin_type: &str) -> Self {
+        self.add_synonym(synonym, plugin_type);
+        self
+    }
+
+    /// Add a synonym. A synonym that is the plugin type itself is ignored.
+    pub fn add_synonym(&mut self, synonym: &str, plugin_type: &str) {
+        if synonym != plugin_type {
+            self.synonyms
+                .insert(synonym.to_string(), plugin_type.to_string());
+        }
+    }
+
+    /// Return the plugin type for `synonym`, or `None` if it is not a known synonym.
+    pub fn normalize(&self, synonym: &str) -> Option<&str> {
+        self.synonyms.get(synonym).map(String::as_str)
+    }
+}
+
+impl Default for PluginTypeSynonyms {
+    fn default() -> Self {
+        PluginTypeSynonyms::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        let synonyms = PluginTypeSynonyms::new().synonym("filtr", "filter");
+        assert_eq!(synonyms.normalize("modules"), Some("module"));
+        assert_eq!(synonyms.normalize("library"), Some("module"));
+        assert_eq!(synonyms.normalize("inventories"), Some("inventory"));
+        assert_eq!(synonyms.normalize("filter_plugin"), Some("filter"));
+        assert_eq!(synonyms.normalize("lookup_plugins"), Some("lookup"));
+        assert_eq!(synonyms.normalize("filtr"), Some("filter"));
+        assert_eq!(synonyms.normalize("vars"), None);
+        assert_eq!(synonyms.normalize("module"), None);
+        assert_eq!(PluginTypeSynonyms::empty().normalize("modules"), None);
+    }
+}
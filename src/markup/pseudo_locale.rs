@@ -0,0 +1,138 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::Part;
+use crate::markup::format::Formatter;
+use crate::util::stringbuilder::Appender;
+
+const LOWERCASE: [char; 26] = [
+    'å', 'ƀ', 'ç', 'ð', 'é', 'ƒ', 'ĝ', 'ĥ', 'î', 'ĵ', 'ķ', 'ļ', 'ɱ', 'ñ', 'ö', 'þ', 'ǫ', 'ŕ', 'š',
+    'ţ', 'û', 'ṽ', 'ŵ', 'ẋ', 'ý', 'ž',
+];
+
+const UPPERCASE: [char; 26] = [
+    'Å', 'Ɓ', 'Ç', 'Ð', 'É', 'Ƒ', 'Ĝ', 'Ĥ', 'Î', 'Ĵ', 'Ķ', 'Ļ', 'Ṁ', 'Ñ', 'Ö', 'Þ', 'Ǫ', 'Ŕ', 'Š',
+    'Ţ', 'Û', 'Ṽ', 'Ŵ', 'Ẋ', 'Ý', 'Ž',
+];
+
+/// Pseudo-localize human-readable text: ASCII letters are replaced by accented variants, and
+/// the text is wrapped in `⟦…⟧`.
+///
+/// Leading and trailing whitespace stays outside of the markers, and text consisting only of
+/// whitespace is returned unchanged. The result only depends on `text`.
+pub fn pseudo_localize(text: &str) -> String {
+    let content = text.trim();
+    if content.is_empty() {
+        return text.to_string();
+    }
+    let start = text.len() - text.trim_start().len();
+    let end = start + content.len();
+    let mut result = String::with_capacity(text.len() * 2 + 6);
+    result.push_str(&text[..start]);
+    result.push('⟦');
+    for c in content.chars() {
+        result.push(match c {
+            'a'..='z' => LOWERCASE[(c as u8 - b'a') as usize],
+            'A'..='Z' => UPPERCASE[(c as u8 - b'A') as usize],
+            _ => c,
+        });
+    }
+    result.push('⟧');
+    result.push_str(&text[end..]);
+    result
+}
+
+/// Wraps another formatter and pseudo-localizes all human-readable text with
+/// [`pseudo_localize`].
+///
+/// Text, italic and bold text, and the texts of links and RST references are pseudo-localized.
+/// Code, module and plugin names, URLs, options, values, environment variables, and return
+/// values are passed to the wrapped formatter unchanged. This allows localization tooling to
+/// verify which parts of generated pages are translatable. Use it with
+/// [`Renderer::with_formatter`](crate::markup::Renderer::with_formatter).
+pub struct PseudoLocalizingFormatter<'f> {
+    formatter: &'f dyn for<'a> Formatter<'a>,
+}
+
+impl<'f> PseudoLocalizingFormatter<'f> {
+    pub fn new(formatter: &'f dyn for<'a> Formatter<'a>) -> PseudoLocalizingFormatter<'f> {
+        PseudoLocalizingFormatter { formatter }
+    }
+}
+
+impl<'a> Formatter<'a> for PseudoLocalizingFormatter<'_> {
+    fn append(&self, appender: &mut dyn Appender<'a>, part: &'a Part<'a>, url: Option<String>) {
+        let localized;
+        let part = match part {
+            Part::Text { text } => {
+                localized = pseudo_localize(text);
+                Part::Text { text: &localized }
+            }
+            Part::Italic { text } => {
+                localized = pseudo_localize(text);
+                Part::Italic { text: &localized }
+            }
+            Part::Bold { text } => {
+                localized = pseudo_localize(text);
+                Part::Bold { text: &localized }
+            }
+            Part::Link { text, url } => {
+                localized = pseudo_localize(text);
+                Part::Link {
+                    text: &localized,
+                    url,
+                }
+            }
+            Part::RSTRef { text, r#ref } => {
+                localized = pseudo_localize(text);
+                Part::RSTRef {
+                    text: &localized,
+                    r#ref,
+                }
+            }
+            _ => {
+                self.formatter.append(appender, part, url);
+                return;
+            }
+        };
+        let mut result = String::new();
+        self.formatter.append(&mut result, &part, url);
+        appender.push_owned_string(result);
+    }
+}
+
+#[cfg(all(test, feature = "html"))]
+mod tests {
+    use super::*;
+    use crate::markup::{parse_paragraphs, Context, NoLinkProvider, ParseOptions, Renderer};
+
+    #[test]
+    fn test_pseudo_localize() {
+        assert_eq!(pseudo_localize(" Hello, World! "), " ⟦Ĥéļļö, Ŵöŕļð!⟧ ");
+        assert_eq!(pseudo_localize("  "), "  ");
+
+        let context = Context::new();
+        let paragraphs = parse_paragraphs(
+            [
+                "Use I(foo) or L(the docs, https://example.com) with C(x=1), M(foo.bar.baz), and O(a=b).",
+            ]
+            .into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let link_provider = NoLinkProvider::new();
+        let renderer = Renderer::plain_html(&link_provider);
+        let formatter = PseudoLocalizingFormatter::new(renderer.formatter());
+        assert_eq!(
+            renderer.with_formatter(&formatter).render(&paragraphs),
+            concat!(
+                "<p>⟦Ûšé⟧ <em>⟦ƒöö⟧</em> ⟦öŕ⟧ <a href='https://example.com'>⟦ţĥé ðöçš⟧</a> ",
+                "⟦ŵîţĥ⟧ <code>x=1</code>⟦,⟧ <span>foo.bar.baz</span>⟦, åñð⟧ ",
+                "<code>a=b</code>⟦.⟧</p>",
+            )
+        );
+    }
+}
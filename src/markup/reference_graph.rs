@@ -0,0 +1,371 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::{Part, PluginIdentifier};
+use crate::util::json::push_json_string;
+use compact_str::CompactString;
+use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
+
+/// What a reference between two plugins points to.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ReferenceTarget {
+    /// The plugin itself, from `M()` or `P()`.
+    Plugin,
+
+    /// An option, from `O()`. The name does not contain array stubs.
+    Option {
+        entrypoint: Option<String>,
+        name: String,
+    },
+
+    /// A return value, from `RV()`. The name does not contain array stubs.
+    ReturnValue {
+        entrypoint: Option<String>,
+        name: String,
+    },
+}
+
+impl ReferenceTarget {
+    fn kind(&self) -> &'static str {
+        match self {
+            ReferenceTarget::Plugin => "plugin",
+            ReferenceTarget::Option { .. } => "option",
+            ReferenceTarget::ReturnValue { .. } => "return_value",
+        }
+    }
+
+    fn entrypoint_and_name(&self) -> Option<(Option<&str>, &str)> {
+        match self {
+            ReferenceTarget::Plugin => None,
+            ReferenceTarget::Option { entrypoint, name }
+            | ReferenceTarget::ReturnValue { entrypoint, name } => {
+                Some((entrypoint.as_deref(), name))
+            }
+        }
+    }
+
+    fn label(&self) -> String {
+        match self.entrypoint_and_name() {
+            None => self.kind().to_string(),
+            Some((Some(entrypoint), name)) => format!("{} {}:{}", self.kind(), entrypoint, name),
+            Some((None, name)) => format!("{} {}", self.kind(), name),
+        }
+    }
+}
+
+/// A directed graph of the references between plugins in their documentation.
+///
+/// Every plugin whose documentation was added is a node, as well as every plugin that is
+/// referenced. References of a plugin to itself are ignored.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ReferenceGraph {
+    nodes: BTreeSet<PluginIdentifier>,
+    edges: BTreeMap<PluginIdentifier, BTreeMap<PluginIdentifier, BTreeSet<ReferenceTarget>>>,
+}
+
+fn option_like_target(
+    entrypoint: &Option<Rc<String>>,
+    link: &[CompactString],
+    option: bool,
+) -> ReferenceTarget {
+    let entrypoint = entrypoint.as_deref().cloned();
+    let name = link.join(".");
+    if option {
+        ReferenceTarget::Option { entrypoint, name }
+    } else {
+        ReferenceTarget::ReturnValue { entrypoint, name }
+    }
+}
+
+/// The collection of an FQCN, like `community.general` for `community.general.foo`.
+fn collection_of(fqcn: &str) -> &str {
+    match fqcn.match_indices('.').nth(1) {
+        Some((index, _)) => &fqcn[..index],
+        None => fqcn,
+    }
+}
+
+fn dot_node(plugin: &PluginIdentifier) -> String {
+    let mut result = String::new();
+    push_json_string(&mut result, &format!("{} ({})", plugin.fqcn, plugin.r#type));
+    result
+}
+
+fn push_plugin(result: &mut String, plugin: &PluginIdentifier) {
+    result.push_str("{\"fqcn\": ");
+    push_json_string(result, &plugin.fqcn);
+    result.push_str(", \"type\": ");
+    push_json_string(result, &plugin.r#type);
+    result.push('}');
+}
+
+impl ReferenceGraph {
+    pub fn new() -> ReferenceGraph {
+        ReferenceGraph::default()
+    }
+
+    /// Add a plugin, even if its documentation does not reference other plugins.
+    pub fn add_plugin(&mut self, plugin: &PluginIdentifier) {
+        self.nodes.insert(plugin.clone());
+    }
+
+    /// Add the references in the paragraphs of the documentation of `from`.
+    ///
+    /// Options and return values without plugin have been resolved to the current plugin by
+    /// the parser, so they are references of `from` to itself.
+    pub fn add_paragraphs<'a, I, II>(&mut self, from: &PluginIdentifier, paragraphs: I)
+    where
+        I: IntoIterator<Item = II>,
+        II: IntoIterator<Item = &'a Part<'a>>,
+    {
+        self.add_plugin(from);
+        for part in paragraphs.into_iter().flatten() {
+            let (to, target) = match part {
+                Part::Module { fqcn } => (
+                    PluginIdentifier {
+                        fqcn: fqcn.to_string(),
+                        r#type: "module".to_string(),
+                    },
+                    ReferenceTarget::Plugin,
+                ),
+                Part::Plugin { plugin } => (plugin.clone(), ReferenceTarget::Plugin),
+                Part::OptionName {
+                    plugin: Some(plugin),
+                    entrypoint,
+                    link,
+                    ..
+                } => (
+                    (**plugin).clone(),
+                    option_like_target(entrypoint, link, true),
+                ),
+                Part::ReturnValue {
+                    plugin: Some(plugin),
+                    entrypoint,
+                    link,
+                    ..
+                } => (
+                    (**plugin).clone(),
+                    option_like_target(entrypoint, link, false),
+                ),
+                _ => continue,
+            };
+            if to == *from {
+                continue;
+            }
+            self.nodes.insert(to.clone());
+            self.edges
+                .entry(from.clone())
+                .or_default()
+                .entry(to)
+                .or_default()
+                .insert(target);
+        }
+    }
+
+    /// All plugins in the graph, ordered by FQCN and type.
+    pub fn plugins(&self) -> impl Iterator<Item = &PluginIdentifier> {
+        self.nodes.iter()
+    }
+
+    /// The plugins referenced by the documentation of `from`, with what is referenced.
+    pub fn references(
+        &self,
+        from: &PluginIdentifier,
+    ) -> Vec<(&PluginIdentifier, &BTreeSet<ReferenceTarget>)> {
+        self.edges
+            .get(from)
+            .map(|edges| edges.iter().collect())
+            .unwrap_or_default()
+    }
+
+    /// The plugins whose documentation references `to`, for example for a "referenced by"
+    /// section.
+    pub fn referenced_by(&self, to: &PluginIdentifier) -> Vec<&PluginIdentifier> {
+        self.edges
+            .iter()
+            .filter(|(_, edges)| edges.contains_key(to))
+            .map(|(from, _)| from)
+            .collect()
+    }
+
+    /// The number of references between collections, keyed by the referencing and the
+    /// referenced collection. References inside a collection are included.
+    pub fn collection_coupling(&self) -> BTreeMap<(String, String), usize> {
+        let mut result = BTreeMap::new();
+        for (from, edges) in &self.edges {
+            for (to, targets) in edges {
+                let key = (
+                    collection_of(&from.fqcn).to_string(),
+                    collection_of(&to.fqcn).to_string(),
+                );
+                *result.entry(key).or_insert(0) += targets.len();
+            }
+        }
+        result
+    }
+
+    /// Render the graph in the DOT language of Graphviz.
+    ///
+    /// Every pair of plugins has one edge, labelled with what is referenced.
+    pub fn to_dot(&self) -> String {
+        let mut result = String::from("digraph references {\n");
+        for node in &self.nodes {
+            result.push_str(&format!("  {};\n", dot_node(node)));
+        }
+        for (from, edges) in &self.edges {
+            for (to, targets) in edges {
+                let labels: Vec<String> = targets.iter().map(ReferenceTarget::label).collect();
+                let mut label = String::new();
+                push_json_string(&mut label, &labels.join("\n"));
+                result.push_str(&format!(
+                    "  {} -> {} [label={}];\n",
+                    dot_node(from),
+                    dot_node(to),
+                    label
+                ));
+            }
+        }
+        result.push_str("}\n");
+        result
+    }
+
+    /// Render the graph as a JSON object with `nodes`, a list of plugins, and `edges`, a list
+    /// of objects with the plugins `from` and `to` and the list of `references`.
+    pub fn to_json(&self) -> String {
+        let mut result = String::from("{\"nodes\": [");
+        for (index, node) in self.nodes.iter().enumerate() {
+            if index > 0 {
+                result.push_str(", ");
+            }
+            push_plugin(&mut result, node);
+        }
+        result.push_str("], \"edges\": [");
+        let mut first = true;
+        for (from, edges) in &self.edges {
+            for (to, targets) in edges {
+                if !first {
+                    result.push_str(", ");
+                }
+                first = false;
+                result.push_str("{\"from\": ");
+                push_plugin(&mut result, from);
+                result.push_str(", \"to\": ");
+                push_plugin(&mut result, to);
+                result.push_str(", \"references\": [");
+                for (index, target) in targets.iter().enumerate() {
+                    if index > 0 {
+                        result.push_str(", ");
+                    }
+                    result.push_str("{\"kind\": ");
+                    push_json_string(&mut result, target.kind());
+                    if let Some((entrypoint, name)) = target.entrypoint_and_name() {
+                        result.push_str(", \"entrypoint\": ");
+                        match entrypoint {
+                            Some(entrypoint) => push_json_string(&mut result, entrypoint),
+                            None => result.push_str("null"),
+                        }
+                        result.push_str(", \"name\": ");
+                        push_json_string(&mut result, name);
+                    }
+                    result.push('}');
+                }
+                result.push_str("]}");
+            }
+        }
+        result.push_str("]}");
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::ParseOptions;
+    use crate::markup::{parse_paragraphs_without_sources, Context};
+
+    #[test]
+    fn test_reference_graph() {
+        let plugin = |fqcn: &str, r#type: &str| PluginIdentifier {
+            fqcn: fqcn.to_string(),
+            r#type: r#type.to_string(),
+        };
+        let baz = plugin("foo.bar.baz", "module");
+        let context = Context::new().current_plugin("foo.bar.baz", "module");
+        let paragraphs = parse_paragraphs_without_sources(
+            [
+                "See M(foo.bar.bam) and O(foo.bar.bam#module:a[].b), not O(c).",
+                "Use P(other.coll.x#lookup) and RV(other.coll.x#lookup:_raw).",
+                "M(foo.bar.bam)",
+            ]
+            .into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let mut graph = ReferenceGraph::new();
+        graph.add_paragraphs(&baz, paragraphs.iter());
+        graph.add_plugin(&plugin("foo.bar.unused", "module"));
+
+        let bam = plugin("foo.bar.bam", "module");
+        let x = plugin("other.coll.x", "lookup");
+        assert_eq!(graph.plugins().count(), 4);
+        assert_eq!(
+            graph.references(&baz),
+            vec![
+                (
+                    &bam,
+                    &BTreeSet::from([
+                        ReferenceTarget::Plugin,
+                        ReferenceTarget::Option {
+                            entrypoint: None,
+                            name: "a.b".to_string(),
+                        },
+                    ])
+                ),
+                (
+                    &x,
+                    &BTreeSet::from([
+                        ReferenceTarget::Plugin,
+                        ReferenceTarget::ReturnValue {
+                            entrypoint: None,
+                            name: "_raw".to_string(),
+                        },
+                    ])
+                ),
+            ]
+        );
+        assert_eq!(graph.referenced_by(&x), vec![&baz]);
+        assert!(graph.referenced_by(&baz).is_empty());
+        assert_eq!(
+            graph.collection_coupling(),
+            BTreeMap::from([
+                (("foo.bar".to_string(), "foo.bar".to_string()), 2),
+                (("foo.bar".to_string(), "other.coll".to_string()), 2),
+            ])
+        );
+
+        let mut graph = ReferenceGraph::new();
+        graph.add_paragraphs(&baz, [paragraphs[2].iter()]);
+        assert_eq!(
+            graph.to_dot(),
+            concat!(
+                "digraph references {\n",
+                "  \"foo.bar.bam (module)\";\n",
+                "  \"foo.bar.baz (module)\";\n",
+                "  \"foo.bar.baz (module)\" -> \"foo.bar.bam (module)\" [label=\"plugin\"];\n",
+                "}\n",
+            )
+        );
+        assert_eq!(
+            graph.to_json(),
+            concat!(
+                r#"{"nodes": [{"fqcn": "foo.bar.bam", "type": "module"}, {"fqcn": "foo.bar.baz", "type": "module"}], "#,
+                r#""edges": [{"from": {"fqcn": "foo.bar.baz", "type": "module"}, "to": {"fqcn": "foo.bar.bam", "type": "module"}, "#,
+                r#""references": [{"kind": "plugin"}]}]}"#,
+            )
+        );
+    }
+}
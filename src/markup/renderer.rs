@@ -0,0 +1,581 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom;
+use crate::markup::format::{self, DeterministicLinkProvider, Formatter, LinkProvider, OptionLike};
+#[cfg(feature = "ansible-doc")]
+use crate::markup::ANSIBLE_DOC_TEXT_FORMATTER;
+#[cfg(feature = "bbcode")]
+use crate::markup::BBCODE_FORMATTER;
+#[cfg(feature = "md")]
+use crate::markup::MARKDOWN_FORMATTER;
+#[cfg(feature = "html")]
+use crate::markup::{ANTSIBULL_HTML_FORMATTER, PLAIN_HTML_FORMATTER};
+#[cfg(feature = "rst")]
+use crate::markup::{ANTSIBULL_RST_FORMATTER, PLAIN_RST_FORMATTER};
+use crate::util::stringbuilder::Appender;
+use compact_str::CompactString;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+/// The sequences a renderer puts around and between paragraphs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParagraphOptions {
+    /// Inserted before every paragraph.
+    pub par_start: &'static str,
+
+    /// Inserted after every paragraph.
+    pub par_end: &'static str,
+
+    /// Inserted between two paragraphs.
+    pub par_sep: &'static str,
+
+    /// Inserted for paragraphs without parts.
+    pub par_empty: &'static str,
+}
+
+impl ParagraphOptions {
+    /// Create paragraph options, for example for a renderer with a custom formatter.
+    pub const fn new(
+        par_start: &'static str,
+        par_end: &'static str,
+        par_sep: &'static str,
+        par_empty: &'static str,
+    ) -> ParagraphOptions {
+        ParagraphOptions {
+            par_start,
+            par_end,
+            par_sep,
+            par_empty,
+        }
+    }
+}
+
+#[cfg(feature = "html")]
+const HTML_PARAGRAPHS: ParagraphOptions = ParagraphOptions::new("<p>", "</p>", "", "");
+#[cfg(feature = "md")]
+const MD_PARAGRAPHS: ParagraphOptions = ParagraphOptions::new("", "", "\n\n", " ");
+#[cfg(feature = "rst")]
+const RST_PARAGRAPHS: ParagraphOptions = ParagraphOptions::new("", "", "\n\n", "\\ ");
+#[cfg(any(feature = "ansible-doc", feature = "bbcode"))]
+const TEXT_PARAGRAPHS: ParagraphOptions = ParagraphOptions::new("", "", "\n\n", "");
+
+/// Remembers the URLs returned by a link provider.
+struct MemoizingLinkProvider<'r> {
+    link_provider: &'r dyn LinkProvider,
+    plugin_links: RefCell<HashMap<(String, String), Option<String>>>,
+    option_like_links: RefCell<HashMap<String, Option<String>>>,
+    env_variable_links: RefCell<HashMap<String, Option<String>>>,
+}
+
+impl LinkProvider for MemoizingLinkProvider<'_> {
+    fn plugin_link(&self, plugin: &dom::PluginIdentifier) -> Option<String> {
+        let key = (plugin.fqcn.clone(), plugin.r#type.clone());
+        if let Some(link) = self.plugin_links.borrow().get(&key) {
+            return link.clone();
+        }
+        let link = self.link_provider.plugin_link(plugin);
+        self.plugin_links.borrow_mut().insert(key, link.clone());
+        link
+    }
+
+    fn plugin_option_like_link(
+        &self,
+        plugin: &dom::PluginIdentifier,
+        entrypoint: Option<&String>,
+        what: OptionLike,
+        name: &[CompactString],
+        current_plugin: bool,
+    ) -> Option<String> {
+        let key = format!(
+            "{}\0{}\0{}\0{}\0{}\0{}",
+            plugin.fqcn,
+            plugin.r#type,
+            entrypoint.map(|ep| ep.as_str()).unwrap_or(""),
+            match what {
+                OptionLike::Option => "option",
+                OptionLike::RetVal => "retval",
+            },
+            name.join("."),
+            current_plugin,
+        );
+        if let Some(link) = self.option_like_links.borrow().get(&key) {
+            return link.clone();
+        }
+        let link = self.link_provider.plugin_option_like_link(
+            plugin,
+            entrypoint,
+            what,
+            name,
+            current_plugin,
+        );
+        self.option_like_links
+            .borrow_mut()
+            .insert(key, link.clone());
+        link
+    }
+
+    fn env_variable_link(&self, name: &str) -> Option<String> {
+        if let Some(link) = self.env_variable_links.borrow().get(name) {
+            return link.clone();
+        }
+        let link = self.link_provider.env_variable_link(name);
+        self.env_variable_links
+            .borrow_mut()
+            .insert(name.to_string(), link.clone());
+        link
+    }
+}
+
+/// Renders paragraphs with a formatter, a link provider, and paragraph options that are bound
+/// once.
+///
+/// The URLs returned by the link provider are memoized for the lifetime of the renderer, so
+/// one renderer should be used for all paragraphs of a plugin.
+///
+/// The output only depends on the paragraphs, the formatter, the paragraph options, the current
+/// plugin, and the links returned by the link provider. Use [`render_deterministic`] to make
+/// sure that the output can be reproduced, for example for reproducible builds or caching.
+pub struct Renderer<'r> {
+    formatter: &'r dyn for<'a> Formatter<'a>,
+    link_provider: MemoizingLinkProvider<'r>,
+    pub(crate) options: ParagraphOptions,
+    current_plugin: Option<Rc<dom::PluginIdentifier>>,
+}
+
+impl<'r> Renderer<'r> {
+    /// Create a renderer for an arbitrary formatter.
+    pub fn new(
+        formatter: &'r dyn for<'a> Formatter<'a>,
+        link_provider: &'r dyn LinkProvider,
+        options: ParagraphOptions,
+    ) -> Renderer<'r> {
+        Renderer {
+            formatter,
+            link_provider: MemoizingLinkProvider {
+                link_provider,
+                plugin_links: RefCell::new(HashMap::new()),
+                option_like_links: RefCell::new(HashMap::new()),
+                env_variable_links: RefCell::new(HashMap::new()),
+            },
+            options,
+            current_plugin: None,
+        }
+    }
+
+    /// Create a renderer for the antsibull-docs HTML formatter.
+    #[cfg(feature = "html")]
+    pub fn antsibull_html(link_provider: &'r dyn LinkProvider) -> Renderer<'r> {
+        Renderer::new(&*ANTSIBULL_HTML_FORMATTER, link_provider, HTML_PARAGRAPHS)
+    }
+
+    /// Create a renderer for the plain HTML formatter.
+    #[cfg(feature = "html")]
+    pub fn plain_html(link_provider: &'r dyn LinkProvider) -> Renderer<'r> {
+        Renderer::new(&*PLAIN_HTML_FORMATTER, link_provider, HTML_PARAGRAPHS)
+    }
+
+    /// Create a renderer for the MarkDown formatter.
+    #[cfg(feature = "md")]
+    pub fn md(link_provider: &'r dyn LinkProvider) -> Renderer<'r> {
+        Renderer::new(&*MARKDOWN_FORMATTER, link_provider, MD_PARAGRAPHS)
+    }
+
+    /// Create a renderer for the antsibull-docs RST formatter.
+    #[cfg(feature = "rst")]
+    pub fn antsibull_rst(link_provider: &'r dyn LinkProvider) -> Renderer<'r> {
+        Renderer::new(&*ANTSIBULL_RST_FORMATTER, link_provider, RST_PARAGRAPHS)
+    }
+
+    /// Create a renderer for the plain RST formatter.
+    #[cfg(feature = "rst")]
+    pub fn plain_rst(link_provider: &'r dyn LinkProvider) -> Renderer<'r> {
+        Renderer::new(&*PLAIN_RST_FORMATTER, link_provider, RST_PARAGRAPHS)
+    }
+
+    /// Create a renderer for the ansible-doc text formatter.
+    #[cfg(feature = "ansible-doc")]
+    pub fn ansible_doc_text(link_provider: &'r dyn LinkProvider) -> Renderer<'r> {
+        Renderer::new(&*ANSIBLE_DOC_TEXT_FORMATTER, link_provider, TEXT_PARAGRAPHS)
+    }
+
+    /// Create a renderer for the BBCode formatter.
+    #[cfg(feature = "bbcode")]
+    pub fn bbcode(link_provider: &'r dyn LinkProvider) -> Renderer<'r> {
+        Renderer::new(&*BBCODE_FORMATTER, link_provider, TEXT_PARAGRAPHS)
+    }
+
+    /// The formatter of this renderer.
+    pub fn formatter(&self) -> &'r dyn for<'a> Formatter<'a> {
+        self.formatter
+    }
+
+    /// Replace the formatter, keeping the link provider, the paragraph options, and the current
+    /// plugin. This is useful for formatters wrapping the formatter of a renderer, like
+    /// [`PseudoLocalizingFormatter`](crate::markup::PseudoLocalizingFormatter).
+    pub fn with_formatter(mut self, formatter: &'r dyn for<'a> Formatter<'a>) -> Renderer<'r> {
+        self.formatter = formatter;
+        self
+    }
+
+    /// Set the plugin whose documentation is rendered.
+    pub fn with_current_plugin(
+        mut self,
+        current_plugin: Option<Rc<dom::PluginIdentifier>>,
+    ) -> Renderer<'r> {
+        self.current_plugin = current_plugin;
+        self
+    }
+
+    /// Render a single paragraph.
+    pub fn render_paragraph<'a, I>(&self, appender: &mut dyn Appender<'a>, paragraph: I)
+    where
+        I: Iterator<Item = &'a dom::Part<'a>>,
+    {
+        format::append_paragraph(
+            appender,
+            paragraph,
+            self.formatter,
+            &self.link_provider,
+            self.options.par_start,
+            self.options.par_end,
+            self.options.par_empty,
+            &self.current_plugin,
+        );
+    }
+
+    /// Render several paragraphs.
+    pub fn render_paragraphs<'a, I, II>(&self, appender: &mut dyn Appender<'a>, paragraphs: I)
+    where
+        I: IntoIterator<Item = II>,
+        II: Iterator<Item = &'a dom::Part<'a>>,
+    {
+        format::append_paragraphs(
+            appender,
+            paragraphs,
+            self.formatter,
+            &self.link_provider,
+            self.options.par_start,
+            self.options.par_end,
+            self.options.par_sep,
+            self.options.par_empty,
+            &self.current_plugin,
+        );
+    }
+
+    /// Render a single part without paragraph sequences.
+    pub(crate) fn render_part<'a>(&self, appender: &mut dyn Appender<'a>, part: &'a dom::Part<'a>) {
+        format::append_paragraph(
+            appender,
+            std::iter::once(part),
+            self.formatter,
+            &self.link_provider,
+            "",
+            "",
+            "",
+            &self.current_plugin,
+        );
+    }
+
+    /// Render parsed paragraphs to a string.
+    pub fn render(&self, paragraphs: &[Vec<dom::PartWithSource<'_>>]) -> String {
+        let mut result = String::new();
+        self.render_paragraphs(
+            &mut result,
+            paragraphs
+                .iter()
+                .map(|paragraph| paragraph.iter().map(|ps| &ps.part)),
+        );
+        result
+    }
+}
+
+/// Returned by [`render_deterministic`] if two renderings of the same paragraphs differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NondeterministicRendering {
+    /// The byte offset of the first difference.
+    pub offset: usize,
+}
+
+impl fmt::Display for NondeterministicRendering {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Rendering is not deterministic: outputs differ at byte {}",
+            self.offset
+        )
+    }
+}
+
+impl std::error::Error for NondeterministicRendering {}
+
+/// Render parsed paragraphs twice with fresh renderers created by `renderer`, and check that
+/// both outputs are identical.
+///
+/// Only link providers marked with [`DeterministicLinkProvider`] are accepted. Since every
+/// renderer memoizes links on its own, the second rendering asks the link provider again.
+pub fn render_deterministic<'r, L, F>(
+    link_provider: &'r L,
+    renderer: F,
+    paragraphs: &[Vec<dom::PartWithSource<'_>>],
+) -> Result<String, NondeterministicRendering>
+where
+    L: DeterministicLinkProvider,
+    F: Fn(&'r dyn LinkProvider) -> Renderer<'r>,
+{
+    let first = renderer(link_provider).render(paragraphs);
+    let second = renderer(link_provider).render(paragraphs);
+    match first.bytes().zip(second.bytes()).position(|(a, b)| a != b) {
+        Some(offset) => Err(NondeterministicRendering { offset }),
+        None if first.len() != second.len() => Err(NondeterministicRendering {
+            offset: first.len().min(second.len()),
+        }),
+        None => Ok(first),
+    }
+}
+
+/// Wraps a [`Renderer`] and renders identical parts only once.
+///
+/// Parts like `O(state=present)` often appear thousands of times in the documentation of a
+/// collection. The rendered fragments of all parts except plain text are cached, keyed by the
+/// parts themselves, so the parts must outlive the renderer.
+pub struct DedupRenderer<'r, 'a> {
+    renderer: Renderer<'r>,
+    cache: RefCell<HashMap<&'a dom::Part<'a>, String>>,
+    hits: Cell<usize>,
+}
+
+impl<'r, 'a> DedupRenderer<'r, 'a> {
+    pub fn new(renderer: Renderer<'r>) -> DedupRenderer<'r, 'a> {
+        DedupRenderer {
+            renderer,
+            cache: RefCell::new(HashMap::new()),
+            hits: Cell::new(0),
+        }
+    }
+
+    /// The number of parts whose rendered fragment was taken from the cache.
+    pub fn cache_hits(&self) -> usize {
+        self.hits.get()
+    }
+
+    /// The number of distinct parts in the cache.
+    pub fn cache_size(&self) -> usize {
+        self.cache.borrow().len()
+    }
+
+    fn render_part(&self, appender: &mut dyn Appender<'a>, part: &'a dom::Part<'a>) {
+        if let dom::Part::Text { .. } = part {
+            self.renderer.render_part(appender, part);
+            return;
+        }
+        if let Some(rendered) = self.cache.borrow().get(part) {
+            self.hits.set(self.hits.get() + 1);
+            appender.push_borrowed_string(rendered);
+            return;
+        }
+        let mut rendered = String::new();
+        self.renderer.render_part(&mut rendered, part);
+        appender.push_borrowed_string(&rendered);
+        self.cache.borrow_mut().insert(part, rendered);
+    }
+
+    /// Render a single paragraph.
+    pub fn render_paragraph<I>(&self, appender: &mut dyn Appender<'a>, paragraph: I)
+    where
+        I: Iterator<Item = &'a dom::Part<'a>>,
+    {
+        let options = &self.renderer.options;
+        appender.push_str(options.par_start);
+        let mut empty = true;
+        for part in paragraph {
+            empty = false;
+            self.render_part(appender, part);
+        }
+        if empty {
+            appender.push_str(options.par_empty);
+        }
+        appender.push_str(options.par_end);
+    }
+
+    /// Render several paragraphs.
+    pub fn render_paragraphs<I, II>(&self, appender: &mut dyn Appender<'a>, paragraphs: I)
+    where
+        I: IntoIterator<Item = II>,
+        II: Iterator<Item = &'a dom::Part<'a>>,
+    {
+        for (index, paragraph) in paragraphs.into_iter().enumerate() {
+            if index > 0 {
+                appender.push_str(self.renderer.options.par_sep);
+            }
+            self.render_paragraph(appender, paragraph);
+        }
+    }
+
+    /// Render parsed paragraphs to a string.
+    pub fn render(&self, paragraphs: &'a [Vec<dom::PartWithSource<'a>>]) -> String {
+        let mut result = String::new();
+        self.render_paragraphs(
+            &mut result,
+            paragraphs
+                .iter()
+                .map(|paragraph| paragraph.iter().map(|ps| &ps.part)),
+        );
+        result
+    }
+}
+
+#[cfg(all(test, feature = "html", feature = "md", feature = "rst"))]
+mod tests {
+    use super::*;
+    use crate::markup::{
+        append_md_paragraphs, parse_paragraphs, Context, NoLinkProvider, ParseOptions,
+    };
+
+    struct CountingLinkProvider {
+        calls: Cell<usize>,
+    }
+
+    impl LinkProvider for CountingLinkProvider {
+        fn plugin_link(&self, plugin: &dom::PluginIdentifier) -> Option<String> {
+            self.calls.set(self.calls.get() + 1);
+            Some(format!("https://docs/{}", plugin.fqcn.replace('.', "/")))
+        }
+
+        fn plugin_option_like_link(
+            &self,
+            plugin: &dom::PluginIdentifier,
+            _entrypoint: Option<&String>,
+            _what: OptionLike,
+            name: &[CompactString],
+            _current_plugin: bool,
+        ) -> Option<String> {
+            self.calls.set(self.calls.get() + 1);
+            Some(format!(
+                "https://docs/{}#{}",
+                plugin.fqcn.replace('.', "/"),
+                name.join("/")
+            ))
+        }
+    }
+
+    #[test]
+    fn test_renderer() {
+        let context = Context::new().current_plugin("foo.bar.baz", "module");
+        let paragraphs = parse_paragraphs(
+            ["M(a.b.c) O(x) O(x)", "", "M(a.b.c) O(y=1)"].into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let link_provider = CountingLinkProvider {
+            calls: Cell::new(0),
+        };
+        let renderer =
+            Renderer::md(&link_provider).with_current_plugin(context.current_plugin.clone());
+        let result = renderer.render(&paragraphs);
+        assert_eq!(link_provider.calls.get(), 3);
+
+        let mut expected = String::new();
+        append_md_paragraphs(
+            &mut expected,
+            paragraphs
+                .iter()
+                .map(|paragraph| paragraph.iter().map(|ps| &ps.part)),
+            &link_provider,
+            &renderer.current_plugin,
+        );
+        assert_eq!(result, expected);
+        assert!(result.contains("\n\n \n\n"));
+
+        assert_eq!(
+            Renderer::plain_html(&link_provider).render(&paragraphs[..1]),
+            concat!(
+                "<p><a href='https://docs/a/b/c'>a.b.c</a> ",
+                "<code><strong><a href=\"https://docs/foo/bar/baz#x\">x</a></strong></code> ",
+                "<code><strong><a href=\"https://docs/foo/bar/baz#x\">x</a></strong></code></p>",
+            )
+        );
+    }
+
+    #[test]
+    fn test_dedup_renderer() {
+        let context = Context::new();
+        let paragraphs = parse_paragraphs(
+            [
+                "Set O(state=present) or O(state=absent).",
+                "",
+                "Use O(state=present) and V(foo).",
+            ]
+            .into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let link_provider = CountingLinkProvider {
+            calls: Cell::new(0),
+        };
+        let renderer = DedupRenderer::new(Renderer::antsibull_rst(&link_provider));
+        assert_eq!(
+            renderer.render(&paragraphs),
+            Renderer::antsibull_rst(&link_provider).render(&paragraphs)
+        );
+        assert_eq!(renderer.cache_hits(), 1);
+        assert_eq!(renderer.cache_size(), 3);
+    }
+
+    struct ShufflingLinkProvider {
+        calls: Cell<usize>,
+    }
+
+    impl LinkProvider for ShufflingLinkProvider {
+        fn plugin_link(&self, plugin: &dom::PluginIdentifier) -> Option<String> {
+            self.calls.set(self.calls.get() + 1);
+            Some(format!("https://docs/{}?{}", plugin.fqcn, self.calls.get()))
+        }
+
+        fn plugin_option_like_link(
+            &self,
+            _plugin: &dom::PluginIdentifier,
+            _entrypoint: Option<&String>,
+            _what: OptionLike,
+            _name: &[CompactString],
+            _current_plugin: bool,
+        ) -> Option<String> {
+            None
+        }
+    }
+
+    // Deliberately wrong, to check that the difference is detected.
+    impl DeterministicLinkProvider for ShufflingLinkProvider {}
+
+    #[test]
+    fn test_render_deterministic() {
+        let context = Context::new();
+        let paragraphs = parse_paragraphs(
+            ["See M(a.b.c) and O(a.b.c#module:x)."].into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        assert_eq!(
+            render_deterministic(&NoLinkProvider::new(), Renderer::md, &paragraphs),
+            Ok("See a\\.b\\.c and <code><strong>x</strong></code>\\.".to_string())
+        );
+        let error = render_deterministic(
+            &ShufflingLinkProvider {
+                calls: Cell::new(0),
+            },
+            Renderer::plain_html,
+            &paragraphs,
+        )
+        .unwrap_err();
+        assert_eq!(error, NondeterministicRendering { offset: 35 });
+        assert_eq!(
+            error.to_string(),
+            "Rendering is not deterministic: outputs differ at byte 35"
+        );
+    }
+}
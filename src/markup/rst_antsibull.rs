@@ -8,21 +8,76 @@ use crate::markup::dom;
 use crate::markup::format;
 use crate::markup::html_helper;
 use crate::markup::rst_helper;
+use crate::markup::value_type::OptionValueType;
 use crate::util::stringbuilder;
 use crate::util::stringbuilder::{Appender, IntoString};
+use std::borrow::Cow;
 use std::rc::Rc;
 use std::sync::LazyLock;
 
+/// Options of the antsibull-docs RST formatter.
+///
+/// The defaults reproduce the output of antsibull-docs.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AntsibullRSTOptions {
+    /// Whether to use a role like `:ansval-boolean:` instead of `:ansval:` for values of `V()`,
+    /// depending on the kind of value guessed by [`OptionValueType::classify`].
+    /// [`OptionValueType::Other`] keeps `:ansval:`.
+    ///
+    /// The Sphinx configuration must define the roles `ansval-boolean`, `ansval-integer`,
+    /// `ansval-float`, `ansval-quoted-string`, and `ansval-list`.
+    pub value_type_roles: bool,
+}
+
+impl AntsibullRSTOptions {
+    pub fn new() -> AntsibullRSTOptions {
+        AntsibullRSTOptions::default()
+    }
+
+    /// Set whether to use roles for the kinds of values.
+    pub fn value_type_roles(mut self, value_type_roles: bool) -> Self {
+        self.value_type_roles = value_type_roles;
+        self
+    }
+}
+
 pub struct AntsibullRSTFormatter {
     rst_escaper: rst_helper::RSTEscaper,
     url_escaper: html_helper::URLEscaper,
+    options: AntsibullRSTOptions,
 }
 
 impl AntsibullRSTFormatter {
     fn new() -> AntsibullRSTFormatter {
+        AntsibullRSTFormatter::with_options(AntsibullRSTOptions::new())
+    }
+
+    /// Create a formatter with custom options.
+    pub fn with_options(options: AntsibullRSTOptions) -> AntsibullRSTFormatter {
         AntsibullRSTFormatter {
             rst_escaper: rst_helper::RSTEscaper::new(),
             url_escaper: html_helper::URLEscaper::new(),
+            options,
+        }
+    }
+
+    /// The options of the formatter.
+    pub fn options(&self) -> &AntsibullRSTOptions {
+        &self.options
+    }
+
+    /// The start of the role for the value of `V()`.
+    fn option_value_start(&self, value: &str) -> &'static str {
+        if !self.options.value_type_roles {
+            return "\\ :ansval:`";
+        }
+        match OptionValueType::classify(value) {
+            OptionValueType::Boolean => "\\ :ansval-boolean:`",
+            OptionValueType::Integer => "\\ :ansval-integer:`",
+            OptionValueType::Float => "\\ :ansval-float:`",
+            OptionValueType::QuotedString => "\\ :ansval-quoted-string:`",
+            OptionValueType::List => "\\ :ansval-list:`",
+            OptionValueType::Other => "\\ :ansval:`",
         }
     }
 
@@ -35,7 +90,7 @@ impl AntsibullRSTFormatter {
         end: &'a str,
     ) {
         appender.push_str(start);
-        appender.push_cow_str(self.rst_escaper.escape(text, true, true));
+        appender.push_cow_str(self.rst_escaper.escape_inline_literal(text));
         appender.push_str(end);
     }
 
@@ -45,11 +100,11 @@ impl AntsibullRSTFormatter {
             return;
         }
         if url.len() == 0 {
-            appender.push_cow_str(self.rst_escaper.escape(text, false, false));
+            appender.push_cow_str(self.rst_escaper.escape_body(text));
             return;
         }
         appender.push_str("\\ `");
-        appender.push_cow_str(self.rst_escaper.escape(text, true, false));
+        appender.push_cow_str(self.rst_escaper.escape_link_text(text));
         appender.push_str(" <");
         appender.push_cow_str(self.url_escaper.escape(url));
         appender.push_str(">`__\\ ");
@@ -58,11 +113,13 @@ impl AntsibullRSTFormatter {
     #[inline]
     fn append_fqcn<'a>(&self, appender: &mut dyn Appender<'a>, fqcn: &'a str, r#type: &'a str) {
         appender.push_str("\\ :ref:`");
-        appender.push_cow_str(self.rst_escaper.escape(fqcn, false, false));
-        appender.push_str(" <ansible_collections.");
-        appender.push_str(fqcn);
-        appender.push_str("_");
-        appender.push_str(r#type);
+        appender.push_cow_str(self.rst_escaper.escape_link_text(fqcn));
+        appender.push_str(" <");
+        appender.push_owned_string(
+            self.rst_escaper
+                .escape_target(&format!("ansible_collections.{}_{}", fqcn, r#type))
+                .into_owned(),
+        );
         appender.push_str(">`\\ ");
     }
 
@@ -72,8 +129,8 @@ impl AntsibullRSTFormatter {
         appender: &mut dyn Appender<'a>,
         plugin: &'a Option<Rc<dom::PluginIdentifier>>,
         entrypoint: &'a Option<Rc<String>>,
-        name: &'a String,
-        value: &'a Option<String>,
+        name: &'a str,
+        value: &'a Option<Cow<'a, str>>,
         what: format::OptionLike,
     ) {
         appender.push_str("\\ :");
@@ -100,7 +157,7 @@ impl AntsibullRSTFormatter {
         }
         appender.push_owned_string(
             self.rst_escaper
-                .escape(&builder.into_string(), true, true)
+                .escape_inline_literal(&builder.into_string())
                 .into_owned(),
         );
         appender.push_str("`\\ ");
@@ -112,32 +169,37 @@ impl<'a> format::Formatter<'a> for AntsibullRSTFormatter {
         &self,
         appender: &mut dyn Appender<'a>,
         part: &'a dom::Part<'a>,
-        _url: Option<String>,
+        url: Option<String>,
     ) {
         match part {
-            dom::Part::Text { text } => {
-                appender.push_cow_str(self.rst_escaper.escape(text, false, false))
-            }
+            dom::Part::Text { text } => appender.push_cow_str(self.rst_escaper.escape_body(text)),
             dom::Part::Bold { text } => self.append_tag(appender, "\\ :strong:`", text, "`\\ "),
             dom::Part::Italic { text } => self.append_tag(appender, "\\ :emphasis:`", text, "`\\ "),
             dom::Part::Code { text } => self.append_tag(appender, "\\ :literal:`", text, "`\\ "),
             dom::Part::HorizontalLine => appender.push_str("\n\n.. raw:: html\n\n  <hr>\n\n"),
             dom::Part::OptionValue { value } => {
-                self.append_tag(appender, "\\ :ansval:`", value, "`\\ ")
-            }
-            dom::Part::EnvVariable { name } => {
-                self.append_tag(appender, "\\ :envvar:`", name, "`\\ ")
+                self.append_tag(appender, self.option_value_start(value), value, "`\\ ")
             }
+            dom::Part::EnvVariable { name } => match url {
+                Some(u) => {
+                    appender.push_str("\\ `");
+                    appender.push_cow_str(self.rst_escaper.escape_link_text(name));
+                    appender.push_str(" <");
+                    appender.push_owned_string(self.url_escaper.escape(&u).into_owned());
+                    appender.push_str(">`__\\ ");
+                }
+                None => self.append_tag(appender, "\\ :envvar:`", name, "`\\ "),
+            },
             dom::Part::Error { message } => {
                 appender.push_str("\\ :strong:`ERROR while parsing`\\ : ");
-                appender.push_cow_str(self.rst_escaper.escape(message, true, true));
+                appender.push_cow_str(self.rst_escaper.escape_inline_literal(message));
                 appender.push_str("\\ ");
             }
             dom::Part::RSTRef { text, r#ref } => {
                 appender.push_str("\\ :ref:`");
-                appender.push_cow_str(self.rst_escaper.escape(text, true, true));
+                appender.push_cow_str(self.rst_escaper.escape_inline_literal(text));
                 appender.push_str(" <");
-                appender.push_str(r#ref);
+                appender.push_cow_str(self.rst_escaper.escape_target(r#ref));
                 appender.push_str(">`\\ ");
             }
             dom::Part::Link { text, url } => self.append_link(appender, text, url),
@@ -228,3 +290,39 @@ pub fn append_antsibull_rst_paragraphs<'a, I, II>(
         current_plugin,
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::format::Formatter;
+
+    #[test]
+    fn test_value_type_roles() {
+        let render = |formatter: &AntsibullRSTFormatter, value: &'static str| {
+            let part: &'static dom::Part<'static> = Box::leak(Box::new(dom::Part::OptionValue {
+                value: Cow::Borrowed(value),
+            }));
+            let mut result = String::new();
+            formatter.append(&mut result, part, None);
+            result
+        };
+        let formatter =
+            AntsibullRSTFormatter::with_options(AntsibullRSTOptions::new().value_type_roles(true));
+        assert_eq!(render(&formatter, "no"), "\\ :ansval-boolean:`no`\\ ");
+        assert_eq!(render(&formatter, "[1, 2]"), "\\ :ansval-list:`[1, 2]`\\ ");
+        assert_eq!(render(&formatter, "a b"), "\\ :ansval:`a b`\\ ");
+        assert_eq!(render(&ANTSIBULL_RST_FORMATTER, "no"), "\\ :ansval:`no`\\ ");
+    }
+
+    #[test]
+    fn test_assumed_entrypoint() {
+        use crate::markup::{parse, Context, MissingEntrypoint, ParseOptions};
+
+        let context = Context::new();
+        let opts = ParseOptions::default().missing_entrypoint(MissingEntrypoint::AssumeMain);
+        let parts = parse("O(foo.bar.baz#role:opt)", &context, &opts);
+        let mut result = String::new();
+        ANTSIBULL_RST_FORMATTER.append(&mut result, &parts[0].part, None);
+        assert_eq!(result, "\\ :ansopt:`foo.bar.baz#role:main:opt`\\ ");
+    }
+}
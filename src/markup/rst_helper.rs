@@ -4,13 +4,19 @@ SPDX-FileCopyrightText: 2024, Felix Fontein
 SPDX-License-Identifier: GPL-3.0-or-later
 */
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
 
 #[inline(always)]
 fn is_rst_safe(c: u8) -> bool {
     !matches!(c, b'\\' | b'<' | b'>' | b'_' | b'*' | b'`')
 }
 
+#[inline(always)]
+fn is_rst_target_safe(c: u8) -> bool {
+    !matches!(c, b'\\' | b'<' | b'>' | b'`')
+}
+
 #[inline(always)]
 fn alloc_string(length: usize) -> String {
     String::with_capacity(length | 15)
@@ -23,53 +29,98 @@ impl RSTEscaper {
         RSTEscaper {}
     }
 
+    /// Escape text for RST.
+    ///
+    /// If `escape_ending_whitespace` is set, leading and trailing spaces are escaped so that the
+    /// result can be placed directly between the backticks of a role. If `must_not_be_empty` is
+    /// set, an empty input results in an escaped space.
     pub fn escape<'a>(
         &self,
         text: &'a str,
         escape_ending_whitespace: bool,
         must_not_be_empty: bool,
     ) -> Cow<'a, str> {
-        let length = text.len();
-        if length == 0 {
-            if must_not_be_empty {
-                return Cow::Owned("\\ ".to_string());
-            } else {
-                return Cow::Borrowed(text);
-            }
+        rst_escape(
+            text,
+            is_rst_safe,
+            escape_ending_whitespace,
+            must_not_be_empty,
+        )
+    }
+
+    /// Escape body text, that is text that is not part of any inline markup.
+    pub fn escape_body<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        rst_escape(text, is_rst_safe, false, false)
+    }
+
+    /// Escape the content of an interpreted text role like `:literal:` or `:strong:`.
+    ///
+    /// The result never is empty, and never starts or ends with unescaped whitespace.
+    pub fn escape_inline_literal<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        rst_escape(text, is_rst_safe, true, true)
+    }
+
+    /// Escape the text of a hyperlink reference or cross-reference that is followed by a
+    /// target in angle brackets.
+    pub fn escape_link_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        rst_escape(text, is_rst_safe, true, false)
+    }
+
+    /// Escape the target in angle brackets of a hyperlink reference or cross-reference.
+    ///
+    /// Backslashes, angle brackets, and backticks are escaped, so that a target ending in a
+    /// backslash does not escape the closing `>`.
+    pub fn escape_target<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        rst_escape(text, is_rst_target_safe, false, false)
+    }
+}
+
+fn rst_escape(
+    text: &str,
+    is_safe: fn(u8) -> bool,
+    escape_ending_whitespace: bool,
+    must_not_be_empty: bool,
+) -> Cow<'_, str> {
+    let length = text.len();
+    if length == 0 {
+        if must_not_be_empty {
+            return Cow::Owned("\\ ".to_string());
+        } else {
+            return Cow::Borrowed(text);
         }
-        let mut index = 0;
-        let mut result = alloc_string(length);
-        let mut can_borrow = true;
-        if escape_ending_whitespace {
-            if text.as_bytes()[0] == b' ' {
-                can_borrow = false;
-                result.push_str("\\ ");
-            } else if text.ends_with(" ") {
-                can_borrow = false;
-            }
+    }
+    let mut index = 0;
+    let mut result = alloc_string(length);
+    let mut can_borrow = true;
+    if escape_ending_whitespace {
+        if text.as_bytes()[0] == b' ' {
+            can_borrow = false;
+            result.push_str("\\ ");
+        } else if text.ends_with(" ") {
+            can_borrow = false;
         }
-        loop {
-            let mut next_index = index;
-            while next_index < length && is_rst_safe(text.as_bytes()[next_index]) {
-                next_index += 1;
-            }
-            if index == 0 && can_borrow && next_index == length {
-                return Cow::Borrowed(text);
-            }
-            if index < next_index {
-                result.push_str(&text[index..next_index]);
-            }
-            if next_index == length {
-                if escape_ending_whitespace && index < length && text.ends_with(" ") {
-                    result.push_str("\\ ");
-                }
-                result.shrink_to_fit();
-                return Cow::Owned(result);
+    }
+    loop {
+        let mut next_index = index;
+        while next_index < length && is_safe(text.as_bytes()[next_index]) {
+            next_index += 1;
+        }
+        if index == 0 && can_borrow && next_index == length {
+            return Cow::Borrowed(text);
+        }
+        if index < next_index {
+            result.push_str(&text[index..next_index]);
+        }
+        if next_index == length {
+            if escape_ending_whitespace && index < length && text.ends_with(" ") {
+                result.push_str("\\ ");
             }
-            result.push_str("\\");
-            index = next_index + 1;
-            result.push_str(&text[next_index..index]);
+            result.shrink_to_fit();
+            return Cow::Owned(result);
         }
+        result.push('\\');
+        index = next_index + 1;
+        result.push_str(&text[next_index..index]);
     }
 }
 
@@ -108,5 +159,12 @@ mod tests {
             e.escape(" a\\b<c>d_e*f`g ", true, true),
             "\\  a\\\\b\\<c\\>d\\_e\\*f\\`g \\ "
         );
+
+        assert_eq!(e.escape_body("a\\"), "a\\\\");
+        assert_eq!(e.escape_inline_literal(""), "\\ ");
+        assert_eq!(e.escape_inline_literal(" a`b\\"), "\\  a\\`b\\\\");
+        assert_eq!(e.escape_link_text("a <b> "), "a \\<b\\> \\ ");
+        assert_eq!(e.escape_target("foo_bar"), "foo_bar");
+        assert_eq!(e.escape_target("a<b>`c\\"), "a\\<b\\>\\`c\\\\");
     }
 }
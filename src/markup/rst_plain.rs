@@ -10,6 +10,7 @@ use crate::markup::html_helper;
 use crate::markup::rst_helper;
 use crate::util::stringbuilder;
 use crate::util::stringbuilder::{Appender, IntoString};
+use std::borrow::Cow;
 use std::rc::Rc;
 use std::sync::LazyLock;
 
@@ -35,7 +36,7 @@ impl PlainRSTFormatter {
         end: &'a str,
     ) {
         appender.push_str(start);
-        appender.push_cow_str(self.rst_escaper.escape(text, true, true));
+        appender.push_cow_str(self.rst_escaper.escape_inline_literal(text));
         appender.push_str(end);
     }
 
@@ -45,11 +46,11 @@ impl PlainRSTFormatter {
             return;
         }
         if url.len() == 0 {
-            appender.push_cow_str(self.rst_escaper.escape(text, false, false));
+            appender.push_cow_str(self.rst_escaper.escape_body(text));
             return;
         }
         appender.push_str("\\ `");
-        appender.push_cow_str(self.rst_escaper.escape(text, true, false));
+        appender.push_cow_str(self.rst_escaper.escape_link_text(text));
         appender.push_str(" <");
         appender.push_cow_str(self.url_escaper.escape(url));
         appender.push_str(">`__\\ ");
@@ -58,11 +59,13 @@ impl PlainRSTFormatter {
     #[inline]
     fn append_fqcn<'a>(&self, appender: &mut dyn Appender<'a>, fqcn: &'a str, r#type: &'a str) {
         appender.push_str("\\ :ref:`");
-        appender.push_cow_str(self.rst_escaper.escape(fqcn, false, false));
-        appender.push_str(" <ansible_collections.");
-        appender.push_str(fqcn);
-        appender.push_str("_");
-        appender.push_str(r#type);
+        appender.push_cow_str(self.rst_escaper.escape_link_text(fqcn));
+        appender.push_str(" <");
+        appender.push_owned_string(
+            self.rst_escaper
+                .escape_target(&format!("ansible_collections.{}_{}", fqcn, r#type))
+                .into_owned(),
+        );
         appender.push_str(">`\\ ");
     }
 
@@ -72,8 +75,8 @@ impl PlainRSTFormatter {
         appender: &mut dyn Appender<'a>,
         plugin: &'a Option<Rc<dom::PluginIdentifier>>,
         entrypoint: &'a Option<Rc<String>>,
-        name: &'a String,
-        value: &'a Option<String>,
+        name: &'a str,
+        value: &'a Option<Cow<'a, str>>,
     ) {
         appender.push_str("\\ :literal:`");
 
@@ -85,14 +88,14 @@ impl PlainRSTFormatter {
         }
         appender.push_owned_string(
             self.rst_escaper
-                .escape(&builder.into_string(), true, true)
+                .escape_inline_literal(&builder.into_string())
                 .into_owned(),
         );
         appender.push_str("`");
 
         let escaped_ep = entrypoint
             .as_ref()
-            .map(|ep| self.rst_escaper.escape(&*ep, true, true).into_owned())
+            .map(|ep| self.rst_escaper.escape_inline_literal(ep).into_owned())
             .unwrap_or("".to_string());
         let mut plugin_result: Vec<&'a str> = Vec::with_capacity(11);
         if let Some(p) = plugin {
@@ -133,12 +136,10 @@ impl<'a> format::Formatter<'a> for PlainRSTFormatter {
         &self,
         appender: &mut dyn Appender<'a>,
         part: &'a dom::Part<'a>,
-        _url: Option<String>,
+        url: Option<String>,
     ) {
         match part {
-            dom::Part::Text { text } => {
-                appender.push_cow_str(self.rst_escaper.escape(text, false, false))
-            }
+            dom::Part::Text { text } => appender.push_cow_str(self.rst_escaper.escape_body(text)),
             dom::Part::Bold { text } => self.append_tag(appender, "\\ :strong:`", text, "`\\ "),
             dom::Part::Italic { text } => self.append_tag(appender, "\\ :emphasis:`", text, "`\\ "),
             dom::Part::Code { text } => self.append_tag(appender, "\\ :literal:`", text, "`\\ "),
@@ -146,19 +147,26 @@ impl<'a> format::Formatter<'a> for PlainRSTFormatter {
             dom::Part::OptionValue { value } => {
                 self.append_tag(appender, "\\ :literal:`", value, "`\\ ")
             }
-            dom::Part::EnvVariable { name } => {
-                self.append_tag(appender, "\\ :envvar:`", name, "`\\ ")
-            }
+            dom::Part::EnvVariable { name } => match url {
+                Some(u) => {
+                    appender.push_str("\\ `");
+                    appender.push_cow_str(self.rst_escaper.escape_link_text(name));
+                    appender.push_str(" <");
+                    appender.push_owned_string(self.url_escaper.escape(&u).into_owned());
+                    appender.push_str(">`__\\ ");
+                }
+                None => self.append_tag(appender, "\\ :envvar:`", name, "`\\ "),
+            },
             dom::Part::Error { message } => {
                 appender.push_str("\\ :strong:`ERROR while parsing`\\ : ");
-                appender.push_cow_str(self.rst_escaper.escape(message, true, true));
+                appender.push_cow_str(self.rst_escaper.escape_inline_literal(message));
                 appender.push_str("\\ ");
             }
             dom::Part::RSTRef { text, r#ref } => {
                 appender.push_str("\\ :ref:`");
-                appender.push_cow_str(self.rst_escaper.escape(text, true, true));
+                appender.push_cow_str(self.rst_escaper.escape_inline_literal(text));
                 appender.push_str(" <");
-                appender.push_str(r#ref);
+                appender.push_cow_str(self.rst_escaper.escape_target(r#ref));
                 appender.push_str(">`\\ ");
             }
             dom::Part::Link { text, url } => self.append_link(appender, text, url),
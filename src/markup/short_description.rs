@@ -0,0 +1,412 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::{Part, PartWithSource};
+use crate::markup::lint::{LintFinding, LintParagraph, LintPass};
+use crate::markup::usage::command_of;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+/// The default maximal length of a short description, in characters.
+pub const SHORT_DESCRIPTION_MAX_LENGTH: usize = 160;
+
+/// Whether a short description ends with a period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingPeriod {
+    /// The short description must not end with a period. This is the convention of the Ansible
+    /// documentation.
+    #[default]
+    Forbid,
+
+    /// The short description must end with a period.
+    Require,
+
+    /// Both are fine.
+    Ignore,
+}
+
+/// The constraints of `short_description`: a single paragraph, a policy for the trailing period,
+/// only a few markup commands, and a maximal length.
+///
+/// Used as a lint pass, and to normalize short descriptions for index pages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortDescriptionRule {
+    trailing_period: TrailingPeriod,
+    allowed_commands: BTreeSet<String>,
+    max_length: Option<usize>,
+}
+
+/// The number of characters a part shows.
+fn display_length(part: &Part<'_>) -> usize {
+    let length = |text: &str| text.chars().count();
+    match part {
+        Part::Text { text } | Part::Italic { text } | Part::Bold { text } | Part::Code { text } => {
+            length(text)
+        }
+        Part::Module { fqcn } => length(fqcn),
+        Part::Plugin { plugin } => length(&plugin.fqcn),
+        Part::URL { url } => length(url),
+        Part::Link { text, .. } | Part::RSTRef { text, .. } => length(text),
+        Part::OptionName { name, value, .. } | Part::ReturnValue { name, value, .. } => {
+            length(name) + value.as_deref().map_or(0, |value| 1 + length(value))
+        }
+        Part::OptionValue { value } => length(value),
+        Part::EnvVariable { name } => length(name),
+        Part::HorizontalLine | Part::Error { .. } => 0,
+    }
+}
+
+/// Append the text shown by a part as plain text parts.
+fn push_plain<'a>(part: &PartWithSource<'a>, result: &mut Vec<Part<'a>>) {
+    let source = part.source;
+    let argument = source
+        .split_once('(')
+        .map(|(_, rest)| rest.strip_suffix(')').unwrap_or(rest))
+        .unwrap_or(source);
+    let text = |cow: &Cow<'a, str>| match cow {
+        Cow::Borrowed(text) => *text,
+        Cow::Owned(_) => argument,
+    };
+    match &part.part {
+        Part::Text { text }
+        | Part::Italic { text }
+        | Part::Bold { text }
+        | Part::Code { text }
+        | Part::Link { text, .. }
+        | Part::RSTRef { text, .. } => result.push(Part::Text { text }),
+        Part::Module { fqcn } => result.push(Part::Text { text: fqcn }),
+        Part::Plugin { .. } => result.push(Part::Text {
+            text: argument.split('#').next().unwrap_or(argument),
+        }),
+        Part::URL { url } => result.push(Part::Text { text: url }),
+        Part::OptionName { name, value, .. } | Part::ReturnValue { name, value, .. } => {
+            result.push(Part::Text { text: text(name) });
+            if let (Cow::Borrowed(_), Some(value)) = (name, value) {
+                result.push(Part::Text { text: "=" });
+                result.push(Part::Text { text: text(value) });
+            }
+        }
+        Part::OptionValue { value } => result.push(Part::Text { text: text(value) }),
+        Part::EnvVariable { name } => result.push(Part::Text { text: text(name) }),
+        Part::HorizontalLine | Part::Error { .. } => {}
+    }
+}
+
+impl ShortDescriptionRule {
+    /// No trailing period, only `B()`, `C()`, `I()`, and `M()`, and at most
+    /// [`SHORT_DESCRIPTION_MAX_LENGTH`] characters.
+    pub fn new() -> ShortDescriptionRule {
+        ShortDescriptionRule {
+            trailing_period: TrailingPeriod::Forbid,
+            allowed_commands: ["B", "C", "I", "M"]
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            max_length: Some(SHORT_DESCRIPTION_MAX_LENGTH),
+        }
+    }
+
+    /// Set the policy for the trailing period.
+    pub fn trailing_period(mut self, trailing_period: TrailingPeriod) -> Self {
+        self.trailing_period = trailing_period;
+        self
+    }
+
+    /// Allow the markup command `command`, like `O` or `HORIZONTALLINE`.
+    pub fn allow_command(mut self, command: &str) -> Self {
+        self.allowed_commands.insert(command.to_string());
+        self
+    }
+
+    /// Forbid the markup command `command`.
+    pub fn forbid_command(mut self, command: &str) -> Self {
+        self.allowed_commands.remove(command);
+        self
+    }
+
+    /// Set the maximal length in characters, or `None` for no limit.
+    pub fn max_length(mut self, max_length: Option<usize>) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    fn is_allowed(&self, part: &Part<'_>) -> bool {
+        match command_of(part) {
+            Some((command, _)) => self.allowed_commands.contains(command),
+            None => true,
+        }
+    }
+
+    /// Normalize a parsed short description for index pages.
+    ///
+    /// Paragraphs are joined with spaces, commands that are not allowed are replaced by the
+    /// text they show, and whitespace at the start and end is removed. A description that is
+    /// too long is cut at a word boundary and ends with `…`; otherwise a trailing period is
+    /// added or removed according to the policy.
+    pub fn normalize<'a>(&self, paragraphs: &[Vec<PartWithSource<'a>>]) -> Vec<Part<'a>> {
+        let mut parts = Vec::new();
+        for paragraph in paragraphs.iter().filter(|paragraph| !paragraph.is_empty()) {
+            if !parts.is_empty() {
+                parts.push(Part::Text { text: " " });
+            }
+            for part in paragraph {
+                if self.is_allowed(&part.part) {
+                    parts.push(part.part.clone());
+                } else {
+                    push_plain(part, &mut parts);
+                }
+            }
+        }
+        if let Some(Part::Text { text }) = parts.first_mut() {
+            *text = text.trim_start();
+        }
+        if let Some(Part::Text { text }) = parts.last_mut() {
+            *text = text.trim_end();
+        }
+        parts.retain(|part| !matches!(part, Part::Text { text } if text.is_empty()));
+
+        if let Some(max_length) = self.max_length {
+            let mut length = 0;
+            for index in 0..parts.len() {
+                let part_length = display_length(&parts[index]);
+                if length + part_length <= max_length {
+                    length += part_length;
+                    continue;
+                }
+                parts.truncate(index + 1);
+                match parts.pop() {
+                    Some(Part::Text { text }) => {
+                        // Leave room for the ellipsis.
+                        let budget = max_length.saturating_sub(length + 1);
+                        let end = text
+                            .char_indices()
+                            .nth(budget)
+                            .map_or(text.len(), |(end, _)| end);
+                        let prefix = &text[..end];
+                        let cut = if text[end..].starts_with(char::is_whitespace) {
+                            prefix
+                        } else {
+                            prefix
+                                .rfind(char::is_whitespace)
+                                .map_or("", |space| &prefix[..space])
+                        };
+                        let cut = cut.trim_end();
+                        if !cut.is_empty() {
+                            parts.push(Part::Text { text: cut });
+                        }
+                    }
+                    _ => {
+                        while let Some(Part::Text { text }) = parts.last_mut() {
+                            *text = text.trim_end();
+                            if !text.is_empty() {
+                                break;
+                            }
+                            parts.pop();
+                        }
+                    }
+                }
+                parts.push(Part::Text { text: "…" });
+                return parts;
+            }
+        }
+
+        match self.trailing_period {
+            TrailingPeriod::Forbid => {
+                if let Some(Part::Text { text }) = parts.last_mut() {
+                    if !text.ends_with("..") {
+                        *text = text.strip_suffix('.').unwrap_or(text).trim_end();
+                    }
+                }
+                parts.retain(|part| !matches!(part, Part::Text { text } if text.is_empty()));
+            }
+            TrailingPeriod::Require => {
+                if !matches!(parts.last(), Some(Part::Text { text }) if text.ends_with('.')) {
+                    parts.push(Part::Text { text: "." });
+                }
+            }
+            TrailingPeriod::Ignore => {}
+        }
+        parts
+    }
+
+    fn check_period(&self, paragraph: &LintParagraph<'_, '_>, findings: &mut Vec<LintFinding>) {
+        let Some(part) = paragraph.parts.len().checked_sub(1) else {
+            return;
+        };
+        let text = match &paragraph.parts[part] {
+            Part::Text { text } => Some(text.trim_end()),
+            _ => None,
+        };
+        let has_period = text.is_some_and(|text| text.ends_with('.') && !text.ends_with(".."));
+        let end = text.map_or(0, str::len);
+        match self.trailing_period {
+            TrailingPeriod::Forbid if has_period => findings.push(
+                paragraph
+                    .finding(
+                        "short-description-period",
+                        "Short description should not end with a period".to_string(),
+                        part,
+                        end - 1,
+                        end,
+                    )
+                    .with_fix(""),
+            ),
+            TrailingPeriod::Require if !has_period => findings.push(
+                paragraph
+                    .finding(
+                        "short-description-period",
+                        "Short description should end with a period".to_string(),
+                        part,
+                        end,
+                        end,
+                    )
+                    .with_fix("."),
+            ),
+            _ => {}
+        }
+    }
+}
+
+impl Default for ShortDescriptionRule {
+    fn default() -> Self {
+        ShortDescriptionRule::new()
+    }
+}
+
+impl LintPass for ShortDescriptionRule {
+    fn check_paragraph(&self, paragraph: &LintParagraph<'_, '_>, findings: &mut Vec<LintFinding>) {
+        if paragraph.index == 1 {
+            findings.push(paragraph.finding(
+                "short-description-paragraphs",
+                "Short description should be a single paragraph".to_string(),
+                0,
+                0,
+                0,
+            ));
+        }
+        for (index, part) in paragraph.parts.iter().enumerate() {
+            if let Some((command, _)) = command_of(part) {
+                if !self.allowed_commands.contains(command) {
+                    findings.push(paragraph.finding(
+                        "short-description-markup",
+                        format!("{}() should not be used in a short description", command),
+                        index,
+                        0,
+                        0,
+                    ));
+                }
+            }
+        }
+        if paragraph.index + 1 == paragraph.count {
+            self.check_period(paragraph, findings);
+        }
+        if let Some(max_length) = self.max_length {
+            let length: usize = paragraph.parts.iter().map(display_length).sum();
+            if length > max_length {
+                findings.push(paragraph.finding(
+                    "short-description-length",
+                    format!(
+                        "Short description has {} characters, more than {}",
+                        length, max_length
+                    ),
+                    0,
+                    0,
+                    0,
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::lint::Linter;
+    use crate::markup::{parse_paragraphs, parse_paragraphs_without_sources};
+    use crate::markup::{Context, ParseOptions};
+
+    #[test]
+    fn test_lint() {
+        let context = Context::new();
+        let paragraphs = parse_paragraphs_without_sources(
+            ["Manage O(state) of C(foo).", "More."].into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let lint = |rule: ShortDescriptionRule| -> Vec<String> {
+            Linter::new()
+                .add_pass(rule)
+                .lint(&paragraphs)
+                .iter()
+                .map(|finding| finding.to_string())
+                .collect()
+        };
+        assert_eq!(
+            lint(ShortDescriptionRule::new().max_length(Some(10))),
+            vec![
+                "Paragraph 1, part 2, 0..0: O() should not be used in a short description [short-description-markup]",
+                "Paragraph 1, part 1, 0..0: Short description has 20 characters, more than 10 [short-description-length]",
+                "Paragraph 2, part 1, 0..0: Short description should be a single paragraph [short-description-paragraphs]",
+                "Paragraph 2, part 1, 4..5: Short description should not end with a period [short-description-period]",
+            ]
+        );
+        assert_eq!(
+            lint(
+                ShortDescriptionRule::new()
+                    .allow_command("O")
+                    .trailing_period(TrailingPeriod::Require)
+            ),
+            vec![
+                "Paragraph 2, part 1, 0..0: Short description should be a single paragraph [short-description-paragraphs]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        let context = Context::new();
+        let normalize = |rule: &ShortDescriptionRule, paragraphs: &[&'static str]| -> String {
+            let paragraphs = parse_paragraphs(
+                paragraphs.iter().copied(),
+                &context,
+                &ParseOptions::default(),
+            );
+            rule.normalize(&paragraphs)
+                .iter()
+                .map(|part| part.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let rule = ShortDescriptionRule::new();
+        assert_eq!(
+            normalize(&rule, &["  Set O(a=b) with C(x).  ", "Really."]),
+            r#"text="Set " text="a" text="=" text="b" text=" with " code="x" text=".  " text=" " text="Really""#
+        );
+        assert_eq!(
+            normalize(&rule, &["Use P(foo.bar.baz#lookup)..."]),
+            r#"text="Use " text="foo.bar.baz" text="...""#
+        );
+        assert_eq!(
+            normalize(
+                &rule.clone().trailing_period(TrailingPeriod::Require),
+                &["Manage C(foo)"]
+            ),
+            r#"text="Manage " code="foo" text=".""#
+        );
+        assert_eq!(
+            normalize(
+                &rule.clone().max_length(Some(14)),
+                &["Manage the foo and the bar."]
+            ),
+            r#"text="Manage the" text="…""#
+        );
+        assert_eq!(
+            normalize(&rule.clone().max_length(Some(8)), &["Manage C(foo)."]),
+            r#"text="Manage" text="…""#
+        );
+        assert_eq!(normalize(&rule, &[]), "");
+    }
+}
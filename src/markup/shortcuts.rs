@@ -0,0 +1,176 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::{Part, PartWithSource};
+use crate::markup::format::NoLinkProvider;
+use crate::markup::parse::{parse_without_sources, Context, ParseOptions};
+use crate::markup::renderer::Renderer;
+
+/// A paragraph that can be converted by [`to_html`] and its siblings: markup that is parsed
+/// first, or an already parsed paragraph.
+pub trait ParagraphSource {
+    /// Return the parts of the paragraph, parsing it with `context` and `opts` if necessary.
+    fn parts<'a>(&'a self, context: &'a Context, opts: &ParseOptions) -> Vec<Part<'a>>;
+}
+
+impl ParagraphSource for str {
+    fn parts<'a>(&'a self, context: &'a Context, opts: &ParseOptions) -> Vec<Part<'a>> {
+        parse_without_sources(self, context, opts)
+    }
+}
+
+impl ParagraphSource for String {
+    fn parts<'a>(&'a self, context: &'a Context, opts: &ParseOptions) -> Vec<Part<'a>> {
+        self.as_str().parts(context, opts)
+    }
+}
+
+impl ParagraphSource for [Part<'_>] {
+    fn parts<'a>(&'a self, _context: &'a Context, _opts: &ParseOptions) -> Vec<Part<'a>> {
+        self.to_vec()
+    }
+}
+
+impl ParagraphSource for Vec<Part<'_>> {
+    fn parts<'a>(&'a self, context: &'a Context, opts: &ParseOptions) -> Vec<Part<'a>> {
+        self.as_slice().parts(context, opts)
+    }
+}
+
+impl ParagraphSource for [PartWithSource<'_>] {
+    fn parts<'a>(&'a self, _context: &'a Context, _opts: &ParseOptions) -> Vec<Part<'a>> {
+        self.iter().map(|ps| ps.part.clone()).collect()
+    }
+}
+
+impl ParagraphSource for Vec<PartWithSource<'_>> {
+    fn parts<'a>(&'a self, context: &'a Context, opts: &ParseOptions) -> Vec<Part<'a>> {
+        self.as_slice().parts(context, opts)
+    }
+}
+
+impl<T: ParagraphSource + ?Sized> ParagraphSource for &T {
+    fn parts<'a>(&'a self, context: &'a Context, opts: &ParseOptions) -> Vec<Part<'a>> {
+        (**self).parts(context, opts)
+    }
+}
+
+fn empty_context() -> Context {
+    Context::new()
+}
+
+fn render_paragraph<P: ParagraphSource + ?Sized>(renderer: Renderer<'_>, paragraph: &P) -> String {
+    let context = empty_context();
+    let parts = paragraph.parts(&context, &ParseOptions::default());
+    let mut result = String::new();
+    renderer.render_paragraph(&mut result, parts.iter());
+    result
+}
+
+fn render_paragraphs<P: ParagraphSource>(renderer: Renderer<'_>, paragraphs: &[P]) -> String {
+    let context = empty_context();
+    let opts = ParseOptions::default();
+    let paragraphs: Vec<Vec<Part<'_>>> = paragraphs
+        .iter()
+        .enumerate()
+        .map(|(index, paragraph)| {
+            paragraph.parts(&context, &opts.add_paragraph_to_where(index + 1))
+        })
+        .collect();
+    let mut result = String::new();
+    renderer.render_paragraphs(&mut result, paragraphs.iter().map(|parts| parts.iter()));
+    result
+}
+
+/// Convert a paragraph to plain HTML, without links to plugins and options.
+///
+/// Use [`Renderer`] for the antsibull-docs HTML flavor, links, or the current plugin.
+#[cfg(feature = "html")]
+pub fn to_html<P: ParagraphSource + ?Sized>(paragraph: &P) -> String {
+    render_paragraph(Renderer::plain_html(&NoLinkProvider::new()), paragraph)
+}
+
+/// Convert paragraphs to plain HTML, like [`to_html`].
+#[cfg(feature = "html")]
+pub fn to_html_paragraphs<P: ParagraphSource>(paragraphs: &[P]) -> String {
+    render_paragraphs(Renderer::plain_html(&NoLinkProvider::new()), paragraphs)
+}
+
+/// Convert a paragraph to MarkDown, without links to plugins and options.
+#[cfg(feature = "md")]
+pub fn to_md<P: ParagraphSource + ?Sized>(paragraph: &P) -> String {
+    render_paragraph(Renderer::md(&NoLinkProvider::new()), paragraph)
+}
+
+/// Convert paragraphs to MarkDown, like [`to_md`].
+#[cfg(feature = "md")]
+pub fn to_md_paragraphs<P: ParagraphSource>(paragraphs: &[P]) -> String {
+    render_paragraphs(Renderer::md(&NoLinkProvider::new()), paragraphs)
+}
+
+/// Convert a paragraph to plain RST, which does not need the antsibull-docs Sphinx extension.
+///
+/// Use [`Renderer`] for the antsibull-docs RST flavor.
+#[cfg(feature = "rst")]
+pub fn to_rst<P: ParagraphSource + ?Sized>(paragraph: &P) -> String {
+    render_paragraph(Renderer::plain_rst(&NoLinkProvider::new()), paragraph)
+}
+
+/// Convert paragraphs to plain RST, like [`to_rst`].
+#[cfg(feature = "rst")]
+pub fn to_rst_paragraphs<P: ParagraphSource>(paragraphs: &[P]) -> String {
+    render_paragraphs(Renderer::plain_rst(&NoLinkProvider::new()), paragraphs)
+}
+
+/// Convert a paragraph to the text format of ansible-doc.
+#[cfg(feature = "ansible-doc")]
+pub fn to_ansible_doc_text<P: ParagraphSource + ?Sized>(paragraph: &P) -> String {
+    render_paragraph(
+        Renderer::ansible_doc_text(&NoLinkProvider::new()),
+        paragraph,
+    )
+}
+
+/// Convert paragraphs to the text format of ansible-doc, like [`to_ansible_doc_text`].
+#[cfg(feature = "ansible-doc")]
+pub fn to_ansible_doc_text_paragraphs<P: ParagraphSource>(paragraphs: &[P]) -> String {
+    render_paragraphs(
+        Renderer::ansible_doc_text(&NoLinkProvider::new()),
+        paragraphs,
+    )
+}
+
+#[cfg(all(test, feature = "full"))]
+mod tests {
+    use super::*;
+    use crate::markup::parse;
+
+    #[test]
+    fn test_shortcuts() {
+        assert_eq!(
+            to_html("B(x) and C(y)"),
+            "<p><b>x</b> and <code>y</code></p>"
+        );
+        assert_eq!(
+            to_html_paragraphs(&["I(a)", "V(b)"]),
+            "<p><em>a</em></p><p><code>b</code></p>"
+        );
+        assert_eq!(to_md("B(x)"), "<b>x</b>");
+        assert_eq!(
+            to_rst_paragraphs(&["I(a)".to_string()]),
+            "\\ :emphasis:`a`\\ "
+        );
+        assert_eq!(to_ansible_doc_text("M(foo.bar.baz)"), "[foo.bar.baz]");
+
+        let context = empty_context();
+        let parsed = parse("E(FOO)", &context, &ParseOptions::default());
+        assert_eq!(to_md(&parsed), to_md("E(FOO)"));
+        assert_eq!(
+            to_html_paragraphs(&[parsed.clone(), parsed]),
+            "<p><code>FOO</code></p><p><code>FOO</code></p>"
+        );
+    }
+}
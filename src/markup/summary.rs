@@ -0,0 +1,197 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::{Part, PartWithSource};
+use crate::markup::renderer::Renderer;
+
+const DEFAULT_ABBREVIATIONS: &[&str] = &[
+    "approx", "ca", "cf", "e.g", "i.e", "incl", "no", "resp", "vs",
+];
+
+/// Extracts the first sentence of paragraphs, for example for index pages.
+///
+/// Only plain text can end a sentence, so periods in code, option names, values, and URLs are
+/// ignored. A sentence ends with `.`, `!`, or `?`, optionally followed by closing quotes or
+/// parentheses, and then whitespace. Periods after abbreviations like `e.g.` do not end a
+/// sentence, and neither do periods followed by a lower-case letter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summarizer {
+    abbreviations: Vec<String>,
+}
+
+impl Summarizer {
+    /// Create a summarizer that knows common English abbreviations.
+    pub fn new() -> Summarizer {
+        Summarizer {
+            abbreviations: DEFAULT_ABBREVIATIONS
+                .iter()
+                .map(|abbreviation| abbreviation.to_string())
+                .collect(),
+        }
+    }
+
+    /// Add an abbreviation whose trailing period does not end a sentence, like `Fig.`.
+    ///
+    /// Abbreviations are compared case-insensitively.
+    pub fn abbreviation(mut self, abbreviation: &str) -> Self {
+        self.abbreviations
+            .push(abbreviation.trim_end_matches('.').to_lowercase());
+        self
+    }
+
+    fn is_abbreviation(&self, before: &str) -> bool {
+        let word = before
+            .rsplit(char::is_whitespace)
+            .next()
+            .unwrap_or("")
+            .trim_start_matches(['(', '"', '\'']);
+        let word = word.to_lowercase();
+        self.abbreviations.contains(&word)
+    }
+
+    /// The byte offset after the end of the first sentence in `text`, if it ends there.
+    ///
+    /// `at_end` tells whether `text` is the last part of the paragraph.
+    fn sentence_end(&self, text: &str, at_end: bool) -> Option<usize> {
+        for (index, c) in text.char_indices() {
+            if !matches!(c, '.' | '!' | '?') {
+                continue;
+            }
+            let rest = &text[index + 1..];
+            let closing = rest.len() - rest.trim_start_matches([')', '"', '\'']).len();
+            let end = index + 1 + closing;
+            let after = &text[end..];
+            if after.is_empty() {
+                if at_end {
+                    return Some(end);
+                }
+                continue;
+            }
+            let following = after.trim_start();
+            if following.len() == after.len() {
+                continue;
+            }
+            if following.starts_with(char::is_lowercase) {
+                continue;
+            }
+            if c == '.' && self.is_abbreviation(&text[..index]) {
+                continue;
+            }
+            return Some(end);
+        }
+        None
+    }
+
+    /// Extract the first sentence of a paragraph.
+    ///
+    /// If no end of a sentence is found, the whole paragraph is returned. Whitespace at the
+    /// start of the paragraph is removed.
+    pub fn first_sentence<'p, 'a: 'p, I>(&self, paragraph: I) -> Vec<Part<'a>>
+    where
+        I: IntoIterator<Item = &'p Part<'a>>,
+    {
+        let parts: Vec<&Part<'a>> = paragraph.into_iter().collect();
+        let mut result = Vec::new();
+        for (index, part) in parts.iter().enumerate() {
+            let Part::Text { text } = part else {
+                result.push((*part).clone());
+                continue;
+            };
+            let text = if result.is_empty() {
+                text.trim_start()
+            } else {
+                text
+            };
+            match self.sentence_end(text, index + 1 == parts.len()) {
+                Some(end) => {
+                    result.push(Part::Text { text: &text[..end] });
+                    return result;
+                }
+                None if text.is_empty() => {}
+                None => result.push(Part::Text { text }),
+            }
+        }
+        result
+    }
+
+    /// Extract the first sentence of the first non-empty parsed paragraph.
+    pub fn summarize<'a>(&self, paragraphs: &[Vec<PartWithSource<'a>>]) -> Vec<Part<'a>> {
+        paragraphs
+            .iter()
+            .find(|paragraph| !paragraph.is_empty())
+            .map(|paragraph| self.first_sentence(paragraph.iter().map(|ps| &ps.part)))
+            .unwrap_or_default()
+    }
+}
+
+impl Default for Summarizer {
+    fn default() -> Self {
+        Summarizer::new()
+    }
+}
+
+impl Renderer<'_> {
+    /// Render the first sentence of the first non-empty parsed paragraph as one paragraph.
+    ///
+    /// See [`Summarizer`] for how the end of the sentence is found.
+    pub fn render_summary(
+        &self,
+        summarizer: &Summarizer,
+        paragraphs: &[Vec<PartWithSource<'_>>],
+    ) -> String {
+        let summary = summarizer.summarize(paragraphs);
+        let mut result = String::new();
+        self.render_paragraph(&mut result, summary.iter());
+        result
+    }
+}
+
+#[cfg(all(test, feature = "md"))]
+mod tests {
+    use super::*;
+    use crate::markup::{parse_paragraphs, Context, NoLinkProvider, ParseOptions};
+
+    #[test]
+    fn test_summarizer() {
+        let context = Context::new();
+        let summarizer = Summarizer::new().abbreviation("Fig.");
+        let summary = |paragraphs: &[&'static str]| {
+            let paragraphs = parse_paragraphs(
+                paragraphs.iter().copied(),
+                &context,
+                &ParseOptions::default(),
+            );
+            let link_provider = NoLinkProvider::new();
+            Renderer::md(&link_provider).render_summary(&summarizer, &paragraphs)
+        };
+        assert_eq!(
+            summary(&["Manage hosts. Second sentence."]),
+            "Manage hosts\\."
+        );
+        assert_eq!(
+            summary(&["Set C(a. B) or O(foo.bar=1. X), e.g. V(x). Then go."]),
+            "Set <code>a\\. B</code> or <code>foo\\.bar\\=1\\. X</code>\\, e\\.g\\. <code>x</code>\\."
+        );
+        assert_eq!(
+            summary(&["Version 1.2 is used (see docs.) By default, no."]),
+            "Version 1\\.2 is used \\(see docs\\.\\)"
+        );
+        assert_eq!(
+            summary(&["Does this work? yes. No!"]),
+            "Does this work\\? yes\\."
+        );
+        assert_eq!(
+            summary(&["See Fig. 3 for details. Done."]),
+            "See Fig\\. 3 for details\\."
+        );
+        assert_eq!(summary(&["", "  Only sentence"]), "Only sentence");
+        assert_eq!(
+            summary(&["See U(https://a.b/c.)"]),
+            "See [https\\://a\\.b/c\\.](https\\://a\\.b/c\\.)"
+        );
+        assert_eq!(summary(&[]), " ");
+    }
+}
@@ -0,0 +1,431 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::PluginIdentifier;
+use crate::markup::format::{LinkProvider, OptionLike};
+use crate::markup::parse::{Context, ParseConfig, ParseOptions};
+use crate::markup::renderer::Renderer;
+use crate::markup::CompactString;
+use crate::util::yaml::{as_hash, get, get_bool, get_string, get_string_list};
+use saphyr::{Hash, Yaml};
+use std::fmt;
+use std::rc::Rc;
+
+/// The output formats of test vectors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestVectorFormat {
+    AntsibullHTML,
+    PlainHTML,
+    MD,
+    AntsibullRST,
+    PlainRST,
+    AnsibleDocText,
+}
+
+impl TestVectorFormat {
+    pub const ALL: [TestVectorFormat; 6] = [
+        TestVectorFormat::AntsibullHTML,
+        TestVectorFormat::PlainHTML,
+        TestVectorFormat::MD,
+        TestVectorFormat::AntsibullRST,
+        TestVectorFormat::PlainRST,
+        TestVectorFormat::AnsibleDocText,
+    ];
+
+    /// The key of the expected output in a test vector.
+    pub fn key(&self) -> &'static str {
+        match self {
+            TestVectorFormat::AntsibullHTML => "html",
+            TestVectorFormat::PlainHTML => "html_plain",
+            TestVectorFormat::MD => "md",
+            TestVectorFormat::AntsibullRST => "rst",
+            TestVectorFormat::PlainRST => "rst_plain",
+            TestVectorFormat::AnsibleDocText => "ansible_doc_text",
+        }
+    }
+
+    /// The key of the render options in a test vector. Formats of the same kind share them.
+    pub fn options_key(&self) -> &'static str {
+        match self {
+            TestVectorFormat::AntsibullHTML | TestVectorFormat::PlainHTML => "html_opts",
+            TestVectorFormat::MD => "md_opts",
+            TestVectorFormat::AntsibullRST | TestVectorFormat::PlainRST => "rst_opts",
+            TestVectorFormat::AnsibleDocText => "ansible_doc_text_opts",
+        }
+    }
+
+    fn renderer<'r>(&self, link_provider: &'r dyn LinkProvider) -> Renderer<'r> {
+        match self {
+            TestVectorFormat::AntsibullHTML => Renderer::antsibull_html(link_provider),
+            TestVectorFormat::PlainHTML => Renderer::plain_html(link_provider),
+            TestVectorFormat::MD => Renderer::md(link_provider),
+            TestVectorFormat::AntsibullRST => Renderer::antsibull_rst(link_provider),
+            TestVectorFormat::PlainRST => Renderer::plain_rst(link_provider),
+            TestVectorFormat::AnsibleDocText => Renderer::ansible_doc_text(link_provider),
+        }
+    }
+}
+
+/// A link provider that fills in URL templates, like `https://docs/{plugin_fqcn_slashes}`.
+///
+/// Plugin templates can use `{plugin_fqcn}`, `{plugin_fqcn_slashes}`, and `{plugin_type}`.
+/// Option and return value templates can additionally use `{what}` (`option` or `retval`),
+/// `{entrypoint}`, `{entrypoint_with_leading_dash}`, `{name_dots}`, and `{name_slashes}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TemplatedLinkProvider {
+    pub plugin_link: Option<String>,
+    pub plugin_option_like_link: Option<String>,
+}
+
+impl TemplatedLinkProvider {
+    pub fn new(
+        plugin_link: Option<String>,
+        plugin_option_like_link: Option<String>,
+    ) -> TemplatedLinkProvider {
+        TemplatedLinkProvider {
+            plugin_link,
+            plugin_option_like_link,
+        }
+    }
+}
+
+fn fill_plugin_template(template: &str, plugin: &PluginIdentifier) -> String {
+    template
+        .replace("{plugin_fqcn}", &plugin.fqcn)
+        .replace("{plugin_fqcn_slashes}", &plugin.fqcn.replace('.', "/"))
+        .replace("{plugin_type}", &plugin.r#type)
+}
+
+impl LinkProvider for TemplatedLinkProvider {
+    fn plugin_link(&self, plugin: &PluginIdentifier) -> Option<String> {
+        let template = self.plugin_link.as_ref()?;
+        Some(fill_plugin_template(template, plugin))
+    }
+
+    fn plugin_option_like_link(
+        &self,
+        plugin: &PluginIdentifier,
+        entrypoint: Option<&String>,
+        what: OptionLike,
+        name: &[CompactString],
+        _current_plugin: bool,
+    ) -> Option<String> {
+        let template = self.plugin_option_like_link.as_ref()?;
+        Some(
+            fill_plugin_template(template, plugin)
+                .replace(
+                    "{what}",
+                    match what {
+                        OptionLike::Option => "option",
+                        OptionLike::RetVal => "retval",
+                    },
+                )
+                .replace("{entrypoint}", entrypoint.map_or("", |ep| ep.as_str()))
+                .replace(
+                    "{entrypoint_with_leading_dash}",
+                    &entrypoint.map_or_else(String::new, |ep| format!("-{}", ep)),
+                )
+                .replace("{name_dots}", &name.join("."))
+                .replace("{name_slashes}", &name.join("/")),
+        )
+    }
+}
+
+/// The expected rendering of a test vector in one format.
+#[derive(Debug, Clone)]
+pub struct ExpectedOutput {
+    pub format: TestVectorFormat,
+    pub output: String,
+    pub link_provider: TemplatedLinkProvider,
+    pub current_plugin: Option<Rc<PluginIdentifier>>,
+}
+
+/// A test vector: markup, how to parse it, and its expected renderings.
+#[derive(Debug, Clone)]
+pub struct TestVector {
+    pub name: String,
+
+    /// The paragraphs of the markup.
+    pub source: Vec<String>,
+
+    /// The parsing context and options, from `parse_opts`.
+    pub config: ParseConfig,
+
+    /// The expected renderings, in the order of [`TestVectorFormat::ALL`].
+    pub outputs: Vec<ExpectedOutput>,
+}
+
+/// A rendering of a test vector that differs from the expected output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestVectorFailure {
+    pub vector: String,
+    pub format: TestVectorFormat,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl TestVectorFailure {
+    /// The index of the first character where the actual and the expected output differ.
+    pub fn first_difference(&self) -> usize {
+        self.expected
+            .chars()
+            .zip(self.actual.chars())
+            .take_while(|(expected, actual)| expected == actual)
+            .count()
+    }
+}
+
+/// The characters of `text` around the character with index `index`, quoted and escaped.
+fn excerpt(text: &str, index: usize) -> String {
+    const BEFORE: usize = 20;
+    const AFTER: usize = 40;
+    let start = index.saturating_sub(BEFORE);
+    let length = text.chars().count();
+    let end = (index + AFTER).min(length);
+    let part: String = text.chars().skip(start).take(end - start).collect();
+    format!(
+        "{}{:?}{}",
+        if start > 0 { "..." } else { "" },
+        part,
+        if end < length { "..." } else { "" }
+    )
+}
+
+impl fmt::Display for TestVectorFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let index = self.first_difference();
+        writeln!(
+            f,
+            "{} ({}): output differs at character {} (expected {} characters, got {})",
+            self.vector,
+            self.format.key(),
+            index,
+            self.expected.chars().count(),
+            self.actual.chars().count()
+        )?;
+        writeln!(f, "  expected: {}", excerpt(&self.expected, index))?;
+        writeln!(f, "  actual:   {}", excerpt(&self.actual, index))
+    }
+}
+
+impl TestVector {
+    /// Parse the source, render it in all formats, and return the renderings that differ from
+    /// the expected output.
+    pub fn run(&self) -> Vec<TestVectorFailure> {
+        let paragraphs = self
+            .config
+            .parse_paragraphs(self.source.iter().map(|paragraph| paragraph.as_str()));
+        let mut failures = Vec::new();
+        for expected in &self.outputs {
+            let actual = expected
+                .format
+                .renderer(&expected.link_provider)
+                .with_current_plugin(expected.current_plugin.clone())
+                .render(&paragraphs);
+            if actual != expected.output {
+                failures.push(TestVectorFailure {
+                    vector: self.name.clone(),
+                    format: expected.format,
+                    expected: expected.output.clone(),
+                    actual,
+                });
+            }
+        }
+        failures
+    }
+}
+
+fn check_keys(hash: &Hash, keys: &[&str], path: &str) -> Result<(), String> {
+    for key in hash.keys() {
+        match key.as_str() {
+            Some(key) if keys.contains(&key) => {}
+            Some(key) => return Err(format!("{}: unknown key {:?}", path, key)),
+            None => return Err(format!("{}: keys must be strings", path)),
+        }
+    }
+    Ok(())
+}
+
+fn get_required_string(hash: &Hash, key: &str, path: &str) -> Result<String, String> {
+    get_string(hash, key, path)?.ok_or_else(|| format!("{}: missing key {:?}", path, key))
+}
+
+fn parse_current_plugin(hash: &Hash, path: &str) -> Result<Option<Rc<PluginIdentifier>>, String> {
+    let Some(value) = get(hash, "currentPlugin") else {
+        return Ok(None);
+    };
+    let path = format!("{}.currentPlugin", path);
+    let plugin = as_hash(value, &path)?;
+    check_keys(plugin, &["fqcn", "type"], &path)?;
+    Ok(Some(Rc::new(PluginIdentifier {
+        fqcn: get_required_string(plugin, "fqcn", &path)?,
+        r#type: get_required_string(plugin, "type", &path)?,
+    })))
+}
+
+fn parse_config(vector: &Hash, path: &str) -> Result<ParseConfig, String> {
+    let mut config = ParseConfig::new(Context::new(), ParseOptions::default());
+    let Some(value) = get(vector, "parse_opts") else {
+        return Ok(config);
+    };
+    let path = format!("{}.parse_opts", path);
+    let options = as_hash(value, &path)?;
+    check_keys(
+        options,
+        &[
+            "currentPlugin",
+            "roleEntrypoint",
+            "onlyClassicMarkup",
+            "helpfulErrors",
+        ],
+        &path,
+    )?;
+    config.context.current_plugin = parse_current_plugin(options, &path)?;
+    config.context.role_entrypoint = get_string(options, "roleEntrypoint", &path)?.map(Rc::new);
+    if let Some(only_classic_markup) = get_bool(options, "onlyClassicMarkup", &path)? {
+        config.options.set_only_classic_markup(only_classic_markup);
+    }
+    if let Some(helpful_errors) = get_bool(options, "helpfulErrors", &path)? {
+        config.options.set_helpful_errors(helpful_errors);
+    }
+    Ok(config)
+}
+
+fn parse_output(
+    vector: &Hash,
+    format: TestVectorFormat,
+    path: &str,
+) -> Result<Option<ExpectedOutput>, String> {
+    let Some(output) = get_string(vector, format.key(), path)? else {
+        return Ok(None);
+    };
+    let mut expected = ExpectedOutput {
+        format,
+        output,
+        link_provider: TemplatedLinkProvider::default(),
+        current_plugin: None,
+    };
+    if let Some(value) = get(vector, format.options_key()) {
+        let path = format!("{}.{}", path, format.options_key());
+        let options = as_hash(value, &path)?;
+        check_keys(
+            options,
+            &[
+                "pluginLinkTemplate",
+                "pluginOptionLikeLinkTemplate",
+                "currentPlugin",
+            ],
+            &path,
+        )?;
+        expected.link_provider = TemplatedLinkProvider::new(
+            get_string(options, "pluginLinkTemplate", &path)?,
+            get_string(options, "pluginOptionLikeLinkTemplate", &path)?,
+        );
+        expected.current_plugin = parse_current_plugin(options, &path)?;
+    }
+    Ok(Some(expected))
+}
+
+fn parse_test_vector(name: &str, vector: &Yaml) -> Result<TestVector, String> {
+    let path = format!("test_vectors.{}", name);
+    let vector = as_hash(vector, &path)?;
+    let mut keys = vec!["source", "parse_opts"];
+    for format in TestVectorFormat::ALL {
+        keys.push(format.key());
+        keys.push(format.options_key());
+    }
+    check_keys(vector, &keys, &path)?;
+    if get(vector, "source").is_none() {
+        return Err(format!("{}: missing key \"source\"", path));
+    }
+    let mut outputs = Vec::new();
+    for format in TestVectorFormat::ALL {
+        outputs.extend(parse_output(vector, format, &path)?);
+    }
+    Ok(TestVector {
+        name: name.to_string(),
+        source: get_string_list(vector, "source", &path)?,
+        config: parse_config(vector, &path)?,
+        outputs,
+    })
+}
+
+/// Load test vectors in the format of `test-vectors.yaml`: a mapping with a `test_vectors`
+/// mapping that maps the names of the test vectors to their source, options, and expected
+/// renderings.
+///
+/// Errors name the test vector and the offending key, like
+/// `test_vectors.simple.parse_opts.currentPlugin: missing key "fqcn"`.
+pub fn load_test_vectors(content: &str) -> Result<Vec<TestVector>, String> {
+    let documents = Yaml::load_from_str(content).map_err(|error| error.to_string())?;
+    let root = match documents.first() {
+        Some(root) => as_hash(root, "document")?,
+        None => return Err("document: expected a mapping".to_string()),
+    };
+    let Some(vectors) = get(root, "test_vectors") else {
+        return Err("document: missing key \"test_vectors\"".to_string());
+    };
+    as_hash(vectors, "test_vectors")?
+        .iter()
+        .map(|(name, vector)| match name.as_str() {
+            Some(name) => parse_test_vector(name, vector),
+            None => Err("test_vectors: keys must be strings".to_string()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_test_vectors() {
+        let vectors = load_test_vectors(concat!(
+            "test_vectors:\n",
+            "  bold:\n",
+            "    source: Some text before B(foo) M(a.b.c)\n",
+            "    md: Some text before <b>foo</b> [a\\.b\\.c](https\\://x/a/b/c)\n",
+            "    md_opts:\n",
+            "      pluginLinkTemplate: https://x/{plugin_fqcn_slashes}\n",
+            "    ansible_doc_text: 'Some text before *foo* [a.b.d]'\n",
+        ))
+        .unwrap();
+        assert_eq!(vectors.len(), 1);
+        assert_eq!(vectors[0].source, vec!["Some text before B(foo) M(a.b.c)"]);
+        let failures = vectors[0].run();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(
+            failures[0].to_string(),
+            concat!(
+                "bold (ansible_doc_text): output differs at character 28 ",
+                "(expected 30 characters, got 30)\n",
+                "  expected: ...\"t before *foo* [a.b.d]\"\n",
+                "  actual:   ...\"t before *foo* [a.b.c]\"\n",
+            )
+        );
+
+        assert_eq!(
+            load_test_vectors(concat!(
+                "test_vectors:\n",
+                "  x:\n",
+                "    source: ''\n",
+                "    parse_opts:\n",
+                "      currentPlugin:\n",
+                "        type: module\n",
+            ))
+            .unwrap_err(),
+            "test_vectors.x.parse_opts.currentPlugin: missing key \"fqcn\""
+        );
+        assert_eq!(
+            load_test_vectors("test_vectors:\n  x:\n    html: ''\n").unwrap_err(),
+            "test_vectors.x: missing key \"source\""
+        );
+        assert_eq!(
+            load_test_vectors("test_vectors:\n  x:\n    source: ''\n    htm: ''\n").unwrap_err(),
+            "test_vectors.x: unknown key \"htm\""
+        );
+    }
+}
@@ -0,0 +1,325 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::{Part, PartWithSource, PluginIdentifier};
+use crate::markup::renderer::Renderer;
+use compact_str::CompactString;
+use std::borrow::Cow;
+use std::rc::Rc;
+
+/// Settings for rendering content from untrusted sources, like snippets submitted for a preview.
+///
+/// See [`Renderer::render_untrusted`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UntrustedProfile {
+    /// The maximal length of the output in bytes. `None` disables the limit.
+    pub max_output_length: Option<usize>,
+}
+
+impl UntrustedProfile {
+    /// Create a profile that limits the output to 64 KiB.
+    pub fn new() -> UntrustedProfile {
+        UntrustedProfile {
+            max_output_length: Some(65536),
+        }
+    }
+
+    /// Set the maximal length of the output in bytes.
+    pub fn max_output_length(mut self, max_output_length: Option<usize>) -> Self {
+        self.max_output_length = max_output_length;
+        self
+    }
+}
+
+impl Default for UntrustedProfile {
+    fn default() -> Self {
+        UntrustedProfile::new()
+    }
+}
+
+/// The result of [`Renderer::render_untrusted`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UntrustedRendering {
+    /// The rendered output.
+    pub output: String,
+
+    /// Whether parts were left out because the output would have been too long.
+    pub truncated: bool,
+}
+
+/// Control characters other than tabs and line breaks, and characters that change the direction
+/// of text, which can make text look different from what it is.
+fn is_unsafe_char(c: char) -> bool {
+    (c.is_control() && c != '\t' && c != '\n')
+        || matches!(c, '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Remove unsafe characters from `text`, or return `None` if it has none.
+fn strip_unsafe(text: &str) -> Option<String> {
+    text.contains(is_unsafe_char)
+        .then(|| text.chars().filter(|&c| !is_unsafe_char(c)).collect())
+}
+
+fn safe_cow<'a>(text: &Cow<'a, str>) -> Cow<'a, str> {
+    strip_unsafe(text).map_or_else(|| text.clone(), Cow::Owned)
+}
+
+fn safe_plugin(plugin: &PluginIdentifier) -> PluginIdentifier {
+    PluginIdentifier {
+        fqcn: strip_unsafe(&plugin.fqcn).unwrap_or_else(|| plugin.fqcn.clone()),
+        r#type: strip_unsafe(&plugin.r#type).unwrap_or_else(|| plugin.r#type.clone()),
+    }
+}
+
+fn safe_shared_plugin(plugin: &Rc<PluginIdentifier>) -> Rc<PluginIdentifier> {
+    if plugin.fqcn.contains(is_unsafe_char) || plugin.r#type.contains(is_unsafe_char) {
+        Rc::new(safe_plugin(plugin))
+    } else {
+        plugin.clone()
+    }
+}
+
+fn safe_entrypoint(entrypoint: &Rc<String>) -> Rc<String> {
+    strip_unsafe(entrypoint).map_or_else(|| entrypoint.clone(), Rc::new)
+}
+
+fn safe_link(link: &[CompactString]) -> Box<[CompactString]> {
+    link.iter()
+        .map(|component| strip_unsafe(component).map_or_else(|| component.clone(), Into::into))
+        .collect()
+}
+
+/// The text of a part that is kept as a string slice, and shown instead of the part for URLs,
+/// links, and RST references.
+fn borrowed_text<'a>(part: &Part<'a>) -> Option<&'a str> {
+    match *part {
+        Part::Text { text }
+        | Part::Italic { text }
+        | Part::Bold { text }
+        | Part::Code { text }
+        | Part::URL { url: text }
+        | Part::Link { text, url: _ }
+        | Part::RSTRef { text, r#ref: _ }
+        | Part::Module { fqcn: text } => Some(text),
+        _ => None,
+    }
+}
+
+/// Prepare the parts of a paragraph from an untrusted source for rendering.
+///
+/// URLs are turned into plain text, and links and RST references into their plain text. Errors
+/// are removed, and control and text direction characters are removed from the texts, names,
+/// and values of all parts. Texts that needed changes are stored in `texts`, which the returned
+/// parts borrow from.
+pub fn sanitize_untrusted<'p, 's, 'a: 's + 'p, I>(
+    paragraph: I,
+    texts: &'s mut Vec<String>,
+) -> Vec<Part<'s>>
+where
+    I: IntoIterator<Item = &'p Part<'a>>,
+{
+    let parts: Vec<&'p Part<'a>> = paragraph.into_iter().collect();
+    let cleaned: Vec<Option<usize>> = parts
+        .iter()
+        .map(|part| {
+            let text = strip_unsafe(borrowed_text(part)?)?;
+            texts.push(text);
+            Some(texts.len() - 1)
+        })
+        .collect();
+    let texts: &'s Vec<String> = texts;
+    let mut result = Vec::new();
+    for (part, cleaned) in parts.into_iter().zip(cleaned) {
+        let text = match (cleaned, borrowed_text(part)) {
+            (Some(index), _) => texts[index].as_str(),
+            (None, Some(text)) => text,
+            (None, None) => "",
+        };
+        match part {
+            Part::Text { .. }
+            | Part::Italic { .. }
+            | Part::Bold { .. }
+            | Part::Code { .. }
+            | Part::URL { .. }
+            | Part::Link { .. }
+            | Part::RSTRef { .. }
+                if text.is_empty() => {}
+            Part::Text { .. } | Part::URL { .. } | Part::Link { .. } | Part::RSTRef { .. } => {
+                result.push(Part::Text { text })
+            }
+            Part::Italic { .. } => result.push(Part::Italic { text }),
+            Part::Bold { .. } => result.push(Part::Bold { text }),
+            Part::Code { .. } => result.push(Part::Code { text }),
+            Part::Module { .. } => result.push(Part::Module { fqcn: text }),
+            Part::Plugin { plugin } => result.push(Part::Plugin {
+                plugin: safe_plugin(plugin),
+            }),
+            Part::OptionName {
+                plugin,
+                entrypoint,
+                link,
+                name,
+                value,
+            } => result.push(Part::OptionName {
+                plugin: plugin.as_ref().map(safe_shared_plugin),
+                entrypoint: entrypoint.as_ref().map(safe_entrypoint),
+                link: safe_link(link),
+                name: safe_cow(name),
+                value: value.as_ref().map(safe_cow),
+            }),
+            Part::ReturnValue {
+                plugin,
+                entrypoint,
+                link,
+                name,
+                value,
+            } => result.push(Part::ReturnValue {
+                plugin: plugin.as_ref().map(safe_shared_plugin),
+                entrypoint: entrypoint.as_ref().map(safe_entrypoint),
+                link: safe_link(link),
+                name: safe_cow(name),
+                value: value.as_ref().map(safe_cow),
+            }),
+            Part::OptionValue { value } => result.push(Part::OptionValue {
+                value: safe_cow(value),
+            }),
+            Part::EnvVariable { name } => result.push(Part::EnvVariable {
+                name: safe_cow(name),
+            }),
+            Part::HorizontalLine => result.push(Part::HorizontalLine),
+            Part::Error { message: _ } => {}
+        }
+    }
+    result
+}
+
+impl Renderer<'_> {
+    /// Render parsed paragraphs from an untrusted source.
+    ///
+    /// The paragraphs are sanitized with [`sanitize_untrusted`] first. If the output would be
+    /// longer than allowed by `profile`, as many parts as fit are rendered, so the output never
+    /// ends in the middle of a part.
+    pub fn render_untrusted(
+        &self,
+        profile: &UntrustedProfile,
+        paragraphs: &[Vec<PartWithSource<'_>>],
+    ) -> UntrustedRendering {
+        let mut texts = vec![Vec::new(); paragraphs.len()];
+        let sanitized: Vec<Vec<Part<'_>>> = paragraphs
+            .iter()
+            .zip(texts.iter_mut())
+            .map(|(paragraph, texts)| {
+                sanitize_untrusted(paragraph.iter().map(|ps| &ps.part), texts)
+            })
+            .collect();
+        let render = |paragraphs: usize, parts: usize| {
+            let mut output = String::new();
+            self.render_paragraphs(
+                &mut output,
+                sanitized[..paragraphs]
+                    .iter()
+                    .map(|paragraph| &paragraph[..])
+                    .chain(
+                        sanitized
+                            .get(paragraphs)
+                            .filter(|_| parts > 0)
+                            .map(|paragraph| &paragraph[..parts]),
+                    )
+                    .map(|paragraph| paragraph.iter()),
+            );
+            output
+        };
+        let output = render(sanitized.len(), 0);
+        let Some(max_output_length) = profile.max_output_length else {
+            return UntrustedRendering {
+                output,
+                truncated: false,
+            };
+        };
+        if output.len() <= max_output_length {
+            return UntrustedRendering {
+                output,
+                truncated: false,
+            };
+        }
+
+        // Every prefix of the paragraphs that can be rendered, as number of complete paragraphs
+        // and number of parts of the next paragraph, from shortest to longest.
+        let mut prefixes = Vec::new();
+        for (index, paragraph) in sanitized.iter().enumerate() {
+            for parts in 1..paragraph.len() {
+                prefixes.push((index, parts));
+            }
+            prefixes.push((index + 1, 0));
+        }
+        let mut output = String::new();
+        let (mut low, mut high) = (0, prefixes.len());
+        while low < high {
+            let middle = (low + high) / 2;
+            let (paragraphs, parts) = prefixes[middle];
+            let candidate = render(paragraphs, parts);
+            if candidate.len() <= max_output_length {
+                output = candidate;
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        UntrustedRendering {
+            output,
+            truncated: true,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "html"))]
+mod tests {
+    use super::*;
+    use crate::markup::{parse_paragraphs, Context, NoLinkProvider, ParseOptions};
+
+    #[test]
+    fn test_render_untrusted() {
+        let context = Context::new();
+        let paragraphs = parse_paragraphs(
+            [
+                "See U(javascript:alert%281%29) and L(click,https://evil.example/) C(a\u{202e}b) V(c\u{202e}d).",
+                "Broken I(.",
+                "More B(text) here.",
+            ]
+            .into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let link_provider = NoLinkProvider::new();
+        let renderer = Renderer::plain_html(&link_provider);
+        let rendering = renderer.render_untrusted(
+            &UntrustedProfile::new().max_output_length(None),
+            &paragraphs,
+        );
+        assert_eq!(
+            rendering.output,
+            "<p>See javascript:alert%281%29 and click <code>ab</code> <code>cd</code>.</p><p>Broken </p><p>More <b>text</b> here.</p>"
+        );
+        assert!(!rendering.truncated);
+
+        for (max_output_length, output) in [
+            (200, rendering.output.as_str()),
+            (
+                110,
+                "<p>See javascript:alert%281%29 and click <code>ab</code> <code>cd</code>.</p><p>Broken </p><p>More </p>",
+            ),
+            (11, "<p>See </p>"),
+            (5, ""),
+        ] {
+            let rendering = renderer.render_untrusted(
+                &UntrustedProfile::new().max_output_length(Some(max_output_length)),
+                &paragraphs,
+            );
+            assert_eq!(rendering.output, output);
+            assert_eq!(rendering.truncated, max_output_length < 200);
+        }
+    }
+}
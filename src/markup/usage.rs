@@ -0,0 +1,143 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::dom::Part;
+use std::collections::BTreeMap;
+
+/// The markup command a part was created from, and whether the command is semantic markup.
+///
+/// Returns `None` for text and errors.
+pub(crate) fn command_of(part: &Part<'_>) -> Option<(&'static str, bool)> {
+    match part {
+        Part::Text { .. } | Part::Error { .. } => None,
+        Part::Italic { .. } => Some(("I", false)),
+        Part::Bold { .. } => Some(("B", false)),
+        Part::Code { .. } => Some(("C", false)),
+        Part::Module { .. } => Some(("M", false)),
+        Part::Plugin { .. } => Some(("P", true)),
+        Part::URL { .. } => Some(("U", false)),
+        Part::Link { .. } => Some(("L", false)),
+        Part::RSTRef { .. } => Some(("R", false)),
+        Part::OptionName { .. } => Some(("O", true)),
+        Part::OptionValue { .. } => Some(("V", true)),
+        Part::EnvVariable { .. } => Some(("E", true)),
+        Part::ReturnValue { .. } => Some(("RV", true)),
+        Part::HorizontalLine => Some(("HORIZONTALLINE", false)),
+    }
+}
+
+/// Statistics on the markup commands used in parsed paragraphs.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MarkupUsage {
+    /// The number of paragraphs.
+    pub paragraphs: usize,
+
+    /// The number of paragraphs with at least one error.
+    pub paragraphs_with_errors: usize,
+
+    /// The number of errors.
+    pub errors: usize,
+
+    /// The number of uses of every command, like `O` or `HORIZONTALLINE`.
+    pub commands: BTreeMap<&'static str, usize>,
+
+    /// The number of uses of classic markup commands.
+    pub classic: usize,
+
+    /// The number of uses of semantic markup commands (`E`, `O`, `P`, `RV`, and `V`).
+    pub semantic: usize,
+}
+
+impl MarkupUsage {
+    pub fn new() -> MarkupUsage {
+        MarkupUsage::default()
+    }
+
+    /// Count the commands and errors of a paragraph.
+    pub fn add_paragraph<'a, I>(&mut self, paragraph: I)
+    where
+        I: IntoIterator<Item = &'a Part<'a>>,
+    {
+        self.paragraphs += 1;
+        let errors = self.errors;
+        for part in paragraph {
+            if let Part::Error { .. } = part {
+                self.errors += 1;
+            }
+            let Some((command, semantic)) = command_of(part) else {
+                continue;
+            };
+            *self.commands.entry(command).or_insert(0) += 1;
+            if semantic {
+                self.semantic += 1;
+            } else {
+                self.classic += 1;
+            }
+        }
+        if self.errors > errors {
+            self.paragraphs_with_errors += 1;
+        }
+    }
+
+    /// Add the statistics of `other`.
+    pub fn merge(&mut self, other: &MarkupUsage) {
+        self.paragraphs += other.paragraphs;
+        self.paragraphs_with_errors += other.paragraphs_with_errors;
+        self.errors += other.errors;
+        for (command, count) in &other.commands {
+            *self.commands.entry(command).or_insert(0) += count;
+        }
+        self.classic += other.classic;
+        self.semantic += other.semantic;
+    }
+
+    /// The fraction of command uses that are semantic markup, or `None` if no commands are used.
+    pub fn semantic_ratio(&self) -> Option<f64> {
+        let total = self.classic + self.semantic;
+        (total > 0).then(|| self.semantic as f64 / total as f64)
+    }
+
+    /// The fraction of paragraphs with errors, or `None` if there are no paragraphs.
+    pub fn error_rate(&self) -> Option<f64> {
+        (self.paragraphs > 0).then(|| self.paragraphs_with_errors as f64 / self.paragraphs as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::{parse_paragraphs_without_sources, Context, ParseOptions};
+
+    #[test]
+    fn test_markup_usage() {
+        let context = Context::new();
+        let paragraphs = parse_paragraphs_without_sources(
+            ["I(a) O(b) V(c) and O(d)", "C(x", "plain"].into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let mut usage = MarkupUsage::new();
+        for paragraph in &paragraphs {
+            usage.add_paragraph(paragraph);
+        }
+        assert_eq!(usage.paragraphs, 3);
+        assert_eq!(usage.paragraphs_with_errors, 1);
+        assert_eq!(usage.errors, 1);
+        assert_eq!(
+            usage.commands,
+            BTreeMap::from([("I", 1), ("O", 2), ("V", 1)])
+        );
+        assert_eq!(usage.semantic_ratio(), Some(0.75));
+        assert_eq!(usage.error_rate(), Some(1.0 / 3.0));
+
+        let mut total = MarkupUsage::new();
+        total.merge(&usage);
+        total.merge(&usage);
+        assert_eq!(total.commands["O"], 4);
+        assert_eq!(total.semantic_ratio(), Some(0.75));
+        assert_eq!(MarkupUsage::new().error_rate(), None);
+    }
+}
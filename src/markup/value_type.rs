@@ -0,0 +1,119 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+/// The kind of value shown by `V()`, as far as it can be guessed from its text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OptionValueType {
+    /// A boolean-like value, like `true`, `False`, or `yes`.
+    Boolean,
+
+    /// An integer, like `42` or `-1`.
+    Integer,
+
+    /// A floating point number, like `1.5` or `1e-3`.
+    Float,
+
+    /// A string in single or double quotes, like `"foo"`.
+    QuotedString,
+
+    /// A YAML flow sequence, like `[a, b]`.
+    List,
+
+    /// Any other value.
+    Other,
+}
+
+fn is_digits(text: &str) -> bool {
+    !text.is_empty() && text.bytes().all(|c| c.is_ascii_digit())
+}
+
+fn is_float(text: &str) -> bool {
+    let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (text, None),
+    };
+    let mantissa_ok = match mantissa.split_once('.') {
+        Some((integer, fraction)) => {
+            (integer.is_empty() || is_digits(integer))
+                && (fraction.is_empty() || is_digits(fraction))
+                && !(integer.is_empty() && fraction.is_empty())
+        }
+        None => exponent.is_some() && is_digits(mantissa),
+    };
+    let exponent_ok = match exponent {
+        Some(exponent) => is_digits(exponent.strip_prefix(['+', '-']).unwrap_or(exponent)),
+        None => true,
+    };
+    mantissa_ok && exponent_ok
+}
+
+impl OptionValueType {
+    /// Guess the kind of a value from its text.
+    ///
+    /// Boolean-like values are the ones YAML 1.1 and Ansible accept for booleans, in lower case,
+    /// title case, or upper case.
+    pub fn classify(value: &str) -> OptionValueType {
+        match value {
+            "true" | "True" | "TRUE" | "false" | "False" | "FALSE" | "yes" | "Yes" | "YES"
+            | "no" | "No" | "NO" | "on" | "On" | "ON" | "off" | "Off" | "OFF" => {
+                return OptionValueType::Boolean
+            }
+            _ => {}
+        }
+        let unsigned = value.strip_prefix(['+', '-']).unwrap_or(value);
+        if is_digits(unsigned) {
+            return OptionValueType::Integer;
+        }
+        if is_float(unsigned) {
+            return OptionValueType::Float;
+        }
+        let quoted =
+            |quote: char| value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote);
+        if quoted('"') || quoted('\'') {
+            return OptionValueType::QuotedString;
+        }
+        if value.starts_with('[') && value.ends_with(']') {
+            return OptionValueType::List;
+        }
+        OptionValueType::Other
+    }
+
+    /// The name of the kind, like `boolean` or `quoted-string`, for example for CSS classes.
+    pub fn name(self) -> &'static str {
+        match self {
+            OptionValueType::Boolean => "boolean",
+            OptionValueType::Integer => "integer",
+            OptionValueType::Float => "float",
+            OptionValueType::QuotedString => "quoted-string",
+            OptionValueType::List => "list",
+            OptionValueType::Other => "other",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let classify = OptionValueType::classify;
+        assert_eq!(classify("true"), OptionValueType::Boolean);
+        assert_eq!(classify("No"), OptionValueType::Boolean);
+        assert_eq!(classify("nO"), OptionValueType::Other);
+        assert_eq!(classify("-42"), OptionValueType::Integer);
+        assert_eq!(classify("1.5"), OptionValueType::Float);
+        assert_eq!(classify(".5e-3"), OptionValueType::Float);
+        assert_eq!(classify("1e3"), OptionValueType::Float);
+        assert_eq!(classify("1.2.3"), OptionValueType::Other);
+        assert_eq!(classify("."), OptionValueType::Other);
+        assert_eq!(classify("'foo'"), OptionValueType::QuotedString);
+        assert_eq!(classify("\""), OptionValueType::Other);
+        assert_eq!(classify("[a, b]"), OptionValueType::List);
+        assert_eq!(classify("present"), OptionValueType::Other);
+        assert_eq!(classify(""), OptionValueType::Other);
+    }
+}
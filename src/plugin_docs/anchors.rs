@@ -0,0 +1,214 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::{
+    CompactString, DeterministicLinkProvider, LinkProvider, OptionLike, PluginIdentifier,
+};
+use crate::plugin_docs::table::Table;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The anchor IDs used on one page.
+///
+/// Anchors are registered in a scope, like the entrypoint of a role whose entrypoints each have
+/// an options table. An ID that is already in use gets the first free suffix `-2`, `-3`, and so
+/// on, so the IDs only depend on the order of registration. References can later be resolved to
+/// the chosen IDs with the scope and the ID they were generated for.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AnchorRegistry {
+    ids: BTreeSet<String>,
+    chosen: BTreeMap<(Option<String>, String), String>,
+}
+
+impl AnchorRegistry {
+    pub fn new() -> AnchorRegistry {
+        AnchorRegistry::default()
+    }
+
+    /// Register the anchor `id` in `scope` and return the ID to use for it.
+    ///
+    /// Registering the same ID in the same scope again returns the same result.
+    pub fn register(&mut self, scope: Option<&str>, id: &str) -> String {
+        let key = (scope.map(|s| s.to_string()), id.to_string());
+        if let Some(chosen) = self.chosen.get(&key) {
+            return chosen.clone();
+        }
+        let mut chosen = id.to_string();
+        let mut suffix = 2;
+        while self.ids.contains(&chosen) {
+            chosen = format!("{}-{}", id, suffix);
+            suffix += 1;
+        }
+        self.ids.insert(chosen.clone());
+        self.chosen.insert(key, chosen.clone());
+        chosen
+    }
+
+    /// Register all anchors of `table` in `scope`, and replace them by the IDs to use.
+    pub fn register_table(&mut self, scope: Option<&str>, table: &mut Table<'_>) {
+        for row in table.rows.iter_mut() {
+            for anchor in row.anchors.iter_mut() {
+                *anchor = self.register(scope, anchor);
+            }
+        }
+    }
+
+    /// The ID chosen for the anchor `id` registered in `scope`.
+    pub fn resolve(&self, scope: Option<&str>, id: &str) -> Option<&str> {
+        self.chosen
+            .get(&(scope.map(|s| s.to_string()), id.to_string()))
+            .map(|chosen| chosen.as_str())
+    }
+
+    /// Whether an anchor was registered under the ID `id`.
+    pub fn contains(&self, id: &str) -> bool {
+        self.ids.contains(id)
+    }
+}
+
+/// Rewrites the anchors of links into the current plugin's page to the IDs chosen by an
+/// [`AnchorRegistry`].
+///
+/// The entrypoint of a reference is used as the scope. Anchors that were not registered are
+/// kept as they are.
+pub struct AnchorRegistryLinkProvider<'l, L: ?Sized> {
+    link_provider: &'l L,
+    registry: &'l AnchorRegistry,
+}
+
+impl<'l, L: ?Sized> AnchorRegistryLinkProvider<'l, L> {
+    pub fn new(
+        link_provider: &'l L,
+        registry: &'l AnchorRegistry,
+    ) -> AnchorRegistryLinkProvider<'l, L> {
+        AnchorRegistryLinkProvider {
+            link_provider,
+            registry,
+        }
+    }
+}
+
+impl<L: LinkProvider + ?Sized> LinkProvider for AnchorRegistryLinkProvider<'_, L> {
+    fn plugin_link(&self, plugin: &PluginIdentifier) -> Option<String> {
+        self.link_provider.plugin_link(plugin)
+    }
+
+    fn plugin_option_like_link(
+        &self,
+        plugin: &PluginIdentifier,
+        entrypoint: Option<&String>,
+        what: OptionLike,
+        name: &[CompactString],
+        current_plugin: bool,
+    ) -> Option<String> {
+        let url = self.link_provider.plugin_option_like_link(
+            plugin,
+            entrypoint,
+            what,
+            name,
+            current_plugin,
+        )?;
+        if !current_plugin {
+            return Some(url);
+        }
+        let Some((base, anchor)) = url.split_once('#') else {
+            return Some(url);
+        };
+        match self
+            .registry
+            .resolve(entrypoint.map(|ep| ep.as_str()), anchor)
+        {
+            Some(chosen) if chosen != anchor => Some(format!("{}#{}", base, chosen)),
+            _ => Some(url),
+        }
+    }
+
+    fn env_variable_link(&self, name: &str) -> Option<String> {
+        self.link_provider.env_variable_link(name)
+    }
+}
+
+impl<L: DeterministicLinkProvider + ?Sized> DeterministicLinkProvider
+    for AnchorRegistryLinkProvider<'_, L>
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::{parse_paragraphs, Context, ParseOptions, Renderer};
+    use crate::plugin_docs::{load_options, options_table, DefaultTypeLocalizer};
+    use saphyr::Yaml;
+
+    struct TestLinkProvider {}
+
+    impl LinkProvider for TestLinkProvider {
+        fn plugin_link(&self, _plugin: &PluginIdentifier) -> Option<String> {
+            None
+        }
+
+        fn plugin_option_like_link(
+            &self,
+            _plugin: &PluginIdentifier,
+            _entrypoint: Option<&String>,
+            _what: OptionLike,
+            name: &[CompactString],
+            _current_plugin: bool,
+        ) -> Option<String> {
+            Some(format!("#parameter-{}", name.join("/")))
+        }
+    }
+
+    #[test]
+    fn test_anchor_registry() {
+        let mut registry = AnchorRegistry::new();
+        assert_eq!(registry.register(None, "a-2"), "a-2");
+        assert_eq!(registry.register(None, "a"), "a");
+        assert_eq!(registry.register(Some("x"), "a"), "a-3");
+        assert_eq!(registry.register(Some("y"), "a"), "a-4");
+        assert_eq!(registry.register(Some("x"), "a"), "a-3");
+        assert_eq!(registry.resolve(Some("y"), "a"), Some("a-4"));
+        assert_eq!(registry.resolve(Some("z"), "a"), None);
+        assert!(registry.contains("a-3"));
+
+        let options = load_options(
+            &Yaml::load_from_str("foo:\n  aliases: [bar]\n")
+                .unwrap()
+                .remove(0),
+        )
+        .unwrap();
+        let context = Context::new();
+        let localizer = DefaultTypeLocalizer::new();
+        let mut registry = AnchorRegistry::new();
+        for entrypoint in ["main", "other"] {
+            let mut table = options_table(&options, &context, &ParseOptions::default(), &localizer);
+            registry.register_table(Some(entrypoint), &mut table);
+            if entrypoint == "other" {
+                assert_eq!(
+                    table.rows[0].anchors,
+                    vec!["parameter-foo-2", "parameter-bar-2"]
+                );
+            }
+        }
+
+        let context = Context::new().current_plugin("foo.bar.baz", "role");
+        let paragraphs = parse_paragraphs(
+            ["O(main:foo) O(other:foo) O(other:baz)"].into_iter(),
+            &context,
+            &ParseOptions::default(),
+        );
+        let link_provider = AnchorRegistryLinkProvider::new(&TestLinkProvider {}, &registry);
+        assert_eq!(
+            Renderer::md(&link_provider)
+                .with_current_plugin(context.current_plugin.clone())
+                .render(&paragraphs),
+            concat!(
+                "<code><strong><a href=\"#parameter-foo\">foo</a></strong></code> ",
+                "<code><strong><a href=\"#parameter-foo-2\">foo</a></strong></code> ",
+                "<code><strong><a href=\"#parameter-baz\">baz</a></strong></code>",
+            )
+        );
+    }
+}
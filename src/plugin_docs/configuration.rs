@@ -0,0 +1,411 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! The configuration settings (`ini`, `env`, `vars`, `cli`, and `keyword`) of plugin options.
+
+use crate::document::{indent, indent_first, Paragraph};
+use crate::markup::{HTMLEscaper, LinkProvider, Part, PluginIdentifier};
+use crate::plugin_docs::table::{
+    ParagraphStyle, ANTSIBULL_HTML_STYLE, GFM_STYLE, PLAIN_HTML_STYLE, RST_STYLE,
+};
+use crate::util::stringbuilder::Appender;
+use crate::util::yaml;
+use saphyr::{Hash, Yaml};
+use std::rc::Rc;
+
+/// An entry of an INI file that sets an option.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IniEntry {
+    /// The section, like `defaults`.
+    pub section: String,
+
+    /// The key inside the section.
+    pub key: String,
+}
+
+/// A command line argument that sets an option.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliEntry {
+    /// The name of the argument.
+    pub name: String,
+
+    /// The argument itself, like `--private-key`. Defaults to the name with `--` in front and
+    /// underscores replaced by dashes.
+    pub option: String,
+}
+
+/// How an option of a controller-side plugin can be configured besides task and play keywords.
+///
+/// All lists are in the order of the documentation, which is the order of precedence.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct OptionConfiguration {
+    /// The INI entries of `ansible.cfg`.
+    pub ini: Vec<IniEntry>,
+
+    /// The names of environment variables.
+    pub env: Vec<String>,
+
+    /// The names of Ansible variables.
+    pub vars: Vec<String>,
+
+    /// The command line arguments.
+    pub cli: Vec<CliEntry>,
+
+    /// The names of playbook keywords.
+    pub keyword: Vec<String>,
+}
+
+fn load_entries<T>(
+    hash: &Hash,
+    key: &str,
+    path: &str,
+    load: impl Fn(&Hash, &str) -> Result<T, String>,
+) -> Result<Vec<T>, String> {
+    match yaml::get(hash, key) {
+        None | Some(Yaml::Null) => Ok(Vec::new()),
+        Some(Yaml::Array(entries)) => entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let path = format!("{}.{}[{}]", path, key, index);
+                load(yaml::as_hash(entry, &path)?, &path)
+            })
+            .collect(),
+        Some(_) => Err(format!("{}.{}: expected a list", path, key)),
+    }
+}
+
+fn required_string(hash: &Hash, key: &str, path: &str) -> Result<String, String> {
+    yaml::get_string(hash, key, path)?.ok_or_else(|| format!("{}.{}: missing", path, key))
+}
+
+impl OptionConfiguration {
+    /// Load the configuration settings from the mapping of an option at `path`.
+    pub(crate) fn load(hash: &Hash, path: &str) -> Result<OptionConfiguration, String> {
+        let name = |hash: &Hash, path: &str| required_string(hash, "name", path);
+        Ok(OptionConfiguration {
+            ini: load_entries(hash, "ini", path, |hash, path| {
+                Ok(IniEntry {
+                    section: required_string(hash, "section", path)?,
+                    key: required_string(hash, "key", path)?,
+                })
+            })?,
+            env: load_entries(hash, "env", path, name)?,
+            vars: load_entries(hash, "vars", path, name)?,
+            cli: load_entries(hash, "cli", path, |hash, path| {
+                let name = required_string(hash, "name", path)?;
+                let option = yaml::get_string(hash, "option", path)?
+                    .unwrap_or_else(|| format!("--{}", name.replace('_', "-")));
+                Ok(CliEntry { name, option })
+            })?,
+            keyword: load_entries(hash, "keyword", path, name)?,
+        })
+    }
+
+    /// Whether the option cannot be configured in any of these ways.
+    pub fn is_empty(&self) -> bool {
+        self.ini.is_empty()
+            && self.env.is_empty()
+            && self.vars.is_empty()
+            && self.cli.is_empty()
+            && self.keyword.is_empty()
+    }
+
+    /// Describe every setting with a paragraph, and for INI entries also with the INI snippet.
+    ///
+    /// Environment variables are shown as `E()`, so that they link to the index of environment
+    /// variables.
+    fn items(&self) -> Vec<(Paragraph<'_>, Option<String>)> {
+        let mut result: Vec<(Paragraph, Option<String>)> = Vec::new();
+        for entry in &self.ini {
+            result.push((
+                vec![Part::Text { text: "INI entry:" }],
+                Some(format!("[{}]\n{} = VALUE", entry.section, entry.key)),
+            ));
+        }
+        for name in &self.env {
+            result.push((
+                vec![
+                    Part::Text {
+                        text: "Environment variable: ",
+                    },
+                    Part::EnvVariable { name: name.into() },
+                ],
+                None,
+            ));
+        }
+        for (label, names) in [("Variable: ", &self.vars), ("Keyword: ", &self.keyword)] {
+            for name in names {
+                result.push((
+                    vec![Part::Text { text: label }, Part::Code { text: name }],
+                    None,
+                ));
+            }
+        }
+        for entry in &self.cli {
+            result.push((
+                vec![
+                    Part::Text {
+                        text: "CLI argument: ",
+                    },
+                    Part::Code {
+                        text: &entry.option,
+                    },
+                ],
+                None,
+            ));
+        }
+        result
+    }
+}
+
+/// Render the configuration settings as RST, using the roles of the `sphinx_antsibull_ext`
+/// extension.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup.
+pub fn append_rst_configuration<'a>(
+    appender: &mut dyn Appender<'a>,
+    configuration: &'a OptionConfiguration,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) {
+    appender.push_str(":ansible-option-configuration:`Configuration:`");
+    for (paragraph, ini) in configuration.items() {
+        let mut item = RST_STYLE.render(&[paragraph], link_provider, current_plugin);
+        if let Some(ini) = ini {
+            item.push_str("\n\n.. code-block:: ini\n\n");
+            item.push_str(&indent(&ini, "  ", ""));
+        }
+        appender.push_str("\n\n");
+        appender.push_owned_string(indent_first(&item, "- ", "  ", ""));
+    }
+}
+
+fn append_html_configuration_impl<'a>(
+    appender: &mut dyn Appender<'a>,
+    configuration: &'a OptionConfiguration,
+    style: &ParagraphStyle,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) {
+    let escaper = HTMLEscaper::new();
+    appender.push_str("<p class=\"ansible-option-line\"><strong class=\"ansible-option-configuration\">Configuration:</strong></p><ul class=\"simple\">");
+    for (paragraph, ini) in configuration.items() {
+        appender.push_str("<li>");
+        appender.push_owned_string(style.render(&[paragraph], link_provider, current_plugin));
+        if let Some(ini) = ini {
+            appender.push_str("<pre><code class=\"language-ini\">");
+            appender.push_owned_string(escaper.escape(&ini).into_owned());
+            appender.push_str("</code></pre>");
+        }
+        appender.push_str("</li>");
+    }
+    appender.push_str("</ul>");
+}
+
+/// Render the configuration settings as HTML, using the Antsibull HTML formatter.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup.
+pub fn append_antsibull_html_configuration<'a>(
+    appender: &mut dyn Appender<'a>,
+    configuration: &'a OptionConfiguration,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) {
+    append_html_configuration_impl(
+        appender,
+        configuration,
+        &ANTSIBULL_HTML_STYLE,
+        link_provider,
+        current_plugin,
+    );
+}
+
+/// Render the configuration settings as HTML, using the plain HTML formatter.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup.
+pub fn append_plain_html_configuration<'a>(
+    appender: &mut dyn Appender<'a>,
+    configuration: &'a OptionConfiguration,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) {
+    append_html_configuration_impl(
+        appender,
+        configuration,
+        &PLAIN_HTML_STYLE,
+        link_provider,
+        current_plugin,
+    );
+}
+
+/// Render the configuration settings for a GitHub Flavored Markdown table cell.
+///
+/// Everything is rendered on a single line, using `<br>` for line breaks.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup.
+pub fn append_gfm_configuration<'a>(
+    appender: &mut dyn Appender<'a>,
+    configuration: &'a OptionConfiguration,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) {
+    let escaper = HTMLEscaper::new();
+    appender.push_str("<b>Configuration:</b>");
+    for (paragraph, ini) in configuration.items() {
+        appender.push_str("<br>- ");
+        appender.push_owned_string(GFM_STYLE.render(&[paragraph], link_provider, current_plugin));
+        if let Some(ini) = ini {
+            appender.push_str(" <code>");
+            appender.push_owned_string(
+                escaper
+                    .escape(&ini)
+                    .replace('|', "&#124;")
+                    .replace('\n', "<br>"),
+            );
+            appender.push_str("</code>");
+        }
+    }
+}
+
+/// Render the configuration settings in the style of ansible-doc's text output.
+///
+/// Like ansible-doc, this lists the settings as YAML below `set_via:`, so neither markup nor
+/// links are used.
+pub fn append_ansible_doc_text_configuration<'a>(
+    appender: &mut dyn Appender<'a>,
+    configuration: &'a OptionConfiguration,
+    _link_provider: &dyn LinkProvider,
+    _current_plugin: &Option<Rc<PluginIdentifier>>,
+) {
+    appender.push_str("set_via:");
+    if !configuration.cli.is_empty() {
+        appender.push_str("\n  cli:");
+        for entry in &configuration.cli {
+            appender.push_owned_string(format!(
+                "\n  - name: {}\n    option: {}",
+                entry.name, entry.option
+            ));
+        }
+    }
+    for (key, names) in [
+        ("env", &configuration.env),
+        ("keyword", &configuration.keyword),
+    ] {
+        if !names.is_empty() {
+            appender.push_owned_string(format!("\n  {}:", key));
+            for name in names {
+                appender.push_owned_string(format!("\n  - name: {}", name));
+            }
+        }
+    }
+    if !configuration.ini.is_empty() {
+        appender.push_str("\n  ini:");
+        for entry in &configuration.ini {
+            appender.push_owned_string(format!(
+                "\n  - key: {}\n    section: {}",
+                entry.key, entry.section
+            ));
+        }
+    }
+    if !configuration.vars.is_empty() {
+        appender.push_str("\n  vars:");
+        for name in &configuration.vars {
+            appender.push_owned_string(format!("\n  - name: {}", name));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::NoLinkProvider;
+    use crate::util::stringbuilder::{CollectorAppender, IntoString};
+
+    type AppendConfiguration<'a> = fn(
+        &mut dyn Appender<'a>,
+        &'a OptionConfiguration,
+        &dyn LinkProvider,
+        &Option<Rc<PluginIdentifier>>,
+    );
+
+    fn render<'a>(
+        append: AppendConfiguration<'a>,
+        configuration: &'a OptionConfiguration,
+    ) -> String {
+        let mut appender = CollectorAppender::new();
+        append(&mut appender, configuration, &NoLinkProvider::new(), &None);
+        appender.into_string()
+    }
+
+    #[test]
+    fn test_configuration() {
+        let option = Yaml::load_from_str(concat!(
+            "ini:\n",
+            "  - section: defaults\n",
+            "    key: remote_user\n",
+            "env:\n",
+            "  - name: ANSIBLE_REMOTE_USER\n",
+            "vars:\n",
+            "  - name: ansible_user\n",
+            "cli:\n",
+            "  - name: remote_user\n",
+            "keyword:\n",
+            "  - name: remote_user\n",
+        ))
+        .unwrap()
+        .remove(0);
+        let configuration =
+            OptionConfiguration::load(yaml::as_hash(&option, "").unwrap(), "options.x").unwrap();
+        assert_eq!(configuration.cli[0].option, "--remote-user");
+        assert!(!configuration.is_empty());
+        assert_eq!(
+            render(append_rst_configuration, &configuration),
+            concat!(
+                ":ansible-option-configuration:`Configuration:`\n\n",
+                "- INI entry:\n\n",
+                "  .. code-block:: ini\n\n",
+                "    [defaults]\n",
+                "    remote_user = VALUE\n\n",
+                "- Environment variable: \\ :envvar:`ANSIBLE\\_REMOTE\\_USER`\\ \n\n",
+                "- Variable: \\ :literal:`ansible\\_user`\\ \n\n",
+                "- Keyword: \\ :literal:`remote\\_user`\\ \n\n",
+                "- CLI argument: \\ :literal:`--remote-user`\\ ",
+            )
+        );
+        assert_eq!(
+            render(append_gfm_configuration, &configuration),
+            concat!(
+                "<b>Configuration:</b>",
+                "<br>- INI entry\\: <code>[defaults]<br>remote_user = VALUE</code>",
+                "<br>- Environment variable\\: <code>ANSIBLE\\_REMOTE\\_USER</code>",
+                "<br>- Variable\\: <code>ansible\\_user</code>",
+                "<br>- Keyword\\: <code>remote\\_user</code>",
+                "<br>- CLI argument\\: <code>\\-\\-remote\\-user</code>",
+            )
+        );
+        assert_eq!(
+            render(append_ansible_doc_text_configuration, &configuration),
+            concat!(
+                "set_via:\n",
+                "  cli:\n  - name: remote_user\n    option: --remote-user\n",
+                "  env:\n  - name: ANSIBLE_REMOTE_USER\n",
+                "  keyword:\n  - name: remote_user\n",
+                "  ini:\n  - key: remote_user\n    section: defaults\n",
+                "  vars:\n  - name: ansible_user",
+            )
+        );
+
+        let option = Yaml::load_from_str("ini:\n  - key: foo\n")
+            .unwrap()
+            .remove(0);
+        assert_eq!(
+            OptionConfiguration::load(yaml::as_hash(&option, "").unwrap(), "options.x")
+                .unwrap_err(),
+            "options.x.ini[0].section: missing"
+        );
+    }
+}
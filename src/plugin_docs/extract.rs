@@ -0,0 +1,275 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::util::yaml;
+use saphyr::Yaml;
+
+/// The documentation blocks of a plugin.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DocBlocks {
+    /// The parsed `DOCUMENTATION` block.
+    pub documentation: Option<Yaml>,
+
+    /// The `EXAMPLES` block. It is not parsed, since it is shown as-is.
+    pub examples: Option<String>,
+
+    /// The parsed `RETURN` block.
+    pub return_values: Option<Yaml>,
+}
+
+pub(crate) fn parse_yaml(content: &str, name: &str) -> Result<Yaml, String> {
+    let mut documents = Yaml::load_from_str(content).map_err(|error| {
+        format!(
+            "{}: syntax error in line {}, column {}: {}",
+            name,
+            error.marker().line(),
+            error.marker().col() + 1,
+            error.info()
+        )
+    })?;
+    if documents.is_empty() {
+        Ok(Yaml::Null)
+    } else {
+        Ok(documents.swap_remove(0))
+    }
+}
+
+/// Decode the escape sequences of a non-raw Python string literal.
+fn unescape(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('\n') | None => {}
+            Some(c @ ('\\' | '\'' | '"')) => result.push(c),
+            Some(c) => {
+                result.push('\\');
+                result.push(c);
+            }
+        }
+    }
+    result
+}
+
+/// The string literal of an assignment `NAME = r'''...'''` in Python source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PythonStringLiteral {
+    /// The byte offset of the literal's content, after the opening quote.
+    pub start: usize,
+
+    /// The byte offset after the literal's content, before the closing quote.
+    pub end: usize,
+
+    /// Whether the literal is a raw string, that is, whether escape sequences are not decoded.
+    pub raw: bool,
+
+    /// The quote that starts and ends the literal, like `'''`.
+    pub quote: &'static str,
+}
+
+impl PythonStringLiteral {
+    /// Return the decoded value of the literal in `source`.
+    pub fn value(&self, source: &str) -> String {
+        let content = &source[self.start..self.end];
+        if self.raw {
+            content.to_string()
+        } else {
+            unescape(content)
+        }
+    }
+}
+
+/// Find the string literal assigned to `name` by `name = '''...'''` at the start of a line.
+pub fn find_string_assignment(
+    source: &str,
+    name: &str,
+) -> Result<Option<PythonStringLiteral>, String> {
+    find_assignment(source, name, false)
+}
+
+/// Find the string literal assigned to `name` like [`find_string_assignment`], but also in
+/// indented lines, like the attributes of the classes of documentation fragments.
+pub(crate) fn find_indented_string_assignment(
+    source: &str,
+    name: &str,
+) -> Result<Option<PythonStringLiteral>, String> {
+    find_assignment(source, name, true)
+}
+
+fn find_assignment(
+    source: &str,
+    name: &str,
+    indented: bool,
+) -> Result<Option<PythonStringLiteral>, String> {
+    let mut offset = 0;
+    for line in source.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let content = if indented {
+            line.trim_start_matches([' ', '\t'])
+        } else {
+            line
+        };
+        let Some(rest) = content.strip_prefix(name) else {
+            continue;
+        };
+        let Some(rest) = rest.trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let rest = rest.trim_start();
+        let prefix_length = rest
+            .find(|c: char| !matches!(c, 'r' | 'R' | 'u' | 'U'))
+            .unwrap_or(rest.len());
+        let raw = rest[..prefix_length].contains(['r', 'R']);
+        let literal = &rest[prefix_length..];
+        let quote = ["'''", "\"\"\"", "'", "\""]
+            .into_iter()
+            .find(|quote| literal.starts_with(quote))
+            .ok_or_else(|| format!("{}: expected a string literal", name))?;
+        let body_start = start + (line.len() - literal.len()) + quote.len();
+        let body = &source[body_start..];
+        let mut index = 0;
+        let end = loop {
+            let Some(found) = body[index..].find(quote) else {
+                return Err(format!("{}: unterminated string literal", name));
+            };
+            let candidate = index + found;
+            let backslashes = body[..candidate]
+                .chars()
+                .rev()
+                .take_while(|c| *c == '\\')
+                .count();
+            if backslashes % 2 == 0 {
+                break candidate;
+            }
+            index = candidate + 1;
+        };
+        return Ok(Some(PythonStringLiteral {
+            start: body_start,
+            end: body_start + end,
+            raw,
+            quote,
+        }));
+    }
+    Ok(None)
+}
+
+/// Extract the documentation blocks from the source of a Python module or plugin.
+pub fn extract_python_doc_blocks(source: &str) -> Result<DocBlocks, String> {
+    let value = |name: &str| -> Result<Option<String>, String> {
+        Ok(find_string_assignment(source, name)?.map(|literal| literal.value(source)))
+    };
+    let documentation = value("DOCUMENTATION")?;
+    let examples = value("EXAMPLES")?;
+    let return_values = value("RETURN")?;
+    Ok(DocBlocks {
+        documentation: documentation
+            .map(|content| parse_yaml(&content, "DOCUMENTATION"))
+            .transpose()?,
+        examples,
+        return_values: return_values
+            .map(|content| parse_yaml(&content, "RETURN"))
+            .transpose()?,
+    })
+}
+
+/// Extract the documentation blocks from a sidecar YAML file, as used for filter and test
+/// plugins.
+pub fn extract_yaml_doc_blocks(source: &str) -> Result<DocBlocks, String> {
+    let document = parse_yaml(source, "sidecar")?;
+    let hash = yaml::as_hash(&document, "sidecar")?;
+    let get = |key: &str| match yaml::get(hash, key) {
+        None | Some(Yaml::Null) => None,
+        Some(value) => Some(value.clone()),
+    };
+    Ok(DocBlocks {
+        documentation: get("DOCUMENTATION"),
+        examples: yaml::get_string(hash, "EXAMPLES", "sidecar")?,
+        return_values: get("RETURN"),
+    })
+}
+
+/// Extract the documentation blocks from a role's `meta/argument_specs.yml`.
+///
+/// The whole file is returned as `DOCUMENTATION`.
+pub fn extract_role_doc_blocks(source: &str) -> Result<DocBlocks, String> {
+    Ok(DocBlocks {
+        documentation: Some(parse_yaml(source, "argument_specs")?),
+        examples: None,
+        return_values: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_python_doc_blocks() {
+        let blocks = extract_python_doc_blocks(concat!(
+            "#!/usr/bin/python\n",
+            "\n",
+            "DOCUMENTATION = r'''\n",
+            "module: foo\n",
+            "short_description: Do C(foo) with \\d\n",
+            "'''\n",
+            "\n",
+            "EXAMPLES = \"\"\"\n",
+            "- name: It\\'s a \\\"test\\\"\n",
+            "  foo.bar.foo:\n",
+            "\"\"\"\n",
+            "\n",
+            "    RETURN = 'ignored'\n",
+            "RETURN = ''' # '''\n",
+        ))
+        .unwrap();
+        let documentation = blocks.documentation.unwrap();
+        let hash = yaml::as_hash(&documentation, "").unwrap();
+        assert_eq!(
+            yaml::get_string(hash, "short_description", "").unwrap(),
+            Some("Do C(foo) with \\d".to_string())
+        );
+        assert_eq!(
+            blocks.examples,
+            Some("\n- name: It's a \"test\"\n  foo.bar.foo:\n".to_string())
+        );
+        assert_eq!(blocks.return_values, Some(Yaml::Null));
+
+        assert_eq!(
+            extract_python_doc_blocks("x = 1\n").unwrap(),
+            DocBlocks::default()
+        );
+        assert_eq!(
+            extract_python_doc_blocks("DOCUMENTATION = '''\nfoo: bar\n").unwrap_err(),
+            "DOCUMENTATION: unterminated string literal"
+        );
+        assert_eq!(
+            extract_python_doc_blocks("DOCUMENTATION = get_docs()\n").unwrap_err(),
+            "DOCUMENTATION: expected a string literal"
+        );
+    }
+
+    #[test]
+    fn test_extract_yaml_doc_blocks() {
+        let blocks = extract_yaml_doc_blocks(concat!(
+            "DOCUMENTATION:\n",
+            "  name: foo\n",
+            "EXAMPLES: |\n",
+            "  x: '{{ y | foo }}'\n",
+        ))
+        .unwrap();
+        assert!(blocks.documentation.is_some());
+        assert_eq!(blocks.examples, Some("x: '{{ y | foo }}'\n".to_string()));
+        assert_eq!(blocks.return_values, None);
+        assert!(extract_yaml_doc_blocks("- foo").is_err());
+    }
+}
@@ -0,0 +1,340 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Field-specific restrictions of the markup in plugin documentation.
+
+use crate::markup::{
+    parse_paragraphs_without_sources, Context, LintFinding, LintReport, ParseOptions, Part,
+};
+use crate::plugin_docs::extract::DocBlocks;
+use crate::util::yaml::collect_fields;
+use saphyr::Yaml;
+use std::collections::BTreeMap;
+
+/// What is allowed in the markup of a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldRule {
+    /// Whether markup commands are allowed at all.
+    pub markup: bool,
+
+    /// Whether semantic markup (`E()`, `O()`, `P()`, `RV()`, and `V()`) is allowed.
+    pub semantic_markup: bool,
+
+    /// Whether URLs are allowed, both as `U()` and `L()` and as plain text.
+    pub urls: bool,
+}
+
+impl FieldRule {
+    /// Allow everything.
+    pub fn new() -> FieldRule {
+        FieldRule {
+            markup: true,
+            semantic_markup: true,
+            urls: true,
+        }
+    }
+
+    /// Set whether markup commands are allowed.
+    pub fn markup(mut self, markup: bool) -> Self {
+        self.markup = markup;
+        self
+    }
+
+    /// Set whether semantic markup is allowed.
+    pub fn semantic_markup(mut self, semantic_markup: bool) -> Self {
+        self.semantic_markup = semantic_markup;
+        self
+    }
+
+    /// Set whether URLs are allowed.
+    pub fn urls(mut self, urls: bool) -> Self {
+        self.urls = urls;
+        self
+    }
+}
+
+impl Default for FieldRule {
+    fn default() -> Self {
+        FieldRule::new()
+    }
+}
+
+/// The rules for the fields of plugin documentation, by key.
+///
+/// Fields are found by their key anywhere in the documentation, so the rule for `description`
+/// also applies to the descriptions of options and of `seealso` entries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldRules {
+    rules: BTreeMap<String, FieldRule>,
+}
+
+impl FieldRules {
+    /// The rules of antsibull-docs: markup is allowed in `description`, `notes`, `why`, and
+    /// `alternative`, but no semantic markup in `short_description`, and neither markup nor
+    /// URLs in `author`.
+    pub fn new() -> FieldRules {
+        FieldRules::empty()
+            .rule("description", FieldRule::new())
+            .rule("notes", FieldRule::new())
+            .rule("why", FieldRule::new())
+            .rule("alternative", FieldRule::new())
+            .rule("short_description", FieldRule::new().semantic_markup(false))
+            .rule("author", FieldRule::new().markup(false).urls(false))
+    }
+
+    /// Create rules without fields.
+    pub fn empty() -> FieldRules {
+        FieldRules {
+            rules: BTreeMap::new(),
+        }
+    }
+
+    /// Set the rule for the fields with key `key`.
+    pub fn rule(mut self, key: &str, rule: FieldRule) -> Self {
+        self.rules.insert(key.to_string(), rule);
+        self
+    }
+
+    /// Return the rule for the fields with key `key`, or `None` if they are not checked.
+    pub fn get(&self, key: &str) -> Option<&FieldRule> {
+        self.rules.get(key)
+    }
+}
+
+impl Default for FieldRules {
+    fn default() -> Self {
+        FieldRules::new()
+    }
+}
+
+/// Return the byte ranges of plain URLs in `text`.
+fn find_urls(text: &str) -> Vec<(usize, usize)> {
+    let mut result = Vec::new();
+    let mut offset = 0;
+    while let Some(index) = text[offset..].find("://") {
+        let separator = offset + index;
+        let start = text[..separator]
+            .rfind(|c: char| !c.is_ascii_alphanumeric() && !matches!(c, '+' | '-' | '.'))
+            .map(|index| index + 1)
+            .unwrap_or(0);
+        let end = text[separator..]
+            .find(|c: char| c.is_whitespace() || matches!(c, ')' | '>' | '"'))
+            .map(|index| separator + index)
+            .unwrap_or(text.len());
+        let end = separator
+            + text[separator..end]
+                .trim_end_matches(['.', ',', ';', ':', '!', '?'])
+                .len();
+        if start < separator {
+            result.push((start, end));
+        }
+        offset = end.max(separator + 3);
+    }
+    result
+}
+
+fn check_part(
+    key: &str,
+    rule: &FieldRule,
+    part: &Part<'_>,
+    paragraph: usize,
+    index: usize,
+    findings: &mut Vec<LintFinding>,
+) {
+    let mut finding = |code: &'static str, message: String, start: usize, end: usize| {
+        findings.push(LintFinding {
+            code,
+            message,
+            paragraph,
+            part: index,
+            start,
+            end,
+            fix: None,
+        })
+    };
+    match part {
+        Part::Error { message } => finding("markup-error", message.clone(), 0, 0),
+        Part::Text { text } if !rule.urls => {
+            for (start, end) in find_urls(text) {
+                finding(
+                    "url-not-allowed",
+                    format!("URLs are not allowed in {}", key),
+                    start,
+                    end,
+                );
+            }
+        }
+        Part::Text { .. } => {}
+        _ if !rule.markup => finding(
+            "markup-not-allowed",
+            format!("Markup is not allowed in {}", key),
+            0,
+            0,
+        ),
+        Part::URL { .. } | Part::Link { .. } if !rule.urls => finding(
+            "url-not-allowed",
+            format!("URLs are not allowed in {}", key),
+            0,
+            0,
+        ),
+        Part::OptionName { .. }
+        | Part::OptionValue { .. }
+        | Part::EnvVariable { .. }
+        | Part::ReturnValue { .. }
+        | Part::Plugin { .. }
+            if !rule.semantic_markup =>
+        {
+            finding(
+                "semantic-markup-not-allowed",
+                format!("Semantic markup is not allowed in {}", key),
+                0,
+                0,
+            )
+        }
+        _ => {}
+    }
+}
+
+/// Parse and check every field of a documentation block that has a rule in `rules`.
+///
+/// The findings are added to `report` for `file`, with YAML paths starting with `block`, like
+/// `DOCUMENTATION.options.state.description`. Parsing errors are reported as `markup-error`,
+/// forbidden markup as `markup-not-allowed`, `semantic-markup-not-allowed`, and
+/// `url-not-allowed`.
+pub fn validate_doc_fields(
+    report: &mut LintReport,
+    file: &str,
+    block: &str,
+    value: &Yaml,
+    rules: &FieldRules,
+    context: &Context,
+    opts: &ParseOptions,
+) {
+    let keys: Vec<&str> = rules.rules.keys().map(String::as_str).collect();
+    let mut fields = Vec::new();
+    collect_fields(value, "", &keys, &mut fields);
+    for field in &fields {
+        let Some((path, key, _)) = field.first() else {
+            continue;
+        };
+        let Some(rule) = rules.get(key) else {
+            continue;
+        };
+        let paragraphs = parse_paragraphs_without_sources(
+            field.iter().map(|(_, _, text)| text.as_str()),
+            context,
+            opts,
+        );
+        let mut findings = Vec::new();
+        for (paragraph, parts) in paragraphs.iter().enumerate() {
+            for (index, part) in parts.iter().enumerate() {
+                check_part(key, rule, part, paragraph, index, &mut findings);
+            }
+        }
+        if !findings.is_empty() {
+            let path = path.strip_suffix("[0]").unwrap_or(path);
+            report.add(file, &format!("{}.{}", block, path), findings);
+        }
+    }
+}
+
+/// Check the `DOCUMENTATION` and `RETURN` blocks of a plugin with [`validate_doc_fields`].
+pub fn validate_doc_blocks(
+    report: &mut LintReport,
+    file: &str,
+    blocks: &DocBlocks,
+    rules: &FieldRules,
+    context: &Context,
+    opts: &ParseOptions,
+) {
+    for (block, value) in [
+        ("DOCUMENTATION", &blocks.documentation),
+        ("RETURN", &blocks.return_values),
+    ] {
+        if let Some(value) = value {
+            validate_doc_fields(report, file, block, value, rules, context, opts);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_doc_blocks() {
+        let documentation = Yaml::load_from_str(concat!(
+            "short_description: Set O(state)\n",
+            "description:\n",
+            "  - Set O(state).\n",
+            "  - See B(foo\n",
+            "author:\n",
+            "  - Foo Bar (@foo) https://example.com/foo\n",
+            "  - I(Baz)\n",
+            "seealso:\n",
+            "  - module: foo.bar.baz\n",
+            "    description: Uses V(x).\n",
+        ))
+        .unwrap()
+        .swap_remove(0);
+        let blocks = DocBlocks {
+            documentation: Some(documentation),
+            ..DocBlocks::default()
+        };
+        let mut report = LintReport::new();
+        validate_doc_blocks(
+            &mut report,
+            "plugins/modules/foo.py",
+            &blocks,
+            &FieldRules::new(),
+            &Context::new(),
+            &ParseOptions::default(),
+        );
+        let entries: Vec<(&str, &str, usize, usize, usize, usize)> = report
+            .entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.yaml_path.as_str(),
+                    entry.finding.code,
+                    entry.finding.paragraph,
+                    entry.finding.part,
+                    entry.finding.start,
+                    entry.finding.end,
+                )
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (
+                    "DOCUMENTATION.short_description",
+                    "semantic-markup-not-allowed",
+                    0,
+                    1,
+                    0,
+                    0
+                ),
+                ("DOCUMENTATION.description", "markup-error", 1, 1, 0, 0),
+                ("DOCUMENTATION.author", "url-not-allowed", 0, 0, 15, 38),
+                ("DOCUMENTATION.author", "markup-not-allowed", 1, 0, 0, 0),
+            ]
+        );
+        assert_eq!(
+            report.entries[3].finding.message,
+            "Markup is not allowed in author"
+        );
+    }
+
+    #[test]
+    fn test_find_urls() {
+        assert_eq!(
+            find_urls("See https://a.b/c, or ftp://x)."),
+            vec![(4, 17), (22, 29)]
+        );
+        assert_eq!(find_urls("a :// b"), vec![]);
+    }
+}
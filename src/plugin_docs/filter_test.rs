@@ -0,0 +1,253 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! The documentation layout of filter and test plugins.
+//!
+//! Filters and tests are called like `input | name(positional, keyword=value)` and
+//! `input is name(positional, keyword=value)`. Their documentation describes the input as the
+//! option `_input`, lists the positional parameters in `positional`, and describes the result
+//! as the return value `_value`.
+
+use crate::markup::{Context, ParseOptions};
+use crate::plugin_docs::options::{load_options, options_table, PluginOption};
+use crate::plugin_docs::return_values::ReturnValue;
+use crate::plugin_docs::table::Table;
+use crate::plugin_docs::types::TypeLocalizer;
+use crate::util::yaml;
+use saphyr::Yaml;
+
+/// The name of the option that documents the input of a filter or test.
+pub const FILTER_TEST_INPUT: &str = "_input";
+
+/// The name of the return value that documents the result of a filter or test.
+pub const FILTER_TEST_VALUE: &str = "_value";
+
+/// The options of a filter or test, split by how they are passed.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FilterTestOptions {
+    /// The input, which is passed before `|` or `is`.
+    pub input: Option<PluginOption>,
+
+    /// The positional parameters, in the order they are passed.
+    pub positional: Vec<PluginOption>,
+
+    /// The keyword parameters, sorted by name.
+    pub keyword: Vec<PluginOption>,
+}
+
+/// Load the options of a filter or test from its parsed `DOCUMENTATION` block.
+///
+/// `positional` can be a comma-separated string or a list of names. It may start with
+/// `_input`, like ansible-core's filters do; the input is never a positional parameter.
+/// Fails if `positional` names an option that is not documented.
+pub fn load_filter_test_options(documentation: &Yaml) -> Result<FilterTestOptions, String> {
+    let hash = yaml::as_hash(documentation, "DOCUMENTATION")?;
+    let mut options = match yaml::get(hash, "options") {
+        Some(options) => load_options(options)?,
+        None => Vec::new(),
+    };
+    let take = |options: &mut Vec<PluginOption>, name: &str| {
+        let index = options.iter().position(|option| option.name == name)?;
+        Some(options.remove(index))
+    };
+    let input = take(&mut options, FILTER_TEST_INPUT);
+    let mut positional = Vec::new();
+    for entry in yaml::get_string_list(hash, "positional", "DOCUMENTATION")? {
+        for name in entry.split(',').map(str::trim) {
+            if name.is_empty() || name == FILTER_TEST_INPUT {
+                continue;
+            }
+            let option = take(&mut options, name)
+                .ok_or_else(|| format!("DOCUMENTATION.positional: unknown option {}", name))?;
+            positional.push(option);
+        }
+    }
+    Ok(FilterTestOptions {
+        input,
+        positional,
+        keyword: options,
+    })
+}
+
+impl FilterTestOptions {
+    /// Show how the filter or test `fqcn` is called, like
+    /// `_input | foo.bar.baz(first, second, key=key)`.
+    ///
+    /// `plugin_type` is `filter` or `test`. Keyword parameters are only shown if they are
+    /// required.
+    pub fn usage(&self, fqcn: &str, plugin_type: &str) -> String {
+        let input = self
+            .input
+            .as_ref()
+            .map(|option| option.name.as_str())
+            .unwrap_or(FILTER_TEST_INPUT);
+        let operator = if plugin_type == "test" { "is" } else { "|" };
+        let mut arguments: Vec<String> = self
+            .positional
+            .iter()
+            .map(|option| option.name.clone())
+            .collect();
+        arguments.extend(
+            self.keyword
+                .iter()
+                .filter(|option| option.required)
+                .map(|option| format!("{}={}", option.name, option.name)),
+        );
+        if arguments.is_empty() {
+            format!("{} {} {}", input, operator, fqcn)
+        } else {
+            format!("{} {} {}({})", input, operator, fqcn, arguments.join(", "))
+        }
+    }
+
+    /// Create the tables of the input, the positional parameters, and the keyword parameters,
+    /// leaving out empty ones.
+    ///
+    /// The tables are created like with [`options_table`], so the anchors are the same as for
+    /// the options of other plugins.
+    pub fn tables<'a>(
+        &'a self,
+        context: &'a Context,
+        opts: &ParseOptions,
+        localizer: &dyn TypeLocalizer,
+    ) -> Vec<Table<'a>> {
+        [
+            ("Input", self.input.as_slice()),
+            ("Positional parameter", self.positional.as_slice()),
+            ("Keyword parameter", self.keyword.as_slice()),
+        ]
+        .into_iter()
+        .filter(|(_, options)| !options.is_empty())
+        .map(|(key_header, options)| Table {
+            key_header,
+            ..options_table(options, context, opts, localizer)
+        })
+        .collect()
+    }
+}
+
+/// Find the return value that describes the result of a filter or test.
+///
+/// Use [`return_values_table`](crate::plugin_docs::return_values_table) with
+/// `std::slice::from_ref()` to show it.
+pub fn filter_test_return_value(values: &[ReturnValue]) -> Option<&ReturnValue> {
+    values.iter().find(|value| value.name == FILTER_TEST_VALUE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::NoLinkProvider;
+    use crate::plugin_docs::{
+        append_ansible_doc_text_table, extract_yaml_doc_blocks, load_return_values,
+        DefaultTypeLocalizer,
+    };
+    use crate::util::stringbuilder::{CollectorAppender, IntoString};
+
+    const SIDECAR: &str = r#"
+DOCUMENTATION:
+  name: regex_replace
+  short_description: Replace a string via regex
+  positional: _input, _regex_match, _regex_replace
+  options:
+    _input:
+      description: String to modify.
+      type: str
+      required: true
+    _regex_match:
+      description: Regular expression to search for.
+      type: str
+      required: true
+    _regex_replace:
+      description: Replacement.
+      type: str
+      required: true
+    multiline:
+      description: Search across lines.
+      type: bool
+    count:
+      description: Maximum number of replacements.
+      type: int
+      required: true
+RETURN:
+  _value:
+    description: String with the replacements.
+    type: str
+"#;
+
+    #[test]
+    fn test_load_filter_test_options() {
+        let blocks = extract_yaml_doc_blocks(SIDECAR).unwrap();
+        let options = load_filter_test_options(blocks.documentation.as_ref().unwrap()).unwrap();
+        assert_eq!(options.input.as_ref().unwrap().name, "_input");
+        assert_eq!(
+            options
+                .positional
+                .iter()
+                .map(|option| option.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["_regex_match", "_regex_replace"]
+        );
+        assert_eq!(
+            options
+                .keyword
+                .iter()
+                .map(|option| option.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["count", "multiline"]
+        );
+        assert_eq!(
+            options.usage("ansible.builtin.regex_replace", "filter"),
+            "_input | ansible.builtin.regex_replace(_regex_match, _regex_replace, count=count)"
+        );
+        assert_eq!(
+            FilterTestOptions::default().usage("foo.bar.truthy", "test"),
+            "_input is foo.bar.truthy"
+        );
+
+        let context = Context::new();
+        let tables = options.tables(
+            &context,
+            &ParseOptions::default(),
+            &DefaultTypeLocalizer::new(),
+        );
+        assert_eq!(
+            tables
+                .iter()
+                .map(|table| (table.key_header, table.rows.len()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Input", 1),
+                ("Positional parameter", 2),
+                ("Keyword parameter", 2)
+            ]
+        );
+        assert_eq!(tables[1].rows[0].anchors, vec!["parameter-_regex_match"]);
+
+        let values = load_return_values(blocks.return_values.as_ref().unwrap()).unwrap();
+        let value = filter_test_return_value(&values).unwrap();
+        let table = crate::plugin_docs::return_values_table(
+            std::slice::from_ref(value),
+            &context,
+            &ParseOptions::default(),
+            &DefaultTypeLocalizer::new(),
+        );
+        let mut appender = CollectorAppender::new();
+        append_ansible_doc_text_table(&mut appender, &table, &NoLinkProvider::new(), &None);
+        assert_eq!(
+            appender.into_string(),
+            "- _value\n    String with the replacements.\n    type: string"
+        );
+
+        let documentation = Yaml::load_from_str("positional: [foo]\noptions: {}\n")
+            .unwrap()
+            .swap_remove(0);
+        assert_eq!(
+            load_filter_test_options(&documentation).unwrap_err(),
+            "DOCUMENTATION.positional: unknown option foo"
+        );
+    }
+}
@@ -0,0 +1,323 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Merging of documentation fragments (`extends_documentation_fragment`).
+
+use crate::plugin_docs::extract::{find_indented_string_assignment, parse_yaml};
+use crate::util::yaml;
+use saphyr::{Hash, Yaml};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Provides the documentation fragments that plugins can extend.
+pub trait DocFragmentProvider {
+    /// Return the parsed variable `variable`, like `DOCUMENTATION`, of the fragment `name`, like
+    /// `community.general.proxmox`. Returns `None` if the fragment or the variable does not exist.
+    fn fragment(&self, name: &str, variable: &str) -> Option<Yaml>;
+}
+
+/// Documentation fragments kept in memory.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DocFragments {
+    fragments: BTreeMap<(String, String), Yaml>,
+}
+
+impl DocFragments {
+    pub fn new() -> DocFragments {
+        DocFragments::default()
+    }
+
+    /// Add the variable `variable` of the fragment `name`.
+    pub fn add(&mut self, name: &str, variable: &str, value: Yaml) {
+        self.fragments
+            .insert((name.to_string(), variable.to_string()), value);
+    }
+
+    /// Add all variables of a fragment from the source of its Python file.
+    ///
+    /// Every string literal assigned to an upper-case name, like the `DOCUMENTATION` attribute of
+    /// the `ModuleDocFragment` class, is parsed as YAML.
+    pub fn add_python_source(&mut self, name: &str, source: &str) -> Result<(), String> {
+        let mut variables = BTreeSet::new();
+        for line in source.lines() {
+            let line = line.trim_start();
+            let end = line
+                .find(|c: char| !(c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_'))
+                .unwrap_or(line.len());
+            let (variable, rest) = line.split_at(end);
+            let Some(value) = rest.trim_start().strip_prefix('=') else {
+                continue;
+            };
+            let value = value.trim_start().trim_start_matches(['r', 'R', 'u', 'U']);
+            if variable.starts_with(|c: char| c.is_ascii_uppercase())
+                && value.starts_with(['\'', '"'])
+            {
+                variables.insert(variable);
+            }
+        }
+        for variable in variables {
+            let Some(literal) = find_indented_string_assignment(source, variable)
+                .map_err(|error| format!("{}: {}", name, error))?
+            else {
+                continue;
+            };
+            let value = parse_yaml(&literal.value(source), &format!("{}.{}", name, variable))?;
+            self.add(name, variable, value);
+        }
+        Ok(())
+    }
+}
+
+impl DocFragmentProvider for DocFragments {
+    fn fragment(&self, name: &str, variable: &str) -> Option<Yaml> {
+        self.fragments
+            .get(&(name.to_string(), variable.to_string()))
+            .cloned()
+    }
+}
+
+/// Look up a fragment like ansible-core: first `name` with the variable `DOCUMENTATION`, then
+/// `name` without its last component, with that component in upper case as the variable.
+fn lookup_fragment(provider: &dyn DocFragmentProvider, name: &str) -> Option<Yaml> {
+    provider.fragment(name, "DOCUMENTATION").or_else(|| {
+        let (name, variable) = name.rsplit_once('.')?;
+        provider.fragment(name, &variable.to_uppercase())
+    })
+}
+
+fn key(name: &str) -> Yaml {
+    Yaml::String(name.to_string())
+}
+
+/// Merge `source` into `target` like ansible-core's `merge_fragment()`.
+///
+/// Mappings are merged with the values of `target` taking precedence, and lists are merged
+/// into a sorted list without duplicates. Other values of `target` cannot be extended.
+fn merge_fragment(target: &mut Hash, source: Hash) -> Result<(), String> {
+    for (name, value) in source {
+        let Some(existing) = target.get_mut(&name) else {
+            target.insert(name, value);
+            continue;
+        };
+        let merged = match (&*existing, value) {
+            (Yaml::Hash(existing), Yaml::Hash(mut value)) => {
+                for (key, entry) in existing {
+                    value.insert(key.clone(), entry.clone());
+                }
+                Yaml::Hash(value)
+            }
+            (Yaml::Array(existing), Yaml::Array(value)) => {
+                let entries: BTreeSet<Yaml> = value.into_iter().chain(existing.clone()).collect();
+                Yaml::Array(entries.into_iter().collect())
+            }
+            _ => {
+                return Err(format!(
+                    "Attempt to extend a documentation fragment, invalid type for {}",
+                    yaml::scalar_to_string(&name).unwrap_or_default()
+                ))
+            }
+        };
+        *existing = merged;
+    }
+    Ok(())
+}
+
+/// Append the list `key` of `fragment` to the list `key` of `doc`.
+fn extend_list(doc: &mut Hash, fragment: &mut Hash, name: &str) -> Result<(), String> {
+    let entries = match fragment.remove(&key(name)) {
+        None | Some(Yaml::Null) => return Ok(()),
+        Some(Yaml::Array(entries)) => entries,
+        Some(_) => return Err(format!("{}: expected a list", name)),
+    };
+    if entries.is_empty() {
+        return Ok(());
+    }
+    match doc.get_mut(&key(name)) {
+        Some(Yaml::Array(existing)) => existing.extend(entries),
+        Some(value @ Yaml::Null) => *value = Yaml::Array(entries),
+        Some(_) => return Err(format!("{}: expected a list", name)),
+        None => {
+            doc.insert(key(name), Yaml::Array(entries));
+        }
+    }
+    Ok(())
+}
+
+/// Merge the fragments listed in `extends_documentation_fragment` into the parsed
+/// `DOCUMENTATION` block `documentation`, like ansible-core does.
+///
+/// The `notes` and `seealso` of fragments are appended to the plugin's. The `options` and
+/// `attributes` are merged, with the plugin's taking precedence over the fragment's; the
+/// other fields are merged the same way, with lists like `requirements` merged into sorted
+/// lists without duplicates. `extends_documentation_fragment` is removed afterwards.
+///
+/// Fails for unknown fragments, fragments without `options` and `attributes`, and fields that
+/// cannot be merged.
+pub fn merge_doc_fragments(
+    documentation: &mut Yaml,
+    provider: &dyn DocFragmentProvider,
+) -> Result<(), String> {
+    let Yaml::Hash(doc) = documentation else {
+        return Err("DOCUMENTATION: expected a mapping".to_string());
+    };
+    let names = match doc.remove(&key("extends_documentation_fragment")) {
+        None | Some(Yaml::Null) => Vec::new(),
+        Some(Yaml::Array(names)) => names
+            .iter()
+            .map(|name| {
+                yaml::scalar_to_string(name).ok_or_else(|| {
+                    "extends_documentation_fragment: expected a list of strings".to_string()
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        Some(name) => vec![yaml::scalar_to_string(&name).ok_or_else(|| {
+            "extends_documentation_fragment: expected a string or a list of strings".to_string()
+        })?],
+    };
+    let mut unknown = Vec::new();
+    for name in &names {
+        let mut fragment = match lookup_fragment(provider, name) {
+            None => {
+                unknown.push(name.as_str());
+                continue;
+            }
+            Some(Yaml::Null) => Hash::new(),
+            Some(Yaml::Hash(fragment)) => fragment,
+            Some(_) => return Err(format!("{}: expected a mapping", name)),
+        };
+        let with_name = |error: String| format!("{} ({})", error, name);
+        extend_list(doc, &mut fragment, "notes").map_err(with_name)?;
+        extend_list(doc, &mut fragment, "seealso").map_err(with_name)?;
+        if !fragment.contains_key(&key("options")) && !fragment.contains_key(&key("attributes")) {
+            return Err(format!(
+                "missing options or attributes in fragment ({}), possibly misformatted?",
+                name
+            ));
+        }
+        for field in ["options", "attributes"] {
+            let Some(value) = fragment.remove(&key(field)) else {
+                continue;
+            };
+            match (doc.get_mut(&key(field)), value) {
+                (Some(Yaml::Hash(existing)), Yaml::Hash(value)) => {
+                    let mut merged = existing.clone();
+                    merge_fragment(&mut merged, value).map_err(with_name)?;
+                    *existing = merged;
+                }
+                (Some(Yaml::Null) | None, value) => {
+                    doc.insert(key(field), value);
+                }
+                _ => return Err(with_name(format!("{} of unknown type", field))),
+            }
+        }
+        merge_fragment(doc, fragment).map_err(with_name)?;
+    }
+    if !unknown.is_empty() {
+        return Err(format!("unknown doc_fragment(s): {}", unknown.join(", ")));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use saphyr::YamlEmitter;
+
+    fn dump(value: &Yaml) -> String {
+        let mut result = String::new();
+        YamlEmitter::new(&mut result).dump(value).unwrap();
+        result
+    }
+
+    #[test]
+    fn test_merge_doc_fragments() {
+        let mut fragments = DocFragments::new();
+        fragments
+            .add_python_source(
+                "foo.bar.auth",
+                concat!(
+                    "class ModuleDocFragment(object):\n",
+                    "    VERSION = 2\n",
+                    "    DOCUMENTATION = r'''\n",
+                    "options:\n",
+                    "  user:\n",
+                    "    description: The user.\n",
+                    "  state:\n",
+                    "    description: Ignored.\n",
+                    "notes:\n",
+                    "  - Needs a login.\n",
+                    "requirements:\n",
+                    "  - requests\n",
+                    "  - foo\n",
+                    "'''\n",
+                    "\n",
+                    "    TOKEN = r'''\n",
+                    "options:\n",
+                    "  token:\n",
+                    "    description: The token.\n",
+                    "'''\n",
+                ),
+            )
+            .unwrap();
+        let mut documentation = parse_yaml(
+            concat!(
+                "module: baz\n",
+                "extends_documentation_fragment:\n",
+                "  - foo.bar.auth\n",
+                "  - foo.bar.auth.token\n",
+                "notes:\n",
+                "  - Own note.\n",
+                "requirements:\n",
+                "  - foo\n",
+                "options:\n",
+                "  state:\n",
+                "    description: The state.\n",
+            ),
+            "DOCUMENTATION",
+        )
+        .unwrap();
+        merge_doc_fragments(&mut documentation, &fragments).unwrap();
+        assert_eq!(
+            dump(&documentation),
+            concat!(
+                "---\n",
+                "module: baz\n",
+                "notes:\n",
+                "  - Own note.\n",
+                "  - Needs a login.\n",
+                "requirements:\n",
+                "  - foo\n",
+                "  - requests\n",
+                "options:\n",
+                "  state:\n",
+                "    description: The state.\n",
+                "  user:\n",
+                "    description: The user.\n",
+                "  token:\n",
+                "    description: The token.",
+            )
+        );
+
+        let mut documentation = parse_yaml(
+            "extends_documentation_fragment: [a.b.c, foo.bar.auth.other]",
+            "",
+        )
+        .unwrap();
+        assert_eq!(
+            merge_doc_fragments(&mut documentation, &fragments),
+            Err("unknown doc_fragment(s): a.b.c, foo.bar.auth.other".to_string())
+        );
+
+        let mut documentation = parse_yaml(
+            "extends_documentation_fragment: foo.bar.auth\nrequirements: requests\n",
+            "",
+        )
+        .unwrap();
+        assert_eq!(
+            merge_doc_fragments(&mut documentation, &fragments),
+            Err("Attempt to extend a documentation fragment, invalid type for requirements (foo.bar.auth)".to_string())
+        );
+    }
+}
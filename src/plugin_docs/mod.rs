@@ -0,0 +1,89 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Models for plugin and module documentation, and their rendering as tables.
+
+mod anchors;
+mod configuration;
+mod extract;
+mod field_rules;
+mod filter_test;
+mod fragments;
+mod options;
+mod return_values;
+mod rewrite;
+mod schema;
+mod sections;
+mod table;
+mod tree;
+mod types;
+mod value_format;
+mod version_added;
+
+pub use anchors::{AnchorRegistry, AnchorRegistryLinkProvider};
+
+pub use configuration::{
+    append_ansible_doc_text_configuration, append_antsibull_html_configuration,
+    append_gfm_configuration, append_plain_html_configuration, append_rst_configuration, CliEntry,
+    IniEntry, OptionConfiguration,
+};
+
+pub use extract::{
+    extract_python_doc_blocks, extract_role_doc_blocks, extract_yaml_doc_blocks,
+    find_string_assignment, DocBlocks, PythonStringLiteral,
+};
+
+pub use field_rules::{validate_doc_blocks, validate_doc_fields, FieldRule, FieldRules};
+pub use filter_test::{
+    filter_test_return_value, load_filter_test_options, FilterTestOptions, FILTER_TEST_INPUT,
+    FILTER_TEST_VALUE,
+};
+
+pub use fragments::{merge_doc_fragments, DocFragmentProvider, DocFragments};
+
+pub use options::{
+    add_option_aliases, flatten_options, load_options, option_anchor, options_table, FlatOption,
+    PluginOption, OPTION_ANCHOR_PREFIX,
+};
+
+pub use return_values::{
+    flatten_return_values, load_return_values, load_return_values_from_str, return_values_table,
+    FlatReturnValue, ReturnValue,
+};
+
+pub use rewrite::{replace_python_doc_block, replace_python_doc_scalar, update_python_doc_block};
+
+pub use schema::{
+    plugin_docs_json_schema, DocField, DocFieldType, DOCUMENTATION_FIELDS, OPTION_FIELDS,
+    PLUGIN_DOCS_SCHEMA_ID, RETURN_VALUE_FIELDS,
+};
+
+pub use sections::{
+    callback_type_blocks, connection_variables_table, requirements_blocks,
+    split_inventory_cache_options, INVENTORY_CACHE_OPTIONS, VARIABLE_ANCHOR_PREFIX,
+};
+
+pub use table::{
+    append_ansible_doc_text_table, append_gfm_table, append_html_table, append_md_table,
+    append_rst_table, Table, TableRow,
+};
+
+pub use tree::{anchor_id, flatten_tree, DocTreeNode, FlatNode};
+
+pub use types::{
+    display_type, is_boolean_type, normalize_type_name, DefaultTypeLocalizer, TypeLocalizer,
+};
+
+pub use value_format::{
+    append_ansible_doc_text_option_values, append_antsibull_html_option_values,
+    append_gfm_option_values, append_plain_html_option_values, append_rst_option_values,
+    format_value, Choice, DefaultValue, OptionValues,
+};
+
+pub use version_added::{
+    append_ansible_doc_text_version_added, append_html_version_added, append_rst_version_added,
+    VersionAdded, ANSIBLE_CORE, HISTORICAL,
+};
@@ -0,0 +1,369 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::{
+    parse_without_sources, Context, OptionAliases, ParseOptions, PluginIdentifier,
+};
+use crate::plugin_docs::configuration::OptionConfiguration;
+use crate::plugin_docs::table::{Table, TableRow};
+use crate::plugin_docs::tree::{flatten_tree, DocTreeNode, FlatNode};
+use crate::plugin_docs::types::{display_type, TypeLocalizer};
+use crate::plugin_docs::value_format::OptionValues;
+use crate::plugin_docs::version_added::VersionAdded;
+use crate::util::yaml;
+use saphyr::Yaml;
+
+/// The prefix of anchor IDs for options.
+pub const OPTION_ANCHOR_PREFIX: &str = "parameter";
+
+/// An option from a plugin's or module's DOCUMENTATION.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginOption {
+    /// The name of the option.
+    pub name: String,
+
+    /// The description paragraphs, in Ansible markup.
+    pub description: Vec<String>,
+
+    /// The type of the option, like `str` or `list`.
+    pub r#type: Option<String>,
+
+    /// The type of the elements, if the option is a list.
+    pub elements: Option<String>,
+
+    /// Whether the option is required.
+    pub required: bool,
+
+    /// The default value.
+    pub default: Option<Yaml>,
+
+    /// The allowed values. This is either a list of values, or a mapping of values to descriptions.
+    pub choices: Option<Yaml>,
+
+    /// Other names of the option.
+    pub aliases: Vec<String>,
+
+    /// The version in which the option was added.
+    pub version_added: Option<String>,
+
+    /// The collection the `version_added` refers to.
+    pub version_added_collection: Option<String>,
+
+    /// Suboptions, if the option is a dictionary or a list of dictionaries.
+    pub suboptions: Vec<PluginOption>,
+
+    /// How the option can be configured besides the plugin's own arguments, like for connection
+    /// plugins.
+    pub configuration: OptionConfiguration,
+}
+
+impl PluginOption {
+    fn load(name: &str, value: &Yaml, path: &str) -> Result<PluginOption, String> {
+        let hash = yaml::as_hash(value, path)?;
+        let suboptions = match yaml::get(hash, "suboptions") {
+            None | Some(Yaml::Null) => Vec::new(),
+            Some(suboptions) => load_options_impl(suboptions, &format!("{}.suboptions", path))?,
+        };
+        Ok(PluginOption {
+            name: name.to_string(),
+            description: yaml::get_string_list(hash, "description", path)?,
+            r#type: yaml::get_string(hash, "type", path)?,
+            elements: yaml::get_string(hash, "elements", path)?,
+            required: yaml::get_bool(hash, "required", path)?.unwrap_or(false),
+            default: yaml::get(hash, "default").cloned(),
+            choices: yaml::get(hash, "choices").cloned(),
+            aliases: yaml::get_string_list(hash, "aliases", path)?,
+            version_added: yaml::get_string(hash, "version_added", path)?,
+            version_added_collection: yaml::get_string(hash, "version_added_collection", path)?,
+            suboptions,
+            configuration: OptionConfiguration::load(hash, path)?,
+        })
+    }
+}
+
+impl DocTreeNode for PluginOption {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    fn children(&self) -> &[PluginOption] {
+        &self.suboptions
+    }
+}
+
+fn load_options_impl(value: &Yaml, path: &str) -> Result<Vec<PluginOption>, String> {
+    let hash = yaml::as_hash(value, path)?;
+    let mut result = hash
+        .iter()
+        .map(|(key, value)| match yaml::scalar_to_string(key) {
+            Some(name) => PluginOption::load(&name, value, &format!("{}.{}", path, name)),
+            None => Err(format!("{}: keys must be strings", path)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    result.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(result)
+}
+
+/// Load options from the parsed YAML of the `options` entry of a DOCUMENTATION block.
+///
+/// The options are sorted by name on every level. A missing value results in no options.
+pub fn load_options(value: &Yaml) -> Result<Vec<PluginOption>, String> {
+    match value {
+        Yaml::Null | Yaml::BadValue => Ok(Vec::new()),
+        _ => load_options_impl(value, "options"),
+    }
+}
+
+/// An option together with its position in the tree of options.
+pub type FlatOption<'a> = FlatNode<'a, PluginOption>;
+
+/// Flatten a tree of options, so that every option is followed by its suboptions.
+///
+/// Besides the depth and the path, every entry also knows all paths that can be used to reference
+/// it through aliases of itself and its parents.
+pub fn flatten_options(options: &[PluginOption]) -> Vec<FlatOption<'_>> {
+    flatten_tree(options)
+}
+
+fn add_option_aliases_impl(
+    aliases: &mut OptionAliases,
+    plugin: &PluginIdentifier,
+    entrypoint: Option<&str>,
+    options: &[PluginOption],
+    parents: &mut Vec<String>,
+) {
+    for option in options {
+        for alias in &option.aliases {
+            let path: Vec<&str> = parents
+                .iter()
+                .map(String::as_str)
+                .chain([alias.as_str()])
+                .collect();
+            aliases.add_alias(plugin, entrypoint, &path, &option.name);
+        }
+        parents.push(option.name.clone());
+        add_option_aliases_impl(aliases, plugin, entrypoint, &option.suboptions, parents);
+        parents.pop();
+    }
+}
+
+/// Add the aliases of all options and suboptions of a plugin, or of an entrypoint of a role, to
+/// `aliases`.
+///
+/// Put the result into the parsing [`Context`] so that `O()` references that use aliases link to
+/// the canonical options.
+pub fn add_option_aliases(
+    aliases: &mut OptionAliases,
+    plugin: &PluginIdentifier,
+    entrypoint: Option<&str>,
+    options: &[PluginOption],
+) {
+    add_option_aliases_impl(aliases, plugin, entrypoint, options, &mut Vec::new());
+}
+
+/// Compute the anchor ID of an option, like `parameter-foo/bar` for the suboption `bar` of `foo`.
+///
+/// Link providers can use this to link to the anchors generated by `options_table()`.
+pub fn option_anchor(path: &[&str]) -> String {
+    crate::plugin_docs::anchor_id(OPTION_ANCHOR_PREFIX, path)
+}
+
+/// Create a table of all options.
+///
+/// Descriptions are parsed as Ansible markup with `context` and `opts`, and types are displayed with
+/// `localizer`. Options without type are shown as strings, since that is Ansible's default type.
+/// Every row gets one anchor for every path it can be referenced by, the first being the anchor for
+/// the option's main path.
+pub fn options_table<'a>(
+    options: &'a [PluginOption],
+    context: &'a Context,
+    opts: &ParseOptions,
+    localizer: &dyn TypeLocalizer,
+) -> Table<'a> {
+    let rows = flatten_options(options)
+        .into_iter()
+        .map(|flat| {
+            let option = flat.value;
+            TableRow {
+                depth: flat.depth,
+                name: &option.name,
+                aliases: option.aliases.iter().map(|alias| alias.as_str()).collect(),
+                anchors: flat.anchors(OPTION_ANCHOR_PREFIX),
+                path: flat.path,
+                type_info: display_type(
+                    Some(option.r#type.as_deref().unwrap_or("str")),
+                    option.elements.as_deref(),
+                    option.required,
+                    localizer,
+                ),
+                version_added: VersionAdded::new(
+                    option.version_added.as_deref(),
+                    option.version_added_collection.as_deref(),
+                ),
+                description: option
+                    .description
+                    .iter()
+                    .map(|paragraph| parse_without_sources(paragraph, context, opts))
+                    .collect(),
+                values: Some(OptionValues::new(
+                    option.required,
+                    option.default.as_ref(),
+                    option.choices.as_ref(),
+                    option.r#type.as_deref(),
+                    context,
+                    opts,
+                )),
+                details: Vec::new(),
+                configuration: Some(&option.configuration).filter(|c| !c.is_empty()),
+            }
+        })
+        .collect();
+    Table {
+        key_header: "Parameter",
+        description_header: "Comments",
+        rows,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::NoLinkProvider;
+    use crate::plugin_docs::{append_gfm_table, append_html_table, DefaultTypeLocalizer};
+    use crate::util::stringbuilder::{CollectorAppender, IntoString};
+
+    const OPTIONS: &str = r#"
+name:
+  description: The name.
+  type: str
+  required: true
+  aliases: [n]
+config:
+  description: The configuration.
+  type: list
+  elements: dict
+  aliases: [cfg, conf]
+  suboptions:
+    key:
+      description: A key.
+      aliases: [k]
+"#;
+
+    fn load(input: &str) -> Result<Vec<PluginOption>, String> {
+        load_options(&Yaml::load_from_str(input).unwrap()[0])
+    }
+
+    #[test]
+    fn test_flatten_options() {
+        let options = load(OPTIONS).unwrap();
+        let flat = flatten_options(&options);
+        assert_eq!(
+            flat.iter()
+                .map(|f| (f.depth, f.path.join("."), f.anchors(OPTION_ANCHOR_PREFIX)))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    0,
+                    "config".to_string(),
+                    vec![
+                        "parameter-config".to_string(),
+                        "parameter-cfg".to_string(),
+                        "parameter-conf".to_string(),
+                    ]
+                ),
+                (
+                    1,
+                    "config.key".to_string(),
+                    vec![
+                        "parameter-config/key".to_string(),
+                        "parameter-config/k".to_string(),
+                        "parameter-cfg/key".to_string(),
+                        "parameter-cfg/k".to_string(),
+                        "parameter-conf/key".to_string(),
+                        "parameter-conf/k".to_string(),
+                    ]
+                ),
+                (
+                    0,
+                    "name".to_string(),
+                    vec!["parameter-name".to_string(), "parameter-n".to_string()]
+                ),
+            ]
+        );
+        assert_eq!(option_anchor(&["config", "key"]), "parameter-config/key");
+
+        let plugin = PluginIdentifier {
+            fqcn: "foo.bar.baz".to_string(),
+            r#type: "module".to_string(),
+        };
+        let mut aliases = OptionAliases::new();
+        add_option_aliases(&mut aliases, &plugin, None, &options);
+        assert_eq!(
+            aliases,
+            OptionAliases::new()
+                .alias(&plugin, None, &["cfg"], "config")
+                .alias(&plugin, None, &["conf"], "config")
+                .alias(&plugin, None, &["config", "k"], "key")
+                .alias(&plugin, None, &["n"], "name")
+        );
+        assert_eq!(
+            load("foo:\n  required: maybe").unwrap_err(),
+            "options.foo.required: expected a boolean"
+        );
+    }
+
+    #[test]
+    fn test_options_table() {
+        let options = load(OPTIONS).unwrap();
+        let context = Context::new();
+        let table = options_table(
+            &options[1..],
+            &context,
+            &ParseOptions::default(),
+            &DefaultTypeLocalizer::new(),
+        );
+        let mut appender = CollectorAppender::new();
+        append_html_table(&mut appender, &table, &NoLinkProvider::new(), &None);
+        assert_eq!(
+            appender.into_string(),
+            concat!(
+                "<table class=\"ansible-option-table\"><thead><tr><th>Parameter</th><th>Comments</th></tr></thead><tbody>",
+                "<tr><td><div class=\"ansible-option-cell\">",
+                "<div class=\"ansibleOptionAnchor\" id=\"parameter-name\"></div>",
+                "<div class=\"ansibleOptionAnchor\" id=\"parameter-n\"></div>",
+                "<p><strong>name</strong></p><p class=\"ansible-option-aliases\">aliases: n</p>",
+                "<p class=\"ansible-option-type-line\">string / required</p>",
+                "</div></td><td><div class=\"ansible-option-cell\"><p>The name.</p></div></td></tr>",
+                "</tbody></table>",
+            )
+        );
+
+        let table = options_table(
+            &options,
+            &context,
+            &ParseOptions::default(),
+            &DefaultTypeLocalizer::new(),
+        );
+        let mut appender = CollectorAppender::new();
+        append_gfm_table(&mut appender, &table, &NoLinkProvider::new(), &None);
+        assert_eq!(
+            appender.into_string(),
+            concat!(
+                "| Parameter | Comments |\n| --- | --- |\n",
+                "| <a id=\"parameter-config\"></a><a id=\"parameter-cfg\"></a><a id=\"parameter-conf\"></a>",
+                "**config**<br>aliases\\: cfg\\, conf<br>list / elements\\=dictionary | The configuration\\. |\n",
+                "| &nbsp;&nbsp;&nbsp;&nbsp;<a id=\"parameter-config/key\"></a><a id=\"parameter-config/k\"></a>",
+                "<a id=\"parameter-cfg/key\"></a><a id=\"parameter-cfg/k\"></a><a id=\"parameter-conf/key\"></a>",
+                "<a id=\"parameter-conf/k\"></a>**key**<br>aliases\\: k<br>string | A key\\. |\n",
+                "| <a id=\"parameter-name\"></a><a id=\"parameter-n\"></a>**name**<br>aliases\\: n<br>string / required | The name\\. |",
+            )
+        );
+    }
+}
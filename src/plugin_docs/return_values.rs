@@ -0,0 +1,316 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::document::Paragraph;
+use crate::markup::{parse_without_sources, Context, ParseOptions, Part};
+use crate::plugin_docs::table::{Table, TableRow};
+use crate::plugin_docs::tree::{flatten_tree, DocTreeNode, FlatNode};
+use crate::plugin_docs::types::{display_type, TypeLocalizer};
+use crate::plugin_docs::value_format::format_value;
+use crate::plugin_docs::version_added::VersionAdded;
+use crate::util::yaml;
+use saphyr::Yaml;
+
+/// A return value from a plugin's or module's RETURN documentation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReturnValue {
+    /// The name (key) of the return value.
+    pub name: String,
+
+    /// The description paragraphs, in Ansible markup.
+    pub description: Vec<String>,
+
+    /// The type of the return value, like `str` or `list`.
+    pub r#type: Option<String>,
+
+    /// The type of the elements, if the return value is a list.
+    pub elements: Option<String>,
+
+    /// When the value is returned, in Ansible markup.
+    pub returned: Option<String>,
+
+    /// A sample value.
+    pub sample: Option<Yaml>,
+
+    /// The version in which the return value was added.
+    pub version_added: Option<String>,
+
+    /// The collection the `version_added` refers to.
+    pub version_added_collection: Option<String>,
+
+    /// Nested return values, if the return value is a dictionary or a list of dictionaries.
+    pub contains: Vec<ReturnValue>,
+}
+
+impl ReturnValue {
+    fn load(name: &str, value: &Yaml, path: &str) -> Result<ReturnValue, String> {
+        let hash = yaml::as_hash(value, path)?;
+        let contains = match yaml::get(hash, "contains") {
+            None | Some(Yaml::Null) => Vec::new(),
+            Some(contains) => load_return_values_impl(contains, &format!("{}.contains", path))?,
+        };
+        Ok(ReturnValue {
+            name: name.to_string(),
+            description: yaml::get_string_list(hash, "description", path)?,
+            r#type: yaml::get_string(hash, "type", path)?,
+            elements: yaml::get_string(hash, "elements", path)?,
+            returned: yaml::get_string(hash, "returned", path)?,
+            sample: yaml::get(hash, "sample").cloned(),
+            version_added: yaml::get_string(hash, "version_added", path)?,
+            version_added_collection: yaml::get_string(hash, "version_added_collection", path)?,
+            contains,
+        })
+    }
+}
+
+fn load_return_values_impl(value: &Yaml, path: &str) -> Result<Vec<ReturnValue>, String> {
+    let hash = yaml::as_hash(value, path)?;
+    let mut result = hash
+        .iter()
+        .map(|(key, value)| match yaml::scalar_to_string(key) {
+            Some(name) => ReturnValue::load(&name, value, &format!("{}.{}", path, name)),
+            None => Err(format!("{}: keys must be strings", path)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    result.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(result)
+}
+
+/// Load return values from the parsed YAML of a RETURN block.
+///
+/// The return values are sorted by name on every level. An empty document results in no return values.
+pub fn load_return_values(value: &Yaml) -> Result<Vec<ReturnValue>, String> {
+    match value {
+        Yaml::Null | Yaml::BadValue => Ok(Vec::new()),
+        _ => load_return_values_impl(value, "RETURN"),
+    }
+}
+
+/// Parse and load the return values of a RETURN block.
+pub fn load_return_values_from_str(input: &str) -> Result<Vec<ReturnValue>, String> {
+    let documents = Yaml::load_from_str(input).map_err(|e| e.to_string())?;
+    match documents.first() {
+        Some(document) => load_return_values(document),
+        None => Ok(Vec::new()),
+    }
+}
+
+impl DocTreeNode for ReturnValue {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn children(&self) -> &[ReturnValue] {
+        &self.contains
+    }
+}
+
+/// A return value together with its position in the tree of return values.
+pub type FlatReturnValue<'a> = FlatNode<'a, ReturnValue>;
+
+/// Flatten a tree of return values, so that every return value is followed by the values it contains.
+pub fn flatten_return_values(values: &[ReturnValue]) -> Vec<FlatReturnValue<'_>> {
+    flatten_tree(values)
+}
+
+/// Create a table of all return values.
+///
+/// Descriptions and the `returned` information are parsed as Ansible markup with `context` and `opts`,
+/// and types are displayed with `localizer`.
+/// Every row gets an anchor of the form `return-<path>`, with the path components joined by `/`.
+pub fn return_values_table<'a>(
+    values: &'a [ReturnValue],
+    context: &'a Context,
+    opts: &ParseOptions,
+    localizer: &dyn TypeLocalizer,
+) -> Table<'a> {
+    let rows = flatten_return_values(values)
+        .into_iter()
+        .map(|flat| {
+            let value = flat.value;
+            let mut details: Vec<Paragraph<'a>> = Vec::new();
+            if let Some(returned) = &value.returned {
+                let mut paragraph = vec![Part::Text { text: "Returned: " }];
+                paragraph.extend(parse_without_sources(returned, context, opts));
+                details.push(paragraph);
+            }
+            if let Some(sample) = &value.sample {
+                details.push(vec![
+                    Part::Text { text: "Sample: " },
+                    Part::OptionValue {
+                        value: format_value(sample).into(),
+                    },
+                ]);
+            }
+            TableRow {
+                depth: flat.depth,
+                name: &value.name,
+                anchors: flat.anchors("return"),
+                path: flat.path,
+                aliases: Vec::new(),
+                values: None,
+                type_info: display_type(
+                    value.r#type.as_deref(),
+                    value.elements.as_deref(),
+                    false,
+                    localizer,
+                ),
+                version_added: VersionAdded::new(
+                    value.version_added.as_deref(),
+                    value.version_added_collection.as_deref(),
+                ),
+                description: value
+                    .description
+                    .iter()
+                    .map(|paragraph| parse_without_sources(paragraph, context, opts))
+                    .collect(),
+                details,
+                configuration: None,
+            }
+        })
+        .collect();
+    Table {
+        key_header: "Key",
+        description_header: "Description",
+        rows,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::NoLinkProvider;
+    use crate::plugin_docs::{
+        append_ansible_doc_text_table, append_rst_table, DefaultTypeLocalizer,
+    };
+    use crate::util::stringbuilder::{CollectorAppender, IntoString};
+
+    const RETURN: &str = r#"
+msg:
+  description: A message.
+  returned: always
+  type: str
+  sample: Hello
+result:
+  description:
+    - The result.
+    - Contains O(foo).
+  returned: success
+  type: list
+  elements: dict
+  contains:
+    name:
+      description: The name.
+      type: string
+      sample: foo
+    data:
+      description: Some data.
+      type: dict
+      sample: {"a": [1, 2.5, true, null], "b": "x\"y"}
+"#;
+
+    #[test]
+    fn test_load_return_values() {
+        let values = load_return_values_from_str(RETURN).unwrap();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0].name, "msg");
+        assert_eq!(values[0].r#type, Some("str".to_string()));
+        assert_eq!(values[1].description.len(), 2);
+        assert_eq!(values[1].elements, Some("dict".to_string()));
+        assert_eq!(values[1].contains[0].name, "data");
+        assert_eq!(
+            format_value(values[1].contains[0].sample.as_ref().unwrap()),
+            r#"{"a": [1, 2.5, true, null], "b": "x\"y"}"#
+        );
+        assert_eq!(load_return_values_from_str("").unwrap(), vec![]);
+        assert_eq!(
+            load_return_values_from_str("foo: bar").unwrap_err(),
+            "RETURN.foo: expected a mapping"
+        );
+        assert_eq!(
+            load_return_values_from_str("foo:\n  description: [[]]").unwrap_err(),
+            "RETURN.foo.description[0]: expected a string"
+        );
+
+        let flat = flatten_return_values(&values);
+        assert_eq!(
+            flat.iter()
+                .map(|f| (f.depth, f.path.join(".")))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, "msg".to_string()),
+                (0, "result".to_string()),
+                (1, "result.data".to_string()),
+                (1, "result.name".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_return_values() {
+        let values = load_return_values_from_str(RETURN).unwrap();
+        let context = Context::new();
+        let table = return_values_table(
+            &values[..1],
+            &context,
+            &ParseOptions::default(),
+            &DefaultTypeLocalizer::new(),
+        );
+        let mut appender = CollectorAppender::new();
+        append_rst_table(&mut appender, &table, &NoLinkProvider::new(), &None);
+        assert_eq!(
+            appender.into_string(),
+            r#".. list-table::
+  :width: 100%
+  :widths: auto
+  :header-rows: 1
+
+  * - Key
+    - Description
+
+  * - .. _return-msg:
+
+      **msg**
+
+      string
+    - A message.
+
+      Returned: always
+
+      Sample: \ :ansval:`"Hello"`\ "#
+        );
+
+        let table = return_values_table(
+            &values,
+            &context,
+            &ParseOptions::default(),
+            &DefaultTypeLocalizer::new(),
+        );
+        let mut appender = CollectorAppender::new();
+        append_ansible_doc_text_table(&mut appender, &table, &NoLinkProvider::new(), &None);
+        assert_eq!(
+            appender.into_string(),
+            r#"- msg
+    A message.
+    Returned: always
+    Sample: `"Hello"'
+    type: string
+- result
+    The result.
+    Contains `foo'.
+    Returned: success
+    type: list / elements=dictionary
+    - data
+        Some data.
+        Sample: `{"a": [1, 2.5, true, null], "b": "x\"y"}'
+        type: dictionary
+    - name
+        The name.
+        Sample: `"foo"'
+        type: string"#
+        );
+    }
+}
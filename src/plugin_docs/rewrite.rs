@@ -0,0 +1,171 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::plugin_docs::extract::{find_string_assignment, PythonStringLiteral};
+use crate::util::yamledit::{replace_scalar, PathSegment};
+
+/// Encode `value` as content of `literal`, keeping its prefix and quotes.
+fn encode(value: &str, literal: &PythonStringLiteral, name: &str) -> Result<String, String> {
+    let quote_char = literal.quote.chars().next().unwrap_or('\'');
+    let single_line = literal.quote.len() == 1;
+    if literal.raw {
+        let backslashes = value.chars().rev().take_while(|c| *c == '\\').count();
+        if value.contains(literal.quote)
+            || value.ends_with(quote_char)
+            || backslashes % 2 != 0
+            || (single_line && value.contains('\n'))
+        {
+            return Err(format!(
+                "{}: value cannot be written as raw string literal",
+                name
+            ));
+        }
+        return Ok(value.to_string());
+    }
+    let mut result = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\n' if single_line => result.push_str("\\n"),
+            c if c == quote_char => {
+                result.push('\\');
+                result.push(c);
+            }
+            c => result.push(c),
+        }
+    }
+    Ok(result)
+}
+
+/// Replace the value of the string assignment `name = r'''...'''` in Python source.
+///
+/// Only the content of the string literal is replaced. Its prefix and quotes, and all other
+/// code, are kept as they are.
+pub fn replace_python_doc_block(source: &str, name: &str, value: &str) -> Result<String, String> {
+    let literal =
+        find_string_assignment(source, name)?.ok_or_else(|| format!("{}: not found", name))?;
+    let mut result = String::with_capacity(source.len() + value.len());
+    result.push_str(&source[..literal.start]);
+    result.push_str(&encode(value, &literal, name)?);
+    result.push_str(&source[literal.end..]);
+    Ok(result)
+}
+
+/// Update the value of the string assignment `name = r'''...'''` in Python source with `edit`.
+pub fn update_python_doc_block<F>(source: &str, name: &str, edit: F) -> Result<String, String>
+where
+    F: FnOnce(&str) -> Result<String, String>,
+{
+    let literal =
+        find_string_assignment(source, name)?.ok_or_else(|| format!("{}: not found", name))?;
+    let value = literal.value(source);
+    let new_value = edit(&value)?;
+    if new_value == value {
+        return Ok(source.to_string());
+    }
+    replace_python_doc_block(source, name, &new_value)
+}
+
+/// Replace a scalar in the YAML of the string assignment `name = r'''...'''` in Python source.
+///
+/// The YAML is edited with [`replace_scalar`], so comments and formatting of the YAML are kept.
+pub fn replace_python_doc_scalar(
+    source: &str,
+    name: &str,
+    path: &[PathSegment],
+    value: &str,
+) -> Result<String, String> {
+    update_python_doc_block(source, name, |yaml| {
+        replace_scalar(yaml, path, value).map_err(|error| format!("{}: {}", name, error))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin_docs::extract_python_doc_blocks;
+    use crate::util::yamledit::parse_path;
+
+    #[test]
+    fn test_replace_python_doc_scalar() {
+        let source = concat!(
+            "#!/usr/bin/python\n",
+            "# -*- coding: utf-8 -*-\n",
+            "\n",
+            "DOCUMENTATION = r'''\n",
+            "module: foo\n",
+            "short_description: Manage foo  # keep this\n",
+            "options:\n",
+            "  path:\n",
+            "    description:\n",
+            "      - Use V(C:\\\\temp).\n",
+            "'''\n",
+            "\n",
+            "RETURN = '''\n",
+            "msg:\n",
+            "  description: It\\'s a message.\n",
+            "'''\n",
+            "\n",
+            "from ansible.module_utils.basic import AnsibleModule\n",
+        );
+        let result = replace_python_doc_scalar(
+            source,
+            "DOCUMENTATION",
+            &parse_path("options.path.description[0]"),
+            "Use O(path=C:\\\\temp).",
+        )
+        .unwrap();
+        let result = replace_python_doc_scalar(
+            &result,
+            "RETURN",
+            &parse_path("msg.description"),
+            "A 'message' with \\.",
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            concat!(
+                "#!/usr/bin/python\n",
+                "# -*- coding: utf-8 -*-\n",
+                "\n",
+                "DOCUMENTATION = r'''\n",
+                "module: foo\n",
+                "short_description: Manage foo  # keep this\n",
+                "options:\n",
+                "  path:\n",
+                "    description:\n",
+                "      - Use O(path=C:\\\\temp).\n",
+                "'''\n",
+                "\n",
+                "RETURN = '''\n",
+                "msg:\n",
+                "  description: A \\'message\\' with \\\\.\n",
+                "'''\n",
+                "\n",
+                "from ansible.module_utils.basic import AnsibleModule\n",
+            )
+        );
+        let blocks = extract_python_doc_blocks(&result).unwrap();
+        assert_eq!(
+            blocks.return_values.unwrap()["msg"]["description"].as_str(),
+            Some("A 'message' with \\.")
+        );
+
+        assert_eq!(
+            replace_python_doc_block(source, "DOCUMENTATION", "x: '''").unwrap_err(),
+            "DOCUMENTATION: value cannot be written as raw string literal"
+        );
+        assert_eq!(
+            replace_python_doc_block(source, "EXAMPLES", "").unwrap_err(),
+            "EXAMPLES: not found"
+        );
+        assert_eq!(
+            replace_python_doc_scalar(source, "DOCUMENTATION", &parse_path("author"), "me")
+                .unwrap_err(),
+            "DOCUMENTATION: author: key not found"
+        );
+    }
+}
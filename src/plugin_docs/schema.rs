@@ -0,0 +1,305 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! The plugin documentation consumed by this crate, and its description as JSON Schema.
+
+use crate::util::json::push_json_string;
+
+/// The `$id` of the JSON Schema. The version at the end changes whenever the schema changes in
+/// an incompatible way.
+pub const PLUGIN_DOCS_SCHEMA_ID: &str = "urn:antsibull-rs:plugin-docs:1";
+
+/// The type of a field of the plugin documentation.
+///
+/// Missing fields and fields with the value `null` are treated the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocFieldType {
+    /// A string. Numbers and booleans are accepted and converted to strings.
+    String,
+
+    /// A boolean.
+    Boolean,
+
+    /// A string or a list of strings, like the paragraphs of a description.
+    StringList,
+
+    /// Any value.
+    Any,
+
+    /// A list of values, or a mapping of values to their descriptions.
+    Choices,
+
+    /// A mapping of option names to options.
+    Options,
+
+    /// A mapping of return value names to return values.
+    ReturnValues,
+}
+
+impl DocFieldType {
+    /// The keywords of the JSON Schema of the type, without braces.
+    fn schema(self) -> &'static str {
+        match self {
+            DocFieldType::String => "\"$ref\": \"#/$defs/string\"",
+            DocFieldType::Boolean => "\"type\": [\"boolean\", \"null\"]",
+            DocFieldType::StringList => "\"$ref\": \"#/$defs/string_list\"",
+            DocFieldType::Any => "",
+            DocFieldType::Choices => "\"type\": [\"array\", \"object\", \"null\"]",
+            DocFieldType::Options => "\"$ref\": \"#/$defs/options\"",
+            DocFieldType::ReturnValues => "\"$ref\": \"#/$defs/return_values\"",
+        }
+    }
+}
+
+/// A field of the plugin documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DocField {
+    /// The key of the field.
+    pub name: &'static str,
+
+    /// The type of the field.
+    pub r#type: DocFieldType,
+
+    /// What the field contains.
+    pub description: &'static str,
+}
+
+const fn field(name: &'static str, r#type: DocFieldType, description: &'static str) -> DocField {
+    DocField {
+        name,
+        r#type,
+        description,
+    }
+}
+
+/// The fields of the `DOCUMENTATION` block that are used. Other fields are allowed.
+pub const DOCUMENTATION_FIELDS: [DocField; 6] = [
+    field(
+        "short_description",
+        DocFieldType::String,
+        "A one-line description, in Ansible markup.",
+    ),
+    field(
+        "description",
+        DocFieldType::StringList,
+        "The description paragraphs, in Ansible markup.",
+    ),
+    field(
+        "notes",
+        DocFieldType::StringList,
+        "Notes, one paragraph each, in Ansible markup.",
+    ),
+    field(
+        "version_added",
+        DocFieldType::String,
+        "The version in which the plugin was added.",
+    ),
+    field("options", DocFieldType::Options, "The options."),
+    field(
+        "positional",
+        DocFieldType::StringList,
+        "For filters and tests, the positional parameters in order, as a list or comma-separated.",
+    ),
+];
+
+/// The fields of an option, see [`PluginOption`](crate::plugin_docs::PluginOption).
+pub const OPTION_FIELDS: [DocField; 15] = [
+    field(
+        "description",
+        DocFieldType::StringList,
+        "The description paragraphs, in Ansible markup.",
+    ),
+    field(
+        "type",
+        DocFieldType::String,
+        "The type of the option, like `str` or `list`.",
+    ),
+    field(
+        "elements",
+        DocFieldType::String,
+        "The type of the elements, if the option is a list.",
+    ),
+    field(
+        "required",
+        DocFieldType::Boolean,
+        "Whether the option is required.",
+    ),
+    field("default", DocFieldType::Any, "The default value."),
+    field(
+        "choices",
+        DocFieldType::Choices,
+        "The allowed values, or a mapping of the allowed values to their descriptions.",
+    ),
+    field(
+        "aliases",
+        DocFieldType::StringList,
+        "Other names of the option.",
+    ),
+    field(
+        "version_added",
+        DocFieldType::String,
+        "The version in which the option was added.",
+    ),
+    field(
+        "version_added_collection",
+        DocFieldType::String,
+        "The collection the `version_added` refers to.",
+    ),
+    field(
+        "suboptions",
+        DocFieldType::Options,
+        "Suboptions, if the option is a dictionary or a list of dictionaries.",
+    ),
+    field(
+        "ini",
+        DocFieldType::Any,
+        "The INI entries that set the option, as a list of mappings with `section` and `key`.",
+    ),
+    field(
+        "env",
+        DocFieldType::Any,
+        "The environment variables that set the option, as a list of mappings with `name`.",
+    ),
+    field(
+        "vars",
+        DocFieldType::Any,
+        "The Ansible variables that set the option, as a list of mappings with `name`.",
+    ),
+    field(
+        "cli",
+        DocFieldType::Any,
+        "The command line arguments that set the option, as a list of mappings with `name` and an optional `option`.",
+    ),
+    field(
+        "keyword",
+        DocFieldType::Any,
+        "The playbook keywords that set the option, as a list of mappings with `name`.",
+    ),
+];
+
+/// The fields of a return value, see [`ReturnValue`](crate::plugin_docs::ReturnValue).
+pub const RETURN_VALUE_FIELDS: [DocField; 8] = [
+    field(
+        "description",
+        DocFieldType::StringList,
+        "The description paragraphs, in Ansible markup.",
+    ),
+    field(
+        "type",
+        DocFieldType::String,
+        "The type of the return value, like `str` or `list`.",
+    ),
+    field(
+        "elements",
+        DocFieldType::String,
+        "The type of the elements, if the return value is a list.",
+    ),
+    field(
+        "returned",
+        DocFieldType::String,
+        "When the value is returned, in Ansible markup.",
+    ),
+    field("sample", DocFieldType::Any, "A sample value."),
+    field(
+        "version_added",
+        DocFieldType::String,
+        "The version in which the return value was added.",
+    ),
+    field(
+        "version_added_collection",
+        DocFieldType::String,
+        "The collection the `version_added` refers to.",
+    ),
+    field(
+        "contains",
+        DocFieldType::ReturnValues,
+        "Nested return values, if the return value is a dictionary or a list of dictionaries.",
+    ),
+];
+
+fn push_object(result: &mut String, description: &str, fields: &[DocField]) {
+    result.push_str("{\"type\": \"object\", \"description\": ");
+    push_json_string(result, description);
+    result.push_str(", \"properties\": {");
+    for (index, field) in fields.iter().enumerate() {
+        if index > 0 {
+            result.push_str(", ");
+        }
+        push_json_string(result, field.name);
+        result.push_str(": {");
+        let schema = field.r#type.schema();
+        if !schema.is_empty() {
+            result.push_str(schema);
+            result.push_str(", ");
+        }
+        result.push_str("\"description\": ");
+        push_json_string(result, field.description);
+        result.push('}');
+    }
+    result.push_str("}}");
+}
+
+/// Describe the plugin documentation as a JSON Schema (draft 2020-12).
+///
+/// The schema describes an object with the parsed `DOCUMENTATION` block as `doc`, the `EXAMPLES`
+/// block as `examples`, and the parsed `RETURN` block as `return`, like the output of
+/// `ansible-doc --json` for one plugin. Only the fields in [`DOCUMENTATION_FIELDS`],
+/// [`OPTION_FIELDS`], and [`RETURN_VALUE_FIELDS`] are checked; other fields are allowed.
+pub fn plugin_docs_json_schema() -> String {
+    let mut result =
+        String::from("{\"$schema\": \"https://json-schema.org/draft/2020-12/schema\", \"$id\": ");
+    push_json_string(&mut result, PLUGIN_DOCS_SCHEMA_ID);
+    result.push_str(concat!(
+        ", \"title\": \"Ansible plugin documentation\", \"type\": \"object\", \"properties\": {",
+        "\"doc\": {\"$ref\": \"#/$defs/documentation\"}, ",
+        "\"examples\": {\"type\": [\"string\", \"null\"]}, ",
+        "\"return\": {\"$ref\": \"#/$defs/return_values\"}}, ",
+        "\"$defs\": {",
+        "\"string\": {\"type\": [\"string\", \"number\", \"boolean\", \"null\"]}, ",
+        "\"string_list\": {\"anyOf\": [{\"$ref\": \"#/$defs/string\"}, ",
+        "{\"type\": \"array\", \"items\": {\"type\": [\"string\", \"number\", \"boolean\"]}}]}, ",
+        "\"options\": {\"type\": [\"object\", \"null\"], ",
+        "\"additionalProperties\": {\"$ref\": \"#/$defs/option\"}}, ",
+        "\"return_values\": {\"type\": [\"object\", \"null\"], ",
+        "\"additionalProperties\": {\"$ref\": \"#/$defs/return_value\"}}, ",
+        "\"documentation\": ",
+    ));
+    push_object(
+        &mut result,
+        "The DOCUMENTATION block.",
+        &DOCUMENTATION_FIELDS,
+    );
+    result.push_str(", \"option\": ");
+    push_object(&mut result, "An option.", &OPTION_FIELDS);
+    result.push_str(", \"return_value\": ");
+    push_object(&mut result, "A return value.", &RETURN_VALUE_FIELDS);
+    result.push_str("}}");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_docs_json_schema() {
+        let schema = plugin_docs_json_schema();
+        assert!(schema.starts_with(concat!(
+            r#"{"$schema": "https://json-schema.org/draft/2020-12/schema", "#,
+            r#""$id": "urn:antsibull-rs:plugin-docs:1", "title": "Ansible plugin documentation", "#,
+        )));
+        assert!(schema.contains(concat!(
+            r#""return_value": {"type": "object", "description": "A return value.", "properties": {"#,
+            r##""description": {"$ref": "#/$defs/string_list", "description": "The description paragraphs, in Ansible markup."}, "##,
+        )));
+        assert!(schema.contains(r#""sample": {"description": "A sample value."}, "#));
+        assert!(schema.contains(
+            r#""required": {"type": ["boolean", "null"], "description": "Whether the option is required."}"#
+        ));
+        assert!(schema.ends_with("}}}}"));
+        assert_eq!(schema.matches('{').count(), schema.matches('}').count());
+    }
+}
@@ -0,0 +1,266 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Sections of plugin pages that only some plugin types have.
+
+use crate::document::{AdmonitionKind, Block, Paragraph};
+use crate::markup::{parse_without_sources, Context, ParseOptions, Part, PluginIdentifier};
+use crate::plugin_docs::options::PluginOption;
+use crate::plugin_docs::table::{Table, TableRow};
+use crate::plugin_docs::tree::anchor_id;
+use crate::plugin_docs::types::{display_type, TypeLocalizer};
+use std::rc::Rc;
+
+/// The prefix of anchor IDs for the variables of connection plugins.
+pub const VARIABLE_ANCHOR_PREFIX: &str = "variable";
+
+/// The options that inventory plugins get from the `inventory_cache` documentation fragment.
+pub const INVENTORY_CACHE_OPTIONS: [&str; 5] = [
+    "cache",
+    "cache_connection",
+    "cache_plugin",
+    "cache_prefix",
+    "cache_timeout",
+];
+
+/// Create the section listing the requirements of a plugin, module, or role.
+///
+/// The requirements are parsed as Ansible markup with `context` and `opts`. Returns no blocks
+/// if there are no requirements.
+pub fn requirements_blocks<'a>(
+    plugin_type: &'a str,
+    requirements: &'a [String],
+    context: &'a Context,
+    opts: &ParseOptions,
+) -> Vec<Block<'a>> {
+    if requirements.is_empty() {
+        return Vec::new();
+    }
+    let mut intro = vec![Part::Text {
+        text: "The below requirements are needed on ",
+    }];
+    match plugin_type {
+        "module" => intro.push(Part::Text {
+            text: "the host that executes this module.",
+        }),
+        "role" => intro.push(Part::Text {
+            text: "the host that executes this role.",
+        }),
+        plugin_type => intro.extend([
+            Part::Text {
+                text: "the local controller node that executes this ",
+            },
+            Part::Text { text: plugin_type },
+            Part::Text { text: " plugin." },
+        ]),
+    }
+    vec![
+        Block::Heading {
+            level: 2,
+            title: "Requirements".to_string(),
+        },
+        Block::Paragraph { parts: intro },
+        Block::UnorderedList {
+            items: requirements
+                .iter()
+                .map(|requirement| vec![parse_without_sources(requirement, context, opts)])
+                .collect(),
+        },
+    ]
+}
+
+/// Create the note on the type of a callback plugin, like `stdout` or `notification`.
+///
+/// Only one `stdout` callback can be active; all others have to be enabled explicitly.
+pub fn callback_type_blocks(callback_type: &str) -> Vec<Block<'_>> {
+    let (intro, variable) = if callback_type == "stdout" {
+        (
+            "It can be used as the output callback by setting ",
+            "ANSIBLE_STDOUT_CALLBACK",
+        )
+    } else {
+        ("It has to be enabled with ", "ANSIBLE_CALLBACKS_ENABLED")
+    };
+    let paragraphs: Vec<Paragraph> = vec![
+        vec![
+            Part::Text {
+                text: "This callback plugin is of type ",
+            },
+            Part::Code {
+                text: callback_type,
+            },
+            Part::Text { text: "." },
+        ],
+        vec![
+            Part::Text { text: intro },
+            Part::EnvVariable {
+                name: variable.into(),
+            },
+            Part::Text { text: "." },
+        ],
+    ];
+    vec![Block::Admonition {
+        kind: AdmonitionKind::Note,
+        title: None,
+        paragraphs,
+    }]
+}
+
+/// Split the options of an inventory plugin into its own options and the cache options from
+/// [`INVENTORY_CACHE_OPTIONS`], keeping the order.
+///
+/// The cache options are usually shown in a table of their own below the other options.
+pub fn split_inventory_cache_options(
+    options: Vec<PluginOption>,
+) -> (Vec<PluginOption>, Vec<PluginOption>) {
+    options
+        .into_iter()
+        .partition(|option| !INVENTORY_CACHE_OPTIONS.contains(&option.name.as_str()))
+}
+
+/// Create a table of the variables that set options of a connection plugin, sorted by name.
+///
+/// Every row gets an anchor of the form `variable-<name>` and links to the option it sets
+/// for `plugin`. The type is the option's type.
+pub fn connection_variables_table<'a>(
+    options: &'a [PluginOption],
+    plugin: Option<Rc<PluginIdentifier>>,
+    localizer: &dyn TypeLocalizer,
+) -> Table<'a> {
+    let mut variables: Vec<(&'a str, &'a PluginOption)> = options
+        .iter()
+        .flat_map(|option| {
+            option
+                .configuration
+                .vars
+                .iter()
+                .map(move |var| (var.as_str(), option))
+        })
+        .collect();
+    variables.sort_by(|a, b| a.0.cmp(b.0));
+    let rows = variables
+        .into_iter()
+        .map(|(name, option)| TableRow {
+            depth: 0,
+            name,
+            path: vec![name],
+            aliases: Vec::new(),
+            anchors: vec![anchor_id(VARIABLE_ANCHOR_PREFIX, &[name])],
+            type_info: display_type(
+                Some(option.r#type.as_deref().unwrap_or("str")),
+                option.elements.as_deref(),
+                false,
+                localizer,
+            ),
+            description: vec![vec![
+                Part::Text {
+                    text: "Sets the option ",
+                },
+                Part::OptionName {
+                    plugin: plugin.clone(),
+                    entrypoint: None,
+                    link: [option.name.as_str().into()].into(),
+                    name: option.name.as_str().into(),
+                    value: None,
+                },
+                Part::Text { text: "." },
+            ]],
+            version_added: None,
+            values: None,
+            details: Vec::new(),
+            configuration: None,
+        })
+        .collect();
+    Table {
+        key_header: "Variable",
+        description_header: "Comments",
+        rows,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::append_md_blocks;
+    use crate::markup::NoLinkProvider;
+    use crate::plugin_docs::{append_gfm_table, load_options, DefaultTypeLocalizer};
+    use crate::util::stringbuilder::{CollectorAppender, IntoString};
+    use saphyr::Yaml;
+
+    #[test]
+    fn test_sections() {
+        let context = Context::new();
+        let requirements = vec!["C(paramiko)".to_string()];
+        let blocks = requirements_blocks(
+            "connection",
+            &requirements,
+            &context,
+            &ParseOptions::default(),
+        );
+        let mut appender = CollectorAppender::new();
+        append_md_blocks(&mut appender, blocks.iter(), &NoLinkProvider::new(), &None);
+        assert_eq!(
+            appender.into_string(),
+            concat!(
+                "## Requirements\n\n",
+                "The below requirements are needed on the local controller node that executes ",
+                "this connection plugin\\.\n\n",
+                "* <code>paramiko</code>",
+            )
+        );
+        assert_eq!(
+            requirements_blocks("module", &[], &context, &ParseOptions::default()),
+            vec![]
+        );
+
+        let blocks = callback_type_blocks("notification");
+        let mut appender = CollectorAppender::new();
+        append_md_blocks(&mut appender, blocks.iter(), &NoLinkProvider::new(), &None);
+        assert_eq!(
+            appender.into_string(),
+            concat!(
+                "> [!NOTE]\n",
+                "> This callback plugin is of type <code>notification</code>\\.\n>\n",
+                "> It has to be enabled with <code>ANSIBLE\\_CALLBACKS\\_ENABLED</code>\\.",
+            )
+        );
+
+        let options = load_options(
+            &Yaml::load_from_str(concat!(
+                "host:\n",
+                "  vars: [{name: ansible_host}, {name: ansible_ssh_host}]\n",
+                "cache:\n",
+                "  type: bool\n",
+                "port:\n",
+                "  type: int\n",
+                "  vars: [{name: ansible_port}]\n",
+            ))
+            .unwrap()[0],
+        )
+        .unwrap();
+        let (own, cache) = split_inventory_cache_options(options.clone());
+        assert_eq!(
+            (own.len(), cache.len(), cache[0].name.as_str()),
+            (2, 1, "cache")
+        );
+
+        let table = connection_variables_table(&options, None, &DefaultTypeLocalizer::new());
+        let mut appender = CollectorAppender::new();
+        append_gfm_table(&mut appender, &table, &NoLinkProvider::new(), &None);
+        assert_eq!(
+            appender.into_string(),
+            concat!(
+                "| Variable | Comments |\n| --- | --- |\n",
+                "| <a id=\"variable-ansible_host\"></a>**ansible\\_host**<br>string ",
+                "| Sets the option <code><strong>host</strong></code>\\. |\n",
+                "| <a id=\"variable-ansible_port\"></a>**ansible\\_port**<br>integer ",
+                "| Sets the option <code><strong>port</strong></code>\\. |\n",
+                "| <a id=\"variable-ansible_ssh_host\"></a>**ansible\\_ssh\\_host**<br>string ",
+                "| Sets the option <code><strong>host</strong></code>\\. |",
+            )
+        );
+    }
+}
@@ -0,0 +1,492 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::document::{indent, indent_first, Paragraph};
+use crate::markup;
+use crate::markup::{HTMLEscaper, LinkProvider, MDEscaper, PluginIdentifier, RSTEscaper};
+use crate::plugin_docs::configuration::{
+    append_ansible_doc_text_configuration, append_antsibull_html_configuration,
+    append_gfm_configuration, append_plain_html_configuration, append_rst_configuration,
+    OptionConfiguration,
+};
+use crate::plugin_docs::value_format::{
+    append_ansible_doc_text_option_values, append_antsibull_html_option_values,
+    append_gfm_option_values, append_plain_html_option_values, append_rst_option_values,
+    OptionValues,
+};
+use crate::plugin_docs::version_added::{
+    append_ansible_doc_text_version_added, append_html_version_added, append_rst_version_added,
+    VersionAdded,
+};
+use crate::util::stringbuilder::{Appender, CollectorAppender, IntoString};
+use std::rc::Rc;
+use std::sync::LazyLock;
+
+/// A row of a documentation table, like an option or a return value.
+#[derive(Debug, PartialEq)]
+pub struct TableRow<'a> {
+    /// The nesting depth. Top-level entries have depth 0.
+    pub depth: usize,
+
+    /// The name of the entry, without its parents.
+    pub name: &'a str,
+
+    /// The name of the entry preceeded by the names of its parents.
+    pub path: Vec<&'a str>,
+
+    /// Other names of the entry.
+    pub aliases: Vec<&'a str>,
+
+    /// The anchor IDs of the entry. The first one is the main anchor, the others are used
+    /// for alternative paths through aliases.
+    pub anchors: Vec<String>,
+
+    /// The type information, like `list / elements=string`.
+    pub type_info: String,
+
+    /// The description paragraphs.
+    pub description: Vec<Paragraph<'a>>,
+
+    /// The version in which the entry was added, if it should be shown.
+    pub version_added: Option<VersionAdded<'a>>,
+
+    /// Choices and default value, for options.
+    pub values: Option<OptionValues<'a>>,
+
+    /// Further information shown after the description, like default values or samples.
+    pub details: Vec<Paragraph<'a>>,
+
+    /// The configuration settings shown after the details, for options of controller-side
+    /// plugins.
+    pub configuration: Option<&'a OptionConfiguration>,
+}
+
+/// A documentation table with a key column and a description column.
+#[derive(Debug, PartialEq)]
+pub struct Table<'a> {
+    /// The header of the key column.
+    pub key_header: &'a str,
+
+    /// The header of the description column.
+    pub description_header: &'a str,
+
+    /// The rows of the table.
+    pub rows: Vec<TableRow<'a>>,
+}
+
+/// How paragraphs are rendered inside table cells.
+pub(crate) struct ParagraphStyle {
+    formatter: &'static (dyn for<'x> markup::Formatter<'x> + Sync),
+    par_start: &'static str,
+    par_end: &'static str,
+    par_sep: &'static str,
+    par_empty: &'static str,
+}
+
+impl ParagraphStyle {
+    pub(crate) fn append<'a>(
+        &self,
+        appender: &mut dyn Appender<'a>,
+        paragraphs: &'a [Paragraph<'a>],
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) {
+        markup::append_paragraphs(
+            appender,
+            paragraphs.iter().map(|paragraph| paragraph.iter()),
+            self.formatter,
+            link_provider,
+            self.par_start,
+            self.par_end,
+            self.par_sep,
+            self.par_empty,
+            current_plugin,
+        );
+    }
+
+    pub(crate) fn render<'a>(
+        &self,
+        paragraphs: &'a [Paragraph<'a>],
+        link_provider: &dyn LinkProvider,
+        current_plugin: &Option<Rc<PluginIdentifier>>,
+    ) -> String {
+        let mut appender = CollectorAppender::new();
+        self.append(&mut appender, paragraphs, link_provider, current_plugin);
+        appender.into_string()
+    }
+}
+
+pub(crate) static RST_STYLE: LazyLock<ParagraphStyle> = LazyLock::new(|| ParagraphStyle {
+    formatter: &*markup::ANTSIBULL_RST_FORMATTER,
+    par_start: "",
+    par_end: "",
+    par_sep: "\n\n",
+    par_empty: "\\ ",
+});
+
+pub(crate) static ANTSIBULL_HTML_STYLE: LazyLock<ParagraphStyle> =
+    LazyLock::new(|| ParagraphStyle {
+        formatter: &*markup::ANTSIBULL_HTML_FORMATTER,
+        par_start: "<p>",
+        par_end: "</p>",
+        par_sep: "",
+        par_empty: "",
+    });
+
+pub(crate) static PLAIN_HTML_STYLE: LazyLock<ParagraphStyle> = LazyLock::new(|| ParagraphStyle {
+    formatter: &*markup::PLAIN_HTML_FORMATTER,
+    par_start: "<p>",
+    par_end: "</p>",
+    par_sep: "",
+    par_empty: "",
+});
+
+pub(crate) static ANSIBLE_DOC_TEXT_STYLE: LazyLock<ParagraphStyle> =
+    LazyLock::new(|| ParagraphStyle {
+        formatter: &*markup::ANSIBLE_DOC_TEXT_FORMATTER,
+        par_start: "",
+        par_end: "",
+        par_sep: "\n",
+        par_empty: "",
+    });
+
+pub(crate) static GFM_STYLE: LazyLock<ParagraphStyle> = LazyLock::new(|| ParagraphStyle {
+    formatter: &*markup::MARKDOWN_FORMATTER,
+    par_start: "",
+    par_end: "",
+    par_sep: "<br><br>",
+    par_empty: "",
+});
+
+type AppendOptionValues<'a> = fn(
+    &mut dyn Appender<'a>,
+    &'a OptionValues<'a>,
+    &dyn LinkProvider,
+    &Option<Rc<PluginIdentifier>>,
+);
+
+type AppendConfiguration<'a> = fn(
+    &mut dyn Appender<'a>,
+    &'a OptionConfiguration,
+    &dyn LinkProvider,
+    &Option<Rc<PluginIdentifier>>,
+);
+
+/// Render the configuration settings of a row with `append`, or return an empty string.
+fn render_configuration<'a>(
+    row: &TableRow<'a>,
+    append: AppendConfiguration<'a>,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) -> String {
+    let mut appender = CollectorAppender::new();
+    if let Some(configuration) = row.configuration {
+        append(&mut appender, configuration, link_provider, current_plugin);
+    }
+    appender.into_string()
+}
+
+/// Render a table as a RST `list-table`.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup in descriptions.
+pub fn append_rst_table<'a>(
+    appender: &mut dyn Appender<'a>,
+    table: &'a Table<'a>,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) {
+    let escaper = RSTEscaper::new();
+    appender
+        .push_str(".. list-table::\n  :width: 100%\n  :widths: auto\n  :header-rows: 1\n\n  * - ");
+    appender.push_cow_str(escaper.escape(table.key_header, false, true));
+    appender.push_str("\n    - ");
+    appender.push_cow_str(escaper.escape(table.description_header, false, true));
+    for row in &table.rows {
+        appender.push_str("\n\n");
+        let mut key = String::new();
+        for anchor in &row.anchors {
+            key.push_str(".. _");
+            key.push_str(anchor);
+            key.push_str(":\n\n");
+        }
+        key.push_str("**");
+        key.push_str(&escaper.escape(&row.path.join("."), true, true));
+        key.push_str("**");
+        if !row.aliases.is_empty() {
+            key.push_str("\n\n");
+            key.push_str(&escaper.escape(
+                &format!("aliases: {}", row.aliases.join(", ")),
+                false,
+                false,
+            ));
+        }
+        if !row.type_info.is_empty() {
+            key.push_str("\n\n");
+            key.push_str(&escaper.escape(&row.type_info, false, false));
+        }
+        if let Some(version_added) = &row.version_added {
+            let mut version_appender = CollectorAppender::new();
+            append_rst_version_added(&mut version_appender, version_added);
+            key.push_str("\n\n");
+            key.push_str(&version_appender.into_string());
+        }
+        appender.push_owned_string(indent_first(&key, "  * - ", "      ", ""));
+        let mut description = RST_STYLE.render(&row.description, link_provider, current_plugin);
+        let mut values = CollectorAppender::new();
+        if let Some(row_values) = &row.values {
+            append_rst_option_values(&mut values, row_values, link_provider, current_plugin);
+        }
+        for part in [
+            values.into_string(),
+            RST_STYLE.render(&row.details, link_provider, current_plugin),
+            render_configuration(row, append_rst_configuration, link_provider, current_plugin),
+        ] {
+            if !description.is_empty() && !part.is_empty() {
+                description.push_str("\n\n");
+            }
+            description.push_str(&part);
+        }
+        appender.push_str("\n");
+        appender.push_owned_string(indent_first(&description, "    - ", "      ", ""));
+    }
+}
+
+fn append_html_table_impl<'a>(
+    appender: &mut dyn Appender<'a>,
+    table: &'a Table<'a>,
+    style: &ParagraphStyle,
+    append_values: AppendOptionValues<'a>,
+    append_configuration: AppendConfiguration<'a>,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) {
+    let escaper = HTMLEscaper::new();
+    appender.push_str("<table class=\"ansible-option-table\"><thead><tr><th>");
+    appender.push_cow_str(escaper.escape(table.key_header));
+    appender.push_str("</th><th>");
+    appender.push_cow_str(escaper.escape(table.description_header));
+    appender.push_str("</th></tr></thead><tbody>");
+    for row in &table.rows {
+        appender.push_str("<tr><td>");
+        for _ in 0..row.depth {
+            appender.push_str("<div class=\"ansible-option-indent\"></div>");
+        }
+        appender.push_str("<div class=\"ansible-option-cell\">");
+        for anchor in &row.anchors {
+            appender.push_str("<div class=\"ansibleOptionAnchor\" id=\"");
+            appender.push_cow_str(escaper.escape_attr_double(anchor));
+            appender.push_str("\"></div>");
+        }
+        appender.push_str("<p><strong>");
+        appender.push_cow_str(escaper.escape(row.name));
+        appender.push_str("</strong></p>");
+        if !row.aliases.is_empty() {
+            appender.push_str("<p class=\"ansible-option-aliases\">aliases: ");
+            appender.push_owned_string(escaper.escape(&row.aliases.join(", ")).into_owned());
+            appender.push_str("</p>");
+        }
+        if !row.type_info.is_empty() {
+            appender.push_str("<p class=\"ansible-option-type-line\">");
+            appender.push_owned_string(escaper.escape(&row.type_info).into_owned());
+            appender.push_str("</p>");
+        }
+        if let Some(version_added) = &row.version_added {
+            append_html_version_added(appender, version_added);
+        }
+        appender.push_str("</div></td><td><div class=\"ansible-option-cell\">");
+        style.append(appender, &row.description, link_provider, current_plugin);
+        if let Some(values) = &row.values {
+            append_values(appender, values, link_provider, current_plugin);
+        }
+        style.append(appender, &row.details, link_provider, current_plugin);
+        if let Some(configuration) = row.configuration {
+            append_configuration(appender, configuration, link_provider, current_plugin);
+        }
+        appender.push_str("</div></td></tr>");
+    }
+    appender.push_str("</tbody></table>");
+}
+
+/// Render a table as HTML using the Antsibull HTML formatter for markup.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup in descriptions.
+pub fn append_html_table<'a>(
+    appender: &mut dyn Appender<'a>,
+    table: &'a Table<'a>,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) {
+    append_html_table_impl(
+        appender,
+        table,
+        &ANTSIBULL_HTML_STYLE,
+        append_antsibull_html_option_values,
+        append_antsibull_html_configuration,
+        link_provider,
+        current_plugin,
+    );
+}
+
+/// Render a table for MarkDown.
+///
+/// Since MarkDown has no tables with multi-paragraph cells, this renders a HTML table
+/// using the plain HTML formatter for markup.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup in descriptions.
+pub fn append_md_table<'a>(
+    appender: &mut dyn Appender<'a>,
+    table: &'a Table<'a>,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) {
+    append_html_table_impl(
+        appender,
+        table,
+        &PLAIN_HTML_STYLE,
+        append_plain_html_option_values,
+        append_plain_html_configuration,
+        link_provider,
+        current_plugin,
+    );
+}
+
+/// Render a table as a GitHub Flavored Markdown table.
+///
+/// In contrast to [`append_md_table`], this does not need HTML tables, which some MarkDown
+/// renderers strip. Every row is a single line; paragraphs are separated by `<br>` and nested
+/// entries are indented with `&nbsp;`.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup in descriptions.
+pub fn append_gfm_table<'a>(
+    appender: &mut dyn Appender<'a>,
+    table: &'a Table<'a>,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) {
+    let escaper = MDEscaper::new().unwrap();
+    // Line breaks would end the table row
+    let cell = |content: &str| content.replace('\n', " ");
+    appender.push_str("| ");
+    appender.push_owned_string(cell(&escaper.escape(table.key_header)));
+    appender.push_str(" | ");
+    appender.push_owned_string(cell(&escaper.escape(table.description_header)));
+    appender.push_str(" |\n| --- | --- |");
+    for row in &table.rows {
+        let mut key = "&nbsp;&nbsp;&nbsp;&nbsp;".repeat(row.depth);
+        for anchor in &row.anchors {
+            key.push_str("<a id=\"");
+            key.push_str(&HTMLEscaper::new().escape_attr_double(anchor));
+            key.push_str("\"></a>");
+        }
+        key.push_str("**");
+        key.push_str(&escaper.escape(row.name));
+        key.push_str("**");
+        if !row.aliases.is_empty() {
+            key.push_str("<br>");
+            key.push_str(&escaper.escape(&format!("aliases: {}", row.aliases.join(", "))));
+        }
+        if !row.type_info.is_empty() {
+            key.push_str("<br>");
+            key.push_str(&escaper.escape(&row.type_info));
+        }
+        if let Some(version_added) = &row.version_added {
+            key.push_str("<br><i>");
+            key.push_str(&escaper.escape(&version_added.to_string()));
+            key.push_str("</i>");
+        }
+        let mut description = GFM_STYLE.render(&row.description, link_provider, current_plugin);
+        let mut values = CollectorAppender::new();
+        if let Some(row_values) = &row.values {
+            append_gfm_option_values(&mut values, row_values, link_provider, current_plugin);
+        }
+        for part in [
+            values.into_string(),
+            GFM_STYLE.render(&row.details, link_provider, current_plugin),
+            render_configuration(row, append_gfm_configuration, link_provider, current_plugin),
+        ] {
+            if !description.is_empty() && !part.is_empty() {
+                description.push_str("<br><br>");
+            }
+            description.push_str(&part);
+        }
+        appender.push_str("\n| ");
+        appender.push_owned_string(cell(&key));
+        appender.push_str(" | ");
+        appender.push_owned_string(cell(&description));
+        appender.push_str(" |");
+    }
+}
+
+/// Render a table as an indented list in the style of ansible-doc's text output.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup in descriptions.
+pub fn append_ansible_doc_text_table<'a>(
+    appender: &mut dyn Appender<'a>,
+    table: &'a Table<'a>,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) {
+    for (index, row) in table.rows.iter().enumerate() {
+        if index > 0 {
+            appender.push_str("\n");
+        }
+        let prefix = "    ".repeat(row.depth);
+        appender.push_borrowed_string(&prefix);
+        appender.push_str("- ");
+        appender.push_str(row.name);
+        let mut content =
+            ANSIBLE_DOC_TEXT_STYLE.render(&row.description, link_provider, current_plugin);
+        let mut values = CollectorAppender::new();
+        if let Some(row_values) = &row.values {
+            append_ansible_doc_text_option_values(
+                &mut values,
+                row_values,
+                link_provider,
+                current_plugin,
+            );
+        }
+        for detail in [
+            values.into_string(),
+            ANSIBLE_DOC_TEXT_STYLE.render(&row.details, link_provider, current_plugin),
+            render_configuration(
+                row,
+                append_ansible_doc_text_configuration,
+                link_provider,
+                current_plugin,
+            ),
+            if row.aliases.is_empty() {
+                String::new()
+            } else {
+                format!("aliases: {}", row.aliases.join(", "))
+            },
+            if row.type_info.is_empty() {
+                String::new()
+            } else {
+                format!("type: {}", row.type_info)
+            },
+            match &row.version_added {
+                Some(version_added) => {
+                    let mut version_appender = CollectorAppender::new();
+                    append_ansible_doc_text_version_added(&mut version_appender, version_added);
+                    version_appender.into_string()
+                }
+                None => String::new(),
+            },
+        ] {
+            if detail.is_empty() {
+                continue;
+            }
+            if !content.is_empty() {
+                content.push('\n');
+            }
+            content.push_str(&detail);
+        }
+        if !content.is_empty() {
+            appender.push_str("\n");
+            appender.push_owned_string(indent(&content, &(prefix + "    "), ""));
+        }
+    }
+}
@@ -0,0 +1,94 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+/// An entry of a documentation tree, like an option with suboptions or a return value
+/// containing other return values.
+pub trait DocTreeNode: Sized {
+    /// The name of the entry.
+    fn name(&self) -> &str;
+
+    /// Alternative names of the entry.
+    fn aliases(&self) -> &[String] {
+        &[]
+    }
+
+    /// The entries nested below this entry.
+    fn children(&self) -> &[Self];
+}
+
+/// An entry of a documentation tree together with its position in the tree.
+#[derive(Debug, PartialEq)]
+pub struct FlatNode<'a, T> {
+    /// The nesting depth. Top-level entries have depth 0.
+    pub depth: usize,
+
+    /// The names of the entry's parents and of the entry itself.
+    pub path: Vec<&'a str>,
+
+    /// All paths under which the entry can be referenced, taking aliases of the entry and its
+    /// parents into account. The first one is always `path`.
+    pub full_paths: Vec<Vec<&'a str>>,
+
+    /// The entry.
+    pub value: &'a T,
+}
+
+impl<T> FlatNode<'_, T> {
+    /// The anchor IDs for all full paths, using `prefix` (like `parameter` or `return`).
+    pub fn anchors(&self, prefix: &str) -> Vec<String> {
+        self.full_paths
+            .iter()
+            .map(|path| anchor_id(prefix, path))
+            .collect()
+    }
+}
+
+/// Compute the anchor ID for an entry, like `parameter-foo/bar` for the suboption `bar`
+/// of the option `foo`.
+///
+/// This is the same scheme antsibull-docs uses for its option and return value tables.
+pub fn anchor_id(prefix: &str, path: &[&str]) -> String {
+    format!("{}-{}", prefix, path.join("/"))
+}
+
+fn flatten_into<'a, T: DocTreeNode>(
+    result: &mut Vec<FlatNode<'a, T>>,
+    values: &'a [T],
+    parent: &[&'a str],
+    parent_full_paths: &[Vec<&'a str>],
+) {
+    for value in values {
+        let mut path = parent.to_vec();
+        path.push(value.name());
+        let names: Vec<&'a str> = std::iter::once(value.name())
+            .chain(value.aliases().iter().map(|alias| alias.as_str()))
+            .collect();
+        let full_paths: Vec<Vec<&'a str>> = parent_full_paths
+            .iter()
+            .flat_map(|parent_path| {
+                names.iter().map(move |name| {
+                    let mut full_path = parent_path.clone();
+                    full_path.push(name);
+                    full_path
+                })
+            })
+            .collect();
+        result.push(FlatNode {
+            depth: parent.len(),
+            path: path.clone(),
+            full_paths: full_paths.clone(),
+            value,
+        });
+        flatten_into(result, value.children(), &path, &full_paths);
+    }
+}
+
+/// Flatten a documentation tree, so that every entry is directly followed by its children.
+pub fn flatten_tree<T: DocTreeNode>(values: &[T]) -> Vec<FlatNode<'_, T>> {
+    let mut result = Vec::new();
+    flatten_into(&mut result, values, &[], &[Vec::new()]);
+    result
+}
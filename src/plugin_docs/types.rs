@@ -0,0 +1,136 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Display of option and return value types.
+
+use std::borrow::Cow;
+
+/// Normalize an Ansible type name to the name shown in documentation.
+///
+/// The abbreviations `str`, `bool`, `int`, and `dict` are expanded; all other names are returned unchanged.
+pub fn normalize_type_name(name: &str) -> &str {
+    match name {
+        "str" => "string",
+        "bool" => "boolean",
+        "int" => "integer",
+        "dict" => "dictionary",
+        name => name,
+    }
+}
+
+/// Texts used when displaying types.
+///
+/// The default implementations produce the English texts used by antsibull-docs. Implement this
+/// trait to translate them.
+pub trait TypeLocalizer {
+    /// The display name of a type.
+    fn type_name<'t>(&self, name: &'t str) -> Cow<'t, str> {
+        Cow::Borrowed(normalize_type_name(name))
+    }
+
+    /// The display of the element type of a list.
+    fn elements(&self, elements: &str) -> String {
+        format!("elements={}", self.type_name(elements))
+    }
+
+    /// The marker for required options.
+    fn required(&self) -> Cow<'_, str> {
+        Cow::Borrowed("required")
+    }
+
+    /// The separator between type, element type, and required marker.
+    fn separator(&self) -> Cow<'_, str> {
+        Cow::Borrowed(" / ")
+    }
+}
+
+/// The English texts used by antsibull-docs.
+pub struct DefaultTypeLocalizer {}
+
+impl DefaultTypeLocalizer {
+    pub fn new() -> DefaultTypeLocalizer {
+        DefaultTypeLocalizer {}
+    }
+}
+
+impl Default for DefaultTypeLocalizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TypeLocalizer for DefaultTypeLocalizer {}
+
+/// Display a type, like `list / elements=string / required`.
+///
+/// Missing parts are skipped. If nothing is known, an empty string is returned.
+pub fn display_type(
+    r#type: Option<&str>,
+    elements: Option<&str>,
+    required: bool,
+    localizer: &dyn TypeLocalizer,
+) -> String {
+    let mut parts: Vec<Cow<str>> = Vec::new();
+    if let Some(r#type) = r#type {
+        parts.push(localizer.type_name(r#type));
+    }
+    if let Some(elements) = elements {
+        parts.push(Cow::Owned(localizer.elements(elements)));
+    }
+    if required {
+        parts.push(localizer.required());
+    }
+    parts.join(&localizer.separator())
+}
+
+/// Whether a type name denotes booleans.
+pub fn is_boolean_type(name: &str) -> bool {
+    normalize_type_name(name) == "boolean"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct GermanTypeLocalizer {}
+
+    impl TypeLocalizer for GermanTypeLocalizer {
+        fn type_name<'t>(&self, name: &'t str) -> Cow<'t, str> {
+            match normalize_type_name(name) {
+                "string" => Cow::Borrowed("Zeichenkette"),
+                "list" => Cow::Borrowed("Liste"),
+                name => Cow::Borrowed(name),
+            }
+        }
+
+        fn required(&self) -> Cow<'_, str> {
+            Cow::Borrowed("erforderlich")
+        }
+    }
+
+    #[test]
+    fn test_display_type() {
+        let localizer = DefaultTypeLocalizer::new();
+        assert_eq!(display_type(None, None, false, &localizer), "");
+        assert_eq!(display_type(Some("str"), None, false, &localizer), "string");
+        assert_eq!(
+            display_type(Some("dict"), None, true, &localizer),
+            "dictionary / required"
+        );
+        assert_eq!(
+            display_type(Some("list"), Some("int"), false, &localizer),
+            "list / elements=integer"
+        );
+        assert_eq!(display_type(Some("raw"), None, false, &localizer), "raw");
+        assert_eq!(
+            display_type(Some("list"), Some("str"), true, &GermanTypeLocalizer {}),
+            "Liste / elements=Zeichenkette / erforderlich"
+        );
+        assert!(is_boolean_type("bool"));
+        assert!(is_boolean_type("boolean"));
+        assert!(!is_boolean_type("str"));
+    }
+}
@@ -0,0 +1,498 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Formatting of default values and choices of options.
+
+use crate::document::{indent, Paragraph};
+use crate::markup::{parse_without_sources, Context, LinkProvider, ParseOptions, PluginIdentifier};
+use crate::markup::{HTMLEscaper, RSTEscaper};
+use crate::plugin_docs::table::{
+    ParagraphStyle, ANSIBLE_DOC_TEXT_STYLE, ANTSIBULL_HTML_STYLE, GFM_STYLE, PLAIN_HTML_STYLE,
+    RST_STYLE,
+};
+use crate::plugin_docs::types::is_boolean_type;
+use crate::util::json::push_json_string;
+use crate::util::stringbuilder::Appender;
+use saphyr::Yaml;
+use std::rc::Rc;
+
+fn push_json(result: &mut String, value: &Yaml) {
+    match value {
+        Yaml::Real(_) => match value.as_f64() {
+            Some(f) if f.is_finite() => result.push_str(&f.to_string()),
+            Some(f) if f.is_nan() => result.push_str("NaN"),
+            Some(f) if f > 0.0 => result.push_str("Infinity"),
+            Some(_) => result.push_str("-Infinity"),
+            None => result.push_str("null"),
+        },
+        Yaml::Integer(i) => result.push_str(&i.to_string()),
+        Yaml::String(s) => push_json_string(result, s),
+        Yaml::Boolean(b) => result.push_str(if *b { "true" } else { "false" }),
+        Yaml::Array(values) => {
+            result.push('[');
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    result.push_str(", ");
+                }
+                push_json(result, value);
+            }
+            result.push(']');
+        }
+        Yaml::Hash(hash) => {
+            result.push('{');
+            for (index, (key, value)) in hash.iter().enumerate() {
+                if index > 0 {
+                    result.push_str(", ");
+                }
+                match key {
+                    Yaml::String(s) => push_json_string(result, s),
+                    _ => {
+                        let mut key_json = String::new();
+                        push_json(&mut key_json, key);
+                        push_json_string(result, &key_json);
+                    }
+                }
+                result.push_str(": ");
+                push_json(result, value);
+            }
+            result.push('}');
+        }
+        Yaml::Alias(_) | Yaml::Null | Yaml::BadValue => result.push_str("null"),
+    }
+}
+
+/// Format a value for display, in the compact JSON style of Python's `json.dumps()`.
+///
+/// Strings are always quoted, so that empty strings and strings like `"true"` can be
+/// distinguished from other values.
+pub fn format_value(value: &Yaml) -> String {
+    let mut result = String::new();
+    push_json(&mut result, value);
+    result
+}
+
+/// The default value of an option.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DefaultValue<'a> {
+    /// The option is required, so a default value is never used.
+    Required,
+
+    /// The option has no default value. This includes an explicit `default: null`.
+    None,
+
+    /// The option has a default value.
+    Value(&'a Yaml),
+}
+
+impl<'a> DefaultValue<'a> {
+    /// Determine the default value from an option's `required` and `default` entries.
+    pub fn new(required: bool, default: Option<&'a Yaml>) -> DefaultValue<'a> {
+        match default {
+            _ if required => DefaultValue::Required,
+            None | Some(Yaml::Null) => DefaultValue::None,
+            Some(value) => DefaultValue::Value(value),
+        }
+    }
+
+    /// Whether `value` is the default value, or part of a default list.
+    pub fn is_default(&self, value: &Yaml) -> bool {
+        match self {
+            DefaultValue::Value(Yaml::Array(values)) => values.contains(value),
+            DefaultValue::Value(default) => *default == value,
+            _ => false,
+        }
+    }
+}
+
+/// A choice of an option, with an optional description.
+#[derive(Debug, PartialEq)]
+pub struct Choice<'a> {
+    /// The value.
+    pub value: Yaml,
+
+    /// The description paragraphs. Empty if the choice has no description.
+    pub description: Vec<Paragraph<'a>>,
+}
+
+/// Default value and choices of an option.
+#[derive(Debug, PartialEq)]
+pub struct OptionValues<'a> {
+    /// The default value.
+    pub default: DefaultValue<'a>,
+
+    /// The choices. Empty if the option has no choices.
+    pub choices: Vec<Choice<'a>>,
+}
+
+impl<'a> OptionValues<'a> {
+    /// Collect default value and choices of an option.
+    ///
+    /// `choices` can be a list of values, or a mapping of values to descriptions; descriptions are
+    /// parsed as Ansible markup with `context` and `opts`. Like antsibull-docs, boolean options without
+    /// explicit choices get the choices `false` and `true`.
+    pub fn new(
+        required: bool,
+        default: Option<&'a Yaml>,
+        choices: Option<&'a Yaml>,
+        r#type: Option<&str>,
+        context: &'a Context,
+        opts: &ParseOptions,
+    ) -> OptionValues<'a> {
+        let choices = match choices {
+            Some(Yaml::Array(values)) => values
+                .iter()
+                .map(|value| Choice {
+                    value: value.clone(),
+                    description: Vec::new(),
+                })
+                .collect(),
+            Some(Yaml::Hash(hash)) => hash
+                .iter()
+                .map(|(value, description)| Choice {
+                    value: value.clone(),
+                    description: match description {
+                        Yaml::Array(paragraphs) => paragraphs.iter().collect(),
+                        description => vec![description],
+                    }
+                    .into_iter()
+                    .filter_map(|paragraph| match paragraph {
+                        Yaml::String(paragraph) => {
+                            Some(parse_without_sources(paragraph, context, opts))
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+                })
+                .collect(),
+            _ if r#type.is_some_and(is_boolean_type) => [false, true]
+                .into_iter()
+                .map(|value| Choice {
+                    value: Yaml::Boolean(value),
+                    description: Vec::new(),
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        OptionValues {
+            default: DefaultValue::new(required, default),
+            choices,
+        }
+    }
+
+    /// Whether there is anything to show.
+    pub fn is_empty(&self) -> bool {
+        self.choices.is_empty() && !matches!(self.default, DefaultValue::Value(_))
+    }
+
+    /// The default value, if it has to be shown on its own since it is not marked in the choices.
+    fn standalone_default(&self) -> Option<&'a Yaml> {
+        match self.default {
+            DefaultValue::Value(value)
+                if !self
+                    .choices
+                    .iter()
+                    .any(|choice| self.default.is_default(&choice.value)) =>
+            {
+                Some(value)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Render choices and default value as RST, using the roles of the `sphinx_antsibull_ext` extension.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup in
+/// descriptions of choices.
+pub fn append_rst_option_values<'a>(
+    appender: &mut dyn Appender<'a>,
+    values: &'a OptionValues<'a>,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) {
+    let escaper = RSTEscaper::new();
+    let style: &ParagraphStyle = &RST_STYLE;
+    let mut first = true;
+    if !values.choices.is_empty() {
+        first = false;
+        appender.push_str(":ansible-option-choices:`Choices:`\n");
+        for choice in &values.choices {
+            let value = format_value(&choice.value);
+            appender.push_str("\n");
+            if values.default.is_default(&choice.value) {
+                appender.push_str("- :ansible-option-choices-entry-default:`");
+                appender.push_owned_string(escaper.escape(&value, true, true).into_owned());
+                appender.push_str("` :ansible-option-choices-default-mark:`← (default)`");
+            } else {
+                appender.push_str("- :ansible-option-choices-entry:`");
+                appender.push_owned_string(escaper.escape(&value, true, true).into_owned());
+                appender.push_str("`");
+            }
+            if !choice.description.is_empty() {
+                appender.push_str("\n\n");
+                appender.push_owned_string(indent(
+                    &style.render(&choice.description, link_provider, current_plugin),
+                    "  ",
+                    "",
+                ));
+                appender.push_str("\n");
+            }
+        }
+    }
+    if let Some(default) = values.standalone_default() {
+        if !first {
+            appender.push_str("\n\n");
+        }
+        appender.push_str(":ansible-option-default-bold:`Default:` :ansible-option-default:`");
+        appender.push_owned_string(
+            escaper
+                .escape(&format_value(default), true, true)
+                .into_owned(),
+        );
+        appender.push_str("`");
+    }
+}
+
+fn append_html_option_values_impl<'a>(
+    appender: &mut dyn Appender<'a>,
+    values: &'a OptionValues<'a>,
+    style: &ParagraphStyle,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) {
+    let escaper = HTMLEscaper::new();
+    if !values.choices.is_empty() {
+        appender.push_str("<p class=\"ansible-option-line\"><strong class=\"ansible-option-choices\">Choices:</strong></p><ul class=\"simple\">");
+        for choice in &values.choices {
+            let value = format_value(&choice.value);
+            appender.push_str("<li><p>");
+            if values.default.is_default(&choice.value) {
+                appender.push_str("<code class=\"ansible-option-choices-entry-default\">");
+                appender.push_owned_string(escaper.escape(&value).into_owned());
+                appender.push_str("</code> <span class=\"ansible-option-choices-default-mark\">← (default)</span>");
+            } else {
+                appender.push_str("<code class=\"ansible-option-choices-entry\">");
+                appender.push_owned_string(escaper.escape(&value).into_owned());
+                appender.push_str("</code>");
+            }
+            appender.push_str("</p>");
+            style.append(appender, &choice.description, link_provider, current_plugin);
+            appender.push_str("</li>");
+        }
+        appender.push_str("</ul>");
+    }
+    if let Some(default) = values.standalone_default() {
+        appender.push_str("<p class=\"ansible-option-line\"><strong class=\"ansible-option-default-bold\">Default:</strong> <code class=\"ansible-option-default\">");
+        appender.push_owned_string(escaper.escape(&format_value(default)).into_owned());
+        appender.push_str("</code></p>");
+    }
+}
+
+/// Render choices and default value as HTML, using the Antsibull HTML formatter for descriptions of choices.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup in
+/// descriptions of choices.
+pub fn append_antsibull_html_option_values<'a>(
+    appender: &mut dyn Appender<'a>,
+    values: &'a OptionValues<'a>,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) {
+    append_html_option_values_impl(
+        appender,
+        values,
+        &ANTSIBULL_HTML_STYLE,
+        link_provider,
+        current_plugin,
+    );
+}
+
+/// Render choices and default value as HTML, using the plain HTML formatter for descriptions of choices.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup in
+/// descriptions of choices.
+pub fn append_plain_html_option_values<'a>(
+    appender: &mut dyn Appender<'a>,
+    values: &'a OptionValues<'a>,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) {
+    append_html_option_values_impl(
+        appender,
+        values,
+        &PLAIN_HTML_STYLE,
+        link_provider,
+        current_plugin,
+    );
+}
+
+/// Render choices and default value for a GitHub Flavored Markdown table cell.
+///
+/// Everything is rendered on a single line, using `<br>` for line breaks.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup in
+/// descriptions of choices.
+pub fn append_gfm_option_values<'a>(
+    appender: &mut dyn Appender<'a>,
+    values: &'a OptionValues<'a>,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) {
+    let escaper = HTMLEscaper::new();
+    let code = |value: &Yaml| escaper.escape(&format_value(value)).replace('|', "&#124;");
+    let style: &ParagraphStyle = &GFM_STYLE;
+    let mut first = true;
+    if !values.choices.is_empty() {
+        first = false;
+        appender.push_str("<b>Choices:</b>");
+        for choice in &values.choices {
+            appender.push_str("<br>- <code>");
+            appender.push_owned_string(code(&choice.value));
+            appender.push_str("</code>");
+            if values.default.is_default(&choice.value) {
+                appender.push_str(" ← (default)");
+            }
+            if !choice.description.is_empty() {
+                appender.push_str(": ");
+                style.append(appender, &choice.description, link_provider, current_plugin);
+            }
+        }
+    }
+    if let Some(default) = values.standalone_default() {
+        if !first {
+            appender.push_str("<br><br>");
+        }
+        appender.push_str("<b>Default:</b> <code>");
+        appender.push_owned_string(code(default));
+        appender.push_str("</code>");
+    }
+}
+
+/// Render choices and default value in the style of ansible-doc's text output.
+///
+/// `link_provider` and `current_plugin` will be used to compute optional URLs for the markup in
+/// descriptions of choices.
+pub fn append_ansible_doc_text_option_values<'a>(
+    appender: &mut dyn Appender<'a>,
+    values: &'a OptionValues<'a>,
+    link_provider: &dyn LinkProvider,
+    current_plugin: &Option<Rc<PluginIdentifier>>,
+) {
+    let style: &ParagraphStyle = &ANSIBLE_DOC_TEXT_STYLE;
+    let mut first = true;
+    if !values.choices.is_empty() {
+        first = false;
+        appender.push_str("choices:");
+        for choice in &values.choices {
+            appender.push_str("\n- ");
+            appender.push_owned_string(format_value(&choice.value));
+            if values.default.is_default(&choice.value) {
+                appender.push_str(" (default)");
+            }
+            if !choice.description.is_empty() {
+                appender.push_str("\n");
+                appender.push_owned_string(indent(
+                    &style.render(&choice.description, link_provider, current_plugin),
+                    "    ",
+                    "",
+                ));
+            }
+        }
+    }
+    if let Some(default) = values.standalone_default() {
+        if !first {
+            appender.push_str("\n");
+        }
+        appender.push_str("default: ");
+        appender.push_owned_string(format_value(default));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::NoLinkProvider;
+    use crate::util::stringbuilder::{CollectorAppender, IntoString};
+
+    type AppendOptionValues<'a> = fn(
+        &mut dyn Appender<'a>,
+        &'a OptionValues<'a>,
+        &dyn LinkProvider,
+        &Option<Rc<PluginIdentifier>>,
+    );
+
+    fn render<'a>(append: AppendOptionValues<'a>, values: &'a OptionValues<'a>) -> String {
+        let mut appender = CollectorAppender::new();
+        append(&mut appender, values, &NoLinkProvider::new(), &None);
+        appender.into_string()
+    }
+
+    fn load(input: &str) -> Yaml {
+        Yaml::load_from_str(input).unwrap().remove(0)
+    }
+
+    #[test]
+    fn test_format_value() {
+        assert_eq!(format_value(&load("''")), "\"\"");
+        assert_eq!(format_value(&load("~")), "null");
+        assert_eq!(format_value(&load("'true'")), "\"true\"");
+        assert_eq!(
+            format_value(&load("[a, 1, 1.5, false]")),
+            "[\"a\", 1, 1.5, false]"
+        );
+        assert_eq!(
+            format_value(&load("{a: {b: c}}")),
+            "{\"a\": {\"b\": \"c\"}}"
+        );
+    }
+
+    #[test]
+    fn test_option_values() {
+        let context = Context::new();
+        let opts = ParseOptions::default();
+        let default = load("b");
+        let choices = load("{a: Use C(a).,  b: [Use b., Really.]}");
+        let values =
+            OptionValues::new(false, Some(&default), Some(&choices), None, &context, &opts);
+        assert_eq!(
+            render(append_rst_option_values, &values),
+            ":ansible-option-choices:`Choices:`\n\n- :ansible-option-choices-entry:`\"a\"`\n\n  Use \\ :literal:`a`\\ .\n\n- :ansible-option-choices-entry-default:`\"b\"` :ansible-option-choices-default-mark:`← (default)`\n\n  Use b.\n\n  Really.\n"
+        );
+        assert_eq!(
+            render(append_ansible_doc_text_option_values, &values),
+            "choices:\n- \"a\"\n    Use `a'.\n- \"b\" (default)\n    Use b.\n    Really."
+        );
+        assert_eq!(
+            render(append_gfm_option_values, &values),
+            "<b>Choices:</b><br>- <code>\"a\"</code>: Use <code>a</code>\\.<br>- <code>\"b\"</code> ← (default): Use b\\.<br><br>Really\\."
+        );
+
+        let values = OptionValues::new(false, Some(&default), None, None, &context, &opts);
+        assert_eq!(
+            render(append_plain_html_option_values, &values),
+            "<p class=\"ansible-option-line\"><strong class=\"ansible-option-default-bold\">Default:</strong> <code class=\"ansible-option-default\">\"b\"</code></p>"
+        );
+
+        let values = OptionValues::new(false, None, None, Some("bool"), &context, &opts);
+        assert_eq!(
+            render(append_ansible_doc_text_option_values, &values),
+            "choices:\n- false\n- true"
+        );
+
+        let empty = load("''");
+        let values = OptionValues::new(false, Some(&empty), None, None, &context, &opts);
+        assert_eq!(
+            render(append_rst_option_values, &values),
+            ":ansible-option-default-bold:`Default:` :ansible-option-default:`\"\"`"
+        );
+
+        let values = OptionValues::new(true, Some(&default), None, None, &context, &opts);
+        assert!(values.is_empty());
+        let null = load("~");
+        let values = OptionValues::new(false, Some(&null), None, None, &context, &opts);
+        assert_eq!(values.default, DefaultValue::None);
+    }
+}
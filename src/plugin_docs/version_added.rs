@@ -0,0 +1,135 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Display of `version_added` information.
+
+use crate::markup::{HTMLEscaper, RSTEscaper};
+use crate::util::stringbuilder::Appender;
+use std::fmt;
+
+/// The special `version_added` value for features that have existed before version tracking.
+pub const HISTORICAL: &str = "historical";
+
+/// The name shown for versions of the `ansible.builtin` and `ansible.legacy` collections.
+pub const ANSIBLE_CORE: &str = "ansible-core";
+
+/// The version in which a feature was added, with the collection the version refers to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionAdded<'a> {
+    /// The version.
+    pub version: &'a str,
+
+    /// The collection the version refers to, if known.
+    pub collection: Option<&'a str>,
+}
+
+impl<'a> VersionAdded<'a> {
+    /// Combine a `version_added` / `version_added_collection` pair.
+    ///
+    /// Returns `None` if there is no version, or if the version is `historical`, since
+    /// antsibull-docs does not show these.
+    pub fn new(version: Option<&'a str>, collection: Option<&'a str>) -> Option<VersionAdded<'a>> {
+        match version {
+            None | Some(HISTORICAL) => None,
+            Some(version) => Some(VersionAdded {
+                version,
+                collection,
+            }),
+        }
+    }
+
+    /// The name of the product the version refers to.
+    ///
+    /// Versions of `ansible.builtin` and `ansible.legacy` are versions of ansible-core.
+    pub fn product(&self) -> Option<&'a str> {
+        match self.collection {
+            Some("ansible.builtin") | Some("ansible.legacy") => Some(ANSIBLE_CORE),
+            collection => collection,
+        }
+    }
+}
+
+impl fmt::Display for VersionAdded<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.product() {
+            Some(product) => write!(f, "added in {} of {}", self.version, product),
+            None => write!(f, "added in {}", self.version),
+        }
+    }
+}
+
+/// Render `version_added` information as RST, using the role of the `sphinx_antsibull_ext` extension.
+pub fn append_rst_version_added(appender: &mut dyn Appender<'_>, version_added: &VersionAdded) {
+    appender.push_str(":ansible-option-versionadded:`");
+    appender.push_owned_string(
+        RSTEscaper::new()
+            .escape(&version_added.to_string(), true, true)
+            .into_owned(),
+    );
+    appender.push_str("`");
+}
+
+/// Render `version_added` information as HTML.
+pub fn append_html_version_added(appender: &mut dyn Appender<'_>, version_added: &VersionAdded) {
+    appender
+        .push_str("<p class=\"ansible-option-line\"><span class=\"ansible-option-versionadded\">");
+    appender.push_owned_string(
+        HTMLEscaper::new()
+            .escape(&version_added.to_string())
+            .into_owned(),
+    );
+    appender.push_str("</span></p>");
+}
+
+/// Render `version_added` information in the style of ansible-doc's text output.
+pub fn append_ansible_doc_text_version_added(
+    appender: &mut dyn Appender<'_>,
+    version_added: &VersionAdded,
+) {
+    appender.push_owned_string(version_added.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::stringbuilder::{CollectorAppender, IntoString};
+
+    #[test]
+    fn test_version_added() {
+        assert_eq!(VersionAdded::new(None, Some("foo.bar")), None);
+        assert_eq!(VersionAdded::new(Some("historical"), None), None);
+        assert_eq!(
+            VersionAdded::new(Some("1.2.0"), Some("foo.bar"))
+                .unwrap()
+                .to_string(),
+            "added in 1.2.0 of foo.bar"
+        );
+        assert_eq!(
+            VersionAdded::new(Some("2.14"), Some("ansible.builtin"))
+                .unwrap()
+                .to_string(),
+            "added in 2.14 of ansible-core"
+        );
+        assert_eq!(
+            VersionAdded::new(Some("2.0.0"), None).unwrap().to_string(),
+            "added in 2.0.0"
+        );
+
+        let version_added = VersionAdded::new(Some("1.0.0"), Some("a_b.c")).unwrap();
+        let mut appender = CollectorAppender::new();
+        append_rst_version_added(&mut appender, &version_added);
+        assert_eq!(
+            appender.into_string(),
+            ":ansible-option-versionadded:`added in 1.0.0 of a\\_b.c`"
+        );
+        let mut appender = CollectorAppender::new();
+        append_html_version_added(&mut appender, &version_added);
+        assert_eq!(
+            appender.into_string(),
+            "<p class=\"ansible-option-line\"><span class=\"ansible-option-versionadded\">added in 1.0.0 of a_b.c</span></p>"
+        );
+    }
+}
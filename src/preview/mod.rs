@@ -0,0 +1,14 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! A web service that renders markup, for live documentation previews.
+
+mod request;
+mod server;
+
+pub use request::{PreviewFormat, PreviewRequest};
+
+pub use server::{handle_request, serve};
@@ -0,0 +1,265 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::markup::{
+    paragraphs_to_json, parse_paragraphs, Context, NoLinkProvider, ParseOptions, PluginIdentifier,
+    Renderer, UntrustedProfile,
+};
+use crate::util::json::push_json_string;
+use serde_json::{Map, Value};
+use std::rc::Rc;
+
+/// Extract an optional string from an object.
+fn get_string(
+    object: &Map<String, Value>,
+    key: &str,
+    path: &str,
+) -> Result<Option<String>, String> {
+    match object.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err(format!("{}.{}: expected a string", path, key)),
+    }
+}
+
+/// Extract an optional boolean from an object.
+fn get_bool(object: &Map<String, Value>, key: &str, path: &str) -> Result<Option<bool>, String> {
+    match object.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Bool(b)) => Ok(Some(*b)),
+        Some(_) => Err(format!("{}.{}: expected a boolean", path, key)),
+    }
+}
+
+/// Extract a string or list of strings from an object.
+///
+/// A single string is converted to a list with one element.
+fn get_string_list(
+    object: &Map<String, Value>,
+    key: &str,
+    path: &str,
+) -> Result<Vec<String>, String> {
+    match object.get(key) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::String(s)) => Ok(vec![s.clone()]),
+        Some(Value::Array(values)) => values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                value
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| format!("{}.{}[{}]: expected a string", path, key, index))
+            })
+            .collect(),
+        Some(_) => Err(format!(
+            "{}.{}: expected a string or a list of strings",
+            path, key
+        )),
+    }
+}
+
+/// The output formats of the preview service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewFormat {
+    AntsibullHTML,
+    PlainHTML,
+    MD,
+    AntsibullRST,
+    PlainRST,
+    AnsibleDocText,
+    BBCode,
+}
+
+impl PreviewFormat {
+    /// Look up a format by the name used in requests, like `antsibull-html` or `md`.
+    pub fn from_name(name: &str) -> Option<PreviewFormat> {
+        match name {
+            "antsibull-html" | "html" => Some(PreviewFormat::AntsibullHTML),
+            "plain-html" => Some(PreviewFormat::PlainHTML),
+            "md" => Some(PreviewFormat::MD),
+            "antsibull-rst" | "rst" => Some(PreviewFormat::AntsibullRST),
+            "plain-rst" => Some(PreviewFormat::PlainRST),
+            "ansible-doc-text" => Some(PreviewFormat::AnsibleDocText),
+            "bbcode" => Some(PreviewFormat::BBCode),
+            _ => None,
+        }
+    }
+
+    fn renderer(self, link_provider: &NoLinkProvider) -> Renderer<'_> {
+        match self {
+            PreviewFormat::AntsibullHTML => Renderer::antsibull_html(link_provider),
+            PreviewFormat::PlainHTML => Renderer::plain_html(link_provider),
+            PreviewFormat::MD => Renderer::md(link_provider),
+            PreviewFormat::AntsibullRST => Renderer::antsibull_rst(link_provider),
+            PreviewFormat::PlainRST => Renderer::plain_rst(link_provider),
+            PreviewFormat::AnsibleDocText => Renderer::ansible_doc_text(link_provider),
+            PreviewFormat::BBCode => Renderer::bbcode(link_provider),
+        }
+    }
+}
+
+/// A request to render markup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviewRequest {
+    /// The paragraphs to render.
+    pub paragraphs: Vec<String>,
+
+    /// The output format.
+    pub format: PreviewFormat,
+
+    /// The plugin the markup belongs to.
+    pub current_plugin: Option<PluginIdentifier>,
+
+    /// The role entrypoint the markup belongs to.
+    pub role_entrypoint: Option<String>,
+
+    /// Whether to parse strictly.
+    pub strict: bool,
+
+    /// Whether to only allow classic markup.
+    pub only_classic_markup: bool,
+
+    /// Whether to render with [`UntrustedProfile`].
+    pub untrusted: bool,
+}
+
+impl PreviewRequest {
+    /// Parse a request from a JSON object.
+    ///
+    /// The object must have a `markup` field with a string or a list of strings, one per
+    /// paragraph. The optional fields are `format` (defaults to `antsibull-html`),
+    /// `current_plugin` (an object with `fqcn` and `type`), `role_entrypoint`, `strict`,
+    /// `classic_markup`, and `untrusted`.
+    pub fn from_json(body: &str) -> Result<PreviewRequest, String> {
+        let request: Value = serde_json::from_str(body).map_err(|error| error.to_string())?;
+        let Some(hash) = request.as_object() else {
+            return Err("request: expected an object".to_string());
+        };
+        if hash.get("markup").is_none() {
+            return Err("request.markup: missing".to_string());
+        }
+        let format = match get_string(hash, "format", "request")? {
+            Some(name) => PreviewFormat::from_name(&name)
+                .ok_or_else(|| format!("request.format: unknown format {:?}", name))?,
+            None => PreviewFormat::AntsibullHTML,
+        };
+        let current_plugin = match hash.get("current_plugin").filter(|v| !v.is_null()) {
+            Some(plugin) => {
+                let Some(plugin) = plugin.as_object() else {
+                    return Err("request.current_plugin: expected an object".to_string());
+                };
+                let field = |key: &str| {
+                    get_string(plugin, key, "request.current_plugin")?
+                        .ok_or_else(|| format!("request.current_plugin.{}: missing", key))
+                };
+                Some(PluginIdentifier {
+                    fqcn: field("fqcn")?,
+                    r#type: field("type")?,
+                })
+            }
+            None => None,
+        };
+        Ok(PreviewRequest {
+            paragraphs: get_string_list(hash, "markup", "request")?,
+            format,
+            current_plugin,
+            role_entrypoint: get_string(hash, "role_entrypoint", "request")?,
+            strict: get_bool(hash, "strict", "request")?.unwrap_or(false),
+            only_classic_markup: get_bool(hash, "classic_markup", "request")?.unwrap_or(false),
+            untrusted: get_bool(hash, "untrusted", "request")?.unwrap_or(false),
+        })
+    }
+
+    /// Render the markup, and return a JSON object with the `output`, whether the output was
+    /// `truncated`, and the parsed paragraphs as `dom` (see [`paragraphs_to_json`]).
+    pub fn render(&self) -> String {
+        let current_plugin = self.current_plugin.clone().map(Rc::new);
+        let context = Context {
+            current_plugin: current_plugin.clone(),
+            role_entrypoint: self.role_entrypoint.clone().map(Rc::new),
+            ..Context::new()
+        };
+        let mut opts = ParseOptions::default();
+        if self.strict {
+            opts = opts.strict();
+        }
+        if self.only_classic_markup {
+            opts = opts.only_classic_markup();
+        }
+        let paragraphs =
+            parse_paragraphs(self.paragraphs.iter().map(String::as_str), &context, &opts);
+        let link_provider = NoLinkProvider::new();
+        let renderer = self
+            .format
+            .renderer(&link_provider)
+            .with_current_plugin(current_plugin);
+        let (output, truncated) = if self.untrusted {
+            let rendering = renderer.render_untrusted(&UntrustedProfile::new(), &paragraphs);
+            (rendering.output, rendering.truncated)
+        } else {
+            (renderer.render(&paragraphs), false)
+        };
+        let mut result = String::from("{\"output\": ");
+        push_json_string(&mut result, &output);
+        result.push_str(", \"truncated\": ");
+        result.push_str(if truncated { "true" } else { "false" });
+        result.push_str(", \"dom\": ");
+        result.push_str(&paragraphs_to_json(&paragraphs));
+        result.push('}');
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_request() {
+        let request = PreviewRequest::from_json(
+            r#"{"markup": ["O(foo)", "U(https://example.com)"], "format": "md", "current_plugin": {"fqcn": "foo.bar.baz", "type": "module"}, "untrusted": true}"#,
+        )
+        .unwrap();
+        assert_eq!(request.format, PreviewFormat::MD);
+        assert_eq!(request.current_plugin.as_ref().unwrap().fqcn, "foo.bar.baz");
+        assert_eq!(
+            request.render(),
+            concat!(
+                r#"{"output": "<code><strong>foo</strong></code>\n\nhttps\\://example\\.com", "truncated": false, "dom": "#,
+                r#"[[{"type": "option_name", "plugin": {"fqcn": "foo.bar.baz", "type": "module"}, "entrypoint": null, "link": ["foo"], "name": "foo", "value": null, "source": "O(foo)"}], "#,
+                r#"[{"type": "url", "url": "https://example.com", "source": "U(https://example.com)"}]]}"#,
+            )
+        );
+
+        assert_eq!(
+            PreviewRequest::from_json(r#"{"markup": "B(x)"}"#)
+                .unwrap()
+                .render(),
+            r#"{"output": "<p><b>x</b></p>", "truncated": false, "dom": [[{"type": "bold", "text": "x", "source": "B(x)"}]]}"#
+        );
+        assert_eq!(
+            PreviewRequest::from_json(r#"{"markup": "x", "format": "pdf"}"#).unwrap_err(),
+            "request.format: unknown format \"pdf\""
+        );
+        assert_eq!(
+            PreviewRequest::from_json(r#"{"format": "md"}"#).unwrap_err(),
+            "request.markup: missing"
+        );
+        assert_eq!(
+            PreviewRequest::from_json("[]").unwrap_err(),
+            "request: expected an object"
+        );
+        assert_eq!(
+            PreviewRequest::from_json(r#"{"markup": "x", "strict": "yes"}"#).unwrap_err(),
+            "request.strict: expected a boolean"
+        );
+        assert_eq!(
+            PreviewRequest::from_json("markup: x").unwrap_err(),
+            "expected value at line 1 column 1"
+        );
+    }
+}
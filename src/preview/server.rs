@@ -0,0 +1,230 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+use crate::preview::request::PreviewRequest;
+use crate::util::json::push_json_string;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// The maximal size of a request body in bytes.
+const MAX_BODY_LENGTH: usize = 1 << 20;
+
+/// The maximal size of the request line and the headers together in bytes.
+const MAX_HEAD_LENGTH: u64 = 16 << 10;
+
+/// The maximal number of connections that are handled at the same time. Further connections
+/// are rejected with `503 Service Unavailable`.
+const MAX_CONNECTIONS: usize = 64;
+
+/// How long reading a request or writing a response may block.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn respond(writer: &mut dyn Write, status: &str, body: &str) -> io::Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    writer.flush()
+}
+
+fn respond_error(writer: &mut dyn Write, status: &str, message: &str) -> io::Result<()> {
+    let mut body = String::from("{\"error\": ");
+    push_json_string(&mut body, message);
+    body.push('}');
+    respond(writer, status, &body)
+}
+
+/// Read a line of the request line and headers, or return `None` if they are too long.
+fn read_head_line<R: BufRead>(head: &mut io::Take<R>) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    head.read_line(&mut line)?;
+    if head.limit() == 0 && !line.ends_with('\n') {
+        return Ok(None);
+    }
+    Ok(Some(line))
+}
+
+/// Handle one HTTP request.
+///
+/// `POST /render` with a JSON body as described in [`PreviewRequest::from_json`] responds with
+/// the JSON object returned by [`PreviewRequest::render`]. Errors are reported as JSON objects
+/// with an `error` field.
+pub fn handle_request<R: BufRead, W: Write>(reader: &mut R, writer: &mut W) -> io::Result<()> {
+    let too_large = |writer: &mut W| {
+        respond_error(
+            writer,
+            "431 Request Header Fields Too Large",
+            "Request headers are too large",
+        )
+    };
+    let mut head = (&mut *reader).take(MAX_HEAD_LENGTH);
+    let Some(request_line) = read_head_line(&mut head)? else {
+        return too_large(writer);
+    };
+    let mut request_line = request_line.split_whitespace();
+    let (method, path) = (request_line.next(), request_line.next());
+
+    let mut content_length = 0;
+    loop {
+        let Some(header) = read_head_line(&mut head)? else {
+            return too_large(writer);
+        };
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                match value.trim().parse() {
+                    Ok(length) => content_length = length,
+                    Err(_) => {
+                        return respond_error(writer, "400 Bad Request", "Invalid Content-Length")
+                    }
+                }
+            }
+        }
+    }
+
+    match (method, path) {
+        (Some("POST"), Some("/render")) => {}
+        (Some(_), Some("/render")) => {
+            return respond_error(writer, "405 Method Not Allowed", "Use POST")
+        }
+        _ => return respond_error(writer, "404 Not Found", "Not found"),
+    }
+    if content_length > MAX_BODY_LENGTH {
+        return respond_error(writer, "413 Content Too Large", "Request body is too large");
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    let Ok(body) = String::from_utf8(body) else {
+        return respond_error(writer, "400 Bad Request", "Request body is not UTF-8");
+    };
+    match PreviewRequest::from_json(&body) {
+        Ok(request) => respond(writer, "200 OK", &request.render()),
+        Err(error) => respond_error(writer, "400 Bad Request", &error),
+    }
+}
+
+/// Counts the connections that are handled at the same time.
+struct ConnectionLimit {
+    active: AtomicUsize,
+    max: usize,
+}
+
+impl ConnectionLimit {
+    fn new(max: usize) -> Arc<ConnectionLimit> {
+        Arc::new(ConnectionLimit {
+            active: AtomicUsize::new(0),
+            max,
+        })
+    }
+
+    /// Reserve a slot for a connection, or return `None` if all slots are taken.
+    fn acquire(self: &Arc<Self>) -> Option<ConnectionSlot> {
+        self.active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |active| {
+                (active < self.max).then_some(active + 1)
+            })
+            .ok()
+            .map(|_| ConnectionSlot(Arc::clone(self)))
+    }
+}
+
+/// A slot of a [`ConnectionLimit`], which is released when it is dropped.
+struct ConnectionSlot(Arc<ConnectionLimit>);
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.active.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn handle_connection(mut stream: TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    handle_request(&mut reader, &mut stream)
+}
+
+/// Serve preview requests on `listener`, handling every connection in its own thread.
+///
+/// At most 64 connections are handled at the same time; further connections are rejected with
+/// `503 Service Unavailable`. Reading a request or writing a response times out after ten
+/// seconds.
+pub fn serve(listener: TcpListener) -> io::Result<()> {
+    let limit = ConnectionLimit::new(MAX_CONNECTIONS);
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        let Some(slot) = limit.acquire() else {
+            if let Err(error) =
+                respond_error(&mut stream, "503 Service Unavailable", "Too many requests")
+            {
+                eprintln!("Error while rejecting request: {}", error);
+            }
+            continue;
+        };
+        thread::spawn(move || {
+            if let Err(error) = handle_connection(stream) {
+                eprintln!("Error while handling request: {}", error);
+            }
+            drop(slot);
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(request: &str) -> String {
+        let mut response = Vec::new();
+        handle_request(&mut request.as_bytes(), &mut response).unwrap();
+        String::from_utf8(response).unwrap()
+    }
+
+    #[test]
+    fn test_handle_request() {
+        let body = r#"{"markup": "C(x)", "format": "rst"}"#;
+        let response = request(&format!(
+            "POST /render HTTP/1.1\r\nHost: localhost\r\ncontent-length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        ));
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(r#"{"output": "\\ :literal:`x`\\ ", "truncated": false, "dom": [[{"type": "code", "text": "x", "source": "C(x)"}]]}"#));
+
+        let response = request("POST /render HTTP/1.1\r\nContent-Length: 2\r\n\r\n[]");
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(response.ends_with(r#"{"error": "request: expected an object"}"#));
+        assert!(request("GET /render HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 405 "));
+        assert!(request("GET / HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 404 "));
+        assert!(
+            request("POST /render HTTP/1.1\r\nContent-Length: 9999999\r\n\r\n")
+                .starts_with("HTTP/1.1 413 ")
+        );
+        let huge_header = format!(
+            "POST /render HTTP/1.1\r\nX: {}\r\n\r\n",
+            "x".repeat(1 << 20)
+        );
+        assert!(request(&huge_header).starts_with("HTTP/1.1 431 "));
+
+        let limit = ConnectionLimit::new(1);
+        let slot = limit.acquire();
+        assert!(slot.is_some());
+        assert!(limit.acquire().is_none());
+        drop(slot);
+        assert!(limit.acquire().is_some());
+    }
+}
@@ -0,0 +1,190 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Validation of generated RST with docutils.
+//!
+//! This runs a Python interpreter with docutils installed as a subprocess, so it is only
+//! available with the `docutils` feature. The Sphinx and antsibull-docs roles used by the RST
+//! formatters are registered as generic roles, so that only markup problems are reported.
+
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+const CHECK_SCRIPT: &str = r#"
+import sys
+from docutils import nodes
+from docutils.core import publish_doctree
+from docutils.parsers.rst import roles
+
+for role in ('ref', 'envvar', 'ansopt', 'ansretval', 'ansval'):
+    roles.register_generic_role(role, nodes.literal)
+
+document = publish_doctree(
+    sys.stdin.read(),
+    settings_overrides={'report_level': 5, 'halt_level': 5, 'input_encoding': 'unicode'},
+)
+for message in document.traverse(nodes.system_message):
+    text = ' '.join(message.children[0].astext().split()) if message.children else ''
+    print('{0}\t{1}\t{2}'.format(message['level'], message.get('line') or '', text))
+"#;
+
+/// A warning or error reported by docutils.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocutilsMessage {
+    /// The docutils level: 1 is info, 2 warning, 3 error, and 4 severe.
+    pub level: u8,
+
+    /// The line in the input the message refers to, if known.
+    pub line: Option<usize>,
+
+    /// The message text.
+    pub message: String,
+}
+
+impl fmt::Display for DocutilsMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.level {
+            0 | 1 => "INFO",
+            2 => "WARNING",
+            3 => "ERROR",
+            _ => "SEVERE",
+        };
+        match self.line {
+            Some(line) => write!(f, "line {}: {}: {}", line, level, self.message),
+            None => write!(f, "{}: {}", level, self.message),
+        }
+    }
+}
+
+/// Runs RST through docutils and collects its warnings and errors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocutilsChecker {
+    /// The Python interpreter that has docutils installed.
+    pub python: PathBuf,
+
+    /// The minimal level of messages that are reported. Defaults to 2 (warnings).
+    pub min_level: u8,
+}
+
+impl DocutilsChecker {
+    /// Use `python3` from `PATH`.
+    pub fn new() -> DocutilsChecker {
+        DocutilsChecker {
+            python: "python3".into(),
+            min_level: 2,
+        }
+    }
+
+    /// Parse `rst` with docutils and return all messages of at least `min_level`.
+    ///
+    /// An error is returned if docutils cannot be run.
+    pub fn check(&self, rst: &str) -> Result<Vec<DocutilsMessage>, String> {
+        let mut child = Command::new(&self.python)
+            .arg("-c")
+            .arg(CHECK_SCRIPT)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|error| format!("Cannot run {}: {}", self.python.display(), error))?;
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(rst.as_bytes())
+            .map_err(|error| format!("Cannot write to docutils: {}", error))?;
+        let output = child
+            .wait_with_output()
+            .map_err(|error| format!("Error while running docutils: {}", error))?;
+        if !output.status.success() {
+            return Err(format!(
+                "docutils failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        let mut messages = Vec::new();
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let mut fields = line.splitn(3, '\t');
+            let (Some(level), Some(line), Some(message)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(format!("Cannot parse docutils output {:?}", line));
+            };
+            let level = level
+                .parse()
+                .map_err(|_| format!("Cannot parse docutils output {:?}", line))?;
+            if level >= self.min_level {
+                messages.push(DocutilsMessage {
+                    level,
+                    line: line.parse().ok(),
+                    message: message.to_string(),
+                });
+            }
+        }
+        Ok(messages)
+    }
+
+    /// Check a corpus of named RST documents.
+    ///
+    /// Returns an error listing all documents with messages, so that all regressions are
+    /// visible at once.
+    pub fn check_corpus<'a, I>(&self, corpus: I) -> Result<(), String>
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        let mut failures = Vec::new();
+        for (name, rst) in corpus {
+            for message in self.check(rst)? {
+                failures.push(format!("{}: {}", name, message));
+            }
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures.join("\n"))
+        }
+    }
+}
+
+impl Default for DocutilsChecker {
+    fn default() -> Self {
+        DocutilsChecker::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::yaml;
+    use saphyr::Yaml;
+
+    #[test]
+    fn test_test_vectors() {
+        let checker = DocutilsChecker::new();
+        let messages = checker.check("Foo\n===\n\n`broken\n").unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].level, 2);
+
+        let contents = std::fs::read_to_string("test-vectors.yaml").unwrap();
+        let data = Yaml::load_from_str(&contents).unwrap();
+        let vectors = yaml::as_hash(&data[0]["test_vectors"], "test_vectors").unwrap();
+        let mut corpus = Vec::new();
+        for (name, vector) in vectors {
+            let vector = vector.as_hash().unwrap();
+            for key in ["rst", "rst_plain"] {
+                if let Some(rst) = yaml::get(vector, key).and_then(|v| v.as_str()) {
+                    corpus.push((format!("{} ({})", name.as_str().unwrap(), key), rst));
+                }
+            }
+        }
+        assert!(!corpus.is_empty());
+        checker
+            .check_corpus(corpus.iter().map(|(name, rst)| (name.as_str(), *rst)))
+            .unwrap();
+    }
+}
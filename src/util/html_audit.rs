@@ -0,0 +1,444 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Verification of rendered HTML against an allow-list of tags and attributes.
+//!
+//! This is meant as a second line of defense after escaping: it catches markup injected through
+//! crafted option values or URLs that slipped through the renderers.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Elements that have no end tag.
+const VOID_ELEMENTS: &[&str] = &["br", "hr", "img", "wbr"];
+
+/// Attributes containing URLs, whose scheme is checked.
+const URL_ATTRIBUTES: &[&str] = &["href", "src"];
+
+/// A problem found in an HTML fragment. `offset` is the byte offset in the fragment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HtmlViolation {
+    /// An element that is not on the allow-list.
+    DisallowedTag { offset: usize, tag: String },
+
+    /// An attribute that is not allowed for its element.
+    DisallowedAttribute {
+        offset: usize,
+        tag: String,
+        attribute: String,
+    },
+
+    /// An URL with a scheme that is not allowed, like `javascript:`.
+    DisallowedUrl {
+        offset: usize,
+        tag: String,
+        attribute: String,
+        url: String,
+    },
+
+    /// An end tag without a matching start tag, or a start tag that is never closed.
+    UnbalancedTag { offset: usize, tag: String },
+
+    /// Something that cannot be parsed, like a comment or an unterminated tag.
+    Malformed { offset: usize, message: String },
+}
+
+impl fmt::Display for HtmlViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HtmlViolation::DisallowedTag { offset, tag } => {
+                write!(f, "Offset {}: element <{}> is not allowed", offset, tag)
+            }
+            HtmlViolation::DisallowedAttribute {
+                offset,
+                tag,
+                attribute,
+            } => write!(
+                f,
+                "Offset {}: attribute {:?} is not allowed for <{}>",
+                offset, attribute, tag
+            ),
+            HtmlViolation::DisallowedUrl {
+                offset,
+                tag,
+                attribute,
+                url,
+            } => write!(
+                f,
+                "Offset {}: URL {:?} in attribute {:?} of <{}> is not allowed",
+                offset, url, attribute, tag
+            ),
+            HtmlViolation::UnbalancedTag { offset, tag } => {
+                write!(f, "Offset {}: unbalanced <{}>", offset, tag)
+            }
+            HtmlViolation::Malformed { offset, message } => {
+                write!(f, "Offset {}: {}", offset, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HtmlViolation {}
+
+/// The elements, attributes, and URL schemes that may appear in rendered HTML.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlAllowList {
+    tags: BTreeMap<String, BTreeSet<String>>,
+    global_attributes: BTreeSet<String>,
+    url_schemes: BTreeSet<String>,
+}
+
+impl HtmlAllowList {
+    /// An allow-list that allows nothing.
+    pub fn new() -> HtmlAllowList {
+        HtmlAllowList {
+            tags: BTreeMap::new(),
+            global_attributes: BTreeSet::new(),
+            url_schemes: BTreeSet::new(),
+        }
+    }
+
+    /// The allow-list for everything this crate renders as HTML.
+    pub fn antsibull() -> HtmlAllowList {
+        let mut allow_list = HtmlAllowList::new()
+            .allow_global_attributes(&["class", "id"])
+            .allow_url_schemes(&["http", "https", "mailto"])
+            .allow_tag("a", &["href"])
+            .allow_tag("ol", &["start"]);
+        for tag in [
+            "b", "br", "code", "dd", "div", "dl", "dt", "em", "h1", "h2", "h3", "h4", "h5", "h6",
+            "hr", "i", "li", "nav", "p", "pre", "span", "strong", "table", "tbody", "td", "th",
+            "thead", "tr", "ul",
+        ] {
+            allow_list = allow_list.allow_tag(tag, &[]);
+        }
+        allow_list
+    }
+
+    /// Allow the element `tag` with the attributes `attributes`, in addition to the global ones.
+    pub fn allow_tag(mut self, tag: &str, attributes: &[&str]) -> HtmlAllowList {
+        self.tags
+            .entry(tag.to_ascii_lowercase())
+            .or_default()
+            .extend(attributes.iter().map(|a| a.to_ascii_lowercase()));
+        self
+    }
+
+    /// Allow the attributes `attributes` for all allowed elements.
+    pub fn allow_global_attributes(mut self, attributes: &[&str]) -> HtmlAllowList {
+        self.global_attributes
+            .extend(attributes.iter().map(|a| a.to_ascii_lowercase()));
+        self
+    }
+
+    /// Allow URLs with the schemes `schemes`. Relative URLs are always allowed.
+    pub fn allow_url_schemes(mut self, schemes: &[&str]) -> HtmlAllowList {
+        self.url_schemes
+            .extend(schemes.iter().map(|s| s.to_ascii_lowercase()));
+        self
+    }
+
+    fn is_attribute_allowed(&self, tag: &str, attribute: &str) -> bool {
+        self.global_attributes.contains(attribute)
+            || self
+                .tags
+                .get(tag)
+                .is_some_and(|attributes| attributes.contains(attribute))
+    }
+
+    fn is_url_allowed(&self, url: &str) -> bool {
+        // Browsers ignore whitespace and control characters in schemes
+        let url: String = url
+            .chars()
+            .filter(|c| !c.is_whitespace() && !c.is_control())
+            .collect();
+        match url.find([':', '/', '?', '#']) {
+            Some(index) if url[index..].starts_with(':') => self
+                .url_schemes
+                .contains(&url[..index].to_ascii_lowercase()),
+            _ => true,
+        }
+    }
+}
+
+impl Default for HtmlAllowList {
+    fn default() -> HtmlAllowList {
+        HtmlAllowList::antsibull()
+    }
+}
+
+/// Decode the character references that can be used to hide a URL scheme.
+fn decode_entities(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(index) = rest.find('&') {
+        result.push_str(&rest[..index]);
+        rest = &rest[index..];
+        let decoded = rest.find(';').and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "colon" => Some(':'),
+                _ => match entity.strip_prefix('#') {
+                    Some(number) => match number.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => number.parse().ok(),
+                    }
+                    .and_then(char::from_u32),
+                    None => None,
+                },
+            };
+            c.map(|c| (c, end + 1))
+        });
+        match decoded {
+            Some((c, length)) => {
+                result.push(c);
+                rest = &rest[length..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+struct Tag<'a> {
+    name: String,
+    end: bool,
+    self_closing: bool,
+    attributes: Vec<(String, Option<&'a str>)>,
+}
+
+/// Parse the tag starting at `html[start]` (which is `<`), returning the tag and the offset after it.
+fn parse_tag(html: &str, start: usize) -> Result<(Tag<'_>, usize), String> {
+    let bytes = html.as_bytes();
+    let mut index = start + 1;
+    let end = bytes.get(index) == Some(&b'/');
+    if end {
+        index += 1;
+    }
+    let name_start = index;
+    while index < bytes.len() && (bytes[index].is_ascii_alphanumeric() || bytes[index] == b'-') {
+        index += 1;
+    }
+    if index == name_start {
+        return Err("unescaped '<'".to_string());
+    }
+    let mut tag = Tag {
+        name: html[name_start..index].to_ascii_lowercase(),
+        end,
+        self_closing: false,
+        attributes: Vec::new(),
+    };
+    loop {
+        while index < bytes.len() && bytes[index].is_ascii_whitespace() {
+            index += 1;
+        }
+        match bytes.get(index) {
+            None => return Err(format!("unterminated <{}>", tag.name)),
+            Some(b'>') => return Ok((tag, index + 1)),
+            Some(b'/') if bytes.get(index + 1) == Some(&b'>') => {
+                tag.self_closing = true;
+                return Ok((tag, index + 2));
+            }
+            Some(_) => {}
+        }
+        let attribute_start = index;
+        while index < bytes.len()
+            && !bytes[index].is_ascii_whitespace()
+            && !matches!(bytes[index], b'=' | b'>' | b'/' | b'"' | b'\'')
+        {
+            index += 1;
+        }
+        if index == attribute_start {
+            return Err(format!("invalid attribute in <{}>", tag.name));
+        }
+        let attribute = html[attribute_start..index].to_ascii_lowercase();
+        while index < bytes.len() && bytes[index].is_ascii_whitespace() {
+            index += 1;
+        }
+        if bytes.get(index) != Some(&b'=') {
+            tag.attributes.push((attribute, None));
+            continue;
+        }
+        index += 1;
+        while index < bytes.len() && bytes[index].is_ascii_whitespace() {
+            index += 1;
+        }
+        let value = match bytes.get(index) {
+            Some(quote @ (b'"' | b'\'')) => {
+                let value_start = index + 1;
+                let length = html[value_start..]
+                    .find(*quote as char)
+                    .ok_or_else(|| format!("unterminated attribute value in <{}>", tag.name))?;
+                index = value_start + length + 1;
+                &html[value_start..value_start + length]
+            }
+            _ => {
+                let value_start = index;
+                while index < bytes.len()
+                    && !bytes[index].is_ascii_whitespace()
+                    && bytes[index] != b'>'
+                {
+                    index += 1;
+                }
+                &html[value_start..index]
+            }
+        };
+        tag.attributes.push((attribute, Some(value)));
+    }
+}
+
+/// Check a rendered HTML fragment against an allow-list.
+///
+/// All violations are returned, so an empty result means that the fragment is fine.
+pub fn audit_html(html: &str, allow_list: &HtmlAllowList) -> Vec<HtmlViolation> {
+    let mut violations = Vec::new();
+    let mut open: Vec<(String, usize)> = Vec::new();
+    let mut index = 0;
+    while let Some(found) = html[index..].find('<') {
+        let offset = index + found;
+        if html[offset..].starts_with("<!--") {
+            violations.push(HtmlViolation::Malformed {
+                offset,
+                message: "comments are not allowed".to_string(),
+            });
+            index = html[offset..]
+                .find("-->")
+                .map_or(html.len(), |end| offset + end + 3);
+            continue;
+        }
+        let (tag, next) = match parse_tag(html, offset) {
+            Ok(result) => result,
+            Err(message) => {
+                violations.push(HtmlViolation::Malformed { offset, message });
+                index = offset + 1;
+                continue;
+            }
+        };
+        index = next;
+        if !allow_list.tags.contains_key(&tag.name) {
+            violations.push(HtmlViolation::DisallowedTag {
+                offset,
+                tag: tag.name,
+            });
+            continue;
+        }
+        if tag.end {
+            match open.iter().rposition(|(name, _)| *name == tag.name) {
+                Some(position) => {
+                    for (name, offset) in open.drain(position..).skip(1) {
+                        violations.push(HtmlViolation::UnbalancedTag { offset, tag: name });
+                    }
+                }
+                None => violations.push(HtmlViolation::UnbalancedTag {
+                    offset,
+                    tag: tag.name,
+                }),
+            }
+            continue;
+        }
+        for (attribute, value) in &tag.attributes {
+            if !allow_list.is_attribute_allowed(&tag.name, attribute) {
+                violations.push(HtmlViolation::DisallowedAttribute {
+                    offset,
+                    tag: tag.name.clone(),
+                    attribute: attribute.clone(),
+                });
+            } else if let (true, Some(value)) =
+                (URL_ATTRIBUTES.contains(&attribute.as_str()), value)
+            {
+                let url = decode_entities(value);
+                if !allow_list.is_url_allowed(&url) {
+                    violations.push(HtmlViolation::DisallowedUrl {
+                        offset,
+                        tag: tag.name.clone(),
+                        attribute: attribute.clone(),
+                        url,
+                    });
+                }
+            }
+        }
+        if !tag.self_closing && !VOID_ELEMENTS.contains(&tag.name.as_str()) {
+            open.push((tag.name, offset));
+        }
+    }
+    violations.extend(
+        open.into_iter()
+            .map(|(tag, offset)| HtmlViolation::UnbalancedTag { offset, tag }),
+    );
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_html() {
+        let allow_list = HtmlAllowList::antsibull();
+        assert_eq!(
+            audit_html(
+                "<p class=\"x\">A <a href=\"https://example.com/?a=1&amp;b\">link</a> &lt;b&gt;<br></p><ol start=3><li>x</li></ol>",
+                &allow_list
+            ),
+            vec![]
+        );
+        assert_eq!(
+            audit_html(
+                "<p onclick=\"x()\"><script>x</script><a href=\" java&#115;cript:x\">y</a></p>",
+                &allow_list
+            )
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>(),
+            vec![
+                "Offset 0: attribute \"onclick\" is not allowed for <p>",
+                "Offset 17: element <script> is not allowed",
+                "Offset 26: element <script> is not allowed",
+                "Offset 35: URL \" javascript:x\" in attribute \"href\" of <a> is not allowed",
+            ]
+        );
+        assert_eq!(
+            audit_html("<p><em>x</p><!-- y --> 1 < 2 <b", &allow_list),
+            vec![
+                HtmlViolation::UnbalancedTag {
+                    offset: 3,
+                    tag: "em".to_string()
+                },
+                HtmlViolation::Malformed {
+                    offset: 12,
+                    message: "comments are not allowed".to_string()
+                },
+                HtmlViolation::Malformed {
+                    offset: 25,
+                    message: "unescaped '<'".to_string()
+                },
+                HtmlViolation::Malformed {
+                    offset: 29,
+                    message: "unterminated <b>".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            audit_html("<custom-tag data-x=1></custom-tag>", &allow_list).len(),
+            2
+        );
+        let allow_list = HtmlAllowList::new().allow_tag("custom-tag", &["data-x"]);
+        assert_eq!(
+            audit_html("<custom-tag data-x=1></custom-tag>", &allow_list),
+            vec![]
+        );
+        assert_eq!(decode_entities("a&#x3A;&colon;&amp;&foo;&"), "a::&&foo;&");
+    }
+}
@@ -0,0 +1,298 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Minification and pretty-printing of HTML while it is being written.
+
+use crate::util::stringbuilder::Appender;
+use alloc::borrow::Cow;
+use alloc::string::String;
+
+/// Elements that start a new block. Whitespace next to their tags is insignificant.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hr",
+    "html",
+    "li",
+    "link",
+    "main",
+    "meta",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "title",
+    "tr",
+    "ul",
+];
+
+/// Block elements that have no end tag.
+const VOID_BLOCK_ELEMENTS: &[&str] = &["hr", "link", "meta"];
+
+/// Elements whose content is written unchanged.
+const PRESERVING_ELEMENTS: &[&str] = &["pre", "script", "style", "textarea"];
+
+/// How [`HtmlLayoutAppender`] changes the layout of HTML.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HtmlLayout {
+    /// Write the HTML unchanged.
+    #[default]
+    Unchanged,
+
+    /// Collapse whitespace in text to single spaces, and remove whitespace next to tags of
+    /// block elements.
+    Minified,
+
+    /// Like [`HtmlLayout::Minified`], but put the tags of block elements and the content
+    /// between them on lines of their own, indented by two spaces per nesting level.
+    Pretty,
+}
+
+/// An [`Appender`] that changes the layout of the HTML written to it before passing it on.
+///
+/// The HTML can be split arbitrarily between calls, so this works with any HTML formatter.
+/// The content of `pre`, `script`, `style`, and `textarea` elements is never changed. Call
+/// [`HtmlLayoutAppender::finish`] after writing everything.
+pub struct HtmlLayoutAppender<'a, 'b> {
+    inner: &'b mut dyn Appender<'a>,
+    layout: HtmlLayout,
+    tag: Option<String>,
+    pending_space: bool,
+    after_block: bool,
+    at_start: bool,
+    preserve: usize,
+    depth: usize,
+}
+
+impl<'a, 'b> HtmlLayoutAppender<'a, 'b> {
+    pub fn new(inner: &'b mut dyn Appender<'a>, layout: HtmlLayout) -> HtmlLayoutAppender<'a, 'b> {
+        HtmlLayoutAppender {
+            inner,
+            layout,
+            tag: None,
+            pending_space: false,
+            after_block: true,
+            at_start: true,
+            preserve: 0,
+            depth: 0,
+        }
+    }
+
+    /// Write out an unterminated tag, if any. Trailing whitespace is dropped.
+    pub fn finish(mut self) {
+        if let Some(tag) = self.tag.take() {
+            self.inner.push_owned_string(tag);
+        }
+    }
+
+    fn new_line(&mut self, output: &mut String) {
+        if !self.at_start {
+            output.push('\n');
+        }
+        for _ in 0..self.depth {
+            output.push_str("  ");
+        }
+    }
+
+    fn write_tag(&mut self, tag: &str, output: &mut String) {
+        let closing = tag.starts_with("</");
+        let name = tag
+            .trim_start_matches(['<', '/'])
+            .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let name = name.as_str();
+        let self_closing = tag.ends_with("/>") || tag.starts_with("<!");
+        if self.preserve > 0 {
+            if PRESERVING_ELEMENTS.contains(&name) {
+                if closing {
+                    self.preserve -= 1;
+                } else if !self_closing {
+                    self.preserve += 1;
+                }
+            }
+            output.push_str(tag);
+            if self.preserve == 0 {
+                self.depth = self.depth.saturating_sub(1);
+                self.after_block = BLOCK_ELEMENTS.contains(&name);
+                self.pending_space = false;
+            }
+            return;
+        }
+        let block = BLOCK_ELEMENTS.contains(&name);
+        let pretty = self.layout == HtmlLayout::Pretty;
+        if block {
+            if closing {
+                self.depth = self.depth.saturating_sub(1);
+            }
+            if pretty {
+                self.new_line(output);
+            }
+        } else if pretty && self.after_block {
+            self.new_line(output);
+        } else if self.pending_space && !self.after_block {
+            output.push(' ');
+        }
+        output.push_str(tag);
+        self.pending_space = false;
+        self.at_start = false;
+        self.after_block = block;
+        if block && !closing && !self_closing && !VOID_BLOCK_ELEMENTS.contains(&name) {
+            self.depth += 1;
+        }
+        if PRESERVING_ELEMENTS.contains(&name) && !closing && !self_closing {
+            self.preserve = 1;
+            if !block {
+                self.depth += 1;
+            }
+        }
+    }
+
+    fn write(&mut self, value: &str) {
+        let mut output = String::with_capacity(value.len());
+        for c in value.chars() {
+            if let Some(tag) = &mut self.tag {
+                tag.push(c);
+                let done = c == '>' && (!tag.starts_with("<!--") || tag.ends_with("-->"));
+                if done {
+                    let tag = self.tag.take().unwrap_or_default();
+                    self.write_tag(&tag, &mut output);
+                }
+            } else if c == '<' {
+                self.tag = Some(String::from("<"));
+            } else if self.preserve > 0 {
+                output.push(c);
+            } else if c.is_whitespace() {
+                self.pending_space = true;
+            } else {
+                if self.layout == HtmlLayout::Pretty && self.after_block {
+                    self.new_line(&mut output);
+                } else if self.pending_space && !self.after_block {
+                    output.push(' ');
+                }
+                output.push(c);
+                self.pending_space = false;
+                self.after_block = false;
+                self.at_start = false;
+            }
+        }
+        if !output.is_empty() {
+            self.inner.push_owned_string(output);
+        }
+    }
+}
+
+impl<'a> Appender<'a> for HtmlLayoutAppender<'a, '_> {
+    fn push_str(&mut self, value: &'a str) {
+        match self.layout {
+            HtmlLayout::Unchanged => self.inner.push_str(value),
+            _ => self.write(value),
+        }
+    }
+
+    fn push_string(&mut self, value: &'a String) {
+        match self.layout {
+            HtmlLayout::Unchanged => self.inner.push_string(value),
+            _ => self.write(value),
+        }
+    }
+
+    fn push_borrowed_string(&mut self, value: &String) {
+        match self.layout {
+            HtmlLayout::Unchanged => self.inner.push_borrowed_string(value),
+            _ => self.write(value),
+        }
+    }
+
+    fn push_owned_string(&mut self, value: String) {
+        match self.layout {
+            HtmlLayout::Unchanged => self.inner.push_owned_string(value),
+            _ => self.write(&value),
+        }
+    }
+
+    fn push_cow_str(&mut self, value: Cow<'a, str>) {
+        match self.layout {
+            HtmlLayout::Unchanged => self.inner.push_cow_str(value),
+            _ => self.write(&value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::stringbuilder::{CollectorAppender, IntoString};
+
+    const HTML: &str = concat!(
+        "<div class=\"x\">\n",
+        "  <p>Foo   <b>bar</b>\n  baz<br/>qux</p>\n",
+        "  <!-- a > b -->\n",
+        "  <pre>  x\n  <i>y</i></pre>\n",
+        "  <hr>\n",
+        "</div>\n",
+    );
+
+    fn render(layout: HtmlLayout, chunk_size: usize) -> String {
+        let mut result = CollectorAppender::new();
+        let mut appender = HtmlLayoutAppender::new(&mut result, layout);
+        let chars: Vec<char> = HTML.chars().collect();
+        for chunk in chars.chunks(chunk_size) {
+            appender.push_owned_string(chunk.iter().collect());
+        }
+        appender.finish();
+        result.into_string()
+    }
+
+    #[test]
+    fn test_html_layout() {
+        assert_eq!(render(HtmlLayout::Unchanged, 5), HTML);
+        let minified = concat!(
+            "<div class=\"x\"><p>Foo <b>bar</b> baz<br/>qux</p><!-- a > b -->",
+            "<pre>  x\n  <i>y</i></pre><hr></div>",
+        );
+        assert_eq!(render(HtmlLayout::Minified, 1000), minified);
+        assert_eq!(render(HtmlLayout::Minified, 1), minified);
+        let pretty = concat!(
+            "<div class=\"x\">\n",
+            "  <p>\n",
+            "    Foo <b>bar</b> baz<br/>qux\n",
+            "  </p>\n",
+            "  <!-- a > b -->\n",
+            "  <pre>  x\n  <i>y</i></pre>\n",
+            "  <hr>\n",
+            "</div>",
+        );
+        assert_eq!(render(HtmlLayout::Pretty, 1000), pretty);
+        assert_eq!(render(HtmlLayout::Pretty, 3), pretty);
+    }
+}
@@ -0,0 +1,283 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Loading plugin documentation and test vectors from JSON, like the output of
+//! `ansible-doc --json`, without parsing YAML. Requires the `json` feature.
+
+use crate::util::MARKUP_KEYS;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Collect the markup in the fields with one of the markup keys (`description`, `notes`, ...)
+/// below `value`.
+///
+/// Every field is added to `fields` as a list of its paragraphs, each with its path below
+/// `path`, the key of the field, and the markup, like for YAML documentation.
+pub fn collect_json_markup_fields(
+    value: &Value,
+    path: &str,
+    fields: &mut Vec<Vec<(String, String, String)>>,
+) {
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                let path = join(key);
+                if !MARKUP_KEYS.contains(&key.as_str()) {
+                    collect_json_markup_fields(value, &path, fields);
+                    continue;
+                }
+                match value {
+                    Value::String(text) => fields.push(vec![(path, key.clone(), text.clone())]),
+                    Value::Array(values) if values.iter().all(Value::is_string) => fields.push(
+                        values
+                            .iter()
+                            .enumerate()
+                            .map(|(index, text)| {
+                                (
+                                    format!("{}[{}]", path, index),
+                                    key.clone(),
+                                    text.as_str().unwrap_or_default().to_string(),
+                                )
+                            })
+                            .collect(),
+                    ),
+                    value => collect_json_markup_fields(value, &path, fields),
+                }
+            }
+        }
+        Value::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                collect_json_markup_fields(value, &format!("{}[{}]", path, index), fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn parse_json(content: &str) -> Result<Value, String> {
+    serde_json::from_str(content).map_err(|error| {
+        format!(
+            "syntax error in line {}, column {}: {}",
+            error.line(),
+            error.column(),
+            error
+        )
+    })
+}
+
+/// The documentation of a plugin, module, or role in the output of `ansible-doc --json`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPluginDocs {
+    /// The plugin's FQCN.
+    pub name: String,
+
+    /// The `doc` entry of plugins and modules.
+    pub documentation: Option<Value>,
+
+    /// The `examples` entry.
+    pub examples: Option<String>,
+
+    /// The `return` entry.
+    pub return_values: Option<Value>,
+
+    /// The `entry_points` entry of roles.
+    pub entry_points: Option<Value>,
+}
+
+impl JsonPluginDocs {
+    /// Collect the markup fields of the documentation, return values, and entry points.
+    ///
+    /// Paths of return values start with `return.`, and paths of entry points with
+    /// `entry_points.`.
+    pub fn markup_fields(&self) -> Vec<Vec<(String, String, String)>> {
+        let mut fields = Vec::new();
+        for (value, path) in [
+            (&self.documentation, ""),
+            (&self.return_values, "return"),
+            (&self.entry_points, "entry_points"),
+        ] {
+            if let Some(value) = value {
+                collect_json_markup_fields(value, path, &mut fields);
+            }
+        }
+        fields
+    }
+}
+
+/// Load the output of `ansible-doc --json`, which maps the names of plugins to their
+/// documentation. The plugins are returned sorted by name, and the keys of all objects are
+/// sorted as well.
+pub fn load_ansible_doc_json(content: &str) -> Result<Vec<JsonPluginDocs>, String> {
+    let Value::Object(plugins) = parse_json(content)? else {
+        return Err("expected an object".to_string());
+    };
+    let mut result = Vec::new();
+    for (name, value) in plugins {
+        let Value::Object(mut entry) = value else {
+            return Err(format!("{}: expected an object", name));
+        };
+        let examples = match entry.remove("examples") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(examples)) => Some(examples),
+            Some(_) => return Err(format!("{}.examples: expected a string", name)),
+        };
+        let mut take = |key: &str| entry.remove(key).filter(|value| !value.is_null());
+        result.push(JsonPluginDocs {
+            documentation: take("doc"),
+            return_values: take("return"),
+            entry_points: take("entry_points"),
+            examples,
+            name,
+        });
+    }
+    Ok(result)
+}
+
+/// The output formats of test vectors, as named in the test vector files.
+pub const TEST_VECTOR_FORMATS: [&str; 6] = [
+    "html",
+    "html_plain",
+    "md",
+    "rst",
+    "rst_plain",
+    "ansible_doc_text",
+];
+
+/// A test vector: markup, and the expected renderings in some output formats.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonTestVector {
+    pub name: String,
+
+    /// The paragraphs of the markup.
+    pub source: Vec<String>,
+
+    /// Whether `source` is a single paragraph given as a string, instead of a list.
+    pub single_paragraph: bool,
+
+    /// The expected output for every format in [`TEST_VECTOR_FORMATS`] the vector tests.
+    pub expected: BTreeMap<String, String>,
+
+    /// All parameters of the test vector, including the parse and render options.
+    pub parameters: serde_json::Map<String, Value>,
+}
+
+/// Load test vectors in the format of `test-vectors.yaml`, as JSON: an object with a
+/// `test_vectors` object that maps the names of the test vectors to their parameters.
+pub fn load_json_test_vectors(content: &str) -> Result<Vec<JsonTestVector>, String> {
+    let data = parse_json(content)?;
+    let Some(Value::Object(vectors)) = data.get("test_vectors") else {
+        return Err("test_vectors: expected an object".to_string());
+    };
+    let mut result = Vec::new();
+    for (name, parameters) in vectors {
+        let Value::Object(parameters) = parameters else {
+            return Err(format!("test_vectors.{}: expected an object", name));
+        };
+        let (source, single_paragraph) = match parameters.get("source") {
+            Some(Value::String(source)) => (vec![source.clone()], true),
+            Some(Value::Array(paragraphs)) => (
+                paragraphs
+                    .iter()
+                    .map(|paragraph| paragraph.as_str().map(|p| p.to_string()))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| {
+                        format!("test_vectors.{}.source: expected a list of strings", name)
+                    })?,
+                false,
+            ),
+            _ => {
+                return Err(format!(
+                    "test_vectors.{}.source: expected a string or a list of strings",
+                    name
+                ))
+            }
+        };
+        let mut expected = BTreeMap::new();
+        for format in TEST_VECTOR_FORMATS {
+            match parameters.get(format) {
+                None => {}
+                Some(Value::String(output)) => {
+                    expected.insert(format.to_string(), output.clone());
+                }
+                Some(_) => {
+                    return Err(format!(
+                        "test_vectors.{}.{}: expected a string",
+                        name, format
+                    ))
+                }
+            }
+        }
+        result.push(JsonTestVector {
+            name: name.clone(),
+            source,
+            single_paragraph,
+            expected,
+            parameters: parameters.clone(),
+        });
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_ansible_doc_json() {
+        let plugins = load_ansible_doc_json(
+            r#"{
+                "foo.bar.baz": {
+                    "doc": {
+                        "short_description": "Do C(things)",
+                        "options": {"name": {"description": ["First.", "Second."]}}
+                    },
+                    "examples": "- foo.bar.baz:\n",
+                    "metadata": null,
+                    "return": {"changed": {"description": "Whether it changed."}}
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(plugins.len(), 1);
+        assert_eq!(plugins[0].examples.as_deref(), Some("- foo.bar.baz:\n"));
+        let fields = plugins[0].markup_fields();
+        assert_eq!(
+            fields
+                .iter()
+                .flatten()
+                .map(|(path, _, _)| path.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "options.name.description[0]",
+                "options.name.description[1]",
+                "short_description",
+                "return.changed.description",
+            ]
+        );
+
+        let vectors = load_json_test_vectors(
+            r#"{"test_vectors": {"bold": {"source": "B(foo)", "md": "<b>foo</b>"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(vectors[0].source, vec!["B(foo)"]);
+        assert_eq!(vectors[0].expected["md"], "<b>foo</b>");
+        assert_eq!(
+            load_json_test_vectors(r#"{"test_vectors": {"x": {"source": [1]}}}"#),
+            Err("test_vectors.x.source: expected a list of strings".to_string())
+        );
+        assert_eq!(
+            load_ansible_doc_json("{\n  \"a\": }"),
+            Err("syntax error in line 2, column 8: expected value at line 2 column 8".to_string())
+        );
+    }
+}
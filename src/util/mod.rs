@@ -6,6 +6,44 @@ SPDX-License-Identifier: GPL-3.0-or-later
 
 //! Utilities.
 
+#[cfg(feature = "std")]
+pub mod cancellation;
+#[cfg(feature = "async")]
+pub mod concurrency;
+#[cfg(feature = "docutils")]
+pub mod docutils;
+#[cfg(feature = "std")]
+pub mod html_audit;
+pub mod html_layout;
+#[cfg(feature = "std")]
+pub(crate) mod json;
+#[cfg(feature = "json")]
+pub mod json_input;
+pub(crate) mod names;
+#[cfg(feature = "std")]
+pub mod progress;
 pub mod stringbuilder;
+#[cfg(feature = "yaml")]
+pub mod verify;
+#[cfg(feature = "std")]
+pub mod version;
+#[cfg(feature = "yaml")]
+#[cfg_attr(not(feature = "full"), allow(dead_code))]
+pub(crate) mod yaml;
+#[cfg(feature = "yaml")]
+pub mod yaml_stream;
+#[cfg(feature = "yaml")]
+pub mod yamledit;
 
+pub use html_layout::{HtmlLayout, HtmlLayoutAppender};
 pub use stringbuilder::{AppendTo, Appender, CollectorAppender, IntoString, StringAppender};
+
+/// The keys of plugin documentation whose values are markup.
+#[cfg(any(feature = "yaml", feature = "json"))]
+pub(crate) const MARKUP_KEYS: [&str; 5] = [
+    "description",
+    "short_description",
+    "notes",
+    "why",
+    "alternative",
+];
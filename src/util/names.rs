@@ -0,0 +1,39 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Validation of collection names, shared by the markup parser and the collection tools.
+
+/// Whether `part` is a valid namespace or collection name, like `community` or `general`.
+fn is_name_part(part: &str) -> bool {
+    part.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && part
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Whether `name` is a valid collection name, like `community.general`.
+pub fn is_valid_collection_name(name: &str) -> bool {
+    match name.split_once('.') {
+        Some((namespace, name)) => is_name_part(namespace) && is_name_part(name),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_collection_name() {
+        assert!(is_valid_collection_name("community.general"));
+        assert!(is_valid_collection_name("0x.foo_bar"));
+        assert!(!is_valid_collection_name("community.general.foo"));
+        assert!(!is_valid_collection_name("Community.general"));
+        assert!(!is_valid_collection_name("_foo.bar"));
+        assert!(!is_valid_collection_name("foo."));
+        assert!(!is_valid_collection_name("foo"));
+    }
+}
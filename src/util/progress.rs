@@ -0,0 +1,142 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Progress reporting for long-running batch operations.
+
+/// Receives the progress of a batch operation, like scanning a collection or writing pages.
+///
+/// The methods take `&self`, so implementations that need to keep state use interior
+/// mutability.
+pub trait ProgressReporter {
+    /// The operation `operation` started. `total` is the number of items, if known.
+    fn start(&self, _operation: &str, _total: Option<usize>) {}
+
+    /// Work on the item `current` started, after `done` items were finished.
+    fn progress(&self, done: usize, current: &str);
+
+    /// The operation finished.
+    fn finish(&self) {}
+}
+
+/// Ignores all progress.
+pub struct NoProgressReporter {}
+
+impl NoProgressReporter {
+    pub fn new() -> NoProgressReporter {
+        NoProgressReporter {}
+    }
+}
+
+impl Default for NoProgressReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProgressReporter for NoProgressReporter {
+    fn progress(&self, _done: usize, _current: &str) {}
+}
+
+/// Shows the progress as a progress bar on the terminal.
+///
+/// Operations with an unknown number of items are shown with a spinner.
+#[cfg(feature = "progress")]
+pub struct ProgressBarReporter {
+    bar: indicatif::ProgressBar,
+}
+
+#[cfg(feature = "progress")]
+impl ProgressBarReporter {
+    /// Create a reporter that draws to standard error.
+    pub fn new() -> ProgressBarReporter {
+        ProgressBarReporter::with_bar(indicatif::ProgressBar::new(0))
+    }
+
+    /// Create a reporter that uses `bar`, for example to draw to a different target.
+    pub fn with_bar(bar: indicatif::ProgressBar) -> ProgressBarReporter {
+        ProgressBarReporter { bar }
+    }
+}
+
+#[cfg(feature = "progress")]
+impl Default for ProgressBarReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "progress")]
+impl ProgressReporter for ProgressBarReporter {
+    fn start(&self, operation: &str, total: Option<usize>) {
+        use indicatif::ProgressStyle;
+        let style = match total {
+            Some(total) => {
+                self.bar.set_length(total as u64);
+                ProgressStyle::with_template("{prefix} [{bar:40}] {pos}/{len} {wide_msg}")
+                    .map(|style| style.progress_chars("=> "))
+            }
+            None => ProgressStyle::with_template("{prefix} {spinner} {pos} {wide_msg}"),
+        };
+        self.bar
+            .set_style(style.unwrap_or_else(|_| ProgressStyle::default_bar()));
+        self.bar.set_prefix(operation.to_string());
+        self.bar.reset();
+    }
+
+    fn progress(&self, done: usize, current: &str) {
+        self.bar.set_position(done as u64);
+        self.bar.set_message(current.to_string());
+    }
+
+    fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Records all calls, for testing batch operations.
+    #[derive(Default)]
+    pub(crate) struct RecordingProgressReporter {
+        pub calls: RefCell<Vec<String>>,
+    }
+
+    impl ProgressReporter for RecordingProgressReporter {
+        fn start(&self, operation: &str, total: Option<usize>) {
+            self.calls
+                .borrow_mut()
+                .push(format!("start {} {:?}", operation, total));
+        }
+
+        fn progress(&self, done: usize, current: &str) {
+            self.calls
+                .borrow_mut()
+                .push(format!("progress {} {}", done, current));
+        }
+
+        fn finish(&self) {
+            self.calls.borrow_mut().push("finish".to_string());
+        }
+    }
+
+    #[test]
+    fn test_progress_reporters() {
+        let reporter = RecordingProgressReporter::default();
+        let reporters: [&dyn ProgressReporter; 2] = [&NoProgressReporter::new(), &reporter];
+        for reporter in reporters {
+            reporter.start("Scanning", Some(1));
+            reporter.progress(0, "a");
+            reporter.finish();
+        }
+        assert_eq!(
+            reporter.calls.into_inner(),
+            vec!["start Scanning Some(1)", "progress 0 a", "finish"]
+        );
+    }
+}
@@ -6,7 +6,9 @@ SPDX-License-Identifier: GPL-3.0-or-later
 
 //! String builders.
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
 
 pub trait Appender<'a> {
     fn push_str(&mut self, value: &'a str);
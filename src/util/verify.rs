@@ -0,0 +1,297 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Checksum and signature verification.
+
+use crate::util::yaml;
+use saphyr::Yaml;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// A verification failure.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyError {
+    /// A file could not be read.
+    Io { path: String, message: String },
+
+    /// A file listed in a checksum manifest does not exist.
+    Missing { path: String },
+
+    /// A file does not have the expected checksum.
+    ChecksumMismatch {
+        path: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// The checksum manifest is invalid.
+    InvalidManifest { message: String },
+
+    /// A signature could not be verified.
+    InvalidSignature { message: String },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerifyError::Io { path, message } => write!(f, "Cannot read {}: {}", path, message),
+            VerifyError::Missing { path } => write!(f, "{} is missing", path),
+            VerifyError::ChecksumMismatch {
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} has SHA-256 checksum {}, expected {}",
+                path, actual, expected
+            ),
+            VerifyError::InvalidManifest { message } => {
+                write!(f, "Invalid checksum manifest: {}", message)
+            }
+            VerifyError::InvalidSignature { message } => {
+                write!(f, "Invalid signature: {}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+fn to_hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// The SHA-256 checksum of `data` as lowercase hex digits.
+pub fn sha256_hex(data: &[u8]) -> String {
+    to_hex(&Sha256::digest(data))
+}
+
+/// The SHA-256 checksum of everything `reader` provides, as lowercase hex digits.
+pub fn sha256_reader<R: Read>(mut reader: R) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 65536];
+    loop {
+        let count = reader.read(&mut buffer)?;
+        if count == 0 {
+            return Ok(to_hex(&hasher.finalize()));
+        }
+        hasher.update(&buffer[..count]);
+    }
+}
+
+/// The SHA-256 checksum of the file at `path`, as lowercase hex digits.
+pub fn sha256_file(path: &Path) -> Result<String, VerifyError> {
+    let io_error = |error: std::io::Error| VerifyError::Io {
+        path: path.display().to_string(),
+        message: error.to_string(),
+    };
+    sha256_reader(File::open(path).map_err(io_error)?).map_err(io_error)
+}
+
+/// Check that `data`, which was read from `path`, has the SHA-256 checksum `expected`.
+///
+/// `expected` is compared case-insensitively.
+pub fn verify_sha256(path: &str, data: &[u8], expected: &str) -> Result<(), VerifyError> {
+    let actual = sha256_hex(data);
+    if actual.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(VerifyError::ChecksumMismatch {
+            path: path.to_string(),
+            expected: expected.to_lowercase(),
+            actual,
+        })
+    }
+}
+
+/// The file checksums of a collection artifact, as listed in its `FILES.json`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChecksumManifest {
+    /// The paths of the files, with their SHA-256 checksums. Directories are not included.
+    pub files: Vec<(String, String)>,
+}
+
+impl ChecksumManifest {
+    /// Parse the contents of a `FILES.json` file.
+    pub fn parse(content: &str) -> Result<ChecksumManifest, VerifyError> {
+        let invalid = |message: String| VerifyError::InvalidManifest { message };
+        let mut documents = Yaml::load_from_str(content)
+            .map_err(|error| invalid(format!("syntax error: {}", error.info())))?;
+        let document = if documents.is_empty() {
+            Yaml::Null
+        } else {
+            documents.swap_remove(0)
+        };
+        let hash = yaml::as_hash(&document, "FILES.json").map_err(invalid)?;
+        let Some(Yaml::Array(entries)) = yaml::get(hash, "files") else {
+            return Err(invalid("FILES.json.files: expected a list".to_string()));
+        };
+        let mut files = Vec::new();
+        for (index, entry) in entries.iter().enumerate() {
+            let path = format!("FILES.json.files[{}]", index);
+            let entry = yaml::as_hash(entry, &path).map_err(invalid)?;
+            let get = |key: &str| -> Result<String, VerifyError> {
+                yaml::get_string(entry, key, &path)
+                    .map_err(invalid)?
+                    .ok_or_else(|| invalid(format!("{}.{}: missing", path, key)))
+            };
+            if get("ftype")? != "file" {
+                continue;
+            }
+            let checksum_type = get("chksum_type")?;
+            if checksum_type != "sha256" {
+                return Err(invalid(format!(
+                    "{}.chksum_type: unsupported checksum type {:?}",
+                    path, checksum_type
+                )));
+            }
+            files.push((get("name")?, get("chksum_sha256")?));
+        }
+        Ok(ChecksumManifest { files })
+    }
+
+    /// Verify all files listed in the manifest.
+    ///
+    /// `read` returns the content of a file, or `None` if it does not exist. All failures are
+    /// returned, so an empty result means that all files are fine.
+    pub fn verify<F>(&self, read: F) -> Vec<VerifyError>
+    where
+        F: Fn(&str) -> Result<Option<Vec<u8>>, VerifyError>,
+    {
+        self.files
+            .iter()
+            .filter_map(|(path, expected)| match read(path) {
+                Ok(Some(data)) => verify_sha256(path, &data, expected).err(),
+                Ok(None) => Some(VerifyError::Missing { path: path.clone() }),
+                Err(error) => Some(error),
+            })
+            .collect()
+    }
+}
+
+/// Verification of detached signatures, like the signatures of a collection's `MANIFEST.json`.
+#[cfg(feature = "gpg")]
+pub trait SignatureVerifier {
+    /// Check that `signature` is a valid detached signature of `data`.
+    fn verify_signature(&self, data: &[u8], signature: &[u8]) -> Result<(), VerifyError>;
+}
+
+/// Verifies signatures by running `gpg --verify` against a keyring.
+#[cfg(feature = "gpg")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpgVerifier {
+    /// The `gpg` executable.
+    pub gpg: std::path::PathBuf,
+
+    /// The keyring containing the trusted keys.
+    pub keyring: std::path::PathBuf,
+}
+
+#[cfg(feature = "gpg")]
+impl GpgVerifier {
+    /// Use `gpg` from `PATH` with the keyring `keyring`.
+    pub fn new(keyring: &Path) -> GpgVerifier {
+        GpgVerifier {
+            gpg: "gpg".into(),
+            keyring: keyring.to_path_buf(),
+        }
+    }
+}
+
+#[cfg(feature = "gpg")]
+impl SignatureVerifier for GpgVerifier {
+    fn verify_signature(&self, data: &[u8], signature: &[u8]) -> Result<(), VerifyError> {
+        let invalid = |message: String| VerifyError::InvalidSignature { message };
+        let directory = std::env::temp_dir().join(format!(
+            "antsibull-verify-{}-{}",
+            std::process::id(),
+            sha256_hex(signature)
+        ));
+        std::fs::create_dir_all(&directory).map_err(|error| invalid(error.to_string()))?;
+        let data_path = directory.join("data");
+        let signature_path = directory.join("data.asc");
+        let result = std::fs::write(&data_path, data)
+            .and_then(|_| std::fs::write(&signature_path, signature))
+            .and_then(|_| {
+                std::process::Command::new(&self.gpg)
+                    .arg("--batch")
+                    .arg("--no-default-keyring")
+                    .arg("--keyring")
+                    .arg(&self.keyring)
+                    .arg("--verify")
+                    .arg(&signature_path)
+                    .arg(&data_path)
+                    .output()
+            });
+        let _ = std::fs::remove_dir_all(&directory);
+        let output = result.map_err(|error| invalid(error.to_string()))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(invalid(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn test_sha256() {
+        assert_eq!(sha256_hex(b"abc"), ABC_SHA256);
+        assert_eq!(sha256_reader(&b"abc"[..]).unwrap(), ABC_SHA256);
+        assert!(verify_sha256("x", b"abc", &ABC_SHA256.to_uppercase()).is_ok());
+        assert_eq!(
+            verify_sha256("x", b"abd", ABC_SHA256)
+                .unwrap_err()
+                .to_string(),
+            format!(
+                "x has SHA-256 checksum {}, expected {}",
+                sha256_hex(b"abd"),
+                ABC_SHA256
+            )
+        );
+    }
+
+    #[test]
+    fn test_checksum_manifest() {
+        let manifest = ChecksumManifest::parse(&format!(
+            r#"{{"files": [
+                {{"name": ".", "ftype": "dir", "chksum_type": null, "chksum_sha256": null}},
+                {{"name": "a", "ftype": "file", "chksum_type": "sha256", "chksum_sha256": "{0}"}},
+                {{"name": "b", "ftype": "file", "chksum_type": "sha256", "chksum_sha256": "{0}"}},
+                {{"name": "c", "ftype": "file", "chksum_type": "sha256", "chksum_sha256": "{0}"}}
+            ], "format": 1}}"#,
+            ABC_SHA256
+        ))
+        .unwrap();
+        assert_eq!(manifest.files.len(), 3);
+        let errors = manifest.verify(|path| match path {
+            "a" => Ok(Some(b"abc".to_vec())),
+            "b" => Ok(Some(b"abd".to_vec())),
+            _ => Ok(None),
+        });
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| match error {
+                    VerifyError::ChecksumMismatch { path, .. } => format!("mismatch {}", path),
+                    error => error.to_string(),
+                })
+                .collect::<Vec<_>>(),
+            vec!["mismatch b", "c is missing"]
+        );
+        assert!(ChecksumManifest::parse(r#"{"files": {}}"#).is_err());
+    }
+}
@@ -0,0 +1,498 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! PEP 440 style versions and version specifiers.
+
+use regex::Regex;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+/// The kind of a pre-release.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PreRelease {
+    Alpha,
+    Beta,
+    ReleaseCandidate,
+}
+
+impl PreRelease {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PreRelease::Alpha => "a",
+            PreRelease::Beta => "b",
+            PreRelease::ReleaseCandidate => "rc",
+        }
+    }
+}
+
+/// A version like `2.14.0`, `2.15.0rc1`, `1.0.0.post1`, or `2.16.0.dev0`.
+///
+/// Epochs and local version labels are not supported.
+#[derive(Debug, Clone, Eq)]
+pub struct Version {
+    /// The release segments, like `[2, 14, 0]`.
+    pub release: Vec<u64>,
+
+    /// The pre-release kind and number.
+    pub pre: Option<(PreRelease, u64)>,
+
+    /// The post-release number.
+    pub post: Option<u64>,
+
+    /// The development release number.
+    pub dev: Option<u64>,
+}
+
+static VERSION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"^v?([0-9]+(?:\.[0-9]+)*)",
+        r"(?:[-_.]?(a|alpha|b|beta|c|rc|pre|preview)[-_.]?([0-9]*))?",
+        r"(?:-([0-9]+)|[-_.]?(post|rev|r)[-_.]?([0-9]*))?",
+        r"(?:[-_.]?(dev)[-_.]?([0-9]*))?$",
+    ))
+    .unwrap()
+});
+
+fn parse_number(text: &str, version: &str) -> Result<u64, String> {
+    if text.is_empty() {
+        return Ok(0);
+    }
+    text.parse::<u64>()
+        .map_err(|_| format!("Invalid version {:?}", version))
+}
+
+impl Version {
+    /// Parse a version.
+    pub fn parse(version: &str) -> Result<Version, String> {
+        let error = || format!("Invalid version {:?}", version);
+        let lower = version.trim().to_lowercase();
+        let captures = VERSION_RE.captures(&lower).ok_or_else(error)?;
+        let number = |index: usize| -> Result<Option<u64>, String> {
+            captures
+                .get(index)
+                .map(|m| parse_number(m.as_str(), version))
+                .transpose()
+        };
+        let release = captures[1]
+            .split('.')
+            .map(|part| parse_number(part, version))
+            .collect::<Result<Vec<_>, _>>()?;
+        let pre = match captures.get(2).map(|m| m.as_str()) {
+            None => None,
+            Some("a" | "alpha") => Some((PreRelease::Alpha, number(3)?.unwrap_or(0))),
+            Some("b" | "beta") => Some((PreRelease::Beta, number(3)?.unwrap_or(0))),
+            Some(_) => Some((PreRelease::ReleaseCandidate, number(3)?.unwrap_or(0))),
+        };
+        let post = match (number(4)?, captures.get(5)) {
+            (Some(post), _) => Some(post),
+            (None, Some(_)) => Some(number(6)?.unwrap_or(0)),
+            (None, None) => None,
+        };
+        let dev = match captures.get(7) {
+            Some(_) => Some(number(8)?.unwrap_or(0)),
+            None => None,
+        };
+        Ok(Version {
+            release,
+            pre,
+            post,
+            dev,
+        })
+    }
+
+    /// Whether this is a pre-release or a development release.
+    pub fn is_prerelease(&self) -> bool {
+        self.pre.is_some() || self.dev.is_some()
+    }
+
+    /// The version without pre-release, post-release, and development release information.
+    pub fn base(&self) -> Version {
+        Version {
+            release: self.release.clone(),
+            pre: None,
+            post: None,
+            dev: None,
+        }
+    }
+
+    fn release_segment(&self, index: usize) -> u64 {
+        self.release.get(index).copied().unwrap_or(0)
+    }
+
+    fn compare_release(&self, other: &Version) -> Ordering {
+        let length = self.release.len().max(other.release.len());
+        (0..length)
+            .map(|index| {
+                self.release_segment(index)
+                    .cmp(&other.release_segment(index))
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+
+    /// The key used for ordering everything after the release segments.
+    ///
+    /// Development releases of a release come before its pre-releases, which come before the
+    /// release itself, which comes before its post-releases.
+    fn suffix_key(&self) -> (u8, Option<(PreRelease, u64)>, Option<u64>, u8, u64) {
+        let phase = match (&self.pre, &self.post, &self.dev) {
+            (None, None, Some(_)) => 0,
+            (Some(_), _, _) => 1,
+            (None, None, None) => 2,
+            (None, Some(_), _) => 3,
+        };
+        let (dev_phase, dev) = match self.dev {
+            Some(dev) => (0, dev),
+            None => (1, 0),
+        };
+        (phase, self.pre, self.post, dev_phase, dev)
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Version) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Version) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Version) -> Ordering {
+        self.compare_release(other)
+            .then_with(|| self.suffix_key().cmp(&other.suffix_key()))
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(version: &str) -> Result<Version, String> {
+        Version::parse(version)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let release: Vec<String> = self.release.iter().map(|s| s.to_string()).collect();
+        write!(f, "{}", release.join("."))?;
+        if let Some((kind, number)) = &self.pre {
+            write!(f, "{}{}", kind.as_str(), number)?;
+        }
+        if let Some(post) = &self.post {
+            write!(f, ".post{}", post)?;
+        }
+        if let Some(dev) = &self.dev {
+            write!(f, ".dev{}", dev)?;
+        }
+        Ok(())
+    }
+}
+
+/// The operator of a version specifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Compatible,
+    Equal,
+    NotEqual,
+    LessEqual,
+    GreaterEqual,
+    Less,
+    Greater,
+    Arbitrary,
+}
+
+impl Operator {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Operator::Compatible => "~=",
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::LessEqual => "<=",
+            Operator::GreaterEqual => ">=",
+            Operator::Less => "<",
+            Operator::Greater => ">",
+            Operator::Arbitrary => "===",
+        }
+    }
+}
+
+/// A single version specifier, like `>=2.14.0` or `==2.15.*`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Specifier {
+    /// The operator.
+    pub operator: Operator,
+
+    /// The version as written.
+    pub text: String,
+
+    /// The parsed version. For `==` and `!=` with a trailing `.*`, this is the prefix.
+    /// For `===`, this is `None` if the text is not a valid version.
+    pub version: Option<Version>,
+
+    /// Whether the version ends with `.*`.
+    pub wildcard: bool,
+}
+
+impl Specifier {
+    /// Parse a specifier.
+    pub fn parse(specifier: &str) -> Result<Specifier, String> {
+        let specifier = specifier.trim();
+        let operator = [
+            Operator::Arbitrary,
+            Operator::Compatible,
+            Operator::Equal,
+            Operator::NotEqual,
+            Operator::LessEqual,
+            Operator::GreaterEqual,
+            Operator::Less,
+            Operator::Greater,
+        ]
+        .into_iter()
+        .find(|operator| specifier.starts_with(operator.as_str()))
+        .ok_or_else(|| format!("Invalid version specifier {:?}", specifier))?;
+        let text = specifier[operator.as_str().len()..].trim();
+        if operator == Operator::Arbitrary {
+            return Ok(Specifier {
+                operator,
+                text: text.to_string(),
+                version: Version::parse(text).ok(),
+                wildcard: false,
+            });
+        }
+        let (version_text, wildcard) = match text.strip_suffix(".*") {
+            Some(prefix) if matches!(operator, Operator::Equal | Operator::NotEqual) => {
+                (prefix, true)
+            }
+            _ => (text, false),
+        };
+        let version = Version::parse(version_text)
+            .map_err(|_| format!("Invalid version specifier {:?}", specifier))?;
+        if operator == Operator::Compatible && version.release.len() < 2 {
+            return Err(format!(
+                "Invalid version specifier {:?}: ~= needs at least two release segments",
+                specifier
+            ));
+        }
+        Ok(Specifier {
+            operator,
+            text: text.to_string(),
+            version: Some(version),
+            wildcard,
+        })
+    }
+
+    fn matches_prefix(version: &Version, prefix: &Version) -> bool {
+        let candidate = version.base();
+        prefix
+            .release
+            .iter()
+            .enumerate()
+            .all(|(index, segment)| candidate.release_segment(index) == *segment)
+            && (prefix.pre.is_none() || prefix.pre == version.pre)
+    }
+
+    /// Whether `version` satisfies this specifier. Pre-releases are not treated specially here.
+    pub fn contains(&self, version: &Version) -> bool {
+        let spec = match &self.version {
+            Some(spec) => spec,
+            None => return false,
+        };
+        match self.operator {
+            Operator::Arbitrary => version.to_string() == self.text,
+            Operator::Equal if self.wildcard => Specifier::matches_prefix(version, spec),
+            Operator::NotEqual if self.wildcard => !Specifier::matches_prefix(version, spec),
+            Operator::Equal => version == spec,
+            Operator::NotEqual => version != spec,
+            Operator::LessEqual => version <= spec,
+            Operator::GreaterEqual => version >= spec,
+            Operator::Less => {
+                version < spec
+                    && (spec.is_prerelease()
+                        || !version.is_prerelease()
+                        || version.compare_release(spec) != Ordering::Equal)
+            }
+            Operator::Greater => {
+                version > spec
+                    && (spec.post.is_some()
+                        || version.post.is_none()
+                        || version.compare_release(spec) != Ordering::Equal)
+            }
+            Operator::Compatible => {
+                let mut prefix = spec.base();
+                prefix.release.pop();
+                version >= spec && Specifier::matches_prefix(version, &prefix)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Specifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.operator.as_str(), self.text)
+    }
+}
+
+/// A comma-separated set of version specifiers, like `>=2.14.0,<2.17`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SpecifierSet {
+    /// The specifiers. A version must satisfy all of them.
+    pub specifiers: Vec<Specifier>,
+}
+
+impl SpecifierSet {
+    /// Parse a specifier set. An empty string results in a set that contains all versions.
+    pub fn parse(specifiers: &str) -> Result<SpecifierSet, String> {
+        Ok(SpecifierSet {
+            specifiers: specifiers
+                .split(',')
+                .filter(|specifier| !specifier.trim().is_empty())
+                .map(Specifier::parse)
+                .collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+
+    /// Whether the specifiers explicitly mention a pre-release.
+    fn mentions_prerelease(&self) -> bool {
+        self.specifiers.iter().any(|specifier| {
+            specifier
+                .version
+                .as_ref()
+                .is_some_and(|version| version.is_prerelease())
+        })
+    }
+
+    /// Whether `version` satisfies all specifiers.
+    ///
+    /// Like pip, pre-releases are only accepted if `prereleases` is `true`, or if one of the
+    /// specifiers mentions a pre-release.
+    pub fn contains(&self, version: &Version, prereleases: bool) -> bool {
+        if version.is_prerelease() && !prereleases && !self.mentions_prerelease() {
+            return false;
+        }
+        self.specifiers
+            .iter()
+            .all(|specifier| specifier.contains(version))
+    }
+
+    /// Select all versions satisfying the specifiers, see `contains()`.
+    pub fn filter<'v, I>(&self, versions: I, prereleases: bool) -> Vec<&'v Version>
+    where
+        I: IntoIterator<Item = &'v Version>,
+    {
+        versions
+            .into_iter()
+            .filter(|version| self.contains(version, prereleases))
+            .collect()
+    }
+}
+
+impl FromStr for SpecifierSet {
+    type Err = String;
+
+    fn from_str(specifiers: &str) -> Result<SpecifierSet, String> {
+        SpecifierSet::parse(specifiers)
+    }
+}
+
+impl fmt::Display for SpecifierSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let specifiers: Vec<String> = self.specifiers.iter().map(|s| s.to_string()).collect();
+        write!(f, "{}", specifiers.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(version: &str) -> Version {
+        Version::parse(version).unwrap()
+    }
+
+    #[test]
+    fn test_version() {
+        assert_eq!(v("2.14").to_string(), "2.14");
+        assert_eq!(v("v1.0.0-RC.1").to_string(), "1.0.0rc1");
+        assert_eq!(v("1.0.0.post1.dev2").to_string(), "1.0.0.post1.dev2");
+        assert_eq!(v("2.14"), v("2.14.0"));
+        assert!(Version::parse("").is_err());
+        assert!(Version::parse("1.0.0foo").is_err());
+        assert!(Version::parse("1.0.0rc1a1").is_err());
+        let mut versions = [
+            v("1.0.0.post1"),
+            v("1.0.0"),
+            v("1.0.0rc1"),
+            v("1.0.0a1"),
+            v("1.0.0.dev0"),
+            v("1.0.0a1.dev1"),
+            v("0.9"),
+            v("1.0.1"),
+        ];
+        versions.sort();
+        assert_eq!(
+            versions.iter().map(|v| v.to_string()).collect::<Vec<_>>(),
+            vec![
+                "0.9",
+                "1.0.0.dev0",
+                "1.0.0a1.dev1",
+                "1.0.0a1",
+                "1.0.0rc1",
+                "1.0.0",
+                "1.0.0.post1",
+                "1.0.1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_specifier_set() {
+        let spec = SpecifierSet::parse(">=2.14.0, <2.17").unwrap();
+        assert_eq!(spec.to_string(), ">=2.14.0,<2.17");
+        assert!(spec.contains(&v("2.14.0"), false));
+        assert!(spec.contains(&v("2.16.5"), false));
+        assert!(!spec.contains(&v("2.17.0"), false));
+        assert!(!spec.contains(&v("2.13.9"), false));
+        assert!(!spec.contains(&v("2.16.0rc1"), false));
+        assert!(spec.contains(&v("2.16.0rc1"), true));
+        assert!(!spec.contains(&v("2.17.0rc1"), true));
+
+        let spec = SpecifierSet::parse("~=2.15.1").unwrap();
+        assert!(spec.contains(&v("2.15.3"), false));
+        assert!(!spec.contains(&v("2.16.0"), false));
+        assert!(!spec.contains(&v("2.15.0"), false));
+
+        let spec = SpecifierSet::parse("==2.15.*,!=2.15.2").unwrap();
+        assert!(spec.contains(&v("2.15.0"), false));
+        assert!(!spec.contains(&v("2.15.2"), false));
+        assert!(!spec.contains(&v("2.16.0"), false));
+
+        let spec = SpecifierSet::parse(">=2.16.0b1").unwrap();
+        assert!(spec.contains(&v("2.16.0rc1"), false));
+        assert!(SpecifierSet::parse(">1.0.0").unwrap().specifiers[0].contains(&v("1.0.1")));
+        assert!(!SpecifierSet::parse(">1.0.0")
+            .unwrap()
+            .contains(&v("1.0.0.post1"), false));
+
+        let versions = [v("2.13.0"), v("2.14.1"), v("2.15.0")];
+        assert_eq!(
+            SpecifierSet::parse(">=2.14")
+                .unwrap()
+                .filter(&versions, false),
+            vec![&versions[1], &versions[2]]
+        );
+        assert!(SpecifierSet::parse("").unwrap().contains(&v("1.0"), false));
+        assert!(SpecifierSet::parse("2.14").is_err());
+        assert!(SpecifierSet::parse("~=2").is_err());
+        assert!(SpecifierSet::parse(">=2.14.*").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Helpers for extracting typed values from YAML documentation.
+
+use crate::util::MARKUP_KEYS;
+use saphyr::{Hash, Yaml};
+
+/// Look up a key in a YAML mapping.
+pub(crate) fn get<'y>(hash: &'y Hash, key: &str) -> Option<&'y Yaml> {
+    hash.get(&Yaml::String(key.to_string()))
+}
+
+/// Convert a scalar to a string.
+///
+/// Numbers and booleans are converted as well, since for example `version_added: 2.9`
+/// is parsed as a floating point number.
+pub(crate) fn scalar_to_string(value: &Yaml) -> Option<String> {
+    match value {
+        Yaml::String(s) => Some(s.clone()),
+        Yaml::Real(s) => Some(s.clone()),
+        Yaml::Integer(i) => Some(i.to_string()),
+        Yaml::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Interpret a YAML value as a mapping.
+pub(crate) fn as_hash<'y>(value: &'y Yaml, path: &str) -> Result<&'y Hash, String> {
+    match value {
+        Yaml::Hash(h) => Ok(h),
+        _ => Err(format!("{}: expected a mapping", path)),
+    }
+}
+
+/// Extract an optional string from a mapping.
+pub(crate) fn get_string(hash: &Hash, key: &str, path: &str) -> Result<Option<String>, String> {
+    match get(hash, key) {
+        None | Some(Yaml::Null) => Ok(None),
+        Some(value) => match scalar_to_string(value) {
+            Some(s) => Ok(Some(s)),
+            None => Err(format!("{}.{}: expected a string", path, key)),
+        },
+    }
+}
+
+/// Extract an optional boolean from a mapping.
+pub(crate) fn get_bool(hash: &Hash, key: &str, path: &str) -> Result<Option<bool>, String> {
+    match get(hash, key) {
+        None | Some(Yaml::Null) => Ok(None),
+        Some(Yaml::Boolean(b)) => Ok(Some(*b)),
+        Some(_) => Err(format!("{}.{}: expected a boolean", path, key)),
+    }
+}
+
+/// Extract a string or list of strings from a mapping.
+///
+/// A single string is converted to a list with one element.
+pub(crate) fn get_string_list(hash: &Hash, key: &str, path: &str) -> Result<Vec<String>, String> {
+    match get(hash, key) {
+        None | Some(Yaml::Null) => Ok(Vec::new()),
+        Some(Yaml::Array(values)) => values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                scalar_to_string(value)
+                    .ok_or_else(|| format!("{}.{}[{}]: expected a string", path, key, index))
+            })
+            .collect(),
+        Some(value) => match scalar_to_string(value) {
+            Some(s) => Ok(vec![s]),
+            None => Err(format!(
+                "{}.{}: expected a string or a list of strings",
+                path, key
+            )),
+        },
+    }
+}
+
+/// Collect the markup in the fields with one of the [`MARKUP_KEYS`] below `value`.
+///
+/// Every field is added to `fields` as a list of its paragraphs, each with its YAML path below
+/// `path`, the key of the field, and the markup.
+pub(crate) fn collect_markup_fields(
+    value: &Yaml,
+    path: &str,
+    fields: &mut Vec<Vec<(String, String, String)>>,
+) {
+    collect_fields(value, path, &MARKUP_KEYS, fields);
+}
+
+/// Collect the fields with one of the keys `keys` below `value`, like [`collect_markup_fields`].
+pub(crate) fn collect_fields(
+    value: &Yaml,
+    path: &str,
+    keys: &[&str],
+    fields: &mut Vec<Vec<(String, String, String)>>,
+) {
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match value {
+        Yaml::Hash(hash) => {
+            for (key, value) in hash {
+                let Yaml::String(key) = key else {
+                    continue;
+                };
+                let path = join(key);
+                if !keys.contains(&key.as_str()) {
+                    collect_fields(value, &path, keys, fields);
+                    continue;
+                }
+                match value {
+                    Yaml::String(text) => fields.push(vec![(path, key.clone(), text.clone())]),
+                    Yaml::Array(values) if values.iter().all(|v| v.as_str().is_some()) => fields
+                        .push(
+                            values
+                                .iter()
+                                .enumerate()
+                                .map(|(index, text)| {
+                                    (
+                                        format!("{}[{}]", path, index),
+                                        key.clone(),
+                                        text.as_str().unwrap_or_default().to_string(),
+                                    )
+                                })
+                                .collect(),
+                        ),
+                    value => collect_fields(value, &path, keys, fields),
+                }
+            }
+        }
+        Yaml::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                collect_fields(value, &format!("{}[{}]", path, index), keys, fields);
+            }
+        }
+        _ => {}
+    }
+}
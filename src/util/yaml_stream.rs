@@ -0,0 +1,333 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Incremental loading of very large YAML inputs, like the aggregated `ansible-doc` output of
+//! the whole Ansible package.
+//!
+//! The input is read line by line and split at document markers, or at the keys of top-level
+//! block mappings. Only one document or one mapping entry is held in memory at a time; each of
+//! them is parsed on its own. Anchors therefore cannot be referenced from another document or
+//! mapping entry.
+
+use saphyr::Yaml;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+/// Whether a line is a document start marker `---`, possibly followed by content.
+fn is_document_start(line: &str) -> bool {
+    line == "---" || line.starts_with("--- ") || line.starts_with("---\t")
+}
+
+/// Whether a line is a document end marker `...`.
+fn is_document_end(line: &str) -> bool {
+    line == "..." || line.starts_with("... ") || line.starts_with("...\t")
+}
+
+/// Whether a line contains nothing but whitespace and comments.
+fn is_blank(line: &str) -> bool {
+    let line = line.trim_start();
+    line.is_empty() || line.starts_with('#')
+}
+
+/// Reads lines, remembers the line number of the next line, and allows to push back one line.
+struct LineReader<R> {
+    reader: R,
+    line_number: usize,
+    pending: Option<String>,
+    done: bool,
+}
+
+impl<R: BufRead> LineReader<R> {
+    fn new(reader: R) -> LineReader<R> {
+        LineReader {
+            reader,
+            line_number: 1,
+            pending: None,
+            done: false,
+        }
+    }
+
+    fn next_line(&mut self) -> Option<Result<String, String>> {
+        if let Some(line) = self.pending.take() {
+            self.line_number += 1;
+            return Some(Ok(line));
+        }
+        if self.done {
+            return None;
+        }
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => {
+                self.done = true;
+                None
+            }
+            Ok(_) => {
+                self.line_number += 1;
+                let length = line.trim_end_matches(['\n', '\r']).len();
+                line.truncate(length);
+                Some(Ok(line))
+            }
+            Err(error) => {
+                self.done = true;
+                Some(Err(format!("line {}: {}", self.line_number, error)))
+            }
+        }
+    }
+
+    fn push_back(&mut self, line: String) {
+        self.line_number -= 1;
+        self.pending = Some(line);
+    }
+}
+
+/// Parse a chunk of the input that starts at line `first_line`.
+fn parse_chunk(chunk: &str, first_line: usize) -> Result<Vec<Yaml>, String> {
+    Yaml::load_from_str(chunk).map_err(|error| {
+        format!(
+            "line {}, column {}: {}",
+            first_line + error.marker().line().saturating_sub(1),
+            error.marker().col() + 1,
+            error.info()
+        )
+    })
+}
+
+/// Iterates over the documents of a YAML stream, reading and parsing one document at a time.
+///
+/// Documents without content are skipped.
+pub struct YamlDocuments<R> {
+    lines: LineReader<R>,
+}
+
+impl<R: BufRead> YamlDocuments<R> {
+    pub fn new(reader: R) -> YamlDocuments<R> {
+        YamlDocuments {
+            lines: LineReader::new(reader),
+        }
+    }
+}
+
+impl YamlDocuments<BufReader<File>> {
+    /// Read the documents of a file.
+    pub fn open(path: &Path) -> io::Result<YamlDocuments<BufReader<File>>> {
+        Ok(YamlDocuments::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> Iterator for YamlDocuments<R> {
+    type Item = Result<Yaml, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut chunk = String::new();
+        let mut first_line = self.lines.line_number;
+        let mut has_content = false;
+        while let Some(line) = self.lines.next_line() {
+            let line = match line {
+                Ok(line) => line,
+                Err(error) => return Some(Err(error)),
+            };
+            if is_document_start(&line) {
+                if has_content {
+                    self.lines.push_back(line);
+                    break;
+                }
+                has_content = !is_blank(&line[3..]);
+            } else if is_document_end(&line) {
+                if has_content {
+                    break;
+                }
+                chunk.clear();
+                first_line = self.lines.line_number;
+                continue;
+            } else {
+                has_content |= !is_blank(&line) && !line.starts_with('%');
+            }
+            chunk.push_str(&line);
+            chunk.push('\n');
+        }
+        if !has_content {
+            return None;
+        }
+        Some(
+            parse_chunk(&chunk, first_line)
+                .map(|documents| documents.into_iter().next().unwrap_or(Yaml::Null)),
+        )
+    }
+}
+
+/// Iterates over the entries of the top-level mappings of a YAML stream, reading and parsing
+/// one entry at a time.
+///
+/// The top-level mappings must be in block style, and document start markers must not be
+/// followed by content on the same line.
+pub struct YamlMappingEntries<R> {
+    lines: LineReader<R>,
+    parsed: VecDeque<(Yaml, Yaml)>,
+}
+
+impl<R: BufRead> YamlMappingEntries<R> {
+    pub fn new(reader: R) -> YamlMappingEntries<R> {
+        YamlMappingEntries {
+            lines: LineReader::new(reader),
+            parsed: VecDeque::new(),
+        }
+    }
+}
+
+impl YamlMappingEntries<BufReader<File>> {
+    /// Read the mapping entries of a file.
+    pub fn open(path: &Path) -> io::Result<YamlMappingEntries<BufReader<File>>> {
+        Ok(YamlMappingEntries::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+/// Whether a line of a top-level block mapping starts a new entry.
+fn starts_entry(line: &str) -> bool {
+    let is_value = line.starts_with(':') && (line.len() == 1 || line[1..].starts_with([' ', '\t']));
+    !line.is_empty() && !line.starts_with([' ', '\t', '#', '%']) && !is_value
+}
+
+/// Whether a line starts an item of a block sequence without indentation. PyYAML writes the
+/// sequences below mapping keys like this, so such a line continues the current entry.
+fn is_sequence_item(line: &str) -> bool {
+    line == "-" || line.starts_with("- ") || line.starts_with("-\t")
+}
+
+impl<R: BufRead> Iterator for YamlMappingEntries<R> {
+    type Item = Result<(Yaml, Yaml), String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(entry) = self.parsed.pop_front() {
+            return Some(Ok(entry));
+        }
+        let mut chunk = String::new();
+        let mut first_line = self.lines.line_number;
+        let mut has_content = false;
+        while let Some(line) = self.lines.next_line() {
+            let line = match line {
+                Ok(line) => line,
+                Err(error) => return Some(Err(error)),
+            };
+            let line_number = self.lines.line_number - 1;
+            if is_document_start(&line) || is_document_end(&line) {
+                if !is_blank(&line[3..]) {
+                    return Some(Err(format!(
+                        "line {}: content after a document marker cannot be streamed",
+                        line_number
+                    )));
+                }
+                if has_content {
+                    break;
+                }
+                first_line = self.lines.line_number;
+                continue;
+            }
+            if starts_entry(&line) && !(has_content && is_sequence_item(&line)) {
+                if has_content {
+                    self.lines.push_back(line);
+                    break;
+                }
+                if line.starts_with(['{', '[', '-']) {
+                    return Some(Err(format!(
+                        "line {}: only block mappings can be streamed",
+                        line_number
+                    )));
+                }
+                has_content = true;
+            }
+            if !has_content {
+                first_line = self.lines.line_number;
+                continue;
+            }
+            chunk.push_str(&line);
+            chunk.push('\n');
+        }
+        if !has_content {
+            return None;
+        }
+        match parse_chunk(&chunk, first_line) {
+            Ok(documents) => match documents.into_iter().next() {
+                Some(Yaml::Hash(hash)) => self.parsed.extend(hash),
+                _ => {
+                    return Some(Err(format!(
+                        "line {}: expected a mapping entry",
+                        first_line
+                    )))
+                }
+            },
+            Err(error) => return Some(Err(error)),
+        }
+        self.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yaml_stream() {
+        let input = concat!(
+            "%YAML 1.2\n",
+            "---\n",
+            "# plugins\n",
+            "foo.bar.baz:\n",
+            "  doc: |\n",
+            "    Some text.\n",
+            "\n",
+            "    More text.\n",
+            "? foo.bar.bam\n",
+            ": doc: x\n",
+            "...\n",
+            "--- # second\n",
+            "a: 1\n",
+            "b: [2, 3]\n",
+        );
+        let documents: Vec<Yaml> = YamlDocuments::new(input.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[1]["b"][1].as_i64(), Some(3));
+
+        let entries: Vec<(Yaml, Yaml)> = YamlMappingEntries::new(input.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|(key, _)| key.as_str().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["foo.bar.baz", "foo.bar.bam", "a", "b"]
+        );
+        assert_eq!(
+            entries[0].1["doc"].as_str(),
+            Some("Some text.\n\nMore text.\n")
+        );
+
+        let errors: Vec<String> = YamlMappingEntries::new("a: 1\nb: [\nc: 3\n".as_bytes())
+            .filter_map(|entry| entry.err())
+            .collect();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("line 3, "), "{}", errors[0]);
+        let entries: Vec<(Yaml, Yaml)> =
+            YamlMappingEntries::new("foo:\n- a\n- b\nbar: 1\n".as_bytes())
+                .collect::<Result<_, _>>()
+                .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].0.as_str(), Some("foo"));
+        assert_eq!(entries[0].1[1].as_str(), Some("b"));
+        assert_eq!(entries[1].1.as_i64(), Some(1));
+        assert_eq!(
+            YamlMappingEntries::new("- a\n".as_bytes())
+                .next()
+                .unwrap()
+                .unwrap_err(),
+            "line 1: only block mappings can be streamed"
+        );
+    }
+}
@@ -0,0 +1,651 @@
+/*
+GNU General Public License v3.0+ (see LICENSES/GPL-3.0-or-later.txt or https://www.gnu.org/licenses/gpl-3.0.txt)
+SPDX-FileCopyrightText: 2024, Felix Fontein
+SPDX-License-Identifier: GPL-3.0-or-later
+*/
+
+//! Replacement of scalar values in YAML documents that keeps everything else intact.
+//!
+//! Only block mappings and block sequences can be traversed. Comments, the order of keys, and
+//! the formatting of all other values are not touched, and the replaced value keeps its
+//! quoting or block scalar style where possible.
+
+use std::fmt;
+
+/// The maximal line length used when wrapping folded block scalars.
+const LINE_WIDTH: usize = 80;
+
+/// A step in the path to a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    /// The value of a key in a mapping.
+    Key(String),
+
+    /// The element of a sequence, starting with 0.
+    Index(usize),
+}
+
+/// Parse a path like `options.state.description[0]`.
+pub fn parse_path(path: &str) -> Vec<PathSegment> {
+    let mut result = Vec::new();
+    for part in path.split('.') {
+        let (key, mut indices) = match part.find('[') {
+            Some(index) => (&part[..index], &part[index..]),
+            None => (part, ""),
+        };
+        if !key.is_empty() {
+            result.push(PathSegment::Key(key.to_string()));
+        }
+        while let Some((index, rest)) = indices
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once(']'))
+        {
+            match index.parse() {
+                Ok(index) => result.push(PathSegment::Index(index)),
+                Err(_) => result.push(PathSegment::Key(index.to_string())),
+            }
+            indices = rest;
+        }
+    }
+    result
+}
+
+fn format_path(path: &[PathSegment]) -> String {
+    let mut result = String::new();
+    for segment in path {
+        match segment {
+            PathSegment::Key(key) => {
+                if !result.is_empty() {
+                    result.push('.');
+                }
+                result.push_str(key);
+            }
+            PathSegment::Index(index) => result.push_str(&format!("[{}]", index)),
+        }
+    }
+    result
+}
+
+/// The style of a scalar in the YAML source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalarStyle {
+    Plain,
+    SingleQuoted,
+    DoubleQuoted,
+    /// A block scalar starting with `|`.
+    Literal,
+    /// A block scalar starting with `>`.
+    Folded,
+}
+
+/// The position of a scalar in the YAML source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScalarLocation {
+    /// The byte offset of the start of the scalar, including quotes and block scalar headers.
+    pub start: usize,
+
+    /// The byte offset after the end of the scalar.
+    pub end: usize,
+
+    /// The style of the scalar.
+    pub style: ScalarStyle,
+
+    /// The indentation used for lines of the scalar after the first.
+    pub indent: usize,
+}
+
+/// An error while locating or replacing a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YamlEditError {
+    /// The path up to the element that caused the error.
+    pub path: String,
+
+    /// The description of the problem.
+    pub message: String,
+}
+
+impl fmt::Display for YamlEditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for YamlEditError {}
+
+/// A line, or the rest of a line after a sequence indicator.
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    line: usize,
+    column: usize,
+}
+
+struct Locator<'s> {
+    lines: Vec<(usize, &'s str)>,
+}
+
+fn leading_spaces(text: &str) -> usize {
+    text.len() - text.trim_start_matches(' ').len()
+}
+
+fn is_sequence_entry(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Return the offset after the colon if `text` starts with `key:`.
+fn match_key(text: &str, key: &str) -> Option<usize> {
+    let length = [
+        key.to_string(),
+        format!("'{}'", key.replace('\'', "''")),
+        format!("\"{}\"", key),
+    ]
+    .into_iter()
+    .find(|candidate| text.starts_with(candidate.as_str()))?
+    .len();
+    let rest = text[length..].trim_start_matches(' ');
+    let rest = rest.strip_prefix(':')?;
+    (rest.is_empty() || rest.starts_with(' ')).then(|| text.len() - rest.len())
+}
+
+impl<'s> Locator<'s> {
+    fn new(source: &'s str) -> Locator<'s> {
+        let mut lines = Vec::new();
+        let mut start = 0;
+        for line in source.split_inclusive('\n') {
+            let text = line.strip_suffix('\n').unwrap_or(line);
+            lines.push((start, text.strip_suffix('\r').unwrap_or(text)));
+            start += line.len();
+        }
+        Locator { lines }
+    }
+
+    fn text(&self, segment: Segment) -> &'s str {
+        &self.lines[segment.line].1[segment.column..]
+    }
+
+    fn indent(&self, segment: Segment) -> usize {
+        segment.column + leading_spaces(self.text(segment))
+    }
+
+    fn is_ignorable(&self, segment: Segment) -> bool {
+        let text = self.text(segment).trim_start();
+        text.is_empty() || text.starts_with('#')
+    }
+
+    fn offset(&self, line: usize, column: usize) -> usize {
+        self.lines[line].0 + column
+    }
+
+    /// Return the segments after `index` that are more indented than `indent`.
+    fn children(
+        &self,
+        region: &[Segment],
+        index: usize,
+        indent: usize,
+        compact_sequence: bool,
+    ) -> Vec<Segment> {
+        region[index + 1..]
+            .iter()
+            .copied()
+            .take_while(|&segment| {
+                self.is_ignorable(segment)
+                    || self.indent(segment) > indent
+                    || (compact_sequence
+                        && self.indent(segment) == indent
+                        && is_sequence_entry(self.text(segment).trim_start()))
+            })
+            .collect()
+    }
+
+    fn locate(
+        &self,
+        region: &[Segment],
+        path: &[PathSegment],
+        depth: usize,
+    ) -> Result<ScalarLocation, YamlEditError> {
+        let error = |message: &str| YamlEditError {
+            path: format_path(&path[..depth.min(path.len())]),
+            message: message.to_string(),
+        };
+        let Some(first) = region.iter().find(|&&s| !self.is_ignorable(s)) else {
+            return Err(error("no value"));
+        };
+        let base = self.indent(*first);
+        let entries = region
+            .iter()
+            .enumerate()
+            .filter(|&(_, &s)| !self.is_ignorable(s) && self.indent(s) == base);
+        match &path[depth] {
+            PathSegment::Key(key) => {
+                for (index, &segment) in entries {
+                    let text = &self.text(segment)[base - segment.column..];
+                    if let Some(after) = match_key(text, key) {
+                        let column = base + after;
+                        let rest = self.lines[segment.line].1[column..].trim_start();
+                        let children = self.children(region, index, base, rest.is_empty());
+                        return self.value(segment.line, column, base, &children, path, depth + 1);
+                    }
+                }
+                Err(YamlEditError {
+                    path: format_path(&path[..depth + 1]),
+                    message: "key not found".to_string(),
+                })
+            }
+            PathSegment::Index(wanted) => {
+                if !is_sequence_entry(&self.text(*first)[base - first.column..]) {
+                    return Err(error("not a sequence"));
+                }
+                let Some((index, &segment)) = entries.into_iter().nth(*wanted) else {
+                    return Err(YamlEditError {
+                        path: format_path(&path[..depth + 1]),
+                        message: "index out of range".to_string(),
+                    });
+                };
+                let column = base + 1;
+                let children = self.children(region, index, base, false);
+                let rest = &self.lines[segment.line].1[column..];
+                if depth + 1 < path.len() && !rest.trim().is_empty() {
+                    // A mapping that starts on the line of the sequence entry.
+                    let mut region = vec![Segment {
+                        line: segment.line,
+                        column: column + leading_spaces(rest),
+                    }];
+                    region.extend(children);
+                    return self.locate(&region, path, depth + 1);
+                }
+                self.value(segment.line, column, base, &children, path, depth + 1)
+            }
+        }
+    }
+
+    fn value(
+        &self,
+        line: usize,
+        column: usize,
+        base: usize,
+        children: &[Segment],
+        path: &[PathSegment],
+        depth: usize,
+    ) -> Result<ScalarLocation, YamlEditError> {
+        let text = self.lines[line].1;
+        let column = column + leading_spaces(&text[column..]);
+        let rest = &text[column..];
+        let error = |message: &str| YamlEditError {
+            path: format_path(&path[..depth]),
+            message: message.to_string(),
+        };
+        if rest.is_empty() || rest.starts_with('#') {
+            if depth == path.len() {
+                return Err(error("not a scalar"));
+            }
+            return self.locate(children, path, depth);
+        }
+        if depth < path.len() {
+            return Err(error("not a block mapping or block sequence"));
+        }
+        let start = self.offset(line, column);
+        match rest.as_bytes()[0] {
+            b'|' | b'>' => {
+                let mut indent = None;
+                let mut last = line;
+                for &segment in children {
+                    let content = self.text(segment);
+                    if content.trim().is_empty() {
+                        continue;
+                    }
+                    let segment_indent = self.indent(segment);
+                    if segment_indent < *indent.get_or_insert(segment_indent) {
+                        break;
+                    }
+                    last = segment.line;
+                }
+                let Some(indent) = indent else {
+                    return Err(error("empty block scalar"));
+                };
+                Ok(ScalarLocation {
+                    start,
+                    end: self.offset(last, self.lines[last].1.len()),
+                    style: if rest.starts_with('|') {
+                        ScalarStyle::Literal
+                    } else {
+                        ScalarStyle::Folded
+                    },
+                    indent,
+                })
+            }
+            quote @ (b'\'' | b'"') => {
+                let source_end =
+                    self.offset(self.lines.len() - 1, self.lines.last().unwrap().1.len());
+                let mut index = start + 1;
+                let mut end = None;
+                while index < source_end {
+                    let (line_start, line_text) = self.lines[self.line_of(index)];
+                    let c = line_text.as_bytes().get(index - line_start).copied();
+                    match (quote, c) {
+                        (b'"', Some(b'\\')) => index += 2,
+                        (b'\'', Some(b'\''))
+                            if line_text.as_bytes().get(index - line_start + 1) == Some(&b'\'') =>
+                        {
+                            index += 2
+                        }
+                        (_, Some(c)) if c == quote => {
+                            end = Some(index + 1);
+                            break;
+                        }
+                        _ => index += 1,
+                    }
+                }
+                let Some(end) = end else {
+                    return Err(error("unterminated quoted scalar"));
+                };
+                Ok(ScalarLocation {
+                    start,
+                    end,
+                    style: if quote == b'\'' {
+                        ScalarStyle::SingleQuoted
+                    } else {
+                        ScalarStyle::DoubleQuoted
+                    },
+                    indent: base + 2,
+                })
+            }
+            _ => {
+                let first = match rest.find(" #") {
+                    Some(index) => &rest[..index],
+                    None => rest,
+                };
+                let mut end = start + first.trim_end().len();
+                for &segment in children {
+                    let content = self.text(segment).trim();
+                    if content.is_empty() || content.starts_with('#') {
+                        break;
+                    }
+                    let (line_start, line_text) = self.lines[segment.line];
+                    end = line_start + line_text.trim_end().len();
+                }
+                Ok(ScalarLocation {
+                    start,
+                    end,
+                    style: ScalarStyle::Plain,
+                    indent: base + 2,
+                })
+            }
+        }
+    }
+
+    fn line_of(&self, offset: usize) -> usize {
+        self.lines
+            .partition_point(|&(start, _)| start <= offset)
+            .saturating_sub(1)
+    }
+}
+
+/// Find the scalar at `path` in the first document of `source`.
+pub fn find_scalar(source: &str, path: &[PathSegment]) -> Result<ScalarLocation, YamlEditError> {
+    let locator = Locator::new(source);
+    let region: Vec<Segment> = (0..locator.lines.len())
+        .filter(|&line| {
+            let text = locator.lines[line].1;
+            !(text.starts_with("---") || text.starts_with("..."))
+        })
+        .map(|line| Segment { line, column: 0 })
+        .collect();
+    if path.is_empty() {
+        return Err(YamlEditError {
+            path: String::new(),
+            message: "empty path".to_string(),
+        });
+    }
+    locator.locate(&region, path, 0)
+}
+
+fn is_plain_safe(value: &str) -> bool {
+    let Some(first) = value.chars().next() else {
+        return false;
+    };
+    !"-?:,[]{}#&*!|>'\"%@` \t".contains(first)
+        && !value.ends_with([' ', '\t'])
+        && !value.contains(['\n', '\r', '\t'])
+        && !value.contains(": ")
+        && !value.contains(" #")
+        && !value.ends_with(':')
+        && !matches!(
+            value.to_lowercase().as_str(),
+            "null" | "~" | "true" | "false" | "yes" | "no" | "on" | "off" | "y" | "n"
+        )
+        && value.parse::<f64>().is_err()
+        && !value.starts_with("0x")
+        && !value.starts_with("0o")
+}
+
+fn single_quoted(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn double_quoted(value: &str) -> String {
+    let mut result = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\x{:02x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+fn wrap(line: &str, width: usize) -> Vec<&str> {
+    if line.contains("  ") {
+        return vec![line];
+    }
+    let mut result = Vec::new();
+    let mut start = 0;
+    let mut last_space = None;
+    for (index, c) in line.char_indices() {
+        if c == ' ' {
+            if index - start > width {
+                if let Some(space) = last_space {
+                    result.push(&line[start..space]);
+                    start = space + 1;
+                }
+            }
+            last_space = Some(index);
+        }
+    }
+    if line.len() - start > width {
+        if let Some(space) = last_space.filter(|&space| space >= start) {
+            result.push(&line[start..space]);
+            start = space + 1;
+        }
+    }
+    result.push(&line[start..]);
+    result
+}
+
+/// Encode `value` for the location of an existing scalar.
+///
+/// The style of the scalar is kept if it can represent the value; otherwise the value is
+/// single-quoted, or double-quoted if it contains line breaks. Block scalars keep their header,
+/// including chomping and indentation indicators.
+fn encode(value: &str, location: &ScalarLocation, header: &str) -> String {
+    let fallback = || {
+        if value.contains(['\n', '\r']) {
+            double_quoted(value)
+        } else {
+            single_quoted(value)
+        }
+    };
+    let indent = " ".repeat(location.indent);
+    match location.style {
+        ScalarStyle::Plain if is_plain_safe(value) => value.to_string(),
+        ScalarStyle::Plain | ScalarStyle::SingleQuoted => fallback(),
+        ScalarStyle::DoubleQuoted => double_quoted(value),
+        ScalarStyle::Literal | ScalarStyle::Folded => {
+            let content = value.strip_suffix('\n').unwrap_or(value);
+            if content.is_empty()
+                || content.starts_with([' ', '\t', '\n'])
+                || content.ends_with('\n')
+                || content.contains('\r')
+            {
+                return double_quoted(value);
+            }
+            let mut lines = Vec::new();
+            if location.style == ScalarStyle::Literal {
+                lines.extend(content.split('\n'));
+            } else {
+                if content.contains("\n ") || content.contains("\n\t") {
+                    return double_quoted(value);
+                }
+                let width = LINE_WIDTH.saturating_sub(location.indent).max(20);
+                for (index, paragraph) in content.split('\n').enumerate() {
+                    if index > 0 {
+                        lines.push("");
+                    }
+                    if !paragraph.is_empty() {
+                        lines.extend(wrap(paragraph, width));
+                    }
+                }
+            }
+            let mut result = header.to_string();
+            for line in lines {
+                result.push('\n');
+                if !line.is_empty() {
+                    result.push_str(&indent);
+                    result.push_str(line);
+                }
+            }
+            result
+        }
+    }
+}
+
+/// Replace the scalar at `path` in the first document of `source` with `value`.
+pub fn replace_scalar(
+    source: &str,
+    path: &[PathSegment],
+    value: &str,
+) -> Result<String, YamlEditError> {
+    let location = find_scalar(source, path)?;
+    let header = source[location.start..]
+        .split(['\n', ' ', '#'])
+        .next()
+        .unwrap_or_default();
+    let mut result = String::with_capacity(source.len() + value.len());
+    result.push_str(&source[..location.start]);
+    result.push_str(&encode(value, &location, header));
+    result.push_str(&source[location.end..]);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use saphyr::Yaml;
+
+    #[test]
+    fn test_replace_scalar() {
+        let source = r#"---
+# The module documentation.
+module: foo
+short_description: Manage foo  # not too long
+description:
+  - 'It''s a module.'
+  - "Second \"paragraph\"."
+  - >-
+    Folded text
+    across lines.
+options:
+  state:
+    description: |
+      Literal text.
+
+      More.
+    choices: [present, absent]
+  name:
+    description:
+    - plain value
+      continued
+    - name: nested
+      type: str
+"#;
+        let replacements = [
+            ("short_description", "Manage bar"),
+            ("description[0]", "Isn't it?"),
+            ("description[1]", "New \"text\"\\"),
+            ("description[2]", "This folded text is long enough to be wrapped over more than one line, since it has more than eighty characters.\nNew line."),
+            ("options.state.description", "A\n\nB"),
+            ("options.name.description[0]", "needs: quoting"),
+            ("options.name.description[1].type", "true"),
+        ];
+        let mut result = source.to_string();
+        for (path, value) in replacements {
+            result = replace_scalar(&result, &parse_path(path), value).unwrap();
+        }
+        assert_eq!(
+            result,
+            r#"---
+# The module documentation.
+module: foo
+short_description: Manage bar  # not too long
+description:
+  - 'Isn''t it?'
+  - "New \"text\"\\"
+  - >-
+    This folded text is long enough to be wrapped over more than one line, since
+    it has more than eighty characters.
+
+    New line.
+options:
+  state:
+    description: |
+      A
+
+      B
+    choices: [present, absent]
+  name:
+    description:
+    - 'needs: quoting'
+    - name: nested
+      type: 'true'
+"#
+        );
+
+        let documents = Yaml::load_from_str(&result).unwrap();
+        let document = &documents[0];
+        for (path, value) in replacements {
+            let mut current = document;
+            for segment in parse_path(path) {
+                current = match segment {
+                    PathSegment::Key(key) => &current[key.as_str()],
+                    PathSegment::Index(index) => &current[index],
+                };
+            }
+            let expected = if path == "options.state.description" {
+                format!("{}\n", value)
+            } else {
+                value.to_string()
+            };
+            assert_eq!(current.as_str(), Some(expected.as_str()), "{}", path);
+        }
+
+        assert_eq!(
+            replace_scalar(source, &parse_path("options.state.choices[0]"), "x")
+                .unwrap_err()
+                .to_string(),
+            "options.state.choices: not a block mapping or block sequence"
+        );
+        assert_eq!(
+            replace_scalar(source, &parse_path("options.foo"), "x")
+                .unwrap_err()
+                .to_string(),
+            "options.foo: key not found"
+        );
+    }
+}